@@ -27,7 +27,7 @@ cd "$ROOT"
 echo "Bumping all crates to v${NEW_VERSION}..."
 
 # 1. Update each crate's Cargo.toml version
-for crate_dir in seedlink-protocol seedlink-client seedlink-server; do
+for crate_dir in seedlink-protocol seedlink-client seedlink-server seedlink-cli seedlink-ffi seedlink-py; do
     toml="$ROOT/$crate_dir/Cargo.toml"
     if [ -f "$toml" ]; then
         sed -i "s/^version = \".*\"/version = \"${NEW_VERSION}\"/" "$toml"
@@ -39,13 +39,17 @@ done
 sed -i "s/seedlink-rs-protocol = { version = \"[^\"]*\"/seedlink-rs-protocol = { version = \"${NEW_VERSION}\"/" "$ROOT/Cargo.toml"
 echo "  Updated workspace dependency in Cargo.toml"
 
-# 3. Verify
+# 3. Update path dependencies on the client crate (server relay, CLI, FFI)
+sed -i "s/seedlink-rs-client = { version = \"[^\"]*\"/seedlink-rs-client = { version = \"${NEW_VERSION}\"/" "$ROOT"/*/Cargo.toml
+echo "  Updated seedlink-rs-client dependencies"
+
+# 4. Verify
 echo ""
 echo "Verifying..."
 cargo check --workspace 2>&1
 echo ""
 
-# 4. Show diff
+# 5. Show diff
 echo "Changes:"
 git diff --stat
 echo ""
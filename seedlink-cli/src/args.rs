@@ -0,0 +1,258 @@
+//! Command-line argument parsing.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use seedlink_rs_protocol::InfoLevel;
+
+use crate::error::{CliError, Result};
+
+pub(crate) const USAGE: &str = "\
+Usage: seedlink-cli [OPTIONS] <COMMAND> <HOST:PORT> [ARGS]
+
+Commands:
+  stream <HOST:PORT> -s NET_STA[:SEL...]...   Print one line per received record
+  info <HOST:PORT> <LEVEL>                    Print the INFO reply (ID, STATIONS, STREAMS, ...)
+  ping <HOST:PORT>                            Time the connect + HELLO exchange
+  dump <HOST:PORT> -s NET_STA[:SEL...]... -d DIR
+                                              Append raw records to DIR/NET.STA.LOC.CHA.mseed
+
+Options:
+  -s, --stream NET_STA[:SEL...]  Subscribe to a station; selectors separated by ',' or ' '
+  -n, --count N                  Stop after N records (stream, dump) or probes (ping, default 4)
+  -o, --output FILE              Also append raw records to FILE (stream)
+  -d, --dir DIR                  Output directory (dump)
+  -t, --timeout SECS             Connect and read timeout (default 30)
+      --v3                       Do not negotiate SeedLink v4
+  -h, --help                     Print this help
+  -V, --version                  Print version";
+
+/// One `-s NET_STA[:SEL...]` subscription.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct StreamSpec {
+    pub network: String,
+    pub station: String,
+    pub selectors: Vec<String>,
+}
+
+impl StreamSpec {
+    fn parse(s: &str) -> Result<Self> {
+        let (id, selectors) = s.split_once(':').unwrap_or((s, ""));
+        let (network, station) = id
+            .split_once('_')
+            .filter(|(n, s)| !n.is_empty() && !s.is_empty())
+            .ok_or_else(|| CliError::Usage(format!("invalid stream {s:?}, expected NET_STA")))?;
+        Ok(Self {
+            network: network.to_owned(),
+            station: station.to_owned(),
+            selectors: selectors
+                .split([',', ' '])
+                .filter(|p| !p.is_empty())
+                .map(str::to_owned)
+                .collect(),
+        })
+    }
+}
+
+/// Options shared by all commands.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Common {
+    pub addr: String,
+    pub timeout: Duration,
+    pub prefer_v4: bool,
+}
+
+/// A parsed command line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Command {
+    Stream {
+        common: Common,
+        streams: Vec<StreamSpec>,
+        count: Option<u64>,
+        output: Option<PathBuf>,
+    },
+    Info {
+        common: Common,
+        level: InfoLevel,
+    },
+    Ping {
+        common: Common,
+        count: u64,
+    },
+    Dump {
+        common: Common,
+        streams: Vec<StreamSpec>,
+        count: Option<u64>,
+        dir: PathBuf,
+    },
+    Help,
+    Version,
+}
+
+/// Parse arguments (without the program name).
+pub(crate) fn parse(args: impl IntoIterator<Item = String>) -> Result<Command> {
+    let mut args = args.into_iter();
+    let mut positional = Vec::new();
+    let mut streams = Vec::new();
+    let mut count = None;
+    let mut output = None;
+    let mut dir = None;
+    let mut timeout = Duration::from_secs(30);
+    let mut prefer_v4 = true;
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| CliError::Usage(format!("{name} requires a value")))
+        };
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-V" | "--version" => return Ok(Command::Version),
+            "-s" | "--stream" => streams.push(StreamSpec::parse(&value(&arg)?)?),
+            "-n" | "--count" => count = Some(parse_number(&arg, &value(&arg)?)?),
+            "-o" | "--output" => output = Some(PathBuf::from(value(&arg)?)),
+            "-d" | "--dir" => dir = Some(PathBuf::from(value(&arg)?)),
+            "-t" | "--timeout" => timeout = Duration::from_secs(parse_number(&arg, &value(&arg)?)?),
+            "--v3" => prefer_v4 = false,
+            s if s.starts_with('-') && s.len() > 1 => {
+                return Err(CliError::Usage(format!("unknown option {s}")));
+            }
+            _ => positional.push(arg),
+        }
+    }
+
+    let mut positional = positional.into_iter();
+    let Some(name) = positional.next() else {
+        return Err(CliError::Usage("missing command".into()));
+    };
+    let addr = positional
+        .next()
+        .ok_or_else(|| CliError::Usage(format!("{name}: missing HOST:PORT")))?;
+    let common = Common {
+        addr,
+        timeout,
+        prefer_v4,
+    };
+    let require_streams = |streams: &[StreamSpec]| {
+        if streams.is_empty() {
+            Err(CliError::Usage(format!(
+                "{name}: at least one -s NET_STA is required"
+            )))
+        } else {
+            Ok(())
+        }
+    };
+
+    let command = match name.as_str() {
+        "stream" => {
+            require_streams(&streams)?;
+            Command::Stream {
+                common,
+                streams,
+                count,
+                output,
+            }
+        }
+        "info" => {
+            let level = positional
+                .next()
+                .ok_or_else(|| CliError::Usage("info: missing LEVEL".into()))?;
+            let level = InfoLevel::parse(&level)
+                .map_err(|_| CliError::Usage(format!("info: unknown level {level:?}")))?;
+            Command::Info { common, level }
+        }
+        "ping" => Command::Ping {
+            common,
+            count: count.unwrap_or(4),
+        },
+        "dump" => {
+            require_streams(&streams)?;
+            let dir = dir.ok_or_else(|| CliError::Usage("dump: -d DIR is required".into()))?;
+            Command::Dump {
+                common,
+                streams,
+                count,
+                dir,
+            }
+        }
+        other => return Err(CliError::Usage(format!("unknown command {other:?}"))),
+    };
+    if let Some(extra) = positional.next() {
+        return Err(CliError::Usage(format!("unexpected argument {extra:?}")));
+    }
+    Ok(command)
+}
+
+fn parse_number(option: &str, value: &str) -> Result<u64> {
+    value
+        .parse()
+        .map_err(|_| CliError::Usage(format!("{option}: invalid number {value:?}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_str(line: &str) -> Result<Command> {
+        parse(line.split_whitespace().map(str::to_owned))
+    }
+
+    #[test]
+    fn stream_with_selectors() {
+        let cmd =
+            parse_str("stream localhost:18000 -s IU_ANMO:BHZ,BHN -s GE_WLF -n 10 --v3").unwrap();
+        let Command::Stream {
+            common,
+            streams,
+            count,
+            output,
+        } = cmd
+        else {
+            panic!("expected stream, got {cmd:?}");
+        };
+        assert_eq!(common.addr, "localhost:18000");
+        assert!(!common.prefer_v4);
+        assert_eq!(streams[0].network, "IU");
+        assert_eq!(streams[0].station, "ANMO");
+        assert_eq!(streams[0].selectors, ["BHZ", "BHN"]);
+        assert!(streams[1].selectors.is_empty());
+        assert_eq!(count, Some(10));
+        assert_eq!(output, None);
+    }
+
+    #[test]
+    fn info_level_and_ping_default_count() {
+        assert!(matches!(
+            parse_str("info host:18000 streams").unwrap(),
+            Command::Info {
+                level: InfoLevel::Streams,
+                ..
+            }
+        ));
+        assert!(matches!(
+            parse_str("ping host:18000").unwrap(),
+            Command::Ping { count: 4, .. }
+        ));
+    }
+
+    #[test]
+    fn usage_errors() {
+        for line in [
+            "",
+            "stream host:18000",
+            "stream host:18000 -s ANMO",
+            "dump host:18000 -s IU_ANMO",
+            "info host:18000 bogus",
+            "ping",
+            "ping host:18000 extra",
+            "ping host:18000 -n x",
+            "ping host:18000 --bogus",
+        ] {
+            assert!(
+                matches!(parse_str(line), Err(CliError::Usage(_))),
+                "{line:?} should be a usage error"
+            );
+        }
+        assert_eq!(parse_str("ping -h").unwrap(), Command::Help);
+    }
+}
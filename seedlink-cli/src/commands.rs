@@ -0,0 +1,369 @@
+//! Subcommand implementations. Output goes to the given writer so the
+//! commands can be exercised against an in-process server in tests.
+
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use seedlink_rs_client::{ClientConfig, OwnedFrame, SeedLinkClient, reassemble_xml};
+use seedlink_rs_protocol::InfoLevel;
+
+use crate::args::{Common, StreamSpec};
+use crate::error::Result;
+
+/// Pause between `ping` probes.
+const PING_INTERVAL: Duration = Duration::from_secs(1);
+
+async fn connect(common: &Common) -> Result<SeedLinkClient> {
+    let config = ClientConfig {
+        connect_timeout: common.timeout,
+        read_timeout: common.timeout,
+        prefer_v4: common.prefer_v4,
+        user_agent: Some(format!("seedlink-cli/{}", env!("CARGO_PKG_VERSION"))),
+        ..ClientConfig::default()
+    };
+    Ok(SeedLinkClient::connect_with_config(&common.addr, config).await?)
+}
+
+/// Connect, subscribe to `streams`, and switch to streaming.
+async fn subscribe(common: &Common, streams: &[StreamSpec]) -> Result<SeedLinkClient> {
+    let mut client = connect(common).await?;
+    for s in streams {
+        client.station(&s.station, &s.network).await?;
+        for pattern in &s.selectors {
+            client.select(pattern).await?;
+        }
+        client.data().await?;
+    }
+    client.end_stream().await?;
+    Ok(client)
+}
+
+/// Receive frames until `count` is reached or the server closes, calling `f`
+/// for each one. Returns the number of frames received.
+async fn for_each_frame(
+    client: &mut SeedLinkClient,
+    count: Option<u64>,
+    mut f: impl FnMut(&OwnedFrame) -> Result<()>,
+) -> Result<u64> {
+    let mut received = 0;
+    while count.is_none_or(|n| received < n) {
+        let Some(frame) = client.next_frame().await? else {
+            break;
+        };
+        f(&frame)?;
+        received += 1;
+    }
+    Ok(received)
+}
+
+/// One summary line for a frame: sequence plus the decoded record header.
+fn describe(frame: &OwnedFrame) -> String {
+    match frame.decode() {
+        Ok(data) => format!("{} {}", frame.sequence(), data.record),
+        Err(_) => {
+            let id = frame
+                .station_key()
+                .map(|k| format!("{}_{}", k.network, k.station))
+                .unwrap_or_else(|| "?".into());
+            format!(
+                "{} {id} | {} bytes (not decodable)",
+                frame.sequence(),
+                frame.payload().len()
+            )
+        }
+    }
+}
+
+/// Output file name for a frame: `NET.STA.LOC.CHA.mseed`, or `NET.STA.mseed`
+/// when the record cannot be decoded. Codes that are not safe in a file name
+/// (see [`safe_code()`]) fall back to `unknown.mseed`.
+fn dump_file_name(frame: &OwnedFrame) -> String {
+    match frame.decode() {
+        Ok(data) => {
+            let r = data.record;
+            if safe_code(&r.network)
+                && safe_code(&r.station)
+                && (r.location.is_empty() || safe_code(&r.location))
+                && safe_code(&r.channel)
+            {
+                return format!(
+                    "{}.{}.{}.{}.mseed",
+                    r.network, r.station, r.location, r.channel
+                );
+            }
+        }
+        Err(_) => {
+            if let Some(k) = frame.station_key()
+                && safe_code(&k.network)
+                && safe_code(&k.station)
+            {
+                return format!("{}.{}.mseed", k.network, k.station);
+            }
+        }
+    }
+    "unknown.mseed".into()
+}
+
+/// Whether a stream code can go into a file name: non-empty and only
+/// `[A-Za-z0-9_-]`, so it can never hold a path separator or be `.`/`..`.
+fn safe_code(code: &str) -> bool {
+    !code.is_empty()
+        && code
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-')
+}
+
+fn append(path: &Path) -> Result<File> {
+    Ok(OpenOptions::new().create(true).append(true).open(path)?)
+}
+
+/// `stream`: print one line per record, optionally appending raw records to a file.
+pub(crate) async fn stream(
+    common: &Common,
+    streams: &[StreamSpec],
+    count: Option<u64>,
+    output: Option<&Path>,
+    out: &mut impl Write,
+) -> Result<()> {
+    let mut file = output.map(append).transpose()?;
+    let mut client = subscribe(common, streams).await?;
+    for_each_frame(&mut client, count, |frame| {
+        writeln!(out, "{}", describe(frame))?;
+        if let Some(file) = &mut file {
+            file.write_all(frame.payload())?;
+        }
+        Ok(())
+    })
+    .await?;
+    client.bye().await?;
+    Ok(())
+}
+
+/// `info`: print the reassembled XML reply for `level`.
+pub(crate) async fn info(common: &Common, level: InfoLevel, out: &mut impl Write) -> Result<()> {
+    let mut client = connect(common).await?;
+    let frames = client.info(level).await?;
+    writeln!(out, "{}", reassemble_xml(&frames))?;
+    client.bye().await?;
+    Ok(())
+}
+
+/// `ping`: time `count` connect + HELLO exchanges.
+pub(crate) async fn ping(common: &Common, count: u64, out: &mut impl Write) -> Result<()> {
+    let mut times = Vec::new();
+    for i in 0..count {
+        if i > 0 {
+            tokio::time::sleep(PING_INTERVAL).await;
+        }
+        let start = Instant::now();
+        let mut client = connect(common).await?;
+        let elapsed = start.elapsed();
+        let server = client.server_info();
+        writeln!(
+            out,
+            "{}: {} {} ({:?}) time={:.1} ms",
+            common.addr,
+            server.software,
+            server.version,
+            client.version(),
+            elapsed.as_secs_f64() * 1000.0
+        )?;
+        times.push(elapsed);
+        client.bye().await?;
+    }
+
+    if let (Some(min), Some(max)) = (times.iter().min(), times.iter().max()) {
+        let avg = times.iter().sum::<Duration>() / times.len() as u32;
+        writeln!(
+            out,
+            "{} probes, min/avg/max = {:.1}/{:.1}/{:.1} ms",
+            times.len(),
+            min.as_secs_f64() * 1000.0,
+            avg.as_secs_f64() * 1000.0,
+            max.as_secs_f64() * 1000.0
+        )?;
+    }
+    Ok(())
+}
+
+/// `dump`: append raw records to one file per stream under `dir`.
+pub(crate) async fn dump(
+    common: &Common,
+    streams: &[StreamSpec],
+    count: Option<u64>,
+    dir: &Path,
+    out: &mut impl Write,
+) -> Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let mut files: HashMap<PathBuf, File> = HashMap::new();
+    let mut client = subscribe(common, streams).await?;
+    let received = for_each_frame(&mut client, count, |frame| {
+        let path = dir.join(dump_file_name(frame));
+        let file = match files.entry(path) {
+            std::collections::hash_map::Entry::Occupied(e) => e.into_mut(),
+            std::collections::hash_map::Entry::Vacant(e) => {
+                let file = append(e.key())?;
+                e.insert(file)
+            }
+        };
+        file.write_all(frame.payload())?;
+        Ok(())
+    })
+    .await?;
+    client.bye().await?;
+    writeln!(
+        out,
+        "wrote {received} records to {} files in {}",
+        files.len(),
+        dir.display()
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use seedlink_rs_protocol::testing::TestRecordBuilder;
+    use seedlink_rs_server::{DataStore, SeedLinkServer};
+
+    fn make_payload(station: &str, network: &str) -> Vec<u8> {
+        TestRecordBuilder::new()
+            .station(station)
+            .network(network)
+            .build()
+    }
+
+    async fn server() -> (Common, DataStore) {
+        let server = SeedLinkServer::bind("127.0.0.1:0").await.unwrap();
+        let common = Common {
+            addr: server.local_addr().unwrap().to_string(),
+            timeout: Duration::from_secs(2),
+            prefer_v4: false,
+        };
+        let store = server.store().clone();
+        tokio::spawn(server.run());
+        (common, store)
+    }
+
+    fn spec(network: &str, station: &str) -> StreamSpec {
+        StreamSpec {
+            network: network.into(),
+            station: station.into(),
+            selectors: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn stream_prints_and_saves_records() {
+        let (common, store) = server().await;
+        store.push("IU", "ANMO", &make_payload("ANMO", "IU"));
+        store.push("IU", "ANMO", &make_payload("ANMO", "IU"));
+
+        let file = std::env::temp_dir().join(format!("seedlink-cli-{}.mseed", std::process::id()));
+        let _ = std::fs::remove_file(&file);
+        let mut out = Vec::new();
+        stream(
+            &common,
+            &[spec("IU", "ANMO")],
+            Some(2),
+            Some(&file),
+            &mut out,
+        )
+        .await
+        .unwrap();
+
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(text.lines().count(), 2);
+        assert!(text.starts_with("1 IU.ANMO.00.BHZ | 2024-001"), "{text}");
+        assert_eq!(std::fs::metadata(&file).unwrap().len(), 1024);
+        std::fs::remove_file(&file).unwrap();
+    }
+
+    #[tokio::test]
+    async fn info_prints_xml() {
+        let (common, store) = server().await;
+        store.push("IU", "ANMO", &make_payload("ANMO", "IU"));
+
+        let mut out = Vec::new();
+        info(&common, InfoLevel::Stations, &mut out).await.unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(
+            text.contains("<station name=\"ANMO\" network=\"IU\""),
+            "{text}"
+        );
+    }
+
+    #[tokio::test]
+    async fn ping_reports_round_trip() {
+        let (common, _store) = server().await;
+        let mut out = Vec::new();
+        ping(&common, 1, &mut out).await.unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains("time="), "{text}");
+        assert!(text.contains("1 probes"), "{text}");
+    }
+
+    #[test]
+    fn dump_file_name_rejects_unsafe_codes() {
+        let frame = |station_id: &str| OwnedFrame::V4 {
+            format: seedlink_rs_protocol::PayloadFormat::MiniSeed2,
+            subformat: seedlink_rs_protocol::PayloadSubformat::Data,
+            sequence: seedlink_rs_protocol::SequenceNumber::new(1),
+            station_id: station_id.into(),
+            payload: vec![0; 16],
+        };
+        assert_eq!(dump_file_name(&frame("IU_ANMO")), "IU.ANMO.mseed");
+        assert_eq!(dump_file_name(&frame("IU_../../etc")), "unknown.mseed");
+        assert_eq!(dump_file_name(&frame("IU_..")), "unknown.mseed");
+        assert_eq!(dump_file_name(&frame("IU_")), "unknown.mseed");
+
+        let hostile = OwnedFrame::V3 {
+            sequence: seedlink_rs_protocol::SequenceNumber::new(1),
+            payload: make_payload("../..", "IU"),
+        };
+        assert_eq!(dump_file_name(&hostile), "unknown.mseed");
+    }
+
+    #[tokio::test]
+    async fn dump_writes_one_file_per_stream() {
+        let (common, store) = server().await;
+        store.push("IU", "ANMO", &make_payload("ANMO", "IU"));
+        store.push("GE", "WLF", &make_payload("WLF", "GE"));
+        store.push("IU", "ANMO", &make_payload("ANMO", "IU"));
+
+        let dir = std::env::temp_dir().join(format!("seedlink-cli-dump-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut out = Vec::new();
+        dump(
+            &common,
+            &[spec("IU", "ANMO"), spec("GE", "WLF")],
+            Some(3),
+            &dir,
+            &mut out,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            std::fs::metadata(dir.join("IU.ANMO.00.BHZ.mseed"))
+                .unwrap()
+                .len(),
+            1024
+        );
+        assert_eq!(
+            std::fs::metadata(dir.join("GE.WLF.00.BHZ.mseed"))
+                .unwrap()
+                .len(),
+            512
+        );
+        assert!(
+            String::from_utf8(out)
+                .unwrap()
+                .starts_with("wrote 3 records to 2 files")
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,17 @@
+/// Errors reported by `seedlink-cli`.
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum CliError {
+    /// Invalid command line.
+    #[error("{0}")]
+    Usage(String),
+
+    /// Client or server error.
+    #[error(transparent)]
+    Client(#[from] seedlink_rs_client::ClientError),
+
+    /// Local file I/O error.
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+pub(crate) type Result<T> = std::result::Result<T, CliError>;
@@ -0,0 +1,66 @@
+//! `seedlink-cli` — command-line SeedLink client, in the spirit of `slinktool`.
+//!
+//! ```bash
+//! seedlink-cli stream rtserve.iris.washington.edu:18000 -s IU_ANMO:BHZ -n 5
+//! seedlink-cli info rtserve.iris.washington.edu:18000 STATIONS
+//! seedlink-cli ping rtserve.iris.washington.edu:18000
+//! seedlink-cli dump rtserve.iris.washington.edu:18000 -s IU_ANMO -d ./data
+//! ```
+
+mod args;
+mod commands;
+mod error;
+
+use std::process::ExitCode;
+
+use args::Command;
+use error::CliError;
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let command = match args::parse(std::env::args().skip(1)) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("seedlink-cli: {e}\n\n{}", args::USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut out = std::io::stdout();
+    let result = match command {
+        Command::Help => {
+            println!("{}", args::USAGE);
+            Ok(())
+        }
+        Command::Version => {
+            println!("seedlink-cli {}", env!("CARGO_PKG_VERSION"));
+            Ok(())
+        }
+        Command::Stream {
+            common,
+            streams,
+            count,
+            output,
+        } => commands::stream(&common, &streams, count, output.as_deref(), &mut out).await,
+        Command::Info { common, level } => commands::info(&common, level, &mut out).await,
+        Command::Ping { common, count } => commands::ping(&common, count, &mut out).await,
+        Command::Dump {
+            common,
+            streams,
+            count,
+            dir,
+        } => commands::dump(&common, &streams, count, &dir, &mut out).await,
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e @ CliError::Usage(_)) => {
+            eprintln!("seedlink-cli: {e}");
+            ExitCode::from(2)
+        }
+        Err(e) => {
+            eprintln!("seedlink-cli: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
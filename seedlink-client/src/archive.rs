@@ -0,0 +1,331 @@
+//! miniSEED archive writer.
+//!
+//! [`MseedArchiver`] appends received records to day files in an SDS or BUD
+//! directory tree, like `slarchive`. The file for each record is chosen from
+//! the record's own header (network, station, location, channel, and the
+//! year/day of its start time), so files rotate at day boundaries as records
+//! for the next day arrive.
+
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::pin::pin;
+
+use futures_core::Stream;
+use miniseed_rs::MseedRecord;
+use tokio::fs::{File, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tracing::debug;
+
+use crate::error::Result;
+use crate::state::OwnedFrame;
+
+/// Directory layout for archived day files.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ArchiveLayout {
+    /// SeisComP Data Structure:
+    /// `YEAR/NET/STA/CHAN.D/NET.STA.LOC.CHAN.D.YEAR.DAY`.
+    #[default]
+    Sds,
+    /// Buffer of Uniform Data (IRIS): `NET/STA/STA.NET.LOC.CHAN.YEAR.DAY`.
+    Bud,
+}
+
+impl ArchiveLayout {
+    /// Path of the day file for `record`, relative to the archive root.
+    ///
+    /// Fails with [`io::ErrorKind::InvalidData`] if a code could escape the
+    /// archive root: network, station and channel must be non-empty and only
+    /// `[A-Za-z0-9_-]`; the location may also be empty.
+    pub fn path(&self, record: &MseedRecord) -> Result<PathBuf> {
+        for (name, code, required) in [
+            ("network", &record.network, true),
+            ("station", &record.station, true),
+            ("location", &record.location, false),
+            ("channel", &record.channel, true),
+        ] {
+            if !is_safe_code(code, required) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{name} code {code:?} is not safe in an archive path"),
+                )
+                .into());
+            }
+        }
+        let (net, sta, loc, cha) = (
+            &record.network,
+            &record.station,
+            &record.location,
+            &record.channel,
+        );
+        let (year, day) = (record.start_time.year, record.start_time.day);
+        Ok(match self {
+            Self::Sds => [
+                format!("{year:04}"),
+                net.clone(),
+                sta.clone(),
+                format!("{cha}.D"),
+                format!("{net}.{sta}.{loc}.{cha}.D.{year:04}.{day:03}"),
+            ]
+            .iter()
+            .collect(),
+            Self::Bud => [
+                net.clone(),
+                sta.clone(),
+                format!("{sta}.{net}.{loc}.{cha}.{year:04}.{day:03}"),
+            ]
+            .iter()
+            .collect(),
+        })
+    }
+}
+
+/// Whether `code` is only `[A-Za-z0-9_-]`, so it can never hold a path
+/// separator or be `.`/`..`. Empty codes pass only if not `required`.
+fn is_safe_code(code: &str, required: bool) -> bool {
+    (!required || !code.is_empty())
+        && code
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-')
+}
+
+/// An open day file for one stream.
+struct DayFile {
+    path: PathBuf,
+    file: File,
+}
+
+/// Writes received miniSEED records into an SDS or BUD archive.
+///
+/// One file per stream stays open; when a record for the same stream maps to a
+/// different day file, the old file is flushed and closed and the new one is
+/// opened (and its directories created). Records are appended, so restarting
+/// an archiver continues existing day files.
+///
+/// ```no_run
+/// # async fn example() -> seedlink_rs_client::Result<()> {
+/// use seedlink_rs_client::{MseedArchiver, SeedLinkClient};
+///
+/// let mut client = SeedLinkClient::connect("rtserve.iris.washington.edu:18000").await?;
+/// client.station("ANMO", "IU").await?;
+/// client.data().await?;
+/// client.end_stream().await?;
+///
+/// let mut archiver = MseedArchiver::new("/data/sds");
+/// archiver.archive_stream(client.into_stream()).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct MseedArchiver {
+    root: PathBuf,
+    layout: ArchiveLayout,
+    files: HashMap<String, DayFile>,
+}
+
+impl MseedArchiver {
+    /// Archive into an SDS tree under `root`.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self::with_layout(root, ArchiveLayout::Sds)
+    }
+
+    /// Archive into a tree with the given layout under `root`.
+    pub fn with_layout(root: impl Into<PathBuf>, layout: ArchiveLayout) -> Self {
+        Self {
+            root: root.into(),
+            layout,
+            files: HashMap::new(),
+        }
+    }
+
+    /// The archive root directory.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Append one frame's record to its day file.
+    ///
+    /// Returns the path written to. Fails if the payload cannot be decoded as
+    /// miniSEED, since the file name comes from the record header.
+    pub async fn write(&mut self, frame: &OwnedFrame) -> Result<PathBuf> {
+        let record = frame.decode()?.record;
+        let stream_id = format!(
+            "{}.{}.{}.{}",
+            record.network, record.station, record.location, record.channel
+        );
+        let path = self.root.join(self.layout.path(&record)?);
+
+        let rotate = self
+            .files
+            .get(&stream_id)
+            .is_some_and(|open| open.path != path);
+        if rotate && let Some(mut old) = self.files.remove(&stream_id) {
+            debug!(file = %old.path.display(), "closing day file");
+            old.file.flush().await?;
+        }
+
+        let day = match self.files.entry(stream_id) {
+            std::collections::hash_map::Entry::Occupied(e) => e.into_mut(),
+            std::collections::hash_map::Entry::Vacant(e) => {
+                if let Some(dir) = path.parent() {
+                    tokio::fs::create_dir_all(dir).await?;
+                }
+                let file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&path)
+                    .await?;
+                debug!(file = %path.display(), "opened day file");
+                e.insert(DayFile {
+                    path: path.clone(),
+                    file,
+                })
+            }
+        };
+        day.file.write_all(frame.payload()).await?;
+        Ok(path)
+    }
+
+    /// Flush every open day file.
+    pub async fn flush(&mut self) -> Result<()> {
+        for day in self.files.values_mut() {
+            day.file.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// Archive every frame from `frames` until it ends, then flush.
+    ///
+    /// Returns the number of records written. Stops at the first stream or
+    /// write error; records written before it are flushed first.
+    pub async fn archive_stream(
+        &mut self,
+        frames: impl Stream<Item = Result<OwnedFrame>>,
+    ) -> Result<u64> {
+        let mut frames = pin!(frames);
+        let mut written = 0;
+        let result = loop {
+            match std::future::poll_fn(|cx| frames.as_mut().poll_next(cx)).await {
+                Some(Ok(frame)) => match self.write(&frame).await {
+                    Ok(_) => written += 1,
+                    Err(e) => break Err(e),
+                },
+                Some(Err(e)) => break Err(e),
+                None => break Ok(written),
+            }
+        };
+        self.flush().await?;
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use miniseed_rs::{EncodingFormat, NanoTime, Samples};
+    use seedlink_rs_protocol::SequenceNumber;
+
+    fn frame(seq: u64, channel: &str, day: u16, hour: u8) -> OwnedFrame {
+        let record = MseedRecord::new()
+            .with_nslc("IU", "ANMO", "00", channel)
+            .with_start_time(NanoTime {
+                year: 2024,
+                day,
+                hour,
+                minute: 0,
+                second: 0,
+                nanosecond: 0,
+            })
+            .with_sample_rate(20.0)
+            .with_encoding(EncodingFormat::Steim2)
+            .with_samples(Samples::Int(vec![1, 2, 3]));
+        OwnedFrame::V3 {
+            sequence: SequenceNumber::new(seq),
+            payload: miniseed_rs::encode(&record).unwrap(),
+        }
+    }
+
+    fn temp_root(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("seedlink-archive-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn layout_paths() {
+        let record = frame(1, "BHZ", 45, 0).decode().unwrap().record;
+        assert_eq!(
+            ArchiveLayout::Sds.path(&record).unwrap(),
+            Path::new("2024/IU/ANMO/BHZ.D/IU.ANMO.00.BHZ.D.2024.045")
+        );
+        assert_eq!(
+            ArchiveLayout::Bud.path(&record).unwrap(),
+            Path::new("IU/ANMO/ANMO.IU.00.BHZ.2024.045")
+        );
+    }
+
+    #[test]
+    fn layout_rejects_unsafe_codes() {
+        let base = frame(1, "BHZ", 45, 0).decode().unwrap().record;
+        let no_location = MseedRecord {
+            location: String::new(),
+            ..base.clone()
+        };
+        assert_eq!(
+            ArchiveLayout::Sds.path(&no_location).unwrap(),
+            Path::new("2024/IU/ANMO/BHZ.D/IU.ANMO..BHZ.D.2024.045")
+        );
+
+        for station in ["../..", "..", ".", "", "AN/MO", "AN MO"] {
+            let record = MseedRecord {
+                station: station.into(),
+                ..base.clone()
+            };
+            for layout in [ArchiveLayout::Sds, ArchiveLayout::Bud] {
+                let err = layout.path(&record).unwrap_err();
+                assert!(
+                    matches!(err, crate::ClientError::Io(ref e) if e.kind() == io::ErrorKind::InvalidData),
+                    "{station:?}: {err}"
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn rotates_at_day_boundary() {
+        let root = temp_root("rotate");
+        let frames = vec![
+            Ok(frame(1, "BHZ", 1, 23)),
+            Ok(frame(2, "BHN", 1, 23)),
+            Ok(frame(3, "BHZ", 2, 0)),
+            Ok(frame(4, "BHZ", 2, 1)),
+        ];
+        let stream = async_stream::stream! {
+            for f in frames {
+                yield f;
+            }
+        };
+
+        let mut archiver = MseedArchiver::new(&root);
+        assert_eq!(archiver.archive_stream(stream).await.unwrap(), 4);
+
+        let sds = root.join("2024/IU/ANMO");
+        let size = |p: &str| std::fs::metadata(sds.join(p)).unwrap().len();
+        assert_eq!(size("BHZ.D/IU.ANMO.00.BHZ.D.2024.001"), 512);
+        assert_eq!(size("BHZ.D/IU.ANMO.00.BHZ.D.2024.002"), 1024);
+        assert_eq!(size("BHN.D/IU.ANMO.00.BHN.D.2024.001"), 512);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn undecodable_payload_is_an_error() {
+        let root = temp_root("invalid");
+        let mut archiver = MseedArchiver::with_layout(&root, ArchiveLayout::Bud);
+        let bad = OwnedFrame::V3 {
+            sequence: SequenceNumber::new(1),
+            payload: vec![0u8; 512],
+        };
+        assert!(archiver.write(&bad).await.is_err());
+        assert!(!root.exists());
+    }
+}
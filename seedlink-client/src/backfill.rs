@@ -0,0 +1,928 @@
+//! Gap backfill from an FDSN dataselect web service.
+//!
+//! [`Backfill`] requests the records of a [`GapEvent`] from a dataselect
+//! endpoint (`fdsnws/dataselect/1/query`) and, with
+//! [`stream()`](Backfill::stream), inserts them into a record stream ahead of
+//! the record that revealed the gap, so each channel arrives gap-free and in
+//! time order.
+//!
+//! Requests are HTTP/1.1 `GET`s with `Connection: close`, over TLS (rustls,
+//! with the Mozilla root certificates of `webpki-roots`) for `https://`
+//! URLs. Redirects are followed, e.g. from `http://` to `https://`, but not
+//! from `https://` back to `http://`.
+
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::pin::pin;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use futures_core::Stream;
+use miniseed_rs::MseedReader;
+use seedlink_rs_protocol::{SequenceNumber, SlTime};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::task::JoinSet;
+use tokio_rustls::TlsConnector;
+use tokio_rustls::rustls::{self, ClientConfig, RootCertStore, pki_types::ServerName};
+use tracing::{debug, info, warn};
+
+use crate::error::{ClientError, Result};
+use crate::gaps::{GapDetector, GapEvent, GapKind};
+use crate::samples::SampleRecord;
+
+/// Redirects followed per request.
+const MAX_REDIRECTS: usize = 5;
+
+/// Gap requests in flight at once in [`Backfill::stream()`].
+const MAX_CONCURRENT_FETCHES: usize = 4;
+
+/// Records [`Backfill::stream()`] holds back behind open gaps before it stops
+/// reading the live stream.
+const MAX_HELD_RECORDS: usize = 4096;
+
+/// Configuration for [`Backfill`].
+#[derive(Clone, Debug)]
+pub struct BackfillConfig {
+    /// Dataselect service URL up to the `query` method, e.g.
+    /// `"https://service.iris.edu/fdsnws/dataselect/1/"`. `https://` or
+    /// `http://`.
+    pub url: String,
+    /// Timeout for each request, connect to end of body, redirects included.
+    /// Default: 60 seconds.
+    pub timeout: Duration,
+    /// Longest gap requested; longer gaps are passed through unfilled.
+    /// Default: 1 hour.
+    pub max_gap: Duration,
+    /// Largest response accepted, headers included; a longer response fails
+    /// the request. Default: 16 MiB, ample for an hour of 200 Hz data.
+    pub max_response_len: usize,
+}
+
+impl BackfillConfig {
+    /// Backfill from the dataselect service at `url` with default limits.
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_owned(),
+            timeout: Duration::from_secs(60),
+            max_gap: Duration::from_secs(3600),
+            max_response_len: 16 * 1024 * 1024,
+        }
+    }
+}
+
+/// Fetches missing data from an FDSN dataselect service.
+///
+/// Both `https://` and plain `http://` services work. Server certificates
+/// are checked against the Mozilla root certificates bundled by
+/// `webpki-roots`, not the system store, so a service with a certificate
+/// from a private CA cannot be reached.
+///
+/// ```no_run
+/// # async fn example() -> seedlink_rs_client::Result<()> {
+/// use seedlink_rs_client::{Backfill, BackfillConfig, SeedLinkClient};
+/// use tokio_stream::StreamExt;
+///
+/// let mut client = SeedLinkClient::connect("rtserve.iris.washington.edu:18000").await?;
+/// client.station("ANMO", "IU").await?;
+/// client.data().await?;
+/// client.end_stream().await?;
+///
+/// let records = client
+///     .into_stream()
+///     .map(|frame| Ok(frame?.decode_samples()?));
+/// let backfill = Backfill::new(BackfillConfig::new(
+///     "https://service.iris.edu/fdsnws/dataselect/1/",
+/// ));
+/// let mut records = std::pin::pin!(backfill.stream(records));
+/// while let Some(record) = records.next().await {
+///     let record = record?;
+///     println!("{}.{} {:?}", record.network, record.station, record.start_time);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Backfill {
+    config: BackfillConfig,
+    tls: Arc<ClientConfig>,
+}
+
+impl Backfill {
+    /// Backfill with `config`.
+    pub fn new(config: BackfillConfig) -> Self {
+        let roots = RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        };
+        Self::with_roots(config, roots)
+    }
+
+    fn with_roots(config: BackfillConfig, roots: RootCertStore) -> Self {
+        let tls =
+            ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+                .with_safe_default_protocol_versions()
+                .expect("ring supports the default TLS versions")
+                .with_root_certificates(roots)
+                .with_no_client_auth();
+        Self {
+            config,
+            tls: Arc::new(tls),
+        }
+    }
+
+    /// Request the records of `gap` (its channel, from `expected` to
+    /// `actual`). Records starting before `expected` repeat data already
+    /// received and are left out; the rest is sorted by start time. An
+    /// endpoint without data (HTTP 204 or 404) gives an empty list.
+    ///
+    /// Backfilled records have sequence [`SequenceNumber::UNSET`].
+    pub async fn fetch(&self, gap: &GapEvent) -> Result<Vec<SampleRecord>> {
+        let query = gap_query(gap)?;
+        let url = format!("{}/query?{query}", self.config.url.trim_end_matches('/'));
+        let body = tokio::time::timeout(self.config.timeout, self.get(url))
+            .await
+            .map_err(|_| ClientError::Timeout(self.config.timeout))??;
+
+        let mut records = Vec::new();
+        for record in MseedReader::new(&body) {
+            let record = SampleRecord::from_mseed(
+                SequenceNumber::UNSET,
+                record.map_err(seedlink_rs_protocol::SeedlinkError::from)?,
+            );
+            // Half a sample period; zero for rates without a usable period
+            let tolerance =
+                Duration::try_from_secs_f64(0.5 / record.sample_rate).unwrap_or(Duration::ZERO);
+            let starts_in_gap = record
+                .start_time
+                .checked_add(tolerance)
+                .is_none_or(|start| start >= gap.expected);
+            if starts_in_gap && record.start_time < gap.actual {
+                records.push(record);
+            }
+        }
+        records.sort_by_key(|r| r.start_time);
+        debug!(channel = %gap.channel, records = records.len(), "backfill fetched");
+        Ok(records)
+    }
+
+    /// Pass `records` through, detecting gaps per channel and yielding the
+    /// backfilled records of each gap before the record that revealed it.
+    ///
+    /// Gaps are requested on background tasks, a few at a time, while
+    /// `records` keeps being read: records of a channel wait behind its open
+    /// gap and other channels pass straight through. Once too many records
+    /// wait, `records` is not read until a request finishes.
+    ///
+    /// Overlaps and gaps longer than [`BackfillConfig::max_gap`] are passed
+    /// through. A failed request is logged and the gap left open; errors
+    /// from `records` are passed through.
+    pub fn stream(
+        self,
+        records: impl Stream<Item = Result<SampleRecord>>,
+    ) -> impl Stream<Item = Result<SampleRecord>> {
+        async_stream::stream! {
+            let mut records = pin!(records);
+            let mut gaps = GapDetector::new();
+            // Channels with an open gap: that gap first, then what arrived
+            // after it, in order
+            let mut queues: HashMap<String, VecDeque<Held>> = HashMap::new();
+            let mut fetches = JoinSet::new();
+            let mut fetching = HashMap::new();
+            let mut held = 0;
+            let mut live = true;
+            loop {
+                for (channel, queue) in &queues {
+                    if fetching.len() >= MAX_CONCURRENT_FETCHES {
+                        break;
+                    }
+                    if let Some(Held::Gap(gap)) = queue.front()
+                        && !fetching.values().any(|c| c == channel)
+                    {
+                        let (backfill, gap) = (self.clone(), gap.clone());
+                        let task = fetches.spawn(async move { backfill.fetch(&gap).await });
+                        fetching.insert(task.id(), channel.clone());
+                    }
+                }
+                if !live && queues.is_empty() {
+                    break;
+                }
+                // A waiting record implies a request in flight, so one
+                // branch is always enabled
+                let next = tokio::select! {
+                    biased;
+                    Some(done) = fetches.join_next_with_id() => Next::Fetched(done),
+                    record = std::future::poll_fn(|cx| records.as_mut().poll_next(cx)),
+                        if live && held < MAX_HELD_RECORDS => Next::Live(record),
+                };
+                match next {
+                    Next::Fetched(done) => {
+                        let (id, filled) = match done {
+                            Ok((id, filled)) => (id, filled),
+                            Err(e) => (e.id(), Err(io::Error::other(e).into())),
+                        };
+                        let channel = fetching.remove(&id).expect("request is tracked");
+                        let mut queue = queues.remove(&channel).expect("gap is queued");
+                        let Some(Held::Gap(gap)) = queue.pop_front() else {
+                            unreachable!("a request is only made for the gap in front");
+                        };
+                        match filled {
+                            Ok(filled) => {
+                                for r in filled {
+                                    yield Ok(r);
+                                }
+                            }
+                            Err(error) => {
+                                warn!(channel = %gap.channel, %error, "backfill failed");
+                            }
+                        }
+                        // Release the records up to the channel's next gap
+                        while let Some(entry) = queue.pop_front() {
+                            match entry {
+                                Held::Record(record) => {
+                                    held -= 1;
+                                    yield Ok(record);
+                                }
+                                gap @ Held::Gap(_) => {
+                                    queue.push_front(gap);
+                                    break;
+                                }
+                            }
+                        }
+                        if !queue.is_empty() {
+                            queues.insert(channel, queue);
+                        }
+                    }
+                    Next::Live(None) => live = false,
+                    Next::Live(Some(Err(e))) => yield Err(e),
+                    Next::Live(Some(Ok(record))) => {
+                        let gap = match gaps.observe(&record) {
+                            Some(gap) if gap.kind == GapKind::Gap => {
+                                if gap.duration > self.config.max_gap {
+                                    info!(channel = %gap.channel, duration = ?gap.duration, "gap too long to backfill");
+                                    None
+                                } else {
+                                    Some(gap)
+                                }
+                            }
+                            _ => None,
+                        };
+                        let channel = format!(
+                            "{}.{}.{}.{}",
+                            record.network, record.station, record.location, record.channel
+                        );
+                        if gap.is_none() && !queues.contains_key(&channel) {
+                            yield Ok(record);
+                            continue;
+                        }
+                        let queue = queues.entry(channel).or_default();
+                        queue.extend(gap.map(Held::Gap));
+                        queue.push_back(Held::Record(record));
+                        held += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    /// `GET` `url` and return the response body, following redirects other
+    /// than from `https://` to `http://`.
+    async fn get(&self, mut url: String) -> Result<Vec<u8>> {
+        let mut tls = false;
+        for _ in 0..=MAX_REDIRECTS {
+            let target = Url::parse(&url)?;
+            if tls && !target.tls {
+                return Err(ClientError::UnexpectedResponse(format!(
+                    "dataselect: refusing redirect from https:// to {url}"
+                )));
+            }
+            tls = target.tls;
+            match parse_response(self.exchange(&target).await?)? {
+                Reply::Body(body) => return Ok(body),
+                Reply::Redirect(location) => {
+                    debug!(%location, "dataselect redirect");
+                    url = target.resolve(&location);
+                }
+            }
+        }
+        Err(ClientError::UnexpectedResponse(format!(
+            "dataselect: more than {MAX_REDIRECTS} redirects"
+        )))
+    }
+
+    /// Send the request for `url` and read the whole response.
+    async fn exchange(&self, url: &Url) -> Result<Vec<u8>> {
+        let tcp = TcpStream::connect((url.host.as_str(), url.port)).await?;
+        let request = format!(
+            "GET {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: seedlink-rs/{}\r\n\
+             Connection: close\r\n\r\n",
+            url.path,
+            url.authority,
+            env!("CARGO_PKG_VERSION")
+        );
+        if !url.tls {
+            return read_response(tcp, &request, self.config.max_response_len).await;
+        }
+        let name = ServerName::try_from(url.host.clone()).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid TLS server name {:?}", url.host),
+            )
+        })?;
+        let tls = TlsConnector::from(self.tls.clone())
+            .connect(name, tcp)
+            .await?;
+        read_response(tls, &request, self.config.max_response_len).await
+    }
+}
+
+/// An entry of a channel queue in [`Backfill::stream()`].
+enum Held {
+    /// A gap to fill before the entries behind it.
+    Gap(GapEvent),
+    /// A live record waiting for the gaps ahead of it.
+    Record(SampleRecord),
+}
+
+/// What [`Backfill::stream()`] wakes up for.
+enum Next {
+    /// A gap request finished.
+    Fetched(
+        std::result::Result<(tokio::task::Id, Result<Vec<SampleRecord>>), tokio::task::JoinError>,
+    ),
+    /// The live stream produced an item or ended.
+    Live(Option<Result<SampleRecord>>),
+}
+
+/// Write `request` to `stream` and read the response, at most `limit`
+/// bytes, until the server closes the connection.
+async fn read_response<S>(mut stream: S, request: &str, limit: usize) -> Result<Vec<u8>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    stream.write_all(request.as_bytes()).await?;
+    stream.flush().await?;
+
+    let mut response = Vec::new();
+    match (&mut stream)
+        .take(limit as u64 + 1)
+        .read_to_end(&mut response)
+        .await
+    {
+        Ok(_) => {}
+        // Many servers close TLS without close_notify; the body framing
+        // still tells whether the response is complete
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {}
+        Err(e) => return Err(e.into()),
+    }
+    if response.len() > limit {
+        return Err(ClientError::UnexpectedResponse(format!(
+            "dataselect response over {limit} bytes"
+        )));
+    }
+    Ok(response)
+}
+
+/// An `http://` or `https://` URL.
+#[derive(Debug, PartialEq)]
+struct Url {
+    tls: bool,
+    /// `host[:port]` as written, for the `Host` header.
+    authority: String,
+    /// Host name or address, without IPv6 brackets.
+    host: String,
+    port: u16,
+    /// Path and query, starting with `/`.
+    path: String,
+}
+
+impl Url {
+    fn parse(url: &str) -> Result<Self> {
+        let invalid = |reason: &str| -> ClientError {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("backfill URL {url:?}: {reason}"),
+            )
+            .into()
+        };
+        let (tls, rest) = if let Some(rest) = url.strip_prefix("https://") {
+            (true, rest)
+        } else if let Some(rest) = url.strip_prefix("http://") {
+            (false, rest)
+        } else {
+            return Err(invalid("expected http:// or https://"));
+        };
+        let (authority, path) = rest.split_at(rest.find(['/', '?']).unwrap_or(rest.len()));
+        let (host, port) = match authority.rsplit_once(':') {
+            // A colon inside brackets is part of an IPv6 address
+            Some((host, port)) if !port.contains(']') => {
+                (host, port.parse().map_err(|_| invalid("invalid port"))?)
+            }
+            _ => (authority, if tls { 443 } else { 80 }),
+        };
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        if host.is_empty() {
+            return Err(invalid("no host"));
+        }
+        Ok(Self {
+            tls,
+            authority: authority.to_owned(),
+            host: host.to_owned(),
+            port,
+            path: match path {
+                "" => "/".to_owned(),
+                p if p.starts_with('?') => format!("/{p}"),
+                p => p.to_owned(),
+            },
+        })
+    }
+
+    /// The URL a `Location` header refers to, relative to this one.
+    fn resolve(&self, location: &str) -> String {
+        if location.contains("://") {
+            location.to_owned()
+        } else {
+            let scheme = if self.tls { "https" } else { "http" };
+            format!("{scheme}://{}{location}", self.authority)
+        }
+    }
+}
+
+/// Dataselect query parameters for `gap`.
+fn gap_query(gap: &GapEvent) -> Result<String> {
+    let mut parts = gap.channel.split('.');
+    let (Some(net), Some(sta), Some(loc), Some(cha), None) = (
+        parts.next(),
+        parts.next(),
+        parts.next(),
+        parts.next(),
+        parts.next(),
+    ) else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("gap channel {:?} is not NET.STA.LOC.CHA", gap.channel),
+        )
+        .into());
+    };
+    // Codes go into the URL unescaped; only the location code may be empty
+    let valid = |code: &str| !code.is_empty() && code.bytes().all(|b| b.is_ascii_alphanumeric());
+    if ![net, sta, cha].into_iter().all(valid) || !(loc.is_empty() || valid(loc)) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "gap channel {:?} has codes other than A-Z, a-z, 0-9",
+                gap.channel
+            ),
+        )
+        .into());
+    }
+    // FDSN spells the empty location code `--`
+    let loc = if loc.is_empty() { "--" } else { loc };
+    Ok(format!(
+        "net={net}&sta={sta}&loc={loc}&cha={cha}&start={}&end={}",
+        fdsn_time(gap.expected),
+        fdsn_time(gap.actual)
+    ))
+}
+
+/// `YYYY-MM-DDThh:mm:ss[.ffffff]`, the FDSN web service time format.
+fn fdsn_time(time: SystemTime) -> String {
+    // Microseconds are the finest resolution FDSN services accept
+    let micros = time.duration_since(UNIX_EPOCH).map_or(Duration::ZERO, |d| {
+        Duration::from_micros(d.as_micros() as u64)
+    });
+    let iso = SlTime::from(UNIX_EPOCH + micros).to_iso8601();
+    iso.trim_end_matches('Z').to_owned()
+}
+
+/// What a dataselect response asks for.
+#[derive(Debug, PartialEq)]
+enum Reply {
+    /// The body of a successful response; empty when there is no data.
+    Body(Vec<u8>),
+    /// Request this `Location` instead.
+    Redirect(String),
+}
+
+/// Check the status of an HTTP response and return its body, de-chunked,
+/// or where it redirects to.
+fn parse_response(response: Vec<u8>) -> Result<Reply> {
+    let invalid = |what: &str| ClientError::UnexpectedResponse(format!("dataselect: {what}"));
+    let head_len = response
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(|| invalid("incomplete response head"))?;
+    let head = std::str::from_utf8(&response[..head_len])
+        .map_err(|_| invalid("response head is not UTF-8"))?;
+    let mut lines = head.split("\r\n");
+    let status_line = lines.next().unwrap_or_default();
+    let status: u16 = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| invalid("malformed status line"))?;
+    let mut chunked = false;
+    let mut length = None;
+    let mut location = None;
+    for (name, value) in lines.filter_map(|line| line.split_once(':')) {
+        let value = value.trim();
+        match name.trim().to_ascii_lowercase().as_str() {
+            "transfer-encoding" => chunked = value.eq_ignore_ascii_case("chunked"),
+            "content-length" => length = value.parse::<usize>().ok(),
+            "location" => location = Some(value),
+            _ => {}
+        }
+    }
+    let body = &response[head_len + 4..];
+
+    match status {
+        200 if chunked => dechunk(body)
+            .map(Reply::Body)
+            .ok_or_else(|| invalid("malformed chunked body")),
+        200 => match length {
+            Some(len) => body
+                .get(..len)
+                .map(|body| Reply::Body(body.to_vec()))
+                .ok_or_else(|| invalid("truncated body")),
+            None => Ok(Reply::Body(body.to_vec())),
+        },
+        // No data for the request
+        204 | 404 => Ok(Reply::Body(Vec::new())),
+        301 | 302 | 303 | 307 | 308 => location
+            .map(|l| Reply::Redirect(l.to_owned()))
+            .ok_or_else(|| invalid("redirect without Location")),
+        _ => Err(ClientError::ServerError(format!(
+            "dataselect: {}",
+            status_line.split_once(' ').map_or(status_line, |(_, s)| s)
+        ))),
+    }
+}
+
+/// Join the chunks of a `Transfer-Encoding: chunked` body.
+fn dechunk(mut body: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    loop {
+        let line_end = body.windows(2).position(|w| w == b"\r\n")?;
+        let size = std::str::from_utf8(&body[..line_end]).ok()?;
+        // Chunk extensions follow a `;`
+        let size = size.split(';').next()?.trim();
+        let size = usize::from_str_radix(size, 16).ok()?;
+        body = &body[line_end + 2..];
+        if size == 0 {
+            return Some(out);
+        }
+        out.extend_from_slice(body.get(..size)?);
+        body = body.get(size + 2..)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use miniseed_rs::{EncodingFormat, MseedRecord, NanoTime, Samples};
+    use tokio::net::TcpListener;
+    use tokio_stream::StreamExt;
+
+    /// A 1 Hz IU.ANMO.00.BHZ record of 10 samples starting `second` after
+    /// 2024-001T00:00:00.
+    fn record(second: u32) -> Vec<u8> {
+        let record = MseedRecord::new()
+            .with_nslc("IU", "ANMO", "00", "BHZ")
+            .with_start_time(NanoTime {
+                year: 2024,
+                day: 1,
+                hour: 0,
+                minute: (second / 60) as u8,
+                second: (second % 60) as u8,
+                nanosecond: 0,
+            })
+            .with_sample_rate(1.0)
+            .with_encoding(EncodingFormat::Int32)
+            .with_samples(Samples::Int(vec![0; 10]));
+        miniseed_rs::encode(&record).unwrap()
+    }
+
+    fn decoded(second: u32) -> SampleRecord {
+        let record = miniseed_rs::decode(&record(second)).unwrap();
+        SampleRecord::from_mseed(SequenceNumber::new(u64::from(second)), record)
+    }
+
+    /// Read one request head from `stream` and answer with `response`.
+    async fn serve_one<S: AsyncRead + AsyncWrite + Unpin>(
+        mut stream: S,
+        response: &[u8],
+    ) -> String {
+        let mut head = Vec::new();
+        while !head.ends_with(b"\r\n\r\n") {
+            let mut byte = [0u8];
+            stream.read_exact(&mut byte).await.unwrap();
+            head.push(byte[0]);
+        }
+        stream.write_all(response).await.unwrap();
+        stream.shutdown().await.unwrap();
+        String::from_utf8(head).unwrap()
+    }
+
+    /// Serve one HTTP request with `response`; the request head is returned
+    /// by the task.
+    async fn http_server(response: Vec<u8>) -> (String, tokio::task::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!(
+            "http://{}/fdsnws/dataselect/1",
+            listener.local_addr().unwrap()
+        );
+        let task = tokio::spawn(async move {
+            let (s, _) = listener.accept().await.unwrap();
+            serve_one(s, &response).await
+        });
+        (url, task)
+    }
+
+    /// Like [`http_server()`], over TLS with the `localhost` test certificate.
+    async fn https_server(response: Vec<u8>) -> (String, tokio::task::JoinHandle<String>) {
+        use rustls::pki_types::pem::PemObject;
+        use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+
+        let certs =
+            CertificateDer::pem_slice_iter(include_bytes!("../../testdata/tls/localhost.pem"))
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .unwrap();
+        let key = PrivateKeyDer::from_pem_slice(include_bytes!("../../testdata/tls/localhost.key"))
+            .unwrap();
+        let config = rustls::ServerConfig::builder_with_provider(Arc::new(
+            rustls::crypto::ring::default_provider(),
+        ))
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .unwrap();
+        let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(config));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let url = format!("https://localhost:{port}/fdsnws/dataselect/1");
+        let task = tokio::spawn(async move {
+            let (s, _) = listener.accept().await.unwrap();
+            serve_one(acceptor.accept(s).await.unwrap(), &response).await
+        });
+        (url, task)
+    }
+
+    /// A backfill trusting the test CA instead of the public roots.
+    fn test_backfill(url: &str) -> Backfill {
+        use rustls::pki_types::CertificateDer;
+        use rustls::pki_types::pem::PemObject;
+
+        let mut roots = RootCertStore::empty();
+        let ca = CertificateDer::from_pem_slice(include_bytes!("../../testdata/tls/ca.pem"));
+        roots.add(ca.unwrap()).unwrap();
+        Backfill::with_roots(BackfillConfig::new(url), roots)
+    }
+
+    fn gap(from: u32, to: u32) -> GapEvent {
+        let at = |second: u32| decoded(second).start_time;
+        GapEvent {
+            channel: "IU.ANMO.00.BHZ".to_owned(),
+            kind: GapKind::Gap,
+            expected: at(from),
+            actual: at(to),
+            duration: Duration::from_secs(u64::from(to - from)),
+        }
+    }
+
+    #[tokio::test]
+    async fn fetch_requests_gap_and_trims_records() {
+        // The record at 0 s repeats data already received
+        let body = [record(0), record(10), record(20)].concat();
+        let mut response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/vnd.fdsn.mseed\r\n\
+             Content-Length: {}\r\n\r\n",
+            body.len()
+        )
+        .into_bytes();
+        response.extend(body);
+        let (url, server) = http_server(response).await;
+
+        let backfill = Backfill::new(BackfillConfig::new(&url));
+        let records = backfill.fetch(&gap(10, 30)).await.unwrap();
+        let starts: Vec<_> = records.iter().map(|r| r.start_time).collect();
+        assert_eq!(starts, [decoded(10).start_time, decoded(20).start_time]);
+        assert_eq!(records[0].sequence, SequenceNumber::UNSET);
+
+        let head = server.await.unwrap();
+        assert!(
+            head.starts_with(
+                "GET /fdsnws/dataselect/1/query?net=IU&sta=ANMO&loc=00&cha=BHZ\
+                 &start=2024-01-01T00:00:10&end=2024-01-01T00:00:30 HTTP/1.1\r\n"
+            ),
+            "{head}"
+        );
+    }
+
+    #[tokio::test]
+    async fn fetch_no_data_and_errors() {
+        let (url, _server) = http_server(b"HTTP/1.1 204 No Content\r\n\r\n".to_vec()).await;
+        let backfill = Backfill::new(BackfillConfig::new(&url));
+        assert!(backfill.fetch(&gap(10, 30)).await.unwrap().is_empty());
+
+        let (url, _server) = http_server(b"HTTP/1.1 413 Payload Too Large\r\n\r\n".to_vec()).await;
+        let backfill = Backfill::new(BackfillConfig::new(&url));
+        let err = backfill.fetch(&gap(10, 30)).await.unwrap_err();
+        assert!(
+            matches!(&err, ClientError::ServerError(msg) if msg == "dataselect: 413 Payload Too Large"),
+            "{err:?}"
+        );
+
+        let (url, _server) =
+            http_server(b"HTTP/1.1 200 OK\r\nContent-Length: 512\r\n\r\nshort".to_vec()).await;
+        let backfill = Backfill::new(BackfillConfig::new(&url));
+        let err = backfill.fetch(&gap(10, 30)).await.unwrap_err();
+        assert!(
+            matches!(&err, ClientError::UnexpectedResponse(_)),
+            "{err:?}"
+        );
+
+        let ftp = Backfill::new(BackfillConfig::new("ftp://service.iris.edu/"));
+        let err = ftp.fetch(&gap(10, 30)).await.unwrap_err();
+        assert!(matches!(&err, ClientError::Io(e) if e.kind() == io::ErrorKind::InvalidInput));
+    }
+
+    #[tokio::test]
+    async fn fetch_follows_redirect_to_https() {
+        let body = record(10);
+        let mut response =
+            format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", body.len()).into_bytes();
+        response.extend(body);
+        let (https_url, https) = https_server(response).await;
+        let location = format!("{https_url}/query?net=IU");
+        let (http_url, http) = http_server(
+            format!("HTTP/1.1 301 Moved Permanently\r\nLocation: {location}\r\n\r\n").into_bytes(),
+        )
+        .await;
+
+        let records = test_backfill(&http_url).fetch(&gap(10, 30)).await.unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].start_time, decoded(10).start_time);
+        assert!(
+            http.await
+                .unwrap()
+                .starts_with("GET /fdsnws/dataselect/1/query?net=IU&")
+        );
+        let head = https.await.unwrap();
+        assert!(
+            head.starts_with("GET /fdsnws/dataselect/1/query?net=IU HTTP/1.1\r\n"),
+            "{head}"
+        );
+        assert!(head.contains("\r\nHost: localhost:"), "{head}");
+
+        // The public roots do not include the test CA
+        let (https_url, _https) = https_server(Vec::new()).await;
+        let err = Backfill::new(BackfillConfig::new(&https_url))
+            .fetch(&gap(10, 30))
+            .await
+            .unwrap_err();
+        assert!(matches!(&err, ClientError::Io(_)), "{err:?}");
+    }
+
+    #[tokio::test]
+    async fn fetch_refuses_redirect_to_http() {
+        let (https_url, _https) = https_server(
+            b"HTTP/1.1 302 Found\r\nLocation: http://127.0.0.1:9/query\r\n\r\n".to_vec(),
+        )
+        .await;
+        let err = test_backfill(&https_url)
+            .fetch(&gap(10, 30))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&err, ClientError::UnexpectedResponse(msg) if msg.contains("refusing redirect")),
+            "{err:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn fetch_tolerates_unusable_sample_rates() {
+        // Half a period of this rate does not fit in a Duration
+        let mut record = miniseed_rs::decode(&record(10)).unwrap();
+        record.format_version = miniseed_rs::FormatVersion::V3;
+        record.sample_rate = 1e-300;
+        let body = miniseed_rs::encode(&record).unwrap();
+        let mut response =
+            format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", body.len()).into_bytes();
+        response.extend(body);
+        let (url, _server) = http_server(response).await;
+
+        let backfill = Backfill::new(BackfillConfig::new(&url));
+        let records = backfill.fetch(&gap(10, 30)).await.unwrap();
+        assert_eq!(records.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn stream_passes_other_channels_during_backfill() {
+        let body = record(10);
+        let mut response =
+            format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", body.len()).into_bytes();
+        response.extend(body);
+        // The dataselect server answers only once released
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let (release, released) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            let (s, _) = listener.accept().await.unwrap();
+            released.await.unwrap();
+            serve_one(s, &response).await
+        });
+
+        let mut other = decoded(20);
+        other.channel = "BHN".to_owned();
+        let live = tokio_stream::iter([decoded(0), decoded(20), other].map(Ok));
+        let backfill = Backfill::new(BackfillConfig::new(&url));
+        let mut records = pin!(backfill.stream(live));
+
+        let first = records.next().await.unwrap().unwrap();
+        assert_eq!(first.start_time, decoded(0).start_time);
+        // BHZ at 20 s waits for its gap; BHN does not
+        let second = records.next().await.unwrap().unwrap();
+        assert_eq!(second.channel, "BHN");
+        release.send(()).unwrap();
+        let rest: Vec<_> = records.map(|r| r.unwrap().start_time).collect().await;
+        assert_eq!(rest, [decoded(10).start_time, decoded(20).start_time]);
+    }
+
+    #[tokio::test]
+    async fn stream_fills_gap_in_order() {
+        // Chunked reply with the missing records 10 and 20
+        let body = [record(10), record(20)].concat();
+        let (first, second) = body.split_at(300);
+        let mut response = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n".to_vec();
+        for chunk in [first, second] {
+            response.extend(format!("{:x};ext=1\r\n", chunk.len()).as_bytes());
+            response.extend(chunk);
+            response.extend(b"\r\n");
+        }
+        response.extend(b"0\r\n\r\n");
+        let (url, _server) = http_server(response).await;
+
+        let live = tokio_stream::iter([decoded(0), decoded(30), decoded(40)].map(Ok));
+        let backfill = Backfill::new(BackfillConfig::new(&url));
+        let records: Vec<_> = backfill
+            .stream(live)
+            .map(|r| r.unwrap())
+            .collect::<Vec<_>>()
+            .await;
+
+        let seconds: Vec<_> = records
+            .iter()
+            .map(|r| {
+                r.start_time
+                    .duration_since(decoded(0).start_time)
+                    .unwrap()
+                    .as_secs()
+            })
+            .collect();
+        assert_eq!(seconds, [0, 10, 20, 30, 40]);
+        assert_eq!(records[1].sequence, SequenceNumber::UNSET);
+        assert_eq!(records[3].sequence, SequenceNumber::new(30));
+    }
+
+    #[test]
+    fn url_and_time_formatting() {
+        let url = Url::parse("https://host:8443/fdsnws/dataselect/1/query?net=IU").unwrap();
+        assert_eq!(
+            url,
+            Url {
+                tls: true,
+                authority: "host:8443".to_owned(),
+                host: "host".to_owned(),
+                port: 8443,
+                path: "/fdsnws/dataselect/1/query?net=IU".to_owned(),
+            }
+        );
+        assert_eq!(url.resolve("/other"), "https://host:8443/other");
+        assert_eq!(url.resolve("http://b/x"), "http://b/x");
+        let url = Url::parse("http://[::1]").unwrap();
+        assert_eq!((url.host.as_str(), url.port), ("::1", 80));
+        assert_eq!(url.path, "/");
+        assert_eq!(Url::parse("https://host?x").unwrap().port, 443);
+        assert!(Url::parse("http:///x").is_err());
+        assert!(Url::parse("http://host:port/").is_err());
+
+        let t = UNIX_EPOCH + Duration::from_nanos(1_704_067_200_123_456_789);
+        assert_eq!(fdsn_time(t), "2024-01-01T00:00:00.123456");
+
+        let mut event = gap(0, 10);
+        event.channel = "IU.ANMO..BHZ".to_owned();
+        assert!(gap_query(&event).unwrap().contains("&loc=--&"));
+        event.channel = "IU.ANMO".to_owned();
+        assert!(gap_query(&event).is_err());
+        for channel in [
+            "IU.AN MO.00.BHZ",
+            "IU.ANMO.00.BHZ\r\nX",
+            "IU.ANMO.0&.BHZ",
+            "IU..00.BHZ",
+        ] {
+            event.channel = channel.to_owned();
+            assert!(gap_query(&event).is_err(), "{channel:?}");
+        }
+    }
+}
@@ -0,0 +1,119 @@
+//! [`SubscriptionBuilder`]: configure many stations with one write.
+//!
+//! Subscribing to hundreds of stations one command at a time costs a round
+//! trip per command. In v3 BATCH mode the server sends no per-command reply,
+//! so the whole configuration can be written at once.
+
+use seedlink_rs_protocol::{Command, SeedlinkError, SequenceNumber};
+
+use crate::client::SeedLinkClient;
+use crate::error::Result;
+
+/// Buffers STATION/SELECT/DATA/TIME commands and sends them together.
+///
+/// Obtained from [`SeedLinkClient::batch()`]. Commands apply to the client
+/// only when [`send()`](Self::send) is called. When the server advertises
+/// `BATCH` (v3 only), BATCH mode is enabled and everything is written in a
+/// single flush; otherwise the commands are sent one at a time, each waiting
+/// for its reply.
+///
+/// ```no_run
+/// # async fn example(client: &mut seedlink_rs_client::SeedLinkClient) -> seedlink_rs_client::Result<()> {
+/// client
+///     .batch()
+///     .station("ANMO", "IU")
+///     .select("BHZ")
+///     .data()
+///     .station("WLF", "GE")
+///     .data()
+///     .send()
+///     .await?;
+/// client.end_stream().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[must_use = "commands are only sent by `send()`"]
+pub struct SubscriptionBuilder<'a> {
+    client: &'a mut SeedLinkClient,
+    commands: Vec<(&'static str, Command)>,
+    /// First command that failed to build, reported by `send()`.
+    invalid: Option<SeedlinkError>,
+}
+
+impl<'a> SubscriptionBuilder<'a> {
+    pub(crate) fn new(client: &'a mut SeedLinkClient) -> Self {
+        Self {
+            client,
+            commands: Vec::new(),
+            invalid: None,
+        }
+    }
+
+    /// Add `STATION station network`.
+    pub fn station(mut self, station: &str, network: &str) -> Self {
+        let cmd = Command::Station {
+            station: station.to_owned(),
+            network: network.to_owned(),
+        };
+        self.commands.push(("STATION", cmd));
+        self
+    }
+
+    /// Add `SELECT pattern` for the preceding station.
+    pub fn select(mut self, pattern: &str) -> Self {
+        let cmd = Command::Select {
+            pattern: pattern.to_owned(),
+        };
+        self.commands.push(("SELECT", cmd));
+        self
+    }
+
+    /// Arm the preceding station with `DATA`.
+    pub fn data(mut self) -> Self {
+        let cmd = Command::Data {
+            sequence: None,
+            start: None,
+            end: None,
+        };
+        self.commands.push(("DATA", cmd));
+        self
+    }
+
+    /// Arm the preceding station with `DATA`, resuming from `sequence`.
+    pub fn data_from(mut self, sequence: SequenceNumber) -> Self {
+        let cmd = Command::Data {
+            sequence: Some(sequence),
+            start: None,
+            end: None,
+        };
+        self.commands.push(("DATA", cmd));
+        self
+    }
+
+    /// Arm the preceding station with `TIME start [end]` (v3 only).
+    ///
+    /// An unparseable time is reported by [`send()`](Self::send).
+    pub fn time_window(mut self, start: &str, end: Option<&str>) -> Self {
+        match Command::time(start, end) {
+            Ok(cmd) => self.commands.push(("TIME", cmd)),
+            Err(e) => {
+                self.invalid.get_or_insert(e);
+            }
+        }
+        self
+    }
+
+    /// Send the buffered commands.
+    ///
+    /// Requires state `Connected` (starting with a station) or `Configured`.
+    /// Transitions to `Configured`. Commands not valid for the negotiated
+    /// version, or with an unparseable time, fail before anything is sent. In
+    /// BATCH mode the server does not report errors, so a rejected command
+    /// goes unnoticed.
+    pub async fn send(self) -> Result<()> {
+        if let Some(e) = self.invalid {
+            return Err(e.into());
+        }
+        self.client.send_batch(self.commands).await
+    }
+}
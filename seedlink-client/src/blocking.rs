@@ -0,0 +1,254 @@
+//! Blocking SeedLink client, for programs that don't run an async runtime.
+//!
+//! [`SeedLinkClient`] wraps the async [`crate::SeedLinkClient`] and drives it
+//! on a private single-threaded tokio runtime, so negotiation, validation and
+//! sequence tracking behave exactly as in the async API.
+//!
+//! ```no_run
+//! # fn example() -> seedlink_rs_client::Result<()> {
+//! use seedlink_rs_client::blocking::SeedLinkClient;
+//!
+//! let mut client = SeedLinkClient::connect("rtserve.iris.washington.edu:18000")?;
+//! client.station("ANMO", "IU")?;
+//! client.select("BHZ")?;
+//! client.data()?;
+//! client.end_stream()?;
+//!
+//! for frame in client.frames() {
+//!     let frame = frame?;
+//!     println!("seq={}, payload={} bytes", frame.sequence(), frame.payload().len());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+
+use seedlink_rs_protocol::{Command, InfoLevel, ProtocolVersion, SequenceNumber};
+use tokio::runtime::Runtime;
+
+use crate::error::Result;
+use crate::info::{StationInfo, StreamInfo};
+use crate::state::{ClientConfig, ClientState, OwnedFrame, ServerInfo, StationKey, StreamEvent};
+
+/// Blocking counterpart of [`crate::SeedLinkClient`].
+///
+/// Each method blocks the calling thread until the async method it mirrors
+/// completes; see there for details. Must not be called from within an async
+/// runtime (it panics, like `Runtime::block_on`).
+pub struct SeedLinkClient {
+    inner: crate::SeedLinkClient,
+    runtime: Runtime,
+}
+
+impl SeedLinkClient {
+    /// Connect to a SeedLink server with default configuration.
+    pub fn connect(addr: &str) -> Result<Self> {
+        Self::connect_with_config(addr, ClientConfig::default())
+    }
+
+    /// Connect to a SeedLink server with custom configuration.
+    pub fn connect_with_config(addr: &str, config: ClientConfig) -> Result<Self> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        let inner = runtime.block_on(crate::SeedLinkClient::connect_with_config(addr, config))?;
+        Ok(Self { inner, runtime })
+    }
+
+    // -- Accessors --
+
+    /// Returns the negotiated protocol version.
+    pub fn version(&self) -> ProtocolVersion {
+        self.inner.version()
+    }
+
+    /// Returns information about the server from the HELLO response.
+    pub fn server_info(&self) -> &ServerInfo {
+        self.inner.server_info()
+    }
+
+    /// Returns the current client state.
+    pub fn state(&self) -> ClientState {
+        self.inner.state()
+    }
+
+    /// Returns the configuration used for this connection.
+    pub fn config(&self) -> &ClientConfig {
+        self.inner.config()
+    }
+
+    /// See [`crate::SeedLinkClient::on_command()`].
+    pub fn on_command(&mut self, hook: impl Fn(&Command) + Send + Sync + 'static) {
+        self.inner.on_command(hook);
+    }
+
+    /// See [`crate::SeedLinkClient::on_frame()`].
+    pub fn on_frame(&mut self, hook: impl Fn(&mut OwnedFrame) + Send + Sync + 'static) {
+        self.inner.on_frame(hook);
+    }
+
+    // -- Configuration --
+
+    /// Select a station and network (`STATION`).
+    pub fn station(&mut self, station: &str, network: &str) -> Result<()> {
+        self.runtime.block_on(self.inner.station(station, network))
+    }
+
+    /// Filter channels of the selected station (`SELECT`).
+    pub fn select(&mut self, pattern: &str) -> Result<()> {
+        self.runtime.block_on(self.inner.select(pattern))
+    }
+
+    /// Arm the selected station from the beginning (`DATA`).
+    pub fn data(&mut self) -> Result<()> {
+        self.runtime.block_on(self.inner.data())
+    }
+
+    /// Arm the selected station from a sequence number (`DATA seq`).
+    pub fn data_from(&mut self, sequence: SequenceNumber) -> Result<()> {
+        self.runtime.block_on(self.inner.data_from(sequence))
+    }
+
+    /// Request a time window (`TIME`, v3 only).
+    pub fn time_window(&mut self, start: &str, end: Option<&str>) -> Result<()> {
+        self.runtime.block_on(self.inner.time_window(start, end))
+    }
+
+    // -- Streaming --
+
+    /// Start continuous streaming (`END`).
+    pub fn end_stream(&mut self) -> Result<()> {
+        self.runtime.block_on(self.inner.end_stream())
+    }
+
+    /// Stream buffered data, then close (`FETCH`, v3 only).
+    pub fn fetch(&mut self) -> Result<()> {
+        self.runtime.block_on(self.inner.fetch())
+    }
+
+    /// Read the next data frame. Returns `None` when the server closes the
+    /// stream.
+    pub fn next_frame(&mut self) -> Result<Option<OwnedFrame>> {
+        self.runtime.block_on(self.inner.next_frame())
+    }
+
+    /// Read the next frame, INFO frame, keepalive or text line.
+    pub fn next_event(&mut self) -> Result<StreamEvent> {
+        self.runtime.block_on(self.inner.next_event())
+    }
+
+    /// Iterate over data frames with [`next_frame()`](Self::next_frame) until
+    /// the stream ends.
+    pub fn frames(&mut self) -> impl Iterator<Item = Result<OwnedFrame>> + '_ {
+        std::iter::from_fn(|| self.next_frame().transpose())
+    }
+
+    // -- INFO --
+
+    /// Request an INFO response and return its frames.
+    pub fn info(&mut self, level: InfoLevel) -> Result<Vec<OwnedFrame>> {
+        self.runtime.block_on(self.inner.info(level))
+    }
+
+    /// `INFO STATIONS` parsed into [`StationInfo`]s.
+    pub fn info_stations(&mut self) -> Result<Vec<StationInfo>> {
+        self.runtime.block_on(self.inner.info_stations())
+    }
+
+    /// `INFO STREAMS` parsed into [`StreamInfo`]s.
+    pub fn info_streams(&mut self) -> Result<Vec<StreamInfo>> {
+        self.runtime.block_on(self.inner.info_streams())
+    }
+
+    // -- Closing --
+
+    /// Send BYE and close the connection.
+    pub fn bye(&mut self) -> Result<()> {
+        self.runtime.block_on(self.inner.bye())
+    }
+
+    /// Send `BYE` and close the connection, waiting at most 2 s.
+    pub fn close(mut self) -> Result<()> {
+        self.runtime.block_on(self.inner.close_in_place())
+    }
+
+    // -- State (no I/O) --
+
+    /// Returns the last received sequence number for a given network/station pair.
+    pub fn last_sequence(&self, network: &str, station: &str) -> Option<SequenceNumber> {
+        self.inner.last_sequence(network, station)
+    }
+
+    /// Returns all tracked sequence numbers.
+    pub fn sequences(&self) -> &HashMap<StationKey, SequenceNumber> {
+        self.inner.sequences()
+    }
+}
+
+impl Drop for SeedLinkClient {
+    /// Send the best-effort `BYE` of [`crate::SeedLinkClient`] on the private
+    /// runtime, which is gone once this returns.
+    fn drop(&mut self) {
+        if tokio::runtime::Handle::try_current().is_ok() {
+            // Dropped inside an async runtime, which can run the BYE itself
+            return;
+        }
+        let task = {
+            let _runtime = self.runtime.enter();
+            self.inner.spawn_bye()
+        };
+        if let Some(task) = task {
+            let _ = self.runtime.block_on(task);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockConfig, MockServer};
+    use seedlink_rs_protocol::frame::v3;
+    use seedlink_rs_protocol::testing::TestRecordBuilder;
+
+    fn make_v3_frame(seq: u64) -> Vec<u8> {
+        let payload = TestRecordBuilder::new()
+            .station("ANMO")
+            .network("IU")
+            .build();
+        v3::write(SequenceNumber::new(seq), &payload).unwrap()
+    }
+
+    #[test]
+    fn blocking_client_streams_frames() {
+        // The mock server needs its own runtime; the client must not run inside one
+        let server_rt = tokio::runtime::Runtime::new().unwrap();
+        let frames = vec![make_v3_frame(1), make_v3_frame(2)];
+        let config = MockConfig {
+            close_after_stream: true,
+            ..MockConfig::v3_default(frames)
+        };
+        let server = server_rt.block_on(MockServer::start(config));
+
+        let mut client = SeedLinkClient::connect(&server.addr().to_string()).unwrap();
+        assert_eq!(client.version(), ProtocolVersion::V3);
+        client.station("ANMO", "IU").unwrap();
+        client.select("BHZ").unwrap();
+        client.data().unwrap();
+        client.end_stream().unwrap();
+        assert_eq!(client.state(), ClientState::Streaming);
+
+        let sequences: Vec<_> = client
+            .frames()
+            .map(|frame| frame.unwrap().sequence())
+            .collect();
+        assert_eq!(sequences, [SequenceNumber::new(1), SequenceNumber::new(2)]);
+        assert_eq!(
+            client.last_sequence("IU", "ANMO"),
+            Some(SequenceNumber::new(2))
+        );
+
+        let cmds = server.captured().connection(0);
+        assert_eq!(cmds[1..], ["STATION ANMO IU", "SELECT BHZ", "DATA", "END"]);
+    }
+}
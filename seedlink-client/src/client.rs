@@ -1,13 +1,30 @@
 use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
 
 use futures_core::Stream;
-use seedlink_rs_protocol::{Command, InfoLevel, ProtocolVersion, Response, SequenceNumber};
+use seedlink_rs_protocol::{
+    Capabilities, Command, DataFrame, InfoLevel, ProtocolVersion, Response, SeedlinkError,
+    SequenceNumber, SlTime, ValidationMode,
+};
+use tokio::io::{AsyncRead, AsyncWrite};
 use tracing::{debug, info, trace, warn};
 
+use crate::batch::SubscriptionBuilder;
 use crate::connection::Connection;
+use crate::demux::DemuxedStreams;
 use crate::error::{ClientError, Result};
-use crate::negotiate;
-use crate::state::{ClientConfig, ClientState, OwnedFrame, ServerInfo, StationKey};
+use crate::hooks::Hooks;
+use crate::info::{self, StationInfo, StreamInfo};
+use crate::ordered::OrderedFrameStream;
+use crate::state::{
+    ClientConfig, ClientState, OwnedFrame, ProtocolPreference, ResumeGap, ServerInfo, StationKey,
+    StreamEvent,
+};
+use crate::stream::{StopCondition, UndecodableFrames};
+
+/// How long [`SeedLinkClient::close()`] and the `BYE` on drop wait to send
+/// `BYE`.
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(2);
 
 /// Async SeedLink client for connecting to seismic data servers.
 ///
@@ -32,6 +49,16 @@ use crate::state::{ClientConfig, ClientState, OwnedFrame, ServerInfo, StationKey
 /// # Ok(())
 /// # }
 /// ```
+///
+/// # Closing
+///
+/// [`close()`](Self::close) sends `BYE` and waits for it to go out. Dropping
+/// a client that is still connected — including the streams returned by
+/// [`into_stream()`](Self::into_stream) and friends — spawns a task on the
+/// current tokio runtime that sends `BYE` and closes the socket, giving up
+/// after 2 s. Dropped outside a runtime, the socket is closed without `BYE`.
+/// A `BYE` that could not be sent is logged as a warning, and only one that
+/// was sent is passed to [`on_command()`](Self::on_command) hooks.
 pub struct SeedLinkClient {
     connection: Connection,
     state: ClientState,
@@ -39,6 +66,22 @@ pub struct SeedLinkClient {
     server_info: ServerInfo,
     sequences: HashMap<StationKey, SequenceNumber>,
     config: ClientConfig,
+    batch: bool,
+    /// A v4 dial-up FETCH window is open; `END` returns to `Configured`.
+    fetching: bool,
+    /// Stations subscribed with exact (non-wildcard) codes, in order.
+    subscribed: Vec<StationKey>,
+    /// Station of the last STATION, if it has exact codes.
+    current_station: Option<StationKey>,
+    /// Stations armed with `DATA seq` whose first frame has not arrived,
+    /// checked for a [`ResumeGap`].
+    resuming: HashMap<StationKey, SequenceNumber>,
+    /// Read error hit by [`next_frames()`](Self::next_frames) after it had
+    /// already read frames; reported by the next read call.
+    pending_error: Option<ClientError>,
+    /// Records that failed [`ClientConfig::validation`].
+    invalid_records: u64,
+    hooks: Hooks,
 }
 
 impl SeedLinkClient {
@@ -56,8 +99,35 @@ impl SeedLinkClient {
     /// On success the client is in [`ClientState::Connected`].
     pub async fn connect_with_config(addr: &str, config: ClientConfig) -> Result<Self> {
         info!(addr, "connecting");
-        let mut connection =
-            Connection::connect(addr, config.connect_timeout, config.read_timeout).await?;
+        let connection = Connection::connect(
+            addr,
+            config.proxy.as_ref(),
+            &config.socket,
+            config.connect_timeout,
+            config.read_timeout,
+        )
+        .await?;
+        Self::handshake(connection, config).await
+    }
+
+    /// Talk SeedLink over an already connected transport instead of TCP: an
+    /// in-memory [`tokio::io::duplex`] stream for tests, a TLS stream, a
+    /// serial link, ...
+    ///
+    /// Sends HELLO and negotiates like
+    /// [`connect_with_config()`](Self::connect_with_config); the connect
+    /// timeout, proxy and socket options do not apply.
+    pub async fn connect_io<IO>(io: IO, config: ClientConfig) -> Result<Self>
+    where
+        IO: AsyncRead + AsyncWrite + Send + Sync + Unpin + 'static,
+    {
+        let connection = Connection::from_io(io, config.read_timeout);
+        Self::handshake(connection, config).await
+    }
+
+    /// HELLO, CAPABILITIES, SLPROTO and USERAGENT on a fresh connection.
+    async fn handshake(mut connection: Connection, config: ClientConfig) -> Result<Self> {
+        connection.max_payload_len = config.max_payload_len;
 
         // Send HELLO
         connection
@@ -83,15 +153,44 @@ impl SeedLinkClient {
             }
         };
 
-        let capabilities = negotiate::parse_capabilities(&extra);
+        let capabilities = Capabilities::from_hello_extra(&extra);
         let mut protocol_version = ProtocolVersion::V3;
+        let slproto = slproto_request(&config, &capabilities)?;
+
+        // CAPABILITIES is a v3 command, so compression is enabled before SLPROTO
+        #[cfg(feature = "zstd")]
+        let zstd = if config.compression
+            && slproto
+                .as_ref()
+                .is_some_and(|(_, v)| *v == ProtocolVersion::V4)
+            && capabilities.has(seedlink_rs_protocol::compression::ZSTD_CAPABILITY)
+        {
+            let cmd = Command::Capabilities {
+                capabilities: vec![seedlink_rs_protocol::compression::ZSTD_CAPABILITY.into()],
+            };
+            connection.send_command(&cmd, ProtocolVersion::V3).await?;
+            let response_line = connection.read_line().await?;
+            match Response::parse_line(&response_line)? {
+                Response::Ok => true,
+                Response::Error { description, .. } => {
+                    warn!(%description, "server rejected CAPABILITIES ZSTD");
+                    false
+                }
+                _ => {
+                    return Err(ClientError::UnexpectedResponse(format!(
+                        "expected OK or ERROR for CAPABILITIES, got: {response_line:?}"
+                    )));
+                }
+            }
+        } else {
+            false
+        };
 
-        // Attempt v4 negotiation if preferred and supported
-        if config.prefer_v4 && negotiate::supports_v4(&capabilities) {
+        if let Some((version, requested)) = slproto {
             connection
                 .send_command(
                     &Command::SlProto {
-                        version: "4.0".into(),
+                        version: version.clone(),
                     },
                     ProtocolVersion::V4,
                 )
@@ -101,11 +200,18 @@ impl SeedLinkClient {
             let response = Response::parse_line(&response_line)?;
             match response {
                 Response::Ok => {
-                    protocol_version = ProtocolVersion::V4;
+                    protocol_version = requested;
                 }
-                Response::Error { description, .. } => {
+                Response::Error { description, .. }
+                    if config.protocol == ProtocolPreference::Auto =>
+                {
                     warn!(%description, "v4 negotiation failed, falling back to v3");
                 }
+                Response::Error { description, .. } => {
+                    return Err(ClientError::NegotiationFailed(format!(
+                        "server rejected SLPROTO {version}: {description}"
+                    )));
+                }
                 _ => {
                     return Err(ClientError::UnexpectedResponse(format!(
                         "expected OK or ERROR for SLPROTO, got: {response_line:?}"
@@ -114,6 +220,25 @@ impl SeedLinkClient {
             }
         }
 
+        // The server only compresses v4 frames
+        #[cfg(feature = "zstd")]
+        {
+            connection.zstd = zstd && protocol_version == ProtocolVersion::V4;
+        }
+
+        if protocol_version == ProtocolVersion::V4
+            && let Some(description) = &config.user_agent
+        {
+            let cmd = Command::UserAgent {
+                description: description.clone(),
+            };
+            connection.send_command(&cmd, protocol_version).await?;
+            let response_line = connection.read_line().await?;
+            if let Response::Error { description, .. } = Response::parse_line(&response_line)? {
+                warn!(%description, "server rejected USERAGENT");
+            }
+        }
+
         let server_info = ServerInfo {
             software,
             version: version_str,
@@ -130,6 +255,14 @@ impl SeedLinkClient {
             server_info,
             sequences: HashMap::new(),
             config,
+            batch: false,
+            fetching: false,
+            subscribed: Vec::new(),
+            current_station: None,
+            resuming: HashMap::new(),
+            pending_error: None,
+            invalid_records: 0,
+            hooks: Hooks::default(),
         })
     }
 
@@ -155,6 +288,46 @@ impl SeedLinkClient {
         &self.config
     }
 
+    // -- Hooks --
+
+    /// Call `hook` with every command sent from now on, e.g. for metrics or
+    /// tracing. Commands of the connect handshake (`HELLO`, `SLPROTO`, ...)
+    /// are sent before a hook can be added and are not seen.
+    ///
+    /// Hooks are added, not replaced, and run in the order they were added.
+    pub fn on_command(&mut self, hook: impl Fn(&Command) + Send + Sync + 'static) {
+        self.hooks.add_command(hook);
+    }
+
+    /// Call `hook` with every data frame before it is returned by
+    /// [`next_frame()`](Self::next_frame), [`next_event()`](Self::next_event),
+    /// [`next_frames()`](Self::next_frames) or the streams built on them.
+    ///
+    /// The hook may modify the frame. It runs after sequence tracking and
+    /// validation, so those always see the frame as received; frames dropped
+    /// by validation are not passed to it. INFO frames are not hooked.
+    ///
+    /// Hooks are added, not replaced, and run in the order they were added.
+    pub fn on_frame(&mut self, hook: impl Fn(&mut OwnedFrame) + Send + Sync + 'static) {
+        self.hooks.add_frame(hook);
+    }
+
+    /// Call `hook` when a station resumed with
+    /// [`data_from()`](Self::data_from) receives its first frame past more
+    /// than [`ClientConfig::resume_gap_threshold`] skipped sequences, e.g. to
+    /// request the missing data from an FDSN dataselect service. Each gap is
+    /// also logged as a warning.
+    ///
+    /// Hooks are added, not replaced, and run in the order they were added.
+    pub fn on_resume_gap(&mut self, hook: impl Fn(&ResumeGap) + Send + Sync + 'static) {
+        self.hooks.add_resume_gap(hook);
+    }
+
+    /// Replace all hooks (used by `ReconnectingClient` on each connection).
+    pub(crate) fn set_hooks(&mut self, hooks: Hooks) {
+        self.hooks = hooks;
+    }
+
     // -- Configuration (Connected|Configured → Configured) --
 
     /// Select a station and network for data subscription.
@@ -172,11 +345,12 @@ impl SeedLinkClient {
             station: station.to_owned(),
             network: network.to_owned(),
         };
-        self.connection.send_command(&cmd, self.version).await?;
+        self.send(&cmd).await?;
 
         // All modern servers reply OK/ERROR (EXTREPLY behavior)
         self.read_ok_response("STATION").await?;
 
+        self.track_subscription(network, station);
         self.state = ClientState::Configured;
         Ok(())
     }
@@ -192,7 +366,7 @@ impl SeedLinkClient {
         let cmd = Command::Select {
             pattern: pattern.to_owned(),
         };
-        self.connection.send_command(&cmd, self.version).await?;
+        self.send(&cmd).await?;
 
         // All modern servers reply OK/ERROR (EXTREPLY behavior)
         self.read_ok_response("SELECT").await?;
@@ -201,6 +375,92 @@ impl SeedLinkClient {
         Ok(())
     }
 
+    /// Select stations by wildcard pattern (e.g., `("IU", "*")`, `("G?", "W??")`).
+    ///
+    /// Patterns may contain `?` (one character) and `*` (any run of characters).
+    /// Whether wildcards are honored is up to the server (`NSWILDCARD` capability).
+    /// Requires state `Connected` or `Configured`. Transitions to `Configured`.
+    pub async fn station_pattern(&mut self, net_pattern: &str, sta_pattern: &str) -> Result<()> {
+        validate_pattern(net_pattern)?;
+        validate_pattern(sta_pattern)?;
+        self.station(sta_pattern, net_pattern).await
+    }
+
+    /// Subscribe to a list of `(network, station, selectors)` streams in one call.
+    ///
+    /// Issues STATION, one SELECT per selector, and DATA for each entry,
+    /// through [`batch()`](Self::batch): when the server advertises `BATCH`
+    /// (v3 only), BATCH mode is enabled and all commands are written in a
+    /// single flush without waiting for per-command replies.
+    /// Requires state `Connected` or `Configured`. Transitions to `Configured`.
+    pub async fn subscribe_all(&mut self, streams: &[(&str, &str, &[&str])]) -> Result<()> {
+        let mut batch = self.batch();
+        for (network, station, selectors) in streams {
+            batch = batch.station(station, network);
+            for pattern in *selectors {
+                batch = batch.select(pattern);
+            }
+            batch = batch.data();
+        }
+        batch.send().await
+    }
+
+    /// Start buffering STATION/SELECT/DATA/TIME commands to send in one go.
+    ///
+    /// See [`SubscriptionBuilder`]; nothing is sent until
+    /// [`SubscriptionBuilder::send()`] is called.
+    pub fn batch(&mut self) -> SubscriptionBuilder<'_> {
+        SubscriptionBuilder::new(self)
+    }
+
+    /// Send commands buffered by a [`SubscriptionBuilder`].
+    pub(crate) async fn send_batch(
+        &mut self,
+        commands: Vec<(&'static str, Command)>,
+    ) -> Result<()> {
+        self.require_state_in(&[ClientState::Connected, ClientState::Configured], "batch")?;
+        let Some((_, first)) = commands.first() else {
+            return Ok(());
+        };
+        if !matches!(first, Command::Station { .. }) {
+            self.require_state_in(&[ClientState::Configured], "batch")?;
+        }
+
+        // Fail on a command the negotiated version lacks before sending any
+        let mut bytes = Vec::new();
+        for (_, cmd) in &commands {
+            bytes.extend(cmd.to_bytes(self.version)?);
+        }
+
+        let use_batch =
+            self.version == ProtocolVersion::V3 && self.server_info.capabilities.has("BATCH");
+        if !use_batch {
+            for (name, cmd) in &commands {
+                debug!(command = name, "sending");
+                self.send(cmd).await?;
+                self.read_ok_response(name).await?;
+                self.track_command(cmd);
+                self.state = ClientState::Configured;
+            }
+            return Ok(());
+        }
+
+        if !self.batch {
+            self.enter_batch().await?;
+        }
+        debug!(count = commands.len(), "sending commands in BATCH mode");
+        for (_, cmd) in &commands {
+            self.hooks.command(cmd);
+        }
+        self.connection.send_raw(&bytes).await?;
+
+        for (_, cmd) in &commands {
+            self.track_command(cmd);
+        }
+        self.state = ClientState::Configured;
+        Ok(())
+    }
+
     // -- Arming (Configured → Configured) --
 
     /// Arm the current station subscription with DATA (stream from beginning).
@@ -217,7 +477,7 @@ impl SeedLinkClient {
             start: None,
             end: None,
         };
-        self.connection.send_command(&cmd, self.version).await?;
+        self.send(&cmd).await?;
 
         // Server replies OK/ERROR
         self.read_ok_response("DATA").await?;
@@ -238,7 +498,48 @@ impl SeedLinkClient {
             start: None,
             end: None,
         };
-        self.connection.send_command(&cmd, self.version).await?;
+        self.send(&cmd).await?;
+
+        // Server replies OK/ERROR
+        self.read_ok_response("DATA").await?;
+        self.track_resume(sequence);
+
+        // State stays Configured — END triggers streaming
+        Ok(())
+    }
+
+    /// Arm the current station subscription with everything the server has
+    /// buffered (`DATA ALL`, v4 only).
+    ///
+    /// Requires state `Configured`. State stays `Configured`.
+    pub async fn data_all(&mut self) -> Result<()> {
+        self.require_v4("DATA ALL")?;
+        self.data_from(SequenceNumber::ALL_DATA).await
+    }
+
+    /// Arm the current station subscription with new data only, skipping
+    /// what the server has buffered (`DATA -1`, v4 only).
+    ///
+    /// Requires state `Configured`. State stays `Configured`.
+    pub async fn data_newest(&mut self) -> Result<()> {
+        self.require_v4("DATA -1")?;
+        self.data_from(SequenceNumber::NEWEST).await
+    }
+
+    /// Arm the current station subscription with a time range (v4 only).
+    ///
+    /// Sends `DATA ALL start [end]` with ISO-8601 times such as
+    /// `"2024-01-15T00:00:00Z"`. Times may also be given in the v3 comma
+    /// format; an unparseable time fails before anything is sent. On v3
+    /// connections use [`time_window()`](Self::time_window) instead.
+    /// Requires state `Configured`. State stays `Configured`.
+    pub async fn data_time_range(&mut self, start: &str, end: Option<&str>) -> Result<()> {
+        self.require_state_in(&[ClientState::Configured], "data_time_range")?;
+        self.require_v4("DATA (time range)")?;
+
+        debug!(start, ?end, "DATA (time range)");
+        let cmd = Command::data_time(Some(SequenceNumber::ALL_DATA), start, end)?;
+        self.send(&cmd).await?;
 
         // Server replies OK/ERROR
         self.read_ok_response("DATA").await?;
@@ -250,16 +551,15 @@ impl SeedLinkClient {
     /// Arm the current station subscription with a time window (v3 only).
     ///
     /// Sends `TIME start [end]` to request data within a specific time range.
+    /// Times are `YYYY,MM,DD,hh,mm,ss` or ISO-8601 and are sent in the v3
+    /// comma format; an unparseable time fails before anything is sent.
     /// Requires state `Configured`. State stays `Configured`.
     pub async fn time_window(&mut self, start: &str, end: Option<&str>) -> Result<()> {
         self.require_state_in(&[ClientState::Configured], "time_window")?;
 
         debug!(start, ?end, "TIME");
-        let cmd = Command::Time {
-            start: start.to_owned(),
-            end: end.map(|s| s.to_owned()),
-        };
-        self.connection.send_command(&cmd, self.version).await?;
+        let cmd = Command::time(start, end)?;
+        self.send(&cmd).await?;
 
         self.read_ok_response("TIME").await?;
 
@@ -267,6 +567,41 @@ impl SeedLinkClient {
         Ok(())
     }
 
+    /// Arm the current station subscription with a time window on either
+    /// protocol version.
+    ///
+    /// Sends `TIME start [end]` on v3 and `DATA ALL start [end]` on v4, with
+    /// the times formatted for the negotiated version. v3 times have whole
+    /// seconds only.
+    /// Requires state `Configured`. State stays `Configured`.
+    pub async fn time_window_range(
+        &mut self,
+        start: SystemTime,
+        end: Option<SystemTime>,
+    ) -> Result<()> {
+        self.require_state_in(&[ClientState::Configured], "time_window_range")?;
+
+        let (start, end) = (SlTime::from(start), end.map(SlTime::from));
+        let (name, cmd) = match self.version {
+            ProtocolVersion::V3 => ("TIME", Command::Time { start, end }),
+            ProtocolVersion::V4 => (
+                "DATA",
+                Command::Data {
+                    sequence: Some(SequenceNumber::ALL_DATA),
+                    start: Some(start),
+                    end,
+                },
+            ),
+        };
+        debug!(%start, ?end, command = name, "time window");
+        self.send(&cmd).await?;
+
+        self.read_ok_response(name).await?;
+
+        // State stays Configured — END triggers streaming
+        Ok(())
+    }
+
     // -- Streaming (Configured → Streaming) --
 
     /// Send END to trigger continuous binary streaming.
@@ -276,9 +611,7 @@ impl SeedLinkClient {
     pub async fn end_stream(&mut self) -> Result<()> {
         self.require_state_in(&[ClientState::Configured], "end_stream")?;
 
-        self.connection
-            .send_command(&Command::End, self.version)
-            .await?;
+        self.send(&Command::End).await?;
 
         // END has NO text response — binary streaming starts immediately
         self.state = ClientState::Streaming;
@@ -292,9 +625,10 @@ impl SeedLinkClient {
     /// Requires state `Configured`. Transitions to `Streaming`.
     pub async fn fetch(&mut self) -> Result<()> {
         self.require_state_in(&[ClientState::Configured], "fetch")?;
+        self.require_v3("FETCH")?;
 
         let cmd = Command::Fetch { sequence: None };
-        self.connection.send_command(&cmd, self.version).await?;
+        self.send(&cmd).await?;
 
         self.state = ClientState::Streaming;
         Ok(())
@@ -305,11 +639,49 @@ impl SeedLinkClient {
     /// Requires state `Configured`. Transitions to `Streaming`.
     pub async fn fetch_from(&mut self, sequence: SequenceNumber) -> Result<()> {
         self.require_state_in(&[ClientState::Configured], "fetch_from")?;
+        self.require_v3("FETCH")?;
 
         let cmd = Command::Fetch {
             sequence: Some(sequence),
         };
-        self.connection.send_command(&cmd, self.version).await?;
+        self.send(&cmd).await?;
+
+        self.state = ClientState::Streaming;
+        Ok(())
+    }
+
+    /// Send FETCH in v4 dial-up mode (v4 only).
+    ///
+    /// The server sends what it has buffered, then an `END` marker, and keeps
+    /// the connection open. [`next_frame()`](Self::next_frame) returns `None`
+    /// at the marker and the state returns to `Configured`, so the client can
+    /// fetch again later without reconnecting; the server resumes after the
+    /// last record sent.
+    /// Requires state `Configured`. Transitions to `Streaming`.
+    pub async fn fetch_v4(&mut self) -> Result<()> {
+        self.require_state_in(&[ClientState::Configured], "fetch_v4")?;
+        self.require_v4("FETCH (dial-up)")?;
+
+        debug!("FETCH (dial-up)");
+        let cmd = Command::Fetch { sequence: None };
+        self.send(&cmd).await?;
+
+        self.fetching = true;
+        self.state = ClientState::Streaming;
+        Ok(())
+    }
+
+    /// Send ENDFETCH to end a v4 dial-up session (v4 only).
+    ///
+    /// The server sends any remaining buffered data, then closes the
+    /// connection: [`next_frame()`](Self::next_frame) returns `None` on EOF.
+    /// Requires state `Configured`. Transitions to `Streaming`.
+    pub async fn end_fetch(&mut self) -> Result<()> {
+        self.require_state_in(&[ClientState::Configured], "end_fetch")?;
+        self.require_v4("ENDFETCH")?;
+
+        debug!("ENDFETCH");
+        self.send(&Command::EndFetch).await?;
 
         self.state = ClientState::Streaming;
         Ok(())
@@ -322,31 +694,226 @@ impl SeedLinkClient {
     /// Returns `Ok(Some(frame))` on success, `Ok(None)` on clean EOF
     /// (server closed connection), or `Err` on protocol/timeout errors.
     /// On EOF, state transitions to `Disconnected`.
+    /// After [`fetch_v4()`](Self::fetch_v4), `Ok(None)` marks the end of the
+    /// fetch window instead and the state returns to `Configured`.
+    /// With [`ClientConfig::validation`] set, invalid records are skipped,
+    /// flagged or reported as [`ClientError::InvalidRecord`].
+    ///
+    /// Cancel-safe: bytes of a partly received frame are kept by the
+    /// connection, so the call can be dropped (e.g. as a losing
+    /// `tokio::select!` branch) and retried without losing or corrupting data.
     /// Requires state `Streaming`.
     pub async fn next_frame(&mut self) -> Result<Option<OwnedFrame>> {
         self.require_state_in(&[ClientState::Streaming], "next_frame")?;
+        if let Some(e) = self.pending_error.take() {
+            return Err(e);
+        }
 
-        let result = match self.version {
-            ProtocolVersion::V3 => self.connection.read_v3_frame().await,
-            ProtocolVersion::V4 => self.connection.read_v4_frame().await,
-        };
+        loop {
+            let Some(mut frame) = self.read_frame().await? else {
+                return Ok(None);
+            };
+            if self.accept_frame(&mut frame)? {
+                return Ok(Some(frame));
+            }
+        }
+    }
+
+    /// Read the next event from the server.
+    ///
+    /// Like [`next_frame()`](Self::next_frame), but everything the server
+    /// sends is reported as a [`StreamEvent`]: data frames, INFO frames and
+    /// keepalives, `END`, `ERROR` and other text lines. Frames are recognized
+    /// by signature, so v3 and v4 frames are both accepted whatever version
+    /// was negotiated. When the connection closes or is reset the last event
+    /// is [`StreamEvent::Disconnected`] and the state is `Disconnected`; after
+    /// `END` closes a [`fetch_v4()`](Self::fetch_v4) window it is `Configured`.
+    /// Cancel-safe, like `next_frame()`.
+    /// Requires state `Streaming`.
+    pub async fn next_event(&mut self) -> Result<StreamEvent> {
+        self.require_state_in(&[ClientState::Streaming], "next_event")?;
+        if let Some(e) = self.pending_error.take() {
+            return Err(e);
+        }
+
+        loop {
+            let mut event = match self.read_event().await {
+                Ok(event) => event,
+                Err(ClientError::Io(e))
+                    if matches!(
+                        e.kind(),
+                        std::io::ErrorKind::ConnectionReset
+                            | std::io::ErrorKind::ConnectionAborted
+                            | std::io::ErrorKind::BrokenPipe
+                    ) =>
+                {
+                    self.state = ClientState::Disconnected;
+                    StreamEvent::Disconnected {
+                        reason: e.to_string(),
+                    }
+                }
+                Err(e) => return Err(e),
+            };
+            if let StreamEvent::Frame(frame) = &mut event
+                && !self.accept_frame(frame)?
+            {
+                continue;
+            }
+            return Ok(event);
+        }
+    }
+
+    /// Read the next data frame off the wire, before validation.
+    ///
+    /// `END` closes a dial-up FETCH window (`None`); otherwise the server is
+    /// about to close the connection and reading continues until EOF. `ERROR`
+    /// fails with [`ClientError::ServerError`]; INFO frames, keepalives and
+    /// other text lines are skipped.
+    async fn read_frame(&mut self) -> Result<Option<OwnedFrame>> {
+        loop {
+            let fetching = self.fetching;
+            match self.read_event().await? {
+                StreamEvent::Frame(frame) => return Ok(Some(frame)),
+                StreamEvent::InfoFrame(_) => debug!("skipping INFO frame"),
+                StreamEvent::KeepAlive => trace!("keepalive received"),
+                StreamEvent::EndOfFetch if fetching => return Ok(None),
+                StreamEvent::EndOfFetch => debug!("END received, waiting for server to close"),
+                StreamEvent::Error { code, description } => {
+                    let msg = match code {
+                        Some(c) => format!("{} {description}", c.as_str()),
+                        None => description,
+                    };
+                    return Err(ClientError::ServerError(msg));
+                }
+                StreamEvent::Text(line) => warn!(line, "ignoring unexpected line in stream"),
+                StreamEvent::Disconnected { .. } => return Ok(None),
+            }
+        }
+    }
 
-        match result {
-            Ok(frame) => {
+    /// Read the next event off the wire and update state: split INFO frames
+    /// from data, track sequences, leave a finished FETCH window, and map EOF
+    /// to [`StreamEvent::Disconnected`].
+    async fn read_event(&mut self) -> Result<StreamEvent> {
+        match self.connection.read_event().await {
+            Ok(StreamEvent::Frame(frame)) if frame.is_keepalive() => Ok(StreamEvent::KeepAlive),
+            Ok(StreamEvent::Frame(frame)) if frame.is_info() => Ok(StreamEvent::InfoFrame(frame)),
+            Ok(StreamEvent::Frame(frame)) => {
                 trace!(sequence = %frame.sequence(), "frame received");
+                self.check_resume_gap(&frame);
                 self.track_sequence(&frame);
-                Ok(Some(frame))
+                Ok(StreamEvent::Frame(frame))
             }
-            Err(ClientError::Disconnected) => {
-                self.state = ClientState::Disconnected;
-                Ok(None)
+            Ok(StreamEvent::EndOfFetch) if self.fetching => {
+                debug!("end of FETCH window");
+                self.fetching = false;
+                self.state = ClientState::Configured;
+                Ok(StreamEvent::EndOfFetch)
             }
+            Ok(event) => Ok(event),
+            Err(ClientError::Disconnected) => Ok(self.disconnected()),
             Err(ClientError::Io(ref e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
-                self.state = ClientState::Disconnected;
-                Ok(None)
+                Ok(self.disconnected())
+            }
+            Err(e) => {
+                self.fetching = false;
+                Err(e)
+            }
+        }
+    }
+
+    /// Record a clean close by the server.
+    fn disconnected(&mut self) -> StreamEvent {
+        self.fetching = false;
+        self.state = ClientState::Disconnected;
+        StreamEvent::Disconnected {
+            reason: "closed by server".into(),
+        }
+    }
+
+    /// Apply [`ClientConfig::validation`], then the frame hooks, to a
+    /// received frame. Returns whether the frame should be delivered.
+    fn accept_frame(&mut self, frame: &mut OwnedFrame) -> Result<bool> {
+        let accepted = self.validate_frame(frame)?;
+        if accepted {
+            self.hooks.frame(frame);
+        }
+        Ok(accepted)
+    }
+
+    /// Apply [`ClientConfig::validation`] to a received frame. Returns whether
+    /// the frame should be delivered.
+    fn validate_frame(&mut self, frame: &OwnedFrame) -> Result<bool> {
+        if self.config.validation == ValidationMode::Off {
+            return Ok(true);
+        }
+        let Err(error) = frame.validate() else {
+            return Ok(true);
+        };
+        self.invalid_records += 1;
+        let sequence = frame.sequence();
+        match self.config.validation {
+            ValidationMode::Drop => {
+                warn!(%sequence, %error, "dropping invalid record");
+                Ok(false)
             }
-            Err(e) => Err(e),
+            ValidationMode::Error => Err(ClientError::InvalidRecord {
+                sequence,
+                source: error,
+            }),
+            ValidationMode::Flag | ValidationMode::Off => {
+                warn!(%sequence, %error, "invalid record");
+                Ok(true)
+            }
+        }
+    }
+
+    /// Read up to `max` frames in one call.
+    ///
+    /// Waits up to `timeout` in total and returns early once `max` frames are
+    /// read or the stream ends ([`next_frame()`](Self::next_frame) returning
+    /// `None`). Only the wait for a frame to start is bounded by `timeout`; a
+    /// frame already arriving is always read completely, so no data is lost.
+    /// An empty `Vec` while the state is still `Streaming` means no frame
+    /// arrived in time.
+    ///
+    /// If a read fails after some frames were read, those frames are returned
+    /// and the error is reported by the next call.
+    /// Requires state `Streaming`.
+    pub async fn next_frames(&mut self, max: usize, timeout: Duration) -> Result<Vec<OwnedFrame>> {
+        self.require_state_in(&[ClientState::Streaming], "next_frames")?;
+        if let Some(e) = self.pending_error.take() {
+            return Err(e);
+        }
+
+        let deadline = tokio::time::Instant::now() + timeout;
+        let mut frames = Vec::new();
+        let error = loop {
+            if frames.len() >= max || self.state != ClientState::Streaming {
+                return Ok(frames);
+            }
+            let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+            match self.connection.wait_readable(remaining).await {
+                Ok(true) => {}
+                Ok(false) => return Ok(frames),
+                Err(e) => break e,
+            }
+            let mut frame = match self.read_frame().await {
+                Ok(Some(frame)) => frame,
+                Ok(None) => return Ok(frames),
+                Err(e) => break e,
+            };
+            match self.accept_frame(&mut frame) {
+                Ok(true) => frames.push(frame),
+                Ok(false) => {}
+                Err(e) => break e,
+            }
+        };
+        if frames.is_empty() {
+            return Err(error);
         }
+        self.pending_error = Some(error);
+        Ok(frames)
     }
 
     // -- Stream conversion --
@@ -354,11 +921,68 @@ impl SeedLinkClient {
     /// Consume this client and return a [`Stream`] of frames.
     ///
     /// The client must be in `Streaming` state. The stream yields
-    /// `Ok(OwnedFrame)` per frame and ends with `None` on EOF.
+    /// `Ok(OwnedFrame)` per frame and ends with `None` on EOF. Dropping the
+    /// stream drops the client, which sends `BYE`.
     pub fn into_stream(self) -> impl Stream<Item = Result<OwnedFrame>> {
         crate::stream::frame_stream(self)
     }
 
+    /// Consume this client and return a [`Stream`] of frames that ends once
+    /// `condition` is met.
+    ///
+    /// The client must be in `Streaming` state. When the condition is met the
+    /// client sends `BYE` via [`close()`](Self::close) and the stream yields
+    /// `None`; it also ends early on EOF. Useful for bounded extraction jobs.
+    pub fn stream_until(self, condition: StopCondition) -> impl Stream<Item = Result<OwnedFrame>> {
+        crate::stream::frame_stream_until(self, condition)
+    }
+
+    /// Consume this client and return a [`Stream`] of decoded miniSEED
+    /// records.
+    ///
+    /// Like [`into_stream()`](Self::into_stream), but each frame is decoded
+    /// with [`OwnedFrame::decode()`]. A frame that fails to decode is yielded
+    /// as [`ClientError::Protocol`] or skipped, per `undecodable`; either way
+    /// the stream continues.
+    pub fn into_record_stream(
+        self,
+        undecodable: UndecodableFrames,
+    ) -> impl Stream<Item = Result<DataFrame>> {
+        crate::stream::record_stream(self.into_stream(), undecodable)
+    }
+
+    /// Consume this client and return a [`Stream`] of [`StreamEvent`]s.
+    ///
+    /// The client must be in `Streaming` state. The stream ends after
+    /// [`StreamEvent::Disconnected`], or after the `END` of a
+    /// [`fetch_v4()`](Self::fetch_v4) window.
+    pub fn into_event_stream(self) -> impl Stream<Item = Result<StreamEvent>> {
+        crate::stream::event_stream(self)
+    }
+
+    /// Consume this client and split its frames into one channel per station.
+    ///
+    /// The client must be in `Streaming` state. Receivers for every station in
+    /// [`subscribed_stations()`](Self::subscribed_stations) are ready at once;
+    /// stations first seen while streaming (wildcard subscriptions) are announced
+    /// through [`DemuxedStreams::next_new_station()`]. Each channel buffers up to
+    /// `buffer` frames; must be called within a tokio runtime.
+    pub fn into_demuxed_streams(self, buffer: usize) -> DemuxedStreams {
+        DemuxedStreams::spawn(self, buffer)
+    }
+
+    /// Consume this client and return a [`Stream`] of frames ordered by record
+    /// start time per channel.
+    ///
+    /// Frames are held for `delay`; see [`OrderedFrameStream`] for how late
+    /// records are flagged.
+    pub fn into_ordered_stream(
+        self,
+        delay: Duration,
+    ) -> OrderedFrameStream<impl Stream<Item = Result<OwnedFrame>>> {
+        OrderedFrameStream::new(self.into_stream(), delay)
+    }
+
     // -- Utility (any state) --
 
     /// Request server information at the given detail level.
@@ -367,71 +991,116 @@ impl SeedLinkClient {
     /// Can be called in any state.
     pub async fn info(&mut self, level: InfoLevel) -> Result<Vec<OwnedFrame>> {
         let cmd = Command::Info { level };
-        self.connection.send_command(&cmd, self.version).await?;
+        self.send(&cmd).await?;
 
         let mut frames = Vec::new();
 
-        // INFO response: SL frames containing XML, terminated by text line or
-        // last frame having '*' in header. Mock sends frames then "END\r\n".
-        loop {
-            let mut peek = [0u8; 2];
-            self.connection.read_exact(&mut peek).await?;
-
-            match &peek {
-                b"SL" => {
-                    let mut buf = [0u8; seedlink_rs_protocol::frame::v3::FRAME_LEN];
-                    buf[0..2].copy_from_slice(&peek);
-                    self.connection.read_exact(&mut buf[2..]).await?;
-                    let raw = seedlink_rs_protocol::frame::v3::parse(&buf)?;
-                    frames.push(OwnedFrame::from(raw));
-                }
-                b"SE" => {
-                    let mut header = [0u8; seedlink_rs_protocol::frame::v4::MIN_HEADER_LEN];
-                    header[0..2].copy_from_slice(&peek);
-                    self.connection.read_exact(&mut header[2..]).await?;
-                    let station_id_len = header[16] as usize;
-                    let payload_len =
-                        u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
-                    let remaining = station_id_len + payload_len;
-                    let mut full = Vec::with_capacity(
-                        seedlink_rs_protocol::frame::v4::MIN_HEADER_LEN + remaining,
-                    );
-                    full.extend_from_slice(&header);
-                    full.resize(
-                        seedlink_rs_protocol::frame::v4::MIN_HEADER_LEN + remaining,
-                        0,
-                    );
-                    self.connection
-                        .read_exact(&mut full[seedlink_rs_protocol::frame::v4::MIN_HEADER_LEN..])
-                        .await?;
-                    let (raw, _) = seedlink_rs_protocol::frame::v4::parse(&full)?;
-                    frames.push(OwnedFrame::from(raw));
-                }
-                _ => {
-                    // Text line (END, ERROR, etc.) — read rest and stop
-                    let prefix = String::from_utf8_lossy(&peek).to_string();
-                    let rest = self.connection.read_line().await?;
-                    let _full_line = format!("{prefix}{rest}");
-                    break;
-                }
+        // INFO response: SL frames whose sequence field reads "INFO *" on all
+        // but the last ("INFO  "), or one SE frame containing XML followed by
+        // "END\r\n". A text line (END, ERROR, etc.) also ends the reply, for
+        // v3 servers that number INFO frames and terminate them with END.
+        while let StreamEvent::Frame(frame) = self.connection.read_event().await? {
+            frames.push(frame);
+            if self.connection.last_v3_info == Some(false) {
+                break;
             }
         }
 
         Ok(frames)
     }
 
+    /// Request `INFO STATIONS` and parse the reply into [`StationInfo`] entries.
+    ///
+    /// Multi-frame replies are reassembled before parsing. Can be called in any state.
+    pub async fn info_stations(&mut self) -> Result<Vec<StationInfo>> {
+        let frames = self.info(InfoLevel::Stations).await?;
+        Ok(info::parse_stations_xml(&info::reassemble_xml(&frames)))
+    }
+
+    /// Request `INFO STREAMS` and parse the reply into [`StreamInfo`] entries.
+    ///
+    /// Multi-frame replies are reassembled before parsing. Can be called in any state.
+    pub async fn info_streams(&mut self) -> Result<Vec<StreamInfo>> {
+        let frames = self.info(InfoLevel::Streams).await?;
+        Ok(info::parse_streams_xml(&info::reassemble_xml(&frames)))
+    }
+
+    /// Request INFO at the given level and decode the reply as JSON.
+    ///
+    /// JSON replies (v4 [`PayloadFormat::Json`](seedlink_rs_protocol::PayloadFormat::Json))
+    /// are parsed directly and XML replies are converted, so callers need not
+    /// know which format the server uses; see [`info::info_json()`]. Can be
+    /// called in any state.
+    pub async fn info_json(&mut self, level: InfoLevel) -> Result<serde_json::Value> {
+        let frames = self.info(level).await?;
+        Ok(info::info_json(&frames)?)
+    }
+
     /// Send BYE and close the connection.
     ///
     /// Transitions to `Disconnected`. Can be called in any state.
     pub async fn bye(&mut self) -> Result<()> {
-        self.connection
-            .send_command(&Command::Bye, self.version)
-            .await?;
+        self.send(&Command::Bye).await?;
         self.connection.shutdown().await.ok();
         self.state = ClientState::Disconnected;
         Ok(())
     }
 
+    /// Send `BYE` and close the connection, waiting at most 2 s.
+    ///
+    /// Does nothing if the client is already `Disconnected`. Fails with
+    /// [`ClientError::Timeout`] if the server does not take the command in
+    /// time (e.g. it stopped reading); the connection is closed regardless.
+    pub async fn close(mut self) -> Result<()> {
+        self.close_in_place().await
+    }
+
+    /// [`close()`](Self::close) for owners that cannot give up the client.
+    pub(crate) async fn close_in_place(&mut self) -> Result<()> {
+        if self.state == ClientState::Disconnected {
+            return Ok(());
+        }
+        let result = tokio::time::timeout(CLOSE_TIMEOUT, self.bye()).await;
+        self.state = ClientState::Disconnected;
+        result.map_err(|_| ClientError::Timeout(CLOSE_TIMEOUT))?
+    }
+
+    /// Spawn the `BYE` on drop, if still connected. Returns the task, or
+    /// `None` if there was nothing to send or no runtime to send it on.
+    pub(crate) fn spawn_bye(&mut self) -> Option<tokio::task::JoinHandle<()>> {
+        if self.state == ClientState::Disconnected {
+            return None;
+        }
+        self.state = ClientState::Disconnected;
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            warn!("client dropped while connected outside a tokio runtime, BYE not sent");
+            return None;
+        };
+        let bye = Command::Bye.to_bytes(self.version).ok()?;
+        let mut writer = self.connection.take_writer();
+        let hooks = std::mem::take(&mut self.hooks);
+        Some(runtime.spawn(async move {
+            use tokio::io::AsyncWriteExt;
+
+            let sent = tokio::time::timeout(CLOSE_TIMEOUT, async {
+                writer.write_all(&bye).await?;
+                writer.flush().await?;
+                writer.shutdown().await
+            })
+            .await;
+            match sent {
+                Ok(Ok(())) => {
+                    debug!("client dropped while connected, sent BYE");
+                    hooks.command(&Command::Bye);
+                }
+                Ok(Err(error)) => warn!(%error, "failed to send BYE for dropped client"),
+                Err(_) => {
+                    warn!(timeout = ?CLOSE_TIMEOUT, "timed out sending BYE for dropped client")
+                }
+            }
+        }))
+    }
+
     // -- State (no I/O) --
 
     /// Returns the last received sequence number for a given network/station pair.
@@ -450,8 +1119,96 @@ impl SeedLinkClient {
         &self.sequences
     }
 
+    /// Number of received records that failed [`ClientConfig::validation`],
+    /// whether they were dropped, flagged or reported as an error.
+    pub fn invalid_records(&self) -> u64 {
+        self.invalid_records
+    }
+
+    /// Stations subscribed with exact network/station codes, in subscription order.
+    ///
+    /// Wildcard subscriptions ([`station_pattern()`](Self::station_pattern)) are not listed.
+    pub fn subscribed_stations(&self) -> &[StationKey] {
+        &self.subscribed
+    }
+
     // -- Private helpers --
 
+    fn track_subscription(&mut self, network: &str, station: &str) {
+        let wildcard = |s: &str| s.contains(['?', '*']);
+        self.current_station = None;
+        if wildcard(network) || wildcard(station) {
+            return;
+        }
+        let key = StationKey {
+            network: network.to_owned(),
+            station: station.to_owned(),
+        };
+        self.current_station = Some(key.clone());
+        if !self.subscribed.contains(&key) {
+            self.subscribed.push(key);
+        }
+    }
+
+    /// Track a STATION or `DATA seq` sent in a batch.
+    fn track_command(&mut self, cmd: &Command) {
+        match cmd {
+            Command::Station { station, network } => self.track_subscription(network, station),
+            Command::Data {
+                sequence: Some(seq),
+                ..
+            } => self.track_resume(*seq),
+            _ => {}
+        }
+    }
+
+    /// Remember that the current station resumes after `sequence`, to check
+    /// its first frame for a [`ResumeGap`].
+    fn track_resume(&mut self, sequence: SequenceNumber) {
+        if self.config.resume_gap_threshold.is_none() || sequence.is_special() {
+            return;
+        }
+        if let Some(key) = self.current_station.clone() {
+            self.resuming.insert(key, sequence);
+        }
+    }
+
+    /// Report a [`ResumeGap`] if `frame` is the first of a resumed station
+    /// and skips more than the configured threshold.
+    fn check_resume_gap(&mut self, frame: &OwnedFrame) {
+        let Some(threshold) = self.config.resume_gap_threshold else {
+            return;
+        };
+        if self.resuming.is_empty() {
+            return;
+        }
+        let Some(station) = frame.station_key() else {
+            return;
+        };
+        // A server may resend the requested record itself first
+        if let Some(&requested) = self.resuming.get(&station)
+            && frame.is_after(requested)
+        {
+            self.resuming.remove(&station);
+            let gap = ResumeGap {
+                station,
+                requested,
+                first_received: frame.sequence(),
+            };
+            if gap.missed() > threshold {
+                warn!(
+                    network = %gap.station.network,
+                    station = %gap.station.station,
+                    requested = %gap.requested,
+                    first_received = %gap.first_received,
+                    missed = gap.missed(),
+                    "records lost before resume"
+                );
+                self.hooks.resume_gap(&gap);
+            }
+        }
+    }
+
     fn require_state_in(&self, allowed: &[ClientState], _method: &str) -> Result<()> {
         if allowed.contains(&self.state) {
             Ok(())
@@ -469,7 +1226,44 @@ impl SeedLinkClient {
         }
     }
 
-    async fn read_ok_response(&mut self, command_name: &str) -> Result<()> {
+    fn require_v3(&self, command: &'static str) -> Result<()> {
+        self.require_version(ProtocolVersion::V3, command)
+    }
+
+    fn require_v4(&self, command: &'static str) -> Result<()> {
+        self.require_version(ProtocolVersion::V4, command)
+    }
+
+    fn require_version(&self, required: ProtocolVersion, command: &'static str) -> Result<()> {
+        if self.version == required {
+            Ok(())
+        } else {
+            Err(ClientError::Protocol(SeedlinkError::VersionMismatch {
+                command,
+                version: self.version,
+            }))
+        }
+    }
+
+    /// Send BATCH and wait for its OK. Later commands get no per-command reply.
+    async fn enter_batch(&mut self) -> Result<()> {
+        self.send(&Command::Batch).await?;
+        self.read_ok_response("BATCH").await?;
+        self.batch = true;
+        Ok(())
+    }
+
+    /// Send `cmd` in the negotiated version, after the command hooks.
+    async fn send(&mut self, cmd: &Command) -> Result<()> {
+        self.hooks.command(cmd);
+        self.connection.send_command(cmd, self.version).await
+    }
+
+    async fn read_ok_response(&mut self, command_name: &str) -> Result<()> {
+        if self.batch {
+            // BATCH mode: server sends no per-command reply
+            return Ok(());
+        }
         let line = self.connection.read_line().await?;
         let response = Response::parse_line(&line)?;
         match response {
@@ -529,28 +1323,89 @@ impl SeedLinkClient {
     }
 }
 
+/// The `SLPROTO` version to send after HELLO and the protocol it selects, or
+/// `None` to stay on v3 without `SLPROTO`.
+fn slproto_request(
+    config: &ClientConfig,
+    capabilities: &Capabilities,
+) -> Result<Option<(String, ProtocolVersion)>> {
+    let advertised = |major: &str| {
+        capabilities
+            .protocols()
+            .iter()
+            .find(|v| v.split('.').next() == Some(major))
+            .cloned()
+    };
+    let unavailable = |wanted: &str| {
+        ClientError::NegotiationFailed(format!(
+            "server does not offer protocol {wanted} (offers: {})",
+            capabilities.protocols().join(", ")
+        ))
+    };
+    match &config.protocol {
+        ProtocolPreference::Auto if config.prefer_v4 => {
+            Ok(advertised("4").map(|v| (v, ProtocolVersion::V4)))
+        }
+        ProtocolPreference::Auto => Ok(None),
+        ProtocolPreference::ForceV3 => match advertised("3") {
+            Some(v) => Ok(Some((v, ProtocolVersion::V3))),
+            None if capabilities.protocols().is_empty() => Ok(None),
+            None => Err(unavailable("3.x")),
+        },
+        ProtocolPreference::ForceV4 => advertised("4")
+            .map(|v| Some((v, ProtocolVersion::V4)))
+            .ok_or_else(|| unavailable("4.x")),
+        ProtocolPreference::Exact(version) => match version.split('.').next() {
+            Some("3") => Ok(Some((version.clone(), ProtocolVersion::V3))),
+            Some("4") => Ok(Some((version.clone(), ProtocolVersion::V4))),
+            _ => Err(ClientError::NegotiationFailed(format!(
+                "unsupported protocol version {version:?}"
+            ))),
+        },
+    }
+}
+
+/// Reject station/network patterns that cannot be sent as a single token.
+fn validate_pattern(pattern: &str) -> Result<()> {
+    if !pattern.is_empty()
+        && pattern
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '?' || c == '*')
+    {
+        Ok(())
+    } else {
+        Err(ClientError::Protocol(SeedlinkError::InvalidCommand(
+            format!("invalid station pattern: {pattern:?}"),
+        )))
+    }
+}
+
+impl Drop for SeedLinkClient {
+    /// Best-effort `BYE` if still connected; see [Closing](Self#closing).
+    fn drop(&mut self) {
+        self.spawn_bye();
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::mock::{MockConfig, MockServer};
+    use seedlink_rs_protocol::InfoDocument;
     use seedlink_rs_protocol::frame::{PayloadFormat, PayloadSubformat, v3, v4};
+    use seedlink_rs_protocol::testing::TestRecordBuilder;
 
     fn make_v3_frame(seq: u64, station: &str, network: &str) -> Vec<u8> {
-        let mut payload = [0u8; v3::PAYLOAD_LEN];
-        let sta_bytes = station.as_bytes();
-        for (i, &b) in sta_bytes.iter().enumerate().take(5) {
-            payload[8 + i] = b;
-        }
-        for i in sta_bytes.len()..5 {
-            payload[8 + i] = b' ';
-        }
-        let net_bytes = network.as_bytes();
-        for (i, &b) in net_bytes.iter().enumerate().take(2) {
-            payload[18 + i] = b;
-        }
-        for i in net_bytes.len()..2 {
-            payload[18 + i] = b' ';
-        }
+        let payload = TestRecordBuilder::new()
+            .station(station)
+            .network(network)
+            .build();
+        v3::write(SequenceNumber::new(seq), &payload).unwrap()
+    }
+
+    /// v3 frame carrying a well-formed miniSEED 2 record.
+    fn make_valid_v3_frame(seq: u64) -> Vec<u8> {
+        let payload = TestRecordBuilder::new().samples(&[1, 2, 3]).build();
         v3::write(SequenceNumber::new(seq), &payload).unwrap()
     }
 
@@ -594,17 +1449,106 @@ mod tests {
         assert_eq!(client.state(), ClientState::Connected);
     }
 
+    #[tokio::test]
+    async fn user_agent_sent_after_v4_negotiation() {
+        let server = MockServer::start(MockConfig::v4_default(vec![])).await;
+        let config = ClientConfig {
+            user_agent: Some("my-app/2.1".to_owned()),
+            ..ClientConfig::default()
+        };
+        let mut client = SeedLinkClient::connect_with_config(&server.addr().to_string(), config)
+            .await
+            .unwrap();
+        assert_eq!(client.state(), ClientState::Connected);
+        client.bye().await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert_eq!(
+            server.captured().connection(0)[..3],
+            ["HELLO", "SLPROTO 4.0", "USERAGENT MY-APP/2.1"]
+        );
+
+        // Not sent when disabled, nor on v3
+        let server = MockServer::start(MockConfig::v4_default(vec![])).await;
+        let config = ClientConfig {
+            user_agent: None,
+            ..ClientConfig::default()
+        };
+        let mut client = SeedLinkClient::connect_with_config(&server.addr().to_string(), config)
+            .await
+            .unwrap();
+        client.bye().await.unwrap();
+        let server3 = MockServer::start(MockConfig::v3_default(vec![])).await;
+        let mut client = SeedLinkClient::connect(&server3.addr().to_string())
+            .await
+            .unwrap();
+        client.bye().await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        for cmds in [server.captured().all(), server3.captured().all()] {
+            assert!(!cmds.concat().iter().any(|c| c.starts_with("USERAGENT")));
+        }
+    }
+
+    #[cfg(feature = "zstd")]
+    #[tokio::test]
+    async fn zstd_payloads_decompressed_when_advertised() {
+        use seedlink_rs_protocol::compression;
+
+        let payload: Vec<u8> = (0..512u32).map(|i| (i % 7) as u8).collect();
+        let frame = v4::write(
+            PayloadFormat::MiniSeed2,
+            PayloadSubformat::Data,
+            SequenceNumber::new(1),
+            "IU_ANMO",
+            &compression::compress(&payload),
+        )
+        .unwrap();
+        let config = MockConfig {
+            hello_line1: "SeedLink v4.0 (mock) :: SLPROTO:4.0 SLPROTO:3.1 ZSTD".to_owned(),
+            ..MockConfig::v4_default(vec![frame])
+        };
+        let server = MockServer::start(config).await;
+        let mut client = SeedLinkClient::connect(&server.addr().to_string())
+            .await
+            .unwrap();
+        assert_eq!(client.version(), ProtocolVersion::V4);
+        client.station("ANMO", "IU").await.unwrap();
+        client.data().await.unwrap();
+        client.end_stream().await.unwrap();
+        let received = client.next_frame().await.unwrap().unwrap();
+        assert_eq!(received.payload(), &payload[..]);
+        assert_eq!(
+            server.captured().connection(0)[..3],
+            ["HELLO", "CAPABILITIES ZSTD", "SLPROTO 4.0"]
+        );
+
+        // Not requested when disabled
+        let server = MockServer::start(MockConfig::v4_default(vec![])).await;
+        let config = ClientConfig {
+            compression: false,
+            ..ClientConfig::default()
+        };
+        let mut client = SeedLinkClient::connect_with_config(&server.addr().to_string(), config)
+            .await
+            .unwrap();
+        client.bye().await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert!(
+            !server
+                .captured()
+                .all()
+                .concat()
+                .iter()
+                .any(|c| c.starts_with("CAPABILITIES"))
+        );
+    }
+
     #[tokio::test]
     async fn v4_fallback_to_v3() {
         let config = MockConfig {
             version: ProtocolVersion::V3,
             hello_line1: "SeedLink v3.1 :: SLPROTO:4.0".to_owned(),
             hello_line2: "Fake v4 Server".to_owned(),
-            frames: vec![make_v3_frame(1, "ANMO", "IU")],
-            connection_frames: None,
-            accept_slproto: false,
-            close_after_stream: false,
-            max_connections: 1,
+            ..MockConfig::v3_default(vec![make_v3_frame(1, "ANMO", "IU")])
         };
         let server = MockServer::start(config).await;
 
@@ -755,6 +1699,43 @@ mod tests {
         assert_eq!(frame.sequence(), SequenceNumber::new(100));
     }
 
+    #[tokio::test]
+    async fn data_from_reports_resume_gap() {
+        use std::sync::{Arc, Mutex};
+
+        let frames = vec![
+            make_v3_frame(40, "ANMO", "IU"),
+            make_v3_frame(50, "ANMO", "IU"),
+            make_v3_frame(60, "ANMO", "IU"),
+        ];
+        let server = MockServer::start(MockConfig::v3_default(frames)).await;
+        let config = ClientConfig {
+            resume_gap_threshold: Some(0),
+            ..ClientConfig::default()
+        };
+        let mut client = SeedLinkClient::connect_with_config(&server.addr().to_string(), config)
+            .await
+            .unwrap();
+        let gaps = Arc::new(Mutex::new(Vec::new()));
+        let sink = gaps.clone();
+        client.on_resume_gap(move |gap| sink.lock().unwrap().push(gap.clone()));
+
+        client.station("ANMO", "IU").await.unwrap();
+        client.data_from(SequenceNumber::new(40)).await.unwrap();
+        client.end_stream().await.unwrap();
+        for _ in 0..3 {
+            client.next_frame().await.unwrap().unwrap();
+        }
+
+        // The resent seq 40 is skipped; only the first new frame is checked
+        let gaps = gaps.lock().unwrap();
+        assert_eq!(gaps.len(), 1, "{gaps:?}");
+        assert_eq!(gaps[0].station.station, "ANMO");
+        assert_eq!(gaps[0].requested, SequenceNumber::new(40));
+        assert_eq!(gaps[0].first_received, SequenceNumber::new(50));
+        assert_eq!(gaps[0].missed(), 9);
+    }
+
     // -- State machine enforcement --
 
     #[tokio::test]
@@ -812,6 +1793,116 @@ mod tests {
         assert_eq!(client.state(), ClientState::Disconnected);
     }
 
+    /// Wait until the mock has captured `BYE` as the last command of the
+    /// first connection.
+    async fn wait_for_bye(server: &MockServer) -> Vec<String> {
+        for _ in 0..100 {
+            let commands = server.captured().connection(0);
+            if commands.last().is_some_and(|c| c == "BYE") {
+                return commands;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("no BYE: {:?}", server.captured().connection(0));
+    }
+
+    #[tokio::test]
+    async fn close_sends_bye() {
+        let server = MockServer::start(MockConfig::v3_default(vec![])).await;
+        let mut client = SeedLinkClient::connect(&server.addr().to_string())
+            .await
+            .unwrap();
+        client.station("ANMO", "IU").await.unwrap();
+        client.close().await.unwrap();
+        assert_eq!(
+            wait_for_bye(&server).await,
+            ["HELLO", "STATION ANMO IU", "BYE"]
+        );
+
+        // Already disconnected: nothing to send
+        let server = MockServer::start(MockConfig::v3_default(vec![])).await;
+        let mut client = SeedLinkClient::connect(&server.addr().to_string())
+            .await
+            .unwrap();
+        client.bye().await.unwrap();
+        client.close().await.unwrap();
+        assert_eq!(wait_for_bye(&server).await, ["HELLO", "BYE"]);
+    }
+
+    #[tokio::test]
+    async fn drop_mid_stream_sends_bye() {
+        use std::sync::{Arc, Mutex};
+
+        let frames = (1..=3)
+            .map(|seq| make_v3_frame(seq, "ANMO", "IU"))
+            .collect();
+        let server = MockServer::start(MockConfig::v3_default(frames)).await;
+        let mut client = SeedLinkClient::connect(&server.addr().to_string())
+            .await
+            .unwrap();
+        let hooked = Arc::new(Mutex::new(Vec::new()));
+        let seen = hooked.clone();
+        client.on_command(move |cmd| seen.lock().unwrap().push(cmd.clone()));
+        client.station("ANMO", "IU").await.unwrap();
+        client.data().await.unwrap();
+        client.end_stream().await.unwrap();
+        client.next_frame().await.unwrap().unwrap();
+        drop(client);
+        assert_eq!(
+            wait_for_bye(&server).await,
+            ["HELLO", "STATION ANMO IU", "DATA", "END", "BYE"]
+        );
+        // The hook runs once the BYE has gone out
+        for _ in 0..100 {
+            if hooked.lock().unwrap().last() == Some(&Command::Bye) {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("BYE not hooked: {:?}", hooked.lock().unwrap());
+    }
+
+    #[test]
+    fn drop_outside_runtime_skips_bye() {
+        use std::sync::{Arc, Mutex};
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let server = runtime.block_on(MockServer::start(MockConfig::v3_default(vec![])));
+        let mut client = runtime
+            .block_on(SeedLinkClient::connect(&server.addr().to_string()))
+            .unwrap();
+        let hooked = Arc::new(Mutex::new(Vec::new()));
+        let seen = hooked.clone();
+        client.on_command(move |cmd| seen.lock().unwrap().push(cmd.clone()));
+
+        // No runtime context here: nothing is spawned and the hook never sees BYE
+        drop(client);
+        runtime.block_on(async { tokio::time::sleep(Duration::from_millis(50)).await });
+        assert!(hooked.lock().unwrap().is_empty());
+        assert_eq!(server.captured().connection(0), ["HELLO"]);
+    }
+
+    #[tokio::test]
+    async fn dropping_frame_stream_sends_bye() {
+        use tokio_stream::StreamExt;
+
+        let frames = (1..=3)
+            .map(|seq| make_v3_frame(seq, "ANMO", "IU"))
+            .collect();
+        let server = MockServer::start(MockConfig::v3_default(frames)).await;
+        let mut client = SeedLinkClient::connect(&server.addr().to_string())
+            .await
+            .unwrap();
+        client.station("ANMO", "IU").await.unwrap();
+        client.data().await.unwrap();
+        client.end_stream().await.unwrap();
+
+        let mut stream = Box::pin(client.into_stream());
+        stream.next().await.unwrap().unwrap();
+        drop(stream);
+        wait_for_bye(&server).await;
+    }
+
     // -- Sequence tracking --
 
     #[tokio::test]
@@ -869,6 +1960,130 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn data_time_range_v4() {
+        let server = MockServer::start(MockConfig::v4_default(vec![])).await;
+        let mut client = SeedLinkClient::connect(&server.addr().to_string())
+            .await
+            .unwrap();
+        assert_eq!(client.version(), ProtocolVersion::V4);
+
+        client.station("ANMO", "IU").await.unwrap();
+        client
+            .data_time_range("2024-01-15T00:00:00Z", Some("2024-01-16T00:00:00Z"))
+            .await
+            .unwrap();
+        assert_eq!(client.state(), ClientState::Configured);
+
+        let cmds = server.captured().connection(0);
+        assert_eq!(
+            cmds.last().unwrap(),
+            "DATA ALL 2024-01-15T00:00:00Z 2024-01-16T00:00:00Z"
+        );
+    }
+
+    #[tokio::test]
+    async fn data_time_range_rejected_on_v3() {
+        let server = MockServer::start(MockConfig::v3_default(vec![])).await;
+        let mut client = SeedLinkClient::connect(&server.addr().to_string())
+            .await
+            .unwrap();
+
+        client.station("ANMO", "IU").await.unwrap();
+        let err = client
+            .data_time_range("2024-01-15T00:00:00Z", None)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ClientError::Protocol(SeedlinkError::VersionMismatch { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn data_all_and_newest() {
+        let server = MockServer::start(MockConfig::v4_default(vec![])).await;
+        let mut client = SeedLinkClient::connect(&server.addr().to_string())
+            .await
+            .unwrap();
+        client.station("ANMO", "IU").await.unwrap();
+        client.data_all().await.unwrap();
+        client.station("WLF", "GE").await.unwrap();
+        client.data_newest().await.unwrap();
+        let cmds = server.captured().connection(0);
+        assert_eq!(
+            cmds[cmds.len() - 3..],
+            ["DATA ALL", "STATION GE_WLF", "DATA -1"]
+        );
+
+        let server = MockServer::start(MockConfig::v3_default(vec![])).await;
+        let mut client = SeedLinkClient::connect(&server.addr().to_string())
+            .await
+            .unwrap();
+        client.station("ANMO", "IU").await.unwrap();
+        assert!(matches!(
+            client.data_all().await,
+            Err(ClientError::Protocol(SeedlinkError::VersionMismatch { .. }))
+        ));
+        assert!(client.data_newest().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn fetch_v4_returns_to_configured() {
+        let frames = vec![make_v4_frame(1, "IU_ANMO"), make_v4_frame(2, "IU_ANMO")];
+        let server = MockServer::start(MockConfig::v4_default(frames)).await;
+        let mut client = SeedLinkClient::connect(&server.addr().to_string())
+            .await
+            .unwrap();
+
+        client.station("ANMO", "IU").await.unwrap();
+        client.data().await.unwrap();
+
+        // Two fetch windows on the same connection
+        for _ in 0..2 {
+            client.fetch_v4().await.unwrap();
+            assert_eq!(client.state(), ClientState::Streaming);
+            let f1 = client.next_frame().await.unwrap().unwrap();
+            assert_eq!(f1.sequence(), SequenceNumber::new(1));
+            let f2 = client.next_frame().await.unwrap().unwrap();
+            assert_eq!(f2.sequence(), SequenceNumber::new(2));
+            assert!(client.next_frame().await.unwrap().is_none());
+            assert_eq!(client.state(), ClientState::Configured);
+        }
+
+        // ENDFETCH: final data, then the server closes
+        client.end_fetch().await.unwrap();
+        let f = client.next_frame().await.unwrap().unwrap();
+        assert_eq!(f.sequence(), SequenceNumber::new(1));
+        let f = client.next_frame().await.unwrap().unwrap();
+        assert_eq!(f.sequence(), SequenceNumber::new(2));
+        assert!(client.next_frame().await.unwrap().is_none());
+        assert_eq!(client.state(), ClientState::Disconnected);
+
+        let cmds = server.captured().connection(0);
+        assert_eq!(cmds[cmds.len() - 3..], ["FETCH", "FETCH", "ENDFETCH"]);
+    }
+
+    #[tokio::test]
+    async fn fetch_v4_rejected_on_v3() {
+        let server = MockServer::start(MockConfig::v3_default(vec![])).await;
+        let mut client = SeedLinkClient::connect(&server.addr().to_string())
+            .await
+            .unwrap();
+
+        client.station("ANMO", "IU").await.unwrap();
+        let err = client.fetch_v4().await.unwrap_err();
+        assert!(matches!(
+            err,
+            ClientError::Protocol(SeedlinkError::VersionMismatch { .. })
+        ));
+        let err = client.end_fetch().await.unwrap_err();
+        assert!(matches!(
+            err,
+            ClientError::Protocol(SeedlinkError::VersionMismatch { .. })
+        ));
+    }
+
     // -- Config --
 
     #[tokio::test]
@@ -886,6 +2101,57 @@ mod tests {
         assert_eq!(client.version(), ProtocolVersion::V3);
     }
 
+    #[tokio::test]
+    async fn connect_with_forced_protocol() {
+        async fn connect(
+            mock: MockConfig,
+            protocol: ProtocolPreference,
+        ) -> (Result<SeedLinkClient>, Vec<String>) {
+            let server = MockServer::start(mock).await;
+            let config = ClientConfig {
+                protocol,
+                user_agent: None,
+                ..ClientConfig::default()
+            };
+            let client =
+                SeedLinkClient::connect_with_config(&server.addr().to_string(), config).await;
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            (client, server.captured().connection(0))
+        }
+
+        // ForceV3 locks legacy mode on a v4 server
+        let (client, sent) =
+            connect(MockConfig::v4_default(vec![]), ProtocolPreference::ForceV3).await;
+        assert_eq!(client.unwrap().version(), ProtocolVersion::V3);
+        assert_eq!(sent, ["HELLO", "SLPROTO 3.1"]);
+
+        // ...and sends nothing to a classic v3 server
+        let (client, sent) =
+            connect(MockConfig::v3_default(vec![]), ProtocolPreference::ForceV3).await;
+        assert_eq!(client.unwrap().version(), ProtocolVersion::V3);
+        assert_eq!(sent, ["HELLO"]);
+
+        let (client, _) =
+            connect(MockConfig::v3_default(vec![]), ProtocolPreference::ForceV4).await;
+        assert!(matches!(client, Err(ClientError::NegotiationFailed(_))));
+
+        let (client, sent) = connect(
+            MockConfig::v3_default(vec![]),
+            ProtocolPreference::Exact("4.0".into()),
+        )
+        .await;
+        let err = client.err().unwrap().to_string();
+        assert!(err.contains("rejected SLPROTO 4.0"), "{err}");
+        assert_eq!(sent, ["HELLO", "SLPROTO 4.0"]);
+
+        let (client, _) = connect(
+            MockConfig::v4_default(vec![]),
+            ProtocolPreference::Exact("5.0".into()),
+        )
+        .await;
+        assert!(matches!(client, Err(ClientError::NegotiationFailed(_))));
+    }
+
     // -- Server error handling --
 
     #[tokio::test]
@@ -943,33 +2209,300 @@ mod tests {
     // -- EOF handling --
 
     #[tokio::test]
-    async fn next_frame_returns_none_on_eof() {
-        let frames = vec![make_v3_frame(1, "ANMO", "IU")];
+    async fn next_event_surfaces_text_between_frames() {
+        let stream = || {
+            let mut info = InfoDocument::new("<seedlink/>").split_v3();
+            vec![
+                make_v4_frame(1, "IU_ANMO"),
+                b"NOTICE buffer replay\r\n".to_vec(),
+                info.remove(0),
+                make_v3_frame(2, "ANMO", "IU"),
+                v4::write(
+                    PayloadFormat::Json,
+                    PayloadSubformat::Info,
+                    SequenceNumber::UNSET,
+                    "",
+                    &[],
+                )
+                .unwrap(),
+                b"ERROR LIMIT too slow\r\n".to_vec(),
+                make_v4_frame(3, "IU_ANMO"),
+                b"END\r\n".to_vec(),
+            ]
+        };
         let config = MockConfig {
             close_after_stream: true,
-            ..MockConfig::v3_default(frames)
+            ..MockConfig::v4_default(stream())
         };
         let server = MockServer::start(config).await;
-
         let mut client = SeedLinkClient::connect(&server.addr().to_string())
             .await
             .unwrap();
-
         client.station("ANMO", "IU").await.unwrap();
         client.data().await.unwrap();
         client.end_stream().await.unwrap();
 
-        // First frame → Some
-        let frame = client.next_frame().await.unwrap();
-        assert!(frame.is_some());
-        assert_eq!(frame.unwrap().sequence(), SequenceNumber::new(1));
-
-        // Second frame → None (server closed)
-        let frame = client.next_frame().await.unwrap();
-        assert!(frame.is_none());
-
+        let mut events = Vec::new();
+        while client.state() == ClientState::Streaming {
+            events.push(client.next_event().await.unwrap());
+        }
+        assert_eq!(events.len(), 9);
+        assert!(
+            matches!(&events[0], StreamEvent::Frame(f) if f.sequence() == SequenceNumber::new(1))
+        );
+        assert_eq!(events[1], StreamEvent::Text("NOTICE buffer replay".into()));
+        assert!(
+            matches!(&events[2], StreamEvent::InfoFrame(f) if f.payload().starts_with(b"<seedlink/>"))
+        );
+        assert!(matches!(
+            &events[3],
+            StreamEvent::Frame(OwnedFrame::V3 { .. })
+        ));
+        assert_eq!(events[4], StreamEvent::KeepAlive);
+        assert!(
+            matches!(&events[5], StreamEvent::Error { description, .. } if description == "too slow")
+        );
+        assert!(
+            matches!(&events[6], StreamEvent::Frame(f) if f.sequence() == SequenceNumber::new(3))
+        );
+        assert_eq!(events[7], StreamEvent::EndOfFetch);
+        assert!(matches!(events[8], StreamEvent::Disconnected { .. }));
         assert_eq!(client.state(), ClientState::Disconnected);
-    }
+        // INFO frames do not move the tracked sequence
+        assert_eq!(
+            client.sequences().get(&StationKey {
+                network: "IU".into(),
+                station: "ANMO".into(),
+            }),
+            Some(&SequenceNumber::new(3))
+        );
+
+        // next_frame skips text lines and reports ERROR as a server error
+        let config = MockConfig {
+            close_after_stream: true,
+            ..MockConfig::v4_default(stream())
+        };
+        let server = MockServer::start(config).await;
+        let mut client = SeedLinkClient::connect(&server.addr().to_string())
+            .await
+            .unwrap();
+        client.station("ANMO", "IU").await.unwrap();
+        client.data().await.unwrap();
+        client.end_stream().await.unwrap();
+        assert!(client.next_frame().await.unwrap().is_some());
+        assert!(client.next_frame().await.unwrap().is_some());
+        let err = client.next_frame().await.unwrap_err();
+        assert!(matches!(err, ClientError::ServerError(ref m) if m == "LIMIT too slow"));
+        let frame = client.next_frame().await.unwrap().unwrap();
+        assert_eq!(frame.sequence(), SequenceNumber::new(3));
+        assert!(client.next_frame().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn next_frame_returns_none_on_eof() {
+        let frames = vec![make_v3_frame(1, "ANMO", "IU")];
+        let config = MockConfig {
+            close_after_stream: true,
+            ..MockConfig::v3_default(frames)
+        };
+        let server = MockServer::start(config).await;
+
+        let mut client = SeedLinkClient::connect(&server.addr().to_string())
+            .await
+            .unwrap();
+
+        client.station("ANMO", "IU").await.unwrap();
+        client.data().await.unwrap();
+        client.end_stream().await.unwrap();
+
+        // First frame → Some
+        let frame = client.next_frame().await.unwrap();
+        assert!(frame.is_some());
+        assert_eq!(frame.unwrap().sequence(), SequenceNumber::new(1));
+
+        // Second frame → None (server closed)
+        let frame = client.next_frame().await.unwrap();
+        assert!(frame.is_none());
+
+        assert_eq!(client.state(), ClientState::Disconnected);
+    }
+
+    #[tokio::test]
+    async fn next_frame_cancelled_mid_frame_loses_nothing() {
+        // Each frame arrives in two halves, well apart
+        let mut chunks = Vec::new();
+        for seq in 1..=3 {
+            let frame = make_v3_frame(seq, "ANMO", "IU");
+            chunks.push(frame[..200].to_vec());
+            chunks.push(frame[200..].to_vec());
+        }
+        let config = MockConfig {
+            frame_delay: Duration::from_millis(40),
+            ..MockConfig::v3_default(chunks)
+        };
+        let server = MockServer::start(config).await;
+        let mut client = SeedLinkClient::connect(&server.addr().to_string())
+            .await
+            .unwrap();
+        client.station("ANMO", "IU").await.unwrap();
+        client.data().await.unwrap();
+        client.end_stream().await.unwrap();
+
+        let mut seqs = Vec::new();
+        let mut cancelled = 0;
+        while seqs.len() < 3 {
+            tokio::select! {
+                frame = client.next_frame() => {
+                    seqs.push(frame.unwrap().unwrap().sequence().value());
+                }
+                _ = tokio::time::sleep(Duration::from_millis(10)) => cancelled += 1,
+            }
+        }
+        assert_eq!(seqs, [1, 2, 3]);
+        assert!(cancelled > 0);
+    }
+
+    #[tokio::test]
+    async fn next_frames_batches_until_max_or_timeout() {
+        let frames = (1..=5)
+            .map(|seq| make_v3_frame(seq, "ANMO", "IU"))
+            .collect();
+        let server = MockServer::start(MockConfig::v3_default(frames)).await;
+        let mut client = SeedLinkClient::connect(&server.addr().to_string())
+            .await
+            .unwrap();
+        client.station("ANMO", "IU").await.unwrap();
+        client.data().await.unwrap();
+        client.end_stream().await.unwrap();
+
+        let batch = client.next_frames(3, Duration::from_secs(5)).await.unwrap();
+        let seqs: Vec<u64> = batch.iter().map(|f| f.sequence().value()).collect();
+        assert_eq!(seqs, [1, 2, 3]);
+
+        // Only two left: the call returns them once the timeout expires
+        let batch = client
+            .next_frames(10, Duration::from_millis(100))
+            .await
+            .unwrap();
+        assert_eq!(batch.len(), 2);
+        let batch = client
+            .next_frames(10, Duration::from_millis(20))
+            .await
+            .unwrap();
+        assert!(batch.is_empty());
+        assert_eq!(client.state(), ClientState::Streaming);
+        assert_eq!(
+            client.last_sequence("IU", "ANMO"),
+            Some(SequenceNumber::new(5))
+        );
+    }
+
+    #[tokio::test]
+    async fn next_frames_stops_at_eof() {
+        let frames = (1..=2)
+            .map(|seq| make_v3_frame(seq, "ANMO", "IU"))
+            .collect();
+        let config = MockConfig {
+            close_after_stream: true,
+            ..MockConfig::v3_default(frames)
+        };
+        let server = MockServer::start(config).await;
+        let mut client = SeedLinkClient::connect(&server.addr().to_string())
+            .await
+            .unwrap();
+        client.station("ANMO", "IU").await.unwrap();
+        client.data().await.unwrap();
+        client.end_stream().await.unwrap();
+
+        let batch = client
+            .next_frames(10, Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(batch.len(), 2);
+        assert_eq!(client.state(), ClientState::Disconnected);
+        assert!(
+            client
+                .next_frames(10, Duration::from_secs(5))
+                .await
+                .is_err()
+        );
+    }
+
+    async fn stream_with_validation(mode: ValidationMode) -> SeedLinkClient {
+        let frames = vec![
+            make_valid_v3_frame(1),
+            v3::write(SequenceNumber::new(2), &[0u8; v3::PAYLOAD_LEN]).unwrap(),
+            make_valid_v3_frame(3),
+        ];
+        let config = MockConfig {
+            close_after_stream: true,
+            ..MockConfig::v3_default(frames)
+        };
+        let server = MockServer::start(config).await;
+        let config = ClientConfig {
+            validation: mode,
+            ..ClientConfig::default()
+        };
+        let mut client = SeedLinkClient::connect_with_config(&server.addr().to_string(), config)
+            .await
+            .unwrap();
+        client.station("ANMO", "IU").await.unwrap();
+        client.data().await.unwrap();
+        client.end_stream().await.unwrap();
+        client
+    }
+
+    #[tokio::test]
+    async fn validation_drops_flags_or_rejects_invalid_records() {
+        let sequences = |frames: Vec<OwnedFrame>| {
+            frames
+                .iter()
+                .map(|f| f.sequence().value())
+                .collect::<Vec<_>>()
+        };
+
+        let mut client = stream_with_validation(ValidationMode::Drop).await;
+        let frames = client
+            .next_frames(10, Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(sequences(frames), [1, 3]);
+        assert_eq!(client.invalid_records(), 1);
+
+        let mut client = stream_with_validation(ValidationMode::Flag).await;
+        let frames = client
+            .next_frames(10, Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(sequences(frames), [1, 2, 3]);
+        assert_eq!(client.invalid_records(), 1);
+
+        let mut client = stream_with_validation(ValidationMode::Error).await;
+        assert!(client.next_frame().await.unwrap().is_some());
+        let err = client.next_frame().await.unwrap_err();
+        assert!(matches!(
+            err,
+            ClientError::InvalidRecord { sequence, .. } if sequence.value() == 2
+        ));
+        assert_eq!(
+            client
+                .next_frame()
+                .await
+                .unwrap()
+                .unwrap()
+                .sequence()
+                .value(),
+            3
+        );
+
+        let mut client = stream_with_validation(ValidationMode::Off).await;
+        let frames = client
+            .next_frames(10, Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(client.invalid_records(), 0);
+    }
 
     // -- Fetch --
 
@@ -1011,7 +2544,7 @@ mod tests {
             .unwrap();
 
         client.station("ANMO", "IU").await.unwrap();
-        client.time_window("2024,1,0,0,0", None).await.unwrap();
+        client.time_window("2024,1,1,0,0,0", None).await.unwrap();
         assert_eq!(client.state(), ClientState::Configured);
 
         client.end_stream().await.unwrap();
@@ -1032,10 +2565,48 @@ mod tests {
 
         client.station("ANMO", "IU").await.unwrap();
         client
-            .time_window("2024,1,0,0,0", Some("2024,2,0,0,0"))
+            .time_window("2024,1,1,0,0,0", Some("2024-02-01T00:00:00Z"))
+            .await
+            .unwrap();
+        assert_eq!(client.state(), ClientState::Configured);
+
+        // Malformed times are rejected locally
+        let err = client.time_window("2024,1,0,0,0", None).await.unwrap_err();
+        assert!(matches!(
+            err,
+            ClientError::Protocol(SeedlinkError::InvalidTime(_))
+        ));
+        assert_eq!(client.state(), ClientState::Configured);
+    }
+
+    #[tokio::test]
+    async fn time_window_range_formats_per_version() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_millis(1_705_276_800_500);
+        let end = start + Duration::from_secs(86_400);
+
+        let server = MockServer::start(MockConfig::v3_default(vec![])).await;
+        let mut client = SeedLinkClient::connect(&server.addr().to_string())
             .await
             .unwrap();
+        let err = client.time_window_range(start, None).await.unwrap_err();
+        assert!(matches!(err, ClientError::InvalidState { .. }));
+        client.station("ANMO", "IU").await.unwrap();
+        client.time_window_range(start, Some(end)).await.unwrap();
         assert_eq!(client.state(), ClientState::Configured);
+        let cmds = server.captured().connection(0);
+        assert_eq!(
+            cmds.last().unwrap(),
+            "TIME 2024,01,15,00,00,00 2024,01,16,00,00,00"
+        );
+
+        let server = MockServer::start(MockConfig::v4_default(vec![])).await;
+        let mut client = SeedLinkClient::connect(&server.addr().to_string())
+            .await
+            .unwrap();
+        client.station("ANMO", "IU").await.unwrap();
+        client.time_window_range(start, None).await.unwrap();
+        let cmds = server.captured().connection(0);
+        assert_eq!(cmds.last().unwrap(), "DATA ALL 2024-01-15T00:00:00.500000Z");
     }
 
     #[tokio::test]
@@ -1047,7 +2618,359 @@ mod tests {
             .unwrap();
 
         // Connected, not Configured — should fail
-        let err = client.time_window("2024,1,0,0,0", None).await.unwrap_err();
+        let err = client
+            .time_window("2024,1,1,0,0,0", None)
+            .await
+            .unwrap_err();
         assert!(matches!(err, ClientError::InvalidState { .. }));
     }
+
+    // -- Typed INFO --
+
+    fn make_info_frames(xml: &str) -> Vec<Vec<u8>> {
+        xml.as_bytes()
+            .chunks(v3::PAYLOAD_LEN)
+            .map(|chunk| {
+                let mut payload = [0u8; v3::PAYLOAD_LEN];
+                payload[..chunk.len()].copy_from_slice(chunk);
+                v3::write(SequenceNumber::new(0), &payload).unwrap()
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn info_stations_typed() {
+        let mut xml = String::from("<?xml version=\"1.0\"?>\n<seedlink>\n");
+        for i in 0..12 {
+            xml.push_str(&format!(
+                "  <station name=\"ST{i:02}\" network=\"XX\" description=\"\" begin_seq=\"000001\" end_seq=\"{:06X}\"/>\n",
+                i + 1
+            ));
+        }
+        xml.push_str("</seedlink>\n");
+        let frames = make_info_frames(&xml);
+        assert!(frames.len() > 1, "test needs a multi-frame reply");
+
+        let server = MockServer::start(MockConfig::v3_default(frames)).await;
+        let mut client = SeedLinkClient::connect(&server.addr().to_string())
+            .await
+            .unwrap();
+
+        let stations = client.info_stations().await.unwrap();
+        assert_eq!(stations.len(), 12);
+        assert_eq!(stations[11].station, "ST11");
+        assert_eq!(stations[11].end_seq, Some(SequenceNumber::new(12)));
+        assert_eq!(server.captured().connection(0)[1], "INFO STATIONS");
+    }
+
+    #[tokio::test]
+    async fn info_streams_typed() {
+        let xml = "<?xml version=\"1.0\"?>\n<seedlink>\n  <station name=\"ANMO\" network=\"IU\">\n    \
+            <stream seedname=\"BHZ\" location=\"00\" type=\"D\" begin_seq=\"000001\" end_seq=\"000002\"/>\n  \
+            </station>\n</seedlink>\n";
+        let server = MockServer::start(MockConfig::v3_default(make_info_frames(xml))).await;
+        let mut client = SeedLinkClient::connect(&server.addr().to_string())
+            .await
+            .unwrap();
+
+        let streams = client.info_streams().await.unwrap();
+        assert_eq!(streams.len(), 1);
+        assert_eq!(streams[0].network, "IU");
+        assert_eq!(streams[0].station, "ANMO");
+        assert_eq!(streams[0].channel, "BHZ");
+        assert_eq!(streams[0].location, "00");
+    }
+
+    #[tokio::test]
+    async fn info_v3_ends_at_last_info_frame() {
+        let xml = format!("<seedlink>{}</seedlink>", " ".repeat(1100));
+        let frames = InfoDocument::new(xml.as_str()).split_v3();
+        assert_eq!(frames.len(), 3);
+        // The mock sends no END after an "INFO  " frame, as v3 servers do
+        let server = MockServer::start(MockConfig::v3_default(frames)).await;
+        let mut client = SeedLinkClient::connect(&server.addr().to_string())
+            .await
+            .unwrap();
+
+        for _ in 0..2 {
+            let reply = client.info(InfoLevel::Id).await.unwrap();
+            assert_eq!(reply.len(), 3);
+            assert_eq!(info::reassemble_xml(&reply), xml);
+        }
+        client.station("ANMO", "IU").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn info_json_from_v4_json_and_v3_xml() {
+        let json = r#"{"software":"mock","station":[{"id":"IU_ANMO"}]}"#;
+        let frame = v4::write(
+            PayloadFormat::Json,
+            PayloadSubformat::Info,
+            SequenceNumber::UNSET,
+            "",
+            json.as_bytes(),
+        )
+        .unwrap();
+        let server = MockServer::start(MockConfig::v4_default(vec![frame])).await;
+        let mut client = SeedLinkClient::connect(&server.addr().to_string())
+            .await
+            .unwrap();
+        assert_eq!(client.version(), ProtocolVersion::V4);
+        let value = client.info_json(InfoLevel::Stations).await.unwrap();
+        assert_eq!(value["software"], "mock");
+        assert_eq!(value["station"][0]["id"], "IU_ANMO");
+
+        let xml = "<seedlink software=\"mock\"><station name=\"ANMO\" network=\"IU\"/></seedlink>";
+        let server = MockServer::start(MockConfig::v3_default(make_info_frames(xml))).await;
+        let mut client = SeedLinkClient::connect(&server.addr().to_string())
+            .await
+            .unwrap();
+        let value = client.info_json(InfoLevel::Stations).await.unwrap();
+        assert_eq!(value["software"], "mock");
+        assert_eq!(value["station"][0]["network"], "IU");
+    }
+
+    // -- Bulk / wildcard subscription --
+
+    #[tokio::test]
+    async fn station_pattern_sends_wildcards() {
+        let server = MockServer::start(MockConfig::v3_default(vec![])).await;
+        let mut client = SeedLinkClient::connect(&server.addr().to_string())
+            .await
+            .unwrap();
+
+        client.station_pattern("IU", "A*").await.unwrap();
+        client.station_pattern("G?", "W??").await.unwrap();
+        assert_eq!(client.state(), ClientState::Configured);
+
+        let cmds = server.captured().connection(0);
+        assert_eq!(cmds[1], "STATION A* IU");
+        assert_eq!(cmds[2], "STATION W?? G?");
+    }
+
+    #[tokio::test]
+    async fn station_pattern_rejects_bad_tokens() {
+        let server = MockServer::start(MockConfig::v3_default(vec![])).await;
+        let mut client = SeedLinkClient::connect(&server.addr().to_string())
+            .await
+            .unwrap();
+
+        let err = client.station_pattern("IU", "AN MO").await.unwrap_err();
+        assert!(matches!(err, ClientError::Protocol(_)));
+        let err = client.station_pattern("", "ANMO").await.unwrap_err();
+        assert!(matches!(err, ClientError::Protocol(_)));
+        assert_eq!(client.state(), ClientState::Connected);
+    }
+
+    #[tokio::test]
+    async fn subscribe_all_without_batch() {
+        let frames = vec![make_v3_frame(1, "ANMO", "IU")];
+        let server = MockServer::start(MockConfig::v3_default(frames)).await;
+        let mut client = SeedLinkClient::connect(&server.addr().to_string())
+            .await
+            .unwrap();
+
+        client
+            .subscribe_all(&[("IU", "ANMO", &["BHZ", "BHN"]), ("GE", "WLF", &[])])
+            .await
+            .unwrap();
+        client.end_stream().await.unwrap();
+        let frame = client.next_frame().await.unwrap().unwrap();
+        assert_eq!(frame.sequence(), SequenceNumber::new(1));
+
+        let cmds = server.captured().connection(0);
+        assert_eq!(
+            &cmds[1..8],
+            [
+                "STATION ANMO IU",
+                "SELECT BHZ",
+                "SELECT BHN",
+                "DATA",
+                "STATION WLF GE",
+                "DATA",
+                "END"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn subscribe_all_uses_batch_when_advertised() {
+        let frames = vec![make_v3_frame(7, "ANMO", "IU")];
+        let config = MockConfig {
+            hello_line1: "SeedLink v3.1 (2020.075) :: SLPROTO:3.1 CAP EXTREPLY BATCH".to_owned(),
+            ..MockConfig::v3_default(frames)
+        };
+        let server = MockServer::start(config).await;
+        let mut client = SeedLinkClient::connect(&server.addr().to_string())
+            .await
+            .unwrap();
+
+        client
+            .subscribe_all(&[("IU", "ANMO", &["BHZ"]), ("GE", "WLF", &[])])
+            .await
+            .unwrap();
+        // Follow-up commands in BATCH mode must not wait for replies
+        client.station("MAJO", "IU").await.unwrap();
+        client.data().await.unwrap();
+        client.end_stream().await.unwrap();
+
+        let frame = client.next_frame().await.unwrap().unwrap();
+        assert_eq!(frame.sequence(), SequenceNumber::new(7));
+
+        let cmds = server.captured().connection(0);
+        assert_eq!(cmds[1], "BATCH");
+        assert_eq!(cmds[2], "STATION ANMO IU");
+        assert_eq!(cmds[3], "SELECT BHZ");
+        assert_eq!(cmds[4], "DATA");
+        assert_eq!(cmds[5], "STATION WLF GE");
+        assert_eq!(cmds[6], "DATA");
+        assert_eq!(cmds[7], "STATION MAJO IU");
+    }
+
+    #[tokio::test]
+    async fn batch_builder_sends_commands_in_one_write() {
+        let frames = vec![make_v3_frame(7, "ANMO", "IU")];
+        let config = MockConfig {
+            hello_line1: "SeedLink v3.1 (2020.075) :: SLPROTO:3.1 CAP EXTREPLY BATCH".to_owned(),
+            ..MockConfig::v3_default(frames)
+        };
+        let server = MockServer::start(config).await;
+        let mut client = SeedLinkClient::connect(&server.addr().to_string())
+            .await
+            .unwrap();
+
+        client
+            .batch()
+            .station("ANMO", "IU")
+            .select("BHZ")
+            .data_from(SequenceNumber::new(0x10))
+            .station("WLF", "GE")
+            .time_window("2024,1,1,0,0,0", None)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(client.state(), ClientState::Configured);
+        assert_eq!(client.subscribed_stations().len(), 2);
+        client.end_stream().await.unwrap();
+        let frame = client.next_frame().await.unwrap().unwrap();
+        assert_eq!(frame.sequence(), SequenceNumber::new(7));
+
+        let cmds = server.captured().connection(0);
+        assert_eq!(
+            &cmds[1..8],
+            [
+                "BATCH",
+                "STATION ANMO IU",
+                "SELECT BHZ",
+                "DATA 000010",
+                "STATION WLF GE",
+                "TIME 2024,01,01,00,00,00",
+                "END"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn batch_builder_without_server_batch() {
+        let server = MockServer::start(MockConfig::v4_default(vec![])).await;
+        let mut client = SeedLinkClient::connect(&server.addr().to_string())
+            .await
+            .unwrap();
+
+        // SELECT before any STATION needs a configured client
+        let err = client.batch().select("BHZ").send().await.unwrap_err();
+        assert!(matches!(err, ClientError::InvalidState { .. }));
+        // TIME is v3-only: rejected before anything is sent
+        let err = client
+            .batch()
+            .station("ANMO", "IU")
+            .time_window("2024,1,1,0,0,0", None)
+            .send()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ClientError::Protocol(_)));
+        let err = client
+            .batch()
+            .station("ANMO", "IU")
+            .time_window("2024-02-30", None)
+            .send()
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ClientError::Protocol(SeedlinkError::InvalidTime(_))
+        ));
+        assert_eq!(client.state(), ClientState::Connected);
+
+        client
+            .batch()
+            .station("ANMO", "IU")
+            .data()
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(client.state(), ClientState::Configured);
+        let cmds = server.captured().connection(0);
+        assert_eq!(cmds[cmds.len() - 2..], ["STATION IU_ANMO", "DATA"]);
+    }
+
+    // -- Hooks --
+
+    #[tokio::test]
+    async fn hooks_see_commands_and_transform_frames() {
+        use std::sync::{Arc, Mutex};
+
+        let frames = vec![
+            make_v3_frame(5, "ANMO", "IU"),
+            make_v3_frame(6, "ANMO", "IU"),
+        ];
+        let server = MockServer::start(MockConfig::v3_default(frames)).await;
+        let mut client = SeedLinkClient::connect(&server.addr().to_string())
+            .await
+            .unwrap();
+
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&sent);
+        client.on_command(move |cmd| {
+            let line = cmd.to_bytes(ProtocolVersion::V3).unwrap();
+            log.lock()
+                .unwrap()
+                .push(String::from_utf8(line).unwrap().trim_end().to_owned());
+        });
+        // Rename the station in the payload; hooks run in the order added
+        client.on_frame(|frame| {
+            if let OwnedFrame::V3 { payload, .. } = frame {
+                payload[8..13].copy_from_slice(b"HOOK ");
+            }
+        });
+        client.on_frame(|frame| {
+            if let OwnedFrame::V3 { payload, .. } = frame {
+                payload[18..20].copy_from_slice(b"XX");
+            }
+        });
+
+        client.station("ANMO", "IU").await.unwrap();
+        client.data().await.unwrap();
+        client.end_stream().await.unwrap();
+        let hooked = Some(StationKey {
+            network: "XX".to_owned(),
+            station: "HOOK".to_owned(),
+        });
+        let frame = client.next_frame().await.unwrap().unwrap();
+        assert_eq!(frame.station_key(), hooked);
+        let mut event = client.next_event().await.unwrap();
+        let StreamEvent::Frame(frame) = &mut event else {
+            panic!("expected a frame, got {event:?}");
+        };
+        assert_eq!(frame.sequence(), SequenceNumber::new(6));
+        assert_eq!(frame.station_key(), hooked);
+
+        // Sequence tracking saw the frames as received
+        assert_eq!(
+            client.last_sequence("IU", "ANMO"),
+            Some(SequenceNumber::new(6))
+        );
+        assert_eq!(client.last_sequence("XX", "HOOK"), None);
+        assert_eq!(*sent.lock().unwrap(), ["STATION ANMO IU", "DATA", "END"]);
+    }
 }
@@ -1,41 +1,103 @@
+use std::collections::VecDeque;
+use std::io;
+use std::net::SocketAddr;
 use std::time::Duration;
 
 use seedlink_rs_protocol::frame::{v3, v4};
-use seedlink_rs_protocol::{Command, ProtocolVersion};
-use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader, BufWriter};
+use seedlink_rs_protocol::{Command, ProtocolVersion, Response};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufWriter};
 use tokio::net::TcpStream;
-use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::task::JoinSet;
+use tokio::time::Instant;
 use tracing::{debug, trace, warn};
 
 use crate::error::{ClientError, Result};
-use crate::state::OwnedFrame;
+use crate::proxy::ProxyConfig;
+use crate::socket::SocketOptions;
+use crate::state::{OwnedFrame, StreamEvent};
+
+/// Bytes reserved in the read buffer before each socket read.
+const READ_CHUNK: usize = 8 * 1024;
+
+/// Wait before racing the next resolved address against pending ones
+/// (RFC 8305 "Connection Attempt Delay").
+const CONNECT_ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+/// Read half of the transport: TCP, or any stream given to
+/// [`SeedLinkClient::connect_io()`](crate::SeedLinkClient::connect_io).
+type Reader = Box<dyn AsyncRead + Send + Sync + Unpin>;
+/// Write half of the transport.
+type Writer = Box<dyn AsyncWrite + Send + Sync + Unpin>;
 
 pub struct Connection {
-    reader: BufReader<OwnedReadHalf>,
-    writer: BufWriter<OwnedWriteHalf>,
+    reader: Reader,
+    /// Bytes read off the socket but not yet consumed. Reads append here and
+    /// lines and frames are only removed once complete, so a read future
+    /// dropped mid-frame (e.g. in `tokio::select!`) loses nothing.
+    pending: Vec<u8>,
+    writer: BufWriter<Writer>,
     read_timeout: Duration,
+    /// [`v3::info_continues()`] of the last v3 frame taken: `Some(false)` after
+    /// the last frame of a v3 INFO response.
+    pub last_v3_info: Option<bool>,
+    /// Largest v4 payload accepted; longer frames fail before any buffer is
+    /// allocated for them.
+    pub max_payload_len: usize,
+    /// v4 payloads are zstd-compressed (negotiated with `CAPABILITIES ZSTD`).
+    #[cfg(feature = "zstd")]
+    pub zstd: bool,
 }
 
 impl Connection {
     pub async fn connect(
         addr: &str,
+        proxy: Option<&ProxyConfig>,
+        socket: &SocketOptions,
         connect_timeout: Duration,
         read_timeout: Duration,
     ) -> Result<Self> {
         debug!(addr, "TCP connecting");
-        let stream = tokio::time::timeout(connect_timeout, TcpStream::connect(addr))
+        let connect = async {
+            match proxy {
+                Some(proxy) => proxy.connect(addr, socket).await,
+                None => connect_tcp(addr, socket).await,
+            }
+        };
+        let stream = tokio::time::timeout(connect_timeout, connect)
             .await
-            .map_err(|_| ClientError::Timeout(connect_timeout))?
-            .map_err(ClientError::Io)?;
+            .map_err(|_| ClientError::Timeout(connect_timeout))??;
 
         stream.set_nodelay(true).ok();
 
         let (read_half, write_half) = stream.into_split();
-        Ok(Self {
-            reader: BufReader::new(read_half),
-            writer: BufWriter::new(write_half),
+        Ok(Self::new(
+            Box::new(read_half),
+            Box::new(write_half),
             read_timeout,
-        })
+        ))
+    }
+
+    /// Use an already connected transport, e.g. one end of a
+    /// [`tokio::io::duplex`] pair.
+    pub fn from_io<IO>(io: IO, read_timeout: Duration) -> Self
+    where
+        IO: AsyncRead + AsyncWrite + Send + Sync + Unpin + 'static,
+    {
+        let (read_half, write_half) = tokio::io::split(io);
+        Self::new(Box::new(read_half), Box::new(write_half), read_timeout)
+    }
+
+    fn new(reader: Reader, writer: Writer, read_timeout: Duration) -> Self {
+        Self {
+            reader,
+            pending: Vec::new(),
+            writer: BufWriter::new(writer),
+            read_timeout,
+            last_v3_info: None,
+            max_payload_len: v4::DEFAULT_MAX_PAYLOAD_LEN,
+            #[cfg(feature = "zstd")]
+            zstd: false,
+        }
     }
 
     pub async fn send_command(&mut self, cmd: &Command, version: ProtocolVersion) -> Result<()> {
@@ -50,9 +112,123 @@ impl Connection {
         Ok(())
     }
 
+    /// Read one line, including its `\n`. Cancel-safe.
     pub async fn read_line(&mut self) -> Result<String> {
-        let mut line = String::new();
-        let n = tokio::time::timeout(self.read_timeout, self.reader.read_line(&mut line))
+        loop {
+            if let Some(end) = self.pending.iter().position(|&b| b == b'\n') {
+                let line = String::from_utf8_lossy(&self.pending[..=end]).into_owned();
+                self.consume(end + 1);
+                return Ok(line);
+            }
+            self.fill().await?;
+        }
+    }
+
+    /// Wait up to `timeout` until bytes (or EOF) are ready to read, without
+    /// consuming anything. Returns `false` on timeout; safe to cancel.
+    pub async fn wait_readable(&mut self, timeout: Duration) -> Result<bool> {
+        if !self.pending.is_empty() {
+            return Ok(true);
+        }
+        self.pending.reserve(READ_CHUNK);
+        match tokio::time::timeout(timeout, self.reader.read_buf(&mut self.pending)).await {
+            Ok(Ok(_)) => Ok(true),
+            Ok(Err(e)) => Err(ClientError::Io(e)),
+            Err(_) => Ok(false),
+        }
+    }
+
+    /// Read the next frame of either version, chosen by its signature, or a
+    /// text line the server sent between frames (`END`, `ERROR`, ...).
+    ///
+    /// Cancel-safe: partial frames stay buffered for the next call.
+    pub async fn read_event(&mut self) -> Result<StreamEvent> {
+        loop {
+            if let Some(event) = self.take_event()? {
+                return Ok(event);
+            }
+            self.fill().await?;
+        }
+    }
+
+    /// Take one complete event off the front of the buffer, or `None` if
+    /// more bytes are needed. A malformed frame is consumed before its error
+    /// is returned, so the next call continues after it.
+    fn take_event(&mut self) -> Result<Option<StreamEvent>> {
+        let data = self.pending.as_slice();
+        match data {
+            [] => Ok(None),
+            [b'\n', ..] => {
+                self.consume(1);
+                Ok(Some(StreamEvent::Text(String::new())))
+            }
+            [_] => Ok(None),
+            [b'S', b'L', ..] => {
+                if data.len() < v3::FRAME_LEN {
+                    return Ok(None);
+                }
+                self.last_v3_info = v3::info_continues(data);
+                let frame = v3::parse(&data[..v3::FRAME_LEN]).map(OwnedFrame::from);
+                self.consume(v3::FRAME_LEN);
+                Ok(Some(StreamEvent::Frame(frame?)))
+            }
+            [b'S', b'E', ..] => {
+                self.last_v3_info = None;
+                Ok(self.take_v4_frame()?.map(StreamEvent::Frame))
+            }
+            _ => {
+                let Some(end) = data.iter().position(|&b| b == b'\n') else {
+                    return Ok(None);
+                };
+                let line = String::from_utf8_lossy(&data[..end])
+                    .trim_end_matches('\r')
+                    .to_owned();
+                self.consume(end + 1);
+                Ok(Some(match Response::parse_line(&line) {
+                    Ok(Response::End) => StreamEvent::EndOfFetch,
+                    Ok(Response::Error { code, description }) => {
+                        StreamEvent::Error { code, description }
+                    }
+                    _ => StreamEvent::Text(line),
+                }))
+            }
+        }
+    }
+
+    /// Take a complete v4 frame off the front of the buffer. The advertised
+    /// length is checked as soon as the header is in, before waiting for (and
+    /// buffering) the payload.
+    fn take_v4_frame(&mut self) -> Result<Option<OwnedFrame>> {
+        let frame = match v4::parse_partial(&self.pending, self.max_payload_len) {
+            Ok(v4::Partial::Incomplete { .. }) => return Ok(None),
+            Ok(v4::Partial::Frame(raw, consumed)) => {
+                let frame = OwnedFrame::from(raw);
+                self.consume(consumed);
+                frame
+            }
+            Err(e) => {
+                // Skip the whole frame if it is here, else just the header
+                let skip = v4::parse_header(&self.pending)
+                    .map(|h| h.frame_len())
+                    .ok()
+                    .filter(|&len| len <= self.pending.len())
+                    .unwrap_or(v4::MIN_HEADER_LEN);
+                self.consume(skip);
+                return Err(e.into());
+            }
+        };
+        #[cfg(feature = "zstd")]
+        if self.zstd {
+            return decompress_payload(frame).map(Some);
+        }
+        Ok(Some(frame))
+    }
+
+    /// Read more bytes into the buffer. Cancel-safe: a dropped read leaves
+    /// the buffer as it was.
+    async fn fill(&mut self) -> Result<()> {
+        self.pending.reserve(READ_CHUNK);
+        let n = tokio::time::timeout(self.read_timeout, self.reader.read_buf(&mut self.pending))
             .await
             .map_err(|_| {
                 warn!(timeout = ?self.read_timeout, "read timeout");
@@ -62,74 +238,127 @@ impl Connection {
         if n == 0 {
             return Err(ClientError::Disconnected);
         }
-        Ok(line)
+        Ok(())
     }
 
-    pub async fn read_exact(&mut self, buf: &mut [u8]) -> Result<()> {
-        tokio::time::timeout(self.read_timeout, self.reader.read_exact(buf))
-            .await
-            .map_err(|_| ClientError::Timeout(self.read_timeout))?
-            .map_err(ClientError::Io)?;
-        Ok(())
+    fn consume(&mut self, n: usize) {
+        self.pending.drain(..n);
     }
 
-    pub async fn read_v3_frame(&mut self) -> Result<OwnedFrame> {
-        let mut buf = [0u8; v3::FRAME_LEN];
-        self.read_exact(&mut buf).await?;
-        let raw = v3::parse(&buf)?;
-        Ok(OwnedFrame::from(raw))
+    /// Detach the write half, to finish writing after this connection is
+    /// dropped (`Drop`). Later writes here go nowhere.
+    pub fn take_writer(&mut self) -> BufWriter<Writer> {
+        let sink: Writer = Box::new(tokio::io::sink());
+        std::mem::replace(&mut self.writer, BufWriter::new(sink))
     }
 
-    pub async fn read_v4_frame(&mut self) -> Result<OwnedFrame> {
-        // Read minimum header to determine frame size
-        let mut header = [0u8; v4::MIN_HEADER_LEN];
-        self.read_exact(&mut header).await?;
+    pub async fn shutdown(&mut self) -> Result<()> {
+        self.writer.shutdown().await.map_err(ClientError::Io)?;
+        Ok(())
+    }
+}
 
-        let station_id_len = header[16] as usize;
-        let payload_len = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
-        let remaining = station_id_len + payload_len;
+/// Replace the zstd-compressed payload of a v4 frame with its decompressed bytes.
+#[cfg(feature = "zstd")]
+fn decompress_payload(mut frame: OwnedFrame) -> Result<OwnedFrame> {
+    if let OwnedFrame::V4 { payload, .. } = &mut frame {
+        *payload = seedlink_rs_protocol::compression::decompress(payload)?;
+    }
+    Ok(frame)
+}
 
-        let mut full = Vec::with_capacity(v4::MIN_HEADER_LEN + remaining);
-        full.extend_from_slice(&header);
-        full.resize(v4::MIN_HEADER_LEN + remaining, 0);
-        self.read_exact(&mut full[v4::MIN_HEADER_LEN..]).await?;
+/// Open a TCP connection to `addr` (`host:port`), trying every resolved
+/// address "happy eyeballs" style: families alternate, IPv6 first, and each
+/// attempt starts [`CONNECT_ATTEMPT_DELAY`] after the previous one or as soon
+/// as it fails. The first connection wins; if all fail, every address's
+/// error is returned in [`ClientError::ConnectFailed`].
+///
+/// Addresses `socket` cannot bind for are skipped.
+pub(crate) async fn connect_tcp(addr: &str, socket: &SocketOptions) -> Result<TcpStream> {
+    let resolved: Vec<SocketAddr> = tokio::net::lookup_host(addr)
+        .await?
+        .filter(|target| socket.allows(target))
+        .collect();
+    if resolved.is_empty() {
+        return Err(ClientError::Io(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{addr} resolved to no usable addresses"),
+        )));
+    }
+    connect_first(interleave_families(resolved), socket).await
+}
 
-        let (raw, _consumed) = v4::parse(&full)?;
-        Ok(OwnedFrame::from(raw))
+/// Race connections to `pending` in order, see [`connect_tcp()`].
+async fn connect_first(
+    mut pending: VecDeque<SocketAddr>,
+    socket: &SocketOptions,
+) -> Result<TcpStream> {
+    let mut attempts = JoinSet::new();
+    let mut failures = Vec::new();
+    let mut next_start = Instant::now();
+    loop {
+        if attempts.is_empty() || Instant::now() >= next_start {
+            if let Some(target) = pending.pop_front() {
+                trace!(%target, "TCP attempt");
+                let socket = socket.clone();
+                attempts.spawn(async move { (target, socket.connect(target).await) });
+                next_start = Instant::now() + CONNECT_ATTEMPT_DELAY;
+            } else if attempts.is_empty() {
+                return Err(ClientError::ConnectFailed(failures));
+            }
+        }
+        tokio::select! {
+            Some(joined) = attempts.join_next() => match joined {
+                // Dropping `attempts` aborts the others
+                Ok((target, Ok(stream))) => {
+                    debug!(%target, "TCP connected");
+                    return Ok(stream);
+                }
+                Ok((target, Err(e))) => {
+                    debug!(%target, error = %e, "TCP attempt failed");
+                    failures.push((target, e));
+                    next_start = Instant::now();
+                }
+                Err(e) => return Err(ClientError::Io(io::Error::other(e))),
+            },
+            _ = tokio::time::sleep_until(next_start), if !pending.is_empty() => {}
+        }
     }
+}
 
-    pub async fn shutdown(&mut self) -> Result<()> {
-        self.writer.shutdown().await.map_err(ClientError::Io)?;
-        Ok(())
+/// Order addresses IPv6, IPv4, IPv6, ..., keeping the resolver's order
+/// within each family.
+fn interleave_families(addrs: Vec<SocketAddr>) -> VecDeque<SocketAddr> {
+    let (mut v6, mut v4): (VecDeque<_>, VecDeque<_>) =
+        addrs.into_iter().partition(SocketAddr::is_ipv6);
+    let mut out = VecDeque::with_capacity(v6.len() + v4.len());
+    while !v6.is_empty() || !v4.is_empty() {
+        out.extend(v6.pop_front());
+        out.extend(v4.pop_front());
     }
+    out
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use seedlink_rs_protocol::SequenceNumber;
     use seedlink_rs_protocol::frame::{PayloadFormat, PayloadSubformat};
-    use tokio::io::AsyncWriteExt;
+    use seedlink_rs_protocol::response::ErrorCode;
+    use seedlink_rs_protocol::{SeedlinkError, SequenceNumber};
+    use tokio::io::{AsyncWriteExt, DuplexStream, ReadHalf, WriteHalf};
     use tokio::net::TcpListener;
 
-    async fn setup_pair() -> (Connection, OwnedWriteHalf, OwnedReadHalf) {
-        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
-        let addr = listener.local_addr().unwrap();
-
-        let (client_stream, server_accept) =
-            tokio::join!(async { TcpStream::connect(addr).await.unwrap() }, async {
-                listener.accept().await.unwrap()
-            });
-
-        let (server_read, server_write) = server_accept.0.into_split();
-        let (client_read, client_write) = client_stream.into_split();
-
-        let conn = Connection {
-            reader: BufReader::new(client_read),
-            writer: BufWriter::new(client_write),
-            read_timeout: Duration::from_secs(5),
-        };
+    async fn read_frame(conn: &mut Connection) -> OwnedFrame {
+        match conn.read_event().await.unwrap() {
+            StreamEvent::Frame(frame) => frame,
+            other => panic!("expected frame, got {other:?}"),
+        }
+    }
 
+    async fn setup_pair() -> (Connection, WriteHalf<DuplexStream>, ReadHalf<DuplexStream>) {
+        let (client, server) = tokio::io::duplex(64 * 1024);
+        let (server_read, server_write) = tokio::io::split(server);
+        let conn = Connection::from_io(client, Duration::from_secs(5));
         (conn, server_write, server_read)
     }
 
@@ -166,7 +395,7 @@ mod tests {
         server_write.write_all(&frame).await.unwrap();
         server_write.flush().await.unwrap();
 
-        let owned = conn.read_v3_frame().await.unwrap();
+        let owned = read_frame(&mut conn).await;
         assert_eq!(owned.sequence(), SequenceNumber::new(42));
         assert_eq!(owned.payload(), &payload[..]);
     }
@@ -187,7 +416,7 @@ mod tests {
         server_write.write_all(&frame).await.unwrap();
         server_write.flush().await.unwrap();
 
-        let owned = conn.read_v4_frame().await.unwrap();
+        let owned = read_frame(&mut conn).await;
         assert_eq!(owned.sequence(), SequenceNumber::new(99));
         assert_eq!(owned.payload(), payload);
         match &owned {
@@ -196,6 +425,31 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn oversized_v4_payload_rejected_from_header() {
+        let (mut conn, mut server_write, _server_read) = setup_pair().await;
+        conn.max_payload_len = 1024;
+
+        let mut frame = v4::write(
+            PayloadFormat::MiniSeed2,
+            PayloadSubformat::Data,
+            SequenceNumber::new(1),
+            "IU_ANMO",
+            b"",
+        )
+        .unwrap();
+        // Claims a 4 GiB payload that never arrives
+        frame[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+        server_write.write_all(&frame).await.unwrap();
+        server_write.flush().await.unwrap();
+
+        let err = conn.read_event().await.unwrap_err();
+        assert!(matches!(
+            err,
+            ClientError::Protocol(SeedlinkError::PayloadTooLarge { max: 1024, .. })
+        ));
+    }
+
     #[tokio::test]
     async fn read_line_disconnected() {
         let (mut conn, server_write, _server_read) = setup_pair().await;
@@ -211,6 +465,8 @@ mod tests {
         // Use a non-routable address to trigger timeout
         let result = Connection::connect(
             "192.0.2.1:18000",
+            None,
+            &SocketOptions::default(),
             Duration::from_millis(50),
             Duration::from_secs(5),
         )
@@ -218,23 +474,55 @@ mod tests {
         assert!(matches!(result, Err(ClientError::Timeout(_))));
     }
 
+    #[test]
+    fn families_alternate_ipv6_first() {
+        let addrs: Vec<SocketAddr> = ["10.0.0.1:1", "10.0.0.2:1", "[::1]:1", "10.0.0.3:1"]
+            .iter()
+            .map(|a| a.parse().unwrap())
+            .collect();
+        let ordered: Vec<String> = interleave_families(addrs)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            ordered,
+            ["[::1]:1", "10.0.0.1:1", "10.0.0.2:1", "10.0.0.3:1"]
+        );
+    }
+
     #[tokio::test]
-    async fn read_timeout_triggers() {
+    async fn connect_tries_every_address() {
+        // Bound then dropped: nothing listens on these ports
+        let mut closed = Vec::new();
+        for _ in 0..2 {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            closed.push(listener.local_addr().unwrap());
+        }
         let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
-        let addr = listener.local_addr().unwrap();
+        let open = listener.local_addr().unwrap();
 
-        let (client_stream, _server_accept) =
-            tokio::join!(async { TcpStream::connect(addr).await.unwrap() }, async {
-                listener.accept().await.unwrap()
-            });
-
-        let (client_read, client_write) = client_stream.into_split();
+        let stream = connect_first(
+            VecDeque::from([closed[0], closed[1], open]),
+            &SocketOptions::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(stream.peer_addr().unwrap(), open);
 
-        let mut conn = Connection {
-            reader: BufReader::new(client_read),
-            writer: BufWriter::new(client_write),
-            read_timeout: Duration::from_millis(50),
+        let err = connect_first(closed.iter().copied().collect(), &SocketOptions::default())
+            .await
+            .unwrap_err();
+        let ClientError::ConnectFailed(failures) = &err else {
+            panic!("{err}");
         };
+        assert_eq!(failures.len(), 2);
+        assert!(err.to_string().contains(&closed[1].to_string()), "{err}");
+    }
+
+    #[tokio::test]
+    async fn read_timeout_triggers() {
+        let (client, _server) = tokio::io::duplex(1024);
+        let mut conn = Connection::from_io(client, Duration::from_millis(50));
 
         // Server sends nothing — read_line should timeout
         let result = conn.read_line().await;
@@ -242,7 +530,7 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn read_exact_partial() {
+    async fn read_line_partial() {
         let (mut conn, mut server_write, _server_read) = setup_pair().await;
 
         // Send data in two parts
@@ -250,22 +538,72 @@ mod tests {
             server_write.write_all(b"HEL").await.unwrap();
             server_write.flush().await.unwrap();
             tokio::time::sleep(Duration::from_millis(10)).await;
-            server_write.write_all(b"LO").await.unwrap();
+            server_write.write_all(b"LO\r\n").await.unwrap();
             server_write.flush().await.unwrap();
         });
 
-        let mut buf = [0u8; 5];
-        conn.read_exact(&mut buf).await.unwrap();
-        assert_eq!(&buf, b"HELLO");
+        assert_eq!(conn.read_line().await.unwrap(), "HELLO\r\n");
 
         server_task.await.unwrap();
     }
 
+    #[tokio::test]
+    async fn read_event_cancelled_mid_frame_resumes() {
+        let (mut conn, mut server_write, _server_read) = setup_pair().await;
+
+        let v3_frame = v3::write(SequenceNumber::new(1), &[0x11; v3::PAYLOAD_LEN]).unwrap();
+        let v4_frame = v4::write(
+            PayloadFormat::MiniSeed2,
+            PayloadSubformat::Data,
+            SequenceNumber::new(2),
+            "IU_ANMO",
+            &[0x22; 300],
+        )
+        .unwrap();
+        let mut stream = v3_frame.clone();
+        stream.extend_from_slice(&v4_frame);
+        stream.extend_from_slice(b"END\r\n");
+
+        // Deliver the bytes in small pieces, cancelling a read after each one
+        for chunk in stream.chunks(100) {
+            server_write.write_all(chunk).await.unwrap();
+            server_write.flush().await.unwrap();
+            let cancelled = tokio::time::timeout(Duration::from_millis(20), async {
+                loop {
+                    match conn.read_event().await.unwrap() {
+                        StreamEvent::Frame(_) | StreamEvent::EndOfFetch => {}
+                        other => panic!("unexpected {other:?}"),
+                    }
+                }
+            })
+            .await;
+            assert!(cancelled.is_err());
+        }
+        // Every byte is buffered, nothing was lost or consumed twice
+        assert_eq!(conn.pending.len(), 0);
+
+        // Cancellation inside a frame, then the rest arrives
+        server_write.write_all(&v3_frame[..300]).await.unwrap();
+        server_write.flush().await.unwrap();
+        let cancelled = tokio::time::timeout(Duration::from_millis(20), conn.read_event()).await;
+        assert!(cancelled.is_err());
+        server_write.write_all(&v3_frame[300..]).await.unwrap();
+        server_write.write_all(&v4_frame).await.unwrap();
+        server_write.flush().await.unwrap();
+
+        let first = read_frame(&mut conn).await;
+        assert_eq!(first.sequence(), SequenceNumber::new(1));
+        assert_eq!(first.payload(), &[0x11; v3::PAYLOAD_LEN][..]);
+        let second = read_frame(&mut conn).await;
+        assert_eq!(second.sequence(), SequenceNumber::new(2));
+        assert_eq!(second.payload(), &[0x22; 300][..]);
+    }
+
     #[tokio::test]
     async fn read_line_then_v3_frame() {
         let (mut conn, mut server_write, _server_read) = setup_pair().await;
 
-        // Send a line followed by a v3 frame — tests BufReader mode switching
+        // Send a line followed by a v3 frame — tests switching between lines and frames
         let payload = [0x55_u8; v3::PAYLOAD_LEN];
         let frame = v3::write(SequenceNumber::new(7), &payload).unwrap();
 
@@ -276,7 +614,55 @@ mod tests {
         let line = conn.read_line().await.unwrap();
         assert_eq!(line.trim(), "OK");
 
-        let owned = conn.read_v3_frame().await.unwrap();
+        let owned = read_frame(&mut conn).await;
         assert_eq!(owned.sequence(), SequenceNumber::new(7));
     }
+
+    #[tokio::test]
+    async fn read_event_handles_interleaved_text() {
+        let (mut conn, mut server_write, _server_read) = setup_pair().await;
+
+        let v3_frame = v3::write(SequenceNumber::new(1), &[0u8; v3::PAYLOAD_LEN]).unwrap();
+        let v4_frame = v4::write(
+            PayloadFormat::MiniSeed2,
+            PayloadSubformat::Data,
+            SequenceNumber::new(2),
+            "IU_ANMO",
+            b"data",
+        )
+        .unwrap();
+        server_write.write_all(&v4_frame).await.unwrap();
+        server_write
+            .write_all(b"ERROR LIMIT too many stations\r\n\r\n")
+            .await
+            .unwrap();
+        server_write.write_all(&v3_frame).await.unwrap();
+        server_write
+            .write_all(b"\nNOTE hi\r\nEND\r\n")
+            .await
+            .unwrap();
+        server_write.flush().await.unwrap();
+
+        let mut events = Vec::new();
+        for _ in 0..7 {
+            events.push(conn.read_event().await.unwrap());
+        }
+        assert!(
+            matches!(&events[0], StreamEvent::Frame(f) if f.sequence() == SequenceNumber::new(2))
+        );
+        assert_eq!(
+            events[1],
+            StreamEvent::Error {
+                code: Some(ErrorCode::Limit),
+                description: "too many stations".into(),
+            }
+        );
+        assert_eq!(events[2], StreamEvent::Text(String::new()));
+        assert!(
+            matches!(&events[3], StreamEvent::Frame(f) if f.sequence() == SequenceNumber::new(1))
+        );
+        assert_eq!(events[4], StreamEvent::Text(String::new()));
+        assert_eq!(events[5], StreamEvent::Text("NOTE hi".into()));
+        assert_eq!(events[6], StreamEvent::EndOfFetch);
+    }
 }
@@ -0,0 +1,517 @@
+//! DataLink forwarding, like `slink2dali`.
+//!
+//! [`DataLinkSink`] sends received miniSEED records to a DataLink server
+//! (e.g. ringserver) with the protocol's `WRITE` command, so a SeedLink feed
+//! can be injected into a ring without the C tools.
+//!
+//! Each DataLink packet is `DL`, a one-byte header length, the header text,
+//! then an optional payload. Records are written with stream ID
+//! `NET_STA_LOC_CHAN/MSEED` and start/end times in microseconds since the
+//! epoch (DataLink 1.0 high-precision time).
+
+use std::io;
+use std::pin::pin;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use futures_core::Stream;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tracing::{debug, info, warn};
+
+use crate::error::{ClientError, Result};
+use crate::header::RecordHeader;
+use crate::reconnect::ReconnectConfig;
+use crate::samples::nano_time_to_system_time;
+use crate::state::OwnedFrame;
+
+/// Longest header a DataLink packet can carry (its length is one byte).
+const MAX_HEADER_LEN: usize = 255;
+/// Largest reply message accepted from the server (libdali's
+/// `MAXPACKETSIZE`), so a bad size field cannot force a huge allocation.
+const MAX_MESSAGE_LEN: usize = 16_384;
+
+/// Configuration for [`DataLinkSink`].
+#[derive(Clone, Debug)]
+pub struct DataLinkConfig {
+    /// Client ID sent with `ID`, shown by the server. Default:
+    /// `"seedlink-rs:<version>"`.
+    pub client_id: String,
+    /// Ask the server to acknowledge each `WRITE` (flag `A`), so a record is
+    /// only counted as written once the server has it. Default: `true`.
+    pub ack: bool,
+    /// Timeout for connecting and for each packet exchange. Default: 30 seconds.
+    pub timeout: Duration,
+    /// User and password sent with `AUTH USERPASS` after `ID`, for servers
+    /// that require authentication to write. Default: `None`.
+    pub credentials: Option<(String, String)>,
+    /// Backoff between reconnect attempts after a failed write. `statefile`
+    /// and `state_save_interval` are not used. Default:
+    /// [`ReconnectConfig::default()`] (unlimited attempts).
+    pub reconnect: ReconnectConfig,
+}
+
+impl Default for DataLinkConfig {
+    fn default() -> Self {
+        Self {
+            client_id: format!("seedlink-rs:{}", env!("CARGO_PKG_VERSION")),
+            ack: true,
+            timeout: Duration::from_secs(30),
+            credentials: None,
+            reconnect: ReconnectConfig::default(),
+        }
+    }
+}
+
+/// Forwards miniSEED records to a DataLink server.
+///
+/// Connects on the first write. When a write fails (connection lost, timeout),
+/// the sink reconnects with backoff and sends the same record again, so a
+/// server restart loses nothing. An `ERROR` reply to a `WRITE` is returned as
+/// [`ClientError::ServerError`] without retrying.
+///
+/// ```no_run
+/// # async fn example() -> seedlink_rs_client::Result<()> {
+/// use seedlink_rs_client::{DataLinkSink, ReconnectingClient};
+///
+/// let mut client = ReconnectingClient::connect("rtserve.iris.washington.edu:18000").await?;
+/// client.station("ANMO", "IU").await?;
+/// client.data().await?;
+/// client.end_stream().await?;
+///
+/// let mut sink = DataLinkSink::new("localhost:16000");
+/// sink.forward_stream(client.into_stream()).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct DataLinkSink {
+    addr: String,
+    config: DataLinkConfig,
+    stream: Option<TcpStream>,
+    server_id: Option<String>,
+}
+
+impl DataLinkSink {
+    /// Forward to `addr` (`host:port`) with default configuration.
+    pub fn new(addr: &str) -> Self {
+        Self::with_config(addr, DataLinkConfig::default())
+    }
+
+    /// Forward with custom configuration.
+    pub fn with_config(addr: &str, config: DataLinkConfig) -> Self {
+        Self {
+            addr: addr.to_owned(),
+            config,
+            stream: None,
+            server_id: None,
+        }
+    }
+
+    /// The server's reply to `ID` from the most recent connection, e.g.
+    /// `"DataLink 2018.078 :: DLPROTO:1.0 PACKETSIZE:512 WRITE"`.
+    pub fn server_id(&self) -> Option<&str> {
+        self.server_id.as_deref()
+    }
+
+    /// Connect and exchange `ID` (and `AUTH`, if
+    /// [`credentials`](DataLinkConfig::credentials) are set) now instead of on
+    /// the first write.
+    ///
+    /// Rejected credentials are [`ClientError::ServerError`].
+    pub async fn connect(&mut self) -> Result<()> {
+        let mut stream = self.with_timeout(TcpStream::connect(&self.addr)).await?;
+        let id = format!("ID {}", self.config.client_id);
+        self.with_timeout(write_packet(&mut stream, &id, &[]))
+            .await?;
+        let (reply, _) = self.with_timeout(read_packet(&mut stream)).await?;
+        let Some(server_id) = reply.strip_prefix("ID ") else {
+            return Err(ClientError::UnexpectedResponse(reply));
+        };
+        let server_id = server_id.to_owned();
+        if let Some((user, password)) = &self.config.credentials {
+            let auth = format!("AUTH USERPASS {user} {password}");
+            self.with_timeout(write_packet(&mut stream, &auth, &[]))
+                .await?;
+            let (reply, message) = self.with_timeout(read_packet(&mut stream)).await?;
+            check_reply(reply, &message)?;
+        }
+        info!(addr = %self.addr, server = %server_id, "connected to DataLink server");
+        self.server_id = Some(server_id);
+        self.stream = Some(stream);
+        Ok(())
+    }
+
+    /// Write one frame's record, reconnecting as needed.
+    ///
+    /// Fails if the payload has no miniSEED header (the stream ID and times
+    /// come from it; samples are not decoded), if the server rejects the
+    /// record, or if [`ReconnectConfig::max_attempts`] reconnects fail.
+    pub async fn write(&mut self, frame: &OwnedFrame) -> Result<()> {
+        let record = RecordHeader::parse(frame.payload()).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, "payload has no miniSEED header")
+        })?;
+        let stream_id = format!(
+            "{}_{}_{}_{}/MSEED",
+            record.network, record.station, record.location, record.channel
+        );
+        let start = nano_time_to_system_time(&record.start_time);
+        let end = record
+            .sample_count
+            .checked_sub(1)
+            .and_then(|n| Duration::try_from_secs_f64(n as f64 / record.sample_rate).ok())
+            .and_then(|span| start.checked_add(span))
+            .unwrap_or(start);
+        let header = format!(
+            "WRITE {stream_id} {} {} {} {}",
+            hptime(start),
+            hptime(end),
+            if self.config.ack { 'A' } else { 'N' },
+            frame.payload().len()
+        );
+
+        let mut backoff = self.config.reconnect.initial_backoff;
+        let mut attempt = 0;
+        loop {
+            let error = match self.try_write(&header, frame.payload()).await {
+                Ok(()) => return Ok(()),
+                Err(e @ ClientError::ServerError(_)) => return Err(e),
+                Err(e) => e,
+            };
+            self.stream = None;
+            attempt += 1;
+            let max_attempts = self.config.reconnect.max_attempts;
+            if max_attempts > 0 && attempt > max_attempts {
+                return Err(ClientError::ReconnectFailed {
+                    attempts: max_attempts,
+                });
+            }
+            let delay = self.config.reconnect.jittered(backoff);
+            warn!(attempt, error = %error, delay_ms = delay.as_millis(), "DataLink write failed, reconnecting");
+            tokio::time::sleep(delay).await;
+            backoff = self.config.reconnect.next_backoff(backoff);
+        }
+    }
+
+    /// Forward every frame from `frames` until it ends.
+    ///
+    /// Returns the number of records written. Stops at the first stream
+    /// error or failed write.
+    pub async fn forward_stream(
+        &mut self,
+        frames: impl Stream<Item = Result<OwnedFrame>>,
+    ) -> Result<u64> {
+        let mut frames = pin!(frames);
+        let mut written = 0;
+        while let Some(frame) = std::future::poll_fn(|cx| frames.as_mut().poll_next(cx)).await {
+            self.write(&frame?).await?;
+            written += 1;
+        }
+        Ok(written)
+    }
+
+    /// One `WRITE` on the current connection, connecting first if needed.
+    async fn try_write(&mut self, header: &str, payload: &[u8]) -> Result<()> {
+        if self.stream.is_none() {
+            self.connect().await?;
+        }
+        let timeout = self.config.timeout;
+        let ack = self.config.ack;
+        let Some(stream) = self.stream.as_mut() else {
+            return Err(ClientError::Disconnected);
+        };
+        let exchange = async {
+            write_packet(stream, header, payload).await?;
+            if !ack {
+                return Ok(());
+            }
+            let (reply, message) = read_packet(stream).await?;
+            check_reply(reply, &message)?;
+            debug!(header, "DataLink write acknowledged");
+            Ok(())
+        };
+        tokio::time::timeout(timeout, exchange)
+            .await
+            .map_err(|_| ClientError::Timeout(timeout))?
+    }
+
+    async fn with_timeout<T, E: Into<ClientError>>(
+        &self,
+        fut: impl Future<Output = std::result::Result<T, E>>,
+    ) -> Result<T> {
+        let timeout = self.config.timeout;
+        match tokio::time::timeout(timeout, fut).await {
+            Ok(result) => result.map_err(Into::into),
+            Err(_) => Err(ClientError::Timeout(timeout)),
+        }
+    }
+}
+
+/// Map an `OK`/`ERROR` reply to a result; `ERROR` carries the server's message.
+fn check_reply(reply: String, message: &[u8]) -> Result<()> {
+    match reply.split_whitespace().next() {
+        Some("OK") => Ok(()),
+        Some("ERROR") => Err(ClientError::ServerError(
+            String::from_utf8_lossy(message).into_owned(),
+        )),
+        _ => Err(ClientError::UnexpectedResponse(reply)),
+    }
+}
+
+/// Microseconds since the epoch: DataLink 1.0 high-precision time.
+fn hptime(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(after) => after.as_micros() as i64,
+        Err(before) => -(before.duration().as_micros() as i64),
+    }
+}
+
+/// Send `DL`, the header length, the header, then `payload`.
+async fn write_packet(stream: &mut TcpStream, header: &str, payload: &[u8]) -> Result<()> {
+    let Ok(len) = u8::try_from(header.len()) else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("DataLink header longer than {MAX_HEADER_LEN} bytes"),
+        )
+        .into());
+    };
+    let mut packet = Vec::with_capacity(3 + header.len() + payload.len());
+    packet.extend_from_slice(b"DL");
+    packet.push(len);
+    packet.extend_from_slice(header.as_bytes());
+    packet.extend_from_slice(payload);
+    stream.write_all(&packet).await?;
+    Ok(())
+}
+
+/// Read one packet from the server: its header, and for `OK`/`ERROR` replies
+/// the message whose size is the header's last field.
+async fn read_packet(stream: &mut TcpStream) -> Result<(String, Vec<u8>)> {
+    let mut preheader = [0u8; 3];
+    read_exact(stream, &mut preheader).await?;
+    if &preheader[..2] != b"DL" {
+        return Err(ClientError::UnexpectedResponse(format!(
+            "not a DataLink packet: {:?}",
+            String::from_utf8_lossy(&preheader)
+        )));
+    }
+    let mut header = vec![0u8; usize::from(preheader[2])];
+    read_exact(stream, &mut header).await?;
+    let header = String::from_utf8_lossy(&header).into_owned();
+
+    let mut message = Vec::new();
+    if header.starts_with("OK ") || header.starts_with("ERROR ") {
+        let size = header
+            .rsplit(' ')
+            .next()
+            .and_then(|size| size.parse::<usize>().ok())
+            .ok_or_else(|| ClientError::UnexpectedResponse(header.clone()))?;
+        if size > MAX_MESSAGE_LEN {
+            return Err(ClientError::UnexpectedResponse(format!(
+                "DataLink reply message of {size} bytes exceeds {MAX_MESSAGE_LEN}"
+            )));
+        }
+        message.resize(size, 0);
+        read_exact(stream, &mut message).await?;
+    }
+    Ok((header, message))
+}
+
+/// `read_exact`, with a clean EOF reported as [`ClientError::Disconnected`].
+async fn read_exact(stream: &mut TcpStream, buf: &mut [u8]) -> Result<()> {
+    match stream.read_exact(buf).await {
+        Ok(_) => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Err(ClientError::Disconnected),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use seedlink_rs_protocol::SequenceNumber;
+    use seedlink_rs_protocol::testing::TestRecordBuilder;
+    use tokio::net::TcpListener;
+
+    fn frame(seq: u64) -> OwnedFrame {
+        let payload = TestRecordBuilder::new()
+            .sample_rate(20.0)
+            .samples(&[1, 2, 3, 4, 5])
+            .build();
+        OwnedFrame::V3 {
+            sequence: SequenceNumber::new(seq),
+            payload,
+        }
+    }
+
+    /// Server side of one packet: header, then the payload of a `WRITE`.
+    async fn recv(stream: &mut TcpStream) -> Option<(String, Vec<u8>)> {
+        let mut pre = [0u8; 3];
+        stream.read_exact(&mut pre).await.ok()?;
+        let mut header = vec![0u8; usize::from(pre[2])];
+        stream.read_exact(&mut header).await.ok()?;
+        let header = String::from_utf8(header).unwrap();
+        let mut payload = Vec::new();
+        if header.starts_with("WRITE ") {
+            payload.resize(header.rsplit(' ').next().unwrap().parse().unwrap(), 0);
+            stream.read_exact(&mut payload).await.ok()?;
+        }
+        Some((header, payload))
+    }
+
+    async fn reply(stream: &mut TcpStream, header: &str, message: &str) {
+        let header = format!("{header} {}", message.len());
+        write_packet(stream, &header, message.as_bytes())
+            .await
+            .unwrap();
+    }
+
+    async fn reply_id(stream: &mut TcpStream) {
+        write_packet(stream, "ID DataLink 2018.078 :: DLPROTO:1.0 WRITE", &[])
+            .await
+            .unwrap();
+    }
+
+    fn fast_config() -> DataLinkConfig {
+        DataLinkConfig {
+            client_id: "test".to_owned(),
+            reconnect: ReconnectConfig {
+                initial_backoff: Duration::from_millis(10),
+                max_attempts: 3,
+                ..ReconnectConfig::default()
+            },
+            ..DataLinkConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn writes_records_with_ack() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut packets = Vec::new();
+            while let Some(packet) = recv(&mut stream).await {
+                if packet.0.starts_with("ID ") {
+                    reply_id(&mut stream).await;
+                } else {
+                    reply(&mut stream, "OK 1", "").await;
+                }
+                packets.push(packet);
+            }
+            packets
+        });
+
+        let mut sink = DataLinkSink::with_config(&addr, fast_config());
+        let frames = async_stream::stream! {
+            yield Ok(frame(1));
+            yield Ok(frame(2));
+        };
+        assert_eq!(sink.forward_stream(frames).await.unwrap(), 2);
+        assert_eq!(
+            sink.server_id(),
+            Some("DataLink 2018.078 :: DLPROTO:1.0 WRITE")
+        );
+        drop(sink);
+
+        let packets = server.await.unwrap();
+        assert_eq!(packets.len(), 3);
+        assert_eq!(packets[0].0, "ID test");
+        // 2024-01-01T00:00:00, last of 5 samples at 20 Hz 0.2 s later
+        assert_eq!(
+            packets[1].0,
+            "WRITE IU_ANMO_00_BHZ/MSEED 1704067200000000 1704067200200000 A 512"
+        );
+        assert_eq!(packets[1].1, frame(1).payload());
+    }
+
+    #[tokio::test]
+    async fn reconnects_and_resends_after_lost_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let server = tokio::spawn(async move {
+            // First connection: drop the WRITE without replying
+            let (mut stream, _) = listener.accept().await.unwrap();
+            recv(&mut stream).await.unwrap();
+            reply_id(&mut stream).await;
+            recv(&mut stream).await.unwrap();
+            drop(stream);
+            // Second connection: acknowledge
+            let (mut stream, _) = listener.accept().await.unwrap();
+            recv(&mut stream).await.unwrap();
+            reply_id(&mut stream).await;
+            let (header, _) = recv(&mut stream).await.unwrap();
+            reply(&mut stream, "OK 1", "").await;
+            header
+        });
+
+        let mut sink = DataLinkSink::with_config(&addr, fast_config());
+        sink.write(&frame(1)).await.unwrap();
+        let resent = server.await.unwrap();
+        assert!(resent.starts_with("WRITE IU_ANMO_00_BHZ/MSEED "));
+    }
+
+    #[tokio::test]
+    async fn server_error_is_not_retried() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            recv(&mut stream).await.unwrap();
+            reply_id(&mut stream).await;
+            recv(&mut stream).await.unwrap();
+            reply(&mut stream, "ERROR 0", "write permission denied").await;
+            // Keep the connection open so only the ERROR can end the write
+            let _ = recv(&mut stream).await;
+        });
+
+        let mut sink = DataLinkSink::with_config(&addr, fast_config());
+        let err = sink.write(&frame(1)).await.unwrap_err();
+        assert!(
+            matches!(&err, ClientError::ServerError(msg) if msg == "write permission denied"),
+            "{err:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn oversized_reply_is_rejected() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let header = format!("OK 1 {}", MAX_MESSAGE_LEN + 1);
+            write_packet(&mut stream, &header, &[]).await.unwrap();
+        });
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let err = read_packet(&mut stream).await.unwrap_err();
+        assert!(
+            matches!(&err, ClientError::UnexpectedResponse(msg) if msg.contains("exceeds")),
+            "{err:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn payload_without_header_is_not_sent() {
+        let mut sink = DataLinkSink::with_config("127.0.0.1:1", fast_config());
+        let frame = OwnedFrame::V3 {
+            sequence: SequenceNumber::new(1),
+            payload: vec![0; 16],
+        };
+        let err = sink.write(&frame).await.unwrap_err();
+        assert!(
+            matches!(&err, ClientError::Io(e) if e.kind() == io::ErrorKind::InvalidData),
+            "{err:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_attempts() {
+        // Nothing listens on this port once the listener is dropped
+        let addr = {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            listener.local_addr().unwrap().to_string()
+        };
+        let mut sink = DataLinkSink::with_config(&addr, fast_config());
+        let err = sink.write(&frame(1)).await.unwrap_err();
+        assert!(matches!(err, ClientError::ReconnectFailed { attempts: 3 }));
+    }
+}
@@ -0,0 +1,207 @@
+//! Per-station demultiplexing of a streaming client.
+//!
+//! [`DemuxedStreams`] moves a [`SeedLinkClient`] into a background task that
+//! reads frames and routes each one to a bounded `mpsc` channel for its
+//! [`StationKey`], so each station can be consumed independently.
+
+use std::collections::HashMap;
+
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+use crate::client::SeedLinkClient;
+use crate::error::{ClientError, Result};
+use crate::state::{OwnedFrame, StationKey};
+
+/// Per-station frame channels fed by a background reader task.
+///
+/// Created by [`SeedLinkClient::into_demuxed_streams()`]. The reader awaits
+/// channel capacity, so a station whose receiver is not drained eventually
+/// stalls all stations. Frames for a station whose receiver was dropped are
+/// discarded; frames without a readable station key are dropped.
+///
+/// ```no_run
+/// # async fn example() -> seedlink_rs_client::Result<()> {
+/// use seedlink_rs_client::SeedLinkClient;
+///
+/// let mut client = SeedLinkClient::connect("rtserve.iris.washington.edu:18000").await?;
+/// client.station("ANMO", "IU").await?;
+/// client.data().await?;
+/// client.station("WLF", "GE").await?;
+/// client.data().await?;
+/// client.end_stream().await?;
+///
+/// let mut demuxed = client.into_demuxed_streams(64);
+/// for (key, mut rx) in demuxed.take_all() {
+///     tokio::spawn(async move {
+///         while let Some(frame) = rx.recv().await {
+///             println!("{}.{} seq={}", key.network, key.station, frame.sequence());
+///         }
+///     });
+/// }
+/// demuxed.finish().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct DemuxedStreams {
+    stations: HashMap<StationKey, mpsc::Receiver<OwnedFrame>>,
+    discovered: mpsc::UnboundedReceiver<(StationKey, mpsc::Receiver<OwnedFrame>)>,
+    task: JoinHandle<Result<()>>,
+}
+
+impl DemuxedStreams {
+    pub(crate) fn spawn(client: SeedLinkClient, buffer: usize) -> Self {
+        let buffer = buffer.max(1);
+        let mut senders = HashMap::new();
+        let mut stations = HashMap::new();
+        for key in client.subscribed_stations() {
+            let (tx, rx) = mpsc::channel(buffer);
+            senders.insert(key.clone(), tx);
+            stations.insert(key.clone(), rx);
+        }
+        let (discovered_tx, discovered) = mpsc::unbounded_channel();
+        let task = tokio::spawn(route(client, senders, discovered_tx, buffer));
+        Self {
+            stations,
+            discovered,
+            task,
+        }
+    }
+
+    /// Take the receiver for one subscribed station.
+    ///
+    /// Returns `None` for stations that were not subscribed with exact codes,
+    /// or whose receiver was already taken.
+    pub fn take(&mut self, network: &str, station: &str) -> Option<mpsc::Receiver<OwnedFrame>> {
+        self.stations.remove(&StationKey {
+            network: network.to_owned(),
+            station: station.to_owned(),
+        })
+    }
+
+    /// Take all remaining receivers for subscribed stations.
+    pub fn take_all(&mut self) -> HashMap<StationKey, mpsc::Receiver<OwnedFrame>> {
+        std::mem::take(&mut self.stations)
+    }
+
+    /// Wait for a station that was not in the subscription list (e.g. matched
+    /// by a wildcard) to send its first frame.
+    ///
+    /// Returns `None` once the reader task has stopped.
+    pub async fn next_new_station(&mut self) -> Option<(StationKey, mpsc::Receiver<OwnedFrame>)> {
+        self.discovered.recv().await
+    }
+
+    /// Wait for the reader task to stop and return how it ended.
+    ///
+    /// `Ok(())` means the server closed the stream, or every receiver was dropped.
+    pub async fn finish(self) -> Result<()> {
+        // Drop untaken receivers so the reader is not blocked on them
+        drop(self.stations);
+        drop(self.discovered);
+        match self.task.await {
+            Ok(result) => result,
+            Err(e) => Err(ClientError::Io(std::io::Error::other(e))),
+        }
+    }
+}
+
+/// Reader task: route frames until EOF, an error, or nobody is listening.
+async fn route(
+    mut client: SeedLinkClient,
+    mut senders: HashMap<StationKey, mpsc::Sender<OwnedFrame>>,
+    discovered: mpsc::UnboundedSender<(StationKey, mpsc::Receiver<OwnedFrame>)>,
+    buffer: usize,
+) -> Result<()> {
+    while let Some(frame) = client.next_frame().await? {
+        let Some(key) = frame.station_key() else {
+            debug!(seq = %frame.sequence(), "dropping frame without station key");
+            continue;
+        };
+        if !senders.contains_key(&key) {
+            if discovered.is_closed() {
+                continue;
+            }
+            let (tx, rx) = mpsc::channel(buffer);
+            debug!(network = %key.network, station = %key.station, "new station");
+            if discovered.send((key.clone(), rx)).is_ok() {
+                senders.insert(key.clone(), tx);
+            }
+        }
+        if let Some(tx) = senders.get(&key)
+            && tx.send(frame).await.is_err()
+        {
+            // Receiver dropped: keep the closed sender so the station is not
+            // announced again, and discard its frames from now on
+            debug!(network = %key.network, station = %key.station, "receiver dropped");
+        }
+        if discovered.is_closed() && senders.values().all(|tx| tx.is_closed()) {
+            debug!("all receivers dropped, stopping demux");
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SeedLinkClient;
+    use crate::mock::{MockConfig, MockServer};
+    use seedlink_rs_protocol::SequenceNumber;
+    use seedlink_rs_protocol::frame::v3;
+
+    fn make_v3_frame(seq: u64, station: &str, network: &str) -> Vec<u8> {
+        let mut payload = [b' '; v3::PAYLOAD_LEN];
+        payload[8..8 + station.len()].copy_from_slice(station.as_bytes());
+        payload[18..18 + network.len()].copy_from_slice(network.as_bytes());
+        v3::write(SequenceNumber::new(seq), &payload).unwrap()
+    }
+
+    #[tokio::test]
+    async fn routes_frames_per_station() {
+        let frames = vec![
+            make_v3_frame(1, "ANMO", "IU"),
+            make_v3_frame(2, "WLF", "GE"),
+            make_v3_frame(3, "ANMO", "IU"),
+            make_v3_frame(4, "COLA", "IU"),
+        ];
+        let config = MockConfig {
+            close_after_stream: true,
+            ..MockConfig::v3_default(frames)
+        };
+        let server = MockServer::start(config).await;
+
+        let mut client = SeedLinkClient::connect(&server.addr().to_string())
+            .await
+            .unwrap();
+        client.station("ANMO", "IU").await.unwrap();
+        client.data().await.unwrap();
+        client.station("WLF", "GE").await.unwrap();
+        client.data().await.unwrap();
+        client.station_pattern("IU", "C*").await.unwrap();
+        client.data().await.unwrap();
+        assert_eq!(client.subscribed_stations().len(), 2);
+        client.end_stream().await.unwrap();
+
+        let mut demuxed = client.into_demuxed_streams(8);
+        let mut anmo = demuxed.take("IU", "ANMO").unwrap();
+        let mut wlf = demuxed.take("GE", "WLF").unwrap();
+        assert!(demuxed.take("IU", "ANMO").is_none());
+
+        let (key, mut cola) = demuxed.next_new_station().await.unwrap();
+        assert_eq!((key.network.as_str(), key.station.as_str()), ("IU", "COLA"));
+
+        let seqs = |v: Vec<crate::OwnedFrame>| -> Vec<u64> {
+            v.iter().map(|f| f.sequence().value()).collect()
+        };
+        let mut got = Vec::new();
+        while let Some(f) = anmo.recv().await {
+            got.push(f);
+        }
+        assert_eq!(seqs(got), [1, 3]);
+        assert_eq!(wlf.recv().await.unwrap().sequence().value(), 2);
+        assert_eq!(cola.recv().await.unwrap().sequence().value(), 4);
+        demuxed.finish().await.unwrap();
+    }
+}
@@ -0,0 +1,314 @@
+//! Dial-up collection: periodic connect, FETCH, disconnect.
+//!
+//! [`DialupCollector`] mirrors the classic dial-up SeedLink workflow for
+//! low-bandwidth or intermittently connected stations: every poll interval it
+//! connects, subscribes with `DATA seq` from the last sequence seen per
+//! station, fetches whatever the server has buffered, and disconnects.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use futures_core::Stream;
+use seedlink_rs_protocol::{ProtocolVersion, SequenceNumber};
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, info, warn};
+
+use crate::client::SeedLinkClient;
+use crate::error::Result;
+use crate::state::{ClientConfig, OwnedFrame, StationKey};
+use crate::statefile::{self, SequenceState};
+
+/// One station to collect, with optional SELECT patterns.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Subscription {
+    /// Network code (e.g., `"IU"`).
+    pub network: String,
+    /// Station code (e.g., `"ANMO"`).
+    pub station: String,
+    /// SELECT patterns (e.g., `["BH?"]`). Empty = all channels.
+    pub selectors: Vec<String>,
+}
+
+impl Subscription {
+    /// Subscribe to all channels of a station.
+    pub fn new(network: &str, station: &str) -> Self {
+        Self {
+            network: network.to_owned(),
+            station: station.to_owned(),
+            selectors: Vec::new(),
+        }
+    }
+
+    /// Add a SELECT pattern.
+    pub fn select(mut self, pattern: &str) -> Self {
+        self.selectors.push(pattern.to_owned());
+        self
+    }
+}
+
+/// Configuration for [`DialupCollector`].
+#[derive(Clone, Debug)]
+pub struct DialupConfig {
+    /// Time between the starts of consecutive polls. Default: 5 minutes.
+    pub poll_interval: Duration,
+    /// Connection settings for each poll.
+    pub client: ClientConfig,
+    /// File to persist per-station sequences to after every poll, so a
+    /// restarted collector resumes where it left off. Default: `None`.
+    pub statefile: Option<PathBuf>,
+}
+
+impl Default for DialupConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_secs(300),
+            client: ClientConfig::default(),
+            statefile: None,
+        }
+    }
+}
+
+/// Periodically collects buffered data from a SeedLink server.
+///
+/// Each poll opens a fresh connection, so no connection is held between
+/// polls. Frames whose sequence is not newer than the last one delivered for
+/// their station are dropped, so resuming with `DATA seq` never produces
+/// duplicates.
+///
+/// ```no_run
+/// # async fn example() {
+/// use std::time::Duration;
+/// use seedlink_rs_client::{DialupCollector, DialupConfig, Subscription};
+///
+/// let config = DialupConfig {
+///     poll_interval: Duration::from_secs(600),
+///     statefile: Some("dialup.state".into()),
+///     ..DialupConfig::default()
+/// };
+/// let collector = DialupCollector::with_config(
+///     "seedlink.example.org:18000",
+///     vec![Subscription::new("XX", "REMOTE").select("HH?")],
+///     config,
+/// );
+/// collector
+///     .run(|frame| println!("seq={} len={}", frame.sequence(), frame.payload().len()))
+///     .await;
+/// # }
+/// ```
+pub struct DialupCollector {
+    addr: String,
+    stations: Vec<Subscription>,
+    config: DialupConfig,
+    sequences: HashMap<StationKey, SequenceNumber>,
+    /// Record timestamps for the state file.
+    state: SequenceState,
+    loaded: bool,
+}
+
+impl DialupCollector {
+    /// Collect `stations` from `addr` with default configuration.
+    pub fn new(addr: &str, stations: Vec<Subscription>) -> Self {
+        Self::with_config(addr, stations, DialupConfig::default())
+    }
+
+    /// Collect with custom configuration.
+    pub fn with_config(addr: &str, stations: Vec<Subscription>, config: DialupConfig) -> Self {
+        Self {
+            addr: addr.to_owned(),
+            stations,
+            config,
+            sequences: HashMap::new(),
+            state: SequenceState::new(),
+            loaded: false,
+        }
+    }
+
+    /// Last delivered sequence number per station.
+    pub fn sequences(&self) -> &HashMap<StationKey, SequenceNumber> {
+        &self.sequences
+    }
+
+    /// Run one poll: connect, fetch buffered data, disconnect.
+    ///
+    /// Calls `on_frame` for each new frame and returns how many were delivered.
+    /// The state file, if configured, is written even when the poll fails
+    /// part-way through.
+    pub async fn poll_once(&mut self, mut on_frame: impl FnMut(OwnedFrame)) -> Result<u64> {
+        self.load_state()?;
+        let result = self.fetch(&mut on_frame).await;
+        if let Some(path) = &self.config.statefile {
+            statefile::save(path, &self.state, &self.sequences)?;
+        }
+        result
+    }
+
+    /// Poll forever, every [`DialupConfig::poll_interval`].
+    ///
+    /// A failed poll is logged and retried at the next interval.
+    pub async fn run(mut self, mut on_frame: impl FnMut(OwnedFrame)) {
+        let mut interval = tokio::time::interval(self.config.poll_interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            match self.poll_once(&mut on_frame).await {
+                Ok(n) => info!(addr = %self.addr, frames = n, "dial-up poll complete"),
+                Err(e) => warn!(addr = %self.addr, error = %e, "dial-up poll failed"),
+            }
+        }
+    }
+
+    /// Poll forever and yield the collected frames as a [`Stream`].
+    ///
+    /// Failed polls are logged and retried at the next interval; the stream
+    /// never ends.
+    pub fn into_stream(mut self) -> impl Stream<Item = OwnedFrame> {
+        async_stream::stream! {
+            let mut interval = tokio::time::interval(self.config.poll_interval);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                let mut batch = Vec::new();
+                if let Err(e) = self.poll_once(|f| batch.push(f)).await {
+                    warn!(addr = %self.addr, error = %e, "dial-up poll failed");
+                }
+                for frame in batch {
+                    yield frame;
+                }
+            }
+        }
+    }
+
+    fn load_state(&mut self) -> Result<()> {
+        if self.loaded {
+            return Ok(());
+        }
+        if let Some(path) = &self.config.statefile {
+            self.state = statefile::load(path)?;
+            for (key, seq) in self.state.sequences() {
+                self.sequences.entry(key).or_insert(seq);
+            }
+            debug!(path = %path.display(), stations = self.sequences.len(), "loaded state file");
+        }
+        self.loaded = true;
+        Ok(())
+    }
+
+    async fn fetch(&mut self, on_frame: &mut impl FnMut(OwnedFrame)) -> Result<u64> {
+        let mut client =
+            SeedLinkClient::connect_with_config(&self.addr, self.config.client.clone()).await?;
+        for s in &self.stations {
+            client.station(&s.station, &s.network).await?;
+            for pattern in &s.selectors {
+                client.select(pattern).await?;
+            }
+            let key = StationKey {
+                network: s.network.clone(),
+                station: s.station.clone(),
+            };
+            match self.sequences.get(&key) {
+                Some(&seq) => client.data_from(seq).await?,
+                None => client.data().await?,
+            }
+        }
+        let v4 = client.version() == ProtocolVersion::V4;
+        if v4 {
+            client.fetch_v4().await?;
+        } else {
+            client.fetch().await?;
+        }
+
+        let mut delivered = 0;
+        while let Some(frame) = client.next_frame().await? {
+            let Some(key) = frame.station_key() else {
+                continue;
+            };
+            if self
+                .sequences
+                .get(&key)
+                .is_some_and(|&last| !frame.is_after(last))
+            {
+                continue;
+            }
+            self.sequences.insert(key, frame.sequence());
+            if self.config.statefile.is_some() {
+                self.state.update(&frame);
+            }
+            on_frame(frame);
+            delivered += 1;
+        }
+        if v4 {
+            // The fetch window ended with END; the connection is still open
+            client.bye().await?;
+        }
+        Ok(delivered)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockConfig, MockServer};
+    use seedlink_rs_protocol::frame::v3;
+
+    fn make_v3_frame(seq: u64, station: &str, network: &str) -> Vec<u8> {
+        let mut payload = [b' '; v3::PAYLOAD_LEN];
+        payload[8..8 + station.len()].copy_from_slice(station.as_bytes());
+        payload[18..18 + network.len()].copy_from_slice(network.as_bytes());
+        v3::write(SequenceNumber::new(seq), &payload).unwrap()
+    }
+
+    #[tokio::test]
+    async fn polls_deduplicate_and_persist() {
+        let frames = vec![
+            make_v3_frame(1, "ANMO", "IU"),
+            make_v3_frame(2, "WLF", "GE"),
+            make_v3_frame(3, "ANMO", "IU"),
+        ];
+        let server = MockServer::start(MockConfig {
+            close_after_stream: true,
+            max_connections: 3,
+            ..MockConfig::v3_default(frames)
+        })
+        .await;
+        let path =
+            std::env::temp_dir().join(format!("seedlink-dialup-{}.state", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let config = DialupConfig {
+            statefile: Some(path.clone()),
+            ..DialupConfig::default()
+        };
+        let stations = vec![
+            Subscription::new("IU", "ANMO"),
+            Subscription::new("GE", "WLF"),
+        ];
+        let mut collector = DialupCollector::with_config(
+            &server.addr().to_string(),
+            stations.clone(),
+            config.clone(),
+        );
+
+        let mut got = Vec::new();
+        assert_eq!(collector.poll_once(|f| got.push(f)).await.unwrap(), 3);
+        assert_eq!(got.len(), 3);
+
+        // The mock replays the same frames: all are duplicates now
+        assert_eq!(collector.poll_once(|_| {}).await.unwrap(), 0);
+        let conn1 = server.captured().connection(1);
+        assert!(conn1.contains(&"DATA 000003".to_owned()), "{conn1:?}");
+        assert!(conn1.contains(&"DATA 000002".to_owned()), "{conn1:?}");
+        assert_eq!(conn1.last().unwrap(), "FETCH");
+
+        // A new collector resumes from the state file
+        let mut restarted =
+            DialupCollector::with_config(&server.addr().to_string(), stations, config);
+        assert_eq!(restarted.poll_once(|_| {}).await.unwrap(), 0);
+        let key = StationKey {
+            network: "IU".into(),
+            station: "ANMO".into(),
+        };
+        assert_eq!(restarted.sequences()[&key], SequenceNumber::new(3));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
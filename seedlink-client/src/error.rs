@@ -1,5 +1,9 @@
+use std::net::SocketAddr;
 use std::time::Duration;
 
+use seedlink_rs_protocol::response::ErrorCode;
+use seedlink_rs_protocol::{ErrorKind, InvalidRecord, SequenceNumber};
+
 /// Errors that can occur during SeedLink client operations.
 #[derive(Debug, thiserror::Error)]
 pub enum ClientError {
@@ -15,6 +19,21 @@ pub enum ClientError {
     #[error("timeout after {0:?}")]
     Timeout(Duration),
 
+    /// No resolved address of the server accepted a TCP connection; each
+    /// address tried with its error.
+    #[error("connect failed: {}", connect_failures(.0))]
+    ConnectFailed(Vec<(SocketAddr, std::io::Error)>),
+
+    /// The proxy in [`ClientConfig::proxy`](crate::ClientConfig::proxy)
+    /// refused or failed the tunnel to the server.
+    #[error("proxy error: {0}")]
+    Proxy(String),
+
+    /// The proxy rejected the configured credentials, or requires
+    /// authentication none were configured for.
+    #[error("proxy authentication failed: {0}")]
+    ProxyAuth(String),
+
     /// Server closed the connection (read returned 0 bytes).
     #[error("disconnected")]
     Disconnected,
@@ -40,6 +59,20 @@ pub enum ClientError {
     #[error("unexpected response: {0}")]
     UnexpectedResponse(String),
 
+    /// An INFO reply carried a JSON payload that failed to parse.
+    #[error("invalid INFO JSON: {0}")]
+    Json(#[from] serde_json::Error),
+
+    /// A received miniSEED record failed validation with
+    /// [`ValidationMode::Error`](seedlink_rs_protocol::ValidationMode::Error).
+    #[error("invalid record at sequence {sequence}: {source}")]
+    InvalidRecord {
+        /// Sequence number of the frame carrying the record.
+        sequence: SequenceNumber,
+        /// What the validation found.
+        source: InvalidRecord,
+    },
+
     /// Auto-reconnect exhausted all retry attempts.
     #[error("reconnect failed after {attempts} attempts")]
     ReconnectFailed {
@@ -48,5 +81,96 @@ pub enum ClientError {
     },
 }
 
+impl ClientError {
+    /// The category of this error; see [`ErrorKind`].
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Io(_)
+            | Self::Timeout(_)
+            | Self::ConnectFailed(_)
+            | Self::Proxy(_)
+            | Self::Disconnected
+            | Self::ReconnectFailed { .. } => ErrorKind::Network,
+            Self::Protocol(e) => e.kind(),
+            Self::ServerError(_) if self.server_code().is_some_and(|c| c.is_auth()) => {
+                ErrorKind::Auth
+            }
+            Self::ProxyAuth(_) => ErrorKind::Auth,
+            Self::InvalidState { .. } => ErrorKind::State,
+            Self::ServerError(_)
+            | Self::NegotiationFailed(_)
+            | Self::UnexpectedResponse(_)
+            | Self::Json(_)
+            | Self::InvalidRecord { .. } => ErrorKind::Protocol,
+        }
+    }
+
+    /// Whether reconnecting may get past this error.
+    ///
+    /// True for network errors (except [`ReconnectFailed`](Self::ReconnectFailed),
+    /// which already gave up), corrupted or unexpected server input, and
+    /// `LIMIT`/`INTERNAL` server errors. False for other server refusals,
+    /// failed negotiation, rejected records and caller errors.
+    pub fn is_retriable(&self) -> bool {
+        match self {
+            Self::ReconnectFailed { .. } => false,
+            Self::Protocol(e) => e.is_retriable(),
+            Self::ServerError(_) => self.server_code().is_some_and(|c| c.is_transient()),
+            Self::NegotiationFailed(_) | Self::Json(_) | Self::InvalidRecord { .. } => false,
+            _ => matches!(self.kind(), ErrorKind::Network | ErrorKind::Protocol),
+        }
+    }
+
+    /// The v4/EXTREPLY error code of a [`ServerError`](Self::ServerError),
+    /// which reads `"CODE description"` or `"COMMAND: CODE description"`.
+    fn server_code(&self) -> Option<ErrorCode> {
+        let Self::ServerError(msg) = self else {
+            return None;
+        };
+        let msg = msg.split_once(": ").map_or(msg.as_str(), |(_, rest)| rest);
+        msg.split_whitespace().next().and_then(ErrorCode::parse)
+    }
+}
+
+fn connect_failures(failures: &[(SocketAddr, std::io::Error)]) -> String {
+    failures
+        .iter()
+        .map(|(addr, e)| format!("{addr}: {e}"))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
 /// Convenience alias for `Result<T, ClientError>`.
 pub type Result<T> = std::result::Result<T, ClientError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_server_errors() {
+        let denied = ClientError::ServerError("STATION: UNAUTHORIZED access denied".into());
+        assert_eq!(denied.kind(), ErrorKind::Auth);
+        assert!(!denied.is_retriable());
+
+        let limited = ClientError::ServerError("LIMIT too many commands".into());
+        assert_eq!(limited.kind(), ErrorKind::Protocol);
+        assert!(limited.is_retriable());
+
+        // Classic v3 ERROR without a code
+        assert!(!ClientError::ServerError(String::new()).is_retriable());
+
+        assert!(ClientError::Timeout(Duration::from_secs(1)).is_retriable());
+        assert!(ClientError::Proxy("connection refused".into()).is_retriable());
+        let proxy_auth = ClientError::ProxyAuth("407 Proxy Authentication Required".into());
+        assert_eq!(proxy_auth.kind(), ErrorKind::Auth);
+        assert!(!proxy_auth.is_retriable());
+        assert!(!ClientError::ReconnectFailed { attempts: 3 }.is_retriable());
+        let misuse = ClientError::InvalidState {
+            expected: "Streaming",
+            actual: "Connected",
+        };
+        assert_eq!(misuse.kind(), ErrorKind::State);
+        assert!(!misuse.is_retriable());
+    }
+}
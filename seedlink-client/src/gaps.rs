@@ -0,0 +1,232 @@
+//! Data completeness monitoring.
+//!
+//! [`GapDetector`] tracks the expected next start time of every channel and
+//! reports a [`GapEvent`] when a record starts later (gap) or earlier
+//! (overlap) than the previous record of its channel ended.
+
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+use tokio::sync::mpsc;
+
+use crate::samples::SampleRecord;
+
+/// Whether a [`GapEvent`] is missing data or repeated data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GapKind {
+    /// The record starts after the previous record ended.
+    Gap,
+    /// The record starts before the previous record ended.
+    Overlap,
+}
+
+/// A discontinuity in one channel.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GapEvent {
+    /// Channel as `NET.STA.LOC.CHA` (e.g., `"IU.ANMO.00.BHZ"`).
+    pub channel: String,
+    /// Gap or overlap.
+    pub kind: GapKind,
+    /// End time of the previous record, i.e. where this record should start.
+    pub expected: SystemTime,
+    /// Start time of this record.
+    pub actual: SystemTime,
+    /// Distance between `expected` and `actual`.
+    pub duration: Duration,
+}
+
+type GapCallback = Box<dyn FnMut(&GapEvent) + Send>;
+
+/// Detects gaps and overlaps between consecutive records of each channel.
+///
+/// Feed it decoded records with [`observe()`](Self::observe). A difference
+/// between the expected and actual start time is reported when it exceeds
+/// the tolerance — by default half a sample period, like libmseed. Events are
+/// returned from `observe()` and also delivered to the callback set with
+/// [`on_event()`](Self::on_event) and to every receiver from
+/// [`subscribe()`](Self::subscribe).
+///
+/// ```no_run
+/// # async fn example() -> seedlink_rs_client::Result<()> {
+/// use seedlink_rs_client::{GapDetector, SeedLinkClient};
+///
+/// let mut client = SeedLinkClient::connect("rtserve.iris.washington.edu:18000").await?;
+/// client.station("ANMO", "IU").await?;
+/// client.data().await?;
+/// client.end_stream().await?;
+///
+/// let mut gaps = GapDetector::new().on_event(|event| {
+///     println!("{:?} in {}: {:?}", event.kind, event.channel, event.duration);
+/// });
+/// while let Some(frame) = client.next_frame().await? {
+///     if let Ok(record) = frame.decode_samples() {
+///         gaps.observe(&record);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Default)]
+pub struct GapDetector {
+    tolerance: Option<Duration>,
+    /// End time of the last record seen, per channel.
+    expected: HashMap<String, SystemTime>,
+    callback: Option<GapCallback>,
+    subscribers: Vec<mpsc::UnboundedSender<GapEvent>>,
+}
+
+impl GapDetector {
+    /// A detector with the default tolerance of half a sample period.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Report only differences larger than `tolerance`, for every channel.
+    pub fn with_tolerance(mut self, tolerance: Duration) -> Self {
+        self.tolerance = Some(tolerance);
+        self
+    }
+
+    /// Call `callback` for every event.
+    pub fn on_event(mut self, callback: impl FnMut(&GapEvent) + Send + 'static) -> Self {
+        self.callback = Some(Box::new(callback));
+        self
+    }
+
+    /// A receiver of every event from now on. Dropped receivers are pruned.
+    pub fn subscribe(&mut self) -> mpsc::UnboundedReceiver<GapEvent> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.subscribers.push(tx);
+        rx
+    }
+
+    /// Check `record` against the previous record of its channel.
+    ///
+    /// The first record of a channel only sets its expected start time.
+    pub fn observe(&mut self, record: &SampleRecord) -> Option<GapEvent> {
+        let channel = format!(
+            "{}.{}.{}.{}",
+            record.network, record.station, record.location, record.channel
+        );
+        let Some(end) = record.end_time() else {
+            // No usable end time: start over with the next record
+            self.expected.remove(&channel);
+            return None;
+        };
+        let expected = self.expected.insert(channel.clone(), end)?;
+        let actual = record.start_time;
+
+        let (kind, duration) = match actual.duration_since(expected) {
+            Ok(d) => (GapKind::Gap, d),
+            Err(e) => (GapKind::Overlap, e.duration()),
+        };
+        let tolerance = self.tolerance.unwrap_or_else(|| {
+            if record.sample_rate > 0.0 {
+                Duration::from_secs_f64(0.5 / record.sample_rate)
+            } else {
+                Duration::ZERO
+            }
+        });
+        if duration <= tolerance {
+            return None;
+        }
+
+        let event = GapEvent {
+            channel,
+            kind,
+            expected,
+            actual,
+            duration,
+        };
+        if let Some(callback) = &mut self.callback {
+            callback(&event);
+        }
+        self.subscribers.retain(|tx| tx.send(event.clone()).is_ok());
+        Some(event)
+    }
+
+    /// Forget the expected start time of every channel, e.g. after a
+    /// deliberate restart of the stream.
+    pub fn reset(&mut self) {
+        self.expected.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::samples::SampleData;
+    use seedlink_rs_protocol::SequenceNumber;
+    use std::sync::{Arc, Mutex};
+    use std::time::UNIX_EPOCH;
+
+    /// A 1 Hz record of `len` samples starting `start_ms` after the epoch.
+    fn record(channel: &str, start_ms: u64, len: usize) -> SampleRecord {
+        SampleRecord {
+            sequence: SequenceNumber::new(1),
+            network: "IU".into(),
+            station: "ANMO".into(),
+            location: "00".into(),
+            channel: channel.into(),
+            start_time: UNIX_EPOCH + Duration::from_millis(start_ms),
+            sample_rate: 1.0,
+            samples: SampleData::Int(vec![0; len]),
+        }
+    }
+
+    #[test]
+    fn contiguous_records_within_tolerance() {
+        let mut gaps = GapDetector::new();
+        assert_eq!(gaps.observe(&record("BHZ", 0, 10)), None);
+        assert_eq!(gaps.observe(&record("BHZ", 10_000, 10)), None);
+        // 400 ms jitter is under half a sample period
+        assert_eq!(gaps.observe(&record("BHZ", 20_400, 10)), None);
+        // Other channels are tracked independently
+        assert_eq!(gaps.observe(&record("BHN", 90_000, 10)), None);
+    }
+
+    #[test]
+    fn reports_gaps_and_overlaps() {
+        let mut gaps = GapDetector::new();
+        gaps.observe(&record("BHZ", 0, 10));
+
+        let gap = gaps.observe(&record("BHZ", 15_000, 10)).unwrap();
+        assert_eq!(gap.channel, "IU.ANMO.00.BHZ");
+        assert_eq!(gap.kind, GapKind::Gap);
+        assert_eq!(gap.expected, UNIX_EPOCH + Duration::from_secs(10));
+        assert_eq!(gap.actual, UNIX_EPOCH + Duration::from_secs(15));
+        assert_eq!(gap.duration, Duration::from_secs(5));
+
+        let overlap = gaps.observe(&record("BHZ", 22_000, 10)).unwrap();
+        assert_eq!(overlap.kind, GapKind::Overlap);
+        assert_eq!(overlap.expected, UNIX_EPOCH + Duration::from_secs(25));
+        assert_eq!(overlap.duration, Duration::from_secs(3));
+    }
+
+    #[test]
+    fn explicit_tolerance() {
+        let mut gaps = GapDetector::new().with_tolerance(Duration::from_secs(2));
+        gaps.observe(&record("BHZ", 0, 10));
+        assert_eq!(gaps.observe(&record("BHZ", 11_500, 10)), None);
+        assert!(gaps.observe(&record("BHZ", 24_000, 10)).is_some());
+    }
+
+    #[test]
+    fn delivers_to_callback_and_subscribers() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&seen);
+        let mut gaps = GapDetector::new().on_event(move |e| sink.lock().unwrap().push(e.clone()));
+        let mut rx = gaps.subscribe();
+        drop(gaps.subscribe());
+
+        gaps.observe(&record("BHZ", 0, 10));
+        let event = gaps.observe(&record("BHZ", 20_000, 10)).unwrap();
+
+        assert_eq!(*seen.lock().unwrap(), std::slice::from_ref(&event));
+        assert_eq!(rx.try_recv().unwrap(), event);
+        assert_eq!(gaps.subscribers.len(), 1);
+
+        gaps.reset();
+        assert_eq!(gaps.observe(&record("BHZ", 90_000, 10)), None);
+    }
+}
@@ -0,0 +1,164 @@
+//! Header-only reads of miniSEED records.
+//!
+//! [`OwnedFrame::decode()`](crate::OwnedFrame::decode) decompresses every
+//! sample. Paths that only need the stream codes or timing of a record read
+//! its fixed header with [`RecordHeader::parse()`] instead, with the same
+//! field layout and byte order as the decoder.
+
+use miniseed_rs::{NanoTime, SourceId};
+
+/// miniSEED 2 fixed header length.
+const MSEED2_HEADER_LEN: usize = 48;
+/// miniSEED 3 fixed header length, before the source identifier.
+const MSEED3_HEADER_LEN: usize = 40;
+
+/// Stream codes and timing from a record's fixed header.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct RecordHeader {
+    pub(crate) network: String,
+    pub(crate) station: String,
+    pub(crate) location: String,
+    pub(crate) channel: String,
+    pub(crate) start_time: NanoTime,
+    pub(crate) sample_count: usize,
+    /// Samples per second; see [`MseedRecord::sample_rate`](miniseed_rs::MseedRecord::sample_rate).
+    pub(crate) sample_rate: f64,
+}
+
+impl RecordHeader {
+    /// Read the fixed header of a miniSEED 2 or 3 record. Returns `None` if
+    /// the payload is too short or a code is not UTF-8.
+    pub(crate) fn parse(payload: &[u8]) -> Option<Self> {
+        if payload.starts_with(b"MS\x03") {
+            Self::parse_mseed3(payload)
+        } else {
+            Self::parse_mseed2(payload)
+        }
+    }
+
+    fn parse_mseed2(p: &[u8]) -> Option<Self> {
+        if p.len() < MSEED2_HEADER_LEN {
+            return None;
+        }
+        let code = |range: std::ops::Range<usize>| -> Option<String> {
+            Some(std::str::from_utf8(&p[range]).ok()?.trim().to_owned())
+        };
+        let u16_at = |i: usize| u16::from_be_bytes([p[i], p[i + 1]]);
+        let i16_at = |i: usize| i16::from_be_bytes([p[i], p[i + 1]]);
+        Some(Self {
+            station: code(8..13)?,
+            location: code(13..15)?,
+            channel: code(15..18)?,
+            network: code(18..20)?,
+            start_time: NanoTime {
+                year: u16_at(20),
+                day: u16_at(22),
+                hour: p[24],
+                minute: p[25],
+                second: p[26],
+                nanosecond: u32::from(u16_at(28)) * 100_000,
+            },
+            sample_count: usize::from(u16_at(30)),
+            sample_rate: mseed2_sample_rate(i16_at(32), i16_at(34)),
+        })
+    }
+
+    fn parse_mseed3(p: &[u8]) -> Option<Self> {
+        if p.len() < MSEED3_HEADER_LEN {
+            return None;
+        }
+        let sid_end = MSEED3_HEADER_LEN + usize::from(p[33]);
+        let sid = std::str::from_utf8(p.get(MSEED3_HEADER_LEN..sid_end)?).ok()?;
+        let (network, station, location, channel) = SourceId::parse(sid).to_nslc();
+        let u16_at = |i: usize| u16::from_le_bytes([p[i], p[i + 1]]);
+        let u32_at = |i: usize| u32::from_le_bytes(p[i..i + 4].try_into().unwrap());
+        Some(Self {
+            network,
+            station,
+            location,
+            channel,
+            start_time: NanoTime {
+                year: u16_at(8),
+                day: u16_at(10),
+                hour: p[12],
+                minute: p[13],
+                second: p[14],
+                nanosecond: u32_at(4),
+            },
+            sample_count: u32_at(24) as usize,
+            sample_rate: f64::from_le_bytes(p[16..24].try_into().unwrap()),
+        })
+    }
+}
+
+/// Sample rate from the miniSEED 2 rate factor and multiplier (SEED manual,
+/// fixed section of data header).
+fn mseed2_sample_rate(factor: i16, multiplier: i16) -> f64 {
+    let (f, m) = (f64::from(factor), f64::from(multiplier));
+    match (factor > 0, multiplier > 0) {
+        (true, true) => f * m,
+        (true, false) => -f / m,
+        (false, true) => -m / f,
+        (false, false) => 1.0 / (f * m),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use miniseed_rs::{EncodingFormat, MseedRecord, Samples};
+
+    fn record(v3: bool) -> MseedRecord {
+        let record = if v3 {
+            MseedRecord::new_v3()
+        } else {
+            MseedRecord::new()
+        };
+        record
+            .with_nslc("IU", "ANMO", "00", "BHZ")
+            .with_start_time(NanoTime {
+                year: 2024,
+                day: 61,
+                hour: 12,
+                minute: 30,
+                second: 15,
+                nanosecond: 500_000_000,
+            })
+            .with_sample_rate(20.0)
+            .with_encoding(EncodingFormat::Steim2)
+            .with_samples(Samples::Int(vec![1, 2, 3]))
+    }
+
+    #[test]
+    fn header_matches_full_decode() {
+        for v3 in [false, true] {
+            let payload = miniseed_rs::encode(&record(v3)).unwrap();
+            let decoded = miniseed_rs::decode(&payload).unwrap();
+            let header = RecordHeader::parse(&payload).unwrap();
+            assert_eq!(header.network, decoded.network);
+            assert_eq!(header.station, decoded.station);
+            assert_eq!(header.location, decoded.location);
+            assert_eq!(header.channel, decoded.channel);
+            assert_eq!(header.start_time, decoded.start_time);
+            assert_eq!(header.sample_count, decoded.samples.len());
+            assert_eq!(header.sample_rate, decoded.sample_rate);
+        }
+    }
+
+    #[test]
+    fn short_payloads_have_no_header() {
+        assert_eq!(RecordHeader::parse(&[0u8; 47]), None);
+        let mut v3 = vec![0u8; MSEED3_HEADER_LEN];
+        v3[..3].copy_from_slice(b"MS\x03");
+        v3[33] = 10; // SID runs past the end
+        assert_eq!(RecordHeader::parse(&v3), None);
+    }
+
+    #[test]
+    fn mseed2_rate_factor_and_multiplier() {
+        assert_eq!(mseed2_sample_rate(20, 1), 20.0);
+        assert_eq!(mseed2_sample_rate(-10, 1), 0.1);
+        assert_eq!(mseed2_sample_rate(10, -2), 5.0);
+        assert_eq!(mseed2_sample_rate(-10, -2), 0.05);
+    }
+}
@@ -0,0 +1,67 @@
+//! Command, frame and resume gap hooks, installed with
+//! [`SeedLinkClient::on_command()`], [`SeedLinkClient::on_frame()`] and
+//! [`SeedLinkClient::on_resume_gap()`].
+//!
+//! [`SeedLinkClient::on_command()`]: crate::SeedLinkClient::on_command
+//! [`SeedLinkClient::on_frame()`]: crate::SeedLinkClient::on_frame
+//! [`SeedLinkClient::on_resume_gap()`]: crate::SeedLinkClient::on_resume_gap
+
+use std::sync::Arc;
+
+use seedlink_rs_protocol::Command;
+
+use crate::state::{OwnedFrame, ResumeGap};
+
+type CommandHook = Arc<dyn Fn(&Command) + Send + Sync>;
+type FrameHook = Arc<dyn Fn(&mut OwnedFrame) + Send + Sync>;
+type ResumeGapHook = Arc<dyn Fn(&ResumeGap) + Send + Sync>;
+
+/// Hooks of one client, run in the order they were added.
+#[derive(Clone, Default)]
+pub(crate) struct Hooks {
+    commands: Vec<CommandHook>,
+    frames: Vec<FrameHook>,
+    resume_gaps: Vec<ResumeGapHook>,
+}
+
+impl Hooks {
+    pub fn add_command(&mut self, hook: impl Fn(&Command) + Send + Sync + 'static) {
+        self.commands.push(Arc::new(hook));
+    }
+
+    pub fn add_frame(&mut self, hook: impl Fn(&mut OwnedFrame) + Send + Sync + 'static) {
+        self.frames.push(Arc::new(hook));
+    }
+
+    pub fn add_resume_gap(&mut self, hook: impl Fn(&ResumeGap) + Send + Sync + 'static) {
+        self.resume_gaps.push(Arc::new(hook));
+    }
+
+    /// The command hooks alone, for a client whose frames are hooked by its
+    /// owner instead (see `ReconnectingClient`).
+    pub fn commands_only(&self) -> Self {
+        Self {
+            commands: self.commands.clone(),
+            frames: Vec::new(),
+            resume_gaps: Vec::new(),
+        }
+    }
+
+    pub fn command(&self, cmd: &Command) {
+        for hook in &self.commands {
+            hook(cmd);
+        }
+    }
+
+    pub fn frame(&self, frame: &mut OwnedFrame) {
+        for hook in &self.frames {
+            hook(frame);
+        }
+    }
+
+    pub fn resume_gap(&self, gap: &ResumeGap) {
+        for hook in &self.resume_gaps {
+            hook(gap);
+        }
+    }
+}
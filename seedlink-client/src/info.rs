@@ -0,0 +1,440 @@
+//! Typed parsing of SeedLink INFO responses.
+//!
+//! INFO replies arrive as one or more frames whose payloads carry an XML
+//! document. [`reassemble_xml`] joins those payloads back into a single
+//! document, and the `parse_*` functions extract the station/stream
+//! elements into plain structs. [`info_json`] decodes any INFO reply, XML
+//! or JSON, into a [`serde_json::Value`].
+
+use seedlink_rs_protocol::{InfoDocument, PayloadFormat, SequenceNumber};
+use serde_json::{Map, Value};
+
+use crate::state::OwnedFrame;
+
+/// A station entry from an `INFO STATIONS` response.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StationInfo {
+    /// FDSN network code (e.g., `"IU"`).
+    pub network: String,
+    /// Station code (e.g., `"ANMO"`).
+    pub station: String,
+    /// Free-form station description (may be empty).
+    pub description: String,
+    /// Oldest sequence number buffered for this station, if reported.
+    pub begin_seq: Option<SequenceNumber>,
+    /// Newest sequence number buffered for this station, if reported.
+    pub end_seq: Option<SequenceNumber>,
+}
+
+/// A stream entry from an `INFO STREAMS` response.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StreamInfo {
+    /// FDSN network code of the owning station.
+    pub network: String,
+    /// Station code of the owning station.
+    pub station: String,
+    /// Location code (may be empty or blank-padded).
+    pub location: String,
+    /// Channel code (e.g., `"BHZ"`).
+    pub channel: String,
+    /// Record type code (e.g., `"D"`).
+    pub type_code: String,
+    /// Oldest sequence number buffered for this stream, if reported.
+    pub begin_seq: Option<SequenceNumber>,
+    /// Newest sequence number buffered for this stream, if reported.
+    pub end_seq: Option<SequenceNumber>,
+    /// Start time of the oldest buffered record, as reported by the server.
+    pub begin_time: Option<String>,
+    /// End time of the newest buffered record, as reported by the server.
+    pub end_time: Option<String>,
+}
+
+/// Reassemble the XML document carried by a sequence of INFO frames.
+///
+/// Thin wrapper over [`InfoDocument::from_frames()`].
+pub fn reassemble_xml(frames: &[OwnedFrame]) -> String {
+    InfoDocument::from_frames(frames.iter().map(OwnedFrame::as_raw_frame)).into_string()
+}
+
+/// Decode the document carried by INFO frames as JSON.
+///
+/// v4 servers may reply with a JSON payload, which is parsed as is. XML
+/// replies are converted: each element becomes an object holding its
+/// attributes as strings, and child elements are collected into arrays keyed
+/// by tag name. The document element's object is returned, so
+/// `<seedlink><station name="ANMO"/></seedlink>` becomes
+/// `{"station": [{"name": "ANMO"}]}`.
+pub fn info_json(frames: &[OwnedFrame]) -> Result<Value, serde_json::Error> {
+    let doc = reassemble_xml(frames);
+    let is_json = frames.iter().any(|f| {
+        matches!(
+            f,
+            OwnedFrame::V4 {
+                format: PayloadFormat::Json,
+                ..
+            }
+        )
+    });
+    if is_json {
+        serde_json::from_str(&doc)
+    } else {
+        Ok(xml_to_json(&doc))
+    }
+}
+
+/// Parse the `<station>` elements of an `INFO STATIONS` document.
+///
+/// Elements missing a `name` or `network` attribute are skipped.
+pub fn parse_stations_xml(xml: &str) -> Vec<StationInfo> {
+    elements(xml)
+        .filter(|e| e.name == "station")
+        .filter_map(|e| {
+            Some(StationInfo {
+                network: e.attr("network")?,
+                station: e.attr("name")?,
+                description: e.attr("description").unwrap_or_default(),
+                begin_seq: e.attr("begin_seq").as_deref().and_then(parse_seq),
+                end_seq: e.attr("end_seq").as_deref().and_then(parse_seq),
+            })
+        })
+        .collect()
+}
+
+/// Parse the `<stream>` elements of an `INFO STREAMS` document.
+///
+/// Each stream inherits network/station from its enclosing `<station>`
+/// element. Streams outside any station, or without a `seedname`, are skipped.
+pub fn parse_streams_xml(xml: &str) -> Vec<StreamInfo> {
+    let mut streams = Vec::new();
+    let mut current: Option<(String, String)> = None;
+
+    for e in elements(xml) {
+        match e.name {
+            "station" if e.closing => current = None,
+            "station" => {
+                current = match (e.attr("network"), e.attr("name")) {
+                    (Some(net), Some(sta)) if !e.self_closing => Some((net, sta)),
+                    _ => None,
+                };
+            }
+            "stream" if !e.closing => {
+                let Some((network, station)) = current.clone() else {
+                    continue;
+                };
+                let Some(channel) = e.attr("seedname") else {
+                    continue;
+                };
+                streams.push(StreamInfo {
+                    network,
+                    station,
+                    location: e.attr("location").unwrap_or_default(),
+                    channel,
+                    type_code: e.attr("type").unwrap_or_default(),
+                    begin_seq: e.attr("begin_seq").as_deref().and_then(parse_seq),
+                    end_seq: e.attr("end_seq").as_deref().and_then(parse_seq),
+                    begin_time: e.attr("begin_time"),
+                    end_time: e.attr("end_time"),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    streams
+}
+
+/// Convert an XML document to JSON as described in [`info_json`].
+///
+/// Unclosed elements (a truncated reply) are closed at the end of input.
+fn xml_to_json(xml: &str) -> Value {
+    let mut open: Vec<(&str, Map<String, Value>)> = Vec::new();
+    let mut root = None;
+    for e in elements(xml) {
+        if e.closing {
+            if let Some((name, object)) = open.pop() {
+                attach(&mut open, &mut root, name, object);
+            }
+            continue;
+        }
+        let object = e
+            .attributes()
+            .map(|(k, v)| (k.to_owned(), Value::String(v)))
+            .collect();
+        if e.self_closing {
+            attach(&mut open, &mut root, e.name, object);
+        } else {
+            open.push((e.name, object));
+        }
+    }
+    while let Some((name, object)) = open.pop() {
+        attach(&mut open, &mut root, name, object);
+    }
+
+    root.map_or(Value::Null, Value::Object)
+}
+
+/// Add a finished element to its parent's array for `name`, or make it the
+/// root if no element is open.
+fn attach(
+    open: &mut [(&str, Map<String, Value>)],
+    root: &mut Option<Map<String, Value>>,
+    name: &str,
+    object: Map<String, Value>,
+) {
+    match open.last_mut() {
+        Some((_, parent)) => {
+            let children = parent
+                .entry(name)
+                .or_insert_with(|| Value::Array(Vec::new()));
+            if let Value::Array(children) = children {
+                children.push(Value::Object(object));
+            }
+        }
+        None => {
+            root.get_or_insert(object);
+        }
+    }
+}
+
+/// Parse a sequence attribute: 6 hex digits (v3) or decimal (v4).
+fn parse_seq(s: &str) -> Option<SequenceNumber> {
+    if s.len() == 6 && s.chars().all(|c| c.is_ascii_hexdigit()) {
+        SequenceNumber::from_v3_hex(s).ok()
+    } else {
+        SequenceNumber::from_v4_decimal(s).ok()
+    }
+}
+
+/// A single XML tag with its raw attribute text.
+struct Element<'a> {
+    name: &'a str,
+    attrs: &'a str,
+    closing: bool,
+    self_closing: bool,
+}
+
+impl<'a> Element<'a> {
+    /// Look up an attribute value by name, unescaping XML entities.
+    fn attr(&self, key: &str) -> Option<String> {
+        self.attributes()
+            .find(|(name, _)| *name == key)
+            .map(|(_, value)| value)
+    }
+
+    /// Iterate over `name="value"` attributes, unescaping XML entities.
+    fn attributes(&self) -> impl Iterator<Item = (&'a str, String)> + use<'a> {
+        let mut rest = self.attrs;
+        std::iter::from_fn(move || {
+            let eq = rest.find('=')?;
+            let name = rest[..eq].trim();
+            let after = rest[eq + 1..].trim_start();
+            let quote = after.chars().next()?;
+            if quote != '"' && quote != '\'' {
+                return None;
+            }
+            let close = after[1..].find(quote)?;
+            let value = &after[1..1 + close];
+            rest = &after[close + 2..];
+            Some((name, xml_unescape(value)))
+        })
+    }
+}
+
+/// Iterate over the tags of an XML document, skipping declarations and comments.
+fn elements(xml: &str) -> impl Iterator<Item = Element<'_>> {
+    let mut rest = xml;
+    std::iter::from_fn(move || {
+        loop {
+            let start = rest.find('<')?;
+            let end = start + rest[start..].find('>')?;
+            let tag = &rest[start + 1..end];
+            rest = &rest[end + 1..];
+
+            if tag.starts_with('?') || tag.starts_with('!') {
+                continue;
+            }
+
+            let (closing, tag) = match tag.strip_prefix('/') {
+                Some(t) => (true, t),
+                None => (false, tag),
+            };
+            let (self_closing, tag) = match tag.strip_suffix('/') {
+                Some(t) => (true, t),
+                None => (false, tag),
+            };
+            let name_end = tag
+                .find(|c: char| c.is_ascii_whitespace())
+                .unwrap_or(tag.len());
+
+            return Some(Element {
+                name: &tag[..name_end],
+                attrs: &tag[name_end..],
+                closing,
+                self_closing,
+            });
+        }
+    })
+}
+
+/// Reverse the escaping applied to XML attribute values.
+fn xml_unescape(s: &str) -> String {
+    if !s.contains('&') {
+        return s.to_owned();
+    }
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use seedlink_rs_protocol::frame::v3;
+
+    const STATIONS_XML: &str = "<?xml version=\"1.0\"?>\n<seedlink>\n  \
+        <station name=\"ANMO\" network=\"IU\" description=\"Albuquerque &amp; NM\" begin_seq=\"000001\" end_seq=\"00000A\" stream_check=\"enabled\"/>\n  \
+        <station name=\"WLF\" network=\"GE\" description=\"\" begin_seq=\"000002\" end_seq=\"000003\" stream_check=\"enabled\"/>\n\
+        </seedlink>\n";
+
+    const STREAMS_XML: &str = "<?xml version=\"1.0\"?>\n<seedlink>\n  \
+        <station name=\"ANMO\" network=\"IU\">\n    \
+        <stream seedname=\"BHZ\" location=\"00\" type=\"D\" begin_seq=\"000001\" end_seq=\"000003\"/>\n    \
+        <stream seedname=\"BHN\" location=\"00\" type=\"D\" begin_seq=\"000002\" end_seq=\"000004\" begin_time=\"2024/01/15 00:00:00.0000\"/>\n  \
+        </station>\n  \
+        <station name=\"WLF\" network=\"GE\">\n    \
+        <stream seedname=\"HHZ\" location=\"\" type=\"D\" begin_seq=\"000005\" end_seq=\"000005\"/>\n  \
+        </station>\n\
+        </seedlink>\n";
+
+    fn chunked_frames(xml: &str) -> Vec<OwnedFrame> {
+        xml.as_bytes()
+            .chunks(v3::PAYLOAD_LEN)
+            .map(|chunk| {
+                let mut payload = vec![0u8; v3::PAYLOAD_LEN];
+                payload[..chunk.len()].copy_from_slice(chunk);
+                OwnedFrame::V3 {
+                    sequence: SequenceNumber::new(0),
+                    payload,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parse_stations() {
+        let stations = parse_stations_xml(STATIONS_XML);
+        assert_eq!(stations.len(), 2);
+        assert_eq!(stations[0].network, "IU");
+        assert_eq!(stations[0].station, "ANMO");
+        assert_eq!(stations[0].description, "Albuquerque & NM");
+        assert_eq!(stations[0].begin_seq, Some(SequenceNumber::new(1)));
+        assert_eq!(stations[0].end_seq, Some(SequenceNumber::new(10)));
+        assert_eq!(stations[1].station, "WLF");
+    }
+
+    #[test]
+    fn parse_streams_grouped_by_station() {
+        let streams = parse_streams_xml(STREAMS_XML);
+        assert_eq!(streams.len(), 3);
+        assert_eq!(streams[0].station, "ANMO");
+        assert_eq!(streams[0].channel, "BHZ");
+        assert_eq!(streams[0].location, "00");
+        assert_eq!(streams[0].type_code, "D");
+        assert_eq!(streams[0].begin_time, None);
+        assert_eq!(
+            streams[1].begin_time.as_deref(),
+            Some("2024/01/15 00:00:00.0000")
+        );
+        assert_eq!(streams[2].network, "GE");
+        assert_eq!(streams[2].station, "WLF");
+        assert_eq!(streams[2].channel, "HHZ");
+        assert_eq!(streams[2].end_seq, Some(SequenceNumber::new(5)));
+    }
+
+    #[test]
+    fn parse_decimal_sequences() {
+        let xml = "<seedlink><station name=\"ANMO\" network=\"IU\" begin_seq=\"12345678\" end_seq=\"12345679\"/></seedlink>";
+        let stations = parse_stations_xml(xml);
+        assert_eq!(stations[0].begin_seq, Some(SequenceNumber::new(12_345_678)));
+    }
+
+    #[test]
+    fn info_json_converts_xml() {
+        let value = info_json(&chunked_frames(STREAMS_XML)).unwrap();
+        let stations = value["station"].as_array().unwrap();
+        assert_eq!(stations.len(), 2);
+        assert_eq!(stations[0]["name"], "ANMO");
+        assert_eq!(stations[0]["stream"][1]["seedname"], "BHN");
+        assert_eq!(
+            stations[0]["stream"][1]["begin_time"],
+            "2024/01/15 00:00:00.0000"
+        );
+        assert_eq!(stations[1]["stream"][0]["location"], "");
+
+        let value = info_json(&chunked_frames(STATIONS_XML)).unwrap();
+        assert_eq!(value["station"][0]["description"], "Albuquerque & NM");
+
+        // Truncated document: open elements are closed at end of input
+        let value = xml_to_json("<seedlink><station name=\"ANMO\"><stream seedname=\"BHZ\"/>");
+        assert_eq!(value["station"][0]["stream"][0]["seedname"], "BHZ");
+        assert_eq!(xml_to_json(""), Value::Null);
+    }
+
+    #[test]
+    fn info_json_parses_json_payload() {
+        let frame = |payload: &str| OwnedFrame::V4 {
+            format: PayloadFormat::Json,
+            subformat: seedlink_rs_protocol::PayloadSubformat::Info,
+            sequence: SequenceNumber::UNSET,
+            station_id: String::new(),
+            payload: payload.as_bytes().to_vec(),
+        };
+        let value = info_json(&[frame(r#"{"station":[{"id":"IU_ANMO"}]}"#)]).unwrap();
+        assert_eq!(value["station"][0]["id"], "IU_ANMO");
+        assert!(info_json(&[frame("{not json")]).is_err());
+    }
+
+    #[test]
+    fn reassemble_multi_frame() {
+        let mut xml = String::from("<?xml version=\"1.0\"?>\n<seedlink>\n");
+        for i in 0..20 {
+            xml.push_str(&format!(
+                "  <station name=\"S{i:03}\" network=\"XX\" description=\"\" begin_seq=\"000001\" end_seq=\"000002\"/>\n"
+            ));
+        }
+        xml.push_str("</seedlink>\n");
+
+        let frames = chunked_frames(&xml);
+        assert!(frames.len() > 1);
+        assert_eq!(reassemble_xml(&frames), xml);
+        assert_eq!(parse_stations_xml(&reassemble_xml(&frames)).len(), 20);
+    }
+
+    #[test]
+    fn reassemble_mseed_wrapped_payload() {
+        let text = b"<seedlink><station name=\"ANMO\" network=\"IU\"/></seedlink>";
+        let mut payload = vec![0u8; v3::PAYLOAD_LEN];
+        payload[..6].copy_from_slice(b"000001");
+        payload[6] = b'D';
+        payload[30..32].copy_from_slice(&(text.len() as u16).to_be_bytes());
+        payload[44..46].copy_from_slice(&56u16.to_be_bytes());
+        payload[56..56 + text.len()].copy_from_slice(text);
+
+        let frames = vec![OwnedFrame::V3 {
+            sequence: SequenceNumber::new(0),
+            payload,
+        }];
+        let stations = parse_stations_xml(&reassemble_xml(&frames));
+        assert_eq!(stations.len(), 1);
+        assert_eq!(stations[0].station, "ANMO");
+    }
+
+    #[test]
+    fn parse_skips_incomplete_elements() {
+        let xml = "<seedlink><station network=\"IU\"/><stream seedname=\"BHZ\"/></seedlink>";
+        assert!(parse_stations_xml(xml).is_empty());
+        assert!(parse_streams_xml(xml).is_empty());
+    }
+}
@@ -21,21 +21,68 @@
 //! # Ok(())
 //! # }
 //! ```
+//!
+//! With the `blocking` feature, [`blocking::SeedLinkClient`] offers the same
+//! calls without `async`, for programs that don't run a tokio runtime.
+//!
+//! With the `datalink` feature, [`DataLinkSink`] forwards received records to
+//! a DataLink server such as ringserver, like `slink2dali`.
+//!
+//! With the `backfill` feature, [`Backfill`] fills detected gaps with data
+//! from an FDSN dataselect web service.
 
+pub(crate) mod archive;
+#[cfg(feature = "backfill")]
+pub(crate) mod backfill;
+pub(crate) mod batch;
+#[cfg(feature = "blocking")]
+pub mod blocking;
 pub(crate) mod client;
 pub(crate) mod connection;
+#[cfg(feature = "datalink")]
+pub(crate) mod datalink;
+pub(crate) mod demux;
+pub(crate) mod dialup;
 pub(crate) mod error;
-#[cfg(test)]
-pub(crate) mod mock;
-pub(crate) mod negotiate;
+pub(crate) mod gaps;
+pub(crate) mod header;
+pub(crate) mod hooks;
+pub(crate) mod info;
+#[cfg(any(test, feature = "test-util"))]
+pub mod mock;
+pub(crate) mod ordered;
+pub(crate) mod proxy;
 pub(crate) mod reconnect;
+pub(crate) mod samples;
+pub(crate) mod socket;
 pub(crate) mod state;
+pub(crate) mod statefile;
 pub(crate) mod stream;
 
+pub use archive::{ArchiveLayout, MseedArchiver};
+#[cfg(feature = "backfill")]
+pub use backfill::{Backfill, BackfillConfig};
+pub use batch::SubscriptionBuilder;
 pub use client::SeedLinkClient;
+#[cfg(feature = "datalink")]
+pub use datalink::{DataLinkConfig, DataLinkSink};
+pub use demux::DemuxedStreams;
+pub use dialup::{DialupCollector, DialupConfig, Subscription};
 pub use error::{ClientError, Result};
 pub use futures_core::Stream;
-pub use reconnect::{ReconnectConfig, ReconnectingClient};
-pub use seedlink_rs_protocol::DataFrame;
-pub use state::{ClientConfig, ClientState, OwnedFrame, ServerInfo, StationKey};
-pub use stream::frame_stream;
+pub use gaps::{GapDetector, GapEvent, GapKind};
+pub use info::{
+    StationInfo, StreamInfo, info_json, parse_stations_xml, parse_streams_xml, reassemble_xml,
+};
+pub use ordered::{OrderedFrame, OrderedFrameStream};
+pub use proxy::{ProxyConfig, ProxyKind};
+pub use reconnect::{ReconnectConfig, ReconnectEvent, ReconnectingClient};
+pub use samples::{SampleData, SampleRecord};
+pub use seedlink_rs_protocol::{Capabilities, DataFrame, ErrorKind, InvalidRecord, ValidationMode};
+pub use socket::{KeepaliveConfig, SocketOptions};
+pub use state::{
+    ClientConfig, ClientState, OwnedFrame, ProtocolPreference, ResumeGap, ServerInfo, StationKey,
+    StreamEvent,
+};
+pub use statefile::SequenceState;
+pub use stream::{StopCondition, UndecodableFrames, event_stream, frame_stream};
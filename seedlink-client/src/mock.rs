@@ -1,26 +1,93 @@
+//! Scriptable SeedLink server for integration tests (`test-util` feature).
+//!
+//! [`MockServer`] listens on a random localhost port and plays a minimal
+//! SeedLink server: HELLO, optional `SLPROTO 4.0`, `OK` to subscription
+//! commands, and a fixed list of raw frames on `END`/`FETCH`/`INFO`. Every
+//! received command is captured for inspection. [`MockConfig::on()`] scripts
+//! per-command behavior, and [`MockConfig::frame_delay`] /
+//! [`MockConfig::disconnect_after`] shape the stream, so error paths such as
+//! server errors, timeouts, dropped connections and corrupt frames can be
+//! tested without a real server.
+//!
+//! ```
+//! # async fn example() -> seedlink_rs_client::Result<()> {
+//! use seedlink_rs_client::SeedLinkClient;
+//! use seedlink_rs_client::mock::{MockAction, MockConfig, MockServer};
+//!
+//! let config = MockConfig::v3_default(vec![])
+//!     .on("STATION XX", vec![MockAction::reply("ERROR\r\n")]);
+//! let server = MockServer::start(config).await;
+//!
+//! let mut client = SeedLinkClient::connect(&server.addr().to_string()).await?;
+//! assert!(client.station("XX", "NET").await.is_err());
+//! assert_eq!(server.captured().connection(0), ["HELLO", "STATION XX NET"]);
+//! # Ok(())
+//! # }
+//! ```
+
 use std::net::SocketAddr;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use seedlink_rs_protocol::ProtocolVersion;
+use seedlink_rs_protocol::frame::v3;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::TcpListener;
+use tokio::net::tcp::OwnedWriteHalf;
 
+/// Behavior of a [`MockServer`].
+#[derive(Clone, Debug)]
 pub struct MockConfig {
-    #[allow(dead_code)]
+    /// Protocol version the mock is set up for (informational).
     pub version: ProtocolVersion,
+    /// First HELLO line: software, version and capabilities.
     pub hello_line1: String,
+    /// Second HELLO line: organization.
     pub hello_line2: String,
+    /// Raw bytes streamed on `END`/`FETCH`/`ENDFETCH` and sent for `INFO`,
+    /// one entry per frame. Entries need not be valid frames.
     pub frames: Vec<Vec<u8>>,
     /// Per-connection frame overrides. When set, `connection_frames[i]` is used
     /// for connection `i`; connections beyond the list fall back to `frames`.
     pub connection_frames: Option<Vec<Vec<Vec<u8>>>>,
+    /// Reply `OK` to `SLPROTO` (v4); otherwise `ERROR UNSUPPORTED`.
     pub accept_slproto: bool,
+    /// Close the connection after streaming on `END`/`FETCH`.
     pub close_after_stream: bool,
     /// How many sequential connections to accept. Default: 1.
     pub max_connections: usize,
+    /// Pause before each streamed frame. Default: none.
+    pub frame_delay: Duration,
+    /// Close the connection after this many frames of a stream, without
+    /// finishing it. Default: `None`.
+    pub disconnect_after: Option<usize>,
+    /// Scripted commands, checked in order before the built-in handling.
+    /// Added with [`on()`](Self::on).
+    pub script: Vec<(String, Vec<MockAction>)>,
+}
+
+/// One step of a scripted reply, see [`MockConfig::on()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MockAction {
+    /// Write these bytes as they are: a response line, a frame, or garbage.
+    Reply(Vec<u8>),
+    /// Wait before the next step.
+    Delay(Duration),
+    /// Close the connection.
+    Disconnect,
+    /// Run the built-in handling of the command after the other steps.
+    Default,
+}
+
+impl MockAction {
+    /// [`Reply`](Self::Reply) from a string or byte slice.
+    pub fn reply(bytes: impl AsRef<[u8]>) -> Self {
+        Self::Reply(bytes.as_ref().to_vec())
+    }
 }
 
 impl MockConfig {
+    /// A v3.1 server without capabilities.
     pub fn v3_default(frames: Vec<Vec<u8>>) -> Self {
         Self {
             version: ProtocolVersion::V3,
@@ -31,9 +98,13 @@ impl MockConfig {
             accept_slproto: false,
             close_after_stream: false,
             max_connections: 1,
+            frame_delay: Duration::ZERO,
+            disconnect_after: None,
+            script: Vec::new(),
         }
     }
 
+    /// A server advertising and accepting `SLPROTO:4.0`.
     pub fn v4_default(frames: Vec<Vec<u8>>) -> Self {
         Self {
             version: ProtocolVersion::V4,
@@ -44,12 +115,38 @@ impl MockConfig {
             accept_slproto: true,
             close_after_stream: false,
             max_connections: 1,
+            frame_delay: Duration::ZERO,
+            disconnect_after: None,
+            script: Vec::new(),
         }
     }
+
+    /// Script the reply to commands starting with `command`, matched on
+    /// whole words and case-insensitively: `"DATA"` matches `DATA` and
+    /// `DATA 00001A` but not `DATAX`. The first matching rule wins and
+    /// replaces the built-in handling unless it includes
+    /// [`MockAction::Default`].
+    pub fn on(mut self, command: &str, actions: Vec<MockAction>) -> Self {
+        self.script.push((command.trim().to_uppercase(), actions));
+        self
+    }
+
+    fn scripted(&self, command: &str) -> Option<&[MockAction]> {
+        self.script
+            .iter()
+            .find(|(prefix, _)| {
+                command
+                    .strip_prefix(prefix.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with(' '))
+            })
+            .map(|(_, actions)| actions.as_slice())
+    }
 }
 
 /// Captured commands from all connections, grouped per connection index.
-#[derive(Clone, Default)]
+///
+/// Commands are trimmed and uppercased.
+#[derive(Clone, Debug, Default)]
 pub struct CapturedCommands(Arc<Mutex<Vec<Vec<String>>>>);
 
 impl CapturedCommands {
@@ -65,6 +162,11 @@ impl CapturedCommands {
         guard.get(idx).cloned().unwrap_or_default()
     }
 
+    /// Number of connections accepted so far.
+    pub fn connections(&self) -> usize {
+        self.0.lock().unwrap().len()
+    }
+
     fn start_connection(&self) {
         self.0.lock().unwrap().push(Vec::new());
     }
@@ -77,12 +179,19 @@ impl CapturedCommands {
     }
 }
 
+/// A scripted SeedLink server on `127.0.0.1`, running until the runtime
+/// shuts down or [`MockConfig::max_connections`] connections were served.
 pub struct MockServer {
     addr: SocketAddr,
     captured: CapturedCommands,
 }
 
 impl MockServer {
+    /// Bind a random localhost port and start serving in the background.
+    ///
+    /// # Panics
+    ///
+    /// If the port cannot be bound.
     pub async fn start(config: MockConfig) -> Self {
         let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
         let addr = listener.local_addr().unwrap();
@@ -96,6 +205,7 @@ impl MockServer {
         Self { addr, captured }
     }
 
+    /// Address to connect to.
     pub fn addr(&self) -> SocketAddr {
         self.addr
     }
@@ -129,7 +239,7 @@ impl MockServer {
         captured: &CapturedCommands,
         conn_idx: usize,
     ) {
-        let (read_half, mut write_half) = stream.into_split();
+        let (read_half, write_half) = stream.into_split();
         let mut reader = BufReader::new(read_half);
         let mut line = String::new();
 
@@ -138,6 +248,13 @@ impl MockServer {
             .as_ref()
             .and_then(|cf| cf.get(conn_idx))
             .unwrap_or(&config.frames);
+        let mut session = Session {
+            write_half,
+            config,
+            frames,
+            batch: false,
+            v4: false,
+        };
 
         loop {
             line.clear();
@@ -152,61 +269,243 @@ impl MockServer {
             let trimmed = line.trim().to_uppercase();
             captured.push(trimmed.clone());
 
-            if trimmed == "HELLO" {
-                let response = format!("{}\r\n{}\r\n", config.hello_line1, config.hello_line2);
-                if write_half.write_all(response.as_bytes()).await.is_err() {
-                    break;
-                }
-                let _ = write_half.flush().await;
-            } else if trimmed.starts_with("SLPROTO") {
-                if config.accept_slproto {
-                    if write_half.write_all(b"OK\r\n").await.is_err() {
-                        break;
-                    }
-                } else if write_half
-                    .write_all(b"ERROR UNSUPPORTED unsupported command\r\n")
-                    .await
-                    .is_err()
-                {
-                    break;
-                }
-                let _ = write_half.flush().await;
-            } else if trimmed.starts_with("STATION")
-                || trimmed.starts_with("SELECT")
-                || trimmed == "DATA"
-                || trimmed.starts_with("DATA ")
-                || trimmed.starts_with("TIME ")
-            {
-                // All servers reply OK to STATION/SELECT/DATA (EXTREPLY behavior)
-                if write_half.write_all(b"OK\r\n").await.is_err() {
-                    break;
-                }
-                let _ = write_half.flush().await;
-            } else if trimmed == "END" || trimmed == "FETCH" || trimmed.starts_with("FETCH ") {
-                // END/FETCH triggers streaming — no text response, just send frames
-                for frame in frames {
-                    if write_half.write_all(frame).await.is_err() {
-                        break;
-                    }
-                }
-                let _ = write_half.flush().await;
-                if config.close_after_stream {
-                    break;
-                }
-            } else if trimmed.starts_with("INFO") {
-                for frame in frames {
-                    if write_half.write_all(frame).await.is_err() {
-                        break;
+            let keep_open = match config.scripted(&trimmed) {
+                Some(actions) => session.run_script(actions, &trimmed).await,
+                None => session.handle(&trimmed).await,
+            };
+            if !keep_open {
+                break;
+            }
+        }
+    }
+}
+
+/// State of one mock connection.
+struct Session<'a> {
+    write_half: OwnedWriteHalf,
+    config: &'a MockConfig,
+    frames: &'a [Vec<u8>],
+    batch: bool,
+    v4: bool,
+}
+
+impl Session<'_> {
+    /// Run scripted actions. Returns `false` when the connection should close.
+    async fn run_script(&mut self, actions: &[MockAction], cmd: &str) -> bool {
+        let mut run_default = false;
+        for action in actions {
+            match action {
+                MockAction::Reply(bytes) => {
+                    if !self.write(bytes).await {
+                        return false;
                     }
                 }
-                if write_half.write_all(b"END\r\n").await.is_err() {
-                    break;
+                MockAction::Delay(delay) => tokio::time::sleep(*delay).await,
+                MockAction::Disconnect => {
+                    let _ = self.write_half.shutdown().await;
+                    return false;
                 }
-                let _ = write_half.flush().await;
-            } else if trimmed == "BYE" {
-                let _ = write_half.shutdown().await;
-                break;
+                MockAction::Default => run_default = true,
             }
         }
+        !run_default || self.handle(cmd).await
+    }
+
+    /// Built-in handling of a command. Returns `false` when the connection
+    /// should close.
+    async fn handle(&mut self, cmd: &str) -> bool {
+        if cmd == "HELLO" {
+            let response = format!(
+                "{}\r\n{}\r\n",
+                self.config.hello_line1, self.config.hello_line2
+            );
+            self.write(response.as_bytes()).await
+        } else if let Some(version) = cmd.strip_prefix("SLPROTO") {
+            if self.config.accept_slproto {
+                self.v4 = version.trim_start().starts_with('4');
+                self.write(b"OK\r\n").await
+            } else {
+                self.write(b"ERROR UNSUPPORTED unsupported command\r\n")
+                    .await
+            }
+        } else if cmd.starts_with("STATION")
+            || cmd.starts_with("SELECT")
+            || cmd == "DATA"
+            || cmd.starts_with("DATA ")
+            || cmd.starts_with("TIME ")
+        {
+            // BATCH mode suppresses per-command replies
+            if self.batch {
+                return true;
+            }
+            // All servers reply OK to STATION/SELECT/DATA (EXTREPLY behavior)
+            self.write(b"OK\r\n").await
+        } else if self.v4 && cmd == "FETCH" {
+            // v4 dial-up FETCH: frames, END marker, connection stays open
+            self.send_frames().await && self.write(b"END\r\n").await
+        } else if cmd == "ENDFETCH" {
+            // Final dial-up fetch: frames, then close
+            self.send_frames().await;
+            false
+        } else if cmd == "END" || cmd == "FETCH" || cmd.starts_with("FETCH ") {
+            // END/FETCH triggers streaming — no text response, just send frames
+            self.send_frames().await && !self.config.close_after_stream
+        } else if cmd.starts_with("INFO") {
+            // A v3 reply ending in an "INFO  " frame needs no END
+            let terminated = self
+                .frames
+                .last()
+                .is_some_and(|frame| v3::info_continues(frame) == Some(false));
+            self.send_frames().await && (terminated || self.write(b"END\r\n").await)
+        } else if cmd.starts_with("USERAGENT") || cmd.starts_with("CAPABILITIES") {
+            self.write(b"OK\r\n").await
+        } else if cmd == "BATCH" {
+            self.batch = true;
+            self.write(b"OK\r\n").await
+        } else if cmd == "BYE" {
+            let _ = self.write_half.shutdown().await;
+            false
+        } else {
+            true
+        }
+    }
+
+    /// Stream the connection's frames, honoring `frame_delay` and
+    /// `disconnect_after`. Returns `false` when the connection should close.
+    async fn send_frames(&mut self) -> bool {
+        for (i, frame) in self.frames.iter().enumerate() {
+            if self.config.disconnect_after == Some(i) {
+                let _ = self.write_half.shutdown().await;
+                return false;
+            }
+            if !self.config.frame_delay.is_zero() {
+                tokio::time::sleep(self.config.frame_delay).await;
+            }
+            if !self.write(frame).await {
+                return false;
+            }
+        }
+        if self.config.disconnect_after == Some(self.frames.len()) {
+            let _ = self.write_half.shutdown().await;
+            return false;
+        }
+        true
+    }
+
+    async fn write(&mut self, bytes: &[u8]) -> bool {
+        self.write_half.write_all(bytes).await.is_ok() && self.write_half.flush().await.is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ClientConfig, ClientError, SeedLinkClient};
+    use seedlink_rs_protocol::SequenceNumber;
+    use seedlink_rs_protocol::frame::v3;
+
+    fn v3_frame(seq: u64) -> Vec<u8> {
+        v3::write(SequenceNumber::new(seq), &[0u8; v3::PAYLOAD_LEN]).unwrap()
+    }
+
+    #[tokio::test]
+    async fn scripted_error_reply_and_default() {
+        let config = MockConfig::v3_default(vec![])
+            .on("station xx", vec![MockAction::reply("ERROR\r\n")])
+            .on(
+                "SELECT",
+                vec![
+                    MockAction::Delay(Duration::from_millis(10)),
+                    MockAction::Default,
+                ],
+            );
+        let server = MockServer::start(config).await;
+        let mut client = SeedLinkClient::connect(&server.addr().to_string())
+            .await
+            .unwrap();
+
+        let err = client.station("XX", "NET").await.unwrap_err();
+        assert!(matches!(err, ClientError::ServerError(_)));
+        // "STATION XX" does not match "STATION XXX"
+        client.station("XXX", "NET").await.unwrap();
+        client.select("BHZ").await.unwrap();
+        assert_eq!(
+            server.captured().connection(0),
+            ["HELLO", "STATION XX NET", "STATION XXX NET", "SELECT BHZ"]
+        );
+        assert_eq!(server.captured().connections(), 1);
+    }
+
+    #[tokio::test]
+    async fn delayed_reply_times_out() {
+        let config = MockConfig::v3_default(vec![]).on(
+            "STATION",
+            vec![
+                MockAction::Delay(Duration::from_secs(5)),
+                MockAction::Default,
+            ],
+        );
+        let server = MockServer::start(config).await;
+        let client_config = ClientConfig {
+            read_timeout: Duration::from_millis(50),
+            ..ClientConfig::default()
+        };
+        let mut client =
+            SeedLinkClient::connect_with_config(&server.addr().to_string(), client_config)
+                .await
+                .unwrap();
+
+        let err = client.station("ANMO", "IU").await.unwrap_err();
+        assert!(matches!(err, ClientError::Timeout(_)));
+    }
+
+    #[tokio::test]
+    async fn disconnect_mid_stream() {
+        let config = MockConfig {
+            frame_delay: Duration::from_millis(1),
+            disconnect_after: Some(2),
+            ..MockConfig::v3_default(vec![v3_frame(1), v3_frame(2), v3_frame(3)])
+        };
+        let server = MockServer::start(config).await;
+        let mut client = SeedLinkClient::connect(&server.addr().to_string())
+            .await
+            .unwrap();
+        client.station("ANMO", "IU").await.unwrap();
+        client.data().await.unwrap();
+        client.end_stream().await.unwrap();
+
+        let mut seqs = Vec::new();
+        while let Some(frame) = client.next_frame().await.unwrap() {
+            seqs.push(frame.sequence().value());
+        }
+        assert_eq!(seqs, [1, 2]);
+    }
+
+    #[tokio::test]
+    async fn malformed_frame_is_a_protocol_error() {
+        let mut corrupt = v3_frame(2);
+        corrupt[2..8].copy_from_slice(b"ZZZZZZ");
+        let server = MockServer::start(MockConfig::v3_default(vec![v3_frame(1), corrupt])).await;
+        let mut client = SeedLinkClient::connect(&server.addr().to_string())
+            .await
+            .unwrap();
+        client.station("ANMO", "IU").await.unwrap();
+        client.data().await.unwrap();
+        client.end_stream().await.unwrap();
+
+        assert!(client.next_frame().await.unwrap().is_some());
+        let err = client.next_frame().await.unwrap_err();
+        assert!(matches!(err, ClientError::Protocol(_)));
+    }
+
+    #[tokio::test]
+    async fn scripted_disconnect() {
+        let config = MockConfig::v3_default(vec![]).on("DATA", vec![MockAction::Disconnect]);
+        let server = MockServer::start(config).await;
+        let mut client = SeedLinkClient::connect(&server.addr().to_string())
+            .await
+            .unwrap();
+        client.station("ANMO", "IU").await.unwrap();
+        assert!(client.data().await.is_err());
     }
 }
@@ -0,0 +1,327 @@
+//! Reordering of frames by record start time.
+//!
+//! [`OrderedFrameStream`] holds each frame for a fixed delay and releases
+//! frames per channel in start-time order, so consumers that cannot handle
+//! out-of-order samples see each channel as a monotonic sequence of records.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::{Duration, SystemTime};
+
+use futures_core::Stream;
+use tokio::time::{Instant, Sleep};
+
+use crate::error::Result;
+use crate::state::OwnedFrame;
+
+/// A frame released by [`OrderedFrameStream`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OrderedFrame {
+    /// The frame as received.
+    pub frame: OwnedFrame,
+    /// The record starts before a record already released for its channel,
+    /// i.e. it arrived after the reorder delay had passed.
+    pub late: bool,
+}
+
+/// Buffered channel: held records keyed by (start time, arrival counter).
+#[derive(Default)]
+struct Channel {
+    pending: BTreeMap<(SystemTime, u64), OwnedFrame>,
+    last_released: Option<SystemTime>,
+}
+
+/// A [`Stream`] adapter that emits frames ordered by record start time per
+/// channel (NSLC).
+///
+/// Each frame is held for `delay` after it arrives. When the delay of a held
+/// frame expires, it is released together with every held frame of the same
+/// channel that starts earlier. A frame starting before a record already
+/// released for its channel is passed on at once with
+/// [`late`](OrderedFrame::late) set. Frames whose payload cannot be decoded
+/// as miniSEED are passed on at once, unflagged.
+///
+/// When the inner stream yields an error or ends, held frames are released
+/// first. Must be polled within a tokio runtime.
+///
+/// ```no_run
+/// # async fn example() -> seedlink_rs_client::Result<()> {
+/// use std::time::Duration;
+/// use seedlink_rs_client::{OrderedFrameStream, SeedLinkClient};
+/// use tokio_stream::StreamExt;
+///
+/// let mut client = SeedLinkClient::connect("rtserve.iris.washington.edu:18000").await?;
+/// client.station("ANMO", "IU").await?;
+/// client.data().await?;
+/// client.end_stream().await?;
+///
+/// let mut frames = client.into_ordered_stream(Duration::from_secs(5));
+/// while let Some(ordered) = frames.next().await {
+///     let ordered = ordered?;
+///     if ordered.late {
+///         println!("late record seq={}", ordered.frame.sequence());
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct OrderedFrameStream<S> {
+    inner: Option<Pin<Box<S>>>,
+    delay: Duration,
+    channels: HashMap<String, Channel>,
+    /// Held frames in arrival order; entries already released are skipped.
+    arrivals: VecDeque<(Instant, String, (SystemTime, u64))>,
+    ready: VecDeque<OrderedFrame>,
+    error: Option<crate::ClientError>,
+    timer: Option<Pin<Box<Sleep>>>,
+    received: u64,
+}
+
+impl<S> OrderedFrameStream<S>
+where
+    S: Stream<Item = Result<OwnedFrame>>,
+{
+    /// Wrap `frames`, holding each frame for `delay` before release.
+    pub fn new(frames: S, delay: Duration) -> Self {
+        Self {
+            inner: Some(Box::pin(frames)),
+            delay,
+            channels: HashMap::new(),
+            arrivals: VecDeque::new(),
+            ready: VecDeque::new(),
+            error: None,
+            timer: None,
+            received: 0,
+        }
+    }
+
+    /// Number of frames currently held.
+    pub fn held(&self) -> usize {
+        self.channels.values().map(|c| c.pending.len()).sum()
+    }
+
+    fn accept(&mut self, frame: OwnedFrame) {
+        let Ok(record) = frame.decode_samples() else {
+            self.ready.push_back(OrderedFrame { frame, late: false });
+            return;
+        };
+        let id = format!(
+            "{}.{}.{}.{}",
+            record.network, record.station, record.location, record.channel
+        );
+        let channel = self.channels.entry(id.clone()).or_default();
+        if channel
+            .last_released
+            .is_some_and(|last| record.start_time < last)
+        {
+            self.ready.push_back(OrderedFrame { frame, late: true });
+            return;
+        }
+        self.received += 1;
+        let key = (record.start_time, self.received);
+        channel.pending.insert(key, frame);
+        self.arrivals.push_back((Instant::now(), id, key));
+    }
+
+    /// Release the oldest arrival and every earlier-starting frame of its channel.
+    fn release_oldest(&mut self) {
+        let Some((_, id, key)) = self.arrivals.pop_front() else {
+            return;
+        };
+        let Some(channel) = self.channels.get_mut(&id) else {
+            return;
+        };
+        while let Some(entry) = channel.pending.first_entry() {
+            if *entry.key() > key {
+                break;
+            }
+            let ((start, _), frame) = entry.remove_entry();
+            channel.last_released = Some(start);
+            self.ready.push_back(OrderedFrame { frame, late: false });
+        }
+    }
+
+    fn release_all(&mut self) {
+        while !self.arrivals.is_empty() {
+            self.release_oldest();
+        }
+    }
+}
+
+impl<S> Stream for OrderedFrameStream<S>
+where
+    S: Stream<Item = Result<OwnedFrame>>,
+{
+    type Item = Result<OrderedFrame>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(frame) = this.ready.pop_front() {
+                return Poll::Ready(Some(Ok(frame)));
+            }
+            if let Some(e) = this.error.take() {
+                return Poll::Ready(Some(Err(e)));
+            }
+
+            match this.inner.as_mut().map(|s| s.as_mut().poll_next(cx)) {
+                Some(Poll::Ready(Some(Ok(frame)))) => {
+                    this.accept(frame);
+                    continue;
+                }
+                Some(Poll::Ready(Some(Err(e)))) => {
+                    this.release_all();
+                    this.error = Some(e);
+                    continue;
+                }
+                Some(Poll::Ready(None)) => {
+                    this.inner = None;
+                    this.release_all();
+                    continue;
+                }
+                Some(Poll::Pending) => {}
+                None if this.arrivals.is_empty() => return Poll::Ready(None),
+                None => {}
+            }
+
+            let Some(&(arrived, ..)) = this.arrivals.front() else {
+                return Poll::Pending;
+            };
+            let deadline = arrived + this.delay;
+            if Instant::now() >= deadline {
+                this.release_oldest();
+                continue;
+            }
+            let timer = this
+                .timer
+                .get_or_insert_with(|| Box::pin(tokio::time::sleep_until(deadline)));
+            timer.as_mut().reset(deadline);
+            if timer.as_mut().poll(cx).is_ready() {
+                this.release_oldest();
+                continue;
+            }
+            return Poll::Pending;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ClientError;
+    use miniseed_rs::{EncodingFormat, MseedRecord, NanoTime, Samples};
+    use seedlink_rs_protocol::SequenceNumber;
+    use std::pin::pin;
+    use tokio_stream::StreamExt;
+
+    fn frame(seq: u64, channel: &str, second: u8) -> OwnedFrame {
+        let record = MseedRecord::new()
+            .with_nslc("IU", "ANMO", "00", channel)
+            .with_start_time(NanoTime {
+                year: 2024,
+                day: 1,
+                hour: 0,
+                minute: 0,
+                second,
+                nanosecond: 0,
+            })
+            .with_sample_rate(1.0)
+            .with_encoding(EncodingFormat::Steim2)
+            .with_samples(Samples::Int(vec![1]));
+        OwnedFrame::V3 {
+            sequence: SequenceNumber::new(seq),
+            payload: miniseed_rs::encode(&record).unwrap(),
+        }
+    }
+
+    fn summary(items: Vec<Result<OrderedFrame>>) -> Vec<(u64, bool)> {
+        items
+            .into_iter()
+            .map(|r| {
+                let r = r.unwrap();
+                (r.frame.sequence().value(), r.late)
+            })
+            .collect()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn orders_each_channel_by_start_time() {
+        let source = async_stream::stream! {
+            yield Ok(frame(1, "BHZ", 2));
+            yield Ok(frame(2, "BHN", 5));
+            yield Ok(frame(3, "BHZ", 1));
+            yield Ok(frame(4, "BHN", 4));
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            yield Ok(frame(5, "BHZ", 0));
+        };
+        let stream = OrderedFrameStream::new(source, Duration::from_secs(1));
+        let out: Vec<_> = stream.collect().await;
+        assert_eq!(
+            summary(out),
+            [(5, false), (3, false), (1, false), (4, false), (2, false)]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn flags_records_after_the_delay_as_late() {
+        let source = async_stream::stream! {
+            yield Ok(frame(1, "BHZ", 5));
+            tokio::time::sleep(Duration::from_secs(2)).await;
+            yield Ok(frame(2, "BHZ", 4));
+            yield Ok(frame(3, "BHN", 4));
+            yield Ok(frame(4, "BHZ", 6));
+        };
+        let mut stream = pin!(OrderedFrameStream::new(source, Duration::from_secs(1)));
+        let first = stream.next().await.unwrap().unwrap();
+        assert_eq!(first.frame.sequence().value(), 1);
+        assert_eq!(stream.held(), 0);
+
+        let rest: Vec<_> = stream.collect().await;
+        assert_eq!(summary(rest), [(2, true), (3, false), (4, false)]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn releases_held_frames_before_error() {
+        let source = async_stream::stream! {
+            yield Ok(frame(1, "BHZ", 3));
+            yield Ok(OwnedFrame::V3 {
+                sequence: SequenceNumber::new(2),
+                payload: vec![0u8; 512],
+            });
+            yield Ok(frame(3, "BHZ", 2));
+            yield Err(ClientError::Disconnected);
+        };
+        let mut stream = pin!(OrderedFrameStream::new(source, Duration::from_secs(60)));
+        // Undecodable payloads pass straight through
+        let undecodable = stream.next().await.unwrap().unwrap();
+        assert_eq!(undecodable.frame.sequence().value(), 2);
+        assert_eq!(
+            stream
+                .next()
+                .await
+                .unwrap()
+                .unwrap()
+                .frame
+                .sequence()
+                .value(),
+            3
+        );
+        assert_eq!(
+            stream
+                .next()
+                .await
+                .unwrap()
+                .unwrap()
+                .frame
+                .sequence()
+                .value(),
+            1
+        );
+        assert!(matches!(
+            stream.next().await,
+            Some(Err(ClientError::Disconnected))
+        ));
+        assert!(stream.next().await.is_none());
+    }
+}
@@ -1,13 +1,20 @@
 use std::collections::HashMap;
-use std::time::Duration;
+use std::hash::{BuildHasher, RandomState};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use futures_core::Stream;
-use seedlink_rs_protocol::SequenceNumber;
+use seedlink_rs_protocol::{Command, DataFrame, InfoLevel, ProtocolVersion, SequenceNumber};
 use tracing::{debug, info, warn};
 
 use crate::SeedLinkClient;
 use crate::error::{ClientError, Result};
-use crate::state::{ClientConfig, OwnedFrame, StationKey};
+use crate::hooks::Hooks;
+use crate::info::{StationInfo, StreamInfo};
+use crate::state::{ClientConfig, OwnedFrame, ResumeGap, ServerInfo, StationKey};
+use crate::statefile::{self, SequenceState};
+use crate::stream::UndecodableFrames;
 
 /// Configuration for automatic reconnect with exponential backoff.
 #[derive(Clone, Debug)]
@@ -16,10 +23,25 @@ pub struct ReconnectConfig {
     pub initial_backoff: Duration,
     /// Maximum delay between reconnect attempts. Default: 60 seconds.
     pub max_backoff: Duration,
-    /// Multiplier applied to backoff after each failed attempt. Default: 2.0.
+    /// Multiplier applied to backoff after each failed attempt. A negative or
+    /// non-finite multiplier jumps straight to `max_backoff`. Default: 2.0.
     pub multiplier: f64,
+    /// Fraction of each delay that is randomized, `0.0..=1.0`: the actual
+    /// wait is drawn from `[delay * (1 - jitter), delay]`, so clients cut off
+    /// together by a server restart do not all reconnect at once. Values
+    /// above 1.0 count as 1.0; negative or non-finite values disable jitter.
+    /// Default: 0.0.
+    pub jitter: f64,
     /// Maximum number of reconnect attempts. 0 = unlimited. Default: 0.
     pub max_attempts: u32,
+    /// File to persist tracked sequence numbers to, like slinktool's `-x`.
+    /// Loaded on connect so a restarted process resumes with `DATA seq`.
+    /// Default: `None`.
+    pub statefile: Option<PathBuf>,
+    /// Minimum time between state file writes while streaming. The state is
+    /// also written on disconnect and by [`ReconnectingClient::save_state`].
+    /// Default: 30 seconds.
+    pub state_save_interval: Duration,
 }
 
 impl Default for ReconnectConfig {
@@ -28,11 +50,61 @@ impl Default for ReconnectConfig {
             initial_backoff: Duration::from_secs(1),
             max_backoff: Duration::from_secs(60),
             multiplier: 2.0,
+            jitter: 0.0,
             max_attempts: 0,
+            statefile: None,
+            state_save_interval: Duration::from_secs(30),
         }
     }
 }
 
+impl ReconnectConfig {
+    /// `delay` shortened by a random part of [`jitter`](Self::jitter).
+    pub(crate) fn jittered(&self, delay: Duration) -> Duration {
+        if !self.jitter.is_finite() || self.jitter <= 0.0 {
+            return delay;
+        }
+        let unit = (RandomState::new().hash_one(Instant::now()) >> 11) as f64 / (1u64 << 53) as f64;
+        delay.mul_f64(1.0 - self.jitter.min(1.0) * unit)
+    }
+
+    pub(crate) fn next_backoff(&self, current: Duration) -> Duration {
+        Duration::try_from_secs_f64(current.as_secs_f64() * self.multiplier)
+            .map_or(self.max_backoff, |next| next.min(self.max_backoff))
+    }
+}
+
+/// Connection health reported to [`ReconnectingClient::on_event()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReconnectEvent {
+    /// The stream ended; reconnecting starts.
+    Disconnected,
+    /// The next reconnect attempt will be made after `delay`.
+    RetryScheduled {
+        /// Attempt number, starting at 1.
+        attempt: u32,
+        /// Wait before the attempt, jitter included.
+        delay: Duration,
+    },
+    /// Connected, but replaying the subscriptions (or END) failed; the
+    /// attempt is retried.
+    ReplayFailed {
+        /// Attempt number, starting at 1.
+        attempt: u32,
+        /// The error that failed the replay.
+        error: String,
+    },
+    /// Reconnected and streaming again.
+    Connected {
+        /// Attempt number that succeeded.
+        attempt: u32,
+    },
+    /// Records of a station were lost between its resume sequence and the
+    /// first frame received after it, see
+    /// [`ClientConfig::resume_gap_threshold`].
+    ResumeGap(ResumeGap),
+}
+
 /// Records a subscription step for replay on reconnect.
 #[derive(Clone, Debug)]
 enum SubscriptionStep {
@@ -56,13 +128,30 @@ enum SubscriptionStep {
 /// after reconnect: any frame whose sequence number is ≤ the last tracked
 /// sequence for its station is silently dropped. Downstream consumers are
 /// guaranteed to never see duplicate frames.
+///
+/// # State file
+///
+/// With [`ReconnectConfig::statefile`] set, tracked sequences are loaded on
+/// connect and written back periodically, so the same deduplication and
+/// `DATA seq` resume also apply across process restarts.
 pub struct ReconnectingClient {
     addr: String,
     config: ClientConfig,
     reconnect: ReconnectConfig,
     subscriptions: Vec<SubscriptionStep>,
     client: Option<SeedLinkClient>,
+    version: ProtocolVersion,
+    server_info: ServerInfo,
+    streaming: bool,
     sequences: HashMap<StationKey, SequenceNumber>,
+    /// Record timestamps for the state file.
+    state: SequenceState,
+    last_save: Instant,
+    on_event: Option<Box<dyn FnMut(ReconnectEvent) + Send + Sync>>,
+    hooks: Hooks,
+    /// Resume gaps reported by the current connection, emitted as events by
+    /// [`next_frame()`](Self::next_frame).
+    resume_gaps: Arc<Mutex<Vec<ResumeGap>>>,
 }
 
 impl ReconnectingClient {
@@ -77,15 +166,66 @@ impl ReconnectingClient {
         config: ClientConfig,
         reconnect: ReconnectConfig,
     ) -> Result<Self> {
+        let state = match &reconnect.statefile {
+            Some(path) => {
+                let state = statefile::load(path)?;
+                debug!(path = %path.display(), stations = state.len(), "loaded state file");
+                state
+            }
+            None => SequenceState::new(),
+        };
         let client = SeedLinkClient::connect_with_config(addr, config.clone()).await?;
-        Ok(Self {
+        let mut this = Self {
             addr: addr.to_owned(),
             config,
             reconnect,
             subscriptions: Vec::new(),
+            version: client.version(),
+            server_info: client.server_info().clone(),
+            streaming: false,
             client: Some(client),
-            sequences: HashMap::new(),
-        })
+            sequences: state.sequences(),
+            state,
+            last_save: Instant::now(),
+            on_event: None,
+            hooks: Hooks::default(),
+            resume_gaps: Arc::default(),
+        };
+        this.install_hooks();
+        Ok(this)
+    }
+
+    /// Returns the protocol version negotiated on the most recent connection.
+    pub fn version(&self) -> ProtocolVersion {
+        self.version
+    }
+
+    /// Returns information about the server from the most recent connection.
+    pub fn server_info(&self) -> &ServerInfo {
+        &self.server_info
+    }
+
+    /// Call `callback` with every [`ReconnectEvent`], e.g. to log or alert on
+    /// connection health. Replaces any previous callback.
+    pub fn on_event(&mut self, callback: impl FnMut(ReconnectEvent) + Send + Sync + 'static) {
+        self.on_event = Some(Box::new(callback));
+    }
+
+    /// Call `hook` with every command sent from now on, including the
+    /// subscription replay after each reconnect. See
+    /// [`SeedLinkClient::on_command()`].
+    pub fn on_command(&mut self, hook: impl Fn(&Command) + Send + Sync + 'static) {
+        self.hooks.add_command(hook);
+        self.install_hooks();
+    }
+
+    /// Call `hook` with every frame before it is returned by
+    /// [`next_frame()`](Self::next_frame). See [`SeedLinkClient::on_frame()`].
+    ///
+    /// Deduplication runs first, so the hook sees each frame once and never
+    /// changes what is tracked for resume.
+    pub fn on_frame(&mut self, hook: impl Fn(&mut OwnedFrame) + Send + Sync + 'static) {
+        self.hooks.add_frame(hook);
     }
 
     /// Select a station and network. Records the step for reconnect replay.
@@ -106,9 +246,21 @@ impl ReconnectingClient {
     }
 
     /// Arm with DATA. Records the step for reconnect replay.
+    ///
+    /// Resumes from the tracked sequence of the current station if one is
+    /// known (e.g. loaded from the state file).
     pub async fn data(&mut self) -> Result<()> {
         self.subscriptions.push(SubscriptionStep::Data);
-        self.client_mut()?.data().await
+        let resume = self
+            .current_station()
+            .and_then(|key| self.sequences.get(&key).copied());
+        match resume {
+            Some(seq) => {
+                debug!(%seq, "resuming from saved sequence");
+                self.client_mut()?.data_from(seq).await
+            }
+            None => self.client_mut()?.data().await,
+        }
     }
 
     /// Arm with DATA from a specific sequence. Records the step for reconnect replay.
@@ -129,17 +281,47 @@ impl ReconnectingClient {
 
     /// Send END to start streaming. Does not record (replayed automatically).
     pub async fn end_stream(&mut self) -> Result<()> {
-        self.client_mut()?.end_stream().await
+        self.client_mut()?.end_stream().await?;
+        self.streaming = true;
+        Ok(())
     }
 
-    /// Read the next frame, automatically reconnecting on EOF.
+    /// Request server information at the given detail level.
+    ///
+    /// Reconnects first if the connection was lost, e.g. after
+    /// [`next_frame()`](Self::next_frame) gave up. See [`SeedLinkClient::info()`].
+    pub async fn info(&mut self, level: InfoLevel) -> Result<Vec<OwnedFrame>> {
+        self.connected_client().await?.info(level).await
+    }
+
+    /// Request `INFO STATIONS`, reconnecting first if necessary.
+    /// See [`SeedLinkClient::info_stations()`].
+    pub async fn info_stations(&mut self) -> Result<Vec<StationInfo>> {
+        self.connected_client().await?.info_stations().await
+    }
+
+    /// Request `INFO STREAMS`, reconnecting first if necessary.
+    /// See [`SeedLinkClient::info_streams()`].
+    pub async fn info_streams(&mut self) -> Result<Vec<StreamInfo>> {
+        self.connected_client().await?.info_streams().await
+    }
+
+    /// Request INFO as JSON, reconnecting first if necessary.
+    /// See [`SeedLinkClient::info_json()`].
+    pub async fn info_json(&mut self, level: InfoLevel) -> Result<serde_json::Value> {
+        self.connected_client().await?.info_json(level).await
+    }
+
+    /// Read the next frame, automatically reconnecting on EOF or a
+    /// [retriable](ClientError::is_retriable) error.
     ///
     /// Returns `Ok(Some(frame))` on success, `Ok(None)` when the stream truly ends
     /// (max attempts exhausted or server sends clean EOF after reconnect),
-    /// or `Err` on non-recoverable errors.
+    /// or `Err` on non-retriable errors.
     ///
-    /// Frames with sequence ≤ the last tracked sequence for their station are
-    /// silently dropped (deduplication after reconnect).
+    /// Frames whose sequence is not after the last tracked sequence for their
+    /// station are silently dropped (deduplication after reconnect). v3
+    /// sequences are compared wrap-aware, see [`OwnedFrame::is_after()`].
     pub async fn next_frame(&mut self) -> Result<Option<OwnedFrame>> {
         loop {
             let result = match self.client.as_mut() {
@@ -147,13 +329,14 @@ impl ReconnectingClient {
                 None => return Err(ClientError::Disconnected),
             };
 
+            self.emit_resume_gaps();
             match result {
-                Ok(Some(frame)) => {
+                Ok(Some(mut frame)) => {
                     // Dedup: skip frames we've already seen (server may resend
                     // the last frame after reconnect with DATA seq)
                     if let Some(key) = frame.station_key()
                         && let Some(&tracked) = self.sequences.get(&key)
-                        && frame.sequence() <= tracked
+                        && !frame.is_after(tracked)
                     {
                         debug!(
                             seq = %frame.sequence(),
@@ -166,25 +349,30 @@ impl ReconnectingClient {
 
                     // Track sequence from the inner client
                     self.sync_sequences();
+                    if self.reconnect.statefile.is_some() {
+                        self.state.update(&frame);
+                    }
+                    if self.last_save.elapsed() >= self.reconnect.state_save_interval {
+                        self.save_state_logged();
+                    }
+                    self.hooks.frame(&mut frame);
                     return Ok(Some(frame));
                 }
-                Ok(None) => {
-                    // EOF — attempt reconnect
-                    debug!("stream ended, attempting reconnect");
-                    match self.attempt_reconnect().await {
-                        Ok(()) => {
-                            // Reconnected — loop to read from new connection
-                            continue;
-                        }
-                        Err(ClientError::ReconnectFailed { attempts }) => {
-                            warn!(attempts, "reconnect failed, giving up");
-                            return Err(ClientError::ReconnectFailed { attempts });
-                        }
-                        Err(e) => return Err(e),
-                    }
+                Ok(None) => debug!("stream ended, attempting reconnect"),
+                Err(e) if e.is_retriable() => {
+                    warn!(error = %e, "stream failed, attempting reconnect");
                 }
                 Err(e) => return Err(e),
             }
+
+            self.save_state_logged();
+            self.emit(ReconnectEvent::Disconnected);
+            if let Err(e) = self.attempt_reconnect().await {
+                if let ClientError::ReconnectFailed { attempts } = e {
+                    warn!(attempts, "reconnect failed, giving up");
+                }
+                return Err(e);
+            }
         }
     }
 
@@ -205,6 +393,27 @@ impl ReconnectingClient {
         }
     }
 
+    /// Consume this client and return a [`Stream`] of decoded miniSEED
+    /// records with auto-reconnect.
+    ///
+    /// See [`SeedLinkClient::into_record_stream()`] for how undecodable frames
+    /// are handled.
+    pub fn into_record_stream(
+        self,
+        undecodable: UndecodableFrames,
+    ) -> impl Stream<Item = Result<DataFrame>> {
+        crate::stream::record_stream(self.into_stream(), undecodable)
+    }
+
+    /// Send `BYE` on the current connection and close it, waiting at most
+    /// 2 s; see [`SeedLinkClient::close()`]. Does nothing while disconnected.
+    pub async fn close(mut self) -> Result<()> {
+        match self.client.take() {
+            Some(client) => client.close().await,
+            None => Ok(()),
+        }
+    }
+
     /// Returns the last received sequence number for a given network/station pair.
     pub fn last_sequence(&self, network: &str, station: &str) -> Option<SequenceNumber> {
         let key = StationKey {
@@ -219,12 +428,56 @@ impl ReconnectingClient {
         &self.sequences
     }
 
+    /// Write tracked sequences to the configured state file now.
+    ///
+    /// Call before shutting down to persist the latest position. Does nothing
+    /// if no state file is configured.
+    pub fn save_state(&mut self) -> Result<()> {
+        if let Some(path) = &self.reconnect.statefile {
+            statefile::save(path, &self.state, &self.sequences)?;
+            self.last_save = Instant::now();
+        }
+        Ok(())
+    }
+
     // -- Private helpers --
 
+    fn save_state_logged(&mut self) {
+        if let Err(e) = self.save_state() {
+            warn!(error = %e, "failed to write state file");
+        }
+    }
+
+    /// The station selected by the most recent STATION step.
+    fn current_station(&self) -> Option<StationKey> {
+        self.subscriptions.iter().rev().find_map(|step| match step {
+            SubscriptionStep::Station { station, network } => Some(StationKey {
+                network: network.clone(),
+                station: station.clone(),
+            }),
+            _ => None,
+        })
+    }
+
+    fn emit(&mut self, event: ReconnectEvent) {
+        if let Some(callback) = &mut self.on_event {
+            callback(event);
+        }
+    }
+
     fn client_mut(&mut self) -> Result<&mut SeedLinkClient> {
         self.client.as_mut().ok_or(ClientError::Disconnected)
     }
 
+    /// The inner client, reconnecting first if there is none.
+    async fn connected_client(&mut self) -> Result<&mut SeedLinkClient> {
+        if self.client.is_none() {
+            debug!("not connected, reconnecting");
+            self.attempt_reconnect().await?;
+        }
+        self.client_mut()
+    }
+
     fn sync_sequences(&mut self) {
         if let Some(client) = &self.client {
             for (key, seq) in client.sequences() {
@@ -233,6 +486,30 @@ impl ReconnectingClient {
         }
     }
 
+    /// Give the current connection our command hooks. Frame hooks run in
+    /// [`next_frame()`](Self::next_frame), after deduplication.
+    fn install_hooks(&mut self) {
+        let hooks = self.client_hooks();
+        if let Some(client) = self.client.as_mut() {
+            client.set_hooks(hooks);
+        }
+    }
+
+    /// Our command hooks, plus collecting the connection's resume gaps.
+    fn client_hooks(&self) -> Hooks {
+        let mut hooks = self.hooks.commands_only();
+        let gaps = self.resume_gaps.clone();
+        hooks.add_resume_gap(move |gap| gaps.lock().unwrap().push(gap.clone()));
+        hooks
+    }
+
+    fn emit_resume_gaps(&mut self) {
+        let gaps = std::mem::take(&mut *self.resume_gaps.lock().unwrap());
+        for gap in gaps {
+            self.emit(ReconnectEvent::ResumeGap(gap));
+        }
+    }
+
     /// Try to reconnect and replay subscriptions.
     async fn attempt_reconnect(&mut self) -> Result<()> {
         self.client = None;
@@ -247,32 +524,47 @@ impl ReconnectingClient {
                 });
             }
 
-            info!(attempt, backoff_ms = backoff.as_millis(), "reconnecting");
-            tokio::time::sleep(backoff).await;
+            let delay = self.reconnect.jittered(backoff);
+            info!(attempt, delay_ms = delay.as_millis(), "reconnecting");
+            self.emit(ReconnectEvent::RetryScheduled { attempt, delay });
+            tokio::time::sleep(delay).await;
 
             match SeedLinkClient::connect_with_config(&self.addr, self.config.clone()).await {
                 Ok(mut new_client) => {
-                    // Replay subscriptions
-                    if let Err(e) = self.replay_subscriptions(&mut new_client).await {
+                    new_client.set_hooks(self.client_hooks());
+                    // Replay subscriptions, then send END to resume streaming
+                    let replayed = match self.replay_subscriptions(&mut new_client).await {
+                        Ok(()) if self.streaming => new_client.end_stream().await,
+                        result => result,
+                    };
+                    if let Err(e) = replayed {
+                        self.emit(ReconnectEvent::ReplayFailed {
+                            attempt,
+                            error: e.to_string(),
+                        });
+                        if !e.is_retriable() {
+                            warn!(attempt, error = %e, "replay refused, giving up");
+                            return Err(e);
+                        }
                         warn!(attempt, error = %e, "replay failed, retrying");
-                        backoff = self.next_backoff(backoff);
-                        continue;
-                    }
-
-                    // Send END to resume streaming
-                    if let Err(e) = new_client.end_stream().await {
-                        warn!(attempt, error = %e, "end_stream failed, retrying");
-                        backoff = self.next_backoff(backoff);
+                        backoff = self.reconnect.next_backoff(backoff);
                         continue;
                     }
 
                     info!(attempt, "reconnected successfully");
+                    self.version = new_client.version();
+                    self.server_info = new_client.server_info().clone();
                     self.client = Some(new_client);
+                    self.emit(ReconnectEvent::Connected { attempt });
                     return Ok(());
                 }
+                Err(e) if !e.is_retriable() => {
+                    warn!(attempt, error = %e, "reconnect refused, giving up");
+                    return Err(e);
+                }
                 Err(e) => {
                     warn!(attempt, error = %e, "reconnect attempt failed");
-                    backoff = self.next_backoff(backoff);
+                    backoff = self.reconnect.next_backoff(backoff);
                 }
             }
         }
@@ -280,11 +572,6 @@ impl ReconnectingClient {
         unreachable!()
     }
 
-    fn next_backoff(&self, current: Duration) -> Duration {
-        let next = current.mul_f64(self.reconnect.multiplier);
-        next.min(self.reconnect.max_backoff)
-    }
-
     /// Replay all recorded subscription steps on a new client.
     ///
     /// Replaces bare `Data` steps with `DataFrom(last_seq)` when we have
@@ -321,7 +608,7 @@ impl ReconnectingClient {
                     // If we have a newer sequence, use that instead
                     if let Some(ref key) = current_station
                         && let Some(tracked) = self.sequences.get(key)
-                        && *tracked > *seq
+                        && is_newer(client.version(), *tracked, *seq)
                     {
                         client.data_from(*tracked).await?;
                         continue;
@@ -338,6 +625,14 @@ impl ReconnectingClient {
     }
 }
 
+/// Whether `a` is a later sequence than `b` on a connection using `version`.
+fn is_newer(version: ProtocolVersion, a: SequenceNumber, b: SequenceNumber) -> bool {
+    match version {
+        ProtocolVersion::V3 => a.is_after(b, SequenceNumber::V3_WRAP_WINDOW),
+        ProtocolVersion::V4 => a > b,
+    }
+}
+
 // Clone ClientConfig so we can reuse it across reconnects
 impl Clone for ClientConfig {
     fn clone(&self) -> Self {
@@ -345,6 +640,14 @@ impl Clone for ClientConfig {
             connect_timeout: self.connect_timeout,
             read_timeout: self.read_timeout,
             prefer_v4: self.prefer_v4,
+            protocol: self.protocol.clone(),
+            user_agent: self.user_agent.clone(),
+            compression: self.compression,
+            validation: self.validation,
+            max_payload_len: self.max_payload_len,
+            proxy: self.proxy.clone(),
+            socket: self.socket.clone(),
+            resume_gap_threshold: self.resume_gap_threshold,
         }
     }
 }
@@ -353,25 +656,46 @@ impl Clone for ClientConfig {
 mod tests {
     use super::*;
     use crate::mock::{MockConfig, MockServer};
+    use seedlink_rs_protocol::ErrorKind;
     use seedlink_rs_protocol::frame::v3;
+    use seedlink_rs_protocol::testing::TestRecordBuilder;
+    use std::sync::{Arc, Mutex};
 
     fn make_v3_frame(seq: u64, station: &str, network: &str) -> Vec<u8> {
-        let mut payload = [0u8; v3::PAYLOAD_LEN];
-        let sta_bytes = station.as_bytes();
-        for (i, &b) in sta_bytes.iter().enumerate().take(5) {
-            payload[8 + i] = b;
-        }
-        for i in sta_bytes.len()..5 {
-            payload[8 + i] = b' ';
-        }
-        let net_bytes = network.as_bytes();
-        for (i, &b) in net_bytes.iter().enumerate().take(2) {
-            payload[18 + i] = b;
+        let payload = TestRecordBuilder::new()
+            .station(station)
+            .network(network)
+            .build();
+        v3::write(SequenceNumber::new(seq), &payload).unwrap()
+    }
+
+    #[test]
+    fn backoff_tolerates_invalid_jitter_and_multiplier() {
+        let delay = Duration::from_secs(10);
+        for jitter in [f64::NAN, f64::NEG_INFINITY, f64::INFINITY, -0.5, 0.0] {
+            let config = ReconnectConfig {
+                jitter,
+                ..Default::default()
+            };
+            assert_eq!(config.jittered(delay), delay, "jitter {jitter}");
         }
-        for i in net_bytes.len()..2 {
-            payload[18 + i] = b' ';
+        let config = ReconnectConfig {
+            jitter: 5.0,
+            ..Default::default()
+        };
+        assert!(config.jittered(delay) <= delay);
+
+        for multiplier in [f64::NAN, f64::INFINITY, -2.0, 1e300] {
+            let config = ReconnectConfig {
+                multiplier,
+                ..Default::default()
+            };
+            assert_eq!(
+                config.next_backoff(delay),
+                config.max_backoff,
+                "{multiplier}"
+            );
         }
-        v3::write(SequenceNumber::new(seq), &payload).unwrap()
     }
 
     #[tokio::test]
@@ -391,6 +715,7 @@ mod tests {
         let reconnect_config = ReconnectConfig {
             initial_backoff: Duration::from_millis(10),
             max_backoff: Duration::from_millis(50),
+            jitter: 0.5,
             max_attempts: 3,
             ..Default::default()
         };
@@ -407,6 +732,9 @@ mod tests {
         )
         .await
         .unwrap();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        client.on_event(move |event| sink.lock().unwrap().push(event));
 
         client.station("ANMO", "IU").await.unwrap();
         client.data().await.unwrap();
@@ -419,6 +747,53 @@ mod tests {
         // Connection closes → auto-reconnect → dedup-clean frame from second connection
         let frame2 = client.next_frame().await.unwrap().unwrap();
         assert_eq!(frame2.sequence(), SequenceNumber::new(2));
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 3, "{events:?}");
+        assert_eq!(events[0], ReconnectEvent::Disconnected);
+        let ReconnectEvent::RetryScheduled { attempt: 1, delay } = events[1] else {
+            panic!("expected RetryScheduled, got {:?}", events[1]);
+        };
+        assert!((Duration::from_millis(5)..=Duration::from_millis(10)).contains(&delay));
+        assert_eq!(events[2], ReconnectEvent::Connected { attempt: 1 });
+    }
+
+    #[tokio::test]
+    async fn info_and_server_info_forwarded() {
+        let config = MockConfig {
+            max_connections: 2,
+            ..MockConfig::v3_default(vec![make_v3_frame(1, "ANMO", "IU")])
+        };
+        let server = MockServer::start(config).await;
+        let reconnect_config = ReconnectConfig {
+            initial_backoff: Duration::from_millis(10),
+            max_attempts: 1,
+            ..Default::default()
+        };
+        let client_config = ClientConfig {
+            prefer_v4: false,
+            ..Default::default()
+        };
+
+        let mut client = ReconnectingClient::connect_with_config(
+            &server.addr().to_string(),
+            client_config,
+            reconnect_config,
+        )
+        .await
+        .unwrap();
+        assert_eq!(client.version(), ProtocolVersion::V3);
+        assert_eq!(client.server_info().software, "SeedLink");
+        assert_eq!(client.info(InfoLevel::Id).await.unwrap().len(), 1);
+
+        // Connection lost outside next_frame(): INFO reconnects first, and
+        // does not send END since streaming was never started
+        client.client = None;
+        assert_eq!(client.info(InfoLevel::Id).await.unwrap().len(), 1);
+        assert_eq!(client.server_info().software, "SeedLink");
+        let second = server.captured().connection(1);
+        assert!(second.iter().any(|c| c.starts_with("INFO")), "{second:?}");
+        assert!(!second.contains(&"END".to_owned()), "{second:?}");
     }
 
     #[tokio::test]
@@ -465,6 +840,53 @@ mod tests {
         assert!(matches!(err, ClientError::ReconnectFailed { attempts: 2 }));
     }
 
+    #[tokio::test]
+    async fn reconnect_only_on_retriable_errors() {
+        let config = MockConfig {
+            connection_frames: Some(vec![
+                vec![
+                    make_v3_frame(1, "ANMO", "IU"),
+                    b"ERROR LIMIT too many clients\r\n".to_vec(),
+                ],
+                vec![
+                    make_v3_frame(2, "ANMO", "IU"),
+                    b"ERROR UNAUTHORIZED station withdrawn\r\n".to_vec(),
+                ],
+            ]),
+            max_connections: 2,
+            ..MockConfig::v3_default(vec![])
+        };
+        let server = MockServer::start(config).await;
+        let reconnect_config = ReconnectConfig {
+            initial_backoff: Duration::from_millis(10),
+            ..Default::default()
+        };
+        let client_config = ClientConfig {
+            prefer_v4: false,
+            ..Default::default()
+        };
+        let mut client = ReconnectingClient::connect_with_config(
+            &server.addr().to_string(),
+            client_config,
+            reconnect_config,
+        )
+        .await
+        .unwrap();
+        client.station("ANMO", "IU").await.unwrap();
+        client.data().await.unwrap();
+        client.end_stream().await.unwrap();
+
+        let frame = client.next_frame().await.unwrap().unwrap();
+        assert_eq!(frame.sequence(), SequenceNumber::new(1));
+        // LIMIT is transient: reconnected and resumed
+        let frame = client.next_frame().await.unwrap().unwrap();
+        assert_eq!(frame.sequence(), SequenceNumber::new(2));
+        // UNAUTHORIZED is surfaced without another attempt
+        let err = client.next_frame().await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Auth);
+        assert_eq!(server.captured().connections(), 2);
+    }
+
     #[tokio::test]
     async fn reconnect_resumes_sequence_verified_on_wire() {
         // Connection 0: seq=10,11. Connection 1: seq=10,11 (dupes) + seq=12 (new).
@@ -738,4 +1160,230 @@ mod tests {
         let err = client.next_frame().await.unwrap_err();
         assert!(matches!(err, ClientError::ReconnectFailed { attempts: 1 }));
     }
+
+    #[tokio::test]
+    async fn dedup_across_sequence_wrap() {
+        let path =
+            std::env::temp_dir().join(format!("seedlink-reconnect-wrap-{}", std::process::id()));
+        std::fs::write(&path, "IU ANMO 16777214\n").unwrap();
+
+        // Resent FFFFFE, then FFFFFF and the wrapped 000001
+        let frames = vec![
+            make_v3_frame(0xFFFFFE, "ANMO", "IU"),
+            make_v3_frame(0xFFFFFF, "ANMO", "IU"),
+            make_v3_frame(1, "ANMO", "IU"),
+        ];
+        let server = MockServer::start(MockConfig::v3_default(frames)).await;
+
+        let reconnect_config = ReconnectConfig {
+            statefile: Some(path.clone()),
+            ..Default::default()
+        };
+        let client_config = ClientConfig {
+            prefer_v4: false,
+            ..Default::default()
+        };
+        let mut client = ReconnectingClient::connect_with_config(
+            &server.addr().to_string(),
+            client_config,
+            reconnect_config,
+        )
+        .await
+        .unwrap();
+        client.station("ANMO", "IU").await.unwrap();
+        client.data().await.unwrap();
+        client.end_stream().await.unwrap();
+
+        let f = client.next_frame().await.unwrap().unwrap();
+        assert_eq!(f.sequence(), SequenceNumber::new(0xFFFFFF));
+        let f = client.next_frame().await.unwrap().unwrap();
+        assert_eq!(f.sequence(), SequenceNumber::new(1));
+        assert_eq!(
+            client.last_sequence("IU", "ANMO"),
+            Some(SequenceNumber::new(1))
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn statefile_resumes_and_persists() {
+        let path =
+            std::env::temp_dir().join(format!("seedlink-reconnect-state-{}", std::process::id()));
+        std::fs::write(
+            &path,
+            "GE WLF 5 2023,12,31,23,59,00\nIU ANMO 11 2023,12,31,23,59,59\n",
+        )
+        .unwrap();
+
+        // Server resends seq=11 (already seen in the previous run) then seq=12
+        let frames = vec![
+            make_v3_frame(11, "ANMO", "IU"),
+            make_v3_frame(12, "ANMO", "IU"),
+        ];
+        let server = MockServer::start(MockConfig::v3_default(frames)).await;
+
+        let reconnect_config = ReconnectConfig {
+            statefile: Some(path.clone()),
+            ..Default::default()
+        };
+        let client_config = ClientConfig {
+            prefer_v4: false,
+            ..Default::default()
+        };
+
+        let mut client = ReconnectingClient::connect_with_config(
+            &server.addr().to_string(),
+            client_config,
+            reconnect_config,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            client.last_sequence("IU", "ANMO"),
+            Some(SequenceNumber::new(11))
+        );
+
+        client.station("ANMO", "IU").await.unwrap();
+        client.data().await.unwrap();
+        client.end_stream().await.unwrap();
+
+        let f = client.next_frame().await.unwrap().unwrap();
+        assert_eq!(f.sequence(), SequenceNumber::new(12));
+
+        // DATA resumed from the saved sequence
+        let conn0 = server.captured().connection(0);
+        assert_eq!(conn0[1], "STATION ANMO IU");
+        assert_eq!(conn0[2], "DATA 00000B");
+
+        // Timestamps survive: loaded for GE.WLF, from the record for IU.ANMO
+        client.save_state().unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "GE WLF 5 2023,12,31,23,59,00\nIU ANMO 12 2024,01,01,00,00,00\n"
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn reconnect_reports_resume_gap() {
+        // Records 2..=9 were evicted while disconnected
+        let config = MockConfig {
+            close_after_stream: true,
+            max_connections: 2,
+            connection_frames: Some(vec![
+                vec![make_v3_frame(1, "ANMO", "IU")],
+                vec![make_v3_frame(10, "ANMO", "IU")],
+            ]),
+            ..MockConfig::v3_default(vec![])
+        };
+        let server = MockServer::start(config).await;
+        let reconnect_config = ReconnectConfig {
+            initial_backoff: Duration::from_millis(10),
+            max_attempts: 3,
+            ..Default::default()
+        };
+        let client_config = ClientConfig {
+            prefer_v4: false,
+            resume_gap_threshold: Some(0),
+            ..Default::default()
+        };
+        let mut client = ReconnectingClient::connect_with_config(
+            &server.addr().to_string(),
+            client_config,
+            reconnect_config,
+        )
+        .await
+        .unwrap();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        client.on_event(move |event| sink.lock().unwrap().push(event));
+
+        client.station("ANMO", "IU").await.unwrap();
+        client.data().await.unwrap();
+        client.end_stream().await.unwrap();
+        client.next_frame().await.unwrap().unwrap();
+        let frame = client.next_frame().await.unwrap().unwrap();
+        assert_eq!(frame.sequence(), SequenceNumber::new(10));
+
+        let events = events.lock().unwrap();
+        let gap = events.iter().find_map(|event| match event {
+            ReconnectEvent::ResumeGap(gap) => Some(gap),
+            _ => None,
+        });
+        let gap = gap.expect("no ResumeGap event");
+        assert_eq!(gap.requested, SequenceNumber::new(1));
+        assert_eq!(gap.first_received, SequenceNumber::new(10));
+        assert_eq!(gap.missed(), 8);
+    }
+
+    #[tokio::test]
+    async fn hooks_survive_reconnect_and_skip_duplicates() {
+        // Connection 1 resends seq=10 before the new seq=11
+        let config = MockConfig {
+            close_after_stream: true,
+            max_connections: 2,
+            connection_frames: Some(vec![
+                vec![make_v3_frame(10, "ANMO", "IU")],
+                vec![
+                    make_v3_frame(10, "ANMO", "IU"),
+                    make_v3_frame(11, "ANMO", "IU"),
+                ],
+            ]),
+            ..MockConfig::v3_default(vec![])
+        };
+        let server = MockServer::start(config).await;
+        let reconnect_config = ReconnectConfig {
+            initial_backoff: Duration::from_millis(10),
+            max_attempts: 3,
+            ..Default::default()
+        };
+        let client_config = ClientConfig {
+            prefer_v4: false,
+            ..Default::default()
+        };
+        let mut client = ReconnectingClient::connect_with_config(
+            &server.addr().to_string(),
+            client_config,
+            reconnect_config,
+        )
+        .await
+        .unwrap();
+
+        let commands = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&commands);
+        client.on_command(move |cmd| {
+            let line = cmd.to_bytes(ProtocolVersion::V3).unwrap();
+            log.lock()
+                .unwrap()
+                .push(String::from_utf8(line).unwrap().trim_end().to_owned());
+        });
+        let hooked = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&hooked);
+        client.on_frame(move |frame| log.lock().unwrap().push(frame.sequence()));
+
+        client.station("ANMO", "IU").await.unwrap();
+        client.data().await.unwrap();
+        client.end_stream().await.unwrap();
+        let f1 = client.next_frame().await.unwrap().unwrap();
+        assert_eq!(f1.sequence(), SequenceNumber::new(10));
+        // Reconnect: the duplicate seq=10 is dropped before the frame hook
+        let f2 = client.next_frame().await.unwrap().unwrap();
+        assert_eq!(f2.sequence(), SequenceNumber::new(11));
+
+        assert_eq!(
+            *hooked.lock().unwrap(),
+            [SequenceNumber::new(10), SequenceNumber::new(11)]
+        );
+        assert_eq!(
+            *commands.lock().unwrap(),
+            [
+                "STATION ANMO IU",
+                "DATA",
+                "END",
+                "STATION ANMO IU",
+                "DATA 00000A",
+                "END"
+            ]
+        );
+    }
 }
@@ -0,0 +1,197 @@
+//! Typed view of decoded miniSEED records.
+//!
+//! [`OwnedFrame::decode_samples()`](crate::OwnedFrame::decode_samples) converts
+//! a frame payload into a [`SampleRecord`] so applications can use stream
+//! identifiers, timing, and sample values without depending on miniseed-rs.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use miniseed_rs::{MseedRecord, NanoTime, Samples};
+use seedlink_rs_protocol::SequenceNumber;
+
+/// Decompressed sample values of a record.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SampleData {
+    /// Integer samples (INT16, INT32, Steim1, Steim2 encodings).
+    Int(Vec<i32>),
+    /// 32-bit float samples.
+    Float(Vec<f32>),
+    /// 64-bit float samples.
+    Double(Vec<f64>),
+}
+
+impl SampleData {
+    /// Number of samples.
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Int(v) => v.len(),
+            Self::Float(v) => v.len(),
+            Self::Double(v) => v.len(),
+        }
+    }
+
+    /// Returns `true` if there are no samples.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl From<Samples> for SampleData {
+    fn from(samples: Samples) -> Self {
+        match samples {
+            Samples::Int(v) => Self::Int(v),
+            Samples::Float(v) => Self::Float(v),
+            Samples::Double(v) => Self::Double(v),
+        }
+    }
+}
+
+/// A decoded data record with typed header fields and samples.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SampleRecord {
+    /// SeedLink sequence number of the frame this record came from.
+    pub sequence: SequenceNumber,
+    /// FDSN network code (e.g., `"IU"`).
+    pub network: String,
+    /// Station code (e.g., `"ANMO"`).
+    pub station: String,
+    /// Location code (e.g., `"00"`, may be empty).
+    pub location: String,
+    /// Channel code (e.g., `"BHZ"`).
+    pub channel: String,
+    /// Time of the first sample.
+    pub start_time: SystemTime,
+    /// Sample rate in Hz.
+    pub sample_rate: f64,
+    /// Decompressed sample values.
+    pub samples: SampleData,
+}
+
+impl SampleRecord {
+    /// Build from a decoded miniSEED record.
+    pub(crate) fn from_mseed(sequence: SequenceNumber, record: MseedRecord) -> Self {
+        Self {
+            sequence,
+            start_time: nano_time_to_system_time(&record.start_time),
+            network: record.network.trim().to_owned(),
+            station: record.station.trim().to_owned(),
+            location: record.location.trim().to_owned(),
+            channel: record.channel.trim().to_owned(),
+            sample_rate: record.sample_rate,
+            samples: record.samples.into(),
+        }
+    }
+
+    /// Time just past the last sample (`start_time + len / sample_rate`).
+    ///
+    /// Returns `start_time` if the sample rate is not positive, and `None` if
+    /// the end is not representable (e.g. a corrupt, tiny sample rate).
+    pub fn end_time(&self) -> Option<SystemTime> {
+        if self.sample_rate > 0.0 {
+            let span = self.samples.len() as f64 / self.sample_rate;
+            let span = Duration::try_from_secs_f64(span).ok()?;
+            self.start_time.checked_add(span)
+        } else {
+            Some(self.start_time)
+        }
+    }
+}
+
+/// Convert a miniSEED year/day-of-year timestamp to `SystemTime`.
+pub(crate) fn nano_time_to_system_time(t: &NanoTime) -> SystemTime {
+    let year = i64::from(t.year);
+    let days_before_year: i64 = if year >= 1970 {
+        (1970..year).map(days_in_year).sum()
+    } else {
+        -(year..1970).map(days_in_year).sum::<i64>()
+    };
+    let days = days_before_year + i64::from(t.day) - 1;
+    let secs =
+        days * 86_400 + i64::from(t.hour) * 3600 + i64::from(t.minute) * 60 + i64::from(t.second);
+    let nanos = Duration::from_nanos(u64::from(t.nanosecond));
+    if secs >= 0 {
+        UNIX_EPOCH + Duration::from_secs(secs as u64) + nanos
+    } else {
+        UNIX_EPOCH - Duration::from_secs(secs.unsigned_abs()) + nanos
+    }
+}
+
+fn days_in_year(y: i64) -> i64 {
+    if (y % 4 == 0 && y % 100 != 0) || y % 400 == 0 {
+        366
+    } else {
+        365
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nano(year: u16, day: u16, hour: u8, minute: u8, second: u8, nanosecond: u32) -> NanoTime {
+        NanoTime {
+            year,
+            day,
+            hour,
+            minute,
+            second,
+            nanosecond,
+        }
+    }
+
+    #[test]
+    fn epoch_converts_to_unix_epoch() {
+        assert_eq!(
+            nano_time_to_system_time(&nano(1970, 1, 0, 0, 0, 0)),
+            UNIX_EPOCH
+        );
+    }
+
+    #[test]
+    fn leap_year_day_of_year() {
+        // 2024-03-01 is DOY 61 (leap year); 1709251200 = 2024-03-01T00:00:00Z
+        let t = nano_time_to_system_time(&nano(2024, 61, 0, 0, 0, 0));
+        assert_eq!(t, UNIX_EPOCH + Duration::from_secs(1_709_251_200));
+    }
+
+    #[test]
+    fn sub_second_precision() {
+        let t = nano_time_to_system_time(&nano(2024, 1, 12, 30, 15, 250_000_000));
+        let expected = UNIX_EPOCH + Duration::from_secs(1_704_112_215) + Duration::from_millis(250);
+        assert_eq!(t, expected);
+    }
+
+    #[test]
+    fn before_epoch() {
+        let t = nano_time_to_system_time(&nano(1969, 365, 0, 0, 0, 0));
+        assert_eq!(t, UNIX_EPOCH - Duration::from_secs(86_400));
+    }
+
+    #[test]
+    fn end_time_from_rate() {
+        let rec = SampleRecord {
+            sequence: SequenceNumber::new(1),
+            network: "IU".into(),
+            station: "ANMO".into(),
+            location: "00".into(),
+            channel: "BHZ".into(),
+            start_time: UNIX_EPOCH,
+            sample_rate: 20.0,
+            samples: SampleData::Int(vec![0; 40]),
+        };
+        assert_eq!(rec.end_time(), Some(UNIX_EPOCH + Duration::from_secs(2)));
+
+        // A near-zero rate overflows even a Duration
+        let corrupt = SampleRecord {
+            sample_rate: f64::MIN_POSITIVE,
+            ..rec.clone()
+        };
+        assert_eq!(corrupt.end_time(), None);
+        // A span that fits a Duration (1.5e19 s) but not a SystemTime
+        let far = SampleRecord {
+            sample_rate: 40.0 / 1.5e19,
+            ..rec
+        };
+        assert_eq!(far.end_time(), None);
+    }
+}
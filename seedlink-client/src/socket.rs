@@ -0,0 +1,174 @@
+//! TCP socket options applied before connecting.
+
+use std::io;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use socket2::SockRef;
+use tokio::net::{TcpSocket, TcpStream};
+
+/// Local binding and TCP tuning for the client socket, set in
+/// [`ClientConfig::socket`](crate::ClientConfig::socket).
+///
+/// Multi-homed acquisition hosts use `local_addr` or `interface` to pin
+/// which network SeedLink traffic leaves through.
+///
+/// ```
+/// use seedlink_rs_client::{ClientConfig, KeepaliveConfig, SocketOptions};
+/// use std::time::Duration;
+///
+/// let config = ClientConfig {
+///     socket: SocketOptions {
+///         local_addr: Some("10.0.1.5:0".parse().unwrap()),
+///         keepalive: Some(KeepaliveConfig::new(Duration::from_secs(60))),
+///         recv_buffer_size: Some(1 << 20),
+///         ..Default::default()
+///     },
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SocketOptions {
+    /// Local address to bind before connecting; port `0` picks an ephemeral
+    /// port. Resolved server addresses of the other IP family are skipped.
+    pub local_addr: Option<SocketAddr>,
+    /// Network interface to bind to (`SO_BINDTODEVICE`, e.g. `"eth1"`).
+    /// Linux, Android and Fuchsia only; elsewhere the connect fails with
+    /// [`io::ErrorKind::Unsupported`].
+    pub interface: Option<String>,
+    /// Enable TCP keepalive probes, detecting a silently dead peer (NAT
+    /// timeout, unplugged link) while no data flows.
+    pub keepalive: Option<KeepaliveConfig>,
+    /// `SO_RCVBUF` in bytes. Set before connecting so the TCP window scale
+    /// is negotiated for it; the kernel may round or cap the value.
+    pub recv_buffer_size: Option<u32>,
+}
+
+/// TCP keepalive timing, see [`SocketOptions::keepalive`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeepaliveConfig {
+    /// Idle time before the first probe (`TCP_KEEPIDLE`).
+    pub time: Duration,
+    /// Time between unanswered probes (`TCP_KEEPINTVL`). `None` keeps the
+    /// system default; ignored on platforms without the option.
+    pub interval: Option<Duration>,
+}
+
+impl KeepaliveConfig {
+    /// Probe after `time` of idleness, at the system default interval.
+    pub fn new(time: Duration) -> Self {
+        Self {
+            time,
+            interval: None,
+        }
+    }
+
+    /// Set the time between unanswered probes.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = Some(interval);
+        self
+    }
+
+    fn to_socket2(self) -> socket2::TcpKeepalive {
+        let keepalive = socket2::TcpKeepalive::new().with_time(self.time);
+        #[cfg(any(
+            target_os = "android",
+            target_os = "freebsd",
+            target_os = "ios",
+            target_os = "linux",
+            target_os = "macos",
+            target_os = "windows",
+        ))]
+        let keepalive = match self.interval {
+            Some(interval) => keepalive.with_interval(interval),
+            None => keepalive,
+        };
+        keepalive
+    }
+}
+
+impl SocketOptions {
+    /// Whether `target` can be reached from [`local_addr`](Self::local_addr).
+    pub(crate) fn allows(&self, target: &SocketAddr) -> bool {
+        self.local_addr
+            .is_none_or(|local| local.is_ipv4() == target.is_ipv4())
+    }
+
+    /// Open a socket with these options and connect it to `target`.
+    pub(crate) async fn connect(&self, target: SocketAddr) -> io::Result<TcpStream> {
+        let socket = if target.is_ipv4() {
+            TcpSocket::new_v4()?
+        } else {
+            TcpSocket::new_v6()?
+        };
+        if let Some(size) = self.recv_buffer_size {
+            socket.set_recv_buffer_size(size)?;
+        }
+        if let Some(keepalive) = self.keepalive {
+            SockRef::from(&socket).set_tcp_keepalive(&keepalive.to_socket2())?;
+        }
+        if let Some(interface) = &self.interface {
+            bind_device(&socket, interface)?;
+        }
+        if let Some(local) = self.local_addr {
+            socket.bind(local)?;
+        }
+        socket.connect(target).await
+    }
+}
+
+#[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+fn bind_device(socket: &TcpSocket, interface: &str) -> io::Result<()> {
+    socket.bind_device(Some(interface.as_bytes()))
+}
+
+#[cfg(not(any(target_os = "android", target_os = "fuchsia", target_os = "linux")))]
+fn bind_device(_socket: &TcpSocket, interface: &str) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("binding to interface {interface} is not supported on this platform"),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn local_addr_filters_family() {
+        let v4: SocketAddr = "10.0.0.1:18000".parse().unwrap();
+        let v6: SocketAddr = "[2001:db8::1]:18000".parse().unwrap();
+        assert!(SocketOptions::default().allows(&v6));
+
+        let options = SocketOptions {
+            local_addr: Some("127.0.0.1:0".parse().unwrap()),
+            ..Default::default()
+        };
+        assert!(options.allows(&v4));
+        assert!(!options.allows(&v6));
+    }
+
+    #[tokio::test]
+    async fn connect_applies_options() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let options = SocketOptions {
+            local_addr: Some("127.0.0.1:0".parse().unwrap()),
+            keepalive: Some(
+                KeepaliveConfig::new(Duration::from_secs(60)).interval(Duration::from_secs(5)),
+            ),
+            recv_buffer_size: Some(256 * 1024),
+            ..Default::default()
+        };
+        let stream = options
+            .connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (_, peer) = listener.accept().await.unwrap();
+        assert_eq!(stream.local_addr().unwrap(), peer);
+
+        let sock = SockRef::from(&stream);
+        assert!(sock.keepalive().unwrap());
+        assert!(sock.recv_buffer_size().unwrap() >= 256 * 1024);
+    }
+}
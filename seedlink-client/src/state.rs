@@ -1,10 +1,19 @@
 use std::time::Duration;
 
-use seedlink_rs_protocol::{PayloadFormat, PayloadSubformat, RawFrame, SequenceNumber};
+use seedlink_rs_protocol::response::ErrorCode;
+use seedlink_rs_protocol::{
+    Capabilities, InvalidRecord, PayloadFormat, PayloadSubformat, RawFrame, SequenceNumber,
+    ValidationMode,
+};
+
+use crate::proxy::ProxyConfig;
+use crate::samples::SampleRecord;
+use crate::socket::SocketOptions;
 
 /// Client connection state machine.
 ///
 /// Transitions: `Disconnected` → `Connected` → `Configured` → `Streaming` → `Disconnected`.
+/// A v4 dial-up FETCH returns from `Streaming` to `Configured` instead.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ClientState {
     /// Not connected to any server.
@@ -29,14 +38,74 @@ impl ClientState {
     }
 }
 
+/// Which protocol version [`SeedLinkClient`](crate::SeedLinkClient)
+/// negotiates with `SLPROTO` after HELLO.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum ProtocolPreference {
+    /// v4 if the server advertises it and [`ClientConfig::prefer_v4`] is set,
+    /// otherwise v3 without `SLPROTO`. A rejected `SLPROTO` falls back to v3.
+    #[default]
+    Auto,
+    /// v3. Sends the advertised `SLPROTO 3.x` so servers that default to v4
+    /// lock the legacy mode; sends nothing to servers that advertise no
+    /// versions. Fails if the server advertises versions but no 3.x.
+    ForceV3,
+    /// v4. Fails if the server does not advertise `SLPROTO:4.x` or rejects it.
+    ForceV4,
+    /// Send `SLPROTO` with this version (e.g. `"3.0"`), whether advertised or
+    /// not. The major version must be 3 or 4. Fails if the server rejects it.
+    Exact(String),
+}
+
 /// Configuration for [`SeedLinkClient`](crate::SeedLinkClient) connections.
+#[derive(Debug)]
 pub struct ClientConfig {
     /// Timeout for the initial TCP connection. Default: 10 seconds.
     pub connect_timeout: Duration,
     /// Timeout for individual read operations (lines and frames). Default: 30 seconds.
     pub read_timeout: Duration,
-    /// Whether to attempt SeedLink v4 negotiation. Default: `true`.
+    /// Whether to attempt SeedLink v4 negotiation with
+    /// [`ProtocolPreference::Auto`]. Default: `true`.
     pub prefer_v4: bool,
+    /// How the protocol version is negotiated. The forced variants fail
+    /// the connect with [`ClientError::NegotiationFailed`](crate::ClientError::NegotiationFailed)
+    /// when the server cannot provide the version.
+    /// Default: [`ProtocolPreference::Auto`].
+    pub protocol: ProtocolPreference,
+    /// Sent as `USERAGENT` after a successful v4 negotiation and shown by the
+    /// server in INFO CONNECTIONS. `None` sends nothing.
+    /// Default: `"seedlink-rs/<crate version>"`.
+    pub user_agent: Option<String>,
+    /// Request zstd-compressed v4 payloads (`CAPABILITIES ZSTD`) when the
+    /// server advertises `ZSTD`. Payloads are decompressed transparently.
+    /// Requires the `zstd` feature; ignored without it.
+    /// Default: `true` with the `zstd` feature.
+    pub compression: bool,
+    /// Check received miniSEED records for corruption (miniSEED 2 header
+    /// sanity, miniSEED 3 CRC) and drop, flag or reject invalid ones.
+    /// Invalid records are counted in
+    /// [`SeedLinkClient::invalid_records()`](crate::SeedLinkClient::invalid_records).
+    /// Default: [`ValidationMode::Off`].
+    pub validation: ValidationMode,
+    /// Largest v4 frame payload accepted. A frame header announcing more
+    /// fails with [`SeedlinkError::PayloadTooLarge`](seedlink_rs_protocol::SeedlinkError::PayloadTooLarge)
+    /// before any buffer is allocated for it.
+    /// Default: [`v4::DEFAULT_MAX_PAYLOAD_LEN`](seedlink_rs_protocol::frame::v4::DEFAULT_MAX_PAYLOAD_LEN) (16 MiB).
+    pub max_payload_len: usize,
+    /// Reach the server through a SOCKS5 or HTTP `CONNECT` proxy. The
+    /// connect timeout covers the proxy handshake. Default: `None`.
+    pub proxy: Option<ProxyConfig>,
+    /// Local bind address or interface and TCP tuning for the connection
+    /// (to the proxy, if one is set). Default: system defaults.
+    pub socket: SocketOptions,
+    /// Report a [`ResumeGap`] when the first frame of a station armed with
+    /// [`data_from()`](crate::SeedLinkClient::data_from) skips more than this
+    /// many sequence numbers, i.e. records were evicted before the resume.
+    /// `Some(0)` suits servers numbering records per station (SeisComP);
+    /// servers with one sequence across all stations (ringserver,
+    /// seedlink-rs) also skip other stations' records, so allow for the
+    /// station's share of the traffic. `None` disables. Default: `None`.
+    pub resume_gap_threshold: Option<u64>,
 }
 
 impl Default for ClientConfig {
@@ -45,6 +114,14 @@ impl Default for ClientConfig {
             connect_timeout: Duration::from_secs(10),
             read_timeout: Duration::from_secs(30),
             prefer_v4: true,
+            protocol: ProtocolPreference::Auto,
+            user_agent: Some(format!("seedlink-rs/{}", env!("CARGO_PKG_VERSION"))),
+            compression: cfg!(feature = "zstd"),
+            validation: ValidationMode::Off,
+            max_payload_len: seedlink_rs_protocol::frame::v4::DEFAULT_MAX_PAYLOAD_LEN,
+            proxy: None,
+            socket: SocketOptions::default(),
+            resume_gap_threshold: None,
         }
     }
 }
@@ -58,8 +135,8 @@ pub struct ServerInfo {
     pub version: String,
     /// Server organization line.
     pub organization: String,
-    /// Advertised capabilities (e.g., `["SLPROTO:4.0", "SLPROTO:3.1"]`).
-    pub capabilities: Vec<String>,
+    /// Advertised capabilities (e.g., `SLPROTO:4.0 SLPROTO:3.1 CAP EXTREPLY`).
+    pub capabilities: Capabilities,
 }
 
 /// Network + station identifier used as a key for sequence tracking.
@@ -71,6 +148,62 @@ pub struct StationKey {
     pub station: String,
 }
 
+/// Records lost between a resume sequence and the first frame received after
+/// it, see [`ClientConfig::resume_gap_threshold`]. The missing range can be
+/// backfilled from an archive, e.g. an FDSN dataselect service.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResumeGap {
+    /// The station resumed.
+    pub station: StationKey,
+    /// Sequence passed to `data_from()`.
+    pub requested: SequenceNumber,
+    /// Sequence of the first frame received for the station.
+    pub first_received: SequenceNumber,
+}
+
+impl ResumeGap {
+    /// Sequence numbers skipped between `requested` and `first_received`,
+    /// counting across a v3 wrap.
+    pub fn missed(&self) -> u64 {
+        let (requested, first) = (self.requested.value(), self.first_received.value());
+        let distance = if first >= requested {
+            first - requested
+        } else {
+            first + SequenceNumber::V3_MAX + 1 - requested
+        };
+        distance.saturating_sub(1)
+    }
+}
+
+/// An item read while streaming: a frame, a text line the server sent
+/// between frames, or the end of the stream.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StreamEvent {
+    /// A v3 or v4 data frame.
+    Frame(OwnedFrame),
+    /// An INFO frame (v3 `SLINFO`, v4 subformat `I`), e.g. the reply to an
+    /// INFO request sent while streaming.
+    InfoFrame(OwnedFrame),
+    /// An INFO frame with an empty document: a server heartbeat.
+    KeepAlive,
+    /// `END`: the end of a FETCH window, or of a stream the server is closing.
+    EndOfFetch,
+    /// An `ERROR` line.
+    Error {
+        /// v4 error code, if the server sent one.
+        code: Option<ErrorCode>,
+        /// Error description (may be empty).
+        description: String,
+    },
+    /// Any other text line, without its terminator.
+    Text(String),
+    /// The connection is gone; always the last event of a stream.
+    Disconnected {
+        /// Why the stream ended (e.g. `"closed by server"`).
+        reason: String,
+    },
+}
+
 /// An owned SeedLink frame with its payload copied to the heap.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum OwnedFrame {
@@ -104,6 +237,18 @@ impl OwnedFrame {
         }
     }
 
+    /// Whether this frame's sequence comes after `tracked`.
+    ///
+    /// v3 sequences wrap at [`SequenceNumber::V3_MAX`], so v3 frames use the
+    /// wrap-aware [`SequenceNumber::is_after()`]; v4 sequences compare
+    /// numerically.
+    pub fn is_after(&self, tracked: SequenceNumber) -> bool {
+        match self {
+            Self::V3 { sequence, .. } => sequence.is_after(tracked, SequenceNumber::V3_WRAP_WINDOW),
+            Self::V4 { sequence, .. } => *sequence > tracked,
+        }
+    }
+
     /// Returns the payload bytes of this frame.
     pub fn payload(&self) -> &[u8] {
         match self {
@@ -146,6 +291,25 @@ impl OwnedFrame {
         }
     }
 
+    /// Whether this is an INFO frame rather than a data frame.
+    pub fn is_info(&self) -> bool {
+        match self {
+            Self::V3 { sequence, .. } => *sequence == SequenceNumber::UNSET,
+            Self::V4 { subformat, .. } => *subformat == PayloadSubformat::Info,
+        }
+    }
+
+    /// Whether this is an INFO frame without a document (payload empty or
+    /// all NUL), as sent for keepalives.
+    pub fn is_keepalive(&self) -> bool {
+        self.is_info() && self.payload().iter().all(|&b| b == 0)
+    }
+
+    /// Check the payload for corruption; see [`RawFrame::validate()`].
+    pub fn validate(&self) -> Result<(), InvalidRecord> {
+        self.as_raw_frame().validate()
+    }
+
     /// Decode the payload as a miniSEED record.
     ///
     /// Delegates to [`RawFrame::decode()`] on a borrowed view of this frame.
@@ -153,7 +317,14 @@ impl OwnedFrame {
         self.as_raw_frame().decode()
     }
 
-    fn as_raw_frame(&self) -> RawFrame<'_> {
+    /// Decode the payload into a [`SampleRecord`] with typed stream identifiers,
+    /// start time, sample rate, and decompressed samples.
+    pub fn decode_samples(&self) -> seedlink_rs_protocol::Result<SampleRecord> {
+        let frame = self.decode()?;
+        Ok(SampleRecord::from_mseed(frame.sequence, frame.record))
+    }
+
+    pub(crate) fn as_raw_frame(&self) -> RawFrame<'_> {
         match self {
             Self::V3 { sequence, payload } => RawFrame::V3 {
                 sequence: *sequence,
@@ -204,6 +375,21 @@ impl<'a> From<RawFrame<'a>> for OwnedFrame {
 mod tests {
     use super::*;
 
+    #[test]
+    fn resume_gap_missed_across_wrap() {
+        let gap = |requested, first_received| ResumeGap {
+            station: StationKey {
+                network: "IU".into(),
+                station: "ANMO".into(),
+            },
+            requested: SequenceNumber::new(requested),
+            first_received: SequenceNumber::new(first_received),
+        };
+        assert_eq!(gap(40, 41).missed(), 0);
+        assert_eq!(gap(40, 50).missed(), 9);
+        assert_eq!(gap(SequenceNumber::V3_MAX - 1, 2).missed(), 3);
+    }
+
     #[test]
     fn decode_zeroed_payload_returns_err() {
         let frame = OwnedFrame::V3 {
@@ -213,6 +399,124 @@ mod tests {
         assert!(frame.decode().is_err());
     }
 
+    #[test]
+    fn decode_samples_typed_fields() {
+        use crate::samples::SampleData;
+        use miniseed_rs::{EncodingFormat, MseedRecord, NanoTime, Samples};
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let record = MseedRecord::new()
+            .with_nslc("IU", "ANMO", "00", "BHZ")
+            .with_start_time(NanoTime {
+                year: 2024,
+                day: 1,
+                hour: 0,
+                minute: 0,
+                second: 1,
+                nanosecond: 0,
+            })
+            .with_sample_rate(20.0)
+            .with_encoding(EncodingFormat::Steim2)
+            .with_samples(Samples::Int(vec![1, -2, 3, 100, -100]));
+        let frame = OwnedFrame::V3 {
+            sequence: SequenceNumber::new(7),
+            payload: miniseed_rs::encode(&record).unwrap(),
+        };
+
+        let decoded = frame.decode_samples().unwrap();
+        assert_eq!(decoded.sequence, SequenceNumber::new(7));
+        assert_eq!(decoded.network, "IU");
+        assert_eq!(decoded.station, "ANMO");
+        assert_eq!(decoded.location, "00");
+        assert_eq!(decoded.channel, "BHZ");
+        assert_eq!(
+            decoded.start_time,
+            UNIX_EPOCH + Duration::from_secs(1_704_067_201)
+        );
+        assert_eq!(decoded.sample_rate, 20.0);
+        assert_eq!(decoded.samples, SampleData::Int(vec![1, -2, 3, 100, -100]));
+    }
+
+    #[test]
+    fn validate_checks_mseed2_and_mseed3_payloads() {
+        use miniseed_rs::{MseedRecord, NanoTime, Samples};
+
+        let start = NanoTime {
+            year: 2024,
+            day: 1,
+            hour: 0,
+            minute: 0,
+            second: 1,
+            nanosecond: 0,
+        };
+        let v2 = MseedRecord::new()
+            .with_nslc("IU", "ANMO", "00", "BHZ")
+            .with_start_time(start)
+            .with_samples(Samples::Int(vec![1, 2, 3]));
+        let frame = OwnedFrame::V3 {
+            sequence: SequenceNumber::new(1),
+            payload: miniseed_rs::encode(&v2).unwrap(),
+        };
+        assert_eq!(frame.validate(), Ok(()));
+
+        let v3 = MseedRecord::new_v3()
+            .with_nslc("IU", "ANMO", "00", "BHZ")
+            .with_start_time(start)
+            .with_samples(Samples::Int(vec![1, 2, 3]));
+        let mut payload = miniseed_rs::encode(&v3).unwrap();
+        let mut frame = OwnedFrame::V4 {
+            format: PayloadFormat::MiniSeed3,
+            subformat: PayloadSubformat::Data,
+            sequence: SequenceNumber::new(2),
+            station_id: "IU_ANMO".into(),
+            payload: payload.clone(),
+        };
+        assert_eq!(frame.validate(), Ok(()));
+
+        *payload.last_mut().unwrap() ^= 0xFF;
+        if let OwnedFrame::V4 { payload: p, .. } = &mut frame {
+            *p = payload;
+        }
+        assert!(matches!(frame.validate(), Err(InvalidRecord::Crc { .. })));
+
+        let zeroed = OwnedFrame::V3 {
+            sequence: SequenceNumber::new(3),
+            payload: vec![0u8; 512],
+        };
+        assert!(zeroed.validate().is_err());
+    }
+
+    #[test]
+    fn decode_samples_zeroed_payload_returns_err() {
+        let frame = OwnedFrame::V3 {
+            sequence: SequenceNumber::new(1),
+            payload: vec![0u8; 512],
+        };
+        assert!(frame.decode_samples().is_err());
+    }
+
+    #[test]
+    fn info_and_keepalive_frames() {
+        let data = OwnedFrame::V3 {
+            sequence: SequenceNumber::new(1),
+            payload: vec![0u8; 512],
+        };
+        assert!(!data.is_info());
+        let info = OwnedFrame::V3 {
+            sequence: SequenceNumber::UNSET,
+            payload: b"<seedlink/>".to_vec(),
+        };
+        assert!(info.is_info() && !info.is_keepalive());
+        let heartbeat = OwnedFrame::V4 {
+            format: PayloadFormat::Json,
+            subformat: PayloadSubformat::Info,
+            sequence: SequenceNumber::UNSET,
+            station_id: String::new(),
+            payload: Vec::new(),
+        };
+        assert!(heartbeat.is_keepalive());
+    }
+
     #[test]
     fn as_raw_frame_roundtrip() {
         let frame = OwnedFrame::V3 {
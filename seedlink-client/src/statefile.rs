@@ -0,0 +1,449 @@
+//! On-disk persistence of tracked sequence numbers.
+//!
+//! [`SequenceState`] reads and writes the classic libslink/slinktool state file
+//! format, one `NET STA SEQ [TIMESTAMP]` line per station, with the sequence
+//! number in decimal and the timestamp as `YYYY,MM,DD,hh,mm,ss`. State files
+//! written by `slinktool -x` or `slarchive -x` can be loaded directly, and files
+//! written here can be handed back to those tools.
+//!
+//! [`ReconnectingClient`](crate::ReconnectingClient) uses the same format when
+//! [`ReconnectConfig::statefile`](crate::ReconnectConfig::statefile) is set.
+
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::path::Path;
+
+use miniseed_rs::NanoTime;
+use seedlink_rs_protocol::SequenceNumber;
+
+use crate::error::Result;
+use crate::state::{OwnedFrame, StationKey};
+
+/// Resume position for one station.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Entry {
+    sequence: SequenceNumber,
+    timestamp: Option<String>,
+}
+
+/// Per-station resume positions, loadable from and savable to libslink state files.
+///
+/// ```no_run
+/// # fn example() -> seedlink_rs_client::Result<()> {
+/// use seedlink_rs_client::SequenceState;
+///
+/// let state = SequenceState::load_slink("slarchive.state")?;
+/// if let Some(seq) = state.sequence("IU", "ANMO") {
+///     println!("resume IU.ANMO from {seq}");
+/// }
+/// state.save_slink("slarchive.state")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SequenceState {
+    entries: HashMap<StationKey, Entry>,
+}
+
+impl SequenceState {
+    /// Create an empty state.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load a libslink/slinktool state file.
+    ///
+    /// Blank lines and `#` comments are skipped. The timestamp field is
+    /// optional. Entries with a negative sequence number (libslink's "unset")
+    /// are ignored.
+    pub fn load_slink(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)?;
+        Ok(Self::parse_slink(path, &text)?)
+    }
+
+    /// Atomically write a libslink/slinktool state file.
+    ///
+    /// The file is written to a temporary `<path>.tmp` and then renamed over
+    /// `path`, so a crash mid-write never leaves a truncated state file. Lines
+    /// are sorted by network and station.
+    pub fn save_slink(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let mut entries: Vec<_> = self
+            .entries
+            .iter()
+            .filter(|(_, e)| !e.sequence.is_special())
+            .collect();
+        entries.sort_by(|a, b| (&a.0.network, &a.0.station).cmp(&(&b.0.network, &b.0.station)));
+
+        let mut tmp_name = path.as_os_str().to_owned();
+        tmp_name.push(".tmp");
+        let tmp = Path::new(&tmp_name);
+
+        let mut file = std::fs::File::create(tmp)?;
+        for (key, entry) in entries {
+            write!(
+                file,
+                "{} {} {}",
+                key.network,
+                key.station,
+                entry.sequence.value()
+            )?;
+            if let Some(ts) = &entry.timestamp {
+                write!(file, " {ts}")?;
+            }
+            writeln!(file)?;
+        }
+        file.sync_all()?;
+        drop(file);
+        std::fs::rename(tmp, path)?;
+        Ok(())
+    }
+
+    /// Last sequence number for a network/station pair.
+    pub fn sequence(&self, network: &str, station: &str) -> Option<SequenceNumber> {
+        self.entries
+            .get(&Self::key(network, station))
+            .map(|e| e.sequence)
+    }
+
+    /// Last record timestamp (`YYYY,MM,DD,hh,mm,ss`) for a network/station pair.
+    pub fn timestamp(&self, network: &str, station: &str) -> Option<&str> {
+        self.entries
+            .get(&Self::key(network, station))
+            .and_then(|e| e.timestamp.as_deref())
+    }
+
+    /// Set the resume position for a station.
+    pub fn set(&mut self, key: StationKey, sequence: SequenceNumber, timestamp: Option<String>) {
+        self.entries.insert(
+            key,
+            Entry {
+                sequence,
+                timestamp,
+            },
+        );
+    }
+
+    /// Record a received frame: its sequence number and, if the payload
+    /// decodes, the start time of its record.
+    ///
+    /// Frames without a station identifier are ignored.
+    pub fn update(&mut self, frame: &OwnedFrame) {
+        let Some(key) = frame.station_key() else {
+            return;
+        };
+        let timestamp = frame
+            .decode()
+            .ok()
+            .map(|f| format_slink_time(&f.record.start_time));
+        self.set(key, frame.sequence(), timestamp);
+    }
+
+    /// Number of stations.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if no stations are tracked.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Copy out the station → sequence map, e.g. to compare with
+    /// [`ReconnectingClient::sequences()`](crate::ReconnectingClient::sequences).
+    pub fn sequences(&self) -> HashMap<StationKey, SequenceNumber> {
+        self.entries
+            .iter()
+            .map(|(k, e)| (k.clone(), e.sequence))
+            .collect()
+    }
+
+    fn key(network: &str, station: &str) -> StationKey {
+        StationKey {
+            network: network.to_owned(),
+            station: station.to_owned(),
+        }
+    }
+
+    fn parse_slink(path: &Path, text: &str) -> io::Result<Self> {
+        let mut state = Self::new();
+        for (lineno, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "{}:{}: invalid state line: {line}",
+                        path.display(),
+                        lineno + 1
+                    ),
+                )
+            };
+            let mut parts = line.split_whitespace();
+            let (Some(network), Some(station), Some(seq), timestamp, None) = (
+                parts.next(),
+                parts.next(),
+                parts.next(),
+                parts.next(),
+                parts.next(),
+            ) else {
+                return Err(invalid());
+            };
+            let seq: i64 = seq.parse().map_err(|_| invalid())?;
+            if seq < 0 {
+                continue;
+            }
+            state.set(
+                Self::key(network, station),
+                SequenceNumber::new(seq as u64),
+                timestamp.map(str::to_owned),
+            );
+        }
+        Ok(state)
+    }
+}
+
+/// Collect station → sequence pairs, e.g. from
+/// [`ReconnectingClient::sequences()`](crate::ReconnectingClient::sequences).
+/// Timestamps are left unset; use [`SequenceState::update()`] to record them.
+impl FromIterator<(StationKey, SequenceNumber)> for SequenceState {
+    fn from_iter<I: IntoIterator<Item = (StationKey, SequenceNumber)>>(iter: I) -> Self {
+        let mut state = Self::new();
+        for (key, seq) in iter {
+            state.set(key, seq, None);
+        }
+        state
+    }
+}
+
+/// Load a state file for [`ReconnectingClient`](crate::ReconnectingClient)
+/// and [`DialupCollector`](crate::DialupCollector). A missing file yields an
+/// empty state.
+pub(crate) fn load(path: &Path) -> Result<SequenceState> {
+    match SequenceState::load_slink(path) {
+        Ok(state) => Ok(state),
+        Err(crate::ClientError::Io(e)) if e.kind() == io::ErrorKind::NotFound => {
+            Ok(SequenceState::new())
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Write a state file with the sequences in `sequences`, keeping each
+/// station's timestamp from `state` as long as its sequence still matches.
+pub(crate) fn save(
+    path: &Path,
+    state: &SequenceState,
+    sequences: &HashMap<StationKey, SequenceNumber>,
+) -> Result<()> {
+    let mut out = SequenceState::new();
+    for (key, &sequence) in sequences {
+        let timestamp = state
+            .entries
+            .get(key)
+            .filter(|e| e.sequence == sequence)
+            .and_then(|e| e.timestamp.clone());
+        out.set(key.clone(), sequence, timestamp);
+    }
+    out.save_slink(path)
+}
+
+/// Format a miniSEED start time as libslink's `YYYY,MM,DD,hh,mm,ss`.
+fn format_slink_time(t: &NanoTime) -> String {
+    let leap =
+        (t.year.is_multiple_of(4) && !t.year.is_multiple_of(100)) || t.year.is_multiple_of(400);
+    let month_days = [
+        31,
+        if leap { 29 } else { 28 },
+        31,
+        30,
+        31,
+        30,
+        31,
+        31,
+        30,
+        31,
+        30,
+        31,
+    ];
+    let mut day = t.day;
+    let mut month = 1;
+    for len in month_days {
+        if day <= len {
+            break;
+        }
+        day -= len;
+        month += 1;
+    }
+    format!(
+        "{:04},{:02},{:02},{:02},{:02},{:02}",
+        t.year, month, day, t.hour, t.minute, t.second
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("seedlink-statefile-{}-{name}", std::process::id()))
+    }
+
+    fn key(network: &str, station: &str) -> StationKey {
+        StationKey {
+            network: network.to_owned(),
+            station: station.to_owned(),
+        }
+    }
+
+    #[test]
+    fn save_load_roundtrip() {
+        let path = temp_path("roundtrip");
+        let mut sequences = HashMap::new();
+        sequences.insert(key("IU", "ANMO"), SequenceNumber::new(11));
+        sequences.insert(key("GE", "WLF"), SequenceNumber::new(5));
+        sequences.insert(key("XX", "SKIP"), SequenceNumber::UNSET);
+
+        save(&path, &SequenceState::new(), &sequences).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(text, "GE WLF 5\nIU ANMO 11\n");
+
+        let loaded = load(&path).unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded.sequence("IU", "ANMO"), Some(SequenceNumber::new(11)));
+        assert_eq!(loaded.sequence("GE", "WLF"), Some(SequenceNumber::new(5)));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn save_keeps_timestamps_of_unchanged_sequences() {
+        let path = temp_path("keep-timestamps");
+        let mut state = SequenceState::new();
+        state.set(
+            key("IU", "ANMO"),
+            SequenceNumber::new(11),
+            Some("2024,01,15,10,30,00".into()),
+        );
+        state.set(
+            key("GE", "WLF"),
+            SequenceNumber::new(5),
+            Some("2024,01,15,10,29,00".into()),
+        );
+        let mut sequences = state.sequences();
+        sequences.insert(key("GE", "WLF"), SequenceNumber::new(6));
+
+        save(&path, &state, &sequences).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(text, "GE WLF 6\nIU ANMO 11 2024,01,15,10,30,00\n");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn load_missing_file_is_empty() {
+        let loaded = load(&temp_path("missing")).unwrap();
+        assert!(loaded.is_empty());
+    }
+
+    #[test]
+    fn load_skips_blank_and_comment_lines() {
+        let path = temp_path("comments");
+        std::fs::write(&path, "# saved state\n\nIU ANMO 42\n").unwrap();
+        let loaded = load(&path).unwrap();
+        assert_eq!(loaded.sequence("IU", "ANMO"), Some(SequenceNumber::new(42)));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn load_rejects_malformed_line() {
+        let path = temp_path("malformed");
+        std::fs::write(&path, "IU ANMO notanumber\n").unwrap();
+        let err = load(&path).unwrap_err();
+        assert!(
+            matches!(err, crate::ClientError::Io(ref e) if e.kind() == io::ErrorKind::InvalidData)
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn slink_roundtrip_with_timestamps() {
+        let path = temp_path("slink");
+        let text = "IU ANMO 1234 2024,03,01,12,30,15\nGE WLF 77\nXX UNSET -1 2024,01,01,00,00,00\n";
+        std::fs::write(&path, text).unwrap();
+
+        let state = SequenceState::load_slink(&path).unwrap();
+        assert_eq!(state.len(), 2);
+        assert_eq!(
+            state.sequence("IU", "ANMO"),
+            Some(SequenceNumber::new(1234))
+        );
+        assert_eq!(state.timestamp("IU", "ANMO"), Some("2024,03,01,12,30,15"));
+        assert_eq!(state.sequence("GE", "WLF"), Some(SequenceNumber::new(77)));
+        assert_eq!(state.timestamp("GE", "WLF"), None);
+        assert_eq!(state.sequence("XX", "UNSET"), None);
+
+        state.save_slink(&path).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "GE WLF 77\nIU ANMO 1234 2024,03,01,12,30,15\n"
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn load_slink_missing_file_is_error() {
+        let err = SequenceState::load_slink(temp_path("slink-missing")).unwrap_err();
+        assert!(matches!(err, crate::ClientError::Io(_)));
+    }
+
+    #[test]
+    fn slink_time_from_day_of_year() {
+        let t = NanoTime {
+            year: 2024,
+            day: 61,
+            hour: 12,
+            minute: 30,
+            second: 15,
+            nanosecond: 0,
+        };
+        assert_eq!(format_slink_time(&t), "2024,03,01,12,30,15");
+        let t = NanoTime {
+            year: 2023,
+            day: 365,
+            ..t
+        };
+        assert_eq!(format_slink_time(&t), "2023,12,31,12,30,15");
+    }
+
+    #[test]
+    fn update_records_sequence_and_start_time() {
+        use miniseed_rs::{EncodingFormat, MseedRecord, Samples};
+
+        let record = MseedRecord::new()
+            .with_nslc("IU", "ANMO", "00", "BHZ")
+            .with_start_time(NanoTime {
+                year: 2024,
+                day: 61,
+                hour: 12,
+                minute: 30,
+                second: 15,
+                nanosecond: 0,
+            })
+            .with_sample_rate(20.0)
+            .with_encoding(EncodingFormat::Steim2)
+            .with_samples(Samples::Int(vec![1, 2, 3]));
+        let frame = OwnedFrame::V3 {
+            sequence: SequenceNumber::new(9),
+            payload: miniseed_rs::encode(&record).unwrap(),
+        };
+
+        let mut state = SequenceState::new();
+        state.update(&frame);
+        assert_eq!(state.sequence("IU", "ANMO"), Some(SequenceNumber::new(9)));
+        assert_eq!(state.timestamp("IU", "ANMO"), Some("2024,03,01,12,30,15"));
+    }
+}
@@ -1,8 +1,42 @@
+use std::pin::pin;
+use std::time::{Duration, SystemTime};
+
 use futures_core::Stream;
+use seedlink_rs_protocol::DataFrame;
+use tracing::warn;
 
 use crate::SeedLinkClient;
 use crate::error::ClientError;
-use crate::state::OwnedFrame;
+use crate::header::RecordHeader;
+use crate::samples::nano_time_to_system_time;
+use crate::state::{ClientState, OwnedFrame, StreamEvent};
+
+/// What a record stream does with a frame whose payload cannot be decoded as
+/// miniSEED.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UndecodableFrames {
+    /// Yield [`ClientError::Protocol`] and carry on with the next frame.
+    #[default]
+    Error,
+    /// Log a warning and skip the frame.
+    Skip,
+}
+
+/// When a stream from [`SeedLinkClient::stream_until()`] ends.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopCondition {
+    /// After this many frames.
+    Frames(u64),
+    /// Once this many payload bytes were yielded; the frame reaching the
+    /// limit is included.
+    Bytes(u64),
+    /// At the first frame whose record starts at or after this time, which is
+    /// not yielded. The time is read from the record header without decoding
+    /// samples; frames without a miniSEED header never match.
+    Time(SystemTime),
+    /// Once this much time has passed since the stream was first polled.
+    Duration(Duration),
+}
 
 /// Convert a streaming [`SeedLinkClient`] into a [`Stream`] of frames.
 ///
@@ -21,13 +55,103 @@ pub fn frame_stream(
     }
 }
 
+/// Like [`frame_stream()`], but ends once `condition` is met and closes the
+/// client with [`close()`](SeedLinkClient::close) before yielding `None`.
+pub(crate) fn frame_stream_until(
+    mut client: SeedLinkClient,
+    condition: StopCondition,
+) -> impl Stream<Item = Result<OwnedFrame, ClientError>> {
+    async_stream::try_stream! {
+        let deadline = match condition {
+            StopCondition::Duration(d) => Some(tokio::time::Instant::now() + d),
+            _ => None,
+        };
+        let mut frames = 0u64;
+        let mut bytes = 0u64;
+        loop {
+            match condition {
+                StopCondition::Frames(n) if frames >= n => break,
+                StopCondition::Bytes(n) if bytes >= n => break,
+                _ => {}
+            }
+            let next = match deadline {
+                Some(deadline) => {
+                    match tokio::time::timeout_at(deadline, client.next_frame()).await {
+                        Ok(next) => next?,
+                        Err(_) => break,
+                    }
+                }
+                None => client.next_frame().await?,
+            };
+            let Some(frame) = next else {
+                break;
+            };
+            if let StopCondition::Time(end) = condition
+                && RecordHeader::parse(frame.payload())
+                    .is_some_and(|h| nano_time_to_system_time(&h.start_time) >= end)
+            {
+                break;
+            }
+            frames += 1;
+            bytes += frame.payload().len() as u64;
+            yield frame;
+        }
+        client.close().await?;
+    }
+}
+
+/// Convert a streaming [`SeedLinkClient`] into a [`Stream`] of events.
+///
+/// Yields every [`StreamEvent`] from
+/// [`next_event()`](SeedLinkClient::next_event) and terminates once the client
+/// leaves the `Streaming` state: after [`StreamEvent::Disconnected`], or after
+/// the `END` of a v4 dial-up FETCH window.
+pub fn event_stream(
+    mut client: SeedLinkClient,
+) -> impl Stream<Item = Result<StreamEvent, ClientError>> {
+    async_stream::try_stream! {
+        while client.state() == ClientState::Streaming {
+            yield client.next_event().await?;
+        }
+    }
+}
+
+/// Decode every frame of `frames` into a [`DataFrame`].
+///
+/// Connection errors from `frames` are passed through; undecodable frames are
+/// handled per `undecodable`.
+pub(crate) fn record_stream(
+    frames: impl Stream<Item = Result<OwnedFrame, ClientError>>,
+    undecodable: UndecodableFrames,
+) -> impl Stream<Item = Result<DataFrame, ClientError>> {
+    async_stream::stream! {
+        let mut frames = pin!(frames);
+        while let Some(frame) = std::future::poll_fn(|cx| frames.as_mut().poll_next(cx)).await {
+            let frame = match frame {
+                Ok(frame) => frame,
+                Err(e) => {
+                    yield Err(e);
+                    continue;
+                }
+            };
+            match frame.decode() {
+                Ok(record) => yield Ok(record),
+                Err(error) if undecodable == UndecodableFrames::Skip => {
+                    warn!(sequence = %frame.sequence(), %error, "skipping undecodable frame");
+                }
+                Err(error) => yield Err(error.into()),
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::mock::{MockConfig, MockServer};
     use seedlink_rs_protocol::SequenceNumber;
     use seedlink_rs_protocol::frame::v3;
-    use std::pin::pin;
+    use seedlink_rs_protocol::testing::TestRecordBuilder;
     use tokio_stream::StreamExt;
 
     fn make_v3_frame(seq: u64, station: &str, network: &str) -> Vec<u8> {
@@ -105,6 +229,99 @@ mod tests {
         assert!(end.is_none());
     }
 
+    #[tokio::test]
+    async fn stream_until_stops_and_sends_bye() {
+        async fn sequences(condition: StopCondition) -> (Vec<u64>, Vec<String>) {
+            let frames = (1..=4)
+                .map(|seq| make_v3_frame(seq, "ANMO", "IU"))
+                .collect();
+            let server = MockServer::start(MockConfig::v3_default(frames)).await;
+            let mut client = SeedLinkClient::connect(&server.addr().to_string())
+                .await
+                .unwrap();
+            client.station("ANMO", "IU").await.unwrap();
+            client.data().await.unwrap();
+            client.end_stream().await.unwrap();
+            let sequences = client
+                .stream_until(condition)
+                .map(|f| f.unwrap().sequence().value())
+                .collect()
+                .await;
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            (sequences, server.captured().connection(0))
+        }
+
+        let (frames, commands) = sequences(StopCondition::Frames(2)).await;
+        assert_eq!(frames, [1, 2]);
+        assert_eq!(commands.last().map(String::as_str), Some("BYE"));
+
+        // 520-byte v3 frames: the second one reaches 600 bytes
+        let (frames, _) = sequences(StopCondition::Bytes(600)).await;
+        assert_eq!(frames, [1, 2]);
+
+        // The mock keeps the connection open after the last frame
+        let (frames, commands) =
+            sequences(StopCondition::Duration(Duration::from_millis(200))).await;
+        assert_eq!(frames, [1, 2, 3, 4]);
+        assert_eq!(commands.last().map(String::as_str), Some("BYE"));
+    }
+
+    #[tokio::test]
+    async fn stream_until_time_stops_before_later_record() {
+        // Records start 10 s apart from 2024-01-01T00:00:10
+        let frames = (1..=4)
+            .map(|seq| {
+                let payload = TestRecordBuilder::new()
+                    .btime(2024, 1, 0, 0, seq as u8 * 10, 0)
+                    .build();
+                v3::write(SequenceNumber::new(seq), &payload).unwrap()
+            })
+            .collect();
+        let server = MockServer::start(MockConfig::v3_default(frames)).await;
+        let mut client = SeedLinkClient::connect(&server.addr().to_string())
+            .await
+            .unwrap();
+        client.station("ANMO", "IU").await.unwrap();
+        client.data().await.unwrap();
+        client.end_stream().await.unwrap();
+
+        let end = SystemTime::UNIX_EPOCH + Duration::from_secs(1_704_067_200 + 25);
+        let frames: Vec<u64> = client
+            .stream_until(StopCondition::Time(end))
+            .map(|f| f.unwrap().sequence().value())
+            .collect()
+            .await;
+        assert_eq!(frames, [1, 2]);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(
+            server.captured().connection(0).last().map(String::as_str),
+            Some("BYE")
+        );
+    }
+
+    #[tokio::test]
+    async fn event_stream_ends_with_disconnected() {
+        let config = MockConfig {
+            close_after_stream: true,
+            ..MockConfig::v3_default(vec![make_v3_frame(1, "ANMO", "IU"), b"END\r\n".to_vec()])
+        };
+        let server = MockServer::start(config).await;
+
+        let mut client = SeedLinkClient::connect(&server.addr().to_string())
+            .await
+            .unwrap();
+
+        client.station("ANMO", "IU").await.unwrap();
+        client.data().await.unwrap();
+        client.fetch().await.unwrap();
+
+        let events: Vec<StreamEvent> = event_stream(client).map(|e| e.unwrap()).collect().await;
+        assert_eq!(events.len(), 3);
+        assert!(matches!(&events[0], StreamEvent::Frame(_)));
+        assert_eq!(events[1], StreamEvent::EndOfFetch);
+        assert!(matches!(&events[2], StreamEvent::Disconnected { .. }));
+    }
+
     #[tokio::test]
     async fn stream_collect_all() {
         let frames = vec![
@@ -138,4 +355,41 @@ mod tests {
             SequenceNumber::new(12)
         );
     }
+
+    #[tokio::test]
+    async fn record_stream_decodes_or_skips_bad_frames() {
+        async fn records(undecodable: UndecodableFrames) -> Vec<Result<DataFrame, ClientError>> {
+            let good = |seq| {
+                let payload = TestRecordBuilder::new().samples(&[1, 2, 3]).build();
+                v3::write(SequenceNumber::new(seq), &payload).unwrap()
+            };
+            let config = MockConfig {
+                close_after_stream: true,
+                ..MockConfig::v3_default(vec![good(1), make_v3_frame(2, "ANMO", "IU"), good(3)])
+            };
+            let server = MockServer::start(config).await;
+            let mut client = SeedLinkClient::connect(&server.addr().to_string())
+                .await
+                .unwrap();
+            client.station("ANMO", "IU").await.unwrap();
+            client.data().await.unwrap();
+            client.end_stream().await.unwrap();
+            client.into_record_stream(undecodable).collect().await
+        }
+
+        let all = records(UndecodableFrames::Error).await;
+        assert_eq!(all.len(), 3);
+        let record = all[0].as_ref().unwrap();
+        assert_eq!(record.sequence, SequenceNumber::new(1));
+        assert_eq!(record.record.station, "ANMO");
+        assert!(matches!(all[1], Err(ClientError::Protocol(_))));
+        assert_eq!(all[2].as_ref().unwrap().sequence, SequenceNumber::new(3));
+
+        let sequences: Vec<_> = records(UndecodableFrames::Skip)
+            .await
+            .into_iter()
+            .map(|r| r.unwrap().sequence.value())
+            .collect();
+        assert_eq!(sequences, [1, 3]);
+    }
 }
@@ -28,6 +28,7 @@ async fn v3_hello() {
         prefer_v4: false,
         connect_timeout: Duration::from_secs(15),
         read_timeout: Duration::from_secs(30),
+        ..ClientConfig::default()
     };
     let client = SeedLinkClient::connect_with_config(&addr, config)
         .await
@@ -53,6 +54,7 @@ async fn v3_station_stream() {
         prefer_v4: false,
         connect_timeout: Duration::from_secs(15),
         read_timeout: Duration::from_secs(60),
+        ..ClientConfig::default()
     };
     let mut client = SeedLinkClient::connect_with_config(&addr, config)
         .await
@@ -112,6 +114,7 @@ async fn v4_negotiate_and_stream() {
         prefer_v4: true,
         connect_timeout: Duration::from_secs(15),
         read_timeout: Duration::from_secs(60),
+        ..ClientConfig::default()
     };
     let mut client = SeedLinkClient::connect_with_config(&addr, config)
         .await
@@ -158,6 +161,7 @@ async fn v3_info_id() {
         prefer_v4: false,
         connect_timeout: Duration::from_secs(15),
         read_timeout: Duration::from_secs(30),
+        ..ClientConfig::default()
     };
     let mut client = SeedLinkClient::connect_with_config(&addr, config)
         .await
@@ -192,6 +196,7 @@ async fn v3_data_resume_no_data_loss() {
         prefer_v4: false,
         connect_timeout: Duration::from_secs(15),
         read_timeout: Duration::from_secs(120),
+        ..ClientConfig::default()
     };
 
     // --- Connection 1: get some frames and record last sequence ---
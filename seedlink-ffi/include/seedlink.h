@@ -0,0 +1,64 @@
+/*
+ * C interface to the seedlink-rs client (crate seedlink-rs-ffi).
+ *
+ * Follows libslink's call pattern: create a descriptor, add streams,
+ * call sl_collect() in a loop, sl_terminate() to stop. See
+ * seedlink-ffi/src/lib.rs for the full documentation of each function.
+ */
+
+#ifndef SEEDLINK_RS_H
+#define SEEDLINK_RS_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* sl_collect() results */
+#define SLTERMINATE 0
+#define SLPACKET    1
+
+/* Opaque connection descriptor */
+typedef struct SLCD SLCD;
+
+/* A received packet, valid until the next sl_collect() or sl_freeslcd() */
+typedef struct SLpacket {
+  uint64_t       seqnum;        /* Sequence number */
+  const uint8_t *payload;       /* 512-byte miniSEED record (v3) or v4 payload */
+  size_t         payloadlength; /* Length of payload in bytes */
+} SLpacket;
+
+/* Returns NULL if the descriptor cannot be created */
+SLCD *sl_newslcd (void);
+void sl_freeslcd (SLCD *slconn);
+
+/* "host:port"; returns 0 or -1 */
+int sl_set_serveraddress (SLCD *slconn, const char *address);
+
+/* selectors: NULL or space-separated SELECT patterns
+ * seqnum: resume with DATA seq, or -1 for new data
+ * timestamp: NULL or TIME start "YYYY,MM,DD,hh,mm,ss" (v3), used if seqnum is -1
+ * Returns 0 or -1 */
+int sl_add_stream (SLCD *slconn, const char *network, const char *station,
+                   const char *selectors, int64_t seqnum, const char *timestamp);
+
+/* Optional: sl_collect() connects if needed. Returns 0 or -1 */
+int sl_connect (SLCD *slconn);
+
+/* Blocks for the next packet. Returns SLPACKET or SLTERMINATE */
+int sl_collect (SLCD *slconn, const SLpacket **slpack);
+
+/* Makes sl_collect() return SLTERMINATE within 100 ms; only sets a
+ * flag, so safe from another thread or a signal handler */
+void sl_terminate (SLCD *slconn);
+
+/* Last failure message or NULL, valid until the next call on slconn */
+const char *sl_lasterror (SLCD *slconn);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* SEEDLINK_RS_H */
@@ -0,0 +1,547 @@
+//! C ABI for the SeedLink client, following libslink's call pattern so
+//! existing C/C++ acquisition software can switch with few changes.
+//!
+//! ```c
+//! #include "seedlink.h"
+//!
+//! SLCD *slconn = sl_newslcd();
+//! sl_set_serveraddress(slconn, "rtserve.iris.washington.edu:18000");
+//! sl_add_stream(slconn, "IU", "ANMO", "BHZ", -1, NULL);
+//!
+//! const SLpacket *packet;
+//! while (sl_collect(slconn, &packet) == SLPACKET) {
+//!     handle_record(packet->payload, packet->payloadlength);
+//! }
+//! if (sl_lasterror(slconn))
+//!     fprintf(stderr, "%s\n", sl_lasterror(slconn));
+//! sl_freeslcd(slconn);
+//! ```
+//!
+//! Each `SLCD` owns a [`ReconnectingClient`] driven on a private
+//! single-threaded tokio runtime, so lost connections are re-established
+//! and resumed per station like libslink does. The declarations are in
+//! `include/seedlink.h`.
+
+use std::ffi::{CStr, CString, c_char, c_int};
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+use seedlink_rs_client::{
+    ClientConfig, ClientError, OwnedFrame, ReconnectConfig, ReconnectingClient,
+};
+use seedlink_rs_protocol::SequenceNumber;
+use tokio::runtime::Runtime;
+
+/// [`sl_collect()`] result: terminated, or failed (see [`sl_lasterror()`]).
+pub const SLTERMINATE: c_int = 0;
+/// [`sl_collect()`] result: a packet was received.
+pub const SLPACKET: c_int = 1;
+
+/// How often a waiting [`sl_collect()`] checks for [`sl_terminate()`].
+const TERMINATE_POLL: Duration = Duration::from_millis(100);
+
+/// A received packet, valid until the next [`sl_collect()`] or
+/// [`sl_freeslcd()`] on the same connection.
+#[repr(C)]
+pub struct SLpacket {
+    /// Sequence number of the packet.
+    pub seqnum: u64,
+    /// Packet payload: a 512-byte miniSEED v2 record on v3, the frame
+    /// payload on v4.
+    pub payload: *const u8,
+    /// Length of `payload` in bytes.
+    pub payloadlength: usize,
+}
+
+/// A SeedLink connection descriptor, created by [`sl_newslcd()`].
+pub struct SLCD {
+    terminate: AtomicBool,
+    state: Mutex<State>,
+}
+
+struct State {
+    runtime: Runtime,
+    address: Option<String>,
+    streams: Vec<StreamRequest>,
+    client: Option<ReconnectingClient>,
+    frame: Option<OwnedFrame>,
+    packet: SLpacket,
+    error: Option<CString>,
+}
+
+/// One [`sl_add_stream()`] request, replayed on [`sl_connect()`].
+struct StreamRequest {
+    network: String,
+    station: String,
+    selectors: Vec<String>,
+    seqnum: Option<SequenceNumber>,
+    timestamp: Option<String>,
+}
+
+impl SLCD {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl State {
+    fn set_error(&mut self, message: impl ToString) {
+        self.error = CString::new(message.to_string()).ok();
+    }
+
+    /// Drop the client, sending `BYE` first if it is connected.
+    fn disconnect(&mut self) {
+        if let Some(client) = self.client.take() {
+            // Best effort: the connection is closed either way
+            let _ = self.runtime.block_on(client.close());
+        }
+    }
+
+    /// Connect, subscribe every stream and start streaming.
+    fn connect(&mut self) -> Result<(), ClientError> {
+        let Some(address) = &self.address else {
+            return Err(ClientError::InvalidState {
+                expected: "server address set",
+                actual: "no server address",
+            });
+        };
+        if self.streams.is_empty() {
+            return Err(ClientError::InvalidState {
+                expected: "streams added",
+                actual: "no streams",
+            });
+        }
+        let client = self.runtime.block_on(async {
+            let mut client = ReconnectingClient::connect_with_config(
+                address,
+                ClientConfig::default(),
+                ReconnectConfig::default(),
+            )
+            .await?;
+            for stream in &self.streams {
+                client.station(&stream.station, &stream.network).await?;
+                for selector in &stream.selectors {
+                    client.select(selector).await?;
+                }
+                match (stream.seqnum, &stream.timestamp) {
+                    (Some(seqnum), _) => client.data_from(seqnum).await?,
+                    (None, Some(start)) => client.time_window(start, None).await?,
+                    (None, None) => client.data().await?,
+                }
+            }
+            client.end_stream().await?;
+            Ok::<_, ClientError>(client)
+        })?;
+        self.client = Some(client);
+        Ok(())
+    }
+}
+
+impl Drop for State {
+    /// Send `BYE` while the runtime is still there to send it.
+    fn drop(&mut self) {
+        self.disconnect();
+    }
+}
+
+/// Read a C string argument. `None` for NULL or invalid UTF-8.
+///
+/// # Safety
+///
+/// `ptr` must be NULL or point to a NUL-terminated string.
+unsafe fn read_str(ptr: *const c_char) -> Option<String> {
+    if ptr.is_null() {
+        return None;
+    }
+    // SAFETY: non-NULL and NUL-terminated per the caller's contract.
+    let s = unsafe { CStr::from_ptr(ptr) };
+    s.to_str().ok().map(str::to_owned)
+}
+
+/// Allocate a connection descriptor. Returns NULL if the internal runtime
+/// cannot be created. Free it with [`sl_freeslcd()`].
+#[unsafe(no_mangle)]
+pub extern "C" fn sl_newslcd() -> *mut SLCD {
+    let Ok(runtime) = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    else {
+        return ptr::null_mut();
+    };
+    let slcd = SLCD {
+        terminate: AtomicBool::new(false),
+        state: Mutex::new(State {
+            runtime,
+            address: None,
+            streams: Vec::new(),
+            client: None,
+            frame: None,
+            packet: SLpacket {
+                seqnum: 0,
+                payload: ptr::null(),
+                payloadlength: 0,
+            },
+            error: None,
+        }),
+    };
+    Box::into_raw(Box::new(slcd))
+}
+
+/// Close the connection (sending `BYE` if connected) and free `slconn`.
+///
+/// # Safety
+///
+/// `slconn` must be NULL or returned by [`sl_newslcd()`] and not yet freed,
+/// and no other call may be using it.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sl_freeslcd(slconn: *mut SLCD) {
+    if !slconn.is_null() {
+        // SAFETY: allocated by sl_newslcd() and exclusively owned per the contract.
+        drop(unsafe { Box::from_raw(slconn) });
+    }
+}
+
+/// Set the server address as `host:port`. Returns 0, or -1 if an argument is
+/// invalid.
+///
+/// # Safety
+///
+/// `slconn` must be NULL or a live descriptor; `address` must be NULL or a
+/// NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sl_set_serveraddress(slconn: *mut SLCD, address: *const c_char) -> c_int {
+    // SAFETY: NULL or a live descriptor per the contract.
+    let Some(slcd) = (unsafe { slconn.as_ref() }) else {
+        return -1;
+    };
+    // SAFETY: NULL or NUL-terminated per the contract.
+    let Some(address) = (unsafe { read_str(address) }) else {
+        return -1;
+    };
+    slcd.lock().address = Some(address);
+    0
+}
+
+/// Add a station to request, like libslink's `sl_add_stream()`.
+///
+/// - `selectors`: NULL, or space-separated `SELECT` patterns (e.g. `"BHZ HH?"`)
+/// - `seqnum`: resume from this sequence number with `DATA seq`; -1 for new data
+/// - `timestamp`: NULL, or a `TIME` start (`YYYY,MM,DD,hh,mm,ss`, v3 servers)
+///   used when `seqnum` is -1
+///
+/// Streams are sent on the next [`sl_connect()`]. Returns 0, or -1 if an
+/// argument is invalid.
+///
+/// # Safety
+///
+/// `slconn` must be NULL or a live descriptor; the string arguments must be
+/// NULL or NUL-terminated.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sl_add_stream(
+    slconn: *mut SLCD,
+    network: *const c_char,
+    station: *const c_char,
+    selectors: *const c_char,
+    seqnum: i64,
+    timestamp: *const c_char,
+) -> c_int {
+    // SAFETY: NULL or a live descriptor per the contract.
+    let Some(slcd) = (unsafe { slconn.as_ref() }) else {
+        return -1;
+    };
+    // SAFETY: each string is NULL or NUL-terminated per the contract.
+    let (network, station, selectors, timestamp) = unsafe {
+        (
+            read_str(network),
+            read_str(station),
+            read_str(selectors),
+            read_str(timestamp),
+        )
+    };
+    let (Some(network), Some(station)) = (network, station) else {
+        return -1;
+    };
+    let seqnum = match u64::try_from(seqnum) {
+        Ok(seq) => Some(SequenceNumber::new(seq)),
+        Err(_) if seqnum == -1 => None,
+        Err(_) => return -1,
+    };
+    slcd.lock().streams.push(StreamRequest {
+        network,
+        station,
+        selectors: selectors
+            .iter()
+            .flat_map(|s| s.split_whitespace())
+            .map(str::to_owned)
+            .collect(),
+        seqnum,
+        timestamp,
+    });
+    0
+}
+
+/// Connect, send every stream added with [`sl_add_stream()`] and start
+/// streaming. Called by [`sl_collect()`] when not yet connected. Returns 0,
+/// or -1 on failure (see [`sl_lasterror()`]).
+///
+/// # Safety
+///
+/// `slconn` must be NULL or a live descriptor.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sl_connect(slconn: *mut SLCD) -> c_int {
+    // SAFETY: NULL or a live descriptor per the contract.
+    let Some(slcd) = (unsafe { slconn.as_ref() }) else {
+        return -1;
+    };
+    let mut state = slcd.lock();
+    match state.connect() {
+        Ok(()) => 0,
+        Err(e) => {
+            state.set_error(e);
+            -1
+        }
+    }
+}
+
+/// Wait for the next packet and point `*slpack` at it.
+///
+/// Returns [`SLPACKET`], or [`SLTERMINATE`] after [`sl_terminate()`] or when
+/// the connection fails for good (see [`sl_lasterror()`]). Connects first if
+/// needed; dropped connections are re-established and resumed.
+///
+/// # Safety
+///
+/// `slconn` must be NULL or a live descriptor; `slpack` must be NULL or
+/// valid for writes. Only one `sl_collect()` may run per descriptor.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sl_collect(slconn: *mut SLCD, slpack: *mut *const SLpacket) -> c_int {
+    // SAFETY: NULL or a live descriptor per the contract.
+    let Some(slcd) = (unsafe { slconn.as_ref() }) else {
+        return SLTERMINATE;
+    };
+    if slpack.is_null() {
+        return SLTERMINATE;
+    }
+    let mut guard = slcd.lock();
+    let state = &mut *guard;
+    if slcd.terminate.load(Ordering::Acquire) {
+        state.disconnect();
+        return SLTERMINATE;
+    }
+    if state.client.is_none()
+        && let Err(e) = state.connect()
+    {
+        state.set_error(e);
+        return SLTERMINATE;
+    }
+    let Some(client) = state.client.as_mut() else {
+        return SLTERMINATE;
+    };
+    // next_frame() is cancel-safe, so sl_terminate() can interrupt the wait.
+    // The flag is polled: setting it is all sl_terminate() may do.
+    let terminated = async {
+        while !slcd.terminate.load(Ordering::Acquire) {
+            tokio::time::sleep(TERMINATE_POLL).await;
+        }
+    };
+    let received = state.runtime.block_on(async {
+        tokio::select! {
+            biased;
+            () = terminated => None,
+            result = client.next_frame() => Some(result),
+        }
+    });
+    let frame = match received {
+        None => {
+            state.disconnect();
+            return SLTERMINATE;
+        }
+        Some(Ok(Some(frame))) => frame,
+        Some(Ok(None)) => {
+            state.disconnect();
+            return SLTERMINATE;
+        }
+        Some(Err(e)) => {
+            state.set_error(e);
+            state.disconnect();
+            return SLTERMINATE;
+        }
+    };
+    let frame = state.frame.insert(frame);
+    state.packet = SLpacket {
+        seqnum: frame.sequence().value(),
+        payload: frame.payload().as_ptr(),
+        payloadlength: frame.payload().len(),
+    };
+    // SAFETY: non-NULL and valid for writes per the contract.
+    unsafe { slpack.write(&state.packet) };
+    SLPACKET
+}
+
+/// Make [`sl_collect()`] return [`SLTERMINATE`]; a waiting `sl_collect()`
+/// notices within 100 ms.
+///
+/// Only sets an atomic flag, so it is safe to call from another thread or
+/// from a signal handler, like libslink's.
+///
+/// # Safety
+///
+/// `slconn` must be NULL or a live descriptor.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sl_terminate(slconn: *mut SLCD) {
+    // SAFETY: NULL or a live descriptor per the contract.
+    if let Some(slcd) = unsafe { slconn.as_ref() } {
+        slcd.terminate.store(true, Ordering::Release);
+    }
+}
+
+/// The message of the last failure on `slconn`, or NULL if none. Valid until
+/// the next call on `slconn`.
+///
+/// # Safety
+///
+/// `slconn` must be NULL or a live descriptor.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sl_lasterror(slconn: *mut SLCD) -> *const c_char {
+    // SAFETY: NULL or a live descriptor per the contract.
+    let Some(slcd) = (unsafe { slconn.as_ref() }) else {
+        return ptr::null();
+    };
+    slcd.lock()
+        .error
+        .as_ref()
+        .map_or(ptr::null(), |error| error.as_ptr())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use seedlink_rs_client::mock::{MockConfig, MockServer};
+    use seedlink_rs_protocol::frame::v3;
+    use seedlink_rs_protocol::testing::TestRecordBuilder;
+
+    fn make_v3_frame(seq: u64) -> Vec<u8> {
+        let payload = TestRecordBuilder::new()
+            .station("ANMO")
+            .network("IU")
+            .build();
+        v3::write(SequenceNumber::new(seq), &payload).unwrap()
+    }
+
+    /// A descriptor for `server` with one stream added.
+    fn new_slcd(server: &MockServer, selectors: &CStr, seqnum: i64) -> *mut SLCD {
+        let address = CString::new(server.addr().to_string()).unwrap();
+        let slconn = sl_newslcd();
+        assert!(!slconn.is_null());
+        unsafe {
+            assert_eq!(sl_set_serveraddress(slconn, address.as_ptr()), 0);
+            let added = sl_add_stream(
+                slconn,
+                c"IU".as_ptr(),
+                c"ANMO".as_ptr(),
+                selectors.as_ptr(),
+                seqnum,
+                ptr::null(),
+            );
+            assert_eq!(added, 0);
+        }
+        slconn
+    }
+
+    #[test]
+    fn collect_packets_then_terminate() {
+        // The mock server runs on its own runtime, outside the descriptor's
+        let server_rt = Runtime::new().unwrap();
+        let frames = vec![make_v3_frame(1), make_v3_frame(2)];
+        let server = server_rt.block_on(MockServer::start(MockConfig::v3_default(frames)));
+        let slconn = new_slcd(&server, c"BHZ HH?", 0x10);
+
+        let mut packet: *const SLpacket = ptr::null();
+        for seq in [1, 2] {
+            assert_eq!(unsafe { sl_collect(slconn, &mut packet) }, SLPACKET);
+            let packet = unsafe { &*packet };
+            assert_eq!(packet.seqnum, seq);
+            assert_eq!(packet.payloadlength, 512);
+            let payload = unsafe { std::slice::from_raw_parts(packet.payload, 512) };
+            assert_eq!(&payload[8..12], b"ANMO");
+        }
+
+        // Terminate while sl_collect() waits for a packet that never comes
+        let handle = slconn as usize;
+        let terminator = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            unsafe { sl_terminate(handle as *mut SLCD) };
+        });
+        assert_eq!(unsafe { sl_collect(slconn, &mut packet) }, SLTERMINATE);
+        terminator.join().unwrap();
+        assert!(unsafe { sl_lasterror(slconn) }.is_null());
+        unsafe { sl_freeslcd(slconn) };
+
+        // Terminating sends BYE; wait for the server to see it
+        for _ in 0..100 {
+            if server
+                .captured()
+                .connection(0)
+                .last()
+                .is_some_and(|c| c == "BYE")
+            {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        let cmds = server.captured().connection(0);
+        assert_eq!(
+            cmds[1..],
+            [
+                "STATION ANMO IU",
+                "SELECT BHZ",
+                "SELECT HH?",
+                "DATA 000010",
+                "END",
+                "BYE"
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_arguments_and_connect_errors() {
+        let slconn = sl_newslcd();
+        unsafe {
+            assert_eq!(sl_set_serveraddress(slconn, ptr::null()), -1);
+            let bad = sl_add_stream(
+                slconn,
+                c"IU".as_ptr(),
+                ptr::null(),
+                ptr::null(),
+                -1,
+                ptr::null(),
+            );
+            assert_eq!(bad, -1);
+            let bad = sl_add_stream(
+                slconn,
+                c"IU".as_ptr(),
+                c"ANMO".as_ptr(),
+                ptr::null(),
+                -2,
+                ptr::null(),
+            );
+            assert_eq!(bad, -1);
+
+            // No address and no streams yet
+            assert_eq!(sl_connect(slconn), -1);
+            let error = CStr::from_ptr(sl_lasterror(slconn));
+            assert!(error.to_str().unwrap().contains("no server address"));
+
+            let mut packet: *const SLpacket = ptr::null();
+            assert_eq!(sl_collect(slconn, &mut packet), SLTERMINATE);
+            assert!(packet.is_null());
+            sl_freeslcd(slconn);
+
+            assert_eq!(sl_collect(ptr::null_mut(), &mut packet), SLTERMINATE);
+            sl_terminate(ptr::null_mut());
+            sl_freeslcd(ptr::null_mut());
+        }
+    }
+}
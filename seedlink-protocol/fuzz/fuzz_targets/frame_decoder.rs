@@ -0,0 +1,23 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use seedlink_rs_protocol::FrameDecoder;
+
+fuzz_target!(|data: &[u8]| {
+    // First byte picks the chunk size so partial frames are exercised
+    let Some((&chunk, rest)) = data.split_first() else {
+        return;
+    };
+    let mut decoder = FrameDecoder::with_max_frame_len(64 * 1024);
+    for piece in rest.chunks(usize::from(chunk).max(1)) {
+        decoder.push(piece);
+        loop {
+            match decoder.next_frame() {
+                Ok(Some(_)) => {}
+                Ok(None) => break,
+                Err(_) => return,
+            }
+        }
+    }
+    assert!(decoder.buffered() <= rest.len());
+});
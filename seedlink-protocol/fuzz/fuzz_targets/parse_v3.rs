@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use seedlink_rs_protocol::frame::v3;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(frame) = v3::parse(data) {
+        assert_eq!(frame.payload().len(), v3::PAYLOAD_LEN);
+    }
+    let _ = v3::info_continues(data);
+});
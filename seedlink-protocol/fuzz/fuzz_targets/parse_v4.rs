@@ -0,0 +1,27 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use seedlink_rs_protocol::frame::v4::{self, Partial};
+
+/// Small enough that the fuzzer reaches the limit.
+const MAX_PAYLOAD_LEN: usize = 4096;
+
+fuzz_target!(|data: &[u8]| {
+    let header = v4::parse_header(data);
+    let full = v4::parse(data);
+    if let Ok((frame, consumed)) = &full {
+        let header = header.as_ref().expect("parsed frame without a header");
+        assert_eq!(*consumed, header.frame_len());
+        assert_eq!(frame.payload().len(), header.payload_len);
+    }
+
+    match v4::parse_partial(data, MAX_PAYLOAD_LEN) {
+        Ok(Partial::Frame(frame, consumed)) => {
+            assert!(consumed <= data.len());
+            assert!(frame.payload().len() <= MAX_PAYLOAD_LEN);
+            assert_eq!(full.ok(), Some((frame, consumed)));
+        }
+        Ok(Partial::Incomplete { needed }) => assert!(needed > data.len()),
+        Err(_) => {}
+    }
+});
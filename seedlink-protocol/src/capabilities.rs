@@ -0,0 +1,253 @@
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::version::ProtocolVersion;
+
+/// Capabilities advertised in a HELLO response.
+///
+/// HELLO line 1 lists capabilities after `::`, e.g.
+/// `"SeedLink v3.1 (2020.075) :: SLPROTO:4.0 SLPROTO:3.1 CAP EXTREPLY NSWILDCARD WS:13"`.
+/// Tokens are classified as:
+/// - `SLPROTO:x.y` — a supported protocol version
+/// - `KEY:value` — a parameter (e.g. `WS:13`); `CAP:FLAG` is a flag
+/// - `CAP` — a marker, ignored
+/// - anything else — a flag (e.g. `EXTREPLY`, `BATCH`)
+///
+/// Flag and key lookups are case-insensitive.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    protocols: Vec<String>,
+    flags: Vec<String>,
+    params: Vec<(String, String)>,
+}
+
+impl Capabilities {
+    /// An empty capability set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse capabilities from the `extra` field of a HELLO response.
+    ///
+    /// The extra field may look like:
+    /// - `"(2020.075) :: SLPROTO:4.0 SLPROTO:3.1"` — tokens after `"::"` are parsed
+    /// - `"SLPROTO:4.0 SLPROTO:3.1 CAP EXTREPLY"` — already stripped by
+    ///   [`Response::parse_hello`](crate::Response::parse_hello) when there is no
+    ///   extra text
+    /// - `"(2020.075)"` — no capabilities
+    pub fn from_hello_extra(extra: &str) -> Self {
+        if let Some(idx) = extra.find("::") {
+            return Self::parse(&extra[idx + 2..]);
+        }
+        if extra
+            .split_whitespace()
+            .any(|t| starts_with_ignore_case(t, "SLPROTO:"))
+        {
+            return Self::parse(extra);
+        }
+        // Free text: only keep capability-style `KEY:value` tokens
+        let mut caps = Self::new();
+        for token in extra.split_whitespace().filter(|t| t.contains(':')) {
+            caps.push_token(token);
+        }
+        caps
+    }
+
+    /// Parse a whitespace-separated capability list.
+    pub fn parse(tokens: &str) -> Self {
+        let mut caps = Self::new();
+        for token in tokens.split_whitespace() {
+            caps.push_token(token);
+        }
+        caps
+    }
+
+    fn push_token(&mut self, token: &str) {
+        if token.eq_ignore_ascii_case("CAP") {
+            return;
+        }
+        match token.split_once(':') {
+            Some((key, version)) if key.eq_ignore_ascii_case("SLPROTO") => {
+                self.protocols.push(version.to_owned());
+            }
+            Some((key, flag)) if key.eq_ignore_ascii_case("CAP") => {
+                self.flags.push(flag.to_owned());
+            }
+            Some((key, value)) => self.params.push((key.to_owned(), value.to_owned())),
+            None => self.flags.push(token.to_owned()),
+        }
+    }
+
+    /// Add a supported protocol version (e.g. `"4.0"`).
+    pub fn with_protocol(mut self, version: &str) -> Self {
+        self.protocols.push(version.to_owned());
+        self
+    }
+
+    /// Add a flag (e.g. `"EXTREPLY"`).
+    pub fn with_flag(mut self, flag: &str) -> Self {
+        self.flags.push(flag.to_owned());
+        self
+    }
+
+    /// Add a `KEY:value` parameter (e.g. `("WS", "13")`).
+    pub fn with_param(mut self, key: &str, value: &str) -> Self {
+        self.params.push((key.to_owned(), value.to_owned()));
+        self
+    }
+
+    /// Advertised protocol versions, as written (e.g. `["4.0", "3.1"]`).
+    pub fn protocols(&self) -> &[String] {
+        &self.protocols
+    }
+
+    /// Advertised flags (e.g. `["EXTREPLY", "NSWILDCARD", "BATCH"]`).
+    pub fn flags(&self) -> &[String] {
+        &self.flags
+    }
+
+    /// Whether the flag `cap` is advertised (e.g. `has("EXTREPLY")`).
+    pub fn has(&self, cap: &str) -> bool {
+        self.flags.iter().any(|f| f.eq_ignore_ascii_case(cap))
+    }
+
+    /// Value of the `KEY:value` parameter `key` (e.g. `param("WS") == Some("13")`).
+    pub fn param(&self, key: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Whether any `SLPROTO:4.x` version is advertised.
+    pub fn supports_v4(&self) -> bool {
+        self.protocols.iter().any(|v| major(v) == Some(4))
+    }
+
+    /// The highest advertised protocol version this crate implements.
+    ///
+    /// Returns `None` when no `SLPROTO` token names v3 or v4; classic v3
+    /// servers advertise nothing, so callers usually fall back to v3.
+    pub fn max_proto(&self) -> Option<ProtocolVersion> {
+        self.protocols
+            .iter()
+            .filter_map(|v| match major(v)? {
+                3 => Some(ProtocolVersion::V3),
+                4 => Some(ProtocolVersion::V4),
+                _ => None,
+            })
+            .max_by_key(|v| match v {
+                ProtocolVersion::V3 => 3,
+                ProtocolVersion::V4 => 4,
+            })
+    }
+
+    /// Whether nothing is advertised.
+    pub fn is_empty(&self) -> bool {
+        self.protocols.is_empty() && self.flags.is_empty() && self.params.is_empty()
+    }
+}
+
+/// Formats as HELLO tokens: `SLPROTO:4.0 SLPROTO:3.1 WS:13 CAP EXTREPLY`.
+impl fmt::Display for Capabilities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut tokens: Vec<String> = self
+            .protocols
+            .iter()
+            .map(|v| format!("SLPROTO:{v}"))
+            .chain(self.params.iter().map(|(k, v)| format!("{k}:{v}")))
+            .collect();
+        if !self.flags.is_empty() {
+            tokens.push("CAP".to_owned());
+            tokens.extend(self.flags.iter().cloned());
+        }
+        f.write_str(&tokens.join(" "))
+    }
+}
+
+fn major(version: &str) -> Option<u32> {
+    version.split('.').next()?.parse().ok()
+}
+
+fn starts_with_ignore_case(s: &str, prefix: &str) -> bool {
+    s.len() >= prefix.len() && s[..prefix.len()].eq_ignore_ascii_case(prefix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_with_v4() {
+        let caps = Capabilities::from_hello_extra("(2020.075) :: SLPROTO:4.0 SLPROTO:3.1");
+        assert_eq!(caps.protocols(), ["4.0", "3.1"]);
+        assert!(caps.supports_v4());
+        assert_eq!(caps.max_proto(), Some(ProtocolVersion::V4));
+    }
+
+    #[test]
+    fn parse_without_v4() {
+        let caps = Capabilities::from_hello_extra("(2020.075) :: SLPROTO:3.1");
+        assert!(!caps.supports_v4());
+        assert_eq!(caps.max_proto(), Some(ProtocolVersion::V3));
+    }
+
+    #[test]
+    fn parse_empty_extra() {
+        let caps = Capabilities::from_hello_extra("");
+        assert!(caps.is_empty());
+        assert!(!caps.supports_v4());
+        assert_eq!(caps.max_proto(), None);
+    }
+
+    #[test]
+    fn parse_no_separator_no_caps() {
+        assert!(Capabilities::from_hello_extra("(2020.075)").is_empty());
+    }
+
+    #[test]
+    fn parse_no_separator_with_caps() {
+        // parse_hello strips "::" when there is no other extra text
+        let caps = Capabilities::from_hello_extra("SLPROTO:4.0 SLPROTO:3.1 CAP EXTREPLY");
+        assert!(caps.supports_v4());
+        assert!(caps.has("EXTREPLY"));
+    }
+
+    #[test]
+    fn parse_separator_but_empty_right() {
+        assert!(Capabilities::from_hello_extra("(2020.075) ::  ").is_empty());
+    }
+
+    #[test]
+    fn parse_ringserver_hello() {
+        let caps = Capabilities::from_hello_extra(
+            "(2020.075 RingServer) :: SLPROTO:4.0 SLPROTO:3.1 CAP EXTREPLY NSWILDCARD BATCH WS:13",
+        );
+        assert_eq!(caps.flags(), ["EXTREPLY", "NSWILDCARD", "BATCH"]);
+        assert!(caps.has("batch"));
+        assert!(!caps.has("CAP"));
+        assert_eq!(caps.param("ws"), Some("13"));
+        assert_eq!(caps.param("AUTH"), None);
+    }
+
+    #[test]
+    fn cap_prefixed_flags() {
+        let caps = Capabilities::parse("SLPROTO:4.0 CAP:AUTH CAP:WINDOW");
+        assert!(caps.has("AUTH"));
+        assert!(caps.has("WINDOW"));
+    }
+
+    #[test]
+    fn display_roundtrip() {
+        let caps = Capabilities::new()
+            .with_protocol("4.0")
+            .with_protocol("3.1")
+            .with_flag("EXTREPLY");
+        assert_eq!(caps.to_string(), "SLPROTO:4.0 SLPROTO:3.1 CAP EXTREPLY");
+        assert_eq!(Capabilities::parse(&caps.to_string()), caps);
+        assert_eq!(Capabilities::new().to_string(), "");
+    }
+}
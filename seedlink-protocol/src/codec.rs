@@ -0,0 +1,329 @@
+//! [`SeedlinkCodec`] for tokio-util's `Framed` (`tokio-util` feature).
+//!
+//! A SeedLink connection starts with text lines (commands and responses) and
+//! switches to binary frames once streaming starts. The codec cannot tell the
+//! phases apart on its own (`SLPROTO` starts with the v3 frame signature), so
+//! the caller switches with [`SeedlinkCodec::set_mode()`]. Bytes already
+//! buffered by `Framed` are decoded in the new mode, so nothing is lost.
+//!
+//! ```
+//! use bytes::BytesMut;
+//! use seedlink_rs_protocol::codec::{CodecMode, Decoded, SeedlinkCodec};
+//! use seedlink_rs_protocol::frame::v3;
+//! use seedlink_rs_protocol::SequenceNumber;
+//! use tokio_util::codec::Decoder;
+//!
+//! let mut codec = SeedlinkCodec::new();
+//! let mut buf = BytesMut::from(&b"OK\r\n"[..]);
+//! buf.extend_from_slice(&v3::write(SequenceNumber::new(1), &[0; 512]).unwrap());
+//!
+//! assert_eq!(codec.decode(&mut buf).unwrap(), Some(Decoded::Line("OK".into())));
+//! codec.set_mode(CodecMode::Frames);
+//! let Some(Decoded::Frame(frame)) = codec.decode(&mut buf).unwrap() else {
+//!     panic!("expected a frame");
+//! };
+//! assert_eq!(frame.frame().sequence(), SequenceNumber::new(1));
+//! ```
+
+use bytes::{Bytes, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::command::Command;
+use crate::error::{Result, SeedlinkError};
+use crate::frame::decoder::{DEFAULT_MAX_FRAME_LEN, frame_len, parse_frame};
+use crate::frame::{RawFrame, v3, v4};
+use crate::response::Response;
+use crate::version::ProtocolVersion;
+
+/// Longest accepted text line, terminator included.
+pub const MAX_LINE_LEN: usize = 8 * 1024;
+
+/// What [`SeedlinkCodec`] decodes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CodecMode {
+    /// Text lines: commands (server side) or responses (client side).
+    #[default]
+    Text,
+    /// Binary frames. Input that does not start with a frame signature is
+    /// still decoded as a line, so `END` and `ERROR` replies come through.
+    Frames,
+}
+
+/// An item decoded by [`SeedlinkCodec`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Decoded {
+    /// A text line without its `\r\n` terminator.
+    Line(String),
+    /// A complete v3 or v4 frame.
+    Frame(FrameBytes),
+}
+
+/// The bytes of one complete frame, checked to parse when decoded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FrameBytes(Bytes);
+
+impl FrameBytes {
+    /// Borrowed view of the frame.
+    pub fn frame(&self) -> RawFrame<'_> {
+        parse_frame(&self.0).expect("frame parsed when decoded")
+    }
+
+    /// The frame as received, header included.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Take the underlying buffer.
+    pub fn into_bytes(self) -> Bytes {
+        self.0
+    }
+}
+
+/// Decoder/encoder for both phases of a SeedLink connection.
+///
+/// Decodes lines or frames depending on [`mode()`](Self::mode). Encodes
+/// [`Command`]s for the negotiated [`version()`](Self::version) (client
+/// side), and [`Response`]s and [`RawFrame`]s (server side).
+#[derive(Clone, Debug)]
+pub struct SeedlinkCodec {
+    mode: CodecMode,
+    version: ProtocolVersion,
+    max_frame_len: usize,
+}
+
+impl Default for SeedlinkCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SeedlinkCodec {
+    /// A codec in [`CodecMode::Text`] for protocol v3, the state of a fresh
+    /// connection.
+    pub fn new() -> Self {
+        Self {
+            mode: CodecMode::Text,
+            version: ProtocolVersion::V3,
+            max_frame_len: DEFAULT_MAX_FRAME_LEN,
+        }
+    }
+
+    /// Reject v4 frames longer than `max_frame_len` bytes, header included.
+    pub fn with_max_frame_len(mut self, max_frame_len: usize) -> Self {
+        self.max_frame_len = max_frame_len;
+        self
+    }
+
+    /// Current decoding mode.
+    pub fn mode(&self) -> CodecMode {
+        self.mode
+    }
+
+    /// Switch decoding mode, e.g. to [`CodecMode::Frames`] after sending `END`.
+    pub fn set_mode(&mut self, mode: CodecMode) {
+        self.mode = mode;
+    }
+
+    /// Protocol version used to encode commands.
+    pub fn version(&self) -> ProtocolVersion {
+        self.version
+    }
+
+    /// Set the protocol version, e.g. after `SLPROTO 4.0` was accepted.
+    pub fn set_version(&mut self, version: ProtocolVersion) {
+        self.version = version;
+    }
+
+    fn decode_line(&self, src: &mut BytesMut) -> Result<Option<Decoded>> {
+        let Some(end) = src.iter().position(|&b| b == b'\n') else {
+            if src.len() > MAX_LINE_LEN {
+                return Err(SeedlinkError::LineTooLong(MAX_LINE_LEN));
+            }
+            return Ok(None);
+        };
+        if end >= MAX_LINE_LEN {
+            return Err(SeedlinkError::LineTooLong(MAX_LINE_LEN));
+        }
+        let line = src.split_to(end + 1);
+        let text = String::from_utf8_lossy(&line[..end]);
+        Ok(Some(Decoded::Line(text.trim_end_matches('\r').to_owned())))
+    }
+}
+
+impl Decoder for SeedlinkCodec {
+    type Item = Decoded;
+    type Error = SeedlinkError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Decoded>> {
+        if self.mode == CodecMode::Frames {
+            if src.len() < 2 {
+                return Ok(None);
+            }
+            if src.starts_with(v3::SIGNATURE) || src.starts_with(v4::SIGNATURE) {
+                let Some(len) = frame_len(src, self.max_frame_len)? else {
+                    return Ok(None);
+                };
+                let bytes = src.split_to(len).freeze();
+                parse_frame(&bytes)?;
+                return Ok(Some(Decoded::Frame(FrameBytes(bytes))));
+            }
+        }
+        self.decode_line(src)
+    }
+}
+
+impl Encoder<Command> for SeedlinkCodec {
+    type Error = SeedlinkError;
+
+    fn encode(&mut self, item: Command, dst: &mut BytesMut) -> Result<()> {
+        dst.extend_from_slice(&item.to_bytes(self.version)?);
+        Ok(())
+    }
+}
+
+impl Encoder<Response> for SeedlinkCodec {
+    type Error = SeedlinkError;
+
+    fn encode(&mut self, item: Response, dst: &mut BytesMut) -> Result<()> {
+        dst.extend_from_slice(&item.to_bytes());
+        Ok(())
+    }
+}
+
+impl Encoder<RawFrame<'_>> for SeedlinkCodec {
+    type Error = SeedlinkError;
+
+    fn encode(&mut self, item: RawFrame<'_>, dst: &mut BytesMut) -> Result<()> {
+        let bytes = match item {
+            RawFrame::V3 { sequence, payload } => v3::write(sequence, payload)?,
+            RawFrame::V4 {
+                format,
+                subformat,
+                sequence,
+                station_id,
+                payload,
+            } => v4::write(format, subformat, sequence, station_id, payload)?,
+        };
+        dst.extend_from_slice(&bytes);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::{PayloadFormat, PayloadSubformat};
+    use crate::sequence::SequenceNumber;
+
+    fn line(s: &str) -> Option<Decoded> {
+        Some(Decoded::Line(s.into()))
+    }
+
+    #[test]
+    fn text_mode_decodes_lines_across_chunks() {
+        let mut codec = SeedlinkCodec::new();
+        let mut buf = BytesMut::from(&b"SLPROTO 4.0\r\nSTA"[..]);
+        assert_eq!(codec.decode(&mut buf).unwrap(), line("SLPROTO 4.0"));
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        buf.extend_from_slice(b"TION IU_ANMO\n");
+        assert_eq!(codec.decode(&mut buf).unwrap(), line("STATION IU_ANMO"));
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn frames_mode_decodes_frames_and_trailing_lines() {
+        let v4_frame = v4::write(
+            PayloadFormat::MiniSeed2,
+            PayloadSubformat::Data,
+            SequenceNumber::new(2),
+            "IU_ANMO",
+            b"payload",
+        )
+        .unwrap();
+        let mut stream = v3::write(SequenceNumber::new(1), &[7; v3::PAYLOAD_LEN]).unwrap();
+        stream.extend_from_slice(&v4_frame);
+        stream.extend_from_slice(b"END\r\n");
+
+        let mut codec = SeedlinkCodec::new();
+        codec.set_mode(CodecMode::Frames);
+        let mut buf = BytesMut::new();
+        let mut items = Vec::new();
+        for piece in stream.chunks(9) {
+            buf.extend_from_slice(piece);
+            while let Some(item) = codec.decode(&mut buf).unwrap() {
+                items.push(item);
+            }
+        }
+        assert_eq!(items.len(), 3);
+        let Decoded::Frame(first) = &items[0] else {
+            panic!("expected frame");
+        };
+        assert_eq!(first.frame().sequence(), SequenceNumber::new(1));
+        assert_eq!(first.as_bytes().len(), v3::FRAME_LEN);
+        let Decoded::Frame(second) = &items[1] else {
+            panic!("expected frame");
+        };
+        assert_eq!(second.frame().payload(), b"payload");
+        assert_eq!(second.as_bytes(), &v4_frame[..]);
+        assert_eq!(items[2], Decoded::Line("END".into()));
+    }
+
+    #[test]
+    fn encodes_commands_responses_and_frames() {
+        let mut codec = SeedlinkCodec::new();
+        let mut buf = BytesMut::new();
+        codec.encode(Command::Hello, &mut buf).unwrap();
+        codec.encode(Response::Ok, &mut buf).unwrap();
+        assert_eq!(&buf[..], b"HELLO\r\nOK\r\n");
+
+        // Version-checked: CAPABILITIES is v3-only
+        codec.set_version(ProtocolVersion::V4);
+        assert!(matches!(
+            codec.encode(
+                Command::Capabilities {
+                    capabilities: vec![]
+                },
+                &mut buf
+            ),
+            Err(SeedlinkError::VersionMismatch { .. })
+        ));
+
+        let mut buf = BytesMut::new();
+        let frame = RawFrame::V3 {
+            sequence: SequenceNumber::new(5),
+            payload: &[1; v3::PAYLOAD_LEN],
+        };
+        codec.encode(frame.clone(), &mut buf).unwrap();
+        codec.set_mode(CodecMode::Frames);
+        let Some(Decoded::Frame(decoded)) = codec.decode(&mut buf).unwrap() else {
+            panic!("expected frame");
+        };
+        assert_eq!(decoded.frame(), frame);
+    }
+
+    #[test]
+    fn rejects_overlong_lines_and_bad_frames() {
+        let mut codec = SeedlinkCodec::new();
+        let mut buf = BytesMut::from(&[b'A'; MAX_LINE_LEN + 1][..]);
+        assert!(matches!(
+            codec.decode(&mut buf),
+            Err(SeedlinkError::LineTooLong(_))
+        ));
+
+        let mut codec = SeedlinkCodec::new().with_max_frame_len(32);
+        codec.set_mode(CodecMode::Frames);
+        let frame = v4::write(
+            PayloadFormat::Json,
+            PayloadSubformat::Info,
+            SequenceNumber::new(1),
+            "",
+            &[b'x'; 64],
+        )
+        .unwrap();
+        let mut buf = BytesMut::from(&frame[..]);
+        assert!(matches!(
+            codec.decode(&mut buf),
+            Err(SeedlinkError::FrameTooLarge { max: 32, .. })
+        ));
+    }
+}
@@ -1,6 +1,12 @@
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
 use crate::error::{Result, SeedlinkError};
 use crate::info::InfoLevel;
 use crate::sequence::SequenceNumber;
+use crate::time::SlTime;
 use crate::version::ProtocolVersion;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -16,8 +22,8 @@ pub enum Command {
     },
     Data {
         sequence: Option<SequenceNumber>,
-        start: Option<String>,
-        end: Option<String>,
+        start: Option<SlTime>,
+        end: Option<SlTime>,
     },
     End,
     Bye,
@@ -31,10 +37,14 @@ pub enum Command {
         sequence: Option<SequenceNumber>,
     },
     Time {
-        start: String,
-        end: Option<String>,
+        start: SlTime,
+        end: Option<SlTime>,
     },
     Cat,
+    /// Enable optional server behaviors, e.g. `CAPABILITIES EXTREPLY`.
+    Capabilities {
+        capabilities: Vec<String>,
+    },
 
     // v4 only
     SlProto {
@@ -52,8 +62,45 @@ pub enum Command {
 impl Command {
     /// Parse a command from a text line (version-agnostic).
     ///
-    /// The line should NOT include the trailing `\r\n`.
+    /// The line should NOT include the trailing `\r\n`. A sequence of exactly
+    /// six hex digits is read as v3 hex, anything else as v4 decimal; use
+    /// [`parse_for()`](Self::parse_for) when the version is known.
     pub fn parse(line: &str) -> Result<Self> {
+        Self::parse_line(line, None)
+    }
+
+    /// Parse a command sent on a connection using `version`.
+    ///
+    /// Sequences are read as v3 hex or v4 decimal, so a 6-digit v4 sequence
+    /// such as `100000` is not mistaken for hex. Both versions accept `ALL`
+    /// ([`SequenceNumber::ALL_DATA`]) and `-1` ([`SequenceNumber::NEWEST`]).
+    pub fn parse_for(line: &str, version: ProtocolVersion) -> Result<Self> {
+        Self::parse_line(line, Some(version))
+    }
+
+    /// `TIME start [end]` from time strings in either v3 or ISO-8601 format.
+    pub fn time(start: &str, end: Option<&str>) -> Result<Self> {
+        Ok(Self::Time {
+            start: SlTime::parse(start)?,
+            end: end.map(SlTime::parse).transpose()?,
+        })
+    }
+
+    /// `DATA [seq] start [end]` from time strings in either v3 or ISO-8601
+    /// format.
+    pub fn data_time(
+        sequence: Option<SequenceNumber>,
+        start: &str,
+        end: Option<&str>,
+    ) -> Result<Self> {
+        Ok(Self::Data {
+            sequence,
+            start: Some(SlTime::parse(start)?),
+            end: end.map(SlTime::parse).transpose()?,
+        })
+    }
+
+    fn parse_line(line: &str, version: Option<ProtocolVersion>) -> Result<Self> {
         let line = line.trim_end_matches('\n').trim_end_matches('\r');
         let mut parts = line.split_whitespace();
         let keyword = parts
@@ -101,9 +148,9 @@ impl Command {
             }
             "DATA" => {
                 let seq_str = parts.next();
-                let start = parts.next().map(|s| s.to_owned());
-                let end = parts.next().map(|s| s.to_owned());
-                let sequence = seq_str.map(parse_sequence).transpose()?;
+                let start = parts.next().map(SlTime::parse).transpose()?;
+                let end = parts.next().map(SlTime::parse).transpose()?;
+                let sequence = seq_str.map(|s| parse_sequence(s, version)).transpose()?;
                 Ok(Self::Data {
                     sequence,
                     start,
@@ -132,21 +179,30 @@ impl Command {
             }
             "FETCH" => {
                 let seq_str = parts.next();
-                let sequence = seq_str.map(parse_sequence).transpose()?;
+                let sequence = seq_str.map(|s| parse_sequence(s, version)).transpose()?;
                 Ok(Self::Fetch { sequence })
             }
             "TIME" => {
                 let start = parts
                     .next()
-                    .ok_or_else(|| SeedlinkError::InvalidCommand("TIME requires start".into()))?
-                    .to_owned();
-                let end = parts.next().map(|s| s.to_owned());
+                    .ok_or_else(|| SeedlinkError::InvalidCommand("TIME requires start".into()))
+                    .and_then(SlTime::parse)?;
+                let end = parts.next().map(SlTime::parse).transpose()?;
                 Ok(Self::Time { start, end })
             }
             "CAT" => {
                 reject_extra_args(&mut parts, "CAT")?;
                 Ok(Self::Cat)
             }
+            "CAPABILITIES" => {
+                let capabilities: Vec<String> = parts.map(|s| s.to_owned()).collect();
+                if capabilities.is_empty() {
+                    return Err(SeedlinkError::InvalidCommand(
+                        "CAPABILITIES requires at least one capability".into(),
+                    ));
+                }
+                Ok(Self::Capabilities { capabilities })
+            }
             "SLPROTO" => {
                 let version = parts
                     .next()
@@ -210,11 +266,18 @@ impl Command {
             Self::Hello
             | Self::Station { .. }
             | Self::Select { .. }
-            | Self::Data { .. }
             | Self::End
             | Self::Bye
-            | Self::Info { .. } => true,
-            Self::Batch | Self::Fetch { .. } | Self::Time { .. } | Self::Cat => {
+            | Self::Info { .. }
+            | Self::Fetch { .. } => true,
+            // `ALL` and `-1` are v4 tokens
+            Self::Data { sequence, .. } => {
+                version == ProtocolVersion::V4
+                    || !sequence.is_some_and(|s| {
+                        s == SequenceNumber::ALL_DATA || s == SequenceNumber::NEWEST
+                    })
+            }
+            Self::Batch | Self::Time { .. } | Self::Cat | Self::Capabilities { .. } => {
                 version == ProtocolVersion::V3
             }
             Self::SlProto { .. } | Self::Auth { .. } | Self::UserAgent { .. } | Self::EndFetch => {
@@ -236,6 +299,7 @@ impl Command {
             Self::Fetch { .. } => "FETCH",
             Self::Time { .. } => "TIME",
             Self::Cat => "CAT",
+            Self::Capabilities { .. } => "CAPABILITIES",
             Self::SlProto { .. } => "SLPROTO",
             Self::Auth { .. } => "AUTH",
             Self::UserAgent { .. } => "USERAGENT",
@@ -263,11 +327,11 @@ impl Command {
                 }
                 if let Some(start_time) = start {
                     s.push(' ');
-                    s.push_str(start_time);
+                    s.push_str(&start_time.format(version));
                 }
                 if let Some(end_time) = end {
                     s.push(' ');
-                    s.push_str(end_time);
+                    s.push_str(&end_time.format(version));
                 }
                 s
             }
@@ -279,11 +343,15 @@ impl Command {
                 Some(seq) => format!("FETCH {}", format_sequence(*seq, version)),
                 None => "FETCH".into(),
             },
+            // TIME is v3-only, so always the comma format
             Self::Time { start, end } => match end {
-                Some(e) => format!("TIME {start} {e}"),
-                None => format!("TIME {start}"),
+                Some(e) => format!("TIME {} {}", start.to_v3(), e.to_v3()),
+                None => format!("TIME {}", start.to_v3()),
             },
             Self::Cat => "CAT".into(),
+            Self::Capabilities { capabilities } => {
+                format!("CAPABILITIES {}", capabilities.join(" "))
+            }
             Self::SlProto { version: v } => format!("SLPROTO {v}"),
             Self::Auth { value } => format!("AUTH {value}"),
             Self::UserAgent { description } => format!("USERAGENT {description}"),
@@ -293,24 +361,40 @@ impl Command {
 }
 
 /// Parse a sequence number from either hex (v3) or decimal (v4) format.
-fn parse_sequence(s: &str) -> Result<SequenceNumber> {
-    // Try v3 hex first (exactly 6 hex chars), then fall back to decimal
-    if s.len() == 6 && s.chars().all(|c| c.is_ascii_hexdigit()) {
-        SequenceNumber::from_v3_hex(s)
-    } else {
-        SequenceNumber::from_v4_decimal(s)
+fn parse_sequence(s: &str, version: Option<ProtocolVersion>) -> Result<SequenceNumber> {
+    // v4 `ALL` and `-1` keywords, then by version; without one, v3 hex
+    // (exactly 6 hex chars), then decimal
+    if s.eq_ignore_ascii_case("ALL") {
+        return Ok(SequenceNumber::ALL_DATA);
+    }
+    if s == "-1" {
+        return Ok(SequenceNumber::NEWEST);
+    }
+    match version {
+        Some(ProtocolVersion::V3) => SequenceNumber::from_v3_hex(s),
+        Some(ProtocolVersion::V4) => SequenceNumber::from_v4_decimal(s),
+        None if s.len() == 6 && s.chars().all(|c| c.is_ascii_hexdigit()) => {
+            SequenceNumber::from_v3_hex(s)
+        }
+        None => SequenceNumber::from_v4_decimal(s),
     }
 }
 
 /// Format a sequence number for the given protocol version.
 fn format_sequence(seq: SequenceNumber, version: ProtocolVersion) -> String {
+    if seq == SequenceNumber::ALL_DATA {
+        return "ALL".into();
+    }
+    if seq == SequenceNumber::NEWEST {
+        return "-1".into();
+    }
     match version {
         ProtocolVersion::V3 => seq.to_v3_hex(),
         ProtocolVersion::V4 => seq.to_v4_decimal(),
     }
 }
 
-fn reject_extra_args(parts: &mut std::str::SplitWhitespace<'_>, command: &str) -> Result<()> {
+fn reject_extra_args(parts: &mut core::str::SplitWhitespace<'_>, command: &str) -> Result<()> {
     if parts.next().is_some() {
         Err(SeedlinkError::InvalidCommand(format!(
             "{command}: unexpected extra arguments"
@@ -391,6 +475,97 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_data_all_with_time_range() {
+        let cmd = Command::parse("DATA ALL 2024-01-15T00:00:00Z 2024-01-16T00:00:00Z").unwrap();
+        assert_eq!(
+            cmd,
+            Command::Data {
+                sequence: Some(SequenceNumber::ALL_DATA),
+                start: Some(SlTime::from_ymd_hms(2024, 1, 15, 0, 0, 0).unwrap()),
+                end: Some(SlTime::from_ymd_hms(2024, 1, 16, 0, 0, 0).unwrap()),
+            }
+        );
+        assert_eq!(
+            cmd.to_bytes(ProtocolVersion::V4).unwrap(),
+            b"DATA ALL 2024-01-15T00:00:00Z 2024-01-16T00:00:00Z\r\n"
+        );
+    }
+
+    #[test]
+    fn data_times_follow_version() {
+        let cmd = Command::data_time(
+            Some(SequenceNumber::new(26)),
+            "2024,1,15,0,0,0",
+            Some("2024-01-16T12:00:00Z"),
+        )
+        .unwrap();
+        assert_eq!(
+            cmd.to_bytes(ProtocolVersion::V3).unwrap(),
+            b"DATA 00001A 2024,01,15,00,00,00 2024,01,16,12,00,00\r\n"
+        );
+        assert_eq!(
+            cmd.to_bytes(ProtocolVersion::V4).unwrap(),
+            b"DATA 26 2024-01-15T00:00:00Z 2024-01-16T12:00:00Z\r\n"
+        );
+        assert!(matches!(
+            Command::parse("DATA ALL 2024-13-01T00:00:00Z"),
+            Err(SeedlinkError::InvalidTime(_))
+        ));
+        assert!(Command::data_time(None, "yesterday", None).is_err());
+    }
+
+    #[test]
+    fn data_special_tokens_and_64_bit_sequences() {
+        let newest = Command::parse("DATA -1").unwrap();
+        assert_eq!(
+            newest,
+            Command::Data {
+                sequence: Some(SequenceNumber::NEWEST),
+                start: None,
+                end: None,
+            }
+        );
+        assert_eq!(
+            newest.to_bytes(ProtocolVersion::V4).unwrap(),
+            b"DATA -1\r\n"
+        );
+        assert!(matches!(
+            newest.to_bytes(ProtocolVersion::V3),
+            Err(SeedlinkError::VersionMismatch { .. })
+        ));
+        assert!(
+            Command::parse("DATA ALL")
+                .unwrap()
+                .to_bytes(ProtocolVersion::V3)
+                .is_err()
+        );
+
+        // Six decimal digits are hex only when the version is unknown
+        let Command::Data { sequence, .. } = Command::parse("DATA 100000").unwrap() else {
+            panic!("expected DATA");
+        };
+        assert_eq!(sequence, Some(SequenceNumber::new(0x100000)));
+        let cmd = Command::parse_for("DATA 100000", ProtocolVersion::V4).unwrap();
+        let Command::Data { sequence, .. } = &cmd else {
+            panic!("expected DATA");
+        };
+        assert_eq!(*sequence, Some(SequenceNumber::new(100_000)));
+        assert_eq!(
+            cmd.to_bytes(ProtocolVersion::V4).unwrap(),
+            b"DATA 100000\r\n"
+        );
+
+        let big = Command::parse_for("FETCH 18446744073709551000", ProtocolVersion::V4).unwrap();
+        assert_eq!(
+            big,
+            Command::Fetch {
+                sequence: Some(SequenceNumber::new(18_446_744_073_709_551_000))
+            }
+        );
+        assert!(Command::parse_for("DATA 26", ProtocolVersion::V3).is_err());
+    }
+
     #[test]
     fn parse_data_with_decimal_seq() {
         let cmd = Command::parse("DATA 26").unwrap();
@@ -453,20 +628,21 @@ mod tests {
         assert_eq!(
             Command::parse("TIME 2024,1,15,0,0,0").unwrap(),
             Command::Time {
-                start: "2024,1,15,0,0,0".into(),
+                start: SlTime::from_ymd_hms(2024, 1, 15, 0, 0, 0).unwrap(),
                 end: None,
             }
         );
+        assert!(matches!(
+            Command::parse("TIME 2024,1,0,0,0"),
+            Err(SeedlinkError::InvalidTime(_))
+        ));
     }
 
     #[test]
     fn parse_time_with_end() {
         assert_eq!(
             Command::parse("TIME 2024,1,15,0,0,0 2024,1,16,0,0,0").unwrap(),
-            Command::Time {
-                start: "2024,1,15,0,0,0".into(),
-                end: Some("2024,1,16,0,0,0".into()),
-            }
+            Command::time("2024,1,15,0,0,0", Some("2024-01-16T00:00:00Z")).unwrap()
         );
     }
 
@@ -475,6 +651,17 @@ mod tests {
         assert_eq!(Command::parse("CAT").unwrap(), Command::Cat);
     }
 
+    #[test]
+    fn parse_capabilities() {
+        assert_eq!(
+            Command::parse("CAPABILITIES EXTREPLY NSWILDCARD").unwrap(),
+            Command::Capabilities {
+                capabilities: vec!["EXTREPLY".into(), "NSWILDCARD".into()],
+            }
+        );
+        assert!(Command::parse("CAPABILITIES").is_err());
+    }
+
     #[test]
     fn parse_slproto() {
         assert_eq!(
@@ -604,6 +791,15 @@ mod tests {
         assert!(!Command::Batch.is_valid_for(ProtocolVersion::V4));
     }
 
+    #[test]
+    fn fetch_valid_for_both() {
+        // v3 one-shot FETCH and v4 dial-up FETCH
+        let cmd = Command::Fetch { sequence: None };
+        assert!(cmd.is_valid_for(ProtocolVersion::V3));
+        assert!(cmd.is_valid_for(ProtocolVersion::V4));
+        assert_eq!(cmd.to_bytes(ProtocolVersion::V4).unwrap(), b"FETCH\r\n");
+    }
+
     #[test]
     fn is_valid_for_v4_only() {
         assert!(!Command::EndFetch.is_valid_for(ProtocolVersion::V3));
@@ -633,10 +829,13 @@ mod tests {
             },
             Command::Batch,
             Command::Cat,
+            Command::Capabilities {
+                capabilities: vec!["EXTREPLY".into()],
+            },
         ];
         for cmd in commands {
             let bytes = cmd.to_bytes(ProtocolVersion::V3).unwrap();
-            let line = std::str::from_utf8(&bytes).unwrap();
+            let line = core::str::from_utf8(&bytes).unwrap();
             let parsed = Command::parse(line).unwrap();
             assert_eq!(parsed, cmd, "roundtrip failed for {cmd:?}");
         }
@@ -664,7 +863,7 @@ mod tests {
         ];
         for cmd in commands {
             let bytes = cmd.to_bytes(ProtocolVersion::V4).unwrap();
-            let line = std::str::from_utf8(&bytes).unwrap();
+            let line = core::str::from_utf8(&bytes).unwrap();
             let parsed = Command::parse(line).unwrap();
             assert_eq!(parsed, cmd, "roundtrip failed for {cmd:?}");
         }
@@ -0,0 +1,69 @@
+//! zstd compression of v4 frame payloads.
+//!
+//! A server that supports compression advertises the [`ZSTD_CAPABILITY`] flag
+//! in HELLO. A client enables it with `CAPABILITIES ZSTD` before `SLPROTO 4.0`;
+//! from then on the payload of every v4 frame the server sends (data and INFO)
+//! is a single zstd frame. The v4 header is unchanged, so its payload length
+//! is the compressed length. v3 frames are never compressed.
+
+use std::io::Read;
+
+use ruzstd::decoding::StreamingDecoder;
+use ruzstd::encoding::{CompressionLevel, compress_to_vec};
+
+use crate::error::{Result, SeedlinkError};
+
+/// HELLO capability flag and `CAPABILITIES` argument for zstd payloads.
+pub const ZSTD_CAPABILITY: &str = "ZSTD";
+
+/// Largest decompressed payload accepted by [`decompress`] (16 MiB).
+pub const MAX_DECOMPRESSED_LEN: usize = 16 * 1024 * 1024;
+
+/// Compress a payload into one zstd frame.
+pub fn compress(payload: &[u8]) -> Vec<u8> {
+    compress_to_vec(payload, CompressionLevel::Fastest)
+}
+
+/// Decompress a payload produced by [`compress`].
+///
+/// Fails on malformed input and on output larger than [`MAX_DECOMPRESSED_LEN`].
+pub fn decompress(payload: &[u8]) -> Result<Vec<u8>> {
+    let mut source = payload;
+    let decoder = StreamingDecoder::new(&mut source)
+        .map_err(|e| SeedlinkError::Decompression(e.to_string()))?;
+    let mut out = Vec::new();
+    decoder
+        .take(MAX_DECOMPRESSED_LEN as u64 + 1)
+        .read_to_end(&mut out)
+        .map_err(|e| SeedlinkError::Decompression(e.to_string()))?;
+    if out.len() > MAX_DECOMPRESSED_LEN {
+        return Err(SeedlinkError::Decompression(format!(
+            "payload exceeds {MAX_DECOMPRESSED_LEN} bytes"
+        )));
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let mut payload = vec![0u8; 512];
+        payload[..6].copy_from_slice(b"000001");
+        payload[8..13].copy_from_slice(b"ANMO ");
+        let compressed = compress(&payload);
+        assert!(compressed.len() < payload.len());
+        assert_eq!(decompress(&compressed).unwrap(), payload);
+        assert_eq!(decompress(&compress(&[])).unwrap(), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        assert!(matches!(
+            decompress(b"not zstd"),
+            Err(SeedlinkError::Decompression(_))
+        ));
+    }
+}
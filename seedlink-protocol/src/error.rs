@@ -1,3 +1,6 @@
+use alloc::string::String;
+
+use crate::response::ErrorCode;
 use crate::version::ProtocolVersion;
 
 #[derive(Debug, thiserror::Error)]
@@ -5,6 +8,12 @@ pub enum SeedlinkError {
     #[error("frame too short: expected {expected}, actual {actual}")]
     FrameTooShort { expected: usize, actual: usize },
 
+    #[error("frame too large: {len} bytes, limit {max}")]
+    FrameTooLarge { len: usize, max: usize },
+
+    #[error("line too long: exceeds {0} bytes")]
+    LineTooLong(usize),
+
     #[error("invalid signature: expected {expected:?}, actual {actual:?}")]
     InvalidSignature {
         expected: &'static str,
@@ -17,6 +26,9 @@ pub enum SeedlinkError {
     #[error("invalid command: {0}")]
     InvalidCommand(String),
 
+    #[error("invalid time: {0}")]
+    InvalidTime(String),
+
     #[error("version mismatch: {command} not valid for {version:?}")]
     VersionMismatch {
         command: &'static str,
@@ -38,11 +50,105 @@ pub enum SeedlinkError {
     #[error("invalid payload subformat: {0}")]
     InvalidPayloadSubformat(u8),
 
+    #[error("payload too large: {len} bytes, limit {max}")]
+    PayloadTooLarge { len: usize, max: usize },
+
     #[error("payload length mismatch: expected {expected}, actual {actual}")]
     PayloadLengthMismatch { expected: usize, actual: usize },
 
+    #[error("payload decompression failed: {0}")]
+    Decompression(String),
+
+    #[cfg(feature = "std")]
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[cfg(feature = "std")]
     #[error("miniseed error: {0}")]
     Miniseed(#[from] miniseed_rs::MseedError),
 }
 
-pub type Result<T> = std::result::Result<T, SeedlinkError>;
+pub type Result<T> = core::result::Result<T, SeedlinkError>;
+
+/// Broad category of an error, for deciding how to react to it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// The connection failed: I/O error, timeout or disconnect.
+    Network,
+    /// The peer sent something malformed, unexpected or refused a command.
+    Protocol,
+    /// The server refused access (`AUTH` or `UNAUTHORIZED` errors).
+    Auth,
+    /// The caller asked for something invalid: a bad argument, a command
+    /// the negotiated version lacks, or a call in the wrong state.
+    State,
+}
+
+impl SeedlinkError {
+    /// The category of this error.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            #[cfg(feature = "std")]
+            Self::Io(_) => ErrorKind::Network,
+            Self::ServerError { code, .. }
+                if ErrorCode::parse(code).is_some_and(|c| c.is_auth()) =>
+            {
+                ErrorKind::Auth
+            }
+            Self::InvalidCommand(_)
+            | Self::InvalidTime(_)
+            | Self::VersionMismatch { .. }
+            | Self::InvalidInfoLevel(_) => ErrorKind::State,
+            _ => ErrorKind::Protocol,
+        }
+    }
+
+    /// Whether a new connection may succeed where this error failed.
+    ///
+    /// Network errors and corrupted input are retriable; refusals by the
+    /// server (other than `LIMIT` and `INTERNAL`), auth and caller errors are
+    /// not.
+    pub fn is_retriable(&self) -> bool {
+        match self {
+            Self::ServerError { code, .. } => {
+                ErrorCode::parse(code).is_some_and(|c| c.is_transient())
+            }
+            _ => matches!(self.kind(), ErrorKind::Network | ErrorKind::Protocol),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kinds_and_retriable() {
+        #[cfg(feature = "std")]
+        {
+            let io = SeedlinkError::Io(std::io::ErrorKind::ConnectionReset.into());
+            assert_eq!(io.kind(), ErrorKind::Network);
+            assert!(io.is_retriable());
+        }
+
+        let garbage = SeedlinkError::InvalidSignature {
+            expected: "SL",
+            actual: *b"XX",
+        };
+        assert_eq!(garbage.kind(), ErrorKind::Protocol);
+        assert!(garbage.is_retriable());
+
+        let server = |code: &str| SeedlinkError::ServerError {
+            code: code.into(),
+            description: String::new(),
+        };
+        assert_eq!(server("unauthorized").kind(), ErrorKind::Auth);
+        assert!(!server("AUTH").is_retriable());
+        assert!(!server("UNSUPPORTED").is_retriable());
+        assert!(server("LIMIT").is_retriable());
+
+        let misuse = SeedlinkError::InvalidCommand("STATION".into());
+        assert_eq!(misuse.kind(), ErrorKind::State);
+        assert!(!misuse.is_retriable());
+    }
+}
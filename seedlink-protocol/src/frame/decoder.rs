@@ -0,0 +1,226 @@
+//! Incremental (Sans-IO) frame decoder.
+//!
+//! [`FrameDecoder`] does no I/O itself: feed it bytes as they arrive from any
+//! transport (blocking sockets, io_uring, a file) and pull complete frames
+//! out. v3 and v4 frames are told apart by their signature, so a stream may
+//! mix both.
+
+use alloc::vec::Vec;
+
+use crate::error::{Result, SeedlinkError};
+use crate::frame::{RawFrame, v3, v4};
+
+/// Default limit on the size of a single frame (16 MiB).
+pub const DEFAULT_MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+
+/// Push-based decoder that turns arbitrary byte chunks into [`RawFrame`]s.
+///
+/// Partial frames are buffered until the rest arrives. After an error the
+/// stream is out of sync and the decoder should be discarded.
+///
+/// ```
+/// use seedlink_rs_protocol::{FrameDecoder, SequenceNumber};
+/// use seedlink_rs_protocol::frame::v3;
+///
+/// let frame = v3::write(SequenceNumber::new(1), &[0u8; v3::PAYLOAD_LEN]).unwrap();
+/// let mut decoder = FrameDecoder::new();
+/// decoder.push(&frame[..100]);
+/// assert!(decoder.next_frame().unwrap().is_none());
+/// decoder.push(&frame[100..]);
+/// let raw = decoder.next_frame().unwrap().unwrap();
+/// assert_eq!(raw.sequence(), SequenceNumber::new(1));
+/// ```
+#[derive(Clone, Debug)]
+pub struct FrameDecoder {
+    buf: Vec<u8>,
+    /// Start of the unconsumed bytes in `buf`.
+    pos: usize,
+    max_frame_len: usize,
+}
+
+impl Default for FrameDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameDecoder {
+    /// Create a decoder with [`DEFAULT_MAX_FRAME_LEN`].
+    pub fn new() -> Self {
+        Self::with_max_frame_len(DEFAULT_MAX_FRAME_LEN)
+    }
+
+    /// Create a decoder that rejects v4 frames longer than `max_frame_len`
+    /// bytes (header included), so a corrupt length field cannot make it
+    /// buffer without bound.
+    pub fn with_max_frame_len(max_frame_len: usize) -> Self {
+        Self {
+            buf: Vec::new(),
+            pos: 0,
+            max_frame_len,
+        }
+    }
+
+    /// Append a chunk of received bytes.
+    pub fn push(&mut self, data: &[u8]) {
+        if self.pos > 0 {
+            self.buf.drain(..self.pos);
+            self.pos = 0;
+        }
+        self.buf.extend_from_slice(data);
+    }
+
+    /// Number of buffered bytes not yet returned as frames.
+    pub fn buffered(&self) -> usize {
+        self.buf.len() - self.pos
+    }
+
+    /// Take the buffered bytes not yet returned as frames, e.g. to hand them
+    /// back to a line reader after the stream ends.
+    pub fn take_buffered(&mut self) -> Vec<u8> {
+        let rest = self.buf.split_off(self.pos);
+        self.buf.clear();
+        self.pos = 0;
+        rest
+    }
+
+    /// Return the next complete frame, or `None` if more bytes are needed.
+    ///
+    /// Fails on an unknown signature or an oversized v4 frame.
+    pub fn next_frame(&mut self) -> Result<Option<RawFrame<'_>>> {
+        let Some(len) = frame_len(&self.buf[self.pos..], self.max_frame_len)? else {
+            return Ok(None);
+        };
+        let start = self.pos;
+        self.pos += len;
+        parse_frame(&self.buf[start..start + len]).map(Some)
+    }
+}
+
+/// Length of the frame at the start of `data`, or `None` if it is not
+/// complete yet.
+pub(crate) fn frame_len(data: &[u8], max_frame_len: usize) -> Result<Option<usize>> {
+    if data.len() < 2 {
+        return Ok(None);
+    }
+    let len = match &data[..2] {
+        sig if sig == v3::SIGNATURE => v3::FRAME_LEN,
+        sig if sig == v4::SIGNATURE => {
+            if data.len() < v4::MIN_HEADER_LEN {
+                return Ok(None);
+            }
+            let len = v4::parse_header(data)?.frame_len();
+            if len > max_frame_len {
+                return Err(SeedlinkError::FrameTooLarge {
+                    len,
+                    max: max_frame_len,
+                });
+            }
+            len
+        }
+        _ => {
+            return Err(SeedlinkError::InvalidSignature {
+                expected: "SL or SE",
+                actual: [data[0], data[1]],
+            });
+        }
+    };
+    Ok((data.len() >= len).then_some(len))
+}
+
+/// Parse one complete v3 or v4 frame, chosen by signature.
+pub(crate) fn parse_frame(data: &[u8]) -> Result<RawFrame<'_>> {
+    if data.starts_with(v3::SIGNATURE) {
+        v3::parse(data)
+    } else {
+        v4::parse(data).map(|(frame, _)| frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::{PayloadFormat, PayloadSubformat};
+    use crate::sequence::SequenceNumber;
+
+    fn v3_frame(seq: u64) -> Vec<u8> {
+        v3::write(SequenceNumber::new(seq), &[seq as u8; v3::PAYLOAD_LEN]).unwrap()
+    }
+
+    fn v4_frame(seq: u64, payload: &[u8]) -> Vec<u8> {
+        v4::write(
+            PayloadFormat::MiniSeed2,
+            PayloadSubformat::Data,
+            SequenceNumber::new(seq),
+            "IU_ANMO",
+            payload,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn decodes_mixed_frames_from_any_chunking() {
+        let mut stream = v3_frame(1);
+        stream.extend(v4_frame(2, b"hello"));
+        stream.extend(v3_frame(3));
+        stream.extend(v4_frame(4, &[]));
+
+        for chunk in [1, 2, 7, 17, 100, 520, stream.len()] {
+            let mut decoder = FrameDecoder::new();
+            let mut seqs = Vec::new();
+            for piece in stream.chunks(chunk) {
+                decoder.push(piece);
+                while let Some(frame) = decoder.next_frame().unwrap() {
+                    seqs.push(frame.sequence().value());
+                }
+            }
+            assert_eq!(seqs, [1, 2, 3, 4], "chunk size {chunk}");
+            assert_eq!(decoder.buffered(), 0);
+        }
+    }
+
+    #[test]
+    fn frames_borrow_payload() {
+        let mut decoder = FrameDecoder::new();
+        decoder.push(&v4_frame(9, b"payload"));
+        match decoder.next_frame().unwrap().unwrap() {
+            RawFrame::V4 {
+                station_id,
+                payload,
+                ..
+            } => {
+                assert_eq!(station_id, "IU_ANMO");
+                assert_eq!(payload, b"payload");
+            }
+            other => panic!("expected v4 frame, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_signature_and_oversized_frames() {
+        let mut decoder = FrameDecoder::new();
+        decoder.push(b"XX");
+        assert!(matches!(
+            decoder.next_frame(),
+            Err(SeedlinkError::InvalidSignature { .. })
+        ));
+
+        let mut decoder = FrameDecoder::with_max_frame_len(64);
+        decoder.push(&v4_frame(1, &[0u8; 100])[..v4::MIN_HEADER_LEN]);
+        assert!(matches!(
+            decoder.next_frame(),
+            Err(SeedlinkError::FrameTooLarge { max: 64, .. })
+        ));
+    }
+
+    #[test]
+    fn take_buffered_returns_trailing_bytes() {
+        let mut decoder = FrameDecoder::new();
+        let mut data = v3_frame(1);
+        data.extend_from_slice(b"END");
+        decoder.push(&data);
+        assert!(decoder.next_frame().unwrap().is_some());
+        assert_eq!(decoder.take_buffered(), b"END");
+        assert_eq!(decoder.buffered(), 0);
+    }
+}
@@ -1,8 +1,13 @@
+pub mod decoder;
 pub mod v3;
 pub mod v4;
 
+pub use decoder::FrameDecoder;
+
 use crate::error::{Result, SeedlinkError};
 use crate::sequence::SequenceNumber;
+#[cfg(feature = "std")]
+use crate::validation::{InvalidRecord, validate_record};
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum PayloadFormat {
@@ -107,7 +112,23 @@ impl<'a> RawFrame<'a> {
         }
     }
 
+    /// Check a miniSEED payload for corruption with
+    /// [`validate_record()`](crate::validation::validate_record).
+    ///
+    /// v4 JSON and XML payloads are not miniSEED and always pass.
+    #[cfg(feature = "std")]
+    pub fn validate(&self) -> core::result::Result<(), InvalidRecord> {
+        match self {
+            Self::V4 {
+                format: PayloadFormat::Json | PayloadFormat::Xml,
+                ..
+            } => Ok(()),
+            _ => validate_record(self.payload()),
+        }
+    }
+
     /// Decode the payload as a miniSEED record.
+    #[cfg(feature = "std")]
     pub fn decode(&self) -> Result<DataFrame> {
         let record = miniseed_rs::decode(self.payload())?;
         Ok(DataFrame {
@@ -118,6 +139,7 @@ impl<'a> RawFrame<'a> {
 }
 
 /// Owned frame with decoded miniSEED record.
+#[cfg(feature = "std")]
 #[derive(Debug)]
 pub struct DataFrame {
     pub sequence: SequenceNumber,
@@ -1,3 +1,5 @@
+use alloc::vec::Vec;
+
 use crate::error::{Result, SeedlinkError};
 use crate::frame::RawFrame;
 use crate::sequence::SequenceNumber;
@@ -6,6 +8,25 @@ pub const SIGNATURE: &[u8; 2] = b"SL";
 pub const HEADER_LEN: usize = 8;
 pub const PAYLOAD_LEN: usize = 512;
 pub const FRAME_LEN: usize = 520;
+/// Sequence field of an INFO frame followed by more INFO frames.
+pub const INFO_CONTINUED: &[u8; 6] = b"INFO *";
+/// Sequence field of the last INFO frame of a response.
+pub const INFO_LAST: &[u8; 6] = b"INFO  ";
+
+/// Whether a v3 frame is an INFO frame followed by more INFO frames.
+///
+/// Returns `Some(true)` for an `SLINFO *` header, `Some(false)` for the
+/// last frame (`SLINFO  `), and `None` for anything else.
+pub fn info_continues(frame: &[u8]) -> Option<bool> {
+    if !frame.starts_with(SIGNATURE) || frame.len() < HEADER_LEN {
+        return None;
+    }
+    match &frame[2..HEADER_LEN] {
+        f if f == INFO_CONTINUED => Some(true),
+        f if f == INFO_LAST => Some(false),
+        _ => None,
+    }
+}
 
 /// Parse a v3 frame from exactly 520 bytes.
 pub fn parse(data: &[u8]) -> Result<RawFrame<'_>> {
@@ -24,8 +45,16 @@ pub fn parse(data: &[u8]) -> Result<RawFrame<'_>> {
         });
     }
 
+    // INFO frames carry "INFO *" / "INFO  " instead of a sequence number
+    if info_continues(data).is_some() {
+        return Ok(RawFrame::V3 {
+            sequence: SequenceNumber::UNSET,
+            payload: &data[HEADER_LEN..FRAME_LEN],
+        });
+    }
+
     // Parse sequence number from 6 hex ASCII chars at bytes 2..8
-    let hex_str = std::str::from_utf8(&data[2..8])
+    let hex_str = core::str::from_utf8(&data[2..8])
         .map_err(|_| SeedlinkError::InvalidSequence("sequence bytes are not valid UTF-8".into()))?;
     let sequence = SequenceNumber::from_v3_hex(hex_str)?;
 
@@ -36,22 +65,28 @@ pub fn parse(data: &[u8]) -> Result<RawFrame<'_>> {
 
 /// Write a v3 frame (520 bytes) from sequence number and payload.
 pub fn write(sequence: SequenceNumber, payload: &[u8]) -> Result<Vec<u8>> {
-    if payload.len() != PAYLOAD_LEN {
-        return Err(SeedlinkError::PayloadLengthMismatch {
-            expected: PAYLOAD_LEN,
-            actual: payload.len(),
-        });
-    }
-
     let mut frame = Vec::with_capacity(FRAME_LEN);
-    frame.extend_from_slice(SIGNATURE);
-    frame.extend_from_slice(sequence.to_v3_hex().as_bytes());
+    write_header(&mut frame, sequence, payload.len())?;
     frame.extend_from_slice(payload);
 
     debug_assert_eq!(frame.len(), FRAME_LEN);
     Ok(frame)
 }
 
+/// Append the 8-byte header of a frame with a `payload_len`-byte payload to
+/// `buf`, so header and payload can be written without joining them.
+pub fn write_header(buf: &mut Vec<u8>, sequence: SequenceNumber, payload_len: usize) -> Result<()> {
+    if payload_len != PAYLOAD_LEN {
+        return Err(SeedlinkError::PayloadLengthMismatch {
+            expected: PAYLOAD_LEN,
+            actual: payload_len,
+        });
+    }
+    buf.extend_from_slice(SIGNATURE);
+    buf.extend_from_slice(sequence.to_v3_hex().as_bytes());
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -74,6 +109,20 @@ mod tests {
         assert_eq!(raw.payload(), &payload[..]);
     }
 
+    #[test]
+    fn parse_info_frames() {
+        let payload = [b'x'; PAYLOAD_LEN];
+        let more = make_test_frame("INFO *", &payload);
+        let last = make_test_frame("INFO  ", &payload);
+        assert_eq!(info_continues(&more), Some(true));
+        assert_eq!(info_continues(&last), Some(false));
+        assert_eq!(info_continues(&make_test_frame("00001A", &payload)), None);
+
+        let raw = parse(&last).unwrap();
+        assert_eq!(raw.sequence(), SequenceNumber::UNSET);
+        assert_eq!(raw.payload(), &payload[..]);
+    }
+
     #[test]
     fn parse_wrong_signature() {
         let payload = [0u8; PAYLOAD_LEN];
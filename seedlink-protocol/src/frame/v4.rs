@@ -1,3 +1,5 @@
+use alloc::vec::Vec;
+
 use crate::error::{Result, SeedlinkError};
 use crate::frame::{PayloadFormat, PayloadSubformat, RawFrame};
 use crate::sequence::SequenceNumber;
@@ -8,10 +10,53 @@ pub const SIGNATURE: &[u8; 2] = b"SE";
 ///                    + 8 (sequence) + 1 (station id len) = 17
 pub const MIN_HEADER_LEN: usize = 17;
 
-/// Parse a v4 frame from the beginning of a buffer.
+/// Default limit on the payload length of a single frame (16 MiB).
+pub const DEFAULT_MAX_PAYLOAD_LEN: usize = 16 * 1024 * 1024;
+
+/// The fixed part of a v4 header, available before the station ID and
+/// payload have arrived.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Header {
+    pub format: PayloadFormat,
+    pub subformat: PayloadSubformat,
+    pub payload_len: usize,
+    pub sequence: SequenceNumber,
+    pub station_id_len: usize,
+}
+
+impl Header {
+    /// Length of the whole frame: header, station ID and payload.
+    pub fn frame_len(&self) -> usize {
+        MIN_HEADER_LEN + self.station_id_len + self.payload_len
+    }
+
+    /// Fail with `PayloadTooLarge` if the payload exceeds `max_payload_len`.
+    pub fn check_payload_len(&self, max_payload_len: usize) -> Result<()> {
+        if self.payload_len > max_payload_len {
+            return Err(SeedlinkError::PayloadTooLarge {
+                len: self.payload_len,
+                max: max_payload_len,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Progress of [`parse_partial()`] on a possibly incomplete buffer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Partial<'a> {
+    /// A complete frame and the number of bytes it occupies.
+    Frame(RawFrame<'a>, usize),
+    /// More bytes are needed: the frame (or its header) is `needed` bytes long.
+    Incomplete { needed: usize },
+}
+
+/// Parse the fixed 17-byte header at the start of `data`.
 ///
-/// Returns `(frame, bytes_consumed)` because v4 frames are variable-length.
-pub fn parse(data: &[u8]) -> Result<(RawFrame<'_>, usize)> {
+/// Only [`MIN_HEADER_LEN`] bytes are needed; [`Header::frame_len()`] then
+/// tells how many bytes the whole frame takes, so a reader can check the
+/// length before allocating a buffer for it.
+pub fn parse_header(data: &[u8]) -> Result<Header> {
     if data.len() < MIN_HEADER_LEN {
         return Err(SeedlinkError::FrameTooShort {
             expected: MIN_HEADER_LEN,
@@ -35,34 +80,75 @@ pub fn parse(data: &[u8]) -> Result<(RawFrame<'_>, usize)> {
     let seq_bytes: [u8; 8] = data[8..16].try_into().unwrap();
     let sequence = SequenceNumber::from_v4_le_bytes(seq_bytes);
 
-    let station_id_len = data[16] as usize;
-    let header_len = MIN_HEADER_LEN + station_id_len;
-    let total_len = header_len + payload_len;
+    Ok(Header {
+        format,
+        subformat,
+        payload_len,
+        sequence,
+        station_id_len: usize::from(data[16]),
+    })
+}
 
+/// Parse a v4 frame from the beginning of a buffer.
+///
+/// Returns `(frame, bytes_consumed)` because v4 frames are variable-length.
+pub fn parse(data: &[u8]) -> Result<(RawFrame<'_>, usize)> {
+    let header = parse_header(data)?;
+    let total_len = header.frame_len();
     if data.len() < total_len {
         return Err(SeedlinkError::FrameTooShort {
             expected: total_len,
             actual: data.len(),
         });
     }
+    Ok((frame_from_header(header, &data[..total_len])?, total_len))
+}
 
-    let station_id = std::str::from_utf8(&data[17..17 + station_id_len])
-        .map_err(|_| SeedlinkError::InvalidCommand("station ID is not valid UTF-8".into()))?;
-
-    let payload = &data[header_len..total_len];
-
-    Ok((
-        RawFrame::V4 {
-            format,
-            subformat,
-            sequence,
-            station_id,
-            payload,
-        },
+/// Parse a v4 frame from a buffer that may hold only part of it.
+///
+/// Returns [`Partial::Incomplete`] with the number of bytes needed when the
+/// header or the rest of the frame has not arrived yet. A payload longer
+/// than `max_payload_len` fails as soon as the header is complete, so a
+/// corrupt length field is never waited for.
+pub fn parse_partial(data: &[u8], max_payload_len: usize) -> Result<Partial<'_>> {
+    if data.len() >= 2 && &data[0..2] != SIGNATURE.as_slice() {
+        return Err(SeedlinkError::InvalidSignature {
+            expected: "SE",
+            actual: [data[0], data[1]],
+        });
+    }
+    if data.len() < MIN_HEADER_LEN {
+        return Ok(Partial::Incomplete {
+            needed: MIN_HEADER_LEN,
+        });
+    }
+    let header = parse_header(data)?;
+    header.check_payload_len(max_payload_len)?;
+    let total_len = header.frame_len();
+    if data.len() < total_len {
+        return Ok(Partial::Incomplete { needed: total_len });
+    }
+    Ok(Partial::Frame(
+        frame_from_header(header, &data[..total_len])?,
         total_len,
     ))
 }
 
+/// Build the frame for `header` from exactly `header.frame_len()` bytes.
+fn frame_from_header(header: Header, data: &[u8]) -> Result<RawFrame<'_>> {
+    let header_len = MIN_HEADER_LEN + header.station_id_len;
+    let station_id = core::str::from_utf8(&data[MIN_HEADER_LEN..header_len])
+        .map_err(|_| SeedlinkError::InvalidCommand("station ID is not valid UTF-8".into()))?;
+
+    Ok(RawFrame::V4 {
+        format: header.format,
+        subformat: header.subformat,
+        sequence: header.sequence,
+        station_id,
+        payload: &data[header_len..],
+    })
+}
+
 /// Write a v4 frame.
 pub fn write(
     format: PayloadFormat,
@@ -71,37 +157,81 @@ pub fn write(
     station_id: &str,
     payload: &[u8],
 ) -> Result<Vec<u8>> {
-    let station_id_bytes = station_id.as_bytes();
-    let station_id_len = station_id_bytes.len();
+    let total_len = MIN_HEADER_LEN + station_id.len() + payload.len();
+    let mut frame = Vec::with_capacity(total_len);
+    write_header(
+        &mut frame,
+        format,
+        subformat,
+        sequence,
+        station_id,
+        payload.len(),
+    )?;
+    // Payload
+    frame.extend_from_slice(payload);
 
-    let header_len = MIN_HEADER_LEN + station_id_len;
-    let total_len = header_len + payload.len();
+    debug_assert_eq!(frame.len(), total_len);
+    Ok(frame)
+}
 
-    let mut frame = Vec::with_capacity(total_len);
+/// Append the header (up to and including the station ID) of a frame with
+/// a `payload_len`-byte payload to `buf`, so header and payload can be
+/// written without joining them.
+pub fn write_header(
+    buf: &mut Vec<u8>,
+    format: PayloadFormat,
+    subformat: PayloadSubformat,
+    sequence: SequenceNumber,
+    station_id: &str,
+    payload_len: usize,
+) -> Result<()> {
+    let station_id_bytes = station_id.as_bytes();
 
     // Signature
-    frame.extend_from_slice(SIGNATURE);
+    buf.extend_from_slice(SIGNATURE);
     // Format + Subformat
-    frame.push(format.to_byte());
-    frame.push(subformat.to_byte());
+    buf.push(format.to_byte());
+    buf.push(subformat.to_byte());
     // Payload length (u32 LE)
-    frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    buf.extend_from_slice(&(payload_len as u32).to_le_bytes());
     // Sequence (u64 LE)
-    frame.extend_from_slice(&sequence.to_v4_le_bytes());
+    buf.extend_from_slice(&sequence.to_v4_le_bytes());
     // Station ID length (u8) + Station ID
-    frame.push(station_id_len as u8);
-    frame.extend_from_slice(station_id_bytes);
-    // Payload
-    frame.extend_from_slice(payload);
-
-    debug_assert_eq!(frame.len(), total_len);
-    Ok(frame)
+    buf.push(station_id_bytes.len() as u8);
+    buf.extend_from_slice(station_id_bytes);
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn write_header_then_payload_matches_write() {
+        let payload = b"payload";
+        let seq = SequenceNumber::new(7);
+        let mut frame = vec![0xEE];
+        write_header(
+            &mut frame,
+            PayloadFormat::MiniSeed2,
+            PayloadSubformat::Data,
+            seq,
+            "GE_WLF",
+            payload.len(),
+        )
+        .unwrap();
+        frame.extend_from_slice(payload);
+        let whole = write(
+            PayloadFormat::MiniSeed2,
+            PayloadSubformat::Data,
+            seq,
+            "GE_WLF",
+            payload,
+        )
+        .unwrap();
+        assert_eq!(frame[1..], whole[..]);
+    }
+
     #[test]
     fn write_parse_roundtrip() {
         let payload = b"test payload data for v4 frame";
@@ -341,4 +471,84 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn parse_header_reports_frame_len() {
+        let frame = write(
+            PayloadFormat::MiniSeed2,
+            PayloadSubformat::Data,
+            SequenceNumber::new(7),
+            "IU_ANMO",
+            b"payload",
+        )
+        .unwrap();
+        let header = parse_header(&frame[..MIN_HEADER_LEN]).unwrap();
+        assert_eq!(header.sequence, SequenceNumber::new(7));
+        assert_eq!(header.payload_len, 7);
+        assert_eq!(header.station_id_len, 7);
+        assert_eq!(header.frame_len(), frame.len());
+        assert!(matches!(
+            parse_header(&frame[..MIN_HEADER_LEN - 1]),
+            Err(SeedlinkError::FrameTooShort { .. })
+        ));
+    }
+
+    #[test]
+    fn parse_partial_grows_to_a_frame() {
+        let frame = write(
+            PayloadFormat::Json,
+            PayloadSubformat::Info,
+            SequenceNumber::new(1),
+            "GE_WLF",
+            b"{}",
+        )
+        .unwrap();
+        for cut in 0..frame.len() {
+            let needed = if cut < MIN_HEADER_LEN {
+                MIN_HEADER_LEN
+            } else {
+                frame.len()
+            };
+            assert_eq!(
+                parse_partial(&frame[..cut], DEFAULT_MAX_PAYLOAD_LEN).unwrap(),
+                Partial::Incomplete { needed },
+                "cut at {cut}"
+            );
+        }
+        let mut data = frame.clone();
+        data.extend_from_slice(b"SE");
+        match parse_partial(&data, DEFAULT_MAX_PAYLOAD_LEN).unwrap() {
+            Partial::Frame(parsed, consumed) => {
+                assert_eq!(consumed, frame.len());
+                assert_eq!(parsed.payload(), b"{}");
+            }
+            other => panic!("expected frame, got {other:?}"),
+        }
+        assert!(matches!(
+            parse_partial(b"SL", DEFAULT_MAX_PAYLOAD_LEN),
+            Err(SeedlinkError::InvalidSignature { .. })
+        ));
+    }
+
+    #[test]
+    fn huge_payload_len_is_rejected_from_the_header() {
+        let mut header = write(
+            PayloadFormat::MiniSeed2,
+            PayloadSubformat::Data,
+            SequenceNumber::new(1),
+            "",
+            b"",
+        )
+        .unwrap();
+        header[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            parse_partial(&header, 4096),
+            Err(SeedlinkError::PayloadTooLarge { max: 4096, .. })
+        ));
+        // Without a limit the frame is merely short: nothing is allocated
+        assert!(matches!(
+            parse(&header),
+            Err(SeedlinkError::FrameTooShort { .. })
+        ));
+    }
 }
@@ -1,4 +1,9 @@
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
+
 use crate::error::{Result, SeedlinkError};
+use crate::frame::{RawFrame, v3};
 use crate::version::ProtocolVersion;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
@@ -61,12 +66,110 @@ impl InfoLevel {
     }
 }
 
-impl std::fmt::Display for InfoLevel {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for InfoLevel {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.write_str(self.as_str())
     }
 }
 
+/// The XML (or JSON) document carried by an INFO response.
+///
+/// v3 sends the document as a run of 512-byte, NUL-padded INFO frames whose
+/// sequence field reads `INFO *` on every frame but the last (`INFO  `).
+/// v4 sends it as a single variable-length frame.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct InfoDocument {
+    text: String,
+}
+
+impl InfoDocument {
+    /// Wrap a complete document.
+    pub fn new(text: impl Into<String>) -> Self {
+        Self { text: text.into() }
+    }
+
+    /// The document text.
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Take the document text.
+    pub fn into_string(self) -> String {
+        self.text
+    }
+
+    /// Split the document into complete v3 INFO frames, header included.
+    ///
+    /// An empty document still produces one (all-NUL) frame.
+    pub fn split_v3(&self) -> Vec<Vec<u8>> {
+        let bytes = self.text.as_bytes();
+        let count = bytes.len().div_ceil(v3::PAYLOAD_LEN).max(1);
+        (0..count)
+            .map(|i| {
+                let start = i * v3::PAYLOAD_LEN;
+                let chunk = &bytes[start..bytes.len().min(start + v3::PAYLOAD_LEN)];
+                let marker = if i + 1 < count {
+                    v3::INFO_CONTINUED
+                } else {
+                    v3::INFO_LAST
+                };
+                let mut frame = Vec::with_capacity(v3::FRAME_LEN);
+                frame.extend_from_slice(v3::SIGNATURE);
+                frame.extend_from_slice(marker);
+                frame.extend_from_slice(chunk);
+                frame.resize(v3::FRAME_LEN, 0);
+                frame
+            })
+            .collect()
+    }
+
+    /// Reassemble a document from the frames of an INFO response.
+    ///
+    /// Each payload is cut at its first NUL. Payloads wrapped in a miniSEED
+    /// record (as some v3 servers send them) have their data section
+    /// extracted first. Invalid UTF-8 is replaced rather than rejected.
+    pub fn from_frames<'a>(frames: impl IntoIterator<Item = RawFrame<'a>>) -> Self {
+        let mut bytes = Vec::new();
+        for frame in frames {
+            let text = info_text(frame.payload());
+            let end = text.iter().position(|&b| b == 0).unwrap_or(text.len());
+            bytes.extend_from_slice(&text[..end]);
+        }
+        Self::new(String::from_utf8_lossy(&bytes))
+    }
+}
+
+impl core::fmt::Display for InfoDocument {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// Extract the text carried by an INFO payload.
+///
+/// Plain XML/JSON is returned as is. A miniSEED 2 wrapper is detected by its
+/// sequence digits and quality byte; its ASCII data section (`num_samples`
+/// bytes at the data offset) is returned instead.
+fn info_text(payload: &[u8]) -> &[u8] {
+    if matches!(payload.first(), Some(b'<' | b'{')) || payload.len() < 48 {
+        return payload;
+    }
+    let is_mseed = matches!(payload[6], b'D' | b'R' | b'Q' | b'M')
+        && payload[..6]
+            .iter()
+            .all(|b| b.is_ascii_digit() || *b == b' ');
+    if !is_mseed {
+        return payload;
+    }
+    let num_samples = u16::from_be_bytes([payload[30], payload[31]]) as usize;
+    let data_offset = u16::from_be_bytes([payload[44], payload[45]]) as usize;
+    if data_offset == 0 || data_offset >= payload.len() {
+        return payload;
+    }
+    let end = (data_offset + num_samples).min(payload.len());
+    &payload[data_offset..end]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -119,6 +222,60 @@ mod tests {
         }
     }
 
+    fn xml(len: usize) -> String {
+        let body = "x".repeat(len.saturating_sub(13));
+        format!("<seedlink>{body}</>")
+    }
+
+    #[test]
+    fn info_document_split_and_reassemble() {
+        for len in [13, 512, 513, 1500] {
+            let doc = InfoDocument::new(xml(len));
+            let frames = doc.split_v3();
+            assert_eq!(frames.len(), len.div_ceil(v3::PAYLOAD_LEN), "len {len}");
+            for (i, frame) in frames.iter().enumerate() {
+                assert_eq!(frame.len(), v3::FRAME_LEN);
+                let last = i + 1 == frames.len();
+                assert_eq!(v3::info_continues(frame), Some(!last));
+            }
+            let back = InfoDocument::from_frames(frames.iter().map(|f| v3::parse(f).unwrap()));
+            assert_eq!(back, doc);
+        }
+
+        let empty = InfoDocument::default().split_v3();
+        assert_eq!(empty.len(), 1);
+        assert_eq!(&empty[0][2..8], v3::INFO_LAST);
+    }
+
+    #[test]
+    fn info_document_from_v4_and_wrapped_payloads() {
+        let json = br#"{"software":"test"}"#;
+        let frame = RawFrame::V4 {
+            format: crate::frame::PayloadFormat::Json,
+            subformat: crate::frame::PayloadSubformat::Info,
+            sequence: crate::SequenceNumber::UNSET,
+            station_id: "",
+            payload: json,
+        };
+        assert_eq!(
+            InfoDocument::from_frames([frame]).as_str(),
+            r#"{"software":"test"}"#
+        );
+
+        // miniSEED 2 record with the XML as its ASCII data section
+        let text = b"<seedlink/>";
+        let mut payload = vec![0u8; v3::PAYLOAD_LEN];
+        payload[..8].copy_from_slice(b"000000M ");
+        payload[30..32].copy_from_slice(&(text.len() as u16).to_be_bytes());
+        payload[44..46].copy_from_slice(&64u16.to_be_bytes());
+        payload[64..64 + text.len()].copy_from_slice(text);
+        let frame = RawFrame::V3 {
+            sequence: crate::SequenceNumber::UNSET,
+            payload: &payload,
+        };
+        assert_eq!(InfoDocument::from_frames([frame]).as_str(), "<seedlink/>");
+    }
+
     #[test]
     fn version_validity() {
         // Both
@@ -2,19 +2,44 @@
 //!
 //! This crate provides the shared protocol layer for SeedLink v3/v4,
 //! used by both the client and server crates.
+//!
+//! With `default-features = false` the crate is `no_std` and needs only
+//! `alloc`: frame encoding and decoding, commands and responses stay
+//! available for embedded dataloggers. miniSEED decoding and validation,
+//! I/O errors and `SystemTime` conversions need the `std` feature.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
 
+pub mod capabilities;
+#[cfg(feature = "tokio-util")]
+pub mod codec;
 pub mod command;
+#[cfg(feature = "zstd")]
+pub mod compression;
 pub mod error;
 pub mod frame;
 pub mod info;
 pub mod response;
 pub mod sequence;
+#[cfg(any(test, feature = "test-util"))]
+pub mod testing;
+pub mod time;
+#[cfg(feature = "std")]
+pub mod validation;
 pub mod version;
 
+pub use capabilities::Capabilities;
 pub use command::Command;
-pub use error::{Result, SeedlinkError};
-pub use frame::{DataFrame, PayloadFormat, PayloadSubformat, RawFrame};
-pub use info::InfoLevel;
+pub use error::{ErrorKind, Result, SeedlinkError};
+#[cfg(feature = "std")]
+pub use frame::DataFrame;
+pub use frame::{FrameDecoder, PayloadFormat, PayloadSubformat, RawFrame};
+pub use info::{InfoDocument, InfoLevel};
 pub use response::Response;
 pub use sequence::SequenceNumber;
+pub use time::SlTime;
+#[cfg(feature = "std")]
+pub use validation::{InvalidRecord, ValidationMode};
 pub use version::ProtocolVersion;
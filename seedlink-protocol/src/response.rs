@@ -1,3 +1,8 @@
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
 use crate::error::{Result, SeedlinkError};
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -12,7 +17,8 @@ pub enum ErrorCode {
 }
 
 impl ErrorCode {
-    fn parse(s: &str) -> Option<Self> {
+    /// Parse a code as sent after `ERROR`, case-insensitively.
+    pub fn parse(s: &str) -> Option<Self> {
         match s.to_uppercase().as_str() {
             "UNSUPPORTED" => Some(Self::Unsupported),
             "UNEXPECTED" => Some(Self::Unexpected),
@@ -36,6 +42,17 @@ impl ErrorCode {
             Self::Internal => "INTERNAL",
         }
     }
+
+    /// Whether the code denies access (`AUTH`, `UNAUTHORIZED`).
+    pub fn is_auth(&self) -> bool {
+        matches!(self, Self::Auth | Self::Unauthorized)
+    }
+
+    /// Whether the code reports a condition that may clear up on a new
+    /// connection (`LIMIT`, `INTERNAL`).
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Self::Limit | Self::Internal)
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -334,14 +351,14 @@ mod tests {
     #[test]
     fn roundtrip_ok() {
         let bytes = Response::Ok.to_bytes();
-        let line = std::str::from_utf8(&bytes).unwrap().trim();
+        let line = core::str::from_utf8(&bytes).unwrap().trim();
         assert_eq!(Response::parse_line(line).unwrap(), Response::Ok);
     }
 
     #[test]
     fn roundtrip_end() {
         let bytes = Response::End.to_bytes();
-        let line = std::str::from_utf8(&bytes).unwrap().trim();
+        let line = core::str::from_utf8(&bytes).unwrap().trim();
         assert_eq!(Response::parse_line(line).unwrap(), Response::End);
     }
 
@@ -352,7 +369,7 @@ mod tests {
             description: "access denied".into(),
         };
         let bytes = original.to_bytes();
-        let line = std::str::from_utf8(&bytes).unwrap().trim();
+        let line = core::str::from_utf8(&bytes).unwrap().trim();
         assert_eq!(Response::parse_line(line).unwrap(), original);
     }
 }
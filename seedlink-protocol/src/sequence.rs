@@ -1,3 +1,6 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+
 use crate::error::{Result, SeedlinkError};
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
@@ -10,9 +13,17 @@ impl SequenceNumber {
     /// Sentinel: request all data (v4).
     pub const ALL_DATA: Self = Self(u64::MAX - 1);
 
+    /// Sentinel: start with the next new record, skipping buffered data
+    /// (v4 `-1`).
+    pub const NEWEST: Self = Self(u64::MAX - 2);
+
     /// Maximum sequence value for v3 (6 hex digits).
     pub const V3_MAX: u64 = 0xFF_FFFF;
 
+    /// Default window for [`is_after()`](Self::is_after): half the v3
+    /// sequence space.
+    pub const V3_WRAP_WINDOW: u64 = 0x80_0000;
+
     pub fn new(value: u64) -> Self {
         Self(value)
     }
@@ -21,9 +32,25 @@ impl SequenceNumber {
         self.0
     }
 
-    /// Returns true if this is a special sentinel value (UNSET or ALL_DATA).
+    /// Returns true if this is a special sentinel value (UNSET, ALL_DATA or
+    /// NEWEST).
     pub fn is_special(self) -> bool {
-        self == Self::UNSET || self == Self::ALL_DATA
+        self == Self::UNSET || self == Self::ALL_DATA || self == Self::NEWEST
+    }
+
+    /// Wrap-aware ordering: whether `self` comes after `other`.
+    ///
+    /// When both values fit in the v3 space, `self` is after `other` if it is
+    /// 1 to `window` steps ahead counting modulo `V3_MAX + 1`, so
+    /// `000002` is after `FFFFFE`. Pass [`V3_WRAP_WINDOW`](Self::V3_WRAP_WINDOW)
+    /// unless the expected distance is known to be smaller. Larger (v4)
+    /// values compare numerically.
+    pub fn is_after(self, other: Self, window: u64) -> bool {
+        if self.0 > Self::V3_MAX || other.0 > Self::V3_MAX {
+            return self.0 > other.0;
+        }
+        let distance = self.0.wrapping_sub(other.0) & Self::V3_MAX;
+        distance != 0 && distance <= window
     }
 
     /// Parse v3 hex representation (6 uppercase hex digits, e.g. "00001A").
@@ -69,23 +96,25 @@ impl SequenceNumber {
 }
 
 impl PartialOrd for SequenceNumber {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
         Some(self.cmp(other))
     }
 }
 
 impl Ord for SequenceNumber {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
         self.0.cmp(&other.0)
     }
 }
 
-impl std::fmt::Display for SequenceNumber {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for SequenceNumber {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         if *self == Self::UNSET {
             write!(f, "UNSET")
         } else if *self == Self::ALL_DATA {
             write!(f, "ALL_DATA")
+        } else if *self == Self::NEWEST {
+            write!(f, "NEWEST")
         } else {
             write!(f, "{}", self.0)
         }
@@ -192,4 +221,31 @@ mod tests {
         assert_eq!(SequenceNumber::ALL_DATA.to_string(), "ALL_DATA");
         assert_eq!(SequenceNumber::new(42).to_string(), "42");
     }
+
+    #[test]
+    fn is_after_without_wrap() {
+        let w = SequenceNumber::V3_WRAP_WINDOW;
+        assert!(SequenceNumber::new(5).is_after(SequenceNumber::new(4), w));
+        assert!(!SequenceNumber::new(4).is_after(SequenceNumber::new(5), w));
+        assert!(!SequenceNumber::new(4).is_after(SequenceNumber::new(4), w));
+    }
+
+    #[test]
+    fn is_after_across_wrap() {
+        let w = SequenceNumber::V3_WRAP_WINDOW;
+        let max = SequenceNumber::new(SequenceNumber::V3_MAX);
+        assert!(SequenceNumber::new(1).is_after(max, w));
+        assert!(!max.is_after(SequenceNumber::new(1), w));
+        assert!(SequenceNumber::new(0x10).is_after(SequenceNumber::new(0xFFFFF0), w));
+        // Outside the window: treated as older
+        assert!(!SequenceNumber::new(10).is_after(SequenceNumber::new(5), 4));
+    }
+
+    #[test]
+    fn is_after_v4_values_compare_numerically() {
+        let w = SequenceNumber::V3_WRAP_WINDOW;
+        let big = SequenceNumber::new(1 << 40);
+        assert!(big.is_after(SequenceNumber::new(1), w));
+        assert!(!SequenceNumber::new(1).is_after(big, w));
+    }
 }
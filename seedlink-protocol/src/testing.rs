@@ -0,0 +1,309 @@
+//! Synthetic miniSEED v2 records for tests and examples (`test-util` feature).
+//!
+//! [`TestRecordBuilder`] writes the 48-byte fixed header and a blockette 1000
+//! by field name instead of magic offsets:
+//!
+//! ```
+//! use seedlink_rs_protocol::testing::TestRecordBuilder;
+//!
+//! let record = TestRecordBuilder::new()
+//!     .nslc("GE", "WLF", "", "HHZ")
+//!     .sample_rate(100.0)
+//!     .samples(&[1, -2, 3])
+//!     .build();
+//! assert_eq!(record.len(), 512);
+//! assert_eq!(&record[8..20], b"WLF    HHZGE");
+//! ```
+
+use crate::time::{SlTime, civil_from_days, days_from_civil};
+
+/// Length of a built record.
+pub const RECORD_LEN: usize = 512;
+
+/// Fixed header plus blockette 1000, padded to the data offset.
+const DATA_OFFSET: usize = 64;
+
+/// Most INT32 samples that fit in a 512-byte record.
+pub const MAX_SAMPLES: usize = (RECORD_LEN - DATA_OFFSET) / 4;
+
+/// Builder for a 512-byte miniSEED v2 record with INT32 big-endian data.
+///
+/// Defaults: `IU.ANMO.00.BHZ`, quality `D`, sequence number 1, start
+/// 2024-001 00:00:00, 20 Hz, no samples. Codes longer than their header
+/// field are truncated, shorter ones space-padded. Built records pass
+/// [`validate_record()`](crate::validation::validate_record) and decode with
+/// `miniseed_rs::decode()`.
+#[derive(Clone, Debug)]
+pub struct TestRecordBuilder {
+    sequence_number: u32,
+    quality: u8,
+    network: String,
+    station: String,
+    location: String,
+    channel: String,
+    year: u16,
+    day: u16,
+    hour: u8,
+    minute: u8,
+    second: u8,
+    ticks: u16,
+    rate_factor: i16,
+    rate_multiplier: i16,
+    num_samples: u16,
+    samples: Vec<i32>,
+}
+
+impl Default for TestRecordBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TestRecordBuilder {
+    /// A builder with the defaults listed on [`TestRecordBuilder`].
+    pub fn new() -> Self {
+        Self {
+            sequence_number: 1,
+            quality: b'D',
+            network: "IU".to_owned(),
+            station: "ANMO".to_owned(),
+            location: "00".to_owned(),
+            channel: "BHZ".to_owned(),
+            year: 2024,
+            day: 1,
+            hour: 0,
+            minute: 0,
+            second: 0,
+            ticks: 0,
+            rate_factor: 20,
+            rate_multiplier: 1,
+            num_samples: 0,
+            samples: Vec::new(),
+        }
+    }
+
+    /// Network code (2 characters).
+    pub fn network(mut self, network: &str) -> Self {
+        self.network = network.to_owned();
+        self
+    }
+
+    /// Station code (5 characters).
+    pub fn station(mut self, station: &str) -> Self {
+        self.station = station.to_owned();
+        self
+    }
+
+    /// Location code (2 characters, may be empty).
+    pub fn location(mut self, location: &str) -> Self {
+        self.location = location.to_owned();
+        self
+    }
+
+    /// Channel code (3 characters).
+    pub fn channel(mut self, channel: &str) -> Self {
+        self.channel = channel.to_owned();
+        self
+    }
+
+    /// Network, station, location and channel at once.
+    pub fn nslc(self, network: &str, station: &str, location: &str, channel: &str) -> Self {
+        self.network(network)
+            .station(station)
+            .location(location)
+            .channel(channel)
+    }
+
+    /// Record sequence number (header bytes 0..6), modulo 1,000,000.
+    pub fn sequence_number(mut self, sequence_number: u32) -> Self {
+        self.sequence_number = sequence_number % 1_000_000;
+        self
+    }
+
+    /// Data quality indicator: `D`, `R`, `Q` or `M`.
+    pub fn quality(mut self, quality: u8) -> Self {
+        self.quality = quality;
+        self
+    }
+
+    /// Start time as BTime fields; `ticks` are 1/10000 s.
+    pub fn btime(
+        mut self,
+        year: u16,
+        day: u16,
+        hour: u8,
+        minute: u8,
+        second: u8,
+        ticks: u16,
+    ) -> Self {
+        (self.year, self.day) = (year, day);
+        (self.hour, self.minute, self.second, self.ticks) = (hour, minute, second, ticks);
+        self
+    }
+
+    /// Start time from an [`SlTime`], truncated to 1/10000 s.
+    ///
+    /// # Panics
+    ///
+    /// If the year is outside `0..=65535`.
+    pub fn start_time(self, time: SlTime) -> Self {
+        let days = time.unix_seconds().div_euclid(86_400);
+        let secs = time.unix_seconds().rem_euclid(86_400);
+        let (year, _, _) = civil_from_days(days);
+        let day = days - days_from_civil(year, 1, 1) + 1;
+        self.btime(
+            u16::try_from(year).expect("year out of BTime range"),
+            day as u16,
+            (secs / 3600) as u8,
+            (secs / 60 % 60) as u8,
+            (secs % 60) as u8,
+            (time.subsec_nanos() / 100_000) as u16,
+        )
+    }
+
+    /// Nominal sample rate in Hz, stored as the header factor/multiplier.
+    ///
+    /// Whole rates are stored exactly, as are whole periods (`0.1` = one
+    /// sample per 10 s); other rates to 1/100 Hz.
+    pub fn sample_rate(self, rate: f64) -> Self {
+        let (factor, multiplier) = if rate <= 0.0 {
+            (0, 0)
+        } else if rate.fract() == 0.0 && rate <= f64::from(i16::MAX) {
+            (rate as i16, 1)
+        } else if (1.0 / rate).fract() == 0.0 && 1.0 / rate <= f64::from(i16::MAX) {
+            (-(1.0 / rate) as i16, 1)
+        } else {
+            ((rate * 100.0).round() as i16, -100)
+        };
+        self.sample_rate_factor(factor, multiplier)
+    }
+
+    /// Raw sample rate factor and multiplier (header bytes 32..36).
+    pub fn sample_rate_factor(mut self, factor: i16, multiplier: i16) -> Self {
+        (self.rate_factor, self.rate_multiplier) = (factor, multiplier);
+        self
+    }
+
+    /// Sample count in the header, without touching the data. Counts above
+    /// [`MAX_SAMPLES`] do not decode.
+    pub fn num_samples(mut self, num_samples: u16) -> Self {
+        self.num_samples = num_samples;
+        self
+    }
+
+    /// INT32 samples; also sets the sample count.
+    ///
+    /// # Panics
+    ///
+    /// If there are more than [`MAX_SAMPLES`].
+    pub fn samples(mut self, samples: &[i32]) -> Self {
+        assert!(
+            samples.len() <= MAX_SAMPLES,
+            "at most {MAX_SAMPLES} samples fit in a {RECORD_LEN}-byte record"
+        );
+        self.samples = samples.to_vec();
+        self.num_samples = samples.len() as u16;
+        self
+    }
+
+    /// Write the record.
+    pub fn build(&self) -> Vec<u8> {
+        let mut record = vec![0u8; RECORD_LEN];
+
+        // Fixed header
+        record[..6].copy_from_slice(format!("{:06}", self.sequence_number).as_bytes());
+        record[6] = self.quality;
+        record[7] = b' ';
+        put_code(&mut record[8..13], &self.station);
+        put_code(&mut record[13..15], &self.location);
+        put_code(&mut record[15..18], &self.channel);
+        put_code(&mut record[18..20], &self.network);
+        record[20..22].copy_from_slice(&self.year.to_be_bytes());
+        record[22..24].copy_from_slice(&self.day.to_be_bytes());
+        record[24] = self.hour;
+        record[25] = self.minute;
+        record[26] = self.second;
+        record[28..30].copy_from_slice(&self.ticks.to_be_bytes());
+        record[30..32].copy_from_slice(&self.num_samples.to_be_bytes());
+        record[32..34].copy_from_slice(&self.rate_factor.to_be_bytes());
+        record[34..36].copy_from_slice(&self.rate_multiplier.to_be_bytes());
+        record[39] = 1; // number of blockettes
+        record[44..46].copy_from_slice(&(DATA_OFFSET as u16).to_be_bytes());
+        record[46..48].copy_from_slice(&48u16.to_be_bytes());
+
+        // Blockette 1000: INT32, big-endian, 2^9 = 512 bytes
+        record[48..50].copy_from_slice(&1000u16.to_be_bytes());
+        record[52] = 3;
+        record[53] = 1;
+        record[54] = 9;
+
+        for (chunk, sample) in record[DATA_OFFSET..].chunks_exact_mut(4).zip(&self.samples) {
+            chunk.copy_from_slice(&sample.to_be_bytes());
+        }
+        record
+    }
+}
+
+/// Write `code` left-aligned into `field`, space-padded or truncated.
+fn put_code(field: &mut [u8], code: &str) {
+    field.fill(b' ');
+    for (dst, src) in field.iter_mut().zip(code.bytes()) {
+        *dst = src;
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::validation::validate_record;
+
+    #[test]
+    fn builds_a_decodable_record() {
+        let record = TestRecordBuilder::new()
+            .nslc("GE", "WLF", "", "HHZ")
+            .sequence_number(42)
+            .quality(b'R')
+            .btime(2024, 60, 12, 30, 15, 5000)
+            .sample_rate(100.0)
+            .samples(&[1, -2, 3])
+            .build();
+        assert_eq!(validate_record(&record), Ok(()));
+        assert_eq!(&record[..8], b"000042R ");
+
+        let decoded = miniseed_rs::decode(&record).unwrap();
+        assert_eq!(decoded.nslc(), "GE.WLF..HHZ");
+        assert_eq!(decoded.sample_rate, 100.0);
+        assert_eq!(decoded.samples, miniseed_rs::Samples::Int(vec![1, -2, 3]));
+        assert_eq!(decoded.start_time.day, 60);
+        assert_eq!(decoded.start_time.nanosecond, 500_000_000);
+    }
+
+    #[test]
+    fn start_time_and_sample_rates() {
+        let t = SlTime::parse("2024-03-01T12:30:15.25Z").unwrap();
+        let record = TestRecordBuilder::new().start_time(t).build();
+        let expected = TestRecordBuilder::new()
+            .btime(2024, 61, 12, 30, 15, 2500)
+            .build();
+        assert_eq!(record, expected);
+
+        let rate = |r: f64| {
+            let record = TestRecordBuilder::new().sample_rate(r).build();
+            (
+                i16::from_be_bytes([record[32], record[33]]),
+                i16::from_be_bytes([record[34], record[35]]),
+            )
+        };
+        assert_eq!(rate(40.0), (40, 1));
+        assert_eq!(rate(0.1), (-10, 1));
+        assert_eq!(rate(2.5), (250, -100));
+    }
+
+    #[test]
+    fn codes_are_padded_and_truncated() {
+        let record = TestRecordBuilder::new()
+            .nslc("NETWORK", "AB", "", "BHZX")
+            .build();
+        assert_eq!(&record[8..20], b"AB     BHZNE");
+    }
+}
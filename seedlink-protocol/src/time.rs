@@ -0,0 +1,415 @@
+//! [`SlTime`]: timestamps in `TIME` and `DATA` commands.
+//!
+//! v3 sends times as comma-separated fields (`2024,01,15,10,30,45`), v4 as
+//! ISO-8601 (`2024-01-15T10:30:45Z`). SeedLink times are always UTC.
+
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+#[cfg(feature = "std")]
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::error::{Result, SeedlinkError};
+use crate::version::ProtocolVersion;
+
+const NANOS_PER_SEC: u32 = 1_000_000_000;
+
+/// A UTC timestamp as used in `TIME` (v3) and `DATA` (v3/v4) commands.
+///
+/// ```
+/// use seedlink_rs_protocol::{ProtocolVersion, SlTime};
+///
+/// let t: SlTime = "2024,1,15,10,30,45".parse().unwrap();
+/// assert_eq!(t.format(ProtocolVersion::V3), "2024,01,15,10,30,45");
+/// assert_eq!(t.format(ProtocolVersion::V4), "2024-01-15T10:30:45Z");
+/// assert_eq!(SlTime::parse("2024-01-15T10:30:45Z").unwrap(), t);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SlTime {
+    seconds: i64,
+    nanos: u32,
+}
+
+impl SlTime {
+    /// Build from calendar fields. Fails if any field is out of range.
+    pub fn from_ymd_hms(
+        year: i32,
+        month: u32,
+        day: u32,
+        hour: u32,
+        minute: u32,
+        second: u32,
+    ) -> Result<Self> {
+        if !(1..=12).contains(&month)
+            || day < 1
+            || day > days_in_month(year, month)
+            || hour > 23
+            || minute > 59
+            || second > 59
+        {
+            return Err(SeedlinkError::InvalidTime(format!(
+                "{year:04}-{month:02}-{day:02} {hour:02}:{minute:02}:{second:02} out of range"
+            )));
+        }
+        let days = days_from_civil(i64::from(year), month, day);
+        let seconds =
+            days * 86_400 + i64::from(hour) * 3600 + i64::from(minute) * 60 + i64::from(second);
+        Ok(Self { seconds, nanos: 0 })
+    }
+
+    /// Build from seconds and nanoseconds since the Unix epoch. Excess
+    /// nanoseconds carry into the seconds.
+    pub fn from_unix(seconds: i64, nanos: u32) -> Self {
+        Self {
+            seconds: seconds + i64::from(nanos / NANOS_PER_SEC),
+            nanos: nanos % NANOS_PER_SEC,
+        }
+    }
+
+    /// The same time with the sub-second part set to `nanos` (`< 1_000_000_000`).
+    pub fn with_nanos(self, nanos: u32) -> Self {
+        Self::from_unix(self.seconds, nanos)
+    }
+
+    /// Whole seconds since the Unix epoch.
+    pub fn unix_seconds(self) -> i64 {
+        self.seconds
+    }
+
+    /// Nanoseconds past [`unix_seconds()`](Self::unix_seconds).
+    pub fn subsec_nanos(self) -> u32 {
+        self.nanos
+    }
+
+    /// Parse either the v3 comma format or ISO-8601.
+    pub fn parse(s: &str) -> Result<Self> {
+        if s.contains(',') {
+            Self::parse_v3(s)
+        } else {
+            Self::parse_iso8601(s)
+        }
+    }
+
+    /// Parse the v3 format `YYYY,MM,DD,hh,mm,ss`. Fields need not be
+    /// zero-padded.
+    pub fn parse_v3(s: &str) -> Result<Self> {
+        let invalid = || SeedlinkError::InvalidTime(s.to_owned());
+        let fields = s
+            .split(',')
+            .map(|f| f.parse::<u32>().map_err(|_| invalid()))
+            .collect::<Result<Vec<_>>>()?;
+        let [year, month, day, hour, minute, second] = fields[..] else {
+            return Err(invalid());
+        };
+        let year = i32::try_from(year).map_err(|_| invalid())?;
+        Self::from_ymd_hms(year, month, day, hour, minute, second).map_err(|_| invalid())
+    }
+
+    /// Parse ISO-8601 as used by v4: `YYYY-MM-DDThh:mm:ss[.fffffffff][Z]`.
+    ///
+    /// The time part may be omitted (`2024-01-15` = midnight). Fractional
+    /// digits beyond nanoseconds are ignored; the `Z` suffix is optional.
+    pub fn parse_iso8601(s: &str) -> Result<Self> {
+        let invalid = || SeedlinkError::InvalidTime(s.to_owned());
+        let field = |f: Option<&str>| -> Result<u32> {
+            f.filter(|f| !f.is_empty() && f.bytes().all(|b| b.is_ascii_digit()))
+                .and_then(|f| f.parse().ok())
+                .ok_or_else(invalid)
+        };
+
+        let rest = s.strip_suffix('Z').unwrap_or(s);
+        let (date, time) = match rest.split_once('T') {
+            Some((d, t)) => (d, Some(t)),
+            None => (rest, None),
+        };
+        let mut date_parts = date.split('-');
+        let (year, month, day) = (
+            field(date_parts.next())?,
+            field(date_parts.next())?,
+            field(date_parts.next())?,
+        );
+        if date_parts.next().is_some() {
+            return Err(invalid());
+        }
+
+        let (hour, minute, second, nanos) = match time {
+            Some(t) => {
+                let (t, nanos) = match t.split_once('.') {
+                    Some((whole, frac)) => {
+                        field(Some(frac))?;
+                        let digits: String =
+                            frac.chars().chain("000000000".chars()).take(9).collect();
+                        (whole, digits.parse().map_err(|_| invalid())?)
+                    }
+                    None => (t, 0),
+                };
+                let mut time_parts = t.split(':');
+                let hms = (
+                    field(time_parts.next())?,
+                    field(time_parts.next())?,
+                    field(time_parts.next())?,
+                );
+                if time_parts.next().is_some() {
+                    return Err(invalid());
+                }
+                (hms.0, hms.1, hms.2, nanos)
+            }
+            None => (0, 0, 0, 0),
+        };
+
+        let year = i32::try_from(year).map_err(|_| invalid())?;
+        Ok(Self::from_ymd_hms(year, month, day, hour, minute, second)
+            .map_err(|_| invalid())?
+            .with_nanos(nanos))
+    }
+
+    /// Format as `YYYY,MM,DD,hh,mm,ss` (v3). Sub-second digits are dropped.
+    pub fn to_v3(self) -> String {
+        let (year, month, day, hour, minute, second) = self.fields();
+        format!("{year:04},{month:02},{day:02},{hour:02},{minute:02},{second:02}")
+    }
+
+    /// Format as ISO-8601 with a `Z` suffix (v4). Fractional seconds are
+    /// written only when non-zero.
+    pub fn to_iso8601(self) -> String {
+        let (year, month, day, hour, minute, second) = self.fields();
+        let frac = match self.nanos {
+            0 => String::new(),
+            n if n % 1000 == 0 => format!(".{:06}", n / 1000),
+            n => format!(".{n:09}"),
+        };
+        format!("{year:04}-{month:02}-{day:02}T{hour:02}:{minute:02}:{second:02}{frac}Z")
+    }
+
+    /// Format for the wire format of `version`.
+    pub fn format(self, version: ProtocolVersion) -> String {
+        match version {
+            ProtocolVersion::V3 => self.to_v3(),
+            ProtocolVersion::V4 => self.to_iso8601(),
+        }
+    }
+
+    fn fields(self) -> (i64, u32, u32, u32, u32, u32) {
+        let days = self.seconds.div_euclid(86_400);
+        let secs = self.seconds.rem_euclid(86_400) as u32;
+        let (year, month, day) = civil_from_days(days);
+        (year, month, day, secs / 3600, secs / 60 % 60, secs % 60)
+    }
+}
+
+/// ISO-8601, as [`to_iso8601()`](SlTime::to_iso8601).
+impl fmt::Display for SlTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_iso8601())
+    }
+}
+
+impl FromStr for SlTime {
+    type Err = SeedlinkError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<SystemTime> for SlTime {
+    fn from(time: SystemTime) -> Self {
+        match time.duration_since(UNIX_EPOCH) {
+            Ok(d) => Self::from_unix(d.as_secs() as i64, d.subsec_nanos()),
+            Err(e) => {
+                let d = e.duration();
+                let (secs, nanos) = (-(d.as_secs() as i64), d.subsec_nanos());
+                if nanos == 0 {
+                    Self::from_unix(secs, 0)
+                } else {
+                    Self::from_unix(secs - 1, NANOS_PER_SEC - nanos)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<SlTime> for SystemTime {
+    fn from(time: SlTime) -> Self {
+        let whole = Duration::from_secs(time.seconds.unsigned_abs());
+        let base = if time.seconds >= 0 {
+            UNIX_EPOCH + whole
+        } else {
+            UNIX_EPOCH - whole
+        };
+        base + Duration::from_nanos(u64::from(time.nanos))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<chrono::DateTime<chrono::Utc>> for SlTime {
+    fn from(time: chrono::DateTime<chrono::Utc>) -> Self {
+        // Leap seconds are reported as nanos >= 1e9; clamp into the second
+        let nanos = time.timestamp_subsec_nanos().min(NANOS_PER_SEC - 1);
+        Self::from_unix(time.timestamp(), nanos)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<SlTime> for chrono::DateTime<chrono::Utc> {
+    type Error = SeedlinkError;
+
+    fn try_from(time: SlTime) -> Result<Self> {
+        chrono::DateTime::from_timestamp(time.seconds, time.nanos)
+            .ok_or_else(|| SeedlinkError::InvalidTime(format!("{time} out of chrono range")))
+    }
+}
+
+fn is_leap(year: i32) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if is_leap(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date.
+pub(crate) fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = i64::from((month + 9) % 12);
+    let doy = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Inverse of [`days_from_civil()`]: `(year, month, day)`.
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_v3_and_iso8601_agree() {
+        let v3 = SlTime::parse_v3("2024,1,15,10,30,45").unwrap();
+        let iso = SlTime::parse_iso8601("2024-01-15T10:30:45Z").unwrap();
+        assert_eq!(v3, iso);
+        assert_eq!(v3.unix_seconds(), 1_705_314_645);
+        assert_eq!(SlTime::parse("2024,01,15,10,30,45").unwrap(), v3);
+        // No Z, date only
+        assert_eq!(
+            SlTime::parse("2024-02-29").unwrap(),
+            SlTime::from_ymd_hms(2024, 2, 29, 0, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_invalid() {
+        for s in [
+            "",
+            "2024,13,1,0,0,0",
+            "2024,0,1,0,0,0",
+            "2024,1,32,0,0,0",
+            "2023,2,29,0,0,0",
+            "2024,1,1,24,0,0",
+            "2024,1,0,0,0",
+            "not,a,time,at,all,x",
+            "2024-13-01T00:00:00Z",
+            "2024-01-01T00:00Z",
+            "2024-01-01T00:00:00.Z",
+            "2024-01-01T00:00:00+01:00",
+        ] {
+            assert!(
+                matches!(SlTime::parse(s), Err(SeedlinkError::InvalidTime(_))),
+                "{s:?}"
+            );
+        }
+        assert!(SlTime::parse_iso8601("2024,1,1,0,0,0").is_err());
+    }
+
+    #[test]
+    fn format_for_each_version() {
+        let t = SlTime::from_ymd_hms(2024, 3, 1, 12, 30, 5).unwrap();
+        assert_eq!(t.to_v3(), "2024,03,01,12,30,05");
+        assert_eq!(t.to_iso8601(), "2024-03-01T12:30:05Z");
+        assert_eq!(
+            t.with_nanos(250_000_000).format(ProtocolVersion::V4),
+            "2024-03-01T12:30:05.250000Z"
+        );
+        assert_eq!(
+            t.with_nanos(1).format(ProtocolVersion::V4),
+            "2024-03-01T12:30:05.000000001Z"
+        );
+        // v3 has no fractional seconds
+        assert_eq!(t.with_nanos(1).format(ProtocolVersion::V3), t.to_v3());
+        assert_eq!(
+            SlTime::parse_iso8601("2024-03-01T12:30:05.25").unwrap(),
+            t.with_nanos(250_000_000)
+        );
+    }
+
+    #[test]
+    fn calendar_roundtrip() {
+        for (y, m, d) in [
+            (1970, 1, 1),
+            (1969, 12, 31),
+            (2000, 2, 29),
+            (2100, 3, 1),
+            (1900, 1, 1),
+        ] {
+            let t = SlTime::from_ymd_hms(y, m, d, 23, 59, 59).unwrap();
+            assert_eq!(t.fields(), (i64::from(y), m, d, 23, 59, 59));
+            assert_eq!(SlTime::parse(&t.to_iso8601()).unwrap(), t);
+        }
+        assert_eq!(
+            SlTime::from_ymd_hms(1970, 1, 1, 0, 0, 0)
+                .unwrap()
+                .unix_seconds(),
+            0
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn system_time_conversions() {
+        let t = SystemTime::UNIX_EPOCH + Duration::new(1_705_314_645, 500);
+        let sl = SlTime::from(t);
+        assert_eq!(sl.to_iso8601(), "2024-01-15T10:30:45.000000500Z");
+        assert_eq!(SystemTime::from(sl), t);
+
+        let before = SystemTime::UNIX_EPOCH - Duration::from_millis(1500);
+        let sl = SlTime::from(before);
+        assert_eq!((sl.unix_seconds(), sl.subsec_nanos()), (-2, 500_000_000));
+        assert_eq!(sl.to_iso8601(), "1969-12-31T23:59:58.500000Z");
+        assert_eq!(SystemTime::from(sl), before);
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn chrono_conversions() {
+        use chrono::{DateTime, TimeZone, Utc};
+
+        let dt = Utc.with_ymd_and_hms(2024, 1, 15, 10, 30, 45).unwrap();
+        let sl = SlTime::from(dt);
+        assert_eq!(sl, SlTime::parse("2024,1,15,10,30,45").unwrap());
+        assert_eq!(DateTime::<Utc>::try_from(sl).unwrap(), dt);
+    }
+}
@@ -0,0 +1,231 @@
+//! miniSEED record validation.
+//!
+//! Records arriving over a flaky link can be corrupted in ways that still
+//! produce a well-formed SeedLink frame. [`validate_record()`] catches the
+//! common cases cheaply: a miniSEED 2 fixed header with impossible values,
+//! or a miniSEED 3 record whose CRC-32C does not match.
+
+use miniseed_rs::crc::crc32c;
+
+/// miniSEED 2 fixed header length.
+const MSEED2_HEADER_LEN: usize = 48;
+/// miniSEED 3 fixed header length, before the source identifier.
+const MSEED3_HEADER_LEN: usize = 40;
+
+/// What to do with a record that fails [`validate_record()`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ValidationMode {
+    /// Do not validate records.
+    #[default]
+    Off,
+    /// Discard invalid records.
+    Drop,
+    /// Keep invalid records, but count them and log a warning.
+    Flag,
+    /// Fail with an error.
+    Error,
+}
+
+/// Why a record failed validation.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum InvalidRecord {
+    #[error("record too short: {0} bytes")]
+    TooShort(usize),
+
+    #[error("invalid sequence number field")]
+    SequenceNumber,
+
+    #[error("invalid data quality indicator: {0:#04x}")]
+    Quality(u8),
+
+    #[error("invalid station identifier")]
+    StationId,
+
+    #[error("invalid start time")]
+    StartTime,
+
+    #[error("invalid header offsets")]
+    Offsets,
+
+    #[error("record truncated: header declares {declared} bytes, actual {actual}")]
+    Truncated { declared: usize, actual: usize },
+
+    #[error("CRC mismatch: stored {stored:#010x}, computed {computed:#010x}")]
+    Crc { stored: u32, computed: u32 },
+}
+
+/// Check a miniSEED record for corruption.
+///
+/// miniSEED 3 records (starting with `MS` and version 3) are checked for
+/// length and CRC-32C. Anything else is treated as miniSEED 2 and its fixed
+/// header is checked for sane values: sequence number digits, data quality
+/// indicator, printable station codes, start time ranges (in either byte
+/// order) and blockette/data offsets within the record.
+pub fn validate_record(payload: &[u8]) -> Result<(), InvalidRecord> {
+    if payload.starts_with(b"MS\x03") {
+        validate_mseed3(payload)
+    } else {
+        validate_mseed2(payload)
+    }
+}
+
+fn validate_mseed2(payload: &[u8]) -> Result<(), InvalidRecord> {
+    if payload.len() < MSEED2_HEADER_LEN {
+        return Err(InvalidRecord::TooShort(payload.len()));
+    }
+    if !payload[..6]
+        .iter()
+        .all(|b| b.is_ascii_digit() || *b == b' ')
+    {
+        return Err(InvalidRecord::SequenceNumber);
+    }
+    if !matches!(payload[6], b'D' | b'R' | b'Q' | b'M') {
+        return Err(InvalidRecord::Quality(payload[6]));
+    }
+    if !payload[7..20].iter().all(|b| (0x20..=0x7E).contains(b)) {
+        return Err(InvalidRecord::StationId);
+    }
+
+    // Fixed header fields are big-endian by convention, but little-endian
+    // records exist; accept whichever order gives a valid start time.
+    let be = |i: usize| u16::from_be_bytes([payload[i], payload[i + 1]]);
+    let le = |i: usize| u16::from_le_bytes([payload[i], payload[i + 1]]);
+    let read: &dyn Fn(usize) -> u16 = if valid_btime(payload, be) {
+        &be
+    } else if valid_btime(payload, le) {
+        &le
+    } else {
+        return Err(InvalidRecord::StartTime);
+    };
+
+    let len = payload.len();
+    let data_offset = usize::from(read(44));
+    let blockette_offset = usize::from(read(46));
+    let in_record = |offset: usize| offset == 0 || (MSEED2_HEADER_LEN..len).contains(&offset);
+    if !in_record(data_offset) || !in_record(blockette_offset) {
+        return Err(InvalidRecord::Offsets);
+    }
+    Ok(())
+}
+
+/// BTime at bytes 20..30: year, day of year, hour, minute, second, unused,
+/// ticks (1/10000 s).
+fn valid_btime(payload: &[u8], read: impl Fn(usize) -> u16) -> bool {
+    (1900..=2100).contains(&read(20))
+        && (1..=366).contains(&read(22))
+        && payload[24] < 24
+        && payload[25] < 60
+        && payload[26] <= 60
+        && read(28) < 10_000
+}
+
+fn validate_mseed3(payload: &[u8]) -> Result<(), InvalidRecord> {
+    if payload.len() < MSEED3_HEADER_LEN {
+        return Err(InvalidRecord::TooShort(payload.len()));
+    }
+    let sid_len = usize::from(payload[33]);
+    let extra_len = usize::from(u16::from_le_bytes([payload[34], payload[35]]));
+    let data_len = u32::from_le_bytes(payload[36..40].try_into().unwrap()) as usize;
+    let declared = MSEED3_HEADER_LEN + sid_len + extra_len + data_len;
+    if declared > payload.len() {
+        return Err(InvalidRecord::Truncated {
+            declared,
+            actual: payload.len(),
+        });
+    }
+
+    let mut record = payload[..declared].to_vec();
+    let stored = u32::from_le_bytes(record[28..32].try_into().unwrap());
+    record[28..32].fill(0);
+    let computed = crc32c(&record);
+    if stored != computed {
+        return Err(InvalidRecord::Crc { stored, computed });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mseed2() -> Vec<u8> {
+        let mut p = vec![0u8; 512];
+        p[..8].copy_from_slice(b"000001D ");
+        p[8..20].copy_from_slice(b"ANMO 00BHZIU");
+        p[20..22].copy_from_slice(&2024u16.to_be_bytes());
+        p[22..24].copy_from_slice(&15u16.to_be_bytes());
+        p[24] = 10;
+        p[25] = 30;
+        p[26] = 0;
+        p[28..30].copy_from_slice(&5000u16.to_be_bytes());
+        p[44..46].copy_from_slice(&64u16.to_be_bytes());
+        p[46..48].copy_from_slice(&48u16.to_be_bytes());
+        p
+    }
+
+    fn mseed3() -> Vec<u8> {
+        let sid = b"FDSN:IU_ANMO_00_B_H_Z";
+        let data = [1u8, 2, 3, 4];
+        let mut p = vec![0u8; MSEED3_HEADER_LEN];
+        p[..3].copy_from_slice(b"MS\x03");
+        p[8..10].copy_from_slice(&2024u16.to_le_bytes());
+        p[10..12].copy_from_slice(&15u16.to_le_bytes());
+        p[33] = sid.len() as u8;
+        p[36..40].copy_from_slice(&(data.len() as u32).to_le_bytes());
+        p.extend_from_slice(sid);
+        p.extend_from_slice(&data);
+        let crc = crc32c(&p);
+        p[28..32].copy_from_slice(&crc.to_le_bytes());
+        p
+    }
+
+    #[test]
+    fn mseed2_header_checks() {
+        assert_eq!(validate_record(&mseed2()), Ok(()));
+
+        // Little-endian headers are accepted too
+        let mut p = mseed2();
+        p[20..22].copy_from_slice(&2024u16.to_le_bytes());
+        p[22..24].copy_from_slice(&15u16.to_le_bytes());
+        p[28..30].copy_from_slice(&5000u16.to_le_bytes());
+        p[44..46].copy_from_slice(&64u16.to_le_bytes());
+        p[46..48].copy_from_slice(&48u16.to_le_bytes());
+        assert_eq!(validate_record(&p), Ok(()));
+
+        let corrupt = |i: usize, b: u8| {
+            let mut p = mseed2();
+            p[i] = b;
+            validate_record(&p)
+        };
+        assert_eq!(corrupt(2, b'x'), Err(InvalidRecord::SequenceNumber));
+        assert_eq!(corrupt(6, b'Z'), Err(InvalidRecord::Quality(b'Z')));
+        assert_eq!(corrupt(10, 0xFF), Err(InvalidRecord::StationId));
+        assert_eq!(corrupt(24, 25), Err(InvalidRecord::StartTime));
+        assert_eq!(corrupt(44, 0x10), Err(InvalidRecord::Offsets));
+        assert_eq!(
+            validate_record(&mseed2()[..40]),
+            Err(InvalidRecord::TooShort(40))
+        );
+    }
+
+    #[test]
+    fn mseed3_crc_checks() {
+        let p = mseed3();
+        assert_eq!(validate_record(&p), Ok(()));
+
+        let mut flipped = p.clone();
+        *flipped.last_mut().unwrap() ^= 0x01;
+        assert!(matches!(
+            validate_record(&flipped),
+            Err(InvalidRecord::Crc { .. })
+        ));
+
+        assert_eq!(
+            validate_record(&p[..p.len() - 1]),
+            Err(InvalidRecord::Truncated {
+                declared: p.len(),
+                actual: p.len() - 1,
+            })
+        );
+    }
+}
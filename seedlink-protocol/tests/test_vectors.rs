@@ -3,7 +3,7 @@
 use std::path::PathBuf;
 
 use seedlink_rs_protocol::frame::{v3, v4};
-use seedlink_rs_protocol::{Command, ProtocolVersion, Response, SequenceNumber};
+use seedlink_rs_protocol::{Command, ProtocolVersion, Response, SequenceNumber, SlTime};
 
 fn vectors_dir() -> Option<PathBuf> {
     let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
@@ -140,12 +140,16 @@ fn test_command_vectors() {
                     _ => panic!("sequence mismatch for {line:?}"),
                 }
                 match (start, &fields["start"]) {
-                    (Some(s), serde_json::Value::String(expected)) => assert_eq!(s, expected),
+                    (Some(s), serde_json::Value::String(expected)) => {
+                        assert_eq!(*s, SlTime::parse(expected).unwrap())
+                    }
                     (None, serde_json::Value::Null) => {}
                     _ => panic!("start mismatch for {line:?}"),
                 }
                 match (end, &fields["end"]) {
-                    (Some(e), serde_json::Value::String(expected)) => assert_eq!(e, expected),
+                    (Some(e), serde_json::Value::String(expected)) => {
+                        assert_eq!(*e, SlTime::parse(expected).unwrap())
+                    }
                     (None, serde_json::Value::Null) => {}
                     _ => panic!("end mismatch for {line:?}"),
                 }
@@ -154,9 +158,14 @@ fn test_command_vectors() {
                 assert_eq!(level.as_str(), fields["level"].as_str().unwrap());
             }
             Command::Time { start, end } => {
-                assert_eq!(start, fields["start"].as_str().unwrap());
+                assert_eq!(
+                    *start,
+                    SlTime::parse(fields["start"].as_str().unwrap()).unwrap()
+                );
                 match (end, &fields["end"]) {
-                    (Some(e), serde_json::Value::String(expected)) => assert_eq!(e, expected),
+                    (Some(e), serde_json::Value::String(expected)) => {
+                        assert_eq!(*e, SlTime::parse(expected).unwrap())
+                    }
                     (None, serde_json::Value::Null) => {}
                     _ => panic!("end mismatch for {line:?}"),
                 }
@@ -0,0 +1,55 @@
+from types import TracebackType
+from typing import final
+
+import numpy as np
+import numpy.typing as npt
+
+class SeedLinkError(Exception): ...
+
+@final
+class Record:
+    @property
+    def sequence(self) -> int: ...
+    @property
+    def network(self) -> str: ...
+    @property
+    def station(self) -> str: ...
+    @property
+    def location(self) -> str: ...
+    @property
+    def channel(self) -> str: ...
+    @property
+    def sample_rate(self) -> float: ...
+    @property
+    def start_time(self) -> float: ...
+    @property
+    def samples(
+        self,
+    ) -> npt.NDArray[np.int32] | npt.NDArray[np.float32] | npt.NDArray[np.float64] | list[int] | list[float]: ...
+    @property
+    def raw(self) -> bytes: ...
+    def __len__(self) -> int: ...
+
+@final
+class Client:
+    def __init__(self, address: str, *, prefer_v4: bool = True, timeout: float = 30.0) -> None: ...
+    @property
+    def version(self) -> int: ...
+    def station(self, station: str, network: str) -> None: ...
+    def select(self, pattern: str) -> None: ...
+    def data(self, sequence: int | None = None) -> None: ...
+    def time_window(self, start: str, end: str | None = None) -> None: ...
+    def end_stream(self) -> None: ...
+    def fetch(self) -> None: ...
+    def next_record(self) -> Record | None: ...
+    def last_sequence(self, network: str, station: str) -> int | None: ...
+    def close(self) -> None: ...
+    def __iter__(self) -> Client: ...
+    def __next__(self) -> Record: ...
+    def __enter__(self) -> Client: ...
+    def __exit__(
+        self,
+        exc_type: type[BaseException] | None,
+        exc_value: BaseException | None,
+        traceback: TracebackType | None,
+    ) -> bool: ...
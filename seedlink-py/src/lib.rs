@@ -0,0 +1,465 @@
+//! Python bindings for the SeedLink client: the `seedlink_rs` module.
+//!
+//! ```python
+//! import seedlink_rs
+//!
+//! with seedlink_rs.Client("rtserve.iris.washington.edu:18000") as client:
+//!     client.station("ANMO", "IU")
+//!     client.select("BHZ")
+//!     client.data()
+//!     client.end_stream()
+//!     for record in client:
+//!         print(record.channel, record.start_time, record.samples.mean())
+//! ```
+//!
+//! [`Client`] wraps the [blocking client](SeedLinkClient) and releases the GIL while
+//! waiting on the network. Build the wheel with `maturin build` in this
+//! directory.
+
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use numpy::IntoPyArray;
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyList};
+use seedlink_rs_client::blocking::SeedLinkClient;
+use seedlink_rs_client::{ClientConfig, ClientError, OwnedFrame, SampleData, SampleRecord};
+use seedlink_rs_protocol::{ProtocolVersion, SequenceNumber};
+
+create_exception!(
+    seedlink_rs,
+    SeedLinkError,
+    PyException,
+    "A SeedLink connection, protocol or decoding error."
+);
+
+fn to_py_err(error: ClientError) -> PyErr {
+    SeedLinkError::new_err(error.to_string())
+}
+
+/// A connection to a SeedLink server.
+///
+/// Connects in the constructor. Subscribe with `station()`, `select()` and
+/// `data()`, start with `end_stream()` or `fetch()`, then iterate to receive
+/// [`Record`]s until the server closes the stream.
+#[pyclass(module = "seedlink_rs")]
+pub struct Client {
+    /// `None` once closed.
+    inner: Mutex<Option<SeedLinkClient>>,
+}
+
+impl Client {
+    /// Run `f` on the connection with the GIL released.
+    fn with_client<T: Send>(
+        &self,
+        py: Python<'_>,
+        f: impl FnOnce(&mut SeedLinkClient) -> Result<T, ClientError> + Send,
+    ) -> PyResult<T> {
+        py.detach(|| {
+            let mut guard = self.lock()?;
+            let client = guard
+                .as_mut()
+                .ok_or_else(|| SeedLinkError::new_err("client is closed"))?;
+            f(client).map_err(to_py_err)
+        })
+    }
+
+    fn lock(&self) -> PyResult<MutexGuard<'_, Option<SeedLinkClient>>> {
+        self.inner
+            .lock()
+            .map_err(|_| SeedLinkError::new_err("client lock poisoned"))
+    }
+}
+
+#[pymethods]
+impl Client {
+    /// Connect to `address` (`host:port`). `timeout` applies to connecting
+    /// and to each read, in seconds.
+    #[new]
+    #[pyo3(signature = (address, *, prefer_v4 = true, timeout = 30.0))]
+    fn new(py: Python<'_>, address: &str, prefer_v4: bool, timeout: f64) -> PyResult<Self> {
+        let timeout = Duration::try_from_secs_f64(timeout)
+            .map_err(|e| PyValueError::new_err(format!("invalid timeout: {e}")))?;
+        let config = ClientConfig {
+            connect_timeout: timeout,
+            read_timeout: timeout,
+            prefer_v4,
+            ..ClientConfig::default()
+        };
+        let client = py
+            .detach(|| SeedLinkClient::connect_with_config(address, config))
+            .map_err(to_py_err)?;
+        Ok(Self {
+            inner: Mutex::new(Some(client)),
+        })
+    }
+
+    /// Negotiated protocol version: 3 or 4.
+    #[getter]
+    fn version(&self) -> PyResult<u8> {
+        let guard = self.lock()?;
+        let client = guard
+            .as_ref()
+            .ok_or_else(|| SeedLinkError::new_err("client is closed"))?;
+        Ok(match client.version() {
+            ProtocolVersion::V3 => 3,
+            ProtocolVersion::V4 => 4,
+        })
+    }
+
+    /// Select a station (`STATION`).
+    fn station(&self, py: Python<'_>, station: &str, network: &str) -> PyResult<()> {
+        self.with_client(py, |c| c.station(station, network))
+    }
+
+    /// Filter channels of the selected station (`SELECT`), e.g. `"BHZ"`.
+    fn select(&self, py: Python<'_>, pattern: &str) -> PyResult<()> {
+        self.with_client(py, |c| c.select(pattern))
+    }
+
+    /// Arm the selected station (`DATA`), optionally resuming from
+    /// `sequence`.
+    #[pyo3(signature = (sequence = None))]
+    fn data(&self, py: Python<'_>, sequence: Option<u64>) -> PyResult<()> {
+        self.with_client(py, |c| match sequence {
+            Some(seq) => c.data_from(SequenceNumber::new(seq)),
+            None => c.data(),
+        })
+    }
+
+    /// Request a time window (`TIME`, v3 only). Times are
+    /// `"YYYY,MM,DD,hh,mm,ss"` or ISO-8601 strings.
+    #[pyo3(signature = (start, end = None))]
+    fn time_window(&self, py: Python<'_>, start: &str, end: Option<&str>) -> PyResult<()> {
+        self.with_client(py, |c| c.time_window(start, end))
+    }
+
+    /// Start continuous streaming (`END`).
+    fn end_stream(&self, py: Python<'_>) -> PyResult<()> {
+        self.with_client(py, |c| c.end_stream())
+    }
+
+    /// Stream buffered data, then close (`FETCH`, v3 only).
+    fn fetch(&self, py: Python<'_>) -> PyResult<()> {
+        self.with_client(py, |c| c.fetch())
+    }
+
+    /// Wait for the next record. Returns `None` when the stream ends.
+    fn next_record(&self, py: Python<'_>) -> PyResult<Option<Record>> {
+        let frame = self.with_client(py, |c| c.next_frame())?;
+        frame.map(Record::from_frame).transpose()
+    }
+
+    /// Last sequence number received for a station, or `None`.
+    fn last_sequence(&self, network: &str, station: &str) -> PyResult<Option<u64>> {
+        let guard = self.lock()?;
+        Ok(guard
+            .as_ref()
+            .and_then(|c| c.last_sequence(network, station))
+            .map(SequenceNumber::value))
+    }
+
+    /// Send `BYE` and close the connection. Does nothing if already closed.
+    fn close(&self, py: Python<'_>) -> PyResult<()> {
+        let client = self.lock()?.take();
+        match client {
+            Some(client) => py.detach(|| client.close()).map_err(to_py_err),
+            None => Ok(()),
+        }
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&self, py: Python<'_>) -> PyResult<Option<Record>> {
+        self.next_record(py)
+    }
+
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __exit__(
+        &self,
+        py: Python<'_>,
+        _exc_type: Py<PyAny>,
+        _exc_value: Py<PyAny>,
+        _traceback: Py<PyAny>,
+    ) -> PyResult<bool> {
+        self.close(py)?;
+        Ok(false)
+    }
+}
+
+/// A decoded miniSEED record.
+///
+/// `samples` is a numpy array (`int32`, `float32` or `float64`), or a list
+/// if numpy cannot be imported, built when accessed; `raw` holds the record bytes, e.g. for `obspy.read(io.BytesIO(raw))`.
+#[pyclass(module = "seedlink_rs", frozen)]
+#[derive(Debug)]
+pub struct Record {
+    /// SeedLink sequence number.
+    #[pyo3(get)]
+    sequence: u64,
+    /// FDSN network code.
+    #[pyo3(get)]
+    network: String,
+    /// Station code.
+    #[pyo3(get)]
+    station: String,
+    /// Location code (may be empty).
+    #[pyo3(get)]
+    location: String,
+    /// Channel code.
+    #[pyo3(get)]
+    channel: String,
+    /// Sample rate in Hz.
+    #[pyo3(get)]
+    sample_rate: f64,
+    start_time: SystemTime,
+    samples: SampleData,
+    raw: Vec<u8>,
+}
+
+impl Record {
+    fn from_frame(frame: OwnedFrame) -> PyResult<Self> {
+        let SampleRecord {
+            sequence,
+            network,
+            station,
+            location,
+            channel,
+            start_time,
+            sample_rate,
+            samples,
+        } = frame.decode_samples().map_err(|e| to_py_err(e.into()))?;
+        Ok(Self {
+            sequence: sequence.value(),
+            network,
+            station,
+            location,
+            channel,
+            sample_rate,
+            start_time,
+            samples,
+            raw: frame.payload().to_vec(),
+        })
+    }
+}
+
+#[pymethods]
+impl Record {
+    /// Time of the first sample as POSIX seconds, e.g. for
+    /// `obspy.UTCDateTime(record.start_time)`.
+    #[getter]
+    fn start_time(&self) -> f64 {
+        match self.start_time.duration_since(UNIX_EPOCH) {
+            Ok(after) => after.as_secs_f64(),
+            Err(before) => -before.duration().as_secs_f64(),
+        }
+    }
+
+    /// Sample values as a numpy array, or as a list of numbers if numpy
+    /// cannot be imported.
+    #[getter]
+    fn samples<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        if py.import("numpy").is_ok() {
+            Ok(self.samples_array(py))
+        } else {
+            self.samples_list(py)
+        }
+    }
+
+    /// The miniSEED record bytes.
+    #[getter]
+    fn raw<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.raw)
+    }
+
+    fn __len__(&self) -> usize {
+        self.samples.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Record({}.{}.{}.{}, sequence={}, {} samples @ {} Hz)",
+            self.network,
+            self.station,
+            self.location,
+            self.channel,
+            self.sequence,
+            self.samples.len(),
+            self.sample_rate
+        )
+    }
+}
+
+impl Record {
+    fn samples_array<'py>(&self, py: Python<'py>) -> Bound<'py, PyAny> {
+        match &self.samples {
+            SampleData::Int(v) => v.clone().into_pyarray(py).into_any(),
+            SampleData::Float(v) => v.clone().into_pyarray(py).into_any(),
+            SampleData::Double(v) => v.clone().into_pyarray(py).into_any(),
+        }
+    }
+
+    fn samples_list<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        Ok(match &self.samples {
+            SampleData::Int(v) => PyList::new(py, v)?.into_any(),
+            SampleData::Float(v) => PyList::new(py, v)?.into_any(),
+            SampleData::Double(v) => PyList::new(py, v)?.into_any(),
+        })
+    }
+}
+
+#[pymodule]
+fn seedlink_rs(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Client>()?;
+    m.add_class::<Record>()?;
+    m.add("SeedLinkError", m.py().get_type::<SeedLinkError>())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pyo3::types::PyDict;
+    use seedlink_rs_client::mock::{MockConfig, MockServer};
+    use seedlink_rs_protocol::frame::v3;
+    use seedlink_rs_protocol::testing::TestRecordBuilder;
+
+    fn make_v3_frame(seq: u64) -> Vec<u8> {
+        let payload = TestRecordBuilder::new()
+            .channel("HHZ")
+            .sample_rate(100.0)
+            .samples(&[1, -2, 3])
+            .build();
+        v3::write(SequenceNumber::new(seq), &payload).unwrap()
+    }
+
+    /// A v3 mock server sending seq 1 and 2, then closing. It runs on its own
+    /// runtime: the blocking client must not run inside one.
+    fn start_server() -> (tokio::runtime::Runtime, MockServer) {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let config = MockConfig {
+            close_after_stream: true,
+            ..MockConfig::v3_default(vec![make_v3_frame(1), make_v3_frame(2)])
+        };
+        let server = runtime.block_on(MockServer::start(config));
+        (runtime, server)
+    }
+
+    #[test]
+    fn client_decodes_records() {
+        let (_runtime, server) = start_server();
+        Python::attach(|py| {
+            let client = Client::new(py, &server.addr().to_string(), false, 5.0).unwrap();
+            assert_eq!(client.version().unwrap(), 3);
+            client.station(py, "ANMO", "IU").unwrap();
+            client.data(py, None).unwrap();
+            client.end_stream(py).unwrap();
+
+            let record = client.next_record(py).unwrap().unwrap();
+            assert_eq!(record.sequence, 1);
+            assert_eq!(
+                (&*record.network, &*record.station, &*record.location),
+                ("IU", "ANMO", "00")
+            );
+            assert_eq!(record.channel, "HHZ");
+            assert_eq!(record.sample_rate, 100.0);
+            assert_eq!(record.start_time(), 1_704_067_200.0); // 2024-01-01
+            assert_eq!(record.__len__(), 3);
+            assert_eq!(record.raw(py).as_bytes().len(), 512);
+            assert_eq!(
+                record.__repr__(),
+                "Record(IU.ANMO.00.HHZ, sequence=1, 3 samples @ 100 Hz)"
+            );
+            assert_eq!(client.last_sequence("IU", "ANMO").unwrap(), Some(1));
+
+            client.close(py).unwrap();
+            client.close(py).unwrap();
+            let err = client.next_record(py).unwrap_err();
+            assert!(err.is_instance_of::<SeedLinkError>(py));
+        });
+    }
+
+    /// The first record from a fresh server, decoded.
+    fn first_record(py: Python<'_>, server: &MockServer) -> Record {
+        let client = Client::new(py, &server.addr().to_string(), false, 5.0).unwrap();
+        client.station(py, "ANMO", "IU").unwrap();
+        client.data(py, None).unwrap();
+        client.end_stream(py).unwrap();
+        client.next_record(py).unwrap().unwrap()
+    }
+
+    #[test]
+    fn samples_as_list() {
+        let (_runtime, server) = start_server();
+        Python::attach(|py| {
+            let record = first_record(py, &server);
+            let samples = record.samples_list(py).unwrap();
+            assert!(samples.is_instance_of::<PyList>());
+            assert_eq!(samples.extract::<Vec<i32>>().unwrap(), [1, -2, 3]);
+            if py.import("numpy").is_err() {
+                assert!(record.samples(py).unwrap().is_instance_of::<PyList>());
+            }
+        });
+    }
+
+    #[test]
+    fn samples_as_numpy_array() {
+        let (_runtime, server) = start_server();
+        Python::attach(|py| {
+            if py.import("numpy").is_err() {
+                // CI installs numpy; without it the getter takes the list path
+                eprintln!("numpy not installed, skipping");
+                return;
+            }
+            let record = first_record(py, &server);
+            let samples = record.samples(py).unwrap();
+            let dtype = samples.getattr("dtype").unwrap().str().unwrap();
+            assert_eq!(dtype.to_str().unwrap(), "int32");
+            let values = samples.call_method0("tolist").unwrap();
+            assert_eq!(values.extract::<Vec<i32>>().unwrap(), [1, -2, 3]);
+        });
+    }
+
+    #[test]
+    fn module_iterates_records_from_python() {
+        let (_runtime, server) = start_server();
+        Python::attach(|py| {
+            let module = PyModule::new(py, "seedlink_rs").unwrap();
+            seedlink_rs(&module).unwrap();
+            let globals = PyDict::new(py);
+            globals.set_item("seedlink_rs", module).unwrap();
+            globals
+                .set_item("address", server.addr().to_string())
+                .unwrap();
+            py.run(
+                c"
+with seedlink_rs.Client(address, prefer_v4=False, timeout=5) as client:
+    client.station('ANMO', 'IU')
+    client.select('HHZ')
+    client.data()
+    client.end_stream()
+    sequences = [record.sequence for record in client]
+assert sequences == [1, 2], sequences
+try:
+    client.station('ANMO', 'IU')
+except seedlink_rs.SeedLinkError as e:
+    assert 'closed' in str(e)
+else:
+    raise AssertionError('expected SeedLinkError')
+",
+                Some(&globals),
+                None,
+            )
+            .unwrap();
+        });
+        let cmds = server.captured().connection(0);
+        assert_eq!(cmds[1..], ["STATION ANMO IU", "SELECT HHZ", "DATA", "END"]);
+    }
+}
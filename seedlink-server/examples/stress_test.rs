@@ -1,6 +1,7 @@
 //! Stress test for seedlink-rs server.
 //!
-//! Measures push throughput, fan-out delivery, and concurrent client handling.
+//! Measures push throughput, fan-out delivery, concurrent client handling,
+//! and FETCH of the buffered backlog.
 //!
 //! ```bash
 //! # Debug mode (correctness check)
@@ -11,6 +12,9 @@
 //!
 //! # High load
 //! CLIENTS=200 RECORDS=50000 cargo run --example stress_test -p seedlink-rs-server --release
+//!
+//! # Compare write batching (0 = one frame per write)
+//! BATCH_BYTES=0 cargo run --example stress_test -p seedlink-rs-server --release
 //! ```
 
 use std::sync::Arc;
@@ -18,7 +22,7 @@ use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Instant;
 
 use seedlink_rs_client::{ClientConfig, SeedLinkClient};
-use seedlink_rs_protocol::frame::v3;
+use seedlink_rs_protocol::testing::TestRecordBuilder;
 use seedlink_rs_server::{SeedLinkServer, ServerConfig};
 use tokio::sync::Barrier;
 
@@ -29,24 +33,12 @@ fn env_or(name: &str, default: u64) -> u64 {
         .unwrap_or(default)
 }
 
-/// Build a 512-byte miniSEED-like payload with station/network in header.
+/// Build a 512-byte miniSEED record for `network.station`.
 fn make_payload(station: &str, network: &str) -> Vec<u8> {
-    let mut payload = vec![0u8; v3::PAYLOAD_LEN];
-    let sta_bytes = station.as_bytes();
-    for (i, &b) in sta_bytes.iter().enumerate().take(5) {
-        payload[8 + i] = b;
-    }
-    for i in sta_bytes.len()..5 {
-        payload[8 + i] = b' ';
-    }
-    let net_bytes = network.as_bytes();
-    for (i, &b) in net_bytes.iter().enumerate().take(2) {
-        payload[18 + i] = b;
-    }
-    for i in net_bytes.len()..2 {
-        payload[18 + i] = b' ';
-    }
-    payload
+    TestRecordBuilder::new()
+        .station(station)
+        .network(network)
+        .build()
 }
 
 #[tokio::main]
@@ -54,6 +46,11 @@ async fn main() {
     let num_clients = env_or("CLIENTS", 50) as usize;
     let num_records = env_or("RECORDS", 10_000) as usize;
     let ring_cap = env_or("RING_CAP", 20_000) as usize;
+    let fetch_clients = env_or("FETCH_CLIENTS", 10) as usize;
+    let batch_bytes = env_or(
+        "BATCH_BYTES",
+        ServerConfig::default().write_batch_bytes as u64,
+    ) as usize;
 
     println!("seedlink-rs stress test");
     println!("========================");
@@ -61,6 +58,7 @@ async fn main() {
     // Phase 1: Start server
     let config = ServerConfig {
         ring_capacity: ring_cap,
+        write_batch_bytes: batch_bytes,
         ..ServerConfig::default()
     };
     let server = match SeedLinkServer::bind_with_config("127.0.0.1:0", config).await {
@@ -76,7 +74,7 @@ async fn main() {
     tokio::spawn(server.run());
     tokio::task::yield_now().await;
 
-    println!("Server:  {addr} (ring_capacity={ring_cap})");
+    println!("Server:  {addr} (ring_capacity={ring_cap}, write_batch_bytes={batch_bytes})");
     println!("Clients: {num_clients}");
     println!("Records: {num_records}");
     println!();
@@ -190,7 +188,40 @@ async fn main() {
         println!("Waiting for delivery... done ({:.0?})", wait_elapsed);
     }
 
-    // Phase 5: Shutdown and print results
+    // Phase 5: FETCH the buffered backlog
+    let backlog = store.len() as u64;
+    let fetch_start = Instant::now();
+    let fetches: Vec<_> = (0..fetch_clients)
+        .map(|_| {
+            let addr = addr.clone();
+            tokio::spawn(async move {
+                let config = ClientConfig {
+                    prefer_v4: false,
+                    read_timeout: std::time::Duration::from_secs(60),
+                    ..ClientConfig::default()
+                };
+                let Ok(mut client) = SeedLinkClient::connect_with_config(&addr, config).await
+                else {
+                    return 0u64;
+                };
+                if client.station("ANMO", "IU").await.is_err() || client.fetch().await.is_err() {
+                    return 0;
+                }
+                let mut count = 0u64;
+                while let Ok(Some(_)) = client.next_frame().await {
+                    count += 1;
+                }
+                count
+            })
+        })
+        .collect();
+    let mut fetched = 0u64;
+    for f in fetches {
+        fetched += f.await.unwrap_or(0);
+    }
+    let fetch_elapsed = fetch_start.elapsed();
+
+    // Phase 6: Shutdown and print results
     shutdown.shutdown();
 
     let actual_total = total_received.load(Ordering::Relaxed);
@@ -230,6 +261,12 @@ async fn main() {
     println!("Wall clock:             {:.2?}", wall_clock);
     println!();
     println!("Per-client: min={min}  max={max}  avg={avg}");
+    println!();
+    println!(
+        "FETCH backlog:          {fetched} frames ({fetch_clients} clients x {backlog} records) in {:.2?} = {:.0} frames/sec",
+        fetch_elapsed,
+        fetched as f64 / fetch_elapsed.as_secs_f64()
+    );
 
     if actual_total == expected_total && !timed_out {
         println!("All clients received all records: OK");
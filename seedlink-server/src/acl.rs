@@ -0,0 +1,322 @@
+//! Access control for stations.
+//!
+//! An [`Acl`] decides, per client address or authenticated identity, which
+//! network/station pairs a client may subscribe to and see in INFO responses.
+//! Rules are checked in order and the first match wins; if none matches, the
+//! ACL's default action applies.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+/// Outcome of a matching rule.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AclAction {
+    /// The client may access the station.
+    #[default]
+    Allow,
+    /// The client may not access the station.
+    Deny,
+}
+
+/// Which clients a rule applies to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AclSubject {
+    /// Every client.
+    Any,
+    /// Clients whose address lies in `addr/prefix` (e.g. `10.0.0.0/8`).
+    Ip {
+        /// Network address.
+        addr: IpAddr,
+        /// Prefix length in bits; 32 (IPv4) or 128 (IPv6) for a single host.
+        prefix: u8,
+    },
+    /// Clients that authenticated as this user with `AUTH USERPASS`.
+    Identity(String),
+}
+
+impl AclSubject {
+    /// A single host.
+    pub fn ip(addr: IpAddr) -> Self {
+        let prefix = if addr.is_ipv4() { 32 } else { 128 };
+        Self::Ip { addr, prefix }
+    }
+
+    fn matches(&self, peer: IpAddr, identity: Option<&str>) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Ip { addr, prefix } => in_network(peer, *addr, *prefix),
+            Self::Identity(user) => identity == Some(user.as_str()),
+        }
+    }
+}
+
+/// Parses `*` (any client), `user:NAME` (identity), `ADDR` or `ADDR/PREFIX`.
+impl FromStr for AclSubject {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "*" {
+            return Ok(Self::Any);
+        }
+        if let Some(user) = s.strip_prefix("user:") {
+            return Ok(Self::Identity(user.to_owned()));
+        }
+        let invalid = || format!("invalid ACL subject: {s}");
+        let Some((addr, prefix)) = s.split_once('/') else {
+            return s.parse().map(Self::ip).map_err(|_| invalid());
+        };
+        let addr: IpAddr = addr.parse().map_err(|_| invalid())?;
+        let prefix: u8 = prefix.parse().map_err(|_| invalid())?;
+        if prefix > if addr.is_ipv4() { 32 } else { 128 } {
+            return Err(invalid());
+        }
+        Ok(Self::Ip { addr, prefix })
+    }
+}
+
+impl fmt::Display for AclSubject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Any => f.write_str("*"),
+            Self::Ip { addr, prefix } => write!(f, "{addr}/{prefix}"),
+            Self::Identity(user) => write!(f, "user:{user}"),
+        }
+    }
+}
+
+/// One allow/deny rule for a network/station pattern.
+///
+/// Patterns are case-insensitive and may use `*` (any run of characters)
+/// and `?` (one character).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AclRule {
+    /// Allow or deny.
+    pub action: AclAction,
+    /// Clients the rule applies to.
+    pub subject: AclSubject,
+    /// Network code pattern (e.g. `"IU"`, `"*"`).
+    pub network: String,
+    /// Station code pattern (e.g. `"ANMO"`, `"A*"`).
+    pub station: String,
+}
+
+impl AclRule {
+    /// A rule allowing `subject` to access `network`/`station`.
+    pub fn allow(subject: AclSubject, network: &str, station: &str) -> Self {
+        Self {
+            action: AclAction::Allow,
+            subject,
+            network: network.to_owned(),
+            station: station.to_owned(),
+        }
+    }
+
+    /// A rule denying `subject` access to `network`/`station`.
+    pub fn deny(subject: AclSubject, network: &str, station: &str) -> Self {
+        Self {
+            action: AclAction::Deny,
+            ..Self::allow(subject, network, station)
+        }
+    }
+
+    fn matches(&self, peer: IpAddr, identity: Option<&str>, network: &str, station: &str) -> bool {
+        self.subject.matches(peer, identity)
+            && glob_match(&self.network, network)
+            && glob_match(&self.station, station)
+    }
+}
+
+/// Station access rules and `AUTH USERPASS` credentials.
+///
+/// The default ACL has no rules and allows everything.
+///
+/// ```
+/// use seedlink_rs_server::{Acl, AclAction, AclRule, AclSubject};
+///
+/// // Only the local network and the user "alice" may see network XX
+/// let acl = Acl::new()
+///     .rule(AclRule::allow("10.0.0.0/8".parse().unwrap(), "XX", "*"))
+///     .rule(AclRule::allow(AclSubject::Identity("alice".into()), "XX", "*"))
+///     .rule(AclRule::deny(AclSubject::Any, "XX", "*"))
+///     .user("alice", "secret");
+/// assert!(!acl.is_allowed("192.0.2.1".parse().unwrap(), None, "XX", "STA1"));
+/// assert!(acl.is_allowed("192.0.2.1".parse().unwrap(), Some("alice"), "XX", "STA1"));
+/// assert!(acl.is_allowed("192.0.2.1".parse().unwrap(), None, "IU", "ANMO"));
+/// ```
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Acl {
+    rules: Vec<AclRule>,
+    default: AclAction,
+    users: HashMap<String, String>,
+}
+
+/// Lists user names but not passwords.
+impl fmt::Debug for Acl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Acl")
+            .field("rules", &self.rules)
+            .field("default", &self.default)
+            .field("users", &self.users.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl Acl {
+    /// An ACL with no rules that allows everything.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Action when no rule matches. Default: [`AclAction::Allow`].
+    pub fn default_action(mut self, action: AclAction) -> Self {
+        self.default = action;
+        self
+    }
+
+    /// Append a rule. Rules are checked in the order they were added.
+    pub fn rule(mut self, rule: AclRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Add a user that can authenticate with `AUTH USERPASS user password`.
+    pub fn user(mut self, name: &str, password: &str) -> Self {
+        self.users.insert(name.to_owned(), password.to_owned());
+        self
+    }
+
+    /// The rules, in evaluation order.
+    pub fn rules(&self) -> &[AclRule] {
+        &self.rules
+    }
+
+    /// Whether a client at `peer`, authenticated as `identity`, may access
+    /// `network`/`station`.
+    pub fn is_allowed(
+        &self,
+        peer: IpAddr,
+        identity: Option<&str>,
+        network: &str,
+        station: &str,
+    ) -> bool {
+        let action = self
+            .rules
+            .iter()
+            .find(|r| r.matches(peer, identity, network, station))
+            .map_or(self.default, |r| r.action);
+        action == AclAction::Allow
+    }
+
+    /// Check `AUTH USERPASS` credentials.
+    pub(crate) fn authenticate(&self, user: &str, password: &str) -> bool {
+        self.users.get(user).is_some_and(|p| p == password)
+    }
+}
+
+fn in_network(peer: IpAddr, network: IpAddr, prefix: u8) -> bool {
+    let (peer, network, bits) = match (peer, network) {
+        (IpAddr::V4(p), IpAddr::V4(n)) => (u128::from(p.to_bits()), u128::from(n.to_bits()), 32),
+        (IpAddr::V6(p), IpAddr::V6(n)) => (p.to_bits(), n.to_bits(), 128),
+        // IPv4 clients seen on a dual-stack listener as ::ffff:a.b.c.d
+        (IpAddr::V6(p), IpAddr::V4(_)) => match p.to_ipv4_mapped() {
+            Some(p) => return in_network(IpAddr::V4(p), network, prefix),
+            None => return false,
+        },
+        (IpAddr::V4(_), IpAddr::V6(_)) => return false,
+    };
+    let prefix = u32::from(prefix.min(bits));
+    if prefix == 0 {
+        return true;
+    }
+    let shift = u32::from(bits) - prefix;
+    peer >> shift == network >> shift
+}
+
+/// Case-insensitive glob match with `*` and `?`.
+pub(crate) fn glob_match(pattern: &str, text: &str) -> bool {
+    let p = pattern.as_bytes();
+    let t = text.as_bytes();
+    let (mut pi, mut ti) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == b'?' || p[pi].eq_ignore_ascii_case(&t[ti])) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == b'*' {
+            star = Some((pi, ti));
+            pi += 1;
+        } else if let Some((sp, st)) = star {
+            pi = sp + 1;
+            ti = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&b| b == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn glob_patterns() {
+        assert!(glob_match("*", "ANMO"));
+        assert!(glob_match("A*", "anmo"));
+        assert!(glob_match("AN?O", "ANMO"));
+        assert!(glob_match("*MO", "ANMO"));
+        assert!(!glob_match("A?", "ANMO"));
+        assert!(!glob_match("B*", "ANMO"));
+        assert!(glob_match("", ""));
+    }
+
+    #[test]
+    fn subject_parse_and_match() {
+        let net: AclSubject = "10.1.0.0/16".parse().unwrap();
+        assert!(net.matches(ip("10.1.2.3"), None));
+        assert!(!net.matches(ip("10.2.0.1"), None));
+        assert!(net.matches(ip("::ffff:10.1.9.9"), None));
+        assert_eq!(net.to_string(), "10.1.0.0/16");
+
+        let host: AclSubject = "2001:db8::1".parse().unwrap();
+        assert!(host.matches(ip("2001:db8::1"), None));
+        assert!(!host.matches(ip("2001:db8::2"), None));
+
+        let user: AclSubject = "user:alice".parse().unwrap();
+        assert!(user.matches(ip("10.0.0.1"), Some("alice")));
+        assert!(!user.matches(ip("10.0.0.1"), None));
+
+        assert_eq!("*".parse::<AclSubject>().unwrap(), AclSubject::Any);
+        assert!("10.0.0.0/33".parse::<AclSubject>().is_err());
+        assert!("nonsense".parse::<AclSubject>().is_err());
+    }
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let acl = Acl::new()
+            .default_action(AclAction::Deny)
+            .rule(AclRule::deny(AclSubject::ip(ip("10.0.0.5")), "IU", "*"))
+            .rule(AclRule::allow("10.0.0.0/8".parse().unwrap(), "IU", "*"))
+            .rule(AclRule::allow(AclSubject::Any, "GE", "W*"));
+        assert!(acl.is_allowed(ip("10.0.0.1"), None, "IU", "ANMO"));
+        assert!(!acl.is_allowed(ip("10.0.0.5"), None, "IU", "ANMO"));
+        assert!(acl.is_allowed(ip("192.0.2.1"), None, "GE", "WLF"));
+        assert!(!acl.is_allowed(ip("192.0.2.1"), None, "GE", "APE"));
+        assert!(Acl::new().is_allowed(ip("192.0.2.1"), None, "XX", "ANY"));
+    }
+
+    #[test]
+    fn authenticate_checks_credentials() {
+        let acl = Acl::new().user("alice", "secret");
+        assert!(acl.authenticate("alice", "secret"));
+        assert!(!acl.authenticate("alice", "wrong"));
+        assert!(!acl.authenticate("bob", "secret"));
+    }
+}
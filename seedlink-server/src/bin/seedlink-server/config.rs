@@ -0,0 +1,710 @@
+//! TOML configuration file for the `seedlink-server` binary.
+
+use std::collections::BTreeMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use seedlink_rs_server::{
+    Acl, AclAction, AclRule, ConsumedAction, DataLinkConfig, DataLinkSource, DataSource,
+    DirectoryConfig, DirectorySource, OverflowPolicy, ProtocolVersion, ResumeMissPolicy,
+    SequencePolicy, ServerConfig, UdpConfig, UdpSource, ValidationMode,
+};
+
+/// Errors loading or applying a configuration file.
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum ConfigError {
+    #[error("cannot read {path}: {source}")]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("invalid config: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("invalid config: {0}")]
+    Invalid(String),
+    #[error(transparent)]
+    Server(#[from] seedlink_rs_server::ServerError),
+}
+
+pub(crate) type Result<T> = std::result::Result<T, ConfigError>;
+
+/// Top-level configuration file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ConfigFile {
+    /// Address to listen on. Default: `0.0.0.0:18000`.
+    #[serde(default = "default_listen")]
+    pub listen: String,
+    #[serde(default)]
+    pub server: ServerSection,
+    #[serde(default)]
+    pub acl: AclSection,
+    /// Serve clients over TLS instead of plain TCP (`tls` feature).
+    pub tls: Option<TlsSection>,
+    #[serde(default, rename = "source")]
+    pub sources: Vec<SourceSection>,
+}
+
+/// `[server]` — maps onto [`ServerConfig`].
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ServerSection {
+    pub software: Option<String>,
+    pub version: Option<String>,
+    pub organization: Option<String>,
+    pub ring_capacity: Option<usize>,
+    pub ring_capacity_bytes: Option<usize>,
+    /// Records kept per station, within `ring_capacity`.
+    pub station_ring_capacity: Option<usize>,
+    /// Per-station overrides of `station_ring_capacity`: `NET_STA` → records.
+    #[serde(default)]
+    pub station_rings: BTreeMap<String, usize>,
+    pub send_queue_capacity: Option<usize>,
+    pub overflow_policy: Option<Overflow>,
+    pub write_batch_bytes: Option<usize>,
+    pub source_restart_delay_secs: Option<f64>,
+    pub keepalive_secs: Option<f64>,
+    pub command_timeout_secs: Option<f64>,
+    pub max_command_len: Option<usize>,
+    pub max_commands_per_second: Option<u32>,
+    pub stream_idle_timeout_secs: Option<f64>,
+    pub max_bytes_per_second: Option<u64>,
+    pub default_station: Option<String>,
+    pub resume_miss: Option<ResumeMiss>,
+    /// `SLPROTO` versions offered, e.g. `["4.0", "3.1"]`.
+    pub protocols: Option<Vec<String>>,
+    pub sequence_policy: Option<Sequences>,
+    pub dedup_window: Option<usize>,
+    pub reorder_depth: Option<usize>,
+    pub validation: Option<Validation>,
+    pub retention_secs: Option<f64>,
+    pub listen_backlog: Option<u32>,
+    pub reuse_address: Option<bool>,
+    pub reuse_port: Option<bool>,
+    pub send_buffer_size: Option<u32>,
+    pub tcp_keepalive_secs: Option<f64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Overflow {
+    Block,
+    DropOldest,
+    Disconnect,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum ResumeMiss {
+    Oldest,
+    Reject,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Sequences {
+    Reject,
+    Reassign,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Validation {
+    Off,
+    Drop,
+    Flag,
+    Error,
+}
+
+/// `[tls]` — certificate and key, in PEM files.
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct TlsSection {
+    /// Certificate chain, leaf first.
+    pub cert: PathBuf,
+    /// PKCS#8, PKCS#1 or SEC1 private key.
+    pub key: PathBuf,
+}
+
+/// `[acl]` — maps onto [`Acl`].
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct AclSection {
+    pub default: Option<Action>,
+    /// `AUTH USERPASS` credentials: user name → password.
+    #[serde(default)]
+    pub users: BTreeMap<String, String>,
+    #[serde(default, rename = "rule")]
+    pub rules: Vec<AclRuleSection>,
+}
+
+/// One `[[acl.rule]]` table.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct AclRuleSection {
+    pub action: Action,
+    /// `*`, `user:NAME`, `ADDR` or `ADDR/PREFIX`.
+    #[serde(default = "any_pattern")]
+    pub subject: String,
+    #[serde(default = "any_pattern")]
+    pub network: String,
+    #[serde(default = "any_pattern")]
+    pub station: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Action {
+    Allow,
+    Deny,
+}
+
+impl From<Action> for AclAction {
+    fn from(action: Action) -> Self {
+        match action {
+            Action::Allow => AclAction::Allow,
+            Action::Deny => AclAction::Deny,
+        }
+    }
+}
+
+fn any_pattern() -> String {
+    "*".to_owned()
+}
+
+/// One `[[source]]` table, selected by its `type` key.
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+pub(crate) enum SourceSection {
+    Directory {
+        path: PathBuf,
+        poll_interval_secs: Option<f64>,
+        min_age_secs: Option<f64>,
+        /// Move consumed files here instead of deleting them.
+        archive: Option<PathBuf>,
+    },
+    Udp {
+        bind: SocketAddr,
+        multicast_group: Option<IpAddr>,
+        multicast_interface: Option<Ipv4Addr>,
+    },
+    /// DataLink `WRITE` ingest; `AUTH` users and station rules come from `[acl]`.
+    Datalink {
+        bind: SocketAddr,
+        /// Accept writes without `AUTH USERPASS`.
+        #[serde(default)]
+        anonymous: bool,
+        idle_timeout_secs: Option<f64>,
+        max_connections: Option<usize>,
+    },
+    Relay {
+        address: String,
+        /// `NET_STA[:SEL,...]` entries, e.g. `"IU_ANMO:BH?"`.
+        stations: Vec<String>,
+        statefile: Option<PathBuf>,
+        /// Keep upstream sequence numbers.
+        #[serde(default)]
+        preserve_sequences: bool,
+    },
+}
+
+fn default_listen() -> String {
+    "0.0.0.0:18000".to_owned()
+}
+
+fn seconds(name: &str, secs: f64) -> Result<Duration> {
+    Duration::try_from_secs_f64(secs)
+        .map_err(|_| ConfigError::Invalid(format!("{name}: invalid duration {secs}")))
+}
+
+impl ConfigFile {
+    /// Read and parse the file at `path`.
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path).map_err(|source| ConfigError::Read {
+            path: path.to_owned(),
+            source,
+        })?;
+        Self::parse(&text)
+    }
+
+    pub(crate) fn parse(text: &str) -> Result<Self> {
+        Ok(toml::from_str(text)?)
+    }
+
+    /// Build the [`ServerConfig`], starting from its defaults.
+    pub(crate) fn server_config(&self) -> Result<ServerConfig> {
+        let s = &self.server;
+        let mut config = ServerConfig::default();
+        if let Some(v) = &s.software {
+            config.software = v.clone();
+        }
+        if let Some(v) = &s.version {
+            config.version = v.clone();
+        }
+        if let Some(v) = &s.organization {
+            config.organization = v.clone();
+        }
+        if let Some(v) = s.ring_capacity {
+            config.ring_capacity = v;
+        }
+        if let Some(v) = s.ring_capacity_bytes {
+            config.ring_capacity_bytes = v;
+        }
+        config.store.station_capacity = s.station_ring_capacity;
+        for (spec, &capacity) in &s.station_rings {
+            let (net, sta) = spec.split_once('_').ok_or_else(|| {
+                ConfigError::Invalid(format!("station_rings {spec:?}: expected NET_STA"))
+            })?;
+            config
+                .store
+                .station_capacities
+                .insert((net.to_owned(), sta.to_owned()), capacity);
+        }
+        if let Some(v) = s.send_queue_capacity {
+            config.send_queue_capacity = v;
+        }
+        if let Some(v) = s.overflow_policy {
+            config.overflow_policy = match v {
+                Overflow::Block => OverflowPolicy::Block,
+                Overflow::DropOldest => OverflowPolicy::DropOldest,
+                Overflow::Disconnect => OverflowPolicy::Disconnect,
+            };
+        }
+        if let Some(v) = s.write_batch_bytes {
+            config.write_batch_bytes = v;
+        }
+        if let Some(v) = s.resume_miss {
+            config.resume_miss = match v {
+                ResumeMiss::Oldest => ResumeMissPolicy::Oldest,
+                ResumeMiss::Reject => ResumeMissPolicy::Reject,
+            };
+        }
+        if let Some(v) = s.sequence_policy {
+            config.store.sequence_policy = match v {
+                Sequences::Reject => SequencePolicy::Reject,
+                Sequences::Reassign => SequencePolicy::Reassign,
+            };
+        }
+        if let Some(v) = s.dedup_window {
+            config.store.dedup_window = v;
+        }
+        if let Some(v) = s.reorder_depth {
+            config.store.reorder_depth = v;
+        }
+        if let Some(v) = s.validation {
+            config.store.validation = match v {
+                Validation::Off => ValidationMode::Off,
+                Validation::Drop => ValidationMode::Drop,
+                Validation::Flag => ValidationMode::Flag,
+                Validation::Error => ValidationMode::Error,
+            };
+        }
+        if let Some(v) = s.retention_secs {
+            config.store.retention = Some(seconds("retention_secs", v)?);
+        }
+        if let Some(v) = s.source_restart_delay_secs {
+            config.source_restart_delay = seconds("source_restart_delay_secs", v)?;
+        }
+        if let Some(v) = s.keepalive_secs {
+            config.keepalive_interval = Some(seconds("keepalive_secs", v)?);
+        }
+        if let Some(v) = s.command_timeout_secs {
+            config.command_timeout = Some(seconds("command_timeout_secs", v)?);
+        }
+        if let Some(v) = s.max_command_len {
+            config.max_command_len = v;
+        }
+        config.max_commands_per_second = s.max_commands_per_second;
+        if let Some(v) = s.stream_idle_timeout_secs {
+            config.stream_idle_timeout = Some(seconds("stream_idle_timeout_secs", v)?);
+        }
+        if s.max_bytes_per_second == Some(0) {
+            return Err(ConfigError::Invalid(
+                "max_bytes_per_second must be at least 1; omit it for no limit".into(),
+            ));
+        }
+        config.max_bytes_per_second = s.max_bytes_per_second;
+        if let Some(spec) = &s.default_station {
+            let (net, sta) = spec.split_once('_').ok_or_else(|| {
+                ConfigError::Invalid(format!("default_station {spec:?}: expected NET_STA"))
+            })?;
+            config.default_station = Some((net.to_owned(), sta.to_owned()));
+        }
+        if let Some(list) = &s.protocols {
+            config.supported_protocols = list
+                .iter()
+                .map(|v| match v.split('.').next() {
+                    Some("3") => Ok(ProtocolVersion::V3),
+                    Some("4") => Ok(ProtocolVersion::V4),
+                    _ => Err(ConfigError::Invalid(format!(
+                        "protocols: unknown version {v:?}"
+                    ))),
+                })
+                .collect::<Result<_>>()?;
+            if config.supported_protocols.is_empty() {
+                return Err(ConfigError::Invalid("protocols must not be empty".into()));
+            }
+        }
+        if let Some(v) = s.listen_backlog {
+            config.socket.backlog = v;
+        }
+        if let Some(v) = s.reuse_address {
+            config.socket.reuse_address = v;
+        }
+        if let Some(v) = s.reuse_port {
+            config.socket.reuse_port = v;
+        }
+        config.socket.send_buffer_size = s.send_buffer_size;
+        if let Some(v) = s.tcp_keepalive_secs {
+            config.socket.tcp_keepalive = Some(seconds("tcp_keepalive_secs", v)?);
+        }
+        config.acl = self.acl.build()?;
+        if self.tls.is_some() && !cfg!(feature = "tls") {
+            return Err(ConfigError::Invalid(
+                "[tls] requires building with the `tls` feature".into(),
+            ));
+        }
+        if config.ring_capacity == 0 || config.send_queue_capacity == 0 {
+            return Err(ConfigError::Invalid(
+                "ring_capacity and send_queue_capacity must be at least 1".into(),
+            ));
+        }
+        Ok(config)
+    }
+
+    /// Create every configured source. UDP and DataLink sockets are bound here.
+    pub(crate) async fn build_sources(&self) -> Result<Vec<Box<dyn DataSource>>> {
+        let acl = self.acl.build()?;
+        let mut sources: Vec<Box<dyn DataSource>> = Vec::new();
+        for section in &self.sources {
+            sources.push(section.build(&acl).await?);
+        }
+        Ok(sources)
+    }
+}
+
+impl AclSection {
+    fn build(&self) -> Result<Acl> {
+        let mut acl = Acl::new();
+        if let Some(default) = self.default {
+            acl = acl.default_action(default.into());
+        }
+        for rule in &self.rules {
+            let subject = rule.subject.parse().map_err(ConfigError::Invalid)?;
+            acl = acl.rule(AclRule {
+                action: rule.action.into(),
+                subject,
+                network: rule.network.clone(),
+                station: rule.station.clone(),
+            });
+        }
+        for (user, password) in &self.users {
+            acl = acl.user(user, password);
+        }
+        Ok(acl)
+    }
+}
+
+impl SourceSection {
+    async fn build(&self, acl: &Acl) -> Result<Box<dyn DataSource>> {
+        match self {
+            Self::Directory {
+                path,
+                poll_interval_secs,
+                min_age_secs,
+                archive,
+            } => {
+                let mut config = DirectoryConfig::default();
+                if let Some(v) = poll_interval_secs {
+                    config.poll_interval = seconds("poll_interval_secs", *v)?;
+                }
+                if let Some(v) = min_age_secs {
+                    config.min_age = seconds("min_age_secs", *v)?;
+                }
+                if let Some(archive) = archive {
+                    config.consumed = ConsumedAction::Archive(archive.clone());
+                }
+                Ok(Box::new(DirectorySource::with_config(path, config)))
+            }
+            Self::Udp {
+                bind,
+                multicast_group,
+                multicast_interface,
+            } => {
+                let mut config = UdpConfig::new(*bind);
+                config.multicast_group = *multicast_group;
+                if let Some(iface) = multicast_interface {
+                    config.multicast_interface = *iface;
+                }
+                Ok(Box::new(UdpSource::bind(config).await?))
+            }
+            Self::Datalink {
+                bind,
+                anonymous,
+                idle_timeout_secs,
+                max_connections,
+            } => {
+                let mut config = DataLinkConfig::new(*bind).acl(acl.clone());
+                config.require_auth = !anonymous;
+                if let Some(secs) = idle_timeout_secs {
+                    config.idle_timeout = Some(seconds("idle_timeout_secs", *secs)?);
+                }
+                if let Some(max) = max_connections {
+                    config.max_connections = *max;
+                }
+                Ok(Box::new(DataLinkSource::bind(config).await?))
+            }
+            Self::Relay {
+                address,
+                stations,
+                statefile,
+                preserve_sequences,
+            } => relay_source(address, stations, statefile.as_deref(), *preserve_sequences),
+        }
+    }
+}
+
+#[cfg(feature = "relay")]
+fn relay_source(
+    address: &str,
+    stations: &[String],
+    statefile: Option<&Path>,
+    preserve_sequences: bool,
+) -> Result<Box<dyn DataSource>> {
+    use seedlink_rs_client::{ClientConfig, ReconnectConfig};
+    use seedlink_rs_server::{RelaySource, RelayStation};
+
+    let stations = stations
+        .iter()
+        .map(|spec| {
+            let (id, selectors) = spec.split_once(':').unwrap_or((spec, ""));
+            let (net, sta) = id.split_once('_').ok_or_else(|| {
+                ConfigError::Invalid(format!("relay station {spec:?}: expected NET_STA"))
+            })?;
+            Ok(selectors
+                .split(',')
+                .filter(|p| !p.is_empty())
+                .fold(RelayStation::new(net, sta), RelayStation::select))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let reconnect = ReconnectConfig {
+        statefile: statefile.map(Path::to_owned),
+        ..ReconnectConfig::default()
+    };
+    Ok(Box::new(
+        RelaySource::with_config(address, stations, ClientConfig::default(), reconnect)
+            .preserve_sequences(preserve_sequences),
+    ))
+}
+
+#[cfg(not(feature = "relay"))]
+fn relay_source(
+    _address: &str,
+    _stations: &[String],
+    _statefile: Option<&Path>,
+    _preserve_sequences: bool,
+) -> Result<Box<dyn DataSource>> {
+    Err(ConfigError::Invalid(
+        "relay sources require building with the `relay` feature".into(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = r#"
+        listen = "127.0.0.1:18000"
+
+        [server]
+        organization = "Test Network"
+        ring_capacity = 500
+        ring_capacity_bytes = 1048576
+        overflow_policy = "drop-oldest"
+        source_restart_delay_secs = 0.5
+        keepalive_secs = 30
+        command_timeout_secs = 60
+        max_commands_per_second = 50
+        max_bytes_per_second = 250000
+        default_station = "IU_ANMO"
+        resume_miss = "oldest"
+        protocols = ["4.0"]
+        sequence_policy = "reassign"
+        dedup_window = 1000
+        validation = "drop"
+        retention_secs = 7200
+        listen_backlog = 4096
+        reuse_port = true
+        tcp_keepalive_secs = 120
+        station_ring_capacity = 100
+
+        [server.station_rings]
+        IU_ANMO = 300
+
+        [[source]]
+        type = "directory"
+        path = "/var/spool/digitizer"
+        min_age_secs = 5
+        archive = "/var/spool/done"
+
+        [[source]]
+        type = "udp"
+        bind = "0.0.0.0:5000"
+        multicast_group = "239.1.2.3"
+    "#;
+
+    #[test]
+    fn parses_server_and_sources() {
+        let file = ConfigFile::parse(EXAMPLE).unwrap();
+        assert_eq!(file.listen, "127.0.0.1:18000");
+
+        let config = file.server_config().unwrap();
+        assert_eq!(config.organization, "Test Network");
+        assert_eq!(config.ring_capacity, 500);
+        assert_eq!(config.ring_capacity_bytes, 1 << 20);
+        assert_eq!(config.overflow_policy, OverflowPolicy::DropOldest);
+        assert_eq!(config.source_restart_delay, Duration::from_millis(500));
+        assert_eq!(config.keepalive_interval, Some(Duration::from_secs(30)));
+        assert_eq!(config.command_timeout, Some(Duration::from_secs(60)));
+        assert_eq!(config.stream_idle_timeout, None);
+        assert_eq!(config.max_command_len, 1024);
+        assert_eq!(config.max_commands_per_second, Some(50));
+        assert_eq!(config.max_bytes_per_second, Some(250_000));
+        assert_eq!(
+            config.default_station,
+            Some(("IU".to_owned(), "ANMO".to_owned()))
+        );
+        assert_eq!(config.resume_miss, ResumeMissPolicy::Oldest);
+        assert_eq!(config.supported_protocols, [ProtocolVersion::V4]);
+        assert_eq!(config.store.sequence_policy, SequencePolicy::Reassign);
+        assert_eq!(config.store.dedup_window, 1000);
+        assert_eq!(config.store.reorder_depth, 0);
+        assert_eq!(config.store.validation, ValidationMode::Drop);
+        assert_eq!(config.store.retention, Some(Duration::from_secs(7200)));
+        assert_eq!(config.socket.backlog, 4096);
+        assert!(config.socket.reuse_address && config.socket.reuse_port);
+        assert_eq!(config.socket.send_buffer_size, None);
+        assert_eq!(config.socket.tcp_keepalive, Some(Duration::from_secs(120)));
+        assert_eq!(config.store.station_capacity, Some(100));
+        assert_eq!(
+            config.store.station_capacities,
+            [(("IU".to_owned(), "ANMO".to_owned()), 300)].into()
+        );
+        assert_eq!(config.software, ServerConfig::default().software);
+        assert_eq!(config.acl, Acl::new());
+
+        assert_eq!(file.sources.len(), 2);
+        assert_eq!(
+            file.sources[1],
+            SourceSection::Udp {
+                bind: "0.0.0.0:5000".parse().unwrap(),
+                multicast_group: Some("239.1.2.3".parse().unwrap()),
+                multicast_interface: None,
+            }
+        );
+    }
+
+    #[test]
+    fn empty_file_uses_defaults() {
+        let file = ConfigFile::parse("").unwrap();
+        assert_eq!(file.listen, "0.0.0.0:18000");
+        assert!(file.sources.is_empty());
+        let config = file.server_config().unwrap();
+        assert_eq!(config.ring_capacity, ServerConfig::default().ring_capacity);
+    }
+
+    #[test]
+    fn parses_acl_rules_and_users() {
+        let file = ConfigFile::parse(
+            r#"
+            [acl]
+            default = "deny"
+            users = { alice = "secret" }
+
+            [[acl.rule]]
+            action = "allow"
+            subject = "10.0.0.0/8"
+            network = "IU"
+
+            [[acl.rule]]
+            action = "allow"
+            subject = "user:alice"
+            "#,
+        )
+        .unwrap();
+        let acl = file.server_config().unwrap().acl;
+        let outside: IpAddr = "192.0.2.1".parse().unwrap();
+        assert_eq!(acl.rules().len(), 2);
+        assert!(acl.is_allowed("10.1.2.3".parse().unwrap(), None, "IU", "ANMO"));
+        assert!(!acl.is_allowed(outside, None, "IU", "ANMO"));
+        assert!(acl.is_allowed(outside, Some("alice"), "GE", "WLF"));
+
+        let bad = ConfigFile::parse("[[acl.rule]]\naction = \"deny\"\nsubject = \"x/y\"").unwrap();
+        assert!(bad.server_config().is_err());
+        assert!(ConfigFile::parse("[[acl.rule]]\naction = \"block\"").is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_keys_and_bad_values() {
+        assert!(ConfigFile::parse("[tls]\ncert = \"x\"").is_err());
+        assert!(ConfigFile::parse("[tls]\ncert = \"x\"\nkey = \"y\"\nca = \"z\"").is_err());
+        let station = ConfigFile::parse("[server.station_rings]\nANMO = 5").unwrap();
+        assert!(station.server_config().is_err());
+        assert!(ConfigFile::parse("[server]\nring_size = 5").is_err());
+        assert!(ConfigFile::parse("[[source]]\ntype = \"ftp\"").is_err());
+        let zero = ConfigFile::parse("[server]\nring_capacity = 0").unwrap();
+        assert!(zero.server_config().is_err());
+        let zero = ConfigFile::parse("[server]\nmax_bytes_per_second = 0").unwrap();
+        assert!(zero.server_config().is_err());
+        let negative = ConfigFile::parse("[server]\nsource_restart_delay_secs = -1").unwrap();
+        assert!(negative.server_config().is_err());
+        for protocols in ["[]", "[\"5.0\"]"] {
+            let file = ConfigFile::parse(&format!("[server]\nprotocols = {protocols}")).unwrap();
+            assert!(file.server_config().is_err(), "{protocols}");
+        }
+    }
+
+    #[test]
+    fn parses_tls_section() {
+        let file = ConfigFile::parse("[tls]\ncert = \"server.pem\"\nkey = \"server.key\"").unwrap();
+        assert_eq!(
+            file.tls,
+            Some(TlsSection {
+                cert: "server.pem".into(),
+                key: "server.key".into(),
+            })
+        );
+        assert_eq!(file.server_config().is_ok(), cfg!(feature = "tls"));
+    }
+
+    #[tokio::test]
+    async fn builds_udp_source() {
+        let file = ConfigFile::parse("[[source]]\ntype = \"udp\"\nbind = \"127.0.0.1:0\"").unwrap();
+        let sources = file.build_sources().await.unwrap();
+        assert_eq!(sources.len(), 1);
+        assert_eq!(sources[0].name(), "udp");
+    }
+
+    #[tokio::test]
+    async fn builds_datalink_source() {
+        let file = ConfigFile::parse(
+            "[acl]\nusers = { digitizer = \"secret\" }\n\n\
+             [[source]]\ntype = \"datalink\"\nbind = \"127.0.0.1:0\"\nanonymous = true",
+        )
+        .unwrap();
+        assert_eq!(
+            file.sources[0],
+            SourceSection::Datalink {
+                bind: "127.0.0.1:0".parse().unwrap(),
+                anonymous: true,
+                idle_timeout_secs: None,
+                max_connections: None,
+            }
+        );
+        let sources = file.build_sources().await.unwrap();
+        assert_eq!(sources[0].name(), "datalink");
+    }
+}
@@ -0,0 +1,168 @@
+//! `seedlink-server` — run a [`SeedLinkServer`] from a TOML configuration file.
+//!
+//! Requires the `bin` feature (plus `relay` for relay sources and `tls` for a
+//! TLS listener):
+//!
+//! ```bash
+//! cargo install seedlink-rs-server --features bin,relay,tls
+//! seedlink-server /etc/seedlink/server.toml
+//! seedlink-server --check /etc/seedlink/server.toml   # validate and exit
+//! ```
+//!
+//! Log verbosity follows `RUST_LOG` (default `info`). Ctrl-C or SIGTERM stops
+//! accepting and gives streaming clients [`SHUTDOWN_GRACE`] to drain. SIGHUP
+//! re-reads the file and applies its [`ReloadableConfig`] values; everything
+//! else needs a restart.
+//!
+//! [`ReloadableConfig`]: seedlink_rs_server::ReloadableConfig
+
+mod config;
+#[cfg(feature = "tls")]
+mod tls;
+
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
+
+use seedlink_rs_server::{SeedLinkServer, ServerError};
+use tracing::{error, info, warn};
+use tracing_subscriber::EnvFilter;
+
+use config::ConfigFile;
+
+const USAGE: &str = "Usage: seedlink-server [--check] <CONFIG.toml>";
+
+/// Drain period after a shutdown signal.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| "info".into()))
+        .init();
+
+    let mut check = false;
+    let mut path = None;
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--check" => check = true,
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            "-V" | "--version" => {
+                println!("seedlink-server {}", env!("CARGO_PKG_VERSION"));
+                return ExitCode::SUCCESS;
+            }
+            _ if path.is_none() && !arg.starts_with('-') => path = Some(PathBuf::from(arg)),
+            _ => {
+                eprintln!("seedlink-server: unexpected argument {arg:?}\n{USAGE}");
+                return ExitCode::from(2);
+            }
+        }
+    }
+    let Some(path) = path else {
+        eprintln!("{USAGE}");
+        return ExitCode::from(2);
+    };
+
+    match run(&path, check).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            error!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(path: &Path, check: bool) -> config::Result<()> {
+    let file = ConfigFile::load(path)?;
+    let server_config = file.server_config()?;
+    #[cfg(feature = "tls")]
+    let tls = file.tls.as_ref().map(tls::acceptor).transpose()?;
+    if check {
+        println!("{}: OK ({} sources)", path.display(), file.sources.len());
+        return Ok(());
+    }
+
+    let socket = server_config.socket.clone();
+    let listener = socket.bind(&file.listen).await.map_err(ServerError::Bind)?;
+    let addr = listener.local_addr().map_err(ServerError::Io)?;
+    info!(%addr, tls = file.tls.is_some(), config = %path.display(), "seedlink-server listening");
+
+    #[cfg(feature = "tls")]
+    if let Some(acceptor) = tls {
+        let server = start(SeedLinkServer::new(server_config)?, &file, path).await?;
+        server
+            .run_on(tls::incoming(listener, socket, acceptor))
+            .await;
+        return Ok(());
+    }
+    let server = start(
+        SeedLinkServer::from_listener(listener, server_config)?,
+        &file,
+        path,
+    )
+    .await?;
+    server.run().await;
+    Ok(())
+}
+
+/// Add the configured sources and install the signal handlers.
+async fn start(
+    mut server: SeedLinkServer,
+    file: &ConfigFile,
+    path: &Path,
+) -> config::Result<SeedLinkServer> {
+    for source in file.build_sources().await? {
+        info!(source = source.name(), "adding data source");
+        server.add_source(source);
+    }
+
+    #[cfg(unix)]
+    tokio::spawn(reload_on_hangup(path.to_owned(), server.config_handle()));
+
+    let shutdown = server.shutdown_handle();
+    tokio::spawn(async move {
+        wait_for_signal().await;
+        info!(grace = ?SHUTDOWN_GRACE, "shutting down");
+        shutdown.shutdown_graceful(SHUTDOWN_GRACE);
+    });
+    Ok(server)
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    let mut term = signal(SignalKind::terminate()).expect("install SIGTERM handler");
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {}
+        _ = term.recv() => {}
+    }
+}
+
+/// Re-read the configuration file on every SIGHUP and apply its reloadable
+/// values. A file that no longer loads is logged and ignored.
+#[cfg(unix)]
+async fn reload_on_hangup(path: PathBuf, handle: seedlink_rs_server::ConfigHandle) {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    let mut hangup = signal(SignalKind::hangup()).expect("install SIGHUP handler");
+    while hangup.recv().await.is_some() {
+        match ConfigFile::load(&path).and_then(|file| file.server_config()) {
+            Ok(config) => {
+                handle.apply(&config);
+                info!(config = %path.display(), "configuration reloaded");
+            }
+            Err(e) => {
+                warn!(config = %path.display(), "reload failed, keeping current configuration: {e}")
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() {
+    let _ = tokio::signal::ctrl_c().await;
+}
@@ -0,0 +1,184 @@
+//! TLS listener for the `seedlink-server` binary (`tls` feature).
+
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_core::Stream;
+use seedlink_rs_server::SocketConfig;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio_rustls::TlsAcceptor;
+use tokio_rustls::rustls;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::server::TlsStream;
+use tracing::warn;
+
+use crate::config::{ConfigError, Result, TlsSection};
+
+/// Time a client has to complete the TLS handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Load the certificate chain and key of `[tls]`.
+pub(crate) fn acceptor(section: &TlsSection) -> Result<TlsAcceptor> {
+    let invalid = |path: &Path, e: &dyn std::fmt::Display| {
+        ConfigError::Invalid(format!("{}: {e}", path.display()))
+    };
+    let certs = CertificateDer::pem_slice_iter(&read(&section.cert)?)
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| invalid(&section.cert, &e))?;
+    if certs.is_empty() {
+        return Err(invalid(&section.cert, &"no certificates"));
+    }
+    let key = PrivateKeyDer::from_pem_slice(&read(&section.key)?)
+        .map_err(|e| invalid(&section.key, &e))?;
+    let config = rustls::ServerConfig::builder_with_provider(Arc::new(
+        rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()
+    .and_then(|builder| builder.with_no_client_auth().with_single_cert(certs, key))
+    .map_err(|e| ConfigError::Invalid(format!("tls: {e}")))?;
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+fn read(path: &Path) -> Result<Vec<u8>> {
+    std::fs::read(path).map_err(|source| ConfigError::Read {
+        path: path.to_owned(),
+        source,
+    })
+}
+
+/// Accept connections from `listener` and yield those that complete the TLS
+/// handshake. Handshakes run in their own tasks, so a slow client does not
+/// hold up the others.
+pub(crate) fn incoming(
+    listener: TcpListener,
+    socket: SocketConfig,
+    acceptor: TlsAcceptor,
+) -> impl Stream<Item = (TlsStream<TcpStream>, SocketAddr)> {
+    let (tx, mut rx) = mpsc::channel(64);
+    tokio::spawn(async move {
+        loop {
+            let (stream, addr) = tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok(conn) => conn,
+                    Err(e) => {
+                        warn!(error = %e, "accept error");
+                        continue;
+                    }
+                },
+                _ = tx.closed() => return,
+            };
+            stream.set_nodelay(true).ok();
+            if let Err(e) = socket.apply(&stream) {
+                warn!(%addr, error = %e, "failed to apply socket options");
+            }
+            let (acceptor, tx) = (acceptor.clone(), tx.clone());
+            tokio::spawn(async move {
+                match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                    Ok(Ok(stream)) => {
+                        let _ = tx.send((stream, addr)).await;
+                    }
+                    Ok(Err(e)) => warn!(%addr, error = %e, "TLS handshake failed"),
+                    Err(_) => warn!(%addr, "TLS handshake timed out"),
+                }
+            });
+        }
+    });
+    async_stream::stream! {
+        while let Some(conn) = rx.recv().await {
+            yield conn;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use seedlink_rs_server::{SeedLinkServer, ServerConfig};
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio_rustls::TlsConnector;
+    use tokio_rustls::rustls::RootCertStore;
+    use tokio_rustls::rustls::pki_types::ServerName;
+
+    fn testdata(name: &str) -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../testdata/tls")
+            .join(name)
+    }
+
+    fn section() -> TlsSection {
+        TlsSection {
+            cert: testdata("localhost.pem"),
+            key: testdata("localhost.key"),
+        }
+    }
+
+    #[test]
+    fn rejects_missing_or_mismatched_files() {
+        let missing = TlsSection {
+            cert: testdata("missing.pem"),
+            ..section()
+        };
+        assert!(matches!(acceptor(&missing), Err(ConfigError::Read { .. })));
+        let no_certs = TlsSection {
+            cert: testdata("localhost.key"),
+            ..section()
+        };
+        assert!(matches!(acceptor(&no_certs), Err(ConfigError::Invalid(_))));
+        let no_key = TlsSection {
+            key: testdata("ca.pem"),
+            ..section()
+        };
+        assert!(matches!(acceptor(&no_key), Err(ConfigError::Invalid(_))));
+    }
+
+    #[tokio::test]
+    async fn serves_seedlink_over_tls() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let incoming = incoming(
+            listener,
+            SocketConfig::default(),
+            acceptor(&section()).unwrap(),
+        );
+        let server = SeedLinkServer::new(ServerConfig::default()).unwrap();
+        let shutdown = server.shutdown_handle();
+        let task = tokio::spawn(server.run_on(incoming));
+
+        // A plain-TCP client fails the handshake without affecting others
+        let mut plain = TcpStream::connect(addr).await.unwrap();
+        plain.write_all(b"HELLO\r\n").await.unwrap();
+
+        let mut roots = RootCertStore::empty();
+        let ca = std::fs::read(testdata("ca.pem")).unwrap();
+        roots
+            .add(CertificateDer::from_pem_slice(&ca).unwrap())
+            .unwrap();
+        let config = rustls::ClientConfig::builder_with_provider(Arc::new(
+            rustls::crypto::ring::default_provider(),
+        ))
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+        let tcp = TcpStream::connect(addr).await.unwrap();
+        let name = ServerName::try_from("localhost").unwrap();
+        let stream = TlsConnector::from(Arc::new(config))
+            .connect(name, tcp)
+            .await
+            .unwrap();
+
+        let mut stream = BufReader::new(stream);
+        stream.write_all(b"HELLO\r\n").await.unwrap();
+        let mut line = String::new();
+        stream.read_line(&mut line).await.unwrap();
+        assert!(line.contains("SeedLink"), "{line}");
+
+        shutdown.shutdown();
+        task.await.unwrap();
+    }
+}
@@ -1,15 +1,22 @@
 //! Connection tracking for SeedLink server.
 //!
 //! Maintains a thread-safe registry of active client connections
-//! for INFO CONNECTIONS support.
+//! for INFO CONNECTIONS support, and the public [`ConnectionManager`]
+//! handle for listing and disconnecting clients. The most recently closed
+//! connections are kept with their [`DisconnectReason`].
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::net::SocketAddr;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::SystemTime;
 
 use seedlink_rs_protocol::ProtocolVersion;
+use tokio::sync::Notify;
+
+use crate::events::DisconnectReason;
+use crate::metrics::ClientStats;
+use crate::queue::SendQueue;
 
 /// Per-connection metadata.
 #[derive(Clone, Debug)]
@@ -19,11 +26,31 @@ pub(crate) struct ConnectionInfo {
     pub protocol_version: ProtocolVersion,
     pub user_agent: Option<String>,
     pub state: String,
+    /// Outbound frame queue, present once the client starts streaming.
+    pub queue: Option<SendQueue>,
+    /// Frames and bytes sent, shared with the client handler.
+    pub stats: Arc<ClientStats>,
+    /// Signals the client handler to close the connection.
+    pub kick: Arc<Notify>,
+}
+
+/// Closed connections remembered for [`ConnectionManager::recently_closed()`].
+const RECENTLY_CLOSED: usize = 32;
+
+/// A connection that has been unregistered, and why.
+#[derive(Clone, Debug)]
+pub(crate) struct ClosedConnection {
+    pub id: u64,
+    pub info: ConnectionInfo,
+    pub closed_at: SystemTime,
+    pub reason: DisconnectReason,
 }
 
 struct RegistryInner {
     next_id: AtomicU64,
     connections: Mutex<HashMap<u64, ConnectionInfo>>,
+    /// Newest last, at most [`RECENTLY_CLOSED`].
+    closed: Mutex<VecDeque<ClosedConnection>>,
 }
 
 /// Thread-safe connection registry. Clone is cheap (Arc).
@@ -36,6 +63,7 @@ impl ConnectionRegistry {
         Self(Arc::new(RegistryInner {
             next_id: AtomicU64::new(1),
             connections: Mutex::new(HashMap::new()),
+            closed: Mutex::new(VecDeque::new()),
         }))
     }
 
@@ -48,14 +76,34 @@ impl ConnectionRegistry {
             protocol_version: ProtocolVersion::V3,
             user_agent: None,
             state: "Connected".to_owned(),
+            queue: None,
+            stats: Arc::default(),
+            kick: Arc::default(),
         };
         self.0.connections.lock().unwrap().insert(id, info);
         id
     }
 
-    /// Remove a connection from the registry.
-    pub fn unregister(&self, id: u64) {
-        self.0.connections.lock().unwrap().remove(&id);
+    /// Remove a connection from the registry, remembering why it closed.
+    pub fn unregister(&self, id: u64, reason: DisconnectReason) {
+        let Some(info) = self.0.connections.lock().unwrap().remove(&id) else {
+            return;
+        };
+        let mut closed = self.0.closed.lock().unwrap();
+        if closed.len() == RECENTLY_CLOSED {
+            closed.pop_front();
+        }
+        closed.push_back(ClosedConnection {
+            id,
+            info,
+            closed_at: SystemTime::now(),
+            reason,
+        });
+    }
+
+    /// The most recently closed connections, oldest first.
+    pub fn closed(&self) -> Vec<ClosedConnection> {
+        self.0.closed.lock().unwrap().iter().cloned().collect()
     }
 
     /// Update connection metadata.
@@ -79,6 +127,33 @@ impl ConnectionRegistry {
             .collect()
     }
 
+    /// Snapshot of all active connections with their IDs, ordered by ID.
+    pub fn snapshot_with_ids(&self) -> Vec<(u64, ConnectionInfo)> {
+        let mut conns: Vec<_> = self
+            .0
+            .connections
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(&id, info)| (id, info.clone()))
+            .collect();
+        conns.sort_by_key(|&(id, _)| id);
+        conns
+    }
+
+    /// Signal the handler of connection `id` to close it. Returns `false` if
+    /// no such connection is registered.
+    pub fn kick(&self, id: u64) -> bool {
+        match self.0.connections.lock().unwrap().get(&id) {
+            Some(info) => {
+                // Stores a permit if the handler is not waiting right now
+                info.kick.notify_one();
+                true
+            }
+            None => false,
+        }
+    }
+
     /// Number of active connections.
     #[cfg(test)]
     pub fn count(&self) -> usize {
@@ -86,6 +161,111 @@ impl ConnectionRegistry {
     }
 }
 
+/// Status of one connected client, as returned by [`ConnectionManager::list()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConnectionStatus {
+    /// Connection ID, unique for the server's lifetime. Pass to
+    /// [`ConnectionManager::kick()`].
+    pub id: u64,
+    /// Client address.
+    pub addr: SocketAddr,
+    /// When the client connected.
+    pub connected_at: SystemTime,
+    /// Negotiated protocol version.
+    pub protocol_version: ProtocolVersion,
+    /// Client identification sent with `USERAGENT`.
+    pub user_agent: Option<String>,
+    /// Connection state (`Connected`, `Configured`, `Streaming`).
+    pub state: String,
+    /// Data frames written to this client.
+    pub frames_sent: u64,
+    /// Data frame bytes written to this client.
+    pub bytes_sent: u64,
+    /// Frames waiting in this client's send queue.
+    pub queue_depth: usize,
+    /// Frames dropped from this client's send queue.
+    pub dropped: u64,
+    /// The last write was held back by
+    /// [`ServerConfig::max_bytes_per_second`](crate::ServerConfig::max_bytes_per_second).
+    pub throttled: bool,
+}
+
+/// A client that has disconnected, as returned by
+/// [`ConnectionManager::recently_closed()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClosedConnectionStatus {
+    /// The client's status when it disconnected.
+    pub status: ConnectionStatus,
+    /// When the connection closed.
+    pub closed_at: SystemTime,
+    /// Why the connection closed.
+    pub reason: DisconnectReason,
+}
+
+/// Handle for listing and disconnecting clients. Clone is cheap (Arc).
+///
+/// Obtained via [`SeedLinkServer::connections()`](crate::SeedLinkServer::connections);
+/// stays valid after [`run()`](crate::SeedLinkServer::run) consumes the server.
+#[derive(Clone)]
+pub struct ConnectionManager {
+    registry: ConnectionRegistry,
+}
+
+impl ConnectionManager {
+    pub(crate) fn new(registry: ConnectionRegistry) -> Self {
+        Self { registry }
+    }
+
+    /// Currently connected clients, ordered by connection ID.
+    pub fn list(&self) -> Vec<ConnectionStatus> {
+        self.registry
+            .snapshot_with_ids()
+            .into_iter()
+            .map(|(id, c)| status(id, c))
+            .collect()
+    }
+
+    /// The last 32 clients to disconnect, oldest first, with the reason
+    /// each connection closed.
+    pub fn recently_closed(&self) -> Vec<ClosedConnectionStatus> {
+        self.registry
+            .closed()
+            .into_iter()
+            .map(|c| ClosedConnectionStatus {
+                status: status(c.id, c.info),
+                closed_at: c.closed_at,
+                reason: c.reason,
+            })
+            .collect()
+    }
+
+    /// Disconnect client `id`. Its handler closes the connection as soon as
+    /// it sees the signal, also in the middle of streaming; frames still in
+    /// its send queue are discarded.
+    ///
+    /// Returns `false` if no client with that ID is connected.
+    pub fn kick(&self, id: u64) -> bool {
+        self.registry.kick(id)
+    }
+}
+
+fn status(id: u64, c: ConnectionInfo) -> ConnectionStatus {
+    let (queue_depth, dropped) = c.queue.as_ref().map_or((0, 0), |q| (q.len(), q.dropped()));
+    ConnectionStatus {
+        id,
+        addr: c.addr,
+        connected_at: c.connected_at,
+        protocol_version: c.protocol_version,
+        user_agent: c.user_agent,
+        state: c.state,
+        frames_sent: c.stats.frames_sent.load(Ordering::Relaxed),
+        bytes_sent: c.stats.bytes_sent.load(Ordering::Relaxed),
+        queue_depth,
+        dropped,
+        throttled: c.stats.throttled.load(Ordering::Relaxed),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -105,11 +285,17 @@ mod tests {
         assert_eq!(reg.count(), 2);
         assert_ne!(id1, id2);
 
-        reg.unregister(id1);
+        reg.unregister(id1, DisconnectReason::Closed);
         assert_eq!(reg.count(), 1);
 
-        reg.unregister(id2);
+        reg.unregister(id2, DisconnectReason::PeerReset);
         assert_eq!(reg.count(), 0);
+
+        let closed = reg.closed();
+        assert_eq!(closed.len(), 2);
+        assert_eq!(closed[0].id, id1);
+        assert_eq!(closed[1].info.addr, addr(1002));
+        assert_eq!(closed[1].reason, DisconnectReason::PeerReset);
     }
 
     #[test]
@@ -141,10 +327,37 @@ mod tests {
         assert_eq!(snap.len(), 3);
     }
 
+    #[test]
+    fn manager_lists_and_kicks() {
+        let reg = ConnectionRegistry::new();
+        let id1 = reg.register(addr(1001));
+        let id2 = reg.register(addr(1002));
+        reg.update(id2, |info| info.state = "Streaming".to_owned());
+
+        let manager = ConnectionManager::new(reg.clone());
+        let list = manager.list();
+        assert_eq!(list.iter().map(|c| c.id).collect::<Vec<_>>(), [id1, id2]);
+        assert_eq!(list[1].addr, addr(1002));
+        assert_eq!(list[1].state, "Streaming");
+
+        let kick = reg.snapshot_with_ids()[0].1.kick.clone();
+        assert!(manager.kick(id1));
+        // The permit is kept until the handler waits for it
+        assert!(is_ready(kick.notified()));
+        reg.unregister(id1, DisconnectReason::Closed);
+        assert!(!manager.kick(id1));
+    }
+
+    fn is_ready<F: std::future::Future>(fut: F) -> bool {
+        let waker = std::task::Waker::noop();
+        let mut cx = std::task::Context::from_waker(waker);
+        std::pin::pin!(fut).poll(&mut cx).is_ready()
+    }
+
     #[test]
     fn unregister_nonexistent_is_noop() {
         let reg = ConnectionRegistry::new();
-        reg.unregister(999); // should not panic
+        reg.unregister(999, DisconnectReason::Closed); // should not panic
         assert_eq!(reg.count(), 0);
     }
 }
@@ -0,0 +1,479 @@
+//! DataLink ingest for remote producers.
+//!
+//! [`DataLinkSource`] listens on its own TCP port and accepts records pushed
+//! with the DataLink protocol's `WRITE` command, as sent by `slink2dali`,
+//! ringserver clients or `DataLinkSink` from `seedlink-rs-client`. Producers
+//! can then run on other hosts instead of in the server process.
+//!
+//! Supported commands: `ID`, `AUTH USERPASS user password` and
+//! `WRITE streamid hpstart hpend flags size` with `NET_STA_LOC_CHAN/MSEED`
+//! stream IDs and 512-byte miniSEED v2 payloads. Anything else gets an
+//! `ERROR` reply. Writes are authorized against an [`Acl`]: its users for
+//! `AUTH`, its rules (by address or identity) for each record's station.
+
+use std::collections::HashMap;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinSet;
+use tracing::{debug, info, warn};
+
+use crate::acl::Acl;
+use crate::error::{Result, ServerError};
+use crate::metrics::SenderMetrics;
+use crate::source::{BoxFuture, DataSource};
+use crate::store::{DataStore, record_ids};
+use crate::udp::SenderStats;
+
+/// Largest `WRITE` payload read; a larger size closes the connection.
+const MAX_PACKET: usize = 16_384;
+/// Default [`DataLinkConfig::idle_timeout`].
+const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(600);
+/// Default [`DataLinkConfig::max_connections`].
+const DEFAULT_MAX_CONNECTIONS: usize = 256;
+
+/// Configuration for [`DataLinkSource`].
+#[derive(Clone, Debug)]
+pub struct DataLinkConfig {
+    /// Local address to listen on (ringserver uses port 16000).
+    pub bind: SocketAddr,
+    /// Users for `AUTH USERPASS` and station rules for writes. Default:
+    /// allow everything, no users.
+    pub acl: Acl,
+    /// Reject `WRITE` from connections that have not authenticated.
+    /// Default: `true`.
+    pub require_auth: bool,
+    /// Close a connection that sends no complete packet for this long.
+    /// Producers that write less often must raise it. `None` or zero
+    /// disables. Default: 10 minutes.
+    pub idle_timeout: Option<Duration>,
+    /// Most producer connections served at once; further connections are
+    /// closed as soon as they are accepted. Default: `256`.
+    pub max_connections: usize,
+}
+
+impl DataLinkConfig {
+    /// Listener on `bind` that requires `AUTH` before writes.
+    pub fn new(bind: SocketAddr) -> Self {
+        Self {
+            bind,
+            acl: Acl::default(),
+            require_auth: true,
+            idle_timeout: Some(DEFAULT_IDLE_TIMEOUT),
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+        }
+    }
+
+    /// Check `AUTH` credentials and writes against `acl`.
+    pub fn acl(mut self, acl: Acl) -> Self {
+        self.acl = acl;
+        self
+    }
+
+    /// Accept writes without `AUTH`; the ACL's address rules still apply.
+    pub fn allow_anonymous(mut self) -> Self {
+        self.require_auth = false;
+        self
+    }
+}
+
+/// State shared with the per-connection tasks.
+struct Shared {
+    acl: Acl,
+    require_auth: bool,
+    idle_timeout: Option<Duration>,
+    max_connections: usize,
+    /// Statistics of connected producers, removed when they disconnect.
+    senders: Mutex<HashMap<SocketAddr, SenderStats>>,
+}
+
+/// A [`DataSource`] that accepts records written over DataLink.
+///
+/// Each `WRITE` is pushed with [`DataStore::push_record()`], so network and
+/// station come from the record header and the store's validation applies.
+/// With the `A` flag the producer gets `OK <sequence>` or `ERROR` with the
+/// reason (not authenticated, station denied by the ACL, invalid record).
+/// [`sender_metrics()`](DataSource::sender_metrics) covers the producers
+/// connected at the time.
+///
+/// ```no_run
+/// # async fn example() -> seedlink_rs_server::Result<()> {
+/// use seedlink_rs_server::{Acl, AclAction, AclRule, AclSubject, SeedLinkServer};
+/// use seedlink_rs_server::datalink::{DataLinkConfig, DataLinkSource};
+///
+/// let acl = Acl::new()
+///     .user("digitizer", "secret")
+///     .default_action(AclAction::Deny)
+///     .rule(AclRule::allow(AclSubject::Identity("digitizer".into()), "XX", "*"));
+/// let config = DataLinkConfig::new("0.0.0.0:16000".parse().unwrap()).acl(acl);
+/// let mut server = SeedLinkServer::bind("0.0.0.0:18000").await?;
+/// server.add_source(Box::new(DataLinkSource::bind(config).await?));
+/// server.run().await;
+/// # Ok(())
+/// # }
+/// ```
+pub struct DataLinkSource {
+    listener: TcpListener,
+    shared: Arc<Shared>,
+}
+
+impl DataLinkSource {
+    /// Bind the listener.
+    pub async fn bind(config: DataLinkConfig) -> Result<Self> {
+        let listener = TcpListener::bind(config.bind)
+            .await
+            .map_err(ServerError::Bind)?;
+        info!(addr = %listener.local_addr()?, "DataLink source bound");
+        Ok(Self {
+            listener,
+            shared: Arc::new(Shared {
+                acl: config.acl,
+                require_auth: config.require_auth,
+                idle_timeout: config.idle_timeout,
+                max_connections: config.max_connections,
+                senders: Mutex::new(HashMap::new()),
+            }),
+        })
+    }
+
+    /// Address the listener is bound to.
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+}
+
+impl DataSource for DataLinkSource {
+    fn name(&self) -> &str {
+        "datalink"
+    }
+
+    fn run(&self, store: DataStore) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            // Dropped with this future, which closes every producer connection
+            let mut connections = JoinSet::new();
+            loop {
+                tokio::select! {
+                    Some(_) = connections.join_next(), if !connections.is_empty() => {}
+                    accepted = self.listener.accept() => {
+                        let (stream, peer) = accepted?;
+                        if connections.len() >= self.shared.max_connections {
+                            warn!(%peer, max = self.shared.max_connections, "DataLink connection limit reached, closing");
+                            continue;
+                        }
+                        debug!(%peer, "DataLink producer connected");
+                        let (shared, store) = (self.shared.clone(), store.clone());
+                        connections.spawn(async move {
+                            if let Err(error) = serve(&shared, &store, stream, peer).await {
+                                debug!(%peer, %error, "DataLink connection closed");
+                            }
+                            shared.senders.lock().unwrap().remove(&peer);
+                        });
+                    }
+                }
+            }
+        })
+    }
+
+    fn sender_metrics(&self) -> Vec<SenderMetrics> {
+        let mut senders: Vec<_> = self
+            .shared
+            .senders
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(addr, s)| SenderMetrics {
+                source: self.name().to_owned(),
+                addr: *addr,
+                records: s.records,
+                rejected: s.rejected,
+                bytes: s.bytes,
+            })
+            .collect();
+        senders.sort_by_key(|s| s.addr);
+        senders
+    }
+}
+
+/// Handle one producer connection until it closes.
+async fn serve(
+    shared: &Shared,
+    store: &DataStore,
+    mut stream: TcpStream,
+    peer: SocketAddr,
+) -> io::Result<()> {
+    let mut identity: Option<String> = None;
+    loop {
+        let mut preheader = [0u8; 3];
+        match read_exact(&mut stream, &mut preheader, shared.idle_timeout).await {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+            Err(e) => return Err(e),
+        }
+        if &preheader[..2] != b"DL" {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a DataLink packet",
+            ));
+        }
+        let mut header = vec![0u8; usize::from(preheader[2])];
+        read_exact(&mut stream, &mut header, shared.idle_timeout).await?;
+        let header = String::from_utf8_lossy(&header).into_owned();
+        shared.stats(peer, |s| s.bytes += 3 + header.len() as u64);
+
+        let mut words = header.split_whitespace();
+        match words.next() {
+            Some("ID") => {
+                let id = format!(
+                    "ID DataLink seedlink-rs:{} :: DLPROTO:1.0 PACKETSIZE:512 WRITE",
+                    env!("CARGO_PKG_VERSION")
+                );
+                write_packet(&mut stream, &id, &[]).await?;
+            }
+            Some("AUTH") => {
+                let reply = match (words.next(), words.next(), words.next()) {
+                    (Some(method), Some(user), Some(password))
+                        if method.eq_ignore_ascii_case("USERPASS")
+                            && shared.acl.authenticate(user, password) =>
+                    {
+                        info!(%peer, user, "DataLink producer authenticated");
+                        identity = Some(user.to_owned());
+                        Ok(0)
+                    }
+                    _ => Err("authentication failed".to_owned()),
+                };
+                reply_to(&mut stream, reply).await?;
+            }
+            Some("WRITE") => {
+                let fields: Vec<&str> = words.collect();
+                let [stream_id, _start, _end, flags, size] = fields[..] else {
+                    reply_to(
+                        &mut stream,
+                        Err("expected WRITE streamid hpstart hpend flags size".into()),
+                    )
+                    .await?;
+                    continue;
+                };
+                let size: usize = size.parse().map_err(|_| {
+                    io::Error::new(io::ErrorKind::InvalidData, "invalid WRITE size")
+                })?;
+                if size > MAX_PACKET {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("WRITE of {size} bytes exceeds {MAX_PACKET}"),
+                    ));
+                }
+                let mut payload = vec![0u8; size];
+                read_exact(&mut stream, &mut payload, shared.idle_timeout).await?;
+                shared.stats(peer, |s| s.bytes += size as u64);
+
+                let result = shared.ingest(store, peer, identity.as_deref(), stream_id, &payload);
+                match &result {
+                    Ok(_) => shared.stats(peer, |s| s.records += 1),
+                    Err(error) => {
+                        debug!(%peer, stream_id, error, "rejecting DataLink write");
+                        shared.stats(peer, |s| s.rejected += 1);
+                    }
+                }
+                if flags.contains('A') {
+                    reply_to(&mut stream, result).await?;
+                }
+            }
+            _ => {
+                reply_to(&mut stream, Err(format!("unsupported command: {header}"))).await?;
+            }
+        }
+    }
+}
+
+impl Shared {
+    fn stats(&self, peer: SocketAddr, update: impl FnOnce(&mut SenderStats)) {
+        update(self.senders.lock().unwrap().entry(peer).or_default());
+    }
+
+    /// Authorize and push one written record. Returns its sequence number
+    /// (`0` for a duplicate or held record).
+    fn ingest(
+        &self,
+        store: &DataStore,
+        peer: SocketAddr,
+        identity: Option<&str>,
+        stream_id: &str,
+        payload: &[u8],
+    ) -> std::result::Result<u64, String> {
+        if self.require_auth && identity.is_none() {
+            return Err("authentication required".into());
+        }
+        if !stream_id.ends_with("/MSEED") {
+            return Err(format!("unsupported stream type: {stream_id}"));
+        }
+        let (network, station) = record_ids(payload).map_err(|e| e.to_string())?;
+        if !self.acl.is_allowed(peer.ip(), identity, &network, &station) {
+            return Err(format!("write to {network}_{station} not allowed"));
+        }
+        store
+            .push_record(payload)
+            .map(|seq| seq.value())
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// `read_exact`, failing with [`io::ErrorKind::TimedOut`] if `timeout`
+/// passes first.
+async fn read_exact(
+    stream: &mut TcpStream,
+    buf: &mut [u8],
+    timeout: Option<Duration>,
+) -> io::Result<()> {
+    let read = stream.read_exact(buf);
+    let result = match timeout.filter(|timeout| !timeout.is_zero()) {
+        Some(timeout) => tokio::time::timeout(timeout, read)
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "DataLink connection idle"))?,
+        None => read.await,
+    };
+    result.map(|_| ())
+}
+
+/// Send `OK value 0` or `ERROR 0 size` followed by the message.
+async fn reply_to(
+    stream: &mut TcpStream,
+    result: std::result::Result<u64, String>,
+) -> io::Result<()> {
+    match result {
+        Ok(value) => write_packet(stream, &format!("OK {value} 0"), &[]).await,
+        Err(message) => {
+            let header = format!("ERROR 0 {}", message.len());
+            write_packet(stream, &header, message.as_bytes()).await
+        }
+    }
+}
+
+/// Send `DL`, the header length, the header, then `payload`.
+async fn write_packet(stream: &mut TcpStream, header: &str, payload: &[u8]) -> io::Result<()> {
+    let len = u8::try_from(header.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "DataLink header too long"))?;
+    let mut packet = Vec::with_capacity(3 + header.len() + payload.len());
+    packet.extend_from_slice(b"DL");
+    packet.push(len);
+    packet.extend_from_slice(header.as_bytes());
+    packet.extend_from_slice(payload);
+    stream.write_all(&packet).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::acl::{AclAction, AclRule, AclSubject};
+    use seedlink_rs_protocol::testing::TestRecordBuilder;
+
+    /// Send a packet and read the `OK`/`ERROR` reply: header and message.
+    async fn request(stream: &mut TcpStream, header: &str, payload: &[u8]) -> (String, String) {
+        write_packet(stream, header, payload).await.unwrap();
+        let mut pre = [0u8; 3];
+        stream.read_exact(&mut pre).await.unwrap();
+        let mut reply = vec![0u8; usize::from(pre[2])];
+        stream.read_exact(&mut reply).await.unwrap();
+        let reply = String::from_utf8(reply).unwrap();
+        let size: usize = reply.rsplit(' ').next().unwrap().parse().unwrap_or(0);
+        let mut message = vec![0u8; if reply.starts_with("ID ") { 0 } else { size }];
+        stream.read_exact(&mut message).await.unwrap();
+        (reply, String::from_utf8(message).unwrap())
+    }
+
+    fn write(network: &str) -> (String, Vec<u8>) {
+        let record = TestRecordBuilder::new().network(network).build();
+        let header = format!("WRITE {network}_ANMO_00_BHZ/MSEED 0 0 A {}", record.len());
+        (header, record)
+    }
+
+    #[tokio::test]
+    async fn authorizes_writes_per_user_and_station() {
+        let acl = Acl::new()
+            .user("alice", "secret")
+            .default_action(AclAction::Deny)
+            .rule(AclRule::allow(
+                AclSubject::Identity("alice".into()),
+                "IU",
+                "*",
+            ));
+        let config = DataLinkConfig::new("127.0.0.1:0".parse().unwrap()).acl(acl);
+        let source = Arc::new(DataLinkSource::bind(config).await.unwrap());
+        let addr = source.local_addr().unwrap();
+        let store = DataStore::new(10);
+        tokio::spawn({
+            let (source, store) = (source.clone(), store.clone());
+            async move { source.run(store).await }
+        });
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let (id, _) = request(&mut stream, "ID test", &[]).await;
+        assert!(id.starts_with("ID DataLink seedlink-rs:"), "{id}");
+        assert!(id.ends_with(":: DLPROTO:1.0 PACKETSIZE:512 WRITE"), "{id}");
+
+        let (header, record) = write("IU");
+        let reply = request(&mut stream, &header, &record).await;
+        assert_eq!(
+            reply,
+            ("ERROR 0 23".into(), "authentication required".into())
+        );
+
+        let reply = request(&mut stream, "AUTH USERPASS alice wrong", &[]).await;
+        assert_eq!(reply.1, "authentication failed");
+        let reply = request(&mut stream, "AUTH USERPASS alice secret", &[]).await;
+        assert_eq!(reply.0, "OK 0 0");
+
+        assert_eq!(request(&mut stream, &header, &record).await.0, "OK 1 0");
+        let (header, record) = write("GE");
+        let reply = request(&mut stream, &header, &record).await;
+        assert_eq!(reply.1, "write to GE_ANMO not allowed");
+        let reply = request(&mut stream, "WRITE IU_ANMO_00_BHZ/MSEED 0 0 A 4", b"junk").await;
+        assert!(
+            reply.1.starts_with("invalid miniSEED header"),
+            "{}",
+            reply.1
+        );
+        let reply = request(&mut stream, "READ 1", &[]).await;
+        assert_eq!(reply.1, "unsupported command: READ 1");
+
+        assert_eq!(store.len(), 1);
+        let metrics = source.sender_metrics();
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].source, "datalink");
+        assert_eq!(metrics[0].addr, stream.local_addr().unwrap());
+        assert_eq!((metrics[0].records, metrics[0].rejected), (1, 3));
+    }
+
+    #[tokio::test]
+    async fn closes_idle_and_excess_connections() {
+        let mut config = DataLinkConfig::new("127.0.0.1:0".parse().unwrap()).allow_anonymous();
+        config.idle_timeout = Some(Duration::from_millis(100));
+        config.max_connections = 1;
+        let source = Arc::new(DataLinkSource::bind(config).await.unwrap());
+        let addr = source.local_addr().unwrap();
+        tokio::spawn({
+            let source = source.clone();
+            async move { source.run(DataStore::new(10)).await }
+        });
+
+        let mut first = TcpStream::connect(addr).await.unwrap();
+        let (header, record) = write("IU");
+        assert_eq!(request(&mut first, &header, &record).await.0, "OK 1 0");
+        assert_eq!(source.sender_metrics().len(), 1);
+
+        // Over the limit: closed without a reply
+        let mut second = TcpStream::connect(addr).await.unwrap();
+        write_packet(&mut second, "ID test", &[]).await.unwrap();
+        let mut buf = [0u8; 1];
+        // (the unread ID can turn the close into a reset)
+        assert!(!matches!(second.read(&mut buf).await, Ok(n) if n > 0));
+
+        // Idle past the timeout: closed, and its statistics dropped
+        assert_eq!(first.read(&mut buf).await.unwrap(), 0);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(source.sender_metrics().is_empty());
+    }
+}
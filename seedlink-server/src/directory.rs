@@ -0,0 +1,266 @@
+//! Spool-directory source for miniSEED files.
+//!
+//! [`DirectorySource`] polls a directory for new files (e.g. written by a
+//! digitizer), splits each into 512-byte miniSEED v2 records, pushes them into
+//! the [`DataStore`] under the network/station codes from each record header,
+//! and then deletes the file or moves it to an archive directory.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use seedlink_rs_protocol::frame::v3;
+use tracing::{debug, info, warn};
+
+use crate::error::Result;
+use crate::source::{BoxFuture, DataSource};
+use crate::store::DataStore;
+
+/// What to do with a file once its records have been pushed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum ConsumedAction {
+    /// Delete the file.
+    #[default]
+    Delete,
+    /// Move the file into this directory (created if missing).
+    Archive(PathBuf),
+}
+
+/// Configuration for [`DirectorySource`].
+#[derive(Clone, Debug)]
+pub struct DirectoryConfig {
+    /// How often to scan the directory. Default: 1 second.
+    pub poll_interval: Duration,
+    /// Files modified more recently than this are left alone, so files still
+    /// being written are not read half-finished. Default: 2 seconds.
+    pub min_age: Duration,
+    /// What to do with consumed files. Default: [`ConsumedAction::Delete`].
+    pub consumed: ConsumedAction,
+}
+
+impl Default for DirectoryConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_secs(1),
+            min_age: Duration::from_secs(2),
+            consumed: ConsumedAction::Delete,
+        }
+    }
+}
+
+/// A [`DataSource`] that ingests miniSEED files dropped into a spool directory.
+///
+/// Files are processed in name order. Chunks that do not look like a
+/// miniSEED v2 header or fail [`StoreConfig::validation`](crate::StoreConfig::validation)
+/// are skipped with a warning, as are trailing bytes
+/// shorter than a record. Subdirectories and hidden files (leading `.`) are
+/// ignored.
+///
+/// ```no_run
+/// # async fn example() -> seedlink_rs_server::Result<()> {
+/// use seedlink_rs_server::{DirectorySource, SeedLinkServer};
+///
+/// let mut server = SeedLinkServer::bind("0.0.0.0:18000").await?;
+/// server.add_source(Box::new(DirectorySource::new("/var/spool/digitizer")));
+/// server.run().await;
+/// # Ok(())
+/// # }
+/// ```
+pub struct DirectorySource {
+    dir: PathBuf,
+    config: DirectoryConfig,
+    /// Files already pushed whose removal failed; never ingested twice.
+    stuck: Mutex<HashSet<PathBuf>>,
+}
+
+impl DirectorySource {
+    /// Watch `dir` with default configuration.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self::with_config(dir, DirectoryConfig::default())
+    }
+
+    /// Watch `dir` with custom configuration.
+    pub fn with_config(dir: impl Into<PathBuf>, config: DirectoryConfig) -> Self {
+        Self {
+            dir: dir.into(),
+            config,
+            stuck: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Scan the directory once and ingest every ready file.
+    ///
+    /// Returns the number of records pushed.
+    async fn scan(&self, store: &DataStore) -> Result<usize> {
+        let mut files = Vec::new();
+        let mut entries = tokio::fs::read_dir(&self.dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let hidden = entry.file_name().to_string_lossy().starts_with('.');
+            let meta = entry.metadata().await?;
+            if hidden || !meta.is_file() || self.stuck.lock().unwrap().contains(&path) {
+                continue;
+            }
+            let age = meta
+                .modified()
+                .ok()
+                .and_then(|m| SystemTime::now().duration_since(m).ok())
+                .unwrap_or_default();
+            if age >= self.config.min_age {
+                files.push(path);
+            }
+        }
+        files.sort();
+
+        let mut pushed = 0;
+        for path in files {
+            let data = tokio::fs::read(&path).await?;
+            let n = push_records(store, &path, &data);
+            debug!(file = %path.display(), records = n, "ingested file");
+            pushed += n;
+
+            if let Err(e) = self.dispose(&path).await {
+                warn!(file = %path.display(), error = %e, "failed to remove consumed file");
+                self.stuck.lock().unwrap().insert(path);
+            }
+        }
+        Ok(pushed)
+    }
+
+    async fn dispose(&self, path: &Path) -> std::io::Result<()> {
+        match &self.config.consumed {
+            ConsumedAction::Delete => tokio::fs::remove_file(path).await,
+            ConsumedAction::Archive(archive) => {
+                tokio::fs::create_dir_all(archive).await?;
+                let name = path.file_name().unwrap_or_default();
+                tokio::fs::rename(path, archive.join(name)).await
+            }
+        }
+    }
+}
+
+impl DataSource for DirectorySource {
+    fn name(&self) -> &str {
+        "directory"
+    }
+
+    fn run(&self, store: DataStore) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            info!(dir = %self.dir.display(), "watching spool directory");
+            loop {
+                let n = self.scan(&store).await?;
+                if n > 0 {
+                    debug!(records = n, "spool scan complete");
+                }
+                tokio::time::sleep(self.config.poll_interval).await;
+            }
+        })
+    }
+}
+
+/// Split `data` into 512-byte records and push those with a valid header.
+fn push_records(store: &DataStore, path: &Path, data: &[u8]) -> usize {
+    let mut pushed = 0;
+    let chunks = data.chunks_exact(v3::PAYLOAD_LEN);
+    if !chunks.remainder().is_empty() {
+        warn!(
+            file = %path.display(),
+            bytes = chunks.remainder().len(),
+            "ignoring trailing partial record"
+        );
+    }
+    for (i, record) in chunks.enumerate() {
+        match store.push_record(record) {
+            Ok(_) => pushed += 1,
+            Err(error) => warn!(file = %path.display(), record = i, %error, "skipping record"),
+        }
+    }
+    pushed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use seedlink_rs_protocol::testing::TestRecordBuilder;
+
+    fn record(network: &str, station: &str) -> Vec<u8> {
+        TestRecordBuilder::new()
+            .network(network)
+            .station(station)
+            .build()
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("seedlink-spool-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn config(consumed: ConsumedAction) -> DirectoryConfig {
+        DirectoryConfig {
+            poll_interval: Duration::from_millis(10),
+            min_age: Duration::ZERO,
+            consumed,
+        }
+    }
+
+    #[tokio::test]
+    async fn ingests_and_deletes_files() {
+        let dir = temp_dir("delete");
+        let mut data = record("IU", "ANMO");
+        data.extend(record("GE", "WLF"));
+        data.extend(vec![0u8; 512]); // not a record
+        data.extend(vec![0u8; 100]); // trailing partial
+        std::fs::write(dir.join("a.mseed"), &data).unwrap();
+        std::fs::write(dir.join(".partial"), record("XX", "TMP")).unwrap();
+
+        let source = DirectorySource::with_config(&dir, config(ConsumedAction::Delete));
+        let store = DataStore::new(10);
+        assert_eq!(source.scan(&store).await.unwrap(), 2);
+
+        let stations = store.station_info();
+        assert_eq!(stations.len(), 2);
+        assert!(!dir.join("a.mseed").exists());
+        assert!(dir.join(".partial").exists());
+
+        // Nothing left to ingest
+        assert_eq!(source.scan(&store).await.unwrap(), 0);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn archives_consumed_files() {
+        let dir = temp_dir("archive");
+        let archive = dir.join("done");
+        std::fs::write(dir.join("b.mseed"), record("IU", "ANMO")).unwrap();
+
+        let source =
+            DirectorySource::with_config(&dir, config(ConsumedAction::Archive(archive.clone())));
+        let store = DataStore::new(10);
+        assert_eq!(source.scan(&store).await.unwrap(), 1);
+        assert!(!dir.join("b.mseed").exists());
+        assert!(archive.join("b.mseed").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn skips_recently_modified_files() {
+        let dir = temp_dir("young");
+        std::fs::write(dir.join("c.mseed"), record("IU", "ANMO")).unwrap();
+
+        let source = DirectorySource::with_config(
+            &dir,
+            DirectoryConfig {
+                min_age: Duration::from_secs(3600),
+                ..config(ConsumedAction::Delete)
+            },
+        );
+        let store = DataStore::new(10);
+        assert_eq!(source.scan(&store).await.unwrap(), 0);
+        assert!(dir.join("c.mseed").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -8,6 +8,25 @@ pub enum ServerError {
     Bind(std::io::Error),
     #[error("invalid payload length: expected 512, got {0}")]
     InvalidPayloadLength(usize),
+    #[error("invalid miniSEED header: {0}")]
+    InvalidHeader(String),
+    #[error("invalid sequence number {0}: must be 1..=0xFFFFFF")]
+    InvalidSequence(seedlink_rs_protocol::SequenceNumber),
+    #[error("sequence {sequence} does not follow last sequence {last}")]
+    SequenceOutOfOrder {
+        sequence: seedlink_rs_protocol::SequenceNumber,
+        last: seedlink_rs_protocol::SequenceNumber,
+    },
+    #[error("duplicate record")]
+    DuplicateRecord,
+    #[error("invalid SELECT pattern: {0}")]
+    InvalidSelect(String),
+    #[error("invalid record: {0}")]
+    InvalidRecord(seedlink_rs_protocol::InvalidRecord),
+    #[error("data source error: {0}")]
+    Source(String),
+    #[error("invalid configuration: {0}")]
+    InvalidConfig(String),
 }
 
 pub type Result<T> = std::result::Result<T, ServerError>;
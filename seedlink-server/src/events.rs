@@ -0,0 +1,112 @@
+//! Server event bus.
+//!
+//! [`SeedLinkServer::events()`](crate::SeedLinkServer::events) returns a
+//! broadcast receiver of [`ServerEvent`]s — connections, subscriptions, stream
+//! starts and shutdown — for audit logging or dashboards without touching the
+//! client handler.
+
+use std::net::SocketAddr;
+
+use seedlink_rs_protocol::{ProtocolVersion, SequenceNumber};
+
+/// Events buffered per receiver; a receiver further behind gets
+/// [`RecvError::Lagged`](tokio::sync::broadcast::error::RecvError::Lagged).
+pub(crate) const EVENT_CAPACITY: usize = 1024;
+
+/// Something that happened on the server.
+///
+/// `id` is the connection ID also used by
+/// [`ConnectionManager`](crate::ConnectionManager) and in log spans.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ServerEvent {
+    /// A client connection was accepted.
+    ClientConnected { id: u64, addr: SocketAddr },
+    /// A client connection was closed.
+    ClientDisconnected {
+        id: u64,
+        addr: SocketAddr,
+        reason: DisconnectReason,
+    },
+    /// A client subscribed to a station with `STATION`, or implicitly in
+    /// uni-station mode. `network`/`station` may contain wildcards.
+    SubscriptionAdded {
+        id: u64,
+        network: String,
+        station: String,
+    },
+    /// A client started streaming: `END` (`fetch: false`), or `FETCH` /
+    /// `ENDFETCH` (`fetch: true`).
+    StreamStarted {
+        id: u64,
+        version: ProtocolVersion,
+        fetch: bool,
+    },
+    /// A client resumed a subscription with `DATA seq` or `FETCH seq` but
+    /// records after `sequence` have been evicted. `fallback` is set when
+    /// the client's `DATA` start time is used instead.
+    ResumeMissed {
+        id: u64,
+        network: String,
+        station: String,
+        sequence: SequenceNumber,
+        fallback: bool,
+    },
+    /// Shutdown was signalled and the server stopped accepting connections.
+    Shutdown { graceful: bool },
+}
+
+/// Why a client connection was closed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DisconnectReason {
+    /// The client sent `BYE` or closed the connection.
+    Closed,
+    /// The stream finished: FETCH data sent, or every TIME window complete.
+    StreamEnded,
+    /// Disconnected with [`ConnectionManager::kick()`](crate::ConnectionManager::kick).
+    Kicked,
+    /// `command_timeout` expired.
+    TimedOut,
+    /// `max_command_len` or `max_commands_per_second` exceeded.
+    Limited,
+    /// The server shut down.
+    Shutdown,
+    /// The client reset or abandoned the connection mid-stream.
+    PeerReset,
+    /// A streaming client stopped keeping up: a write made no progress for
+    /// `stream_idle_timeout`, its send queue overflowed under
+    /// [`OverflowPolicy::Disconnect`](crate::OverflowPolicy::Disconnect), or a
+    /// write failed as would-block or timed out.
+    SlowConsumer,
+    /// Reading from or writing to the client failed.
+    Error(String),
+}
+
+impl DisconnectReason {
+    /// Short name, as in the `reason` attribute of INFO CONNECTIONS.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Closed => "closed",
+            Self::StreamEnded => "stream_ended",
+            Self::Kicked => "kicked",
+            Self::TimedOut => "timed_out",
+            Self::Limited => "limited",
+            Self::Shutdown => "shutdown",
+            Self::PeerReset => "peer_reset",
+            Self::SlowConsumer => "slow_consumer",
+            Self::Error(_) => "error",
+        }
+    }
+
+    /// The reason for a failed socket read or write.
+    pub(crate) fn from_io(error: &std::io::Error) -> Self {
+        use std::io::ErrorKind;
+        match error.kind() {
+            ErrorKind::ConnectionReset
+            | ErrorKind::ConnectionAborted
+            | ErrorKind::BrokenPipe
+            | ErrorKind::UnexpectedEof => Self::PeerReset,
+            ErrorKind::WouldBlock | ErrorKind::TimedOut => Self::SlowConsumer,
+            _ => Self::Error(error.to_string()),
+        }
+    }
+}
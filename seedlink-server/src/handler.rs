@@ -1,14 +1,36 @@
+use std::borrow::Cow;
+use std::io::{self, IoSlice};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
 use seedlink_rs_protocol::frame::{PayloadFormat, PayloadSubformat, v3, v4};
-use seedlink_rs_protocol::{Command, InfoLevel, ProtocolVersion, Response, SequenceNumber};
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, BufWriter};
-use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
-use tokio::sync::watch;
-use tracing::{debug, info, trace};
+use seedlink_rs_protocol::{
+    Capabilities, Command, InfoDocument, InfoLevel, ProtocolVersion, Response, SeedlinkError,
+    SequenceNumber,
+};
+use tokio::io::{
+    AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader, BufWriter, Take,
+};
+use tokio::sync::{Notify, broadcast, watch};
+use tokio::time::Instant;
+use tracing::{Instrument, Span, debug, field, info, info_span, trace, warn};
 
+use crate::ShutdownSignal;
 use crate::connections::ConnectionRegistry;
+use crate::events::{DisconnectReason, ServerEvent};
 use crate::info as info_xml;
-use crate::select::SelectPattern;
-use crate::store::{DataStore, Record, Subscription};
+use crate::metrics::{ClientStats, Counters};
+use crate::queue::{FramePayload, OverflowPolicy, PushError, QueuedFrame, SendQueue};
+use crate::reload::ReloadableConfig;
+use crate::select::Selector;
+use crate::store::{
+    DataStore, Record, ResumeMissPolicy, Subscription, clear_passed_resume, is_after_cursor,
+    matches_any,
+};
+use crate::throttle::TokenBucket;
 use crate::time::TimeWindow;
 
 /// Per-client connection state.
@@ -19,71 +41,208 @@ enum State {
     Streaming,
 }
 
+/// Capabilities a client can enable with `CAPABILITIES`.
+const SUPPORTED_CAPABILITIES: &[&str] = &[
+    "EXTREPLY",
+    #[cfg(feature = "zstd")]
+    seedlink_rs_protocol::compression::ZSTD_CAPABILITY,
+];
+
+/// `SLPROTO` version string of a protocol version, as advertised in HELLO.
+fn slproto_version(version: ProtocolVersion) -> &'static str {
+    match version {
+        ProtocolVersion::V3 => "3.1",
+        ProtocolVersion::V4 => "4.0",
+    }
+}
+
+/// Capabilities advertised in HELLO.
+fn hello_capabilities(protocols: &[ProtocolVersion]) -> Capabilities {
+    let caps = protocols
+        .iter()
+        .fold(Capabilities::new(), |caps, &version| {
+            caps.with_protocol(slproto_version(version))
+        });
+    SUPPORTED_CAPABILITIES
+        .iter()
+        .fold(caps, |caps, flag| caps.with_flag(flag))
+        .with_flag("BATCH")
+}
+
 /// Server config values needed by the handler.
 pub(crate) struct HandlerConfig {
     pub software: String,
     pub version: String,
-    pub organization: String,
     pub started: String,
+    pub send_queue_capacity: usize,
+    pub overflow_policy: OverflowPolicy,
+    pub write_batch_bytes: usize,
+    /// Bandwidth limit while streaming.
+    pub max_bytes_per_second: Option<u64>,
+    /// `(network, station)` for uni-station clients; `None` means all stations.
+    pub default_station: Option<(String, String)>,
+    pub resume_miss: ResumeMissPolicy,
+    /// Versions advertised in HELLO and accepted by `SLPROTO`.
+    pub supported_protocols: Vec<ProtocolVersion>,
+    pub counters: Arc<Counters>,
+    /// Values changed through [`ConfigHandle`](crate::ConfigHandle); read
+    /// on use so changes apply to this connection.
+    pub runtime: watch::Receiver<ReloadableConfig>,
+    /// Address of the connected client; its IP is checked against the ACL.
+    pub peer: SocketAddr,
+    pub events: broadcast::Sender<ServerEvent>,
 }
 
+/// Read half of a client connection: TCP or any stream given to `run_on()`.
+pub(crate) type ClientReader = Box<dyn AsyncRead + Send + Unpin>;
+/// Write half of a client connection.
+pub(crate) type ClientWriter = Box<dyn AsyncWrite + Send + Unpin>;
+
 /// Per-client connection handler — runs as a spawned tokio task.
 pub(crate) struct ClientHandler {
-    reader: BufReader<OwnedReadHalf>,
-    writer: BufWriter<OwnedWriteHalf>,
+    /// Limited to one command line per read, see `max_command_len`.
+    reader: Take<BufReader<ClientReader>>,
+    writer: BufWriter<ClientWriter>,
     store: DataStore,
     config: HandlerConfig,
     state: State,
     protocol_version: ProtocolVersion,
+    /// Capabilities enabled with `CAPABILITIES` (upper-cased).
+    capabilities: Vec<String>,
+    /// User authenticated with `AUTH USERPASS`.
+    identity: Option<String>,
+    /// Set by `BATCH` (v3): `OK`/`ERROR` replies are no longer sent.
+    batch: bool,
     subscriptions: Vec<Subscription>,
+    /// Set when SELECT/DATA/TIME arrived without STATION (uni-station mode).
+    uni_station: bool,
+    /// Index of the first subscription opened by the last STATION.
+    station_group: usize,
+    /// Set by `FETCH seq` and after a v4 FETCH window. Applies on top of each
+    /// subscription's own `DATA seq`.
     resume_seq: Option<u64>,
-    shutdown_rx: watch::Receiver<bool>,
+    shutdown_rx: watch::Receiver<ShutdownSignal>,
     conn_id: u64,
     connections: ConnectionRegistry,
+    stats: Arc<ClientStats>,
+    /// Notified by [`ConnectionRegistry::kick()`].
+    kick: Arc<Notify>,
+    /// Set once a kick has been received; the connection then closes.
+    kicked: bool,
+    /// Start of the current one-second rate window and commands seen in it.
+    rate_window: (Instant, u32),
+    /// `client` span all of this connection's events are logged in. Fields:
+    /// `conn_id`, `peer`, `version` and `user_agent`.
+    span: Span,
+    /// Set once `version` has been recorded on `span`.
+    version_recorded: bool,
+    /// Why the connection is closing, reported in `ClientDisconnected`. The
+    /// first cause noted wins; `None` at the end means the client left.
+    disconnect: Option<DisconnectReason>,
 }
 
 impl ClientHandler {
     pub fn new(
-        read_half: OwnedReadHalf,
-        write_half: OwnedWriteHalf,
+        read_half: ClientReader,
+        write_half: ClientWriter,
         store: DataStore,
         config: HandlerConfig,
-        shutdown_rx: watch::Receiver<bool>,
+        shutdown_rx: watch::Receiver<ShutdownSignal>,
         conn_id: u64,
         connections: ConnectionRegistry,
     ) -> Self {
+        let stats = Arc::new(ClientStats::default());
+        let kick = Arc::new(Notify::new());
+        connections.update(conn_id, |info| {
+            info.stats = stats.clone();
+            info.kick = kick.clone();
+        });
+        let span = info_span!(
+            "client",
+            conn_id,
+            peer = %config.peer,
+            version = field::Empty,
+            user_agent = field::Empty,
+        );
         Self {
-            reader: BufReader::new(read_half),
+            reader: BufReader::new(read_half).take(0),
             writer: BufWriter::new(write_half),
             store,
             config,
             state: State::Connected,
             protocol_version: ProtocolVersion::V3,
+            capabilities: Vec::new(),
+            identity: None,
+            batch: false,
             subscriptions: Vec::new(),
+            uni_station: false,
+            station_group: 0,
             resume_seq: None,
             shutdown_rx,
             conn_id,
             connections,
+            stats,
+            kick,
+            kicked: false,
+            rate_window: (Instant::now(), 0),
+            span,
+            version_recorded: false,
+            disconnect: None,
         }
     }
 
-    /// Main loop: read commands, handle them, stream when END/FETCH is received.
-    pub async fn run(mut self) {
+    /// Run the connection inside its `client` span.
+    pub async fn run(self) {
+        let span = self.span.clone();
+        self.serve().instrument(span).await;
+    }
+
+    /// Main loop: read commands, handle them, stream when END/FETCH/ENDFETCH is received.
+    async fn serve(mut self) {
         info!("client connected");
         let mut line = String::new();
 
         loop {
             line.clear();
+            let (max_command_len, command_timeout) = {
+                let runtime = self.config.runtime.borrow();
+                (runtime.max_command_len, runtime.command_timeout)
+            };
+            // Room for the longest accepted command plus "\r\n"; a client
+            // sending an endless line is cut off here instead of buffered
+            self.reader.set_limit(max_command_len as u64 + 2);
 
             let n = tokio::select! {
                 result = self.reader.read_line(&mut line) => {
                     match result {
                         Ok(n) => n,
-                        Err(_) => break,
+                        Err(e) => {
+                            self.note_disconnect(DisconnectReason::from_io(&e));
+                            break;
+                        }
                     }
                 }
                 _ = self.shutdown_rx.changed() => {
                     debug!("shutdown received during command loop");
+                    self.note_disconnect(DisconnectReason::Shutdown);
+                    break;
+                }
+                _ = self.kick.notified() => {
+                    info!("client kicked");
+                    self.note_disconnect(DisconnectReason::Kicked);
+                    break;
+                }
+                _ = tokio::time::sleep(command_timeout.unwrap_or_default()),
+                    if command_timeout.is_some() =>
+                {
+                    warn!("no command received in time, closing connection");
+                    self.config.counters.clients_timed_out.fetch_add(1, Ordering::Relaxed);
+                    self.note_disconnect(DisconnectReason::TimedOut);
+                    let resp = Response::Error {
+                        code: Some(seedlink_rs_protocol::response::ErrorCode::Limit),
+                        description: "idle timeout".to_owned(),
+                    };
+                    let _ = self.send_response(&resp).await;
                     break;
                 }
             };
@@ -92,18 +251,68 @@ impl ClientHandler {
                 break; // client disconnected
             }
 
+            if line.trim_end_matches(['\r', '\n']).len() > max_command_len {
+                warn!(
+                    max = max_command_len,
+                    "command line too long, closing connection"
+                );
+                self.config
+                    .counters
+                    .clients_limited
+                    .fetch_add(1, Ordering::Relaxed);
+                self.note_disconnect(DisconnectReason::Limited);
+                let resp = Response::Error {
+                    code: Some(seedlink_rs_protocol::response::ErrorCode::Arguments),
+                    description: "command line too long".to_owned(),
+                };
+                let _ = self.send_response(&resp).await;
+                break;
+            }
+
             let trimmed = line.trim();
             if trimmed.is_empty() {
                 continue;
             }
 
-            match Command::parse(trimmed) {
+            if !self.within_command_rate() {
+                warn!("too many commands, closing connection");
+                self.config
+                    .counters
+                    .clients_limited
+                    .fetch_add(1, Ordering::Relaxed);
+                self.note_disconnect(DisconnectReason::Limited);
+                let resp = Response::Error {
+                    code: Some(seedlink_rs_protocol::response::ErrorCode::Limit),
+                    description: "too many commands".to_owned(),
+                };
+                let _ = self.send_response(&resp).await;
+                break;
+            }
+
+            match Command::parse_for(trimmed, self.protocol_version) {
                 Ok(cmd) => {
                     debug!(command = %cmd_name(&cmd), "received command");
+                    // SLPROTO comes right after HELLO (and CAPABILITIES); any
+                    // other command settles the version
+                    if !matches!(
+                        cmd,
+                        Command::Hello | Command::Capabilities { .. } | Command::SlProto { .. }
+                    ) {
+                        self.record_version();
+                    }
                     if !self.handle_command(cmd).await {
                         break;
                     }
                 }
+                Err(SeedlinkError::InvalidTime(time)) => {
+                    let resp = Response::Error {
+                        code: Some(seedlink_rs_protocol::response::ErrorCode::Arguments),
+                        description: format!("invalid time: {time}"),
+                    };
+                    if self.send_response(&resp).await.is_err() {
+                        break;
+                    }
+                }
                 Err(_) => {
                     let keyword = trimmed.split_whitespace().next().unwrap_or(trimmed);
                     let resp = Response::Error {
@@ -117,45 +326,154 @@ impl ClientHandler {
             }
         }
 
-        self.connections.unregister(self.conn_id);
-        info!("client disconnected");
+        let reason = self.disconnect.take().unwrap_or(DisconnectReason::Closed);
+        self.connections.unregister(self.conn_id, reason.clone());
+        info!(?reason, "client disconnected");
+        self.publish(ServerEvent::ClientDisconnected {
+            id: self.conn_id,
+            addr: self.config.peer,
+            reason,
+        });
+    }
+
+    /// Note why the connection is about to close, unless a cause is already known.
+    fn note_disconnect(&mut self, reason: DisconnectReason) {
+        self.disconnect.get_or_insert(reason);
+    }
+
+    /// Publish on the server event bus; no receivers is fine.
+    fn publish(&self, event: ServerEvent) {
+        let _ = self.config.events.send(event);
+    }
+
+    /// Record the negotiated protocol version on the connection span, once.
+    fn record_version(&mut self) {
+        if !self.version_recorded {
+            self.span
+                .record("version", field::debug(self.protocol_version));
+            self.version_recorded = true;
+        }
+    }
+
+    /// Whether `cmd` must wait for `SLPROTO 4.0`: the server does not speak
+    /// v3 and the client has not switched to v4 yet.
+    fn requires_slproto(&self, cmd: &Command) -> bool {
+        self.protocol_version == ProtocolVersion::V3
+            && !self
+                .config
+                .supported_protocols
+                .contains(&ProtocolVersion::V3)
+            && !matches!(
+                cmd,
+                Command::Hello
+                    | Command::SlProto { .. }
+                    | Command::UserAgent { .. }
+                    | Command::Auth { .. }
+                    | Command::Bye
+            )
+    }
+
+    /// Count a command against `max_commands_per_second`. Returns `false`
+    /// once the limit is exceeded within the current one-second window.
+    fn within_command_rate(&mut self) -> bool {
+        let Some(max) = self.config.runtime.borrow().max_commands_per_second else {
+            return true;
+        };
+        let now = Instant::now();
+        if now.duration_since(self.rate_window.0) >= Duration::from_secs(1) {
+            self.rate_window = (now, 0);
+        }
+        self.rate_window.1 += 1;
+        self.rate_window.1 <= max
     }
 
     /// Handle a parsed command. Returns `false` if connection should close.
     async fn handle_command(&mut self, cmd: Command) -> bool {
+        if self.requires_slproto(&cmd) {
+            let resp = Response::Error {
+                code: Some(seedlink_rs_protocol::response::ErrorCode::Unsupported),
+                description: "SLPROTO 4.0 required".to_owned(),
+            };
+            return self.send_response(&resp).await.is_ok();
+        }
         match cmd {
             Command::Hello => {
                 let resp = Response::Hello {
                     software: self.config.software.clone(),
                     version: self.config.version.clone(),
-                    extra: ":: SLPROTO:4.0 SLPROTO:3.1".to_owned(),
-                    organization: self.config.organization.clone(),
+                    extra: format!(
+                        ":: {}",
+                        hello_capabilities(&self.config.supported_protocols)
+                    ),
+                    organization: self.config.runtime.borrow().organization.clone(),
                 };
                 self.send_response(&resp).await.is_ok()
             }
             Command::SlProto { version } => {
-                if version == "4.0" {
-                    self.protocol_version = ProtocolVersion::V4;
-                    self.connections.update(self.conn_id, |info| {
-                        info.protocol_version = ProtocolVersion::V4;
-                    });
-                    debug!("negotiated v4");
-                    self.send_response(&Response::Ok).await.is_ok()
-                } else {
-                    let resp = Response::Error {
-                        code: Some(seedlink_rs_protocol::response::ErrorCode::Unsupported),
-                        description: format!("unsupported protocol version: {version}"),
-                    };
-                    self.send_response(&resp).await.is_ok()
+                let requested = match version.split('.').next() {
+                    Some("3") => Some(ProtocolVersion::V3),
+                    Some("4") => Some(ProtocolVersion::V4),
+                    _ => None,
+                };
+                match requested.filter(|v| self.config.supported_protocols.contains(v)) {
+                    Some(negotiated) => {
+                        self.protocol_version = negotiated;
+                        self.connections.update(self.conn_id, |info| {
+                            info.protocol_version = negotiated;
+                        });
+                        self.record_version();
+                        debug!(?negotiated, "negotiated protocol version");
+                        self.send_response(&Response::Ok).await.is_ok()
+                    }
+                    None => {
+                        let resp = Response::Error {
+                            code: Some(seedlink_rs_protocol::response::ErrorCode::Unsupported),
+                            description: format!("unsupported protocol version: {version}"),
+                        };
+                        self.send_response(&resp).await.is_ok()
+                    }
                 }
             }
             Command::Station { station, network } => {
-                self.subscriptions.push(Subscription {
-                    network,
-                    station,
-                    select_patterns: Vec::new(),
-                    time_window: None,
+                let Some(stations) = self.station_list(&network, &station) else {
+                    let resp = Response::Error {
+                        code: Some(seedlink_rs_protocol::response::ErrorCode::Arguments),
+                        description: format!("invalid station list: {network}_{station}"),
+                    };
+                    return self.send_response(&resp).await.is_ok();
+                };
+                // Patterns are checked per record while streaming instead
+                let denied = stations.iter().find(|(net, sta)| {
+                    !is_pattern(net) && !is_pattern(sta) && !self.may_access(net, sta)
                 });
+                if let Some((network, station)) = denied {
+                    debug!(%network, %station, "STATION denied by ACL");
+                    let resp = Response::Error {
+                        code: Some(seedlink_rs_protocol::response::ErrorCode::Unauthorized),
+                        description: format!("access to {network}_{station} denied"),
+                    };
+                    return self.send_response(&resp).await.is_ok();
+                }
+                if self.uni_station {
+                    // Multi-station mode replaces the implicit subscription
+                    self.uni_station = false;
+                    self.subscriptions.clear();
+                }
+                self.station_group = self.subscriptions.len();
+                for (network, station) in stations {
+                    self.publish(ServerEvent::SubscriptionAdded {
+                        id: self.conn_id,
+                        network: network.clone(),
+                        station: station.clone(),
+                    });
+                    self.subscriptions.push(Subscription {
+                        network,
+                        station,
+                        select_patterns: Vec::new(),
+                        time_window: None,
+                        resume_seq: None,
+                    });
+                }
                 self.state = State::Configured;
                 self.connections.update(self.conn_id, |info| {
                     info.state = "Configured".to_owned();
@@ -163,33 +481,89 @@ impl ClientHandler {
                 self.send_response(&Response::Ok).await.is_ok()
             }
             Command::Select { pattern } => {
-                if let Some(sub) = self.subscriptions.last_mut() {
-                    if let Some(pat) = SelectPattern::parse(&pattern) {
-                        sub.select_patterns.push(pat);
-                        self.send_response(&Response::Ok).await.is_ok()
-                    } else {
-                        let resp = Response::Error {
-                            code: Some(seedlink_rs_protocol::response::ErrorCode::Unsupported),
-                            description: format!("invalid SELECT pattern: {pattern}"),
-                        };
-                        self.send_response(&resp).await.is_ok()
+                if let Some(pat) = Selector::parse(self.protocol_version, &pattern) {
+                    for sub in self.current_subscriptions() {
+                        sub.select_patterns.push(pat.clone());
                     }
+                    self.send_response(&Response::Ok).await.is_ok()
                 } else {
                     let resp = Response::Error {
                         code: Some(seedlink_rs_protocol::response::ErrorCode::Unsupported),
-                        description: "SELECT requires prior STATION".to_owned(),
+                        description: format!("invalid SELECT pattern: {pattern}"),
                     };
                     self.send_response(&resp).await.is_ok()
                 }
             }
-            Command::Data { sequence, .. } => {
-                if let Some(seq) = sequence {
-                    self.resume_seq = Some(seq.value());
+            Command::Data {
+                sequence,
+                start,
+                end,
+            } => {
+                // Time-based subscription: DATA seq|ALL start [end]
+                let time_window = start.map(|start| TimeWindow::new(start, end));
+                // `-1`: only records stored from now on
+                let newest = self.store.last_sequence().map(|s| s.value());
+                let store = self.store.clone();
+                let missed: Vec<bool> = match sequence.filter(|s| !s.is_special()) {
+                    Some(seq) => self
+                        .current_subscriptions()
+                        .iter()
+                        .map(|sub| store.resume_missed(sub, seq.value()))
+                        .collect(),
+                    None => Vec::new(),
+                };
+                let fallback = time_window.is_some();
+                if let Some(seq) = sequence.filter(|_| missed.contains(&true)) {
+                    let stations: Vec<_> = self
+                        .current_subscriptions()
+                        .iter()
+                        .zip(&missed)
+                        .filter(|(_, missed)| **missed)
+                        .map(|(sub, _)| (sub.network.clone(), sub.station.clone()))
+                        .collect();
+                    if !self.report_resume_misses(&stations, seq, fallback) {
+                        return self.reject_resume(&stations, seq).await;
+                    }
+                }
+                for (i, sub) in self.current_subscriptions().iter_mut().enumerate() {
+                    if time_window.is_some() {
+                        sub.time_window = time_window.clone();
+                    }
+                    // Each station resumes from its own DATA sequence
+                    match sequence {
+                        Some(SequenceNumber::NEWEST) => sub.resume_seq = newest,
+                        // Past evicted records the start time applies instead
+                        Some(_) if fallback && missed.get(i) == Some(&true) => {
+                            sub.resume_seq = None;
+                        }
+                        Some(seq) if !seq.is_special() => sub.resume_seq = Some(seq.value()),
+                        _ => {}
+                    }
                 }
                 self.send_response(&Response::Ok).await.is_ok()
             }
             Command::Fetch { sequence } => {
-                if let Some(seq) = sequence {
+                if let Some(seq) = sequence.filter(|s| !s.is_special()) {
+                    let implicit;
+                    let subscriptions = if self.subscriptions.is_empty() {
+                        let (network, station) = self
+                            .config
+                            .default_station
+                            .clone()
+                            .unwrap_or_else(|| ("*".to_owned(), "*".to_owned()));
+                        implicit = [Subscription::new(&network, &station)];
+                        &implicit[..]
+                    } else {
+                        &self.subscriptions[..]
+                    };
+                    let stations: Vec<_> = subscriptions
+                        .iter()
+                        .filter(|sub| self.store.resume_missed(sub, seq.value()))
+                        .map(|sub| (sub.network.clone(), sub.station.clone()))
+                        .collect();
+                    if !stations.is_empty() && !self.report_resume_misses(&stations, seq, false) {
+                        return self.reject_resume(&stations, seq).await;
+                    }
                     self.resume_seq = Some(seq.value());
                 }
                 // No response for FETCH — binary streaming starts immediately
@@ -197,28 +571,31 @@ impl ClientHandler {
                 self.connections.update(self.conn_id, |info| {
                     info.state = "Streaming".to_owned();
                 });
-                self.stream_frames(false).await;
-                false // streaming ended, close connection
+                let stopped = self.stream_frames(false).await;
+                // v4 dial-up: mark the end of the fetch window and keep the
+                // connection open for further commands
+                if self.protocol_version == ProtocolVersion::V4 && self.end_fetch_window().await {
+                    return true;
+                }
+                // v3: streaming ended, close connection
+                self.note_disconnect(stopped);
+                false
+            }
+            Command::EndFetch => {
+                // Final dial-up fetch: send buffered data, then close
+                self.state = State::Streaming;
+                self.connections.update(self.conn_id, |info| {
+                    info.state = "Streaming".to_owned();
+                });
+                let stopped = self.stream_frames(false).await;
+                self.note_disconnect(stopped);
+                false
             }
             Command::Time { start, end } => {
-                if let Some(sub) = self.subscriptions.last_mut() {
-                    if let Some(tw) = TimeWindow::parse(&start, end.as_deref()) {
-                        sub.time_window = Some(tw);
-                        self.send_response(&Response::Ok).await.is_ok()
-                    } else {
-                        let resp = Response::Error {
-                            code: Some(seedlink_rs_protocol::response::ErrorCode::Unsupported),
-                            description: format!("invalid TIME format: {start}"),
-                        };
-                        self.send_response(&resp).await.is_ok()
-                    }
-                } else {
-                    let resp = Response::Error {
-                        code: Some(seedlink_rs_protocol::response::ErrorCode::Unsupported),
-                        description: "TIME requires prior STATION".to_owned(),
-                    };
-                    self.send_response(&resp).await.is_ok()
+                for sub in self.current_subscriptions() {
+                    sub.time_window = Some(TimeWindow::new(start, end));
                 }
+                self.send_response(&Response::Ok).await.is_ok()
             }
             Command::End => {
                 // No response for END — binary streaming starts immediately
@@ -226,23 +603,50 @@ impl ClientHandler {
                 self.connections.update(self.conn_id, |info| {
                     info.state = "Streaming".to_owned();
                 });
-                self.stream_frames(true).await;
+                let stopped = self.stream_frames(true).await;
+                self.note_disconnect(stopped);
                 false // streaming ended, close connection
             }
             Command::Bye => false,
             Command::Info { level } => self.handle_info(level).await,
             Command::UserAgent { description } => {
+                self.span.record("user_agent", description.as_str());
                 self.connections.update(self.conn_id, |info| {
                     info.user_agent = Some(description.clone());
                 });
                 self.send_response(&Response::Ok).await.is_ok()
             }
-            Command::Batch => {
-                // Our handler already accumulates STATION+SELECT+DATA before END.
-                // BATCH mode just suppresses per-command responses, but for simplicity
-                // we acknowledge it.
+            Command::Capabilities { capabilities } => {
+                for cap in capabilities {
+                    let cap = cap.to_uppercase();
+                    if SUPPORTED_CAPABILITIES.contains(&cap.as_str()) {
+                        if !self.capabilities.contains(&cap) {
+                            debug!(capability = %cap, "capability enabled");
+                            self.capabilities.push(cap);
+                        }
+                    } else {
+                        debug!(capability = %cap, "ignoring unsupported capability");
+                    }
+                }
                 self.send_response(&Response::Ok).await.is_ok()
             }
+            Command::Auth { value } => self.handle_auth(&value).await,
+            Command::Batch => {
+                if self.protocol_version == ProtocolVersion::V4 {
+                    let resp = Response::Error {
+                        code: Some(seedlink_rs_protocol::response::ErrorCode::Unsupported),
+                        description: "BATCH is not supported in v4".to_owned(),
+                    };
+                    return self.send_response(&resp).await.is_ok();
+                }
+                // BATCH itself is acknowledged; later commands are not
+                if self.send_response(&Response::Ok).await.is_err() {
+                    return false;
+                }
+                debug!("batch mode enabled");
+                self.batch = true;
+                true
+            }
             _ => {
                 let resp = Response::Error {
                     code: Some(seedlink_rs_protocol::response::ErrorCode::Unsupported),
@@ -253,68 +657,439 @@ impl ClientHandler {
         }
     }
 
-    /// Build a frame for the current protocol version.
-    fn build_frame(&self, record: &Record) -> Result<Vec<u8>, seedlink_rs_protocol::SeedlinkError> {
-        match self.protocol_version {
-            ProtocolVersion::V3 => v3::write(record.sequence, &record.payload),
-            ProtocolVersion::V4 => {
-                let station_id = format!("{}_{}", record.network, record.station);
-                v4::write(
-                    PayloadFormat::MiniSeed2,
-                    PayloadSubformat::Data,
-                    record.sequence,
-                    &station_id,
-                    &record.payload,
-                )
-            }
-        }
-    }
-
     /// Stream frames to client.
     ///
     /// If `continuous` is true (END), loops forever waiting for new data.
-    /// If `continuous` is false (FETCH), sends current buffer then returns.
-    async fn stream_frames(&mut self, continuous: bool) {
+    /// If `continuous` is false (FETCH/ENDFETCH), sends current buffer then returns.
+    /// In either mode, if every subscription has a TIME window with an end,
+    /// the stream ends once each station has produced data past its end time.
+    ///
+    /// Records are read from the store into a bounded [`SendQueue`] and written
+    /// to the socket concurrently, so a slow client never stalls the store read.
+    /// A full queue is handled according to the configured [`OverflowPolicy`].
+    /// Queued frames are coalesced into vectored writes of up to
+    /// `write_batch_bytes`; record payloads are written from the store's
+    /// buffers and frame header buffers are reused, see [`FrameEncoder`].
+    ///
+    /// On graceful shutdown, records already in the ring are still sent, followed
+    /// by `END` on v3, until the drain deadline closes the connection.
+    ///
+    /// Returns why streaming stopped.
+    async fn stream_frames(&mut self, continuous: bool) -> DisconnectReason {
+        self.publish(ServerEvent::StreamStarted {
+            id: self.conn_id,
+            version: self.protocol_version,
+            fetch: !continuous,
+        });
+        let queue = SendQueue::new(self.config.send_queue_capacity, self.config.overflow_policy);
+        self.connections.update(self.conn_id, |info| {
+            info.queue = Some(queue.clone());
+        });
+
+        let mut encoder = FrameEncoder::new(self.protocol_version, self.compress_v4());
+        let store = &self.store;
+        // Mutable so each `DATA seq` cursor can be dropped once passed
+        let subscriptions = &mut self.subscriptions;
+        let mut drain_rx = self.shutdown_rx.clone();
+        let shutdown_rx = &mut self.shutdown_rx;
+        // Set on graceful shutdown: remaining records are flushed, then END
+        let draining = AtomicBool::new(false);
+        let writer = &mut self.writer;
+        let (stats, counters) = (&self.stats, &self.config.counters);
+        let mut bucket = self.config.max_bytes_per_second.map(TokenBucket::new);
+        // A throttled client gets smaller writes, so each wait stays short
+        let batch_bytes = match &bucket {
+            Some(bucket) => self.config.write_batch_bytes.min(bucket.burst()),
+            None => self.config.write_batch_bytes,
+        };
+        // Read on use, so config changes reach clients already streaming
+        let runtime = &self.config.runtime;
+        let mut runtime_changes = runtime.clone();
+        let idle_timeout = || runtime.borrow().stream_idle_timeout;
+        let version = self.protocol_version;
+        // Pattern and uni-station subscriptions are not checked at STATION
+        let (peer, identity) = (self.config.peer.ip(), self.identity.as_deref());
+        let allowed = |r: &Record| {
+            runtime
+                .borrow()
+                .acl
+                .is_allowed(peer, identity, &r.network, &r.station)
+        };
         let mut cursor = self.resume_seq.unwrap_or(0);
+        // `from`/`to`: sequence position when streaming starts and stops
+        let span = info_span!("stream", from = cursor, to = field::Empty);
+        let kick = self.kick.clone();
+        let mut kicked = false;
+        let stop = StopReason::default();
 
-        loop {
-            // Capture notified BEFORE read to avoid race condition
-            let notified = self.store.notified();
-
-            let records = self.store.read_since(cursor, &self.subscriptions);
-            if !records.is_empty() {
-                for r in &records {
-                    let frame = match self.build_frame(r) {
-                        Ok(f) => f,
-                        Err(_) => return,
-                    };
-                    if self.writer.write_all(&frame).await.is_err() {
-                        return;
+        let produce = async {
+            // Subscribe BEFORE the ring scan so no push falls between the two
+            let mut live = store.subscribe_live();
+            let mut catch_up = true;
+
+            // When every subscription has a TIME end, the stream terminates once
+            // data past the end has been seen for all of them.
+            let bounded = !subscriptions.is_empty() && subscriptions.iter().all(|s| s.is_bounded());
+            let mut open_windows: Vec<usize> = if bounded {
+                (0..subscriptions.len()).collect()
+            } else {
+                Vec::new()
+            };
+            // Time of the last frame queued, for keepalives
+            let mut last_sent = Instant::now();
+
+            loop {
+                if catch_up {
+                    for r in store
+                        .read_since(cursor, subscriptions)
+                        .iter()
+                        .filter(|r| allowed(r))
+                    {
+                        if !enqueue(&queue, &stop, &mut encoder, r).await {
+                            return;
+                        }
+                        cursor = r.sequence.value();
+                        last_sent = Instant::now();
+                    }
+                    catch_up = false;
+                    clear_passed_resume(subscriptions, cursor);
+
+                    if draining.load(Ordering::Relaxed) {
+                        stop.set(DisconnectReason::Shutdown);
+                        return queue.close();
+                    }
+
+                    if bounded {
+                        open_windows.retain(|&i| !store.any(|r| subscriptions[i].is_past_end(r)));
+                        if open_windows.is_empty() {
+                            debug!("all TIME windows complete, ending stream");
+                            stop.set(DisconnectReason::StreamEnded);
+                            return queue.close();
+                        }
+                    }
+
+                    if !continuous {
+                        // FETCH mode: done, let the writer drain and close
+                        stop.set(DisconnectReason::StreamEnded);
+                        return queue.close();
                     }
-                    trace!(sequence = %r.sequence, "frame sent");
-                    cursor = r.sequence.value();
                 }
-                if self.writer.flush().await.is_err() {
-                    return;
+
+                // Continuous mode (END): wait for new data, shutdown, or writer failure
+                let keepalive_interval = runtime_changes
+                    .borrow_and_update()
+                    .keepalive_interval
+                    .filter(|d| !d.is_zero());
+                let received = tokio::select! {
+                    r = live.recv() => r,
+                    _ = shutdown_rx.changed() => {
+                        if let ShutdownSignal::Graceful { .. } = *shutdown_rx.borrow() {
+                            debug!("graceful shutdown received, draining remaining records");
+                            draining.store(true, Ordering::Relaxed);
+                            catch_up = true;
+                            continue;
+                        }
+                        debug!("shutdown received during streaming");
+                        stop.set(DisconnectReason::Shutdown);
+                        return queue.abort();
+                    }
+                    _ = queue.closed() => return,
+                    _ = tokio::time::sleep_until(last_sent + keepalive_interval.unwrap_or_default()),
+                        if keepalive_interval.is_some() =>
+                    {
+                        trace!("stream idle, sending keepalive");
+                        if !push_frame(&queue, &stop, encoder.keepalive()).await {
+                            return;
+                        }
+                        last_sent = Instant::now();
+                        continue;
+                    }
+                    // Pick up a changed keepalive interval
+                    Ok(()) = runtime_changes.changed() => continue,
+                };
+
+                match received {
+                    Ok(r) => {
+                        // Already delivered by the catch-up scan
+                        if !is_after_cursor(r.sequence, cursor) {
+                            continue;
+                        }
+                        if matches_any(subscriptions, &r) && allowed(&r) {
+                            if !enqueue(&queue, &stop, &mut encoder, &r).await {
+                                return;
+                            }
+                            last_sent = Instant::now();
+                        }
+                        cursor = r.sequence.value();
+                        clear_passed_resume(subscriptions, cursor);
+
+                        if bounded {
+                            open_windows.retain(|&i| !subscriptions[i].is_past_end(&r));
+                            if open_windows.is_empty() {
+                                debug!("all TIME windows complete, ending stream");
+                                stop.set(DisconnectReason::StreamEnded);
+                                return queue.close();
+                            }
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        debug!(skipped, "live feed lagged, catching up from ring");
+                        catch_up = true;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => {
+                        stop.set(DisconnectReason::StreamEnded);
+                        return queue.close();
+                    }
                 }
-                continue;
             }
+        };
+
+        let write = async {
+            let mut batch = Vec::new();
+            // Frames copied into one buffer, for writers without vectored IO
+            let mut flat = Vec::new();
+            while let Some(bytes) = queue.pop_batch(batch_bytes, &mut batch).await {
+                if let Some(bucket) = &mut bucket {
+                    let wait = bucket.reserve(bytes);
+                    stats.throttled.store(!wait.is_zero(), Ordering::Relaxed);
+                    if !wait.is_zero() {
+                        trace!(?wait, "throttling");
+                        tokio::time::sleep(wait).await;
+                    }
+                }
+                let written = write_frames(&mut *writer, &batch, &mut flat);
+                let written = match idle_timeout() {
+                    Some(limit) => match tokio::time::timeout(limit, written).await {
+                        Ok(result) => result,
+                        Err(_) => {
+                            warn!("client stopped reading, closing connection");
+                            counters.clients_timed_out.fetch_add(1, Ordering::Relaxed);
+                            stop.set(DisconnectReason::SlowConsumer);
+                            return queue.abort();
+                        }
+                    },
+                    None => written.await,
+                };
+                if let Err(e) = written {
+                    let reason = DisconnectReason::from_io(&e);
+                    warn!(error = %e, reason = reason.as_str(), "write to client failed");
+                    stop.set(reason);
+                    return queue.abort();
+                }
+                let frames = batch.len();
+                stats.record_frames(counters, frames, bytes);
+                trace!(frames, bytes, "frames sent");
+                queue.recycle(&mut batch);
+            }
+            if draining.load(Ordering::Relaxed) && version == ProtocolVersion::V3 {
+                let _ = writer.write_all(b"END\r\n").await;
+            }
+            let _ = writer.flush().await;
+        };
 
-            // No more buffered data
-            if !continuous {
-                // FETCH mode: done, let connection close
-                return;
+        // On graceful shutdown, give up on the client once the drain deadline passes
+        let drain_deadline = async {
+            loop {
+                let signal = *drain_rx.borrow_and_update();
+                if let ShutdownSignal::Graceful { deadline } = signal {
+                    return tokio::time::sleep_until(deadline).await;
+                }
+                if drain_rx.changed().await.is_err() {
+                    return std::future::pending().await;
+                }
             }
+        };
 
-            // Continuous mode (END): wait for new data or shutdown
+        async {
             tokio::select! {
-                _ = notified => {}
-                _ = self.shutdown_rx.changed() => {
-                    debug!("shutdown received during streaming");
-                    return;
+                _ = async { tokio::join!(produce, write) } => {}
+                _ = drain_deadline => {
+                    debug!("drain timeout reached, closing connection");
+                    stop.set(DisconnectReason::Shutdown);
+                }
+                _ = kick.notified() => {
+                    info!("client kicked while streaming");
+                    kicked = true;
                 }
             }
         }
+        .instrument(span.clone())
+        .await;
+        self.kicked = kicked;
+        span.record("to", cursor);
+        span.in_scope(|| debug!("streaming stopped"));
+
+        // A later v4 FETCH continues after the last record sent
+        self.resume_seq = Some(cursor);
+        if kicked {
+            return DisconnectReason::Kicked;
+        }
+        stop.0
+            .into_inner()
+            .unwrap()
+            .unwrap_or(DisconnectReason::StreamEnded)
+    }
+
+    /// Finish a v4 dial-up FETCH: send `END` and return to `Configured`.
+    ///
+    /// The next FETCH resumes after the last record sent. Returns `false` if
+    /// the connection should close (shutdown, kick or write failure).
+    async fn end_fetch_window(&mut self) -> bool {
+        if self.kicked || *self.shutdown_rx.borrow() != ShutdownSignal::Running {
+            return false;
+        }
+        let written = async {
+            self.writer.write_all(b"END\r\n").await?;
+            self.writer.flush().await
+        };
+        if let Err(e) = written.await {
+            self.note_disconnect(DisconnectReason::from_io(&e));
+            return false;
+        }
+        self.state = State::Configured;
+        self.connections.update(self.conn_id, |info| {
+            info.state = "Configured".to_owned();
+        });
+        true
+    }
+
+    /// The subscriptions SELECT/DATA/TIME apply to: those opened by the last
+    /// STATION (several for a v4 station list). Without STATION (uni-station
+    /// mode) one is opened for the configured default station, or for all
+    /// stations.
+    fn current_subscriptions(&mut self) -> &mut [Subscription] {
+        if self.subscriptions.is_empty() {
+            let (network, station) = self
+                .config
+                .default_station
+                .clone()
+                .unwrap_or_else(|| ("*".to_owned(), "*".to_owned()));
+            debug!(%network, %station, "uni-station mode");
+            self.publish(ServerEvent::SubscriptionAdded {
+                id: self.conn_id,
+                network: network.clone(),
+                station: station.clone(),
+            });
+            self.subscriptions
+                .push(Subscription::new(&network, &station));
+            self.station_group = 0;
+            self.uni_station = true;
+            self.state = State::Configured;
+            self.connections.update(self.conn_id, |info| {
+                info.state = "Configured".to_owned();
+            });
+        }
+        &mut self.subscriptions[self.station_group..]
+    }
+
+    /// Count, log and publish resumes from `seq` that missed evicted records
+    /// of `stations`. Returns `false` if the resume is to be rejected.
+    fn report_resume_misses(
+        &self,
+        stations: &[(String, String)],
+        seq: SequenceNumber,
+        fallback: bool,
+    ) -> bool {
+        self.config
+            .counters
+            .resume_misses
+            .fetch_add(stations.len() as u64, Ordering::Relaxed);
+        for (network, station) in stations {
+            info!(%network, %station, sequence = %seq, fallback, "resume sequence expired");
+            self.publish(ServerEvent::ResumeMissed {
+                id: self.conn_id,
+                network: network.clone(),
+                station: station.clone(),
+                sequence: seq,
+                fallback,
+            });
+        }
+        fallback || self.config.resume_miss == ResumeMissPolicy::Oldest
+    }
+
+    /// Reply `ERROR ARGUMENTS` to a resume from an expired sequence,
+    /// leaving the subscriptions unchanged.
+    async fn reject_resume(&mut self, stations: &[(String, String)], seq: SequenceNumber) -> bool {
+        let names: Vec<_> = stations
+            .iter()
+            .map(|(network, station)| format!("{network}_{station}"))
+            .collect();
+        let resp = Response::Error {
+            code: Some(seedlink_rs_protocol::response::ErrorCode::Arguments),
+            description: format!("sequence {seq} expired for {}", names.join(",")),
+        };
+        self.send_response(&resp).await.is_ok()
+    }
+
+    /// The `(network, station)` patterns of a STATION command. On v4 the
+    /// `NET_STA` token may be a comma-separated list (`IU_ANMO,GE_*`), which
+    /// the parser has split at its first `_`; `None` if an element is not
+    /// `NET_STA`.
+    fn station_list(&self, network: &str, station: &str) -> Option<Vec<(String, String)>> {
+        if self.protocol_version == ProtocolVersion::V3 || !station.contains(',') {
+            return Some(vec![(network.to_owned(), station.to_owned())]);
+        }
+        format!("{network}_{station}")
+            .split(',')
+            .map(|id| {
+                let (net, sta) = id.split_once('_')?;
+                let valid = !net.is_empty() && !sta.is_empty() && !sta.contains('_');
+                valid.then(|| (net.to_owned(), sta.to_owned()))
+            })
+            .collect()
+    }
+
+    /// Whether v4 payloads are zstd-compressed for this client.
+    fn compress_v4(&self) -> bool {
+        self.protocol_version == ProtocolVersion::V4
+            && self.capabilities.iter().any(|c| c == "ZSTD")
+    }
+
+    /// Whether the ACL lets this client access `network`/`station`.
+    fn may_access(&self, network: &str, station: &str) -> bool {
+        self.config.runtime.borrow().acl.is_allowed(
+            self.config.peer.ip(),
+            self.identity.as_deref(),
+            network,
+            station,
+        )
+    }
+
+    /// Handle `AUTH USERPASS user password` against the ACL's users.
+    async fn handle_auth(&mut self, value: &str) -> bool {
+        use seedlink_rs_protocol::response::ErrorCode;
+
+        let mut parts = value.split_whitespace();
+        let method = parts.next().unwrap_or_default();
+        let resp = if !method.eq_ignore_ascii_case("USERPASS") {
+            Response::Error {
+                code: Some(ErrorCode::Unsupported),
+                description: format!("unsupported AUTH method: {method}"),
+            }
+        } else {
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some(user), Some(password), None)
+                    if self
+                        .config
+                        .runtime
+                        .borrow()
+                        .acl
+                        .authenticate(user, password) =>
+                {
+                    info!(user, "client authenticated");
+                    self.identity = Some(user.to_owned());
+                    Response::Ok
+                }
+                _ => {
+                    warn!("AUTH failed");
+                    Response::Error {
+                        code: Some(ErrorCode::Auth),
+                        description: "authentication failed".to_owned(),
+                    }
+                }
+            }
+        };
+        self.send_response(&resp).await.is_ok()
     }
 
     /// Handle INFO command — build XML, send as frame(s), then END.
@@ -324,21 +1099,23 @@ impl ClientHandler {
                 let software = format!("{} {}", self.config.software, self.config.version);
                 info_xml::build_info_id_xml(
                     &software,
-                    &self.config.organization,
+                    &self.config.runtime.borrow().organization,
                     &self.config.started,
                 )
             }
             InfoLevel::Stations => {
-                let stations = self.store.station_info();
+                let mut stations = self.store.station_info();
+                stations.retain(|s| self.may_access(&s.network, &s.station));
                 info_xml::build_info_stations_xml(&stations)
             }
             InfoLevel::Streams => {
-                let streams = self.store.stream_info();
+                let mut streams = self.store.stream_info();
+                streams.retain(|s| self.may_access(&s.network, &s.station));
                 info_xml::build_info_streams_xml(&streams)
             }
             InfoLevel::Connections => {
                 let conns = self.connections.snapshot();
-                info_xml::build_info_connections_xml(&conns)
+                info_xml::build_info_connections_xml(&conns, &self.connections.closed())
             }
             _ => {
                 let resp = Response::Error {
@@ -349,19 +1126,10 @@ impl ClientHandler {
             }
         };
 
-        let xml_bytes = xml.as_bytes();
-
         // Send as frame(s) depending on protocol version
         match self.protocol_version {
             ProtocolVersion::V3 => {
-                // Split XML into 512-byte chunks, null-pad last one
-                for chunk in xml_bytes.chunks(v3::PAYLOAD_LEN) {
-                    let mut padded = vec![0u8; v3::PAYLOAD_LEN];
-                    padded[..chunk.len()].copy_from_slice(chunk);
-                    let frame = match v3::write(SequenceNumber::new(0), &padded) {
-                        Ok(f) => f,
-                        Err(_) => return false,
-                    };
+                for frame in InfoDocument::new(xml.as_str()).split_v3() {
                     if self.writer.write_all(&frame).await.is_err() {
                         return false;
                     }
@@ -373,7 +1141,7 @@ impl ClientHandler {
                     PayloadSubformat::Info,
                     SequenceNumber::new(0),
                     "",
-                    xml_bytes,
+                    &v4_payload(xml.as_bytes(), self.compress_v4()),
                 ) {
                     Ok(f) => f,
                     Err(_) => return false,
@@ -384,20 +1152,223 @@ impl ClientHandler {
             }
         }
 
-        // Terminate with END
-        if self.writer.write_all(b"END\r\n").await.is_err() {
+        // v3 replies end with the "INFO  " frame; v4 with END
+        if self.protocol_version == ProtocolVersion::V4
+            && self.writer.write_all(b"END\r\n").await.is_err()
+        {
             return false;
         }
         self.writer.flush().await.is_ok()
     }
 
+    /// Whether `ERROR` replies carry a code and description: always on v4,
+    /// and on v3 once the client enabled `EXTREPLY`.
+    fn extended_replies(&self) -> bool {
+        self.protocol_version == ProtocolVersion::V4
+            || self.capabilities.iter().any(|c| c == "EXTREPLY")
+    }
+
+    /// Send a reply. A failed write is noted as the disconnect cause.
     async fn send_response(&mut self, resp: &Response) -> Result<(), std::io::Error> {
-        self.writer.write_all(&resp.to_bytes()).await?;
+        let result = self.write_response(resp).await;
+        if let Err(e) = &result {
+            self.note_disconnect(DisconnectReason::from_io(e));
+        }
+        result
+    }
+
+    async fn write_response(&mut self, resp: &Response) -> Result<(), std::io::Error> {
+        if self.batch && matches!(resp, Response::Ok | Response::Error { .. }) {
+            trace!("batch mode, reply suppressed");
+            return Ok(());
+        }
+        let bytes = match resp {
+            // Classic v3 clients only understand a bare ERROR line
+            Response::Error { .. } if !self.extended_replies() => Response::Error {
+                code: None,
+                description: String::new(),
+            }
+            .to_bytes(),
+            _ => resp.to_bytes(),
+        };
+        self.writer.write_all(&bytes).await?;
         self.writer.flush().await?;
         Ok(())
     }
 }
 
+/// Why a stream stopped, noted by whichever of its producer and writer
+/// gets there first.
+#[derive(Default)]
+struct StopReason(Mutex<Option<DisconnectReason>>);
+
+impl StopReason {
+    fn set(&self, reason: DisconnectReason) {
+        self.0.lock().unwrap().get_or_insert(reason);
+    }
+}
+
+/// Build a frame for `record` and queue it. Returns `false` if streaming should stop.
+async fn enqueue(
+    queue: &SendQueue,
+    stop: &StopReason,
+    encoder: &mut FrameEncoder,
+    record: &Record,
+) -> bool {
+    match encoder.encode(record, queue.header_buffer()) {
+        Ok(frame) => push_frame(queue, stop, frame).await,
+        Err(e) => {
+            stop.set(DisconnectReason::Error(e.to_string()));
+            queue.abort();
+            false
+        }
+    }
+}
+
+/// Write `frames` and flush: as header and payload slices in vectored
+/// writes where the writer supports them, otherwise copied into `flat`.
+async fn write_frames<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    frames: &[QueuedFrame],
+    flat: &mut Vec<u8>,
+) -> io::Result<()> {
+    if writer.is_write_vectored() {
+        let mut slices: Vec<IoSlice<'_>> = frames
+            .iter()
+            .flat_map(QueuedFrame::slices)
+            .filter(|s| !s.is_empty())
+            .map(IoSlice::new)
+            .collect();
+        write_all_vectored(writer, &mut slices).await
+    } else {
+        flat.clear();
+        for frame in frames {
+            frame
+                .slices()
+                .iter()
+                .for_each(|s| flat.extend_from_slice(s));
+        }
+        write_all_vectored(writer, &mut [IoSlice::new(flat)]).await
+    }
+}
+
+/// Write all of `bufs` and flush. Empty slices must be left out.
+async fn write_all_vectored<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    mut bufs: &mut [IoSlice<'_>],
+) -> io::Result<()> {
+    loop {
+        let result = if bufs.is_empty() {
+            writer.flush().await.map(|()| None)
+        } else {
+            writer.write_vectored(bufs).await.map(Some)
+        };
+        match result {
+            Ok(None) => return Ok(()),
+            Ok(Some(0)) => return Err(io::ErrorKind::WriteZero.into()),
+            Ok(Some(n)) => IoSlice::advance_slices(&mut bufs, n),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Queue a built frame. Returns `false` if streaming should stop.
+async fn push_frame(queue: &SendQueue, stop: &StopReason, frame: QueuedFrame) -> bool {
+    match queue.push(frame).await {
+        Ok(()) => true,
+        Err(PushError::Overflow) => {
+            warn!(depth = queue.len(), "send queue full, disconnecting client");
+            stop.set(DisconnectReason::SlowConsumer);
+            queue.abort();
+            false
+        }
+        Err(PushError::Closed) => false,
+    }
+}
+
+/// Whether a STATION network or station contains `*` or `?`.
+fn is_pattern(s: &str) -> bool {
+    s.contains(['*', '?'])
+}
+
+/// Encodes records as frames of one protocol version, reusing its station
+/// ID buffer across records.
+struct FrameEncoder {
+    version: ProtocolVersion,
+    compress: bool,
+    station_id: String,
+}
+
+impl FrameEncoder {
+    fn new(version: ProtocolVersion, compress: bool) -> Self {
+        Self {
+            version,
+            compress,
+            station_id: String::new(),
+        }
+    }
+
+    /// Build the frame for `record`, encoding its header into `header`.
+    fn encode(
+        &mut self,
+        record: &Record,
+        mut header: Vec<u8>,
+    ) -> Result<QueuedFrame, seedlink_rs_protocol::SeedlinkError> {
+        let payload = match self.version {
+            ProtocolVersion::V3 => {
+                v3::write_header(&mut header, record.sequence, record.payload.len())?;
+                FramePayload::Shared(record.payload.clone())
+            }
+            ProtocolVersion::V4 => {
+                let payload = match v4_payload(&record.payload, self.compress) {
+                    Cow::Borrowed(_) => FramePayload::Shared(record.payload.clone()),
+                    Cow::Owned(compressed) => FramePayload::Owned(compressed),
+                };
+                self.station_id.clear();
+                self.station_id.push_str(&record.network);
+                self.station_id.push('_');
+                self.station_id.push_str(&record.station);
+                v4::write_header(
+                    &mut header,
+                    PayloadFormat::MiniSeed2,
+                    PayloadSubformat::Data,
+                    record.sequence,
+                    &self.station_id,
+                    payload.bytes().len(),
+                )?;
+                payload
+            }
+        };
+        Ok(QueuedFrame { header, payload })
+    }
+
+    /// An INFO frame without a document, sent to keep an idle stream alive.
+    fn keepalive(&self) -> QueuedFrame {
+        match self.version {
+            ProtocolVersion::V3 => InfoDocument::new("").split_v3().remove(0),
+            ProtocolVersion::V4 => v4::write(
+                PayloadFormat::Xml,
+                PayloadSubformat::Info,
+                SequenceNumber::new(0),
+                "",
+                &v4_payload(&[], self.compress),
+            )
+            .expect("empty INFO frame fits"),
+        }
+        .into()
+    }
+}
+
+/// A v4 payload as sent: zstd-compressed if `compress` is set.
+fn v4_payload(payload: &[u8], compress: bool) -> Cow<'_, [u8]> {
+    match compress {
+        #[cfg(feature = "zstd")]
+        true => Cow::Owned(seedlink_rs_protocol::compression::compress(payload)),
+        _ => Cow::Borrowed(payload),
+    }
+}
+
 fn cmd_name(cmd: &Command) -> &'static str {
     match cmd {
         Command::Hello => "HELLO",
@@ -411,9 +1382,115 @@ fn cmd_name(cmd: &Command) -> &'static str {
         Command::Fetch { .. } => "FETCH",
         Command::Time { .. } => "TIME",
         Command::Cat => "CAT",
+        Command::Capabilities { .. } => "CAPABILITIES",
         Command::SlProto { .. } => "SLPROTO",
         Command::Auth { .. } => "AUTH",
         Command::UserAgent { .. } => "USERAGENT",
         Command::EndFetch => "ENDFETCH",
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    /// Accepts up to `chunk` bytes per write, failing with `errors` first.
+    struct Flaky {
+        errors: Vec<io::ErrorKind>,
+        chunk: usize,
+        written: Vec<u8>,
+    }
+
+    impl AsyncWrite for Flaky {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            if let Some(kind) = self.errors.pop() {
+                return Poll::Ready(Err(kind.into()));
+            }
+            let n = buf.len().min(self.chunk);
+            self.written.extend_from_slice(&buf[..n]);
+            Poll::Ready(Ok(n))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[tokio::test]
+    async fn write_all_vectored_writes_partial_chunks() {
+        use io::ErrorKind::{ConnectionReset, Interrupted, WouldBlock};
+
+        let mut writer = Flaky {
+            errors: vec![Interrupted],
+            chunk: 4,
+            written: Vec::new(),
+        };
+        let mut bufs = [IoSlice::new(b"01234"), IoSlice::new(b"56789")];
+        write_all_vectored(&mut writer, &mut bufs).await.unwrap();
+        assert_eq!(writer.written, b"0123456789");
+
+        // Not retried
+        writer.errors = vec![WouldBlock];
+        let e = write_all_vectored(&mut writer, &mut [IoSlice::new(b"x")])
+            .await
+            .unwrap_err();
+        assert_eq!(e.kind(), WouldBlock);
+        assert_eq!(writer.written, b"0123456789");
+
+        writer.errors = vec![ConnectionReset];
+        let e = write_all_vectored(&mut writer, &mut [IoSlice::new(b"x")])
+            .await
+            .unwrap_err();
+        assert_eq!(DisconnectReason::from_io(&e), DisconnectReason::PeerReset);
+    }
+
+    #[tokio::test]
+    async fn frames_written_from_shared_payloads() {
+        let record = Record::new(
+            SequenceNumber::new(1),
+            "IU".into(),
+            "ANMO".into(),
+            Arc::from(vec![0x42; v3::PAYLOAD_LEN]),
+        );
+        let mut encoder = FrameEncoder::new(ProtocolVersion::V3, false);
+        let frame = encoder.encode(&record, Vec::new()).unwrap();
+        assert!(
+            matches!(&frame.payload, FramePayload::Shared(p) if Arc::ptr_eq(p, &record.payload))
+        );
+
+        let mut writer = Flaky {
+            errors: Vec::new(),
+            chunk: 100,
+            written: Vec::new(),
+        };
+        let frames = [frame, encoder.keepalive()];
+        write_frames(&mut writer, &frames, &mut Vec::new())
+            .await
+            .unwrap();
+        let mut expected = v3::write(record.sequence, &record.payload).unwrap();
+        expected.extend(InfoDocument::new("").split_v3().remove(0));
+        assert_eq!(writer.written, expected);
+
+        let mut encoder = FrameEncoder::new(ProtocolVersion::V4, false);
+        let frame = encoder.encode(&record, Vec::new()).unwrap();
+        let expected = v4::write(
+            PayloadFormat::MiniSeed2,
+            PayloadSubformat::Data,
+            record.sequence,
+            "IU_ANMO",
+            &record.payload,
+        )
+        .unwrap();
+        assert_eq!(frame.slices().concat(), expected);
+    }
+}
@@ -1,8 +1,10 @@
 //! XML generation for SeedLink INFO responses (ID, STATIONS, STREAMS, CONNECTIONS).
 
-use crate::connections::ConnectionInfo;
+use crate::connections::{ClosedConnection, ConnectionInfo};
 use crate::format_timestamp;
 use crate::store::{StationInfo, StreamInfo};
+use std::sync::atomic::Ordering;
+use std::time::SystemTime;
 
 /// Escape XML special characters in attribute values.
 fn xml_escape(s: &str) -> String {
@@ -68,8 +70,18 @@ pub(crate) fn build_info_streams_xml(streams: &[StreamInfo]) -> String {
             current_station = Some((&s.network, &s.station));
         }
 
+        let time = |name: &str, t: Option<SystemTime>| {
+            t.map(|t| format!(" {name}=\"{}\"", format_record_time(t)))
+                .unwrap_or_default()
+        };
+        let begin_time = time("begin_time", s.begin_time);
+        let end_time = time("end_time", s.end_time);
+        let latency = s
+            .latency
+            .map(|l| format!(" latency=\"{:.1}\"", l.as_secs_f64()))
+            .unwrap_or_default();
         xml.push_str(&format!(
-            "    <stream seedname=\"{}\" location=\"{}\" type=\"{}\" begin_seq=\"{:06X}\" end_seq=\"{:06X}\"/>\n",
+            "    <stream seedname=\"{}\" location=\"{}\" type=\"{}\" begin_seq=\"{:06X}\" end_seq=\"{:06X}\"{begin_time}{end_time}{latency}/>\n",
             xml_escape(&s.channel),
             xml_escape(&s.location),
             xml_escape(&s.type_code),
@@ -85,27 +97,59 @@ pub(crate) fn build_info_streams_xml(streams: &[StreamInfo]) -> String {
     xml
 }
 
+/// Format a record time as `YYYY/MM/DD HH:MM:SS.FFFF`, the resolution of
+/// miniSEED BTime.
+fn format_record_time(time: SystemTime) -> String {
+    let ticks = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .subsec_micros()
+        / 100;
+    format!("{}.{ticks:04}", format_timestamp(time))
+}
+
 /// Build INFO CONNECTIONS XML response.
-pub(crate) fn build_info_connections_xml(connections: &[ConnectionInfo]) -> String {
+pub(crate) fn build_info_connections_xml(
+    connections: &[ConnectionInfo],
+    closed: &[ClosedConnection],
+) -> String {
     let mut xml = String::from("<?xml version=\"1.0\"?>\n<seedlink>\n");
     for c in connections {
-        let ctime = format_timestamp(c.connected_at);
-        let host = xml_escape(&c.addr.to_string());
-        let port = c.addr.port();
-        let ua = c.user_agent.as_deref().map(xml_escape).unwrap_or_default();
-        let proto = match c.protocol_version {
-            seedlink_rs_protocol::ProtocolVersion::V3 => "3.1",
-            seedlink_rs_protocol::ProtocolVersion::V4 => "4.0",
-        };
+        let (depth, dropped) = c.queue.as_ref().map_or((0, 0), |q| (q.len(), q.dropped()));
+        let throttled = u8::from(c.stats.throttled.load(Ordering::Relaxed));
         xml.push_str(&format!(
-            "  <connection host=\"{host}\" port=\"{port}\" ctime=\"{ctime}\" proto=\"{proto}\" useragent=\"{ua}\" state=\"{}\"/>\n",
+            "  <connection {} state=\"{}\" queue_depth=\"{depth}\" dropped=\"{dropped}\" throttled=\"{throttled}\"/>\n",
+            connection_attrs(c),
             xml_escape(&c.state),
         ));
     }
+    for c in closed {
+        xml.push_str(&format!(
+            "  <disconnected {} dtime=\"{}\" reason=\"{}\"/>\n",
+            connection_attrs(&c.info),
+            format_timestamp(c.closed_at),
+            c.reason.as_str(),
+        ));
+    }
     xml.push_str("</seedlink>\n");
     xml
 }
 
+/// Attributes identifying a client: address, connect time, protocol and agent.
+fn connection_attrs(c: &ConnectionInfo) -> String {
+    let proto = match c.protocol_version {
+        seedlink_rs_protocol::ProtocolVersion::V3 => "3.1",
+        seedlink_rs_protocol::ProtocolVersion::V4 => "4.0",
+    };
+    format!(
+        "host=\"{}\" port=\"{}\" ctime=\"{}\" proto=\"{proto}\" useragent=\"{}\"",
+        xml_escape(&c.addr.to_string()),
+        c.addr.port(),
+        format_timestamp(c.connected_at),
+        c.user_agent.as_deref().map(xml_escape).unwrap_or_default(),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -164,6 +208,9 @@ mod tests {
                 type_code: "D".into(),
                 begin_seq: 1,
                 end_seq: 3,
+                begin_time: None,
+                end_time: None,
+                latency: Some(std::time::Duration::from_millis(2_540)),
             },
             StreamInfo {
                 network: "IU".into(),
@@ -173,12 +220,23 @@ mod tests {
                 type_code: "D".into(),
                 begin_seq: 2,
                 end_seq: 4,
+                begin_time: Some(
+                    SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_705_276_800),
+                ),
+                end_time: Some(
+                    SystemTime::UNIX_EPOCH + std::time::Duration::from_millis(1_705_276_812_345),
+                ),
+                latency: None,
             },
         ];
         let xml = build_info_streams_xml(&streams);
         assert!(xml.contains("<station name=\"ANMO\" network=\"IU\">"));
         assert!(xml.contains("seedname=\"BHZ\""));
         assert!(xml.contains("seedname=\"BHN\""));
+        assert!(xml.contains("end_seq=\"000003\" latency=\"2.5\"/>"));
+        assert!(xml.contains(
+            "end_seq=\"000004\" begin_time=\"2024/01/15 00:00:00.0000\" end_time=\"2024/01/15 00:00:12.3450\"/>"
+        ));
         // Should only have one station open/close
         assert_eq!(xml.matches("<station ").count(), 1);
         assert_eq!(xml.matches("</station>").count(), 1);
@@ -195,6 +253,9 @@ mod tests {
                 type_code: "D".into(),
                 begin_seq: 1,
                 end_seq: 1,
+                begin_time: None,
+                end_time: None,
+                latency: None,
             },
             StreamInfo {
                 network: "IU".into(),
@@ -204,10 +265,63 @@ mod tests {
                 type_code: "D".into(),
                 begin_seq: 2,
                 end_seq: 2,
+                begin_time: None,
+                end_time: None,
+                latency: None,
             },
         ];
         let xml = build_info_streams_xml(&streams);
         assert_eq!(xml.matches("<station ").count(), 2);
         assert_eq!(xml.matches("</station>").count(), 2);
     }
+
+    #[tokio::test]
+    async fn info_connections_xml_reports_queue() {
+        use crate::queue::{OverflowPolicy, SendQueue};
+        use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+        use std::time::SystemTime;
+
+        let queue = SendQueue::new(8, OverflowPolicy::Block);
+        let conns = vec![
+            ConnectionInfo {
+                addr: SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 1001),
+                connected_at: SystemTime::now(),
+                protocol_version: seedlink_rs_protocol::ProtocolVersion::V3,
+                user_agent: None,
+                state: "Streaming".into(),
+                queue: Some(queue.clone()),
+                stats: Default::default(),
+                kick: Default::default(),
+            },
+            ConnectionInfo {
+                addr: SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 1002),
+                connected_at: SystemTime::now(),
+                protocol_version: seedlink_rs_protocol::ProtocolVersion::V3,
+                user_agent: None,
+                state: "Connected".into(),
+                queue: None,
+                stats: Default::default(),
+                kick: Default::default(),
+            },
+        ];
+        queue.push(vec![0].into()).await.unwrap();
+        queue.push(vec![1].into()).await.unwrap();
+        conns[0].stats.throttled.store(true, Ordering::Relaxed);
+        let closed = vec![ClosedConnection {
+            id: 3,
+            info: conns[1].clone(),
+            closed_at: SystemTime::now(),
+            reason: crate::events::DisconnectReason::SlowConsumer,
+        }];
+        let xml = build_info_connections_xml(&conns, &closed);
+        assert!(xml.contains("port=\"1001\""));
+        assert!(xml.contains("queue_depth=\"2\" dropped=\"0\" throttled=\"1\""));
+        assert!(xml.contains("throttled=\"0\""));
+        assert!(xml.contains("queue_depth=\"0\""));
+        assert!(
+            xml.contains("<disconnected host=\"127.0.0.1:1002\""),
+            "{xml}"
+        );
+        assert!(xml.contains("reason=\"slow_consumer\"/>"), "{xml}");
+    }
 }
@@ -21,24 +21,63 @@
 //! # }
 //! ```
 
+pub mod acl;
 pub(crate) mod connections;
+pub mod datalink;
+pub mod directory;
 pub mod error;
+pub mod events;
 pub(crate) mod handler;
 pub(crate) mod info;
+pub(crate) mod memory;
+pub mod metrics;
+pub(crate) mod queue;
+#[cfg(feature = "relay")]
+pub mod relay;
+pub(crate) mod reload;
 pub(crate) mod select;
+pub(crate) mod socket;
+pub mod source;
 pub mod store;
+pub(crate) mod throttle;
 pub(crate) mod time;
+pub mod udp;
 
+pub use acl::{Acl, AclAction, AclRule, AclSubject};
+pub use connections::{ClosedConnectionStatus, ConnectionManager, ConnectionStatus};
+pub use datalink::{DataLinkConfig, DataLinkSource};
+pub use directory::{ConsumedAction, DirectoryConfig, DirectorySource};
 pub use error::{Result, ServerError};
-pub use store::DataStore;
+pub use events::{DisconnectReason, ServerEvent};
+pub use memory::{MemoryConnector, MemoryIncoming};
+pub use metrics::{ClientMetrics, MetricsSnapshot, SenderMetrics, ServerMetrics, StreamMetrics};
+pub use queue::OverflowPolicy;
+#[cfg(feature = "relay")]
+pub use relay::{RelaySource, RelayStation};
+pub use reload::{ConfigHandle, ReloadableConfig};
+pub use seedlink_rs_protocol::{ProtocolVersion, ValidationMode};
+pub use socket::SocketConfig;
+pub use source::DataSource;
+pub use store::{
+    DataStore, Record, ResumeMissPolicy, RingSnapshot, SequencePolicy, SnapshotRecord, StoreConfig,
+    Subscription,
+};
+pub use udp::{UdpConfig, UdpSource};
 
 use std::net::SocketAddr;
-use std::time::SystemTime;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 
 use connections::ConnectionRegistry;
-use handler::{ClientHandler, HandlerConfig};
-use tokio::net::TcpListener;
-use tokio::sync::watch;
+use futures_core::Stream;
+use handler::{ClientHandler, ClientReader, ClientWriter, HandlerConfig};
+use metrics::Counters;
+use std::future::poll_fn;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, watch};
+use tokio::task::JoinSet;
+use tokio::time::Instant;
 use tracing::{info, warn};
 
 /// Format a SystemTime as "YYYY/MM/DD HH:MM:SS" without chrono.
@@ -100,6 +139,9 @@ fn is_leap(y: i64) -> bool {
 }
 
 /// Configuration for [`SeedLinkServer`].
+///
+/// The fields in [`ReloadableConfig`] can be changed after binding through
+/// [`SeedLinkServer::config_handle()`].
 #[derive(Clone, Debug)]
 pub struct ServerConfig {
     /// Software name reported in HELLO response. Default: `"SeedLink"`.
@@ -110,6 +152,69 @@ pub struct ServerConfig {
     pub organization: String,
     /// Ring buffer capacity (number of records). Default: `10_000`.
     pub ring_capacity: usize,
+    /// Ring buffer budget in payload bytes. The oldest records are evicted
+    /// when either this or [`ring_capacity`](Self::ring_capacity) is
+    /// exceeded. `0` disables. Default: `0`.
+    pub ring_capacity_bytes: usize,
+    /// Maximum frames queued per streaming client before
+    /// [`overflow_policy`](Self::overflow_policy) applies. Default: `1024`.
+    pub send_queue_capacity: usize,
+    /// What to do when a client's send queue is full. Default: [`OverflowPolicy::Block`].
+    pub overflow_policy: OverflowPolicy,
+    /// Maximum bytes of queued frames written to a client with one socket
+    /// write. A single frame larger than this is written on its own.
+    /// Default: 64 KiB.
+    pub write_batch_bytes: usize,
+    /// Delay before restarting a [`DataSource`] that panicked or returned an
+    /// error. Default: 1 second.
+    pub source_restart_delay: Duration,
+    /// Send a keepalive to streaming clients when no frame has been sent for
+    /// this long, so idle connections are not dropped by firewalls and NATs.
+    /// v3 clients get an empty `SLINFO` frame, v4 clients an empty INFO
+    /// packet. `None` or zero disables. Default: `None`.
+    pub keepalive_interval: Option<Duration>,
+    /// Close a client that sends no command for this long before streaming
+    /// (or between v4 FETCH windows), after an `ERROR`. `None` disables.
+    /// Default: `None`.
+    pub command_timeout: Option<Duration>,
+    /// Longest command line accepted, in bytes without the line terminator.
+    /// A longer line (or one with no newline at all) gets `ERROR ARGUMENTS`
+    /// and the connection is closed, so a client cannot grow the read buffer
+    /// without bound. Default: `1024`.
+    pub max_command_len: usize,
+    /// Commands a client may send per second; one more gets `ERROR LIMIT`
+    /// and the connection is closed. Blank lines are not counted. `None`
+    /// disables. Default: `None`.
+    pub max_commands_per_second: Option<u32>,
+    /// Close a streaming client when a write to it makes no progress for this
+    /// long, i.e. the client has stopped reading. `None` disables.
+    /// Default: `None`.
+    pub stream_idle_timeout: Option<Duration>,
+    /// Bandwidth limit per streaming client in bytes per second, with bursts
+    /// of up to one second's worth. A client held back by it shows
+    /// `throttled="1"` in INFO CONNECTIONS. `None` disables. Default: `None`.
+    pub max_bytes_per_second: Option<u64>,
+    /// `(network, station)` that SELECT/DATA/TIME apply to when a client
+    /// sends no STATION (uni-station mode). `None` subscribes such clients
+    /// to all stations the ACL allows. Default: `None`.
+    pub default_station: Option<(String, String)>,
+    /// What to do when a client resumes with `DATA seq`/`FETCH seq` from a
+    /// sequence whose successors were evicted and gives no start time to
+    /// fall back to. Default: [`ResumeMissPolicy::Reject`].
+    pub resume_miss: ResumeMissPolicy,
+    /// Data store options (sequence handling). Default: [`StoreConfig::default()`].
+    pub store: StoreConfig,
+    /// Station access rules applied to STATION, streamed records and INFO
+    /// STATIONS/STREAMS. Default: allow everything.
+    pub acl: Acl,
+    /// Protocol versions advertised in HELLO and accepted by `SLPROTO`, in
+    /// the order advertised. Without [`ProtocolVersion::V3`], clients must
+    /// send `SLPROTO 4.0` before any other command but HELLO, USERAGENT, AUTH
+    /// and BYE. Must not be empty. Default: `[V4, V3]`.
+    pub supported_protocols: Vec<ProtocolVersion>,
+    /// Listen backlog, address reuse and client socket tuning.
+    /// Default: [`SocketConfig::default()`].
+    pub socket: SocketConfig,
 }
 
 impl Default for ServerConfig {
@@ -119,37 +224,86 @@ impl Default for ServerConfig {
             version: "v3.1".to_owned(),
             organization: "seedlink-rs".to_owned(),
             ring_capacity: 10_000,
+            ring_capacity_bytes: 0,
+            send_queue_capacity: 1024,
+            overflow_policy: OverflowPolicy::default(),
+            write_batch_bytes: 64 * 1024,
+            source_restart_delay: Duration::from_secs(1),
+            keepalive_interval: None,
+            command_timeout: None,
+            max_command_len: 1024,
+            max_commands_per_second: None,
+            stream_idle_timeout: None,
+            max_bytes_per_second: None,
+            default_station: None,
+            resume_miss: ResumeMissPolicy::default(),
+            store: StoreConfig::default(),
+            acl: Acl::default(),
+            supported_protocols: vec![ProtocolVersion::V4, ProtocolVersion::V3],
+            socket: SocketConfig::default(),
         }
     }
 }
 
+/// Shutdown state broadcast from [`ShutdownHandle`] to the accept loop and handlers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ShutdownSignal {
+    Running,
+    /// Close all connections now.
+    Immediate,
+    /// Stop accepting, let streaming clients drain until `deadline`.
+    Graceful {
+        deadline: Instant,
+    },
+}
+
 /// Handle for triggering graceful server shutdown.
 ///
 /// Obtained via [`SeedLinkServer::shutdown_handle()`]. Calling [`shutdown()`](Self::shutdown)
-/// stops the accept loop and all active client handlers.
+/// stops the accept loop and all active client handlers;
+/// [`shutdown_graceful()`](Self::shutdown_graceful) lets streaming clients drain first.
 pub struct ShutdownHandle {
-    tx: watch::Sender<bool>,
+    tx: watch::Sender<ShutdownSignal>,
 }
 
 impl ShutdownHandle {
-    /// Signal the server to shut down gracefully.
+    /// Signal the server to shut down, closing all client connections immediately.
     pub fn shutdown(&self) {
-        let _ = self.tx.send(true);
+        let _ = self.tx.send(ShutdownSignal::Immediate);
+    }
+
+    /// Signal the server to shut down after a drain period.
+    ///
+    /// New connections are refused at once and idle (non-streaming) clients are
+    /// closed. Streaming clients are sent the records still in the ring, followed
+    /// by `END` on v3, and are disconnected once `timeout` has elapsed if they
+    /// have not finished by then. [`SeedLinkServer::run()`] returns when all
+    /// clients are done or the timeout expires.
+    pub fn shutdown_graceful(&self, timeout: Duration) {
+        let deadline = Instant::now() + timeout;
+        let _ = self.tx.send(ShutdownSignal::Graceful { deadline });
     }
 }
 
 /// Async SeedLink v3/v4 server.
 ///
 /// Binds to a TCP port, accepts client connections, and distributes
-/// miniSEED records from a shared [`DataStore`].
+/// miniSEED records from a shared [`DataStore`]. Connections can also come
+/// from an existing listener ([`from_listener()`](Self::from_listener)) or
+/// any stream of byte streams ([`run_on()`](Self::run_on)).
 pub struct SeedLinkServer {
-    listener: TcpListener,
+    /// `None` for servers created with [`new()`](Self::new).
+    listener: Option<TcpListener>,
     config: ServerConfig,
     store: DataStore,
     started: String,
-    shutdown_tx: watch::Sender<bool>,
-    shutdown_rx: watch::Receiver<bool>,
+    shutdown_tx: watch::Sender<ShutdownSignal>,
+    shutdown_rx: watch::Receiver<ShutdownSignal>,
     connections: ConnectionRegistry,
+    counters: Arc<Counters>,
+    sources: source::SourceList,
+    runtime: ConfigHandle,
+    events: broadcast::Sender<ServerEvent>,
 }
 
 impl SeedLinkServer {
@@ -158,28 +312,81 @@ impl SeedLinkServer {
         Self::bind_with_config(addr, ServerConfig::default()).await
     }
 
-    /// Bind to the given address with custom configuration.
+    /// Bind to the given address with custom configuration. Fails with
+    /// [`ServerError::InvalidConfig`] if `config` is unusable (see
+    /// [`new()`](Self::new)).
     pub async fn bind_with_config(addr: &str, config: ServerConfig) -> Result<Self> {
-        let listener = TcpListener::bind(addr).await.map_err(ServerError::Bind)?;
-        let store = DataStore::new(config.ring_capacity);
+        validate_config(&config)?;
+        let listener = config.socket.bind(addr).await.map_err(ServerError::Bind)?;
+        info!(addr, "server bound");
+        Self::from_listener(listener, config)
+    }
+
+    /// Serve clients accepted from an already bound listener, e.g. one
+    /// inherited through systemd socket activation:
+    ///
+    /// ```no_run
+    /// # fn example(fd: std::os::fd::RawFd) -> Result<(), Box<dyn std::error::Error>> {
+    /// use std::os::fd::FromRawFd;
+    /// use seedlink_rs_server::{SeedLinkServer, ServerConfig};
+    ///
+    /// // SAFETY: `fd` is a listening socket passed by systemd (LISTEN_FDS)
+    /// let std_listener = unsafe { std::net::TcpListener::from_raw_fd(fd) };
+    /// std_listener.set_nonblocking(true)?;
+    /// let listener = tokio::net::TcpListener::from_std(std_listener)?;
+    /// let server = SeedLinkServer::from_listener(listener, ServerConfig::default())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Only the per-client options of [`ServerConfig::socket`] apply; the
+    /// listener keeps its own backlog and address reuse settings.
+    pub fn from_listener(listener: TcpListener, config: ServerConfig) -> Result<Self> {
+        let mut server = Self::new(config)?;
+        server.listener = Some(listener);
+        Ok(server)
+    }
+
+    /// Create a server without a listener. Feed it connections with
+    /// [`run_on()`](Self::run_on); [`run()`](Self::run) only runs the data
+    /// sources until shutdown.
+    ///
+    /// Fails with [`ServerError::InvalidConfig`] if
+    /// [`supported_protocols`](ServerConfig::supported_protocols) is empty.
+    pub fn new(config: ServerConfig) -> Result<Self> {
+        validate_config(&config)?;
+        let store = DataStore::with_limits(
+            config.ring_capacity,
+            config.ring_capacity_bytes,
+            config.store.clone(),
+        );
         let started = format_timestamp(SystemTime::now());
-        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let (shutdown_tx, shutdown_rx) = watch::channel(ShutdownSignal::Running);
         let connections = ConnectionRegistry::new();
-        info!(addr, "server bound");
+        let runtime = ConfigHandle::new(&config);
         Ok(Self {
-            listener,
+            listener: None,
             config,
             store,
             started,
             shutdown_tx,
             shutdown_rx,
             connections,
+            counters: Arc::default(),
+            sources: Default::default(),
+            runtime,
+            events: broadcast::channel(events::EVENT_CAPACITY).0,
         })
     }
 
-    /// Returns the local address this server is bound to.
+    /// Returns the local address this server is bound to. Fails with
+    /// [`io::ErrorKind::NotConnected`](std::io::ErrorKind::NotConnected) for
+    /// a server created with [`new()`](Self::new).
     pub fn local_addr(&self) -> Result<SocketAddr> {
-        self.listener.local_addr().map_err(ServerError::Io)
+        let listener = self.listener.as_ref().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::NotConnected, "server has no listener")
+        })?;
+        listener.local_addr().map_err(ServerError::Io)
     }
 
     /// Returns a reference to the shared data store.
@@ -187,6 +394,53 @@ impl SeedLinkServer {
         &self.store
     }
 
+    /// Returns a handle for reading server statistics.
+    ///
+    /// The handle stays valid after [`run()`](Self::run) consumes the server.
+    pub fn metrics(&self) -> ServerMetrics {
+        ServerMetrics::new(
+            self.store.clone(),
+            self.connections.clone(),
+            self.counters.clone(),
+            self.sources.clone(),
+        )
+    }
+
+    /// Returns a handle for listing connected clients and disconnecting them
+    /// with [`kick()`](ConnectionManager::kick).
+    ///
+    /// The handle stays valid after [`run()`](Self::run) consumes the server.
+    pub fn connections(&self) -> ConnectionManager {
+        ConnectionManager::new(self.connections.clone())
+    }
+
+    /// Subscribe to the server's [`ServerEvent`]s: connections, disconnections
+    /// with their [`DisconnectReason`], subscriptions, stream starts and
+    /// shutdown.
+    ///
+    /// Only events published after this call are received. A receiver that
+    /// falls more than 1024 events behind skips the oldest and gets
+    /// [`RecvError::Lagged`](broadcast::error::RecvError::Lagged). Receivers
+    /// stay valid after [`run()`](Self::run) consumes the server.
+    pub fn events(&self) -> broadcast::Receiver<ServerEvent> {
+        self.events.subscribe()
+    }
+
+    /// Register a data source. It is started by [`run()`](Self::run), restarted
+    /// if it panics or fails, and stopped when the server shuts down.
+    pub fn add_source(&mut self, source: Box<dyn DataSource>) {
+        self.sources.lock().unwrap().push(Arc::from(source));
+    }
+
+    /// Returns a handle for changing the [`ReloadableConfig`] values —
+    /// organization, keepalive, command limits, timeouts and ACL — while the
+    /// server runs. Changes apply to connected clients as well as new ones.
+    ///
+    /// The handle stays valid after [`run()`](Self::run) consumes the server.
+    pub fn config_handle(&self) -> ConfigHandle {
+        self.runtime.clone()
+    }
+
     /// Returns a handle that can be used to trigger graceful shutdown.
     pub fn shutdown_handle(&self) -> ShutdownHandle {
         ShutdownHandle {
@@ -194,17 +448,67 @@ impl SeedLinkServer {
         }
     }
 
-    /// Run the accept loop. Spawns a task per client connection.
+    /// Run the accept loop. Spawns a task per client connection and one per
+    /// registered [`DataSource`].
     ///
-    /// Returns when shutdown is signalled or the listener fails.
+    /// Returns when shutdown is signalled or the listener fails. After a
+    /// [graceful shutdown](ShutdownHandle::shutdown_graceful), waits for
+    /// clients to drain (up to the drain timeout) before returning.
     pub async fn run(mut self) {
+        let incoming = accept_tcp(self.listener.take(), self.config.socket.clone());
+        self.serve(incoming, split_tcp).await
+    }
+
+    /// Like [`run()`](Self::run), but serve the connections yielded by
+    /// `incoming` instead of accepting from the listener: a custom accept
+    /// loop, TLS-terminated streams, or [`tokio::io::duplex`] pairs in tests.
+    /// Each item is the connection and the peer address reported in events,
+    /// ACL checks and INFO CONNECTIONS.
+    ///
+    /// When `incoming` ends, no more clients are accepted, but connected
+    /// clients and sources keep running until shutdown.
+    pub async fn run_on<S, IO>(self, incoming: S)
+    where
+        S: Stream<Item = (IO, SocketAddr)>,
+        IO: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+    {
+        self.serve(incoming, split_io).await
+    }
+
+    /// Serve `incoming`, splitting each connection into read and write
+    /// halves with `split`.
+    async fn serve<S, IO>(mut self, incoming: S, split: fn(IO) -> (ClientReader, ClientWriter))
+    where
+        S: Stream<Item = (IO, SocketAddr)>,
+    {
+        // Dropped on return, which stops all sources
+        let mut sources = JoinSet::new();
+        let registered = self.sources.lock().unwrap().clone();
+        for source in registered {
+            info!(source = source.name(), "starting data source");
+            sources.spawn(source::supervise(
+                source,
+                self.store.clone(),
+                self.config.source_restart_delay,
+            ));
+        }
+
+        if let Some(retention) = self.store.retention() {
+            sources.spawn(store::sweep_expired(self.store.clone(), retention));
+        }
+
+        let mut clients = JoinSet::new();
+        let mut incoming = std::pin::pin!(incoming);
+        let mut incoming_done = false;
         loop {
             let (stream, addr) = tokio::select! {
-                result = self.listener.accept() => {
-                    match result {
-                        Ok(conn) => conn,
-                        Err(e) => {
-                            warn!(error = %e, "accept error");
+                Some(_) = clients.join_next(), if !clients.is_empty() => continue,
+                next = poll_fn(|cx| incoming.as_mut().poll_next(cx)), if !incoming_done => {
+                    match next {
+                        Some(conn) => conn,
+                        None => {
+                            info!("incoming connections ended");
+                            incoming_done = true;
                             continue;
                         }
                     }
@@ -215,22 +519,36 @@ impl SeedLinkServer {
                 }
             };
 
-            info!(%addr, "accepted connection");
-            stream.set_nodelay(true).ok();
-
             let conn_id = self.connections.register(addr);
-            let (read_half, write_half) = stream.into_split();
+            info!(conn_id, %addr, "accepted connection");
+            let _ = self
+                .events
+                .send(ServerEvent::ClientConnected { id: conn_id, addr });
+            self.counters
+                .clients_total
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            let (read_half, write_half) = split(stream);
             let store = self.store.clone();
             let handler_config = HandlerConfig {
                 software: self.config.software.clone(),
                 version: self.config.version.clone(),
-                organization: self.config.organization.clone(),
                 started: self.started.clone(),
+                send_queue_capacity: self.config.send_queue_capacity,
+                overflow_policy: self.config.overflow_policy,
+                write_batch_bytes: self.config.write_batch_bytes,
+                max_bytes_per_second: self.config.max_bytes_per_second,
+                default_station: self.config.default_station.clone(),
+                resume_miss: self.config.resume_miss,
+                supported_protocols: self.config.supported_protocols.clone(),
+                counters: self.counters.clone(),
+                runtime: self.runtime.subscribe(),
+                peer: addr,
+                events: self.events.clone(),
             };
             let shutdown_rx = self.shutdown_rx.clone();
             let connections = self.connections.clone();
 
-            tokio::spawn(async move {
+            clients.spawn(async move {
                 let handler = ClientHandler::new(
                     read_half,
                     write_half,
@@ -243,6 +561,75 @@ impl SeedLinkServer {
                 handler.run().await;
             });
         }
+
+        let signal = *self.shutdown_rx.borrow();
+        let _ = self.events.send(ServerEvent::Shutdown {
+            graceful: matches!(signal, ShutdownSignal::Graceful { .. }),
+        });
+        match signal {
+            ShutdownSignal::Graceful { deadline } => {
+                let drained = tokio::time::timeout_at(deadline, async {
+                    while clients.join_next().await.is_some() {}
+                })
+                .await;
+                if drained.is_err() {
+                    warn!(
+                        remaining = clients.len(),
+                        "drain timeout, aborting client handlers"
+                    );
+                }
+            }
+            _ => clients.detach_all(),
+        }
+    }
+}
+
+/// Reject a configuration no client could use.
+fn validate_config(config: &ServerConfig) -> Result<()> {
+    if config.supported_protocols.is_empty() {
+        return Err(ServerError::InvalidConfig(
+            "supported_protocols must not be empty".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Split a TCP connection into owned halves, which need no lock between
+/// reads and writes.
+fn split_tcp(stream: TcpStream) -> (ClientReader, ClientWriter) {
+    let (read_half, write_half) = stream.into_split();
+    (Box::new(read_half), Box::new(write_half))
+}
+
+/// Split any other connection; [`tokio::io::split`] locks around each poll.
+fn split_io<IO>(stream: IO) -> (ClientReader, ClientWriter)
+where
+    IO: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+{
+    let (read_half, write_half) = tokio::io::split(stream);
+    (Box::new(read_half), Box::new(write_half))
+}
+
+/// Accept connections from `listener` with the per-client socket options
+/// applied. Yields nothing without a listener.
+fn accept_tcp(
+    listener: Option<TcpListener>,
+    socket: SocketConfig,
+) -> impl Stream<Item = (TcpStream, SocketAddr)> {
+    async_stream::stream! {
+        let Some(listener) = listener else { return };
+        loop {
+            match listener.accept().await {
+                Ok((stream, addr)) => {
+                    stream.set_nodelay(true).ok();
+                    if let Err(e) = socket.apply(&stream) {
+                        warn!(%addr, error = %e, "failed to apply socket options");
+                    }
+                    yield (stream, addr);
+                }
+                Err(e) => warn!(error = %e, "accept error"),
+            }
+        }
     }
 }
 
@@ -250,31 +637,28 @@ impl SeedLinkServer {
 mod tests {
     use super::*;
 
-    use seedlink_rs_client::{ClientConfig, ClientState, OwnedFrame, SeedLinkClient};
+    use seedlink_rs_client::{ClientConfig, ClientState, OwnedFrame, SeedLinkClient, StreamEvent};
     use seedlink_rs_protocol::SequenceNumber;
     use seedlink_rs_protocol::frame::v3;
+    use seedlink_rs_protocol::testing::TestRecordBuilder;
     use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
     use tokio::net::TcpStream;
 
-    /// Build a valid 512-byte miniSEED-like payload with station/network in header.
+    /// Build a valid 512-byte miniSEED record for `network.station`.
     fn make_payload(station: &str, network: &str) -> Vec<u8> {
-        let mut payload = vec![0u8; v3::PAYLOAD_LEN];
-        // miniSEED v2 header: station at bytes 8-12, network at bytes 18-19
-        let sta_bytes = station.as_bytes();
-        for (i, &b) in sta_bytes.iter().enumerate().take(5) {
-            payload[8 + i] = b;
-        }
-        for i in sta_bytes.len()..5 {
-            payload[8 + i] = b' ';
-        }
-        let net_bytes = network.as_bytes();
-        for (i, &b) in net_bytes.iter().enumerate().take(2) {
-            payload[18 + i] = b;
-        }
-        for i in net_bytes.len()..2 {
-            payload[18 + i] = b' ';
-        }
-        payload
+        TestRecordBuilder::new()
+            .station(station)
+            .network(network)
+            .build()
+    }
+
+    /// Whether `data` ends with the last frame of a v3 INFO reply.
+    fn v3_info_complete(data: &[u8]) -> bool {
+        data.len().is_multiple_of(v3::FRAME_LEN)
+            && data
+                .rchunks(v3::FRAME_LEN)
+                .next()
+                .is_some_and(|frame| v3::info_continues(frame) == Some(false))
     }
 
     async fn start_server() -> (DataStore, String) {
@@ -519,11 +903,20 @@ mod tests {
         let stream = TcpStream::connect(&addr).await.unwrap();
         let (read_half, mut write_half) = stream.into_split();
         let mut reader = BufReader::new(read_half);
+        let mut line = String::new();
+
+        // Error codes and descriptions need EXTREPLY on v3
+        write_half
+            .write_all(b"CAPABILITIES EXTREPLY\r\n")
+            .await
+            .unwrap();
+        reader.read_line(&mut line).await.unwrap();
+        assert_eq!(line, "OK\r\n");
 
         write_half.write_all(b"FOOBAR\r\n").await.unwrap();
         write_half.flush().await.unwrap();
 
-        let mut line = String::new();
+        line.clear();
         reader.read_line(&mut line).await.unwrap();
         assert!(line.starts_with("ERROR"), "expected ERROR, got: {line:?}");
         assert!(line.contains("UNSUPPORTED"));
@@ -822,6 +1215,12 @@ mod tests {
             "should list location 00: {xml}"
         );
         assert!(xml.contains("type=\"D\""), "should list type D: {xml}");
+        assert!(
+            xml.contains(
+                "begin_time=\"2024/01/01 00:00:00.0000\" end_time=\"2024/01/01 00:00:00.0000\""
+            ),
+            "should list the record time range: {xml}"
+        );
     }
 
     // ---- Test 19: info_unsupported_level_returns_error ----
@@ -1108,9 +1507,7 @@ mod tests {
         write_half.write_all(b"INFO CONNECTIONS\r\n").await.unwrap();
         write_half.flush().await.unwrap();
 
-        // Read response frames (binary SL frames + END)
-        // The response is INFO frames followed by "END\r\n"
-        // Read raw bytes until we see END
+        // Read response frames until the last one ("SLINFO  ")
         let mut all_data = Vec::new();
         loop {
             let mut buf = [0u8; 4096];
@@ -1125,8 +1522,7 @@ mod tests {
                 break;
             }
             all_data.extend_from_slice(&buf[..n]);
-            // Check if we've received the END marker
-            if all_data.windows(5).any(|w| w == b"END\r\n") {
+            if v3_info_complete(&all_data) {
                 break;
             }
         }
@@ -1192,31 +1588,12 @@ mod tests {
             "expected OK for BATCH, got: {line:?}"
         );
 
-        write_half.write_all(b"STATION ANMO IU\r\n").await.unwrap();
-        write_half.flush().await.unwrap();
-        line.clear();
-        reader.read_line(&mut line).await.unwrap();
-        assert!(line.starts_with("OK"), "expected OK for STATION IU");
-
-        write_half.write_all(b"DATA\r\n").await.unwrap();
-        write_half.flush().await.unwrap();
-        line.clear();
-        reader.read_line(&mut line).await.unwrap();
-        assert!(line.starts_with("OK"), "expected OK for DATA");
-
-        write_half.write_all(b"STATION WLF GE\r\n").await.unwrap();
-        write_half.flush().await.unwrap();
-        line.clear();
-        reader.read_line(&mut line).await.unwrap();
-        assert!(line.starts_with("OK"), "expected OK for STATION GE");
-
-        write_half.write_all(b"DATA\r\n").await.unwrap();
-        write_half.flush().await.unwrap();
-        line.clear();
-        reader.read_line(&mut line).await.unwrap();
-        assert!(line.starts_with("OK"), "expected OK for DATA 2");
-
-        // Send FETCH to get buffered data then close
+        // No per-command replies in BATCH mode, not even for errors: the
+        // first bytes after the commands are frames
+        write_half
+            .write_all(b"STATION ANMO IU\r\nSELECT !\r\nDATA\r\nSTATION WLF GE\r\nDATA\r\n")
+            .await
+            .unwrap();
         write_half.write_all(b"FETCH\r\n").await.unwrap();
         write_half.flush().await.unwrap();
 
@@ -1234,6 +1611,26 @@ mod tests {
         // Both frames should have SL signature
         assert_eq!(&frame1[0..2], b"SL");
         assert_eq!(&frame2[0..2], b"SL");
+
+        // v3 clients use BATCH for subscribe_all since HELLO advertises it
+        let config = ClientConfig {
+            prefer_v4: false,
+            ..ClientConfig::default()
+        };
+        let mut client = SeedLinkClient::connect_with_config(&addr, config)
+            .await
+            .unwrap();
+        assert!(client.server_info().capabilities.has("BATCH"));
+        client
+            .subscribe_all(&[("IU", "ANMO", &[]), ("GE", "WLF", &[])])
+            .await
+            .unwrap();
+        client.fetch().await.unwrap();
+        let mut stations = Vec::new();
+        while let Some(frame) = client.next_frame().await.unwrap() {
+            stations.push(frame.station_key().unwrap().station);
+        }
+        assert_eq!(stations, ["ANMO", "WLF"]);
     }
 
     // ---- Test 28: connection_unregistered_on_disconnect ----
@@ -1269,7 +1666,7 @@ mod tests {
                     break;
                 }
                 all.extend_from_slice(&buf[..n]);
-                if all.windows(5).any(|w| w == b"END\r\n") {
+                if v3_info_complete(&all) {
                     break;
                 }
             }
@@ -1306,7 +1703,7 @@ mod tests {
                     break;
                 }
                 all.extend_from_slice(&buf[..n]);
-                if all.windows(5).any(|w| w == b"END\r\n") {
+                if v3_info_complete(&all) {
                     break;
                 }
             }
@@ -1322,4 +1719,1782 @@ mod tests {
             "expected fewer connections after BYE: before={count_before}, after={count_after}"
         );
     }
+
+    // ---- Test 29: small_send_queue_delivers_all ----
+
+    #[tokio::test]
+    async fn small_send_queue_delivers_all() {
+        let config = ServerConfig {
+            send_queue_capacity: 1,
+            ..ServerConfig::default()
+        };
+        let (store, addr) = start_server_with_config(config).await;
+
+        let payload = make_payload("ANMO", "IU");
+        for _ in 0..20 {
+            store.push("IU", "ANMO", &payload);
+        }
+
+        let client_config = ClientConfig {
+            prefer_v4: false,
+            ..ClientConfig::default()
+        };
+        let mut client = SeedLinkClient::connect_with_config(&addr, client_config)
+            .await
+            .unwrap();
+        client.station("ANMO", "IU").await.unwrap();
+        client.data().await.unwrap();
+        client.fetch().await.unwrap();
+
+        // Block policy: every record arrives, in order, then EOF
+        for seq in 1..=20 {
+            let f = client.next_frame().await.unwrap().unwrap();
+            assert_eq!(f.sequence(), SequenceNumber::new(seq));
+        }
+        assert!(client.next_frame().await.unwrap().is_none());
+    }
+
+    // ---- Test 30: lagged_client_catches_up_from_ring ----
+
+    #[tokio::test]
+    async fn lagged_client_catches_up_from_ring() {
+        let (store, addr) = start_server().await;
+
+        let mut client = SeedLinkClient::connect(&addr).await.unwrap();
+        client.station("ANMO", "IU").await.unwrap();
+        client.data().await.unwrap();
+        client.end_stream().await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+        // Push more than the live channel holds without yielding, so the
+        // handler's receiver lags and must rescan the ring.
+        let payload = make_payload("ANMO", "IU");
+        for _ in 0..2000 {
+            store.push("IU", "ANMO", &payload);
+        }
+
+        for seq in 1..=2000 {
+            let f = client.next_frame().await.unwrap().unwrap();
+            assert_eq!(f.sequence(), SequenceNumber::new(seq));
+        }
+    }
+
+    // ---- Test 31: time_window_end_terminates_stream ----
+
+    #[tokio::test]
+    async fn time_window_end_terminates_stream() {
+        let (store, addr) = start_server().await;
+
+        let config = ClientConfig {
+            prefer_v4: false,
+            ..ClientConfig::default()
+        };
+        let mut client = SeedLinkClient::connect_with_config(&addr, config)
+            .await
+            .unwrap();
+        client.station("ANMO", "IU").await.unwrap();
+        client
+            .time_window("2024,1,1,0,0,0", Some("2024,1,31,23,59,59"))
+            .await
+            .unwrap();
+        client.data().await.unwrap();
+        client.end_stream().await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+        // Live pushes during END streaming: in-window, then past the end
+        let mut payload_jan = make_payload("ANMO", "IU");
+        set_btime(&mut payload_jan, 2024, 15, 12, 0, 0);
+        store.push("IU", "ANMO", &payload_jan);
+
+        let mut payload_feb = make_payload("ANMO", "IU");
+        set_btime(&mut payload_feb, 2024, 46, 12, 0, 0);
+        store.push("IU", "ANMO", &payload_feb);
+
+        let f1 = client.next_frame().await.unwrap().unwrap();
+        assert_eq!(f1.sequence(), SequenceNumber::new(1));
+
+        // Window complete — server ends the stream instead of waiting forever
+        let f2 = client.next_frame().await.unwrap();
+        assert!(f2.is_none(), "expected EOF after TIME window end");
+    }
+
+    // ---- Test 32: time_window_end_waits_for_all_stations ----
+
+    #[tokio::test]
+    async fn time_window_end_waits_for_all_stations() {
+        let (store, addr) = start_server().await;
+
+        let mut payload_anmo = make_payload("ANMO", "IU");
+        set_btime(&mut payload_anmo, 2024, 46, 12, 0, 0); // past end
+        store.push("IU", "ANMO", &payload_anmo);
+
+        let config = ClientConfig {
+            prefer_v4: false,
+            ..ClientConfig::default()
+        };
+        let mut client = SeedLinkClient::connect_with_config(&addr, config)
+            .await
+            .unwrap();
+        for (sta, net) in [("ANMO", "IU"), ("WLF", "GE")] {
+            client.station(sta, net).await.unwrap();
+            client
+                .time_window("2024,1,1,0,0,0", Some("2024,1,31,23,59,59"))
+                .await
+                .unwrap();
+            client.data().await.unwrap();
+        }
+        client.end_stream().await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+        // ANMO is complete but WLF is not: stream stays open for WLF data
+        let mut payload_wlf = make_payload("WLF", "GE");
+        set_btime(&mut payload_wlf, 2024, 20, 0, 0, 0);
+        store.push("GE", "WLF", &payload_wlf);
+        let f1 = client.next_frame().await.unwrap().unwrap();
+        assert_eq!(f1.sequence(), SequenceNumber::new(2));
+
+        set_btime(&mut payload_wlf, 2024, 40, 0, 0, 0);
+        store.push("GE", "WLF", &payload_wlf);
+        assert!(client.next_frame().await.unwrap().is_none());
+    }
+
+    // ---- Test 33: data_time_range_filters_v4 ----
+
+    #[tokio::test]
+    async fn data_time_range_filters_v4() {
+        let (store, addr) = start_server().await;
+
+        let mut payload_jan = make_payload("ANMO", "IU");
+        set_btime(&mut payload_jan, 2024, 15, 12, 0, 0);
+        store.push("IU", "ANMO", &payload_jan);
+        let mut payload_feb = make_payload("ANMO", "IU");
+        set_btime(&mut payload_feb, 2024, 46, 12, 0, 0);
+        store.push("IU", "ANMO", &payload_feb);
+
+        let mut client = SeedLinkClient::connect(&addr).await.unwrap();
+        assert_eq!(client.version(), seedlink_rs_protocol::ProtocolVersion::V4);
+        client.station("ANMO", "IU").await.unwrap();
+        client
+            .data_time_range("2024-02-01T00:00:00Z", None)
+            .await
+            .unwrap();
+        client.end_stream().await.unwrap();
+
+        // Only the Feb record is inside the window
+        let f = client.next_frame().await.unwrap().unwrap();
+        assert_eq!(f.sequence(), SequenceNumber::new(2));
+    }
+
+    // ---- Test 34: data_invalid_time_returns_error ----
+
+    #[tokio::test]
+    async fn data_invalid_time_returns_error() {
+        let (_store, addr) = start_server().await;
+
+        let stream = TcpStream::connect(&addr).await.unwrap();
+        let (read_half, mut write_half) = stream.into_split();
+        let mut reader = BufReader::new(read_half);
+        let mut line = String::new();
+
+        write_half
+            .write_all(b"CAPABILITIES EXTREPLY\r\n")
+            .await
+            .unwrap();
+        reader.read_line(&mut line).await.unwrap();
+        assert!(line.starts_with("OK"));
+
+        write_half.write_all(b"STATION ANMO IU\r\n").await.unwrap();
+        line.clear();
+        reader.read_line(&mut line).await.unwrap();
+        assert!(line.starts_with("OK"));
+
+        write_half
+            .write_all(b"DATA ALL 2024-13-01T00:00:00Z\r\n")
+            .await
+            .unwrap();
+        line.clear();
+        reader.read_line(&mut line).await.unwrap();
+        assert_eq!(
+            line,
+            "ERROR ARGUMENTS invalid time: 2024-13-01T00:00:00Z\r\n"
+        );
+    }
+
+    // ---- Test 35: metrics_track_frames_and_clients ----
+
+    #[tokio::test]
+    async fn metrics_track_frames_and_clients() {
+        let config = ServerConfig {
+            ring_capacity: 2,
+            ..ServerConfig::default()
+        };
+        let server = SeedLinkServer::bind_with_config("127.0.0.1:0", config)
+            .await
+            .unwrap();
+        let addr = server.local_addr().unwrap().to_string();
+        let store = server.store().clone();
+        let metrics = server.metrics();
+        tokio::spawn(server.run());
+
+        let payload = make_payload("ANMO", "IU");
+        for _ in 0..3 {
+            store.push("IU", "ANMO", &payload);
+        }
+
+        let client_config = ClientConfig {
+            prefer_v4: false,
+            ..ClientConfig::default()
+        };
+        let mut client = SeedLinkClient::connect_with_config(&addr, client_config)
+            .await
+            .unwrap();
+        client.station("ANMO", "IU").await.unwrap();
+        client.data().await.unwrap();
+        client.end_stream().await.unwrap();
+        client.next_frame().await.unwrap().unwrap();
+        client.next_frame().await.unwrap().unwrap();
+
+        let snap = metrics.snapshot();
+        assert_eq!(snap.records_pushed, 3);
+        assert_eq!(snap.records_evicted, 1);
+        assert_eq!(snap.ring_len, 2);
+        assert_eq!(snap.frames_sent, 2);
+        assert_eq!(snap.bytes_sent, 2 * v3::FRAME_LEN as u64);
+        assert_eq!(snap.clients_connected, 1);
+        assert_eq!(snap.clients_total, 1);
+        assert_eq!(snap.clients[0].state, "Streaming");
+        assert_eq!(snap.clients[0].frames_sent, 2);
+    }
+
+    // ---- Test 36: graceful_shutdown_drains_buffered_records ----
+
+    #[tokio::test]
+    async fn graceful_shutdown_drains_buffered_records() {
+        let server = SeedLinkServer::bind("127.0.0.1:0").await.unwrap();
+        let addr = server.local_addr().unwrap().to_string();
+        let store = server.store().clone();
+        let handle = server.shutdown_handle();
+        let run = tokio::spawn(server.run());
+
+        let config = ClientConfig {
+            prefer_v4: false,
+            ..ClientConfig::default()
+        };
+        let mut client = SeedLinkClient::connect_with_config(&addr, config)
+            .await
+            .unwrap();
+        client.station("ANMO", "IU").await.unwrap();
+        client.data().await.unwrap();
+        client.end_stream().await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+        // Push and shut down without yielding: records are still pending
+        let payload = make_payload("ANMO", "IU");
+        for _ in 0..3 {
+            store.push("IU", "ANMO", &payload);
+        }
+        handle.shutdown_graceful(std::time::Duration::from_secs(5));
+
+        for seq in 1..=3 {
+            let f = client.next_frame().await.unwrap().unwrap();
+            assert_eq!(f.sequence(), SequenceNumber::new(seq));
+        }
+        // END marker then close
+        assert!(client.next_frame().await.unwrap().is_none());
+
+        // run() returns once the client has drained, well before the timeout
+        tokio::time::timeout(std::time::Duration::from_secs(1), run)
+            .await
+            .expect("run() should return after drain")
+            .unwrap();
+    }
+
+    // ---- Test 37: graceful_shutdown_closes_idle_clients ----
+
+    #[tokio::test]
+    async fn graceful_shutdown_closes_idle_clients() {
+        let (_store, addr, handle) = start_server_with_shutdown().await;
+
+        let stream = TcpStream::connect(&addr).await.unwrap();
+        let (read_half, _write_half) = stream.into_split();
+        let mut reader = BufReader::new(read_half);
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+        handle.shutdown_graceful(std::time::Duration::from_secs(5));
+
+        let mut line = String::new();
+        let n = tokio::time::timeout(
+            std::time::Duration::from_millis(500),
+            reader.read_line(&mut line),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(n, 0, "expected EOF for idle client, got {line:?}");
+    }
+
+    /// Send a command line and return the single-line response.
+    async fn command(
+        reader: &mut BufReader<tokio::net::tcp::OwnedReadHalf>,
+        writer: &mut tokio::net::tcp::OwnedWriteHalf,
+        cmd: &str,
+    ) -> String {
+        writer
+            .write_all(format!("{cmd}\r\n").as_bytes())
+            .await
+            .unwrap();
+        let mut line = String::new();
+        reader.read_line(&mut line).await.unwrap();
+        line
+    }
+
+    /// Read one v4 data frame for station id `IU_ANMO` and return its sequence.
+    async fn read_v4_sequence(reader: &mut BufReader<tokio::net::tcp::OwnedReadHalf>) -> u64 {
+        use seedlink_rs_protocol::frame::v4;
+        let mut frame = vec![0u8; v4::MIN_HEADER_LEN + "IU_ANMO".len() + v3::PAYLOAD_LEN];
+        tokio::io::AsyncReadExt::read_exact(reader, &mut frame)
+            .await
+            .unwrap();
+        let (raw, _) = v4::parse(&frame).unwrap();
+        raw.sequence().value()
+    }
+
+    // ---- Test 38: v4_fetch_keeps_connection_open ----
+
+    #[tokio::test]
+    async fn v4_fetch_keeps_connection_open() {
+        let (store, addr) = start_server().await;
+        store.push("IU", "ANMO", &make_payload("ANMO", "IU"));
+
+        let stream = TcpStream::connect(&addr).await.unwrap();
+        let (read_half, mut write_half) = stream.into_split();
+        let mut reader = BufReader::new(read_half);
+
+        for cmd in ["SLPROTO 4.0", "STATION ANMO IU", "DATA"] {
+            let line = command(&mut reader, &mut write_half, cmd).await;
+            assert!(line.starts_with("OK"), "{cmd}: {line:?}");
+        }
+
+        // First fetch window: buffered record, then END marker
+        write_half.write_all(b"FETCH\r\n").await.unwrap();
+        assert_eq!(read_v4_sequence(&mut reader).await, 1);
+        let mut line = String::new();
+        reader.read_line(&mut line).await.unwrap();
+        assert_eq!(line, "END\r\n");
+
+        // Connection stays open and accepts commands
+        store.push("IU", "ANMO", &make_payload("ANMO", "IU"));
+        let line = command(&mut reader, &mut write_half, "USERAGENT test/1.0").await;
+        assert!(line.starts_with("OK"), "expected OK, got: {line:?}");
+
+        // Second window resumes after the last record sent
+        write_half.write_all(b"FETCH\r\n").await.unwrap();
+        assert_eq!(read_v4_sequence(&mut reader).await, 2);
+        let mut line = String::new();
+        reader.read_line(&mut line).await.unwrap();
+        assert_eq!(line, "END\r\n");
+    }
+
+    // ---- Test 39: v4_endfetch_sends_buffered_then_closes ----
+
+    #[tokio::test]
+    async fn v4_endfetch_sends_buffered_then_closes() {
+        let (store, addr) = start_server().await;
+        store.push("IU", "ANMO", &make_payload("ANMO", "IU"));
+
+        let stream = TcpStream::connect(&addr).await.unwrap();
+        let (read_half, mut write_half) = stream.into_split();
+        let mut reader = BufReader::new(read_half);
+
+        for cmd in ["SLPROTO 4.0", "STATION ANMO IU", "DATA"] {
+            let line = command(&mut reader, &mut write_half, cmd).await;
+            assert!(line.starts_with("OK"), "{cmd}: {line:?}");
+        }
+
+        write_half.write_all(b"ENDFETCH\r\n").await.unwrap();
+        assert_eq!(read_v4_sequence(&mut reader).await, 1);
+
+        let mut rest = Vec::new();
+        tokio::io::AsyncReadExt::read_to_end(&mut reader, &mut rest)
+            .await
+            .unwrap();
+        assert!(rest.is_empty(), "expected EOF after ENDFETCH, got {rest:?}");
+    }
+
+    // ---- Test 40: client_fetch_v4_polls_without_reconnect ----
+
+    #[tokio::test]
+    async fn client_fetch_v4_polls_without_reconnect() {
+        let (store, addr) = start_server().await;
+        store.push("IU", "ANMO", &make_payload("ANMO", "IU"));
+
+        let mut client = SeedLinkClient::connect(&addr).await.unwrap();
+        client.station("ANMO", "IU").await.unwrap();
+        client.data().await.unwrap();
+
+        client.fetch_v4().await.unwrap();
+        let f = client.next_frame().await.unwrap().unwrap();
+        assert_eq!(f.sequence(), SequenceNumber::new(1));
+        assert!(client.next_frame().await.unwrap().is_none());
+        assert_eq!(client.state(), ClientState::Configured);
+
+        // Poll again: only the new record
+        store.push("IU", "ANMO", &make_payload("ANMO", "IU"));
+        client.fetch_v4().await.unwrap();
+        let f = client.next_frame().await.unwrap().unwrap();
+        assert_eq!(f.sequence(), SequenceNumber::new(2));
+        assert!(client.next_frame().await.unwrap().is_none());
+
+        client.end_fetch().await.unwrap();
+        assert!(client.next_frame().await.unwrap().is_none());
+        assert_eq!(client.state(), ClientState::Disconnected);
+    }
+
+    // ---- Test 41: data_source_restarted_and_streamed ----
+
+    /// Panics on its first run, then pushes one record per run.
+    struct PanicOnce(std::sync::atomic::AtomicBool);
+
+    impl DataSource for PanicOnce {
+        fn name(&self) -> &str {
+            "panic-once"
+        }
+
+        fn run(&self, store: DataStore) -> source::BoxFuture<'_, Result<()>> {
+            Box::pin(async move {
+                if !self.0.swap(true, std::sync::atomic::Ordering::SeqCst) {
+                    panic!("source crashed");
+                }
+                store.push("IU", "ANMO", &make_payload("ANMO", "IU"));
+                Ok(())
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn data_source_restarted_and_streamed() {
+        let config = ServerConfig {
+            source_restart_delay: std::time::Duration::from_millis(10),
+            ..ServerConfig::default()
+        };
+        let mut server = SeedLinkServer::bind_with_config("127.0.0.1:0", config)
+            .await
+            .unwrap();
+        server.add_source(Box::new(PanicOnce(Default::default())));
+        let addr = server.local_addr().unwrap().to_string();
+        tokio::spawn(server.run());
+
+        let mut client = SeedLinkClient::connect(&addr).await.unwrap();
+        client.station("ANMO", "IU").await.unwrap();
+        client.data().await.unwrap();
+        client.end_stream().await.unwrap();
+
+        // Record pushed by the restarted source
+        let f = client.next_frame().await.unwrap().unwrap();
+        assert_eq!(f.sequence(), SequenceNumber::new(1));
+    }
+
+    // ---- Test 42: udp_source_senders_in_metrics ----
+
+    #[tokio::test]
+    async fn udp_source_senders_in_metrics() {
+        let udp = UdpSource::bind(UdpConfig::new("127.0.0.1:0".parse().unwrap()))
+            .await
+            .unwrap();
+        let target = udp.local_addr().unwrap();
+        let mut server = SeedLinkServer::bind("127.0.0.1:0").await.unwrap();
+        server.add_source(Box::new(udp));
+        let metrics = server.metrics();
+        tokio::spawn(server.run());
+
+        let sender = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let mut record = make_payload("ANMO", "IU");
+        record[0..6].copy_from_slice(b"000001");
+        record[6] = b'D';
+        sender.send_to(&record, target).await.unwrap();
+
+        let snap = tokio::time::timeout(std::time::Duration::from_secs(2), async {
+            loop {
+                let snap = metrics.snapshot();
+                if snap.records_pushed == 1 {
+                    return snap;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(snap.senders.len(), 1);
+        assert_eq!(snap.senders[0].addr, sender.local_addr().unwrap());
+        assert_eq!(snap.senders[0].records, 1);
+    }
+
+    // ---- Test 43: extreply_capability_negotiation ----
+
+    #[tokio::test]
+    async fn extreply_capability_negotiation() {
+        let (_store, addr) = start_server().await;
+
+        let stream = TcpStream::connect(&addr).await.unwrap();
+        let (read_half, mut write_half) = stream.into_split();
+        let mut reader = BufReader::new(read_half);
+        let mut line = String::new();
+
+        write_half.write_all(b"HELLO\r\n").await.unwrap();
+        reader.read_line(&mut line).await.unwrap();
+        assert!(line.contains("CAP EXTREPLY"), "HELLO: {line:?}");
+        reader.read_line(&mut line).await.unwrap();
+
+        // Classic v3: bare ERROR
+        write_half.write_all(b"SELECT\r\n").await.unwrap();
+        line.clear();
+        reader.read_line(&mut line).await.unwrap();
+        assert_eq!(line, "ERROR\r\n");
+
+        // Unknown capabilities are ignored
+        write_half
+            .write_all(b"CAPABILITIES extreply NSWILDCARD\r\n")
+            .await
+            .unwrap();
+        line.clear();
+        reader.read_line(&mut line).await.unwrap();
+        assert_eq!(line, "OK\r\n");
+
+        write_half.write_all(b"SELECT !\r\n").await.unwrap();
+        line.clear();
+        reader.read_line(&mut line).await.unwrap();
+        assert_eq!(line, "ERROR UNSUPPORTED invalid SELECT pattern: !\r\n");
+    }
+
+    // ---- Test 44: client_user_agent_in_info_connections ----
+
+    #[tokio::test]
+    async fn client_user_agent_in_info_connections() {
+        let (_store, addr) = start_server().await;
+
+        let config = ClientConfig {
+            user_agent: Some("my-app/2.1".to_owned()),
+            ..ClientConfig::default()
+        };
+        let agent = SeedLinkClient::connect_with_config(&addr, config)
+            .await
+            .unwrap();
+        assert_eq!(agent.version(), seedlink_rs_protocol::ProtocolVersion::V4);
+
+        let config = ClientConfig {
+            prefer_v4: false,
+            ..ClientConfig::default()
+        };
+        let mut client = SeedLinkClient::connect_with_config(&addr, config)
+            .await
+            .unwrap();
+        let frames = client
+            .info(seedlink_rs_protocol::InfoLevel::Connections)
+            .await
+            .unwrap();
+        let xml = seedlink_rs_client::reassemble_xml(&frames);
+        assert!(xml.contains("useragent=\"my-app/2.1\""), "{xml}");
+        // The v3 client sent no USERAGENT
+        assert!(xml.contains("useragent=\"\""), "{xml}");
+    }
+
+    // ---- Test 45: acl_denies_station_and_filters_info ----
+
+    #[tokio::test]
+    async fn acl_denies_station_and_filters_info() {
+        let config = ServerConfig {
+            acl: Acl::new()
+                .rule(AclRule::allow(
+                    AclSubject::Identity("alice".into()),
+                    "XX",
+                    "*",
+                ))
+                .rule(AclRule::deny(AclSubject::Any, "XX", "*"))
+                .user("alice", "secret"),
+            ..ServerConfig::default()
+        };
+        let (store, addr) = start_server_with_config(config).await;
+        store.push("IU", "ANMO", &make_payload("ANMO", "IU"));
+        store.push("XX", "STA1", &make_payload("STA1", "XX"));
+
+        let mut client = SeedLinkClient::connect(&addr).await.unwrap();
+        let stations = client.info_stations().await.unwrap();
+        assert_eq!(stations.len(), 1);
+        assert_eq!(stations[0].station, "ANMO");
+        let err = client.station("STA1", "XX").await.unwrap_err();
+        assert!(err.to_string().contains("UNAUTHORIZED"), "{err}");
+        client.station("ANMO", "IU").await.unwrap();
+
+        // After AUTH the identity rule allows XX
+        let stream = TcpStream::connect(&addr).await.unwrap();
+        let (read_half, mut write_half) = stream.into_split();
+        let mut reader = BufReader::new(read_half);
+        let mut line = String::new();
+        for (cmd, reply) in [
+            ("SLPROTO 4.0", "OK\r\n"),
+            (
+                "AUTH USERPASS alice wrong",
+                "ERROR AUTH authentication failed\r\n",
+            ),
+            (
+                "STATION STA1 XX",
+                "ERROR UNAUTHORIZED access to XX_STA1 denied\r\n",
+            ),
+            ("AUTH USERPASS alice secret", "OK\r\n"),
+            ("STATION STA1 XX", "OK\r\n"),
+        ] {
+            write_half
+                .write_all(format!("{cmd}\r\n").as_bytes())
+                .await
+                .unwrap();
+            line.clear();
+            reader.read_line(&mut line).await.unwrap();
+            assert_eq!(line, reply, "{cmd}");
+        }
+    }
+
+    // ---- Test 46: kick_disconnects_streaming_and_idle_clients ----
+
+    #[tokio::test]
+    async fn kick_disconnects_streaming_and_idle_clients() {
+        let server = SeedLinkServer::bind("127.0.0.1:0").await.unwrap();
+        let addr = server.local_addr().unwrap().to_string();
+        let store = server.store().clone();
+        let manager = server.connections();
+        tokio::spawn(server.run());
+        store.push("IU", "ANMO", &make_payload("ANMO", "IU"));
+
+        let mut streaming = SeedLinkClient::connect(&addr).await.unwrap();
+        streaming.station("ANMO", "IU").await.unwrap();
+        streaming.data().await.unwrap();
+        streaming.end_stream().await.unwrap();
+        streaming.next_frame().await.unwrap().unwrap();
+
+        let mut idle = SeedLinkClient::connect(&addr).await.unwrap();
+
+        let list = manager.list();
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].state, "Streaming");
+        assert_eq!(list[0].frames_sent, 1);
+        assert_eq!(
+            list[0].protocol_version,
+            seedlink_rs_protocol::ProtocolVersion::V4
+        );
+        assert_eq!(list[1].state, "Connected");
+        let (streaming_id, idle_id) = (list[0].id, list[1].id);
+
+        assert!(manager.kick(streaming_id));
+        let next = tokio::time::timeout(Duration::from_secs(5), streaming.next_frame())
+            .await
+            .expect("kicked client not closed");
+        assert!(!matches!(next, Ok(Some(_))), "unexpected frame: {next:?}");
+
+        assert!(manager.kick(idle_id));
+        let info = tokio::time::timeout(Duration::from_secs(5), idle.info_stations())
+            .await
+            .expect("kicked client not closed");
+        assert!(info.is_err());
+
+        for _ in 0..50 {
+            if manager.list().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(manager.list().is_empty());
+        assert!(!manager.kick(streaming_id));
+    }
+
+    // ---- Test 47: zstd_compressed_v4_payloads ----
+
+    #[cfg(feature = "zstd")]
+    #[tokio::test]
+    async fn zstd_compressed_v4_payloads() {
+        use seedlink_rs_protocol::compression;
+        use seedlink_rs_protocol::frame::v4;
+        use tokio::io::AsyncReadExt;
+
+        let (store, addr) = start_server().await;
+        let payload = make_payload("ANMO", "IU");
+        store.push("IU", "ANMO", &payload);
+
+        let stream = TcpStream::connect(&addr).await.unwrap();
+        let (read_half, mut write_half) = stream.into_split();
+        let mut reader = BufReader::new(read_half);
+        let mut line = String::new();
+
+        write_half.write_all(b"HELLO\r\n").await.unwrap();
+        reader.read_line(&mut line).await.unwrap();
+        assert!(line.contains("ZSTD"), "{line}");
+        reader.read_line(&mut line).await.unwrap();
+
+        for cmd in [
+            "CAPABILITIES ZSTD",
+            "SLPROTO 4.0",
+            "STATION IU_ANMO",
+            "DATA",
+        ] {
+            write_half
+                .write_all(format!("{cmd}\r\n").as_bytes())
+                .await
+                .unwrap();
+            line.clear();
+            reader.read_line(&mut line).await.unwrap();
+            assert_eq!(line, "OK\r\n", "{cmd}");
+        }
+        write_half.write_all(b"END\r\n").await.unwrap();
+
+        let mut header = [0u8; v4::MIN_HEADER_LEN];
+        reader.read_exact(&mut header).await.unwrap();
+        let payload_len = u32::from_le_bytes(header[4..8].try_into().unwrap()) as usize;
+        let mut rest = vec![0u8; header[16] as usize + payload_len];
+        reader.read_exact(&mut rest).await.unwrap();
+        let frame = [&header[..], &rest].concat();
+        let (raw, _) = v4::parse(&frame).unwrap();
+        assert!(payload_len < payload.len());
+        assert_eq!(compression::decompress(raw.payload()).unwrap(), payload);
+    }
+
+    // ---- Test 48: keepalive_to_idle_streaming_clients ----
+
+    #[tokio::test]
+    async fn keepalive_to_idle_streaming_clients() {
+        let config = ServerConfig {
+            keepalive_interval: Some(Duration::from_millis(50)),
+            ..ServerConfig::default()
+        };
+        let (store, addr) = start_server_with_config(config).await;
+        store.push("IU", "ANMO", &make_payload("ANMO", "IU"));
+
+        for prefer_v4 in [true, false] {
+            let config = ClientConfig {
+                prefer_v4,
+                ..ClientConfig::default()
+            };
+            let mut client = SeedLinkClient::connect_with_config(&addr, config)
+                .await
+                .unwrap();
+            client.station("ANMO", "IU").await.unwrap();
+            client.data().await.unwrap();
+            client.end_stream().await.unwrap();
+
+            let event = client.next_event().await.unwrap();
+            assert!(matches!(event, StreamEvent::Frame(_)));
+            // Nothing more to send: keepalives follow
+            for _ in 0..2 {
+                let event = tokio::time::timeout(Duration::from_secs(5), client.next_event())
+                    .await
+                    .unwrap()
+                    .unwrap();
+                assert_eq!(event, StreamEvent::KeepAlive, "prefer_v4={prefer_v4}");
+            }
+        }
+    }
+
+    // ---- Test 49: idle_clients_time_out ----
+
+    #[tokio::test]
+    async fn idle_clients_time_out() {
+        let config = ServerConfig {
+            ring_capacity: 20_000,
+            command_timeout: Some(Duration::from_millis(100)),
+            stream_idle_timeout: Some(Duration::from_millis(200)),
+            ..ServerConfig::default()
+        };
+        let server = SeedLinkServer::bind_with_config("127.0.0.1:0", config)
+            .await
+            .unwrap();
+        let addr = server.local_addr().unwrap().to_string();
+        let store = server.store().clone();
+        let metrics = server.metrics();
+        let mut events = server.events();
+        tokio::spawn(server.run());
+
+        // Command phase: ERROR, then EOF
+        let stream = TcpStream::connect(&addr).await.unwrap();
+        let (read_half, mut write_half) = stream.into_split();
+        let mut reader = BufReader::new(read_half);
+        let mut line = String::new();
+        write_half.write_all(b"SLPROTO 4.0\r\n").await.unwrap();
+        reader.read_line(&mut line).await.unwrap();
+        assert_eq!(line, "OK\r\n");
+        line.clear();
+        let n = tokio::time::timeout(Duration::from_secs(5), reader.read_line(&mut line))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(&line[..n], "ERROR LIMIT idle timeout\r\n");
+        line.clear();
+        assert_eq!(reader.read_line(&mut line).await.unwrap(), 0);
+
+        // Streaming phase: a client that never reads is closed once the
+        // socket buffers are full
+        let payload = make_payload("ANMO", "IU");
+        for _ in 0..20_000 {
+            store.push("IU", "ANMO", &payload);
+        }
+        let stream = TcpStream::connect(&addr).await.unwrap();
+        let (_read_half, mut write_half) = stream.into_split();
+        write_half
+            .write_all(b"STATION ANMO IU\r\nDATA\r\nEND\r\n")
+            .await
+            .unwrap();
+
+        for _ in 0..500 {
+            if metrics.snapshot().clients_timed_out == 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(metrics.snapshot().clients_timed_out, 2);
+
+        let mut reasons = Vec::new();
+        while reasons.len() < 2 {
+            let event = tokio::time::timeout(Duration::from_secs(5), events.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            if let ServerEvent::ClientDisconnected { reason, .. } = event {
+                reasons.push(reason);
+            }
+        }
+        assert_eq!(
+            reasons,
+            [DisconnectReason::TimedOut, DisconnectReason::SlowConsumer]
+        );
+    }
+
+    // ---- Test 50: uni_station_mode ----
+
+    #[tokio::test]
+    async fn uni_station_mode() {
+        async fn stream_without_station(
+            addr: &str,
+        ) -> (
+            BufReader<tokio::net::tcp::OwnedReadHalf>,
+            tokio::net::tcp::OwnedWriteHalf,
+        ) {
+            let stream = TcpStream::connect(addr).await.unwrap();
+            let (read_half, mut write_half) = stream.into_split();
+            let mut reader = BufReader::new(read_half);
+            let line = command(&mut reader, &mut write_half, "DATA").await;
+            assert_eq!(line, "OK\r\n");
+            write_half.write_all(b"END\r\n").await.unwrap();
+            (reader, write_half)
+        }
+
+        async fn next_station(
+            reader: &mut BufReader<tokio::net::tcp::OwnedReadHalf>,
+        ) -> (u64, String) {
+            let mut frame = [0u8; v3::FRAME_LEN];
+            tokio::io::AsyncReadExt::read_exact(reader, &mut frame)
+                .await
+                .unwrap();
+            let raw = v3::parse(&frame).unwrap();
+            let station = String::from_utf8_lossy(&raw.payload()[8..13]);
+            (raw.sequence().value(), station.trim_end().to_owned())
+        }
+
+        // No default station: everything the ACL allows
+        let config = ServerConfig {
+            acl: Acl::new().rule(AclRule::deny(AclSubject::Any, "XX", "*")),
+            ..ServerConfig::default()
+        };
+        let (store, addr) = start_server_with_config(config).await;
+        store.push("IU", "ANMO", &make_payload("ANMO", "IU"));
+        store.push("XX", "STA1", &make_payload("STA1", "XX"));
+        store.push("GE", "WLF", &make_payload("WLF", "GE"));
+
+        let (mut reader, _writer) = stream_without_station(&addr).await;
+        assert_eq!(next_station(&mut reader).await, (1, "ANMO".to_owned()));
+        assert_eq!(next_station(&mut reader).await, (3, "WLF".to_owned()));
+        store.push("XX", "STA1", &make_payload("STA1", "XX"));
+        store.push("GE", "WLF", &make_payload("WLF", "GE"));
+        assert_eq!(next_station(&mut reader).await, (5, "WLF".to_owned()));
+
+        // Configured default station
+        let config = ServerConfig {
+            default_station: Some(("IU".to_owned(), "ANMO".to_owned())),
+            ..ServerConfig::default()
+        };
+        let (store, addr) = start_server_with_config(config).await;
+        store.push("GE", "WLF", &make_payload("WLF", "GE"));
+        store.push("IU", "ANMO", &make_payload("ANMO", "IU"));
+
+        let (mut reader, _writer) = stream_without_station(&addr).await;
+        assert_eq!(next_station(&mut reader).await, (2, "ANMO".to_owned()));
+    }
+
+    // ---- Test 51: per_station_resume_sequences ----
+
+    #[tokio::test]
+    async fn per_station_resume_sequences() {
+        let (store, addr) = start_server().await;
+        for _ in 0..3 {
+            store.push("IU", "ANMO", &make_payload("ANMO", "IU"));
+            store.push("GE", "WLF", &make_payload("WLF", "GE"));
+        }
+
+        // ANMO has 1, 3, 5 and WLF 2, 4, 6: each resumes from its own DATA seq
+        let config = ClientConfig {
+            prefer_v4: false,
+            ..ClientConfig::default()
+        };
+        let mut client = SeedLinkClient::connect_with_config(&addr, config)
+            .await
+            .unwrap();
+        client.station("ANMO", "IU").await.unwrap();
+        client.data_from(SequenceNumber::new(3)).await.unwrap();
+        client.station("WLF", "GE").await.unwrap();
+        client.data_from(SequenceNumber::new(2)).await.unwrap();
+        client.fetch().await.unwrap();
+
+        let mut received = Vec::new();
+        while let Some(frame) = client.next_frame().await.unwrap() {
+            received.push(frame.sequence().value());
+        }
+        assert_eq!(received, vec![4, 5, 6]);
+    }
+
+    // ---- Test 52: v4_data_all_and_newest ----
+
+    #[tokio::test]
+    async fn v4_data_all_and_newest() {
+        let (store, addr) = start_server().await;
+        store.push("IU", "ANMO", &make_payload("ANMO", "IU"));
+        store.push("GE", "WLF", &make_payload("WLF", "GE"));
+
+        let mut client = SeedLinkClient::connect(&addr).await.unwrap();
+        assert_eq!(client.version(), seedlink_rs_protocol::ProtocolVersion::V4);
+        client.station("ANMO", "IU").await.unwrap();
+        client.data_all().await.unwrap();
+        client.station("WLF", "GE").await.unwrap();
+        client.data_newest().await.unwrap();
+        client.end_stream().await.unwrap();
+
+        // WLF skips its buffered record 2 and starts with new data
+        let first = client.next_frame().await.unwrap().unwrap();
+        assert_eq!(first.sequence(), SequenceNumber::new(1));
+        store.push("GE", "WLF", &make_payload("WLF", "GE"));
+        let second = client.next_frame().await.unwrap().unwrap();
+        assert_eq!(second.sequence(), SequenceNumber::new(3));
+    }
+
+    // ---- Test 53: command_line_and_rate_limits ----
+
+    #[tokio::test]
+    async fn command_line_and_rate_limits() {
+        let config = ServerConfig {
+            max_command_len: 64,
+            max_commands_per_second: Some(5),
+            ..ServerConfig::default()
+        };
+        let server = SeedLinkServer::bind_with_config("127.0.0.1:0", config)
+            .await
+            .unwrap();
+        let addr = server.local_addr().unwrap().to_string();
+        let metrics = server.metrics();
+        tokio::spawn(server.run());
+
+        let exchange = |request: Vec<u8>| {
+            let addr = addr.clone();
+            async move {
+                let stream = TcpStream::connect(&addr).await.unwrap();
+                let (read_half, mut write_half) = stream.into_split();
+                let mut reader = BufReader::new(read_half);
+                write_half.write_all(&request).await.unwrap();
+                // Read until the server closes; unread request bytes may turn
+                // the close into a reset, which also ends the reply
+                let mut reply = String::new();
+                while let Ok(Ok(n)) =
+                    tokio::time::timeout(Duration::from_secs(5), reader.read_line(&mut reply)).await
+                    && n > 0
+                {}
+                reply
+            }
+        };
+
+        // A line of exactly the limit is accepted
+        let long_ok = format!("SLPROTO 4.0\r\nUSERAGENT {}\r\nBYE\r\n", "x".repeat(54));
+        assert_eq!(exchange(long_ok.into_bytes()).await, "OK\r\nOK\r\n");
+
+        // An endless line with no newline is cut off and refused
+        let mut endless = b"SLPROTO 4.0\r\n".to_vec();
+        endless.extend(std::iter::repeat_n(b'A', 100_000));
+        assert_eq!(
+            exchange(endless).await,
+            "OK\r\nERROR ARGUMENTS command line too long\r\n"
+        );
+
+        // Six commands within a second: the sixth is refused
+        let flood = "SLPROTO 4.0\r\n".to_owned() + &"HELLO\r\n".repeat(10);
+        let reply = exchange(flood.into_bytes()).await;
+        assert_eq!(reply.matches("SeedLink").count(), 4, "{reply}");
+        assert!(
+            reply.ends_with("ERROR LIMIT too many commands\r\n"),
+            "{reply}"
+        );
+
+        assert_eq!(metrics.snapshot().clients_limited, 2);
+    }
+
+    // ---- Test 54: logs_carry_connection_span ----
+
+    /// Log sink shared with a `tracing_subscriber::fmt` subscriber.
+    #[derive(Clone, Default)]
+    struct Logs(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Logs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Logs {
+        fn text(&self) -> String {
+            String::from_utf8_lossy(&self.0.lock().unwrap()).into_owned()
+        }
+    }
+
+    #[tokio::test]
+    async fn logs_carry_connection_span() {
+        let logs = Logs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::DEBUG)
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+        // Current-thread runtime: the server tasks log through this subscriber
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let (store, addr) = start_server().await;
+        store.push("IU", "ANMO", &make_payload("ANMO", "IU"));
+
+        let config = ClientConfig {
+            user_agent: Some("span-test".into()),
+            ..ClientConfig::default()
+        };
+        let mut client = SeedLinkClient::connect_with_config(&addr, config)
+            .await
+            .unwrap();
+        client.station("ANMO", "IU").await.unwrap();
+        client.data().await.unwrap();
+        client.fetch_v4().await.unwrap();
+        assert!(client.next_frame().await.unwrap().is_some());
+        assert!(client.next_frame().await.unwrap().is_none());
+        client.close().await.unwrap();
+
+        for _ in 0..500 {
+            if logs.text().contains("client disconnected") {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let text = logs.text();
+        let accepted = text.lines().find(|l| l.contains("accepted connection"));
+        assert!(accepted.is_some_and(|l| l.contains("conn_id=1")), "{text}");
+        let disconnected = text
+            .lines()
+            .find(|l| l.contains("client disconnected"))
+            .unwrap_or_else(|| panic!("{text}"));
+        assert!(
+            disconnected.contains("client{conn_id=1 peer=127.0.0.1:"),
+            "{disconnected}"
+        );
+        assert!(
+            disconnected.contains(" version=V4 user_agent=\"span-test\"}"),
+            "{text}"
+        );
+        let stopped = text
+            .lines()
+            .find(|l| l.contains("streaming stopped"))
+            .unwrap_or_else(|| panic!("{text}"));
+        assert!(stopped.contains(":stream{from=0 to=1}"), "{stopped}");
+    }
+
+    // ---- Test 55: server_events_bus ----
+
+    #[tokio::test]
+    async fn server_events_bus() {
+        let server = SeedLinkServer::bind("127.0.0.1:0").await.unwrap();
+        let addr = server.local_addr().unwrap().to_string();
+        let store = server.store().clone();
+        let handle = server.shutdown_handle();
+        let mut events = server.events();
+        tokio::spawn(server.run());
+
+        let mut next = async || {
+            tokio::time::timeout(Duration::from_secs(5), events.recv())
+                .await
+                .unwrap()
+                .unwrap()
+        };
+
+        // A client that says BYE
+        let client = SeedLinkClient::connect(&addr).await.unwrap();
+        assert!(matches!(
+            next().await,
+            ServerEvent::ClientConnected { id: 1, .. }
+        ));
+        client.close().await.unwrap();
+        assert!(matches!(
+            next().await,
+            ServerEvent::ClientDisconnected {
+                id: 1,
+                reason: DisconnectReason::Closed,
+                ..
+            }
+        ));
+
+        // A streaming client, cut off by shutdown
+        let mut client = SeedLinkClient::connect(&addr).await.unwrap();
+        client.station("ANMO", "IU").await.unwrap();
+        client.end_stream().await.unwrap();
+        store.push("IU", "ANMO", &make_payload("ANMO", "IU"));
+        client.next_frame().await.unwrap().unwrap();
+        assert!(matches!(
+            next().await,
+            ServerEvent::ClientConnected { id: 2, .. }
+        ));
+        assert_eq!(
+            next().await,
+            ServerEvent::SubscriptionAdded {
+                id: 2,
+                network: "IU".into(),
+                station: "ANMO".into(),
+            }
+        );
+        assert_eq!(
+            next().await,
+            ServerEvent::StreamStarted {
+                id: 2,
+                version: seedlink_rs_protocol::ProtocolVersion::V4,
+                fetch: false,
+            }
+        );
+
+        // Handler and accept loop see the signal independently: either order
+        handle.shutdown();
+        let (a, b) = (next().await, next().await);
+        let (shutdown, disconnected) = if matches!(a, ServerEvent::Shutdown { .. }) {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        assert_eq!(shutdown, ServerEvent::Shutdown { graceful: false });
+        assert!(matches!(
+            disconnected,
+            ServerEvent::ClientDisconnected {
+                id: 2,
+                reason: DisconnectReason::Shutdown,
+                ..
+            }
+        ));
+    }
+
+    // ---- Test 56: datalink_producer_to_seedlink_client ----
+
+    #[tokio::test]
+    async fn datalink_producer_to_seedlink_client() {
+        use seedlink_rs_client::{ClientError, DataLinkConfig as SinkConfig, DataLinkSink};
+
+        let acl = Acl::new().user("digitizer", "secret");
+        let config = DataLinkConfig::new("127.0.0.1:0".parse().unwrap()).acl(acl);
+        let source = DataLinkSource::bind(config).await.unwrap();
+        let datalink = source.local_addr().unwrap().to_string();
+        let mut server = SeedLinkServer::bind("127.0.0.1:0").await.unwrap();
+        let addr = server.local_addr().unwrap().to_string();
+        server.add_source(Box::new(source));
+        tokio::spawn(server.run());
+
+        let mut client = SeedLinkClient::connect(&addr).await.unwrap();
+        client.station("ANMO", "IU").await.unwrap();
+        client.end_stream().await.unwrap();
+
+        let record = make_payload("ANMO", "IU");
+        let frame = OwnedFrame::V3 {
+            sequence: SequenceNumber::new(1),
+            payload: record.clone(),
+        };
+        let mut sink = DataLinkSink::with_config(
+            &datalink,
+            SinkConfig {
+                credentials: Some(("digitizer".into(), "wrong".into())),
+                ..SinkConfig::default()
+            },
+        );
+        let err = sink.write(&frame).await.unwrap_err();
+        assert!(matches!(err, ClientError::ServerError(ref m) if m == "authentication failed"));
+
+        let mut sink = DataLinkSink::with_config(
+            &datalink,
+            SinkConfig {
+                credentials: Some(("digitizer".into(), "secret".into())),
+                ..SinkConfig::default()
+            },
+        );
+        sink.write(&frame).await.unwrap();
+        assert!(
+            sink.server_id()
+                .unwrap()
+                .starts_with("DataLink seedlink-rs:")
+        );
+
+        let received = tokio::time::timeout(Duration::from_secs(5), client.next_frame())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(received.payload(), &record[..]);
+    }
+
+    // ---- Test 57: v4_station_patterns_and_lists ----
+
+    #[tokio::test]
+    async fn v4_station_patterns_and_lists() {
+        use seedlink_rs_protocol::frame::v4;
+        use tokio::io::AsyncReadExt;
+
+        async fn next_station(reader: &mut BufReader<tokio::net::tcp::OwnedReadHalf>) -> String {
+            let mut header = [0u8; v4::MIN_HEADER_LEN];
+            reader.read_exact(&mut header).await.unwrap();
+            let header = v4::parse_header(&header).unwrap();
+            let mut rest = vec![0u8; header.station_id_len + header.payload_len];
+            reader.read_exact(&mut rest).await.unwrap();
+            String::from_utf8(rest[..header.station_id_len].to_vec()).unwrap()
+        }
+
+        let config = ServerConfig {
+            acl: Acl::new()
+                .default_action(AclAction::Deny)
+                .rule(AclRule::allow(AclSubject::Any, "IU", "*"))
+                .rule(AclRule::allow(AclSubject::Any, "GE", "*")),
+            ..ServerConfig::default()
+        };
+        let (store, addr) = start_server_with_config(config).await;
+        store.push("IU", "ANMO", &make_payload("ANMO", "IU"));
+        store.push("GE", "WLF", &make_payload("WLF", "GE"));
+        store.push("XX", "STA1", &make_payload("STA1", "XX"));
+
+        let stream = TcpStream::connect(&addr).await.unwrap();
+        let (read_half, mut write_half) = stream.into_split();
+        let mut reader = BufReader::new(read_half);
+        for (cmd, reply) in [
+            ("SLPROTO 4.0", "OK\r\n"),
+            (
+                "STATION IU_ANMO,XX_STA1",
+                "ERROR UNAUTHORIZED access to XX_STA1 denied\r\n",
+            ),
+            (
+                "STATION IU_ANMO,WLF",
+                "ERROR ARGUMENTS invalid station list: IU_ANMO,WLF\r\n",
+            ),
+            // Patterns pass the ACL check; records are filtered when sent
+            ("STATION IU_*,G?_W*", "OK\r\n"),
+            ("DATA", "OK\r\n"),
+        ] {
+            let line = command(&mut reader, &mut write_half, cmd).await;
+            assert_eq!(line, reply, "{cmd}");
+        }
+        write_half.write_all(b"END\r\n").await.unwrap();
+        assert_eq!(next_station(&mut reader).await, "IU_ANMO");
+        assert_eq!(next_station(&mut reader).await, "GE_WLF");
+
+        // Stations that appear after STATION match too
+        store.push("XX", "STA1", &make_payload("STA1", "XX"));
+        store.push("GE", "WAB", &make_payload("WAB", "GE"));
+        store.push("GE", "APE", &make_payload("APE", "GE"));
+        store.push("IU", "COLA", &make_payload("COLA", "IU"));
+        assert_eq!(next_station(&mut reader).await, "GE_WAB");
+        assert_eq!(next_station(&mut reader).await, "IU_COLA");
+
+        // `*_*` subscribes to every station the ACL allows
+        let mut client = SeedLinkClient::connect(&addr).await.unwrap();
+        client.station("*", "*").await.unwrap();
+        client.data().await.unwrap();
+        client.end_stream().await.unwrap();
+        let mut stations = Vec::new();
+        for _ in 0..5 {
+            let frame = client.next_frame().await.unwrap().unwrap();
+            let record = frame.decode_samples().unwrap();
+            stations.push(format!("{}_{}", record.network, record.station));
+        }
+        assert_eq!(
+            stations,
+            ["IU_ANMO", "GE_WLF", "GE_WAB", "GE_APE", "IU_COLA"]
+        );
+    }
+
+    // ---- Test 58: late_stations_reach_wildcard_subscribers ----
+
+    #[tokio::test]
+    async fn late_stations_reach_wildcard_subscribers() {
+        let (store, addr) = start_server().await;
+        store.push("IU", "ANMO", &make_payload("ANMO", "IU"));
+
+        let config = ClientConfig {
+            prefer_v4: false,
+            ..ClientConfig::default()
+        };
+        let mut client = SeedLinkClient::connect_with_config(&addr, config)
+            .await
+            .unwrap();
+        client.station("A*", "IU").await.unwrap();
+        client.select("BHZ").await.unwrap();
+        client.data().await.unwrap();
+
+        // New station between subscribing and END: found by the ring scan
+        store.push("IU", "ADK", &make_payload("ADK", "IU"));
+        store.push("IU", "COLA", &make_payload("COLA", "IU"));
+        client.end_stream().await.unwrap();
+
+        let mut next_station = async || {
+            let frame = tokio::time::timeout(Duration::from_secs(5), client.next_frame())
+                .await
+                .unwrap()
+                .unwrap()
+                .unwrap();
+            let record = frame.decode_samples().unwrap();
+            (frame.sequence().value(), record.station)
+        };
+        assert_eq!(next_station().await, (1, "ANMO".to_owned()));
+        assert_eq!(next_station().await, (2, "ADK".to_owned()));
+
+        // New stations while streaming: delivered live
+        store.push("GE", "APE", &make_payload("APE", "GE"));
+        store.push("IU", "AFI", &make_payload("AFI", "IU"));
+        assert_eq!(next_station().await, (5, "AFI".to_owned()));
+    }
+
+    // ---- Test 59: config_handle_reaches_connected_clients ----
+
+    #[tokio::test]
+    async fn config_handle_reaches_connected_clients() {
+        let server = SeedLinkServer::bind("127.0.0.1:0").await.unwrap();
+        let addr = server.local_addr().unwrap();
+        let handle = server.config_handle();
+        tokio::spawn(server.run());
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let (read_half, mut write_half) = stream.into_split();
+        let mut reader = BufReader::new(read_half);
+        let mut line = String::new();
+        let mut send = async |cmd: &str, lines: usize| {
+            write_half
+                .write_all(format!("{cmd}\r\n").as_bytes())
+                .await
+                .unwrap();
+            line.clear();
+            for _ in 0..lines {
+                reader.read_line(&mut line).await.unwrap();
+            }
+            line.clone()
+        };
+
+        assert!(send("HELLO", 2).await.ends_with("seedlink-rs\r\n"));
+        handle.update(|c| c.organization = "Reloaded".into());
+        assert!(send("HELLO", 2).await.ends_with("Reloaded\r\n"));
+        assert_eq!(send("STATION ANMO IU", 1).await, "OK\r\n");
+
+        // Reload from a full config: ACL and command limit change
+        handle.apply(&ServerConfig {
+            acl: Acl::new().rule(AclRule::deny(AclSubject::Any, "IU", "*")),
+            max_command_len: 16,
+            ..ServerConfig::default()
+        });
+        assert_eq!(handle.current().organization, "seedlink-rs");
+        assert_eq!(send("STATION ANMO IU", 1).await, "ERROR\r\n");
+        assert_eq!(send("STATION ANMO IU SOME MORE", 1).await, "ERROR\r\n");
+        line.clear();
+        assert_eq!(reader.read_line(&mut line).await.unwrap(), 0);
+    }
+
+    // ---- Test 60: supported_protocols_limit_negotiation ----
+
+    #[tokio::test]
+    async fn supported_protocols_limit_negotiation() {
+        async fn session(protocols: Vec<ProtocolVersion>, script: &[(&str, &str)]) {
+            let config = ServerConfig {
+                supported_protocols: protocols,
+                ..ServerConfig::default()
+            };
+            let (_store, addr) = start_server_with_config(config).await;
+            let stream = TcpStream::connect(&addr).await.unwrap();
+            let (read_half, mut write_half) = stream.into_split();
+            let mut reader = BufReader::new(read_half);
+            for &(cmd, reply) in script {
+                write_half
+                    .write_all(format!("{cmd}\r\n").as_bytes())
+                    .await
+                    .unwrap();
+                let mut line = String::new();
+                reader.read_line(&mut line).await.unwrap();
+                assert!(line.starts_with(reply), "{cmd}: {line:?}");
+                if cmd == "HELLO" {
+                    reader.read_line(&mut String::new()).await.unwrap();
+                }
+            }
+        }
+
+        session(
+            vec![ProtocolVersion::V3],
+            &[
+                ("HELLO", "SeedLink v3.1 :: SLPROTO:3.1 "),
+                ("SLPROTO 4.0", "ERROR"),
+                ("SLPROTO 3.1", "OK"),
+                ("STATION ANMO IU", "OK"),
+            ],
+        )
+        .await;
+        session(
+            vec![ProtocolVersion::V4],
+            &[
+                ("HELLO", "SeedLink v3.1 :: SLPROTO:4.0 "),
+                ("STATION ANMO IU", "ERROR"),
+                ("SLPROTO 3.1", "ERROR"),
+                ("SLPROTO 4.0", "OK"),
+                ("STATION IU_ANMO", "OK"),
+            ],
+        )
+        .await;
+
+        // The client falls back to v3 when 4.0 is not advertised
+        let config = ServerConfig {
+            supported_protocols: vec![ProtocolVersion::V3],
+            ..ServerConfig::default()
+        };
+        let (_store, addr) = start_server_with_config(config).await;
+        let client = SeedLinkClient::connect(&addr).await.unwrap();
+        assert_eq!(client.version(), ProtocolVersion::V3);
+    }
+
+    #[tokio::test]
+    async fn empty_supported_protocols_is_rejected() {
+        let config = || ServerConfig {
+            supported_protocols: Vec::new(),
+            ..ServerConfig::default()
+        };
+        assert!(matches!(
+            SeedLinkServer::new(config()),
+            Err(ServerError::InvalidConfig(_))
+        ));
+        assert!(matches!(
+            SeedLinkServer::bind_with_config("127.0.0.1:0", config()).await,
+            Err(ServerError::InvalidConfig(_))
+        ));
+    }
+
+    // ---- Test 61: max_bytes_per_second_throttles_streaming ----
+
+    #[tokio::test]
+    async fn max_bytes_per_second_throttles_streaming() {
+        let config = ServerConfig {
+            max_bytes_per_second: Some(2048),
+            ..ServerConfig::default()
+        };
+        let server = SeedLinkServer::bind_with_config("127.0.0.1:0", config)
+            .await
+            .unwrap();
+        let addr = server.local_addr().unwrap().to_string();
+        let store = server.store().clone();
+        let connections = server.connections();
+        tokio::spawn(server.run());
+        for _ in 0..8 {
+            store.push("IU", "ANMO", &make_payload("ANMO", "IU"));
+        }
+
+        let config = ClientConfig {
+            prefer_v4: false,
+            ..ClientConfig::default()
+        };
+        let mut client = SeedLinkClient::connect_with_config(&addr, config)
+            .await
+            .unwrap();
+        client.station("ANMO", "IU").await.unwrap();
+        client.data().await.unwrap();
+        let started = Instant::now();
+        client.end_stream().await.unwrap();
+
+        // One second's worth goes out at once, then the client waits
+        for _ in 0..3 {
+            client.next_frame().await.unwrap().unwrap();
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(connections.list()[0].throttled);
+        let mut info = SeedLinkClient::connect(&addr).await.unwrap();
+        let frames = info
+            .info(seedlink_rs_protocol::InfoLevel::Connections)
+            .await
+            .unwrap();
+        let xml = seedlink_rs_client::reassemble_xml(&frames);
+        assert!(xml.contains("throttled=\"1\""), "{xml}");
+
+        for _ in 3..8 {
+            client.next_frame().await.unwrap().unwrap();
+        }
+        // 8 × 520 bytes at 2048 B/s with a 2048-byte burst
+        assert!(started.elapsed() >= Duration::from_millis(900));
+    }
+
+    // ---- Test 62: recently_closed_records_disconnect_reasons ----
+
+    #[tokio::test]
+    async fn recently_closed_records_disconnect_reasons() {
+        let server = SeedLinkServer::bind("127.0.0.1:0").await.unwrap();
+        let addr = server.local_addr().unwrap().to_string();
+        let store = server.store().clone();
+        let manager = server.connections();
+        tokio::spawn(server.run());
+
+        // A streaming client that goes away without BYE
+        let mut raw = TcpStream::connect(&addr).await.unwrap();
+        raw.write_all(b"STATION ANMO IU\r\nDATA\r\nEND\r\n")
+            .await
+            .unwrap();
+        let mut ok = [0u8; 8];
+        tokio::io::AsyncReadExt::read_exact(&mut raw, &mut ok)
+            .await
+            .unwrap();
+        drop(raw);
+
+        let idle = SeedLinkClient::connect(&addr).await.unwrap();
+        let idle_id = manager.list().last().unwrap().id;
+        assert!(manager.kick(idle_id));
+        drop(idle);
+
+        for _ in 0..100 {
+            if manager.recently_closed().len() == 2 {
+                break;
+            }
+            store.push("IU", "ANMO", &make_payload("ANMO", "IU"));
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let mut reasons: Vec<_> = manager
+            .recently_closed()
+            .into_iter()
+            .map(|c| c.reason)
+            .collect();
+        reasons.sort_by_key(|r| r.as_str());
+        assert_eq!(
+            reasons,
+            [DisconnectReason::Kicked, DisconnectReason::PeerReset]
+        );
+
+        let mut client = SeedLinkClient::connect(&addr).await.unwrap();
+        let frames = client
+            .info(seedlink_rs_protocol::InfoLevel::Connections)
+            .await
+            .unwrap();
+        let xml = seedlink_rs_client::reassemble_xml(&frames);
+        assert!(xml.contains("reason=\"kicked\""), "{xml}");
+        assert!(xml.contains("reason=\"peer_reset\""), "{xml}");
+    }
+
+    // ---- Test 63: v4_select_syntax ----
+
+    #[tokio::test]
+    async fn v4_select_syntax() {
+        let (store, addr) = start_server().await;
+        for (loc, cha) in [(b"00", b"BHZ"), (b"  ", b"LOG"), (b"10", b"BHN")] {
+            let mut payload = make_payload("ANMO", "IU");
+            payload[13..15].copy_from_slice(loc);
+            payload[15..18].copy_from_slice(cha);
+            store.push("IU", "ANMO", &payload);
+        }
+
+        for (patterns, expected) in [
+            (&["!*_LOG"][..], &[1, 3][..]),
+            (&["10:BH?"], &[3]),
+            (&["*.D", "!B_H_Z"], &[2, 3]),
+        ] {
+            let mut client = SeedLinkClient::connect(&addr).await.unwrap();
+            assert_eq!(client.version(), ProtocolVersion::V4);
+            client.station("ANMO", "IU").await.unwrap();
+            for pattern in patterns {
+                client.select(pattern).await.unwrap();
+            }
+            client.data().await.unwrap();
+            client.end_stream().await.unwrap();
+            for &seq in expected {
+                let frame = client.next_frame().await.unwrap().unwrap();
+                assert_eq!(frame.sequence(), SequenceNumber::new(seq), "{patterns:?}");
+            }
+        }
+    }
+
+    // ---- Test 64: run_on_duplex_connections ----
+
+    #[tokio::test]
+    async fn run_on_duplex_connections() {
+        let server = SeedLinkServer::new(ServerConfig::default()).unwrap();
+        assert!(server.local_addr().is_err());
+        let store = server.store().clone();
+        let handle = server.shutdown_handle();
+        store.push("IU", "ANMO", &make_payload("ANMO", "IU"));
+
+        let (tx, rx) = tokio::sync::mpsc::channel(1);
+        let incoming = tokio_stream::wrappers::ReceiverStream::new(rx);
+        let task = tokio::spawn(server.run_on(incoming));
+
+        let (client, server_end) = tokio::io::duplex(64 * 1024);
+        let peer: SocketAddr = "192.0.2.7:4000".parse().unwrap();
+        tx.send((server_end, peer)).await.unwrap();
+        drop(tx);
+
+        let (read_half, mut write_half) = tokio::io::split(client);
+        let mut reader = BufReader::new(read_half);
+        for cmd in ["STATION ANMO IU", "DATA"] {
+            write_half
+                .write_all(format!("{cmd}\r\n").as_bytes())
+                .await
+                .unwrap();
+            let mut line = String::new();
+            reader.read_line(&mut line).await.unwrap();
+            assert!(line.starts_with("OK"), "{cmd}: {line:?}");
+        }
+        write_half.write_all(b"END\r\n").await.unwrap();
+        let mut frame = [0u8; v3::FRAME_LEN];
+        tokio::io::AsyncReadExt::read_exact(&mut reader, &mut frame)
+            .await
+            .unwrap();
+        let raw = v3::parse(&frame).unwrap();
+        assert_eq!(raw.sequence(), SequenceNumber::new(1));
+
+        // The ended stream stops accepting but keeps serving the client
+        store.push("IU", "ANMO", &make_payload("ANMO", "IU"));
+        tokio::io::AsyncReadExt::read_exact(&mut reader, &mut frame)
+            .await
+            .unwrap();
+        assert_eq!(
+            v3::parse(&frame).unwrap().sequence(),
+            SequenceNumber::new(2)
+        );
+
+        handle.shutdown();
+        tokio::time::timeout(std::time::Duration::from_secs(2), task)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    // ---- Test 65: from_listener_serves_existing_socket ----
+
+    #[tokio::test]
+    async fn from_listener_serves_existing_socket() {
+        let std_listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        std_listener.set_nonblocking(true).unwrap();
+        let listener = TcpListener::from_std(std_listener).unwrap();
+        let server = SeedLinkServer::from_listener(listener, ServerConfig::default()).unwrap();
+        let addr = server.local_addr().unwrap().to_string();
+        tokio::spawn(server.run());
+
+        let client = SeedLinkClient::connect(&addr).await.unwrap();
+        assert_eq!(client.server_info().software, "SeedLink");
+    }
+
+    // ---- Test 66: memory_transport_end_to_end ----
+
+    #[tokio::test]
+    async fn memory_transport_end_to_end() {
+        let (connector, incoming) = MemoryConnector::new();
+        let server = SeedLinkServer::new(ServerConfig::default()).unwrap();
+        let store = server.store().clone();
+        let handle = server.shutdown_handle();
+        let task = tokio::spawn(server.run_on(incoming));
+        for _ in 0..3 {
+            store.push("IU", "ANMO", &make_payload("ANMO", "IU"));
+        }
+
+        let io = connector.connect().unwrap();
+        let mut client = SeedLinkClient::connect_io(io, ClientConfig::default())
+            .await
+            .unwrap();
+        assert_eq!(client.version(), ProtocolVersion::V4);
+        client.station("ANMO", "IU").await.unwrap();
+        client.data().await.unwrap();
+        client.end_stream().await.unwrap();
+        for seq in 1..=3 {
+            let frame = client.next_frame().await.unwrap().unwrap();
+            assert_eq!(frame.sequence(), SequenceNumber::new(seq));
+        }
+
+        let mut info =
+            SeedLinkClient::connect_io(connector.connect().unwrap(), ClientConfig::default())
+                .await
+                .unwrap();
+        let frames = info
+            .info(seedlink_rs_protocol::InfoLevel::Connections)
+            .await
+            .unwrap();
+        let xml = seedlink_rs_client::reassemble_xml(&frames);
+        assert!(xml.contains("host=\"127.0.0.1:1\""), "{xml}");
+        assert!(xml.contains("host=\"127.0.0.1:2\""), "{xml}");
+
+        handle.shutdown();
+        task.await.unwrap();
+        let err = connector.connect().unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::ConnectionRefused);
+    }
+
+    // ---- Test 67: expired_resume_falls_back_to_time ----
+
+    #[tokio::test]
+    async fn expired_resume_falls_back_to_time() {
+        let config = ServerConfig {
+            ring_capacity: 2,
+            resume_miss: ResumeMissPolicy::Reject,
+            ..ServerConfig::default()
+        };
+        let server = SeedLinkServer::bind_with_config("127.0.0.1:0", config)
+            .await
+            .unwrap();
+        let addr = server.local_addr().unwrap().to_string();
+        let store = server.store().clone();
+        let metrics = server.metrics();
+        let mut events = server.events();
+        tokio::spawn(server.run());
+
+        // Jan to Apr 2024; Jan and Feb (seq 1 and 2) are evicted
+        for doy in [15, 46, 75, 106] {
+            let mut payload = make_payload("ANMO", "IU");
+            set_btime(&mut payload, 2024, doy, 12, 0, 0);
+            store.push("IU", "ANMO", &payload);
+        }
+
+        let stream = TcpStream::connect(&addr).await.unwrap();
+        let (read_half, mut write_half) = stream.into_split();
+        let mut reader = BufReader::new(read_half);
+        for cmd in ["SLPROTO 4.0", "STATION ANMO IU"] {
+            let line = command(&mut reader, &mut write_half, cmd).await;
+            assert!(line.starts_with("OK"), "{cmd}: {line:?}");
+        }
+
+        // No start time to fall back to: rejected
+        let line = command(&mut reader, &mut write_half, "DATA 1").await;
+        assert_eq!(line, "ERROR ARGUMENTS sequence 1 expired for IU_ANMO\r\n");
+        // Resuming after a buffered record is no miss
+        let line = command(&mut reader, &mut write_half, "DATA 2").await;
+        assert!(line.starts_with("OK"), "{line:?}");
+
+        // With a start time, the time window applies instead
+        let line = command(&mut reader, &mut write_half, "DATA 1 2024-04-01T00:00:00Z").await;
+        assert!(line.starts_with("OK"), "{line:?}");
+        write_half.write_all(b"FETCH\r\n").await.unwrap();
+        assert_eq!(read_v4_sequence(&mut reader).await, 4);
+
+        let mut missed = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            if let ServerEvent::ResumeMissed { fallback, .. } = event {
+                missed.push(fallback);
+            }
+        }
+        assert_eq!(missed, [false, true]);
+        assert_eq!(metrics.snapshot().resume_misses, 2);
+    }
 }
@@ -0,0 +1,85 @@
+//! In-memory client connections, without binding ports.
+
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+use tokio::io::DuplexStream;
+use tokio::sync::mpsc;
+
+/// Buffer of each direction of an in-memory connection.
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// Opens in-memory connections to a server run with
+/// [`SeedLinkServer::run_on()`](crate::SeedLinkServer::run_on), for tests that
+/// should not depend on free ports or the network stack:
+///
+/// ```no_run
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// use seedlink_rs_server::{MemoryConnector, SeedLinkServer, ServerConfig};
+///
+/// let (connector, incoming) = MemoryConnector::new();
+/// let server = SeedLinkServer::new(ServerConfig::default())?;
+/// tokio::spawn(server.run_on(incoming));
+///
+/// // Pass to `SeedLinkClient::connect_io()`, or speak the protocol directly
+/// let stream = connector.connect()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct MemoryConnector {
+    tx: mpsc::UnboundedSender<(DuplexStream, SocketAddr)>,
+    next_port: Arc<AtomicU16>,
+}
+
+/// The server side of a [`MemoryConnector`]: a stream of accepted
+/// connections for [`SeedLinkServer::run_on()`](crate::SeedLinkServer::run_on).
+/// Ends once every connector is dropped.
+#[derive(Debug)]
+pub struct MemoryIncoming {
+    rx: mpsc::UnboundedReceiver<(DuplexStream, SocketAddr)>,
+}
+
+impl MemoryConnector {
+    /// Create a connector and the stream its connections arrive on.
+    pub fn new() -> (Self, MemoryIncoming) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let connector = Self {
+            tx,
+            next_port: Arc::new(AtomicU16::new(1)),
+        };
+        (connector, MemoryIncoming { rx })
+    }
+
+    /// Open a connection the server sees coming from `127.0.0.1`, with a
+    /// port unique to this connector.
+    pub fn connect(&self) -> io::Result<DuplexStream> {
+        let port = self.next_port.fetch_add(1, Ordering::Relaxed);
+        self.connect_from(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port))
+    }
+
+    /// Open a connection the server sees coming from `peer`, e.g. to test
+    /// address-based ACL rules. Fails with
+    /// [`ConnectionRefused`](io::ErrorKind::ConnectionRefused) once the
+    /// server has stopped.
+    pub fn connect_from(&self, peer: SocketAddr) -> io::Result<DuplexStream> {
+        let (client, server) = tokio::io::duplex(BUFFER_SIZE);
+        self.tx.send((server, peer)).map_err(|_| {
+            io::Error::new(io::ErrorKind::ConnectionRefused, "server is not running")
+        })?;
+        Ok(client)
+    }
+}
+
+impl Stream for MemoryIncoming {
+    type Item = (DuplexStream, SocketAddr);
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.rx.poll_recv(cx)
+    }
+}
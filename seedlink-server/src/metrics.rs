@@ -0,0 +1,488 @@
+//! Server statistics for operators.
+//!
+//! [`ServerMetrics`] is a cheap handle obtained from
+//! [`SeedLinkServer::metrics()`](crate::SeedLinkServer::metrics). Counters are
+//! updated lock-free by the store and client handlers; [`ServerMetrics::snapshot()`]
+//! collects them into a plain [`MetricsSnapshot`].
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
+
+use crate::connections::ConnectionRegistry;
+use crate::source::SourceList;
+use crate::store::DataStore;
+
+/// Server-wide counters shared with client handlers.
+#[derive(Debug, Default)]
+pub(crate) struct Counters {
+    pub frames_sent: AtomicU64,
+    pub bytes_sent: AtomicU64,
+    pub clients_total: AtomicU64,
+    pub clients_timed_out: AtomicU64,
+    pub clients_limited: AtomicU64,
+    pub resume_misses: AtomicU64,
+}
+
+/// Per-client counters, shared between a handler and its registry entry.
+#[derive(Debug, Default)]
+pub(crate) struct ClientStats {
+    pub frames_sent: AtomicU64,
+    pub bytes_sent: AtomicU64,
+    /// The last write waited for `max_bytes_per_second`.
+    pub throttled: AtomicBool,
+}
+
+impl ClientStats {
+    /// Record `frames` data frames totalling `bytes` written to the client.
+    pub fn record_frames(&self, counters: &Counters, frames: usize, bytes: usize) {
+        self.frames_sent.fetch_add(frames as u64, Ordering::Relaxed);
+        self.bytes_sent.fetch_add(bytes as u64, Ordering::Relaxed);
+        counters
+            .frames_sent
+            .fetch_add(frames as u64, Ordering::Relaxed);
+        counters
+            .bytes_sent
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+}
+
+/// Handle for reading server statistics. Clone is cheap (Arc).
+#[derive(Clone)]
+pub struct ServerMetrics {
+    store: DataStore,
+    connections: ConnectionRegistry,
+    counters: Arc<Counters>,
+    sources: SourceList,
+}
+
+/// Point-in-time copy of all server statistics.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MetricsSnapshot {
+    /// Records pushed into the store since startup.
+    pub records_pushed: u64,
+    /// Records evicted from the ring buffer since startup.
+    pub records_evicted: u64,
+    /// Records dropped as duplicates since startup (see
+    /// [`StoreConfig::dedup_window`](crate::StoreConfig::dedup_window)).
+    pub records_duplicate: u64,
+    /// Records that failed validation since startup, dropped or flagged (see
+    /// [`StoreConfig::validation`](crate::StoreConfig::validation)).
+    pub records_invalid: u64,
+    /// Records currently in the ring buffer.
+    pub ring_len: usize,
+    /// Ring buffer capacity.
+    pub ring_capacity: usize,
+    /// Payload bytes of the records currently in the ring buffer.
+    pub ring_bytes: usize,
+    /// Ring buffer byte budget (`0` = no limit, see
+    /// [`ServerConfig::ring_capacity_bytes`](crate::ServerConfig::ring_capacity_bytes)).
+    pub ring_capacity_bytes: usize,
+    /// Data frames written to clients since startup.
+    pub frames_sent: u64,
+    /// Data frame bytes written to clients since startup.
+    pub bytes_sent: u64,
+    /// Currently connected clients.
+    pub clients_connected: usize,
+    /// Clients accepted since startup.
+    pub clients_total: u64,
+    /// Clients closed by an idle timeout since startup (see
+    /// [`ServerConfig::command_timeout`](crate::ServerConfig::command_timeout) and
+    /// [`ServerConfig::stream_idle_timeout`](crate::ServerConfig::stream_idle_timeout)).
+    pub clients_timed_out: u64,
+    /// Clients closed for an over-long command line or too many commands
+    /// since startup (see
+    /// [`ServerConfig::max_command_len`](crate::ServerConfig::max_command_len) and
+    /// [`ServerConfig::max_commands_per_second`](crate::ServerConfig::max_commands_per_second)).
+    pub clients_limited: u64,
+    /// Subscriptions resumed with `DATA seq`/`FETCH seq` from a sequence
+    /// whose successors were already evicted, since startup (see
+    /// [`ServerConfig::resume_miss`](crate::ServerConfig::resume_miss)).
+    pub resume_misses: u64,
+    /// Per-client statistics for connected clients.
+    pub clients: Vec<ClientMetrics>,
+    /// Per-sender statistics reported by data sources (e.g. UDP senders).
+    pub senders: Vec<SenderMetrics>,
+    /// Per-stream data latency for every stream seen since startup.
+    pub streams: Vec<StreamMetrics>,
+}
+
+/// Statistics for one connected client.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClientMetrics {
+    /// Client address.
+    pub addr: SocketAddr,
+    /// Connection state (`Connected`, `Configured`, `Streaming`).
+    pub state: String,
+    /// Data frames written to this client.
+    pub frames_sent: u64,
+    /// Data frame bytes written to this client.
+    pub bytes_sent: u64,
+    /// Frames waiting in this client's send queue.
+    pub queue_depth: usize,
+    /// Frames dropped from this client's send queue.
+    pub dropped: u64,
+}
+
+/// Ingest statistics for one remote sender of a data source.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SenderMetrics {
+    /// Name of the reporting source ([`DataSource::name()`](crate::DataSource::name)).
+    pub source: String,
+    /// Sender address.
+    pub addr: SocketAddr,
+    /// Records accepted and pushed into the store.
+    pub records: u64,
+    /// Datagrams or records rejected as invalid.
+    pub rejected: u64,
+    /// Bytes received.
+    pub bytes: u64,
+}
+
+/// Data latency of one stream (NSLC).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StreamMetrics {
+    /// FDSN network code.
+    pub network: String,
+    /// Station code.
+    pub station: String,
+    /// Location code (may be empty).
+    pub location: String,
+    /// Channel code.
+    pub channel: String,
+    /// End time of the latest record (start time plus samples / sample rate).
+    pub end_time: SystemTime,
+    /// Time between `end_time` and the snapshot; zero for records in the future.
+    pub latency: Duration,
+}
+
+impl ServerMetrics {
+    pub(crate) fn new(
+        store: DataStore,
+        connections: ConnectionRegistry,
+        counters: Arc<Counters>,
+        sources: SourceList,
+    ) -> Self {
+        Self {
+            store,
+            connections,
+            counters,
+            sources,
+        }
+    }
+
+    /// Collect current statistics.
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let (records_pushed, records_evicted) = self.store.push_counts();
+        let mut clients: Vec<ClientMetrics> = self
+            .connections
+            .snapshot()
+            .into_iter()
+            .map(|c| {
+                let (queue_depth, dropped) =
+                    c.queue.as_ref().map_or((0, 0), |q| (q.len(), q.dropped()));
+                ClientMetrics {
+                    addr: c.addr,
+                    state: c.state,
+                    frames_sent: c.stats.frames_sent.load(Ordering::Relaxed),
+                    bytes_sent: c.stats.bytes_sent.load(Ordering::Relaxed),
+                    queue_depth,
+                    dropped,
+                }
+            })
+            .collect();
+        clients.sort_by_key(|c| c.addr);
+
+        let sources = self.sources.lock().unwrap().clone();
+        let senders = sources.iter().flat_map(|s| s.sender_metrics()).collect();
+
+        MetricsSnapshot {
+            records_pushed,
+            records_evicted,
+            records_duplicate: self.store.duplicate_count(),
+            records_invalid: self.store.invalid_count(),
+            ring_len: self.store.len(),
+            ring_capacity: self.store.capacity(),
+            ring_bytes: self.store.bytes(),
+            ring_capacity_bytes: self.store.capacity_bytes(),
+            frames_sent: self.counters.frames_sent.load(Ordering::Relaxed),
+            bytes_sent: self.counters.bytes_sent.load(Ordering::Relaxed),
+            clients_connected: clients.len(),
+            clients_total: self.counters.clients_total.load(Ordering::Relaxed),
+            clients_timed_out: self.counters.clients_timed_out.load(Ordering::Relaxed),
+            clients_limited: self.counters.clients_limited.load(Ordering::Relaxed),
+            resume_misses: self.counters.resume_misses.load(Ordering::Relaxed),
+            clients,
+            senders,
+            streams: self.store.stream_latencies(SystemTime::now()),
+        }
+    }
+}
+
+#[cfg(feature = "prometheus")]
+impl MetricsSnapshot {
+    /// Render in the Prometheus text exposition format.
+    pub fn to_prometheus(&self) -> String {
+        use std::fmt::Write;
+
+        let mut out = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, value: String| {
+            let _ = writeln!(out, "# HELP seedlink_{name} {help}");
+            let _ = writeln!(out, "# TYPE seedlink_{name} {kind}");
+            let _ = writeln!(out, "seedlink_{name} {value}");
+        };
+        metric(
+            "records_pushed_total",
+            "counter",
+            "Records pushed into the store.",
+            self.records_pushed.to_string(),
+        );
+        metric(
+            "records_evicted_total",
+            "counter",
+            "Records evicted from the ring buffer.",
+            self.records_evicted.to_string(),
+        );
+        metric(
+            "records_duplicate_total",
+            "counter",
+            "Records dropped as duplicates.",
+            self.records_duplicate.to_string(),
+        );
+        metric(
+            "records_invalid_total",
+            "counter",
+            "Records that failed validation.",
+            self.records_invalid.to_string(),
+        );
+        metric(
+            "ring_records",
+            "gauge",
+            "Records currently in the ring buffer.",
+            self.ring_len.to_string(),
+        );
+        metric(
+            "ring_capacity",
+            "gauge",
+            "Ring buffer capacity.",
+            self.ring_capacity.to_string(),
+        );
+        metric(
+            "ring_bytes",
+            "gauge",
+            "Payload bytes in the ring buffer.",
+            self.ring_bytes.to_string(),
+        );
+        metric(
+            "ring_capacity_bytes",
+            "gauge",
+            "Ring buffer byte budget (0 = no limit).",
+            self.ring_capacity_bytes.to_string(),
+        );
+        metric(
+            "frames_sent_total",
+            "counter",
+            "Data frames sent to clients.",
+            self.frames_sent.to_string(),
+        );
+        metric(
+            "bytes_sent_total",
+            "counter",
+            "Data frame bytes sent to clients.",
+            self.bytes_sent.to_string(),
+        );
+        metric(
+            "clients_connected",
+            "gauge",
+            "Currently connected clients.",
+            self.clients_connected.to_string(),
+        );
+        metric(
+            "clients_total",
+            "counter",
+            "Clients accepted since startup.",
+            self.clients_total.to_string(),
+        );
+        metric(
+            "clients_timed_out_total",
+            "counter",
+            "Clients closed by an idle timeout.",
+            self.clients_timed_out.to_string(),
+        );
+        metric(
+            "clients_limited_total",
+            "counter",
+            "Clients closed for an over-long command line or too many commands.",
+            self.clients_limited.to_string(),
+        );
+        metric(
+            "resume_misses_total",
+            "counter",
+            "Client resumes from a sequence no longer buffered.",
+            self.resume_misses.to_string(),
+        );
+
+        let header = |out: &mut String, name: &str, kind: &str, help: &str| {
+            let _ = writeln!(out, "# HELP seedlink_{name} {help}");
+            let _ = writeln!(out, "# TYPE seedlink_{name} {kind}");
+        };
+        header(
+            &mut out,
+            "client_bytes_sent_total",
+            "counter",
+            "Data frame bytes sent to this client.",
+        );
+        for c in &self.clients {
+            let _ = writeln!(
+                out,
+                "seedlink_client_bytes_sent_total{{client=\"{}\"}} {}",
+                c.addr, c.bytes_sent
+            );
+        }
+        header(
+            &mut out,
+            "client_frames_sent_total",
+            "counter",
+            "Data frames sent to this client.",
+        );
+        for c in &self.clients {
+            let _ = writeln!(
+                out,
+                "seedlink_client_frames_sent_total{{client=\"{}\"}} {}",
+                c.addr, c.frames_sent
+            );
+        }
+        header(
+            &mut out,
+            "client_queue_depth",
+            "gauge",
+            "Frames waiting in this client's send queue.",
+        );
+        for c in &self.clients {
+            let _ = writeln!(
+                out,
+                "seedlink_client_queue_depth{{client=\"{}\"}} {}",
+                c.addr, c.queue_depth
+            );
+        }
+        header(
+            &mut out,
+            "sender_records_total",
+            "counter",
+            "Records received from this sender.",
+        );
+        for s in &self.senders {
+            let _ = writeln!(
+                out,
+                "seedlink_sender_records_total{{source=\"{}\",sender=\"{}\"}} {}",
+                s.source, s.addr, s.records
+            );
+        }
+        header(
+            &mut out,
+            "sender_rejected_total",
+            "counter",
+            "Invalid datagrams or records from this sender.",
+        );
+        for s in &self.senders {
+            let _ = writeln!(
+                out,
+                "seedlink_sender_rejected_total{{source=\"{}\",sender=\"{}\"}} {}",
+                s.source, s.addr, s.rejected
+            );
+        }
+        header(
+            &mut out,
+            "stream_latency_seconds",
+            "gauge",
+            "Time since the end of the latest record of this stream.",
+        );
+        for s in &self.streams {
+            let _ = writeln!(
+                out,
+                "seedlink_stream_latency_seconds{{stream=\"{}.{}.{}.{}\"}} {:.3}",
+                s.network,
+                s.station,
+                s.location,
+                s.channel,
+                s.latency.as_secs_f64()
+            );
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    fn metrics() -> (ServerMetrics, DataStore, ConnectionRegistry, Arc<Counters>) {
+        let store = DataStore::new(2);
+        let connections = ConnectionRegistry::new();
+        let counters = Arc::new(Counters::default());
+        let m = ServerMetrics::new(
+            store.clone(),
+            connections.clone(),
+            counters.clone(),
+            SourceList::default(),
+        );
+        (m, store, connections, counters)
+    }
+
+    #[test]
+    fn snapshot_reflects_store_and_clients() {
+        let (m, store, connections, counters) = metrics();
+        for _ in 0..3 {
+            store.push("IU", "ANMO", &[0u8; 512]);
+        }
+        let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 1001);
+        let id = connections.register(addr);
+        counters.clients_total.fetch_add(1, Ordering::Relaxed);
+
+        let stats = Arc::new(ClientStats::default());
+        connections.update(id, |info| info.stats = stats.clone());
+        stats.record_frames(&counters, 1, 520);
+        stats.record_frames(&counters, 1, 520);
+
+        let snap = m.snapshot();
+        assert_eq!(snap.records_pushed, 3);
+        assert_eq!(snap.records_evicted, 1);
+        assert_eq!(snap.ring_len, 2);
+        assert_eq!(snap.ring_capacity, 2);
+        assert_eq!(snap.ring_bytes, 1024);
+        assert_eq!(snap.ring_capacity_bytes, 0);
+        assert_eq!(snap.frames_sent, 2);
+        assert_eq!(snap.bytes_sent, 1040);
+        assert_eq!(snap.clients_connected, 1);
+        assert_eq!(snap.clients_total, 1);
+        assert_eq!(snap.clients_timed_out, 0);
+        assert_eq!(snap.clients_limited, 0);
+        assert_eq!(snap.resume_misses, 0);
+        assert_eq!(snap.clients[0].addr, addr);
+        assert_eq!(snap.clients[0].bytes_sent, 1040);
+        assert_eq!(snap.clients[0].queue_depth, 0);
+    }
+
+    #[cfg(feature = "prometheus")]
+    #[test]
+    fn prometheus_text_format() {
+        let (m, store, connections, _counters) = metrics();
+        let mut payload = [0u8; 512];
+        payload[8..20].copy_from_slice(b"ANMO 00BHZIU");
+        payload[20..22].copy_from_slice(&2024u16.to_be_bytes());
+        payload[22..24].copy_from_slice(&1u16.to_be_bytes());
+        store.push("IU", "ANMO", &payload);
+        connections.register(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 1001));
+
+        let text = m.snapshot().to_prometheus();
+        assert!(text.contains("# TYPE seedlink_records_pushed_total counter\n"));
+        assert!(text.contains("seedlink_records_pushed_total 1\n"));
+        assert!(text.contains("seedlink_records_invalid_total 0\n"));
+        assert!(text.contains("seedlink_clients_connected 1\n"));
+        assert!(text.contains("seedlink_client_bytes_sent_total{client=\"127.0.0.1:1001\"} 0\n"));
+        assert!(text.contains("# TYPE seedlink_sender_rejected_total counter\n"));
+        assert!(text.contains("seedlink_stream_latency_seconds{stream=\"IU.ANMO.00.BHZ\"} "));
+    }
+}
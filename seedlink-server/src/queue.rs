@@ -0,0 +1,379 @@
+//! Bounded per-client outbound frame queue.
+//!
+//! Decouples reading records from the [`DataStore`](crate::DataStore) from
+//! writing frames to the socket, so a slow client only fills its own queue.
+//! What happens when the queue is full is governed by [`OverflowPolicy`].
+//!
+//! Frames are queued as an encoded header plus the record's shared payload,
+//! and header buffers are handed back after writing for the next frames.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::{Notify, watch};
+
+/// What to do when a client's outbound queue is full.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Wait for the client to drain the queue (the producer stalls). Default.
+    #[default]
+    Block,
+    /// Discard the oldest queued frame to make room for the new one.
+    DropOldest,
+    /// Close the connection to the lagging client.
+    Disconnect,
+}
+
+/// Why a frame could not be queued.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum PushError {
+    /// Queue was full and the policy is [`OverflowPolicy::Disconnect`].
+    Overflow,
+    /// Queue was closed (writer finished or failed).
+    Closed,
+}
+
+/// A frame waiting to be written: its encoded header, then the payload,
+/// which is usually the record's own buffer rather than a copy.
+#[derive(Debug)]
+pub(crate) struct QueuedFrame {
+    pub header: Vec<u8>,
+    pub payload: FramePayload,
+}
+
+/// Payload of a [`QueuedFrame`].
+#[derive(Debug)]
+pub(crate) enum FramePayload {
+    /// A record payload shared with the store.
+    Shared(Arc<[u8]>),
+    /// A payload built for this frame, e.g. compressed.
+    Owned(Vec<u8>),
+}
+
+impl FramePayload {
+    pub fn bytes(&self) -> &[u8] {
+        match self {
+            Self::Shared(p) => p,
+            Self::Owned(p) => p,
+        }
+    }
+}
+
+impl QueuedFrame {
+    /// Frame length in bytes.
+    pub fn len(&self) -> usize {
+        self.header.len() + self.payload.bytes().len()
+    }
+
+    /// Header and payload, in write order.
+    pub fn slices(&self) -> [&[u8]; 2] {
+        [&self.header, self.payload.bytes()]
+    }
+}
+
+impl From<Vec<u8>> for QueuedFrame {
+    /// A complete frame, queued as its header with an empty payload.
+    fn from(frame: Vec<u8>) -> Self {
+        Self {
+            header: frame,
+            payload: FramePayload::Owned(Vec::new()),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct QueueState {
+    frames: VecDeque<QueuedFrame>,
+    /// Header buffers of written frames, reused by [`SendQueue::header_buffer()`].
+    spare: Vec<Vec<u8>>,
+    closed: bool,
+}
+
+#[derive(Debug)]
+struct QueueInner {
+    state: Mutex<QueueState>,
+    capacity: usize,
+    policy: OverflowPolicy,
+    dropped: AtomicU64,
+    /// Signalled when a frame is pushed (consumer waits on this).
+    pushed: Notify,
+    /// Signalled when a frame is popped (blocked producer waits on this).
+    popped: Notify,
+    closed_tx: watch::Sender<bool>,
+}
+
+/// Single-producer / single-consumer bounded frame queue. Clone is cheap (Arc).
+#[derive(Clone, Debug)]
+pub(crate) struct SendQueue(Arc<QueueInner>);
+
+impl SendQueue {
+    /// Create an open queue holding at most `capacity` frames (minimum 1).
+    pub fn new(capacity: usize, policy: OverflowPolicy) -> Self {
+        let capacity = capacity.max(1);
+        Self(Arc::new(QueueInner {
+            state: Mutex::new(QueueState {
+                frames: VecDeque::with_capacity(capacity),
+                spare: Vec::new(),
+                closed: false,
+            }),
+            capacity,
+            policy,
+            dropped: AtomicU64::new(0),
+            pushed: Notify::new(),
+            popped: Notify::new(),
+            closed_tx: watch::channel(false).0,
+        }))
+    }
+
+    /// Queue a frame, applying the overflow policy if the queue is full.
+    pub async fn push(&self, frame: QueuedFrame) -> Result<(), PushError> {
+        let mut frame = Some(frame);
+        loop {
+            {
+                let mut state = self.0.state.lock().unwrap();
+                if state.closed {
+                    return Err(PushError::Closed);
+                }
+                if state.frames.len() < self.0.capacity {
+                    state.frames.extend(frame.take());
+                    self.0.pushed.notify_one();
+                    return Ok(());
+                }
+                match self.0.policy {
+                    OverflowPolicy::DropOldest => {
+                        state.frames.pop_front();
+                        state.frames.extend(frame.take());
+                        self.0.dropped.fetch_add(1, Ordering::Relaxed);
+                        self.0.pushed.notify_one();
+                        return Ok(());
+                    }
+                    OverflowPolicy::Disconnect => return Err(PushError::Overflow),
+                    OverflowPolicy::Block => {}
+                }
+            }
+            self.0.popped.notified().await;
+        }
+    }
+
+    /// Take the next frame, waiting if the queue is empty.
+    ///
+    /// Returns `None` once the queue is closed and fully drained.
+    #[cfg(test)]
+    pub async fn pop(&self) -> Option<Vec<u8>> {
+        loop {
+            {
+                let mut state = self.0.state.lock().unwrap();
+                if let Some(frame) = state.frames.pop_front() {
+                    self.0.popped.notify_one();
+                    return Some(frame.slices().concat());
+                }
+                if state.closed {
+                    return None;
+                }
+            }
+            self.0.pushed.notified().await;
+        }
+    }
+
+    /// Move queued frames to `batch` until their total length would exceed
+    /// `max_bytes`, waiting if the queue is empty. At least one frame is
+    /// taken, however large. Returns the number of bytes taken.
+    ///
+    /// Returns `None` once the queue is closed and fully drained.
+    pub async fn pop_batch(&self, max_bytes: usize, batch: &mut Vec<QueuedFrame>) -> Option<usize> {
+        loop {
+            {
+                let mut state = self.0.state.lock().unwrap();
+                let mut bytes = 0;
+                let mut taken = 0;
+                while let Some(frame) = state.frames.front() {
+                    if taken > 0 && bytes + frame.len() > max_bytes {
+                        break;
+                    }
+                    bytes += frame.len();
+                    batch.extend(state.frames.pop_front());
+                    taken += 1;
+                }
+                if taken > 0 {
+                    self.0.popped.notify_one();
+                    return Some(bytes);
+                }
+                if state.closed {
+                    return None;
+                }
+            }
+            self.0.pushed.notified().await;
+        }
+    }
+
+    /// An empty buffer to encode a frame header into, reusing one from a
+    /// written frame when available.
+    pub fn header_buffer(&self) -> Vec<u8> {
+        self.0.state.lock().unwrap().spare.pop().unwrap_or_default()
+    }
+
+    /// Empty `batch` of written frames, keeping their header buffers (up to
+    /// the queue capacity) for [`header_buffer()`](Self::header_buffer).
+    pub fn recycle(&self, batch: &mut Vec<QueuedFrame>) {
+        let mut state = self.0.state.lock().unwrap();
+        for frame in batch.drain(..) {
+            if state.spare.len() < self.0.capacity {
+                let mut header = frame.header;
+                header.clear();
+                state.spare.push(header);
+            }
+        }
+    }
+
+    /// Close the queue. Already-queued frames can still be popped.
+    pub fn close(&self) {
+        self.0.state.lock().unwrap().closed = true;
+        self.wake_all();
+    }
+
+    /// Close the queue and discard any frames not yet written.
+    pub fn abort(&self) {
+        {
+            let mut state = self.0.state.lock().unwrap();
+            state.closed = true;
+            state.frames.clear();
+        }
+        self.wake_all();
+    }
+
+    /// Resolves once the queue has been closed or aborted.
+    pub async fn closed(&self) {
+        let mut rx = self.0.closed_tx.subscribe();
+        let _ = rx.wait_for(|closed| *closed).await;
+    }
+
+    /// Number of frames currently waiting to be written.
+    pub fn len(&self) -> usize {
+        self.0.state.lock().unwrap().frames.len()
+    }
+
+    /// Number of frames discarded under [`OverflowPolicy::DropOldest`].
+    pub fn dropped(&self) -> u64 {
+        self.0.dropped.load(Ordering::Relaxed)
+    }
+
+    fn wake_all(&self) {
+        self.0.closed_tx.send_replace(true);
+        self.0.pushed.notify_one();
+        self.0.popped.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn push_pop_fifo() {
+        let q = SendQueue::new(4, OverflowPolicy::Block);
+        q.push(vec![1].into()).await.unwrap();
+        q.push(vec![2].into()).await.unwrap();
+        assert_eq!(q.len(), 2);
+        assert_eq!(q.pop().await, Some(vec![1]));
+        assert_eq!(q.pop().await, Some(vec![2]));
+        assert_eq!(q.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn pop_batch_respects_max_bytes() {
+        let q = SendQueue::new(8, OverflowPolicy::Block);
+        for i in 0..5u8 {
+            q.push(vec![i; 4].into()).await.unwrap();
+        }
+        let mut batch = Vec::new();
+        assert_eq!(q.pop_batch(10, &mut batch).await, Some(8));
+        assert_eq!(batch.len(), 2);
+        assert_eq!(batch[1].slices(), [&[1; 4][..], &[]]);
+
+        // A frame larger than the limit is still taken on its own
+        q.recycle(&mut batch);
+        assert_eq!(q.pop_batch(1, &mut batch).await, Some(4));
+        assert_eq!(batch.len(), 1);
+
+        q.recycle(&mut batch);
+        q.close();
+        assert_eq!(q.pop_batch(usize::MAX, &mut batch).await, Some(8));
+        assert_eq!(batch.len(), 2);
+        assert_eq!(q.pop_batch(usize::MAX, &mut batch).await, None);
+    }
+
+    #[tokio::test]
+    async fn written_headers_are_reused() {
+        let q = SendQueue::new(1, OverflowPolicy::Block);
+        assert_eq!(q.header_buffer().capacity(), 0);
+        let frame = QueuedFrame {
+            header: Vec::with_capacity(64),
+            payload: FramePayload::Shared(Arc::from(&[1u8, 2][..])),
+        };
+        let mut batch = vec![frame, vec![0; 8].into()];
+        q.recycle(&mut batch);
+        assert!(batch.is_empty());
+
+        // Only `capacity` buffers are kept, emptied
+        let header = q.header_buffer();
+        assert!(header.is_empty() && header.capacity() == 64);
+        assert_eq!(q.header_buffer().capacity(), 0);
+    }
+
+    #[tokio::test]
+    async fn drop_oldest_discards_front() {
+        let q = SendQueue::new(2, OverflowPolicy::DropOldest);
+        for i in 0..5u8 {
+            q.push(vec![i].into()).await.unwrap();
+        }
+        assert_eq!(q.len(), 2);
+        assert_eq!(q.dropped(), 3);
+        assert_eq!(q.pop().await, Some(vec![3]));
+        assert_eq!(q.pop().await, Some(vec![4]));
+    }
+
+    #[tokio::test]
+    async fn disconnect_policy_overflows() {
+        let q = SendQueue::new(1, OverflowPolicy::Disconnect);
+        q.push(vec![0].into()).await.unwrap();
+        assert_eq!(q.push(vec![1].into()).await, Err(PushError::Overflow));
+    }
+
+    #[tokio::test]
+    async fn block_policy_waits_for_space() {
+        let q = SendQueue::new(1, OverflowPolicy::Block);
+        q.push(vec![0].into()).await.unwrap();
+
+        let producer = {
+            let q = q.clone();
+            tokio::spawn(async move { q.push(vec![1].into()).await })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!producer.is_finished());
+
+        assert_eq!(q.pop().await, Some(vec![0]));
+        producer.await.unwrap().unwrap();
+        assert_eq!(q.pop().await, Some(vec![1]));
+    }
+
+    #[tokio::test]
+    async fn close_drains_then_ends() {
+        let q = SendQueue::new(4, OverflowPolicy::Block);
+        q.push(vec![7].into()).await.unwrap();
+        q.close();
+        assert_eq!(q.push(vec![8].into()).await, Err(PushError::Closed));
+        assert_eq!(q.pop().await, Some(vec![7]));
+        assert_eq!(q.pop().await, None);
+        q.closed().await;
+    }
+
+    #[tokio::test]
+    async fn abort_discards_pending() {
+        let q = SendQueue::new(4, OverflowPolicy::Block);
+        q.push(vec![7].into()).await.unwrap();
+        q.abort();
+        assert_eq!(q.pop().await, None);
+    }
+}
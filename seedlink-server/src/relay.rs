@@ -0,0 +1,311 @@
+//! Relay source: chain this server to an upstream SeedLink server.
+//!
+//! Requires the `relay` feature. [`RelaySource`] subscribes to an upstream
+//! server (IRIS, GEOFON, another seedlink-rs instance) through a
+//! [`ReconnectingClient`] and republishes every miniSEED record into the local
+//! [`DataStore`] under the upstream network/station codes.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use seedlink_rs_client::{ClientConfig, ReconnectConfig, ReconnectingClient, StationKey};
+use seedlink_rs_protocol::SequenceNumber;
+use seedlink_rs_protocol::frame::v3;
+use tracing::{debug, info, warn};
+
+use crate::error::{Result, ServerError};
+use crate::source::{BoxFuture, DataSource};
+use crate::store::DataStore;
+
+/// One upstream station subscription.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RelayStation {
+    /// Network code (e.g., `"IU"`).
+    pub network: String,
+    /// Station code (e.g., `"ANMO"`).
+    pub station: String,
+    /// SELECT patterns (e.g., `["BH?"]`). Empty = all channels.
+    pub selectors: Vec<String>,
+}
+
+impl RelayStation {
+    /// Subscribe to all channels of a station.
+    pub fn new(network: &str, station: &str) -> Self {
+        Self {
+            network: network.to_owned(),
+            station: station.to_owned(),
+            selectors: Vec::new(),
+        }
+    }
+
+    /// Add a SELECT pattern.
+    pub fn select(mut self, pattern: &str) -> Self {
+        self.selectors.push(pattern.to_owned());
+        self
+    }
+}
+
+/// A [`DataSource`] that relays records from an upstream SeedLink server.
+///
+/// The last upstream sequence number of each station is kept across restarts
+/// of the source, so after an upstream failure the relay resumes with
+/// `DATA seq` and skips records it has already pushed. Set
+/// [`ReconnectConfig::statefile`] to also keep it across process restarts.
+///
+/// Records must be 512-byte miniSEED v2; other upstream payloads are skipped.
+///
+/// By default records get local sequence numbers. With
+/// [`preserve_sequences()`](Self::preserve_sequences) they keep their upstream
+/// sequence numbers, so clients can switch between the upstream server and the
+/// relay and resume with the same `DATA seq`.
+///
+/// ```no_run
+/// # async fn example() -> seedlink_rs_server::Result<()> {
+/// use seedlink_rs_server::SeedLinkServer;
+/// use seedlink_rs_server::relay::{RelaySource, RelayStation};
+///
+/// let mut server = SeedLinkServer::bind("0.0.0.0:18000").await?;
+/// server.add_source(Box::new(RelaySource::new(
+///     "rtserve.iris.washington.edu:18000",
+///     vec![RelayStation::new("IU", "ANMO").select("BH?")],
+/// )));
+/// server.run().await;
+/// # Ok(())
+/// # }
+/// ```
+pub struct RelaySource {
+    addr: String,
+    stations: Vec<RelayStation>,
+    client: ClientConfig,
+    reconnect: ReconnectConfig,
+    preserve_sequences: bool,
+    sequences: Mutex<HashMap<StationKey, SequenceNumber>>,
+}
+
+impl RelaySource {
+    /// Relay `stations` from the server at `addr` with default client and
+    /// reconnect configuration.
+    pub fn new(addr: &str, stations: Vec<RelayStation>) -> Self {
+        Self::with_config(
+            addr,
+            stations,
+            ClientConfig::default(),
+            ReconnectConfig::default(),
+        )
+    }
+
+    /// Relay with custom client and reconnect configuration.
+    pub fn with_config(
+        addr: &str,
+        stations: Vec<RelayStation>,
+        client: ClientConfig,
+        reconnect: ReconnectConfig,
+    ) -> Self {
+        Self {
+            addr: addr.to_owned(),
+            stations,
+            client,
+            reconnect,
+            preserve_sequences: false,
+            sequences: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Store records under their upstream sequence numbers.
+    ///
+    /// Uses [`DataStore::push_with_sequence()`]; records the store refuses
+    /// (see [`SequencePolicy`](crate::SequencePolicy)) are logged and skipped.
+    /// The store should be fed by this source only, or the sequences of the
+    /// sources will collide.
+    pub fn preserve_sequences(mut self, preserve: bool) -> Self {
+        self.preserve_sequences = preserve;
+        self
+    }
+
+    async fn relay(&self, store: DataStore) -> seedlink_rs_client::Result<()> {
+        let mut client = ReconnectingClient::connect_with_config(
+            &self.addr,
+            self.client.clone(),
+            self.reconnect.clone(),
+        )
+        .await?;
+
+        for s in &self.stations {
+            client.station(&s.station, &s.network).await?;
+            for pattern in &s.selectors {
+                client.select(pattern).await?;
+            }
+            let key = StationKey {
+                network: s.network.clone(),
+                station: s.station.clone(),
+            };
+            let resume = self.sequences.lock().unwrap().get(&key).copied();
+            match resume {
+                Some(seq) => client.data_from(seq).await?,
+                None => client.data().await?,
+            }
+        }
+        client.end_stream().await?;
+        info!(upstream = %self.addr, stations = self.stations.len(), "relay streaming");
+
+        while let Some(frame) = client.next_frame().await? {
+            let Some(key) = frame.station_key() else {
+                continue;
+            };
+            if frame.payload().len() != v3::PAYLOAD_LEN {
+                debug!(len = frame.payload().len(), "skipping non-512-byte payload");
+                continue;
+            }
+
+            let mut sequences = self.sequences.lock().unwrap();
+            if sequences
+                .get(&key)
+                .is_some_and(|&last| !frame.is_after(last))
+            {
+                continue;
+            }
+            if self.preserve_sequences {
+                if let Err(e) = store.push_with_sequence(
+                    &key.network,
+                    &key.station,
+                    frame.payload(),
+                    frame.sequence(),
+                ) {
+                    warn!(error = %e, "skipping upstream record");
+                }
+            } else {
+                store.push(&key.network, &key.station, frame.payload());
+            }
+            sequences.insert(key, frame.sequence());
+        }
+        Ok(())
+    }
+}
+
+impl DataSource for RelaySource {
+    fn name(&self) -> &str {
+        "relay"
+    }
+
+    fn run(&self, store: DataStore) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            self.relay(store)
+                .await
+                .map_err(|e| ServerError::Source(format!("relay {}: {e}", self.addr)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SeedLinkServer;
+    use seedlink_rs_protocol::testing::TestRecordBuilder;
+    use std::time::Duration;
+
+    fn make_payload(station: &str, network: &str) -> Vec<u8> {
+        TestRecordBuilder::new()
+            .station(station)
+            .network(network)
+            .build()
+    }
+
+    async fn wait_for_len(store: &DataStore, len: usize) {
+        tokio::time::timeout(Duration::from_secs(2), async {
+            while store.len() < len {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("relay did not deliver records in time");
+    }
+
+    #[tokio::test]
+    async fn relays_upstream_records() {
+        let upstream = SeedLinkServer::bind("127.0.0.1:0").await.unwrap();
+        let addr = upstream.local_addr().unwrap().to_string();
+        let upstream_store = upstream.store().clone();
+        tokio::spawn(upstream.run());
+
+        upstream_store.push("IU", "ANMO", &make_payload("ANMO", "IU"));
+        upstream_store.push("GE", "WLF", &make_payload("WLF", "GE"));
+        upstream_store.push("IU", "ANMO", &make_payload("ANMO", "IU"));
+
+        let relay = RelaySource::new(&addr, vec![RelayStation::new("IU", "ANMO")]);
+        let local = DataStore::new(100);
+        tokio::spawn({
+            let local = local.clone();
+            async move { relay.run(local).await }
+        });
+
+        wait_for_len(&local, 2).await;
+        let info = local.station_info();
+        assert_eq!(info.len(), 1);
+        assert_eq!(info[0].network, "IU");
+        assert_eq!(info[0].station, "ANMO");
+
+        // Live records are relayed too
+        upstream_store.push("IU", "ANMO", &make_payload("ANMO", "IU"));
+        wait_for_len(&local, 3).await;
+    }
+
+    #[tokio::test]
+    async fn restart_resumes_without_duplicates() {
+        let upstream = SeedLinkServer::bind("127.0.0.1:0").await.unwrap();
+        let addr = upstream.local_addr().unwrap().to_string();
+        let upstream_store = upstream.store().clone();
+        tokio::spawn(upstream.run());
+
+        upstream_store.push("IU", "ANMO", &make_payload("ANMO", "IU"));
+        upstream_store.push("IU", "ANMO", &make_payload("ANMO", "IU"));
+
+        let relay = std::sync::Arc::new(RelaySource::new(
+            &addr,
+            vec![RelayStation::new("IU", "ANMO")],
+        ));
+        let local = DataStore::new(100);
+
+        // First run relays both records, then is stopped
+        let first = tokio::spawn({
+            let (relay, local) = (relay.clone(), local.clone());
+            async move { relay.run(local).await }
+        });
+        wait_for_len(&local, 2).await;
+        first.abort();
+
+        // Second run resumes after upstream seq 2
+        upstream_store.push("IU", "ANMO", &make_payload("ANMO", "IU"));
+        tokio::spawn({
+            let (relay, local) = (relay.clone(), local.clone());
+            async move { relay.run(local).await }
+        });
+        wait_for_len(&local, 3).await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(local.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn preserves_upstream_sequences() {
+        let upstream = SeedLinkServer::bind("127.0.0.1:0").await.unwrap();
+        let addr = upstream.local_addr().unwrap().to_string();
+        let upstream_store = upstream.store().clone();
+        tokio::spawn(upstream.run());
+
+        upstream_store.push("GE", "WLF", &make_payload("WLF", "GE"));
+        upstream_store.push("IU", "ANMO", &make_payload("ANMO", "IU"));
+        upstream_store.push("GE", "WLF", &make_payload("WLF", "GE"));
+        upstream_store.push("IU", "ANMO", &make_payload("ANMO", "IU"));
+
+        let relay =
+            RelaySource::new(&addr, vec![RelayStation::new("IU", "ANMO")]).preserve_sequences(true);
+        let local = DataStore::new(100);
+        tokio::spawn({
+            let local = local.clone();
+            async move { relay.run(local).await }
+        });
+
+        wait_for_len(&local, 2).await;
+        let info = local.station_info();
+        assert_eq!((info[0].begin_seq, info[0].end_seq), (2, 4));
+    }
+}
@@ -0,0 +1,87 @@
+//! Runtime changes to [`ServerConfig`] values without a restart.
+//!
+//! [`ConfigHandle`] publishes a [`ReloadableConfig`] on a watch channel; every
+//! client handler reads the latest values when it needs them, so a change
+//! applies to connected clients as well as new ones.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::watch;
+
+use crate::ServerConfig;
+use crate::acl::Acl;
+
+/// The [`ServerConfig`] values that can be changed while the server runs.
+///
+/// See the field of the same name on [`ServerConfig`] for each meaning.
+#[derive(Clone, Debug)]
+pub struct ReloadableConfig {
+    /// Organization reported in HELLO and INFO ID.
+    pub organization: String,
+    /// Keepalive interval for streaming clients.
+    pub keepalive_interval: Option<Duration>,
+    /// Idle limit while waiting for a command.
+    pub command_timeout: Option<Duration>,
+    /// Longest command line accepted, without the line terminator.
+    pub max_command_len: usize,
+    /// Commands accepted per second before the client is disconnected.
+    pub max_commands_per_second: Option<u32>,
+    /// Limit on a single stalled write while streaming.
+    pub stream_idle_timeout: Option<Duration>,
+    /// Station access rules. A narrower ACL stops records of newly denied
+    /// stations on open streams; subscriptions are not re-checked.
+    pub acl: Arc<Acl>,
+}
+
+impl From<&ServerConfig> for ReloadableConfig {
+    fn from(config: &ServerConfig) -> Self {
+        Self {
+            organization: config.organization.clone(),
+            keepalive_interval: config.keepalive_interval,
+            command_timeout: config.command_timeout,
+            max_command_len: config.max_command_len,
+            max_commands_per_second: config.max_commands_per_second,
+            stream_idle_timeout: config.stream_idle_timeout,
+            acl: Arc::new(config.acl.clone()),
+        }
+    }
+}
+
+/// Handle for changing a running server's [`ReloadableConfig`].
+///
+/// Obtained via [`SeedLinkServer::config_handle()`](crate::SeedLinkServer::config_handle);
+/// stays valid after [`run()`](crate::SeedLinkServer::run) consumes the server.
+/// Clone is cheap.
+#[derive(Clone)]
+pub struct ConfigHandle {
+    tx: Arc<watch::Sender<ReloadableConfig>>,
+}
+
+impl ConfigHandle {
+    pub(crate) fn new(config: &ServerConfig) -> Self {
+        Self {
+            tx: Arc::new(watch::Sender::new(config.into())),
+        }
+    }
+
+    /// The values in effect now.
+    pub fn current(&self) -> ReloadableConfig {
+        self.tx.borrow().clone()
+    }
+
+    /// Change values in place, e.g. `handle.update(|c| c.max_command_len = 256)`.
+    pub fn update(&self, f: impl FnOnce(&mut ReloadableConfig)) {
+        self.tx.send_modify(f);
+    }
+
+    /// Take every reloadable value from `config`, e.g. after re-reading a
+    /// configuration file. Other fields of `config` are ignored.
+    pub fn apply(&self, config: &ServerConfig) {
+        self.tx.send_replace(config.into());
+    }
+
+    pub(crate) fn subscribe(&self) -> watch::Receiver<ReloadableConfig> {
+        self.tx.subscribe()
+    }
+}
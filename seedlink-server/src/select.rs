@@ -1,10 +1,18 @@
-/// SELECT pattern parsing and matching for SeedLink v3.
+/// SELECT pattern parsing and matching.
 ///
-/// Pattern format: `[LL]CCC[.T]`
+/// v3 pattern format: `[LL]CCC[.T]`
 /// - LL = 2-char location code (optional)
 /// - CCC = 3-char channel code (required)
 /// - .T = type/quality code suffix (optional)
 /// - `?` is single-char wildcard
+/// - leading `!` negates the pattern (exclude matching channels)
+///
+/// v4 patterns ([`SelectPatternV4`]) separate location and channel with `_`
+/// or `:` and also accept `*`; [`Selector`] picks the syntax by protocol
+/// version, reading legacy `LLCCC` patterns as v3 even after SLPROTO 4.0.
+use seedlink_rs_protocol::ProtocolVersion;
+
+use crate::acl::glob_match;
 
 #[derive(Clone, Debug)]
 enum PatternChar {
@@ -35,13 +43,18 @@ pub(crate) struct SelectPattern {
     location: Option<[PatternChar; 2]>,
     channel: [PatternChar; 3],
     type_code: Option<u8>,
+    negated: bool,
 }
 
 impl SelectPattern {
     /// Parse a SELECT pattern string.
     ///
-    /// Format: `[LL]CCC[.T]` — NO dot between location and channel.
+    /// Format: `[!][LL]CCC[.T]` — NO dot between location and channel.
     pub fn parse(pattern: &str) -> Option<Self> {
+        let (negated, pattern) = match pattern.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, pattern),
+        };
         if pattern.is_empty() {
             return None;
         }
@@ -134,38 +147,45 @@ impl SelectPattern {
             location,
             channel,
             type_code,
+            negated,
         })
     }
 
-    /// Check if this pattern matches a miniSEED v2 payload.
+    /// Whether this is an exclusion (`!`) pattern.
+    pub fn is_negated(&self) -> bool {
+        self.negated
+    }
+
+    /// Check if this pattern matches a record's location, channel and
+    /// quality/type indicator.
     ///
-    /// miniSEED v2 fixed header offsets:
-    /// - byte 6: quality/type indicator
-    /// - bytes 13..15: location (2 chars)
-    /// - bytes 15..18: channel (3 chars)
-    pub fn matches_payload(&self, payload: &[u8]) -> bool {
-        if payload.len() < 20 {
-            return false;
-        }
+    /// Negation is not applied here — a `!BHZ` pattern matches BHZ records;
+    /// callers decide whether a match includes or excludes the record.
+    ///
+    /// Codes are compared space-padded to their fixed header width
+    /// (location 2, channel 3), as they appear in the record.
+    pub fn matches(&self, location: &str, channel: &str, quality: u8) -> bool {
+        let location = padded::<2>(location);
+        let channel = padded::<3>(channel);
 
         // Match channel (always required)
-        if !self.channel[0].matches(payload[15])
-            || !self.channel[1].matches(payload[16])
-            || !self.channel[2].matches(payload[17])
+        if !self.channel[0].matches(channel[0])
+            || !self.channel[1].matches(channel[1])
+            || !self.channel[2].matches(channel[2])
         {
             return false;
         }
 
         // Match location (only if pattern specifies it)
         if let Some(ref loc) = self.location
-            && (!loc[0].matches(payload[13]) || !loc[1].matches(payload[14]))
+            && (!loc[0].matches(location[0]) || !loc[1].matches(location[1]))
         {
             return false;
         }
 
         // Match type code (only if pattern specifies .T suffix)
         if let Some(tc) = self.type_code {
-            if PatternChar::from_byte(tc).matches(payload[6]) {
+            if PatternChar::from_byte(tc).matches(quality) {
                 // match
             } else {
                 return false;
@@ -176,32 +196,144 @@ impl SelectPattern {
     }
 }
 
+/// A parsed SeedLink v4 SELECT pattern.
+///
+/// Format: `[!][LOC_]CHA[.T]`, where `CHA` is a channel (`BHZ`) or FDSN
+/// band/source/subsource (`B_H_Z`), and `LOC:CHA` may be used instead of
+/// `LOC_CHA`. Codes are matched trimmed, with `*` and `?` wildcards; no
+/// location matches any, an empty one (`_BHZ`) only a blank location.
+/// E.g. `*.D`, `!*_LOG`, `00:BH?`, `10_B_H_Z.D`.
+#[derive(Clone, Debug)]
+pub(crate) struct SelectPatternV4 {
+    location: Option<String>,
+    channel: String,
+    type_code: Option<u8>,
+    negated: bool,
+}
+
+impl SelectPatternV4 {
+    /// Parse a v4 SELECT pattern string.
+    pub fn parse(pattern: &str) -> Option<Self> {
+        let (negated, pattern) = match pattern.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, pattern),
+        };
+        let (main, type_code) = match pattern.rsplit_once('.') {
+            Some((main, "*")) => (main, None),
+            Some((main, t)) if t.len() == 1 => (main, Some(t.as_bytes()[0])),
+            Some(_) => return None,
+            None => (pattern, None),
+        };
+        let (location, channel) = if let Some((loc, cha)) = main.split_once(':') {
+            (Some(loc), cha.to_owned())
+        } else {
+            let parts: Vec<&str> = main.split('_').collect();
+            match parts.as_slice() {
+                [cha] => (None, (*cha).to_owned()),
+                [loc, cha] => (Some(*loc), (*cha).to_owned()),
+                [band, source, sub] => (None, [*band, *source, *sub].concat()),
+                [loc, band, source, sub] => (Some(*loc), [*band, *source, *sub].concat()),
+                _ => return None,
+            }
+        };
+        if channel.is_empty() || channel.contains([':', '_']) {
+            return None;
+        }
+        Some(Self {
+            location: location.filter(|l| *l != "*").map(str::to_owned),
+            channel,
+            type_code,
+            negated,
+        })
+    }
+
+    /// Check if this pattern matches a record's location, channel and
+    /// quality/type indicator; negation is not applied, as for
+    /// [`SelectPattern::matches()`].
+    pub fn matches(&self, location: &str, channel: &str, quality: u8) -> bool {
+        glob_match(&self.channel, channel.trim())
+            && self
+                .location
+                .as_ref()
+                .is_none_or(|loc| glob_match(loc, location.trim()))
+            && self
+                .type_code
+                .is_none_or(|t| PatternChar::from_byte(t).matches(quality))
+    }
+}
+
+/// A SELECT pattern in the syntax of the client's protocol version.
+#[derive(Clone, Debug)]
+pub(crate) enum Selector {
+    V3(SelectPattern),
+    V4(SelectPatternV4),
+}
+
+impl Selector {
+    /// Parse `pattern` with the v3 or v4 SELECT syntax. A v4 pattern of
+    /// location and channel with no `_`, `:` or `*` (`00BHZ`, `!00BH?.D`) is
+    /// a v3 pattern from a client that negotiated 4.0 but kept its old
+    /// selectors, so it is read as one rather than as a 5-character channel.
+    pub fn parse(version: ProtocolVersion, pattern: &str) -> Option<Self> {
+        match version {
+            ProtocolVersion::V4 if !is_legacy_pattern(pattern) => {
+                SelectPatternV4::parse(pattern).map(Self::V4)
+            }
+            _ => SelectPattern::parse(pattern).map(Self::V3),
+        }
+    }
+
+    /// Whether this is an exclusion (`!`) pattern.
+    pub fn is_negated(&self) -> bool {
+        match self {
+            Self::V3(p) => p.is_negated(),
+            Self::V4(p) => p.negated,
+        }
+    }
+
+    /// Whether the pattern matches, ignoring negation.
+    pub fn matches(&self, location: &str, channel: &str, quality: u8) -> bool {
+        match self {
+            Self::V3(p) => p.matches(location, channel, quality),
+            Self::V4(p) => p.matches(location, channel, quality),
+        }
+    }
+}
+
+/// Whether `pattern` has the v3 `[!]LLCCC[.T]` shape and none of the v4
+/// separators or `*`.
+fn is_legacy_pattern(pattern: &str) -> bool {
+    let pattern = pattern.strip_prefix('!').unwrap_or(pattern);
+    let main = match pattern.rsplit_once('.') {
+        Some((main, t)) if t.len() == 1 => main,
+        Some(_) => return false,
+        None => pattern,
+    };
+    main.len() == 5 && !main.contains(['_', ':', '*', '.'])
+}
+
+/// `code` as `N` bytes, space-padded or truncated.
+fn padded<const N: usize>(code: &str) -> [u8; N] {
+    let mut out = [b' '; N];
+    for (dst, src) in out.iter_mut().zip(code.bytes()) {
+        *dst = src;
+    }
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    fn make_mseed_payload(location: &[u8; 2], channel: &[u8; 3], quality: u8) -> Vec<u8> {
-        let mut payload = vec![0u8; 512];
-        payload[6] = quality;
-        payload[13] = location[0];
-        payload[14] = location[1];
-        payload[15] = channel[0];
-        payload[16] = channel[1];
-        payload[17] = channel[2];
-        payload
-    }
-
     #[test]
     fn parse_channel_only() {
         let pat = SelectPattern::parse("BHZ").unwrap();
         assert!(pat.location.is_none());
         assert!(pat.type_code.is_none());
 
-        let payload = make_mseed_payload(b"00", b"BHZ", b'D');
-        assert!(pat.matches_payload(&payload));
+        assert!(pat.matches("00", "BHZ", b'D'));
 
-        let payload2 = make_mseed_payload(b"00", b"BHN", b'D');
-        assert!(!pat.matches_payload(&payload2));
+        assert!(!pat.matches("00", "BHN", b'D'));
     }
 
     #[test]
@@ -209,12 +341,10 @@ mod tests {
         let pat = SelectPattern::parse("00BHZ").unwrap();
         assert!(pat.location.is_some());
 
-        let payload = make_mseed_payload(b"00", b"BHZ", b'D');
-        assert!(pat.matches_payload(&payload));
+        assert!(pat.matches("00", "BHZ", b'D'));
 
         // Different location
-        let payload2 = make_mseed_payload(b"10", b"BHZ", b'D');
-        assert!(!pat.matches_payload(&payload2));
+        assert!(!pat.matches("10", "BHZ", b'D'));
     }
 
     #[test]
@@ -222,26 +352,19 @@ mod tests {
         let pat = SelectPattern::parse("BHZ.D").unwrap();
         assert!(pat.type_code.is_some());
 
-        let payload = make_mseed_payload(b"00", b"BHZ", b'D');
-        assert!(pat.matches_payload(&payload));
+        assert!(pat.matches("00", "BHZ", b'D'));
 
-        let payload2 = make_mseed_payload(b"00", b"BHZ", b'R');
-        assert!(!pat.matches_payload(&payload2));
+        assert!(!pat.matches("00", "BHZ", b'R'));
     }
 
     #[test]
     fn wildcard_channel() {
         let pat = SelectPattern::parse("BH?").unwrap();
 
-        let bhz = make_mseed_payload(b"00", b"BHZ", b'D');
-        let bhn = make_mseed_payload(b"00", b"BHN", b'D');
-        let bhe = make_mseed_payload(b"00", b"BHE", b'D');
-        let lhz = make_mseed_payload(b"00", b"LHZ", b'D');
-
-        assert!(pat.matches_payload(&bhz));
-        assert!(pat.matches_payload(&bhn));
-        assert!(pat.matches_payload(&bhe));
-        assert!(!pat.matches_payload(&lhz));
+        assert!(pat.matches("00", "BHZ", b'D'));
+        assert!(pat.matches("00", "BHN", b'D'));
+        assert!(pat.matches("00", "BHE", b'D'));
+        assert!(!pat.matches("00", "LHZ", b'D'));
     }
 
     #[test]
@@ -249,17 +372,25 @@ mod tests {
         let pat = SelectPattern::parse("??BHZ").unwrap();
         assert!(pat.location.is_some());
 
-        let payload00 = make_mseed_payload(b"00", b"BHZ", b'D');
-        let payload10 = make_mseed_payload(b"10", b"BHZ", b'D');
-
-        assert!(pat.matches_payload(&payload00));
-        assert!(pat.matches_payload(&payload10));
+        assert!(pat.matches("00", "BHZ", b'D'));
+        assert!(pat.matches("10", "BHZ", b'D'));
     }
 
     #[test]
-    fn short_payload_returns_false() {
+    fn empty_codes_are_blank() {
         let pat = SelectPattern::parse("BHZ").unwrap();
-        assert!(!pat.matches_payload(&[0u8; 10]));
+        assert!(!pat.matches("", "", b'D'));
+        // Blank location matches only a wildcard location
+        assert!(
+            SelectPattern::parse("??BHZ")
+                .unwrap()
+                .matches("", "BHZ", b'D')
+        );
+        assert!(
+            !SelectPattern::parse("00BHZ")
+                .unwrap()
+                .matches("", "BHZ", b'D')
+        );
     }
 
     #[test]
@@ -273,25 +404,134 @@ mod tests {
         assert!(pat.location.is_some());
         assert!(pat.type_code.is_some());
 
-        let payload = make_mseed_payload(b"00", b"BHZ", b'D');
-        assert!(pat.matches_payload(&payload));
+        assert!(pat.matches("00", "BHZ", b'D'));
 
         // Wrong location
-        let payload2 = make_mseed_payload(b"10", b"BHZ", b'D');
-        assert!(!pat.matches_payload(&payload2));
+        assert!(!pat.matches("10", "BHZ", b'D'));
 
         // Wrong type
-        let payload3 = make_mseed_payload(b"00", b"BHZ", b'R');
-        assert!(!pat.matches_payload(&payload3));
+        assert!(!pat.matches("00", "BHZ", b'R'));
+    }
+
+    #[test]
+    fn negated_pattern_parses() {
+        let pat = SelectPattern::parse("!LOG").unwrap();
+        assert!(pat.is_negated());
+        assert!(pat.location.is_none());
+
+        assert!(pat.matches("", "LOG", b'D'));
+        assert!(!pat.matches("00", "BHZ", b'D'));
+
+        assert!(!SelectPattern::parse("BHZ").unwrap().is_negated());
+    }
+
+    #[test]
+    fn negated_with_location_and_type() {
+        let pat = SelectPattern::parse("!00BH?.D").unwrap();
+        assert!(pat.is_negated());
+        assert!(pat.location.is_some());
+        assert!(pat.type_code.is_some());
+    }
+
+    #[test]
+    fn bare_negation_returns_none() {
+        assert!(SelectPattern::parse("!").is_none());
     }
 
     #[test]
     fn single_char_padded() {
         // "Z" → matches any channel ending in Z
         let pat = SelectPattern::parse("Z").unwrap();
-        let bhz = make_mseed_payload(b"00", b"BHZ", b'D');
-        let bhn = make_mseed_payload(b"00", b"BHN", b'D');
-        assert!(pat.matches_payload(&bhz));
-        assert!(!pat.matches_payload(&bhn));
+        assert!(pat.matches("00", "BHZ", b'D'));
+        assert!(!pat.matches("00", "BHN", b'D'));
+    }
+
+    #[test]
+    fn v4_wildcards_and_type() {
+        let pat = SelectPatternV4::parse("*.D").unwrap();
+        assert!(pat.matches("00", "BHZ", b'D'));
+        assert!(pat.matches("", "LOG", b'D'));
+        assert!(!pat.matches("00", "BHZ", b'E'));
+
+        let pat = SelectPatternV4::parse("BH*").unwrap();
+        assert!(pat.matches("10", "BHN", b'D'));
+        assert!(!pat.matches("10", "HHZ", b'D'));
+    }
+
+    #[test]
+    fn v4_location_separators() {
+        let pat = SelectPatternV4::parse("!*_LOG").unwrap();
+        assert!(pat.negated);
+        assert!(pat.matches("", "LOG", b'D'));
+        assert!(pat.matches("00", "LOG", b'D'));
+
+        let pat = SelectPatternV4::parse("00:BH?").unwrap();
+        assert!(pat.matches("00", "BHZ", b'D'));
+        assert!(!pat.matches("10", "BHZ", b'D'));
+
+        // Empty location matches only a blank one
+        let pat = SelectPatternV4::parse("_BHZ").unwrap();
+        assert!(pat.matches("", "BHZ", b'D'));
+        assert!(!pat.matches("00", "BHZ", b'D'));
+    }
+
+    #[test]
+    fn v4_fdsn_channel_codes() {
+        let pat = SelectPatternV4::parse("B_H_Z").unwrap();
+        assert!(pat.location.is_none());
+        assert!(pat.matches("00", "BHZ", b'D'));
+
+        let pat = SelectPatternV4::parse("10_B_H_?.D").unwrap();
+        assert!(pat.matches("10", "BHE", b'D'));
+        assert!(!pat.matches("00", "BHE", b'D'));
+    }
+
+    #[test]
+    fn v4_rejects_malformed() {
+        for bad in ["", "!", ".D", "BHZ.DD", "00_", "a_b_c_d_e", "00:BH_Z"] {
+            assert!(SelectPatternV4::parse(bad).is_none(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn selector_syntax_follows_version() {
+        let v3 = Selector::parse(ProtocolVersion::V3, "BH?").unwrap();
+        let v4 = Selector::parse(ProtocolVersion::V4, "BH?").unwrap();
+        assert!(matches!(v3, Selector::V3(_)));
+        assert!(matches!(v4, Selector::V4(_)));
+        assert!(
+            Selector::parse(ProtocolVersion::V3, "*.D")
+                .is_some_and(|p| !p.matches("00", "BHZ", b'D'))
+        );
+        assert!(
+            Selector::parse(ProtocolVersion::V4, "!*_LOG")
+                .unwrap()
+                .is_negated()
+        );
+    }
+
+    #[test]
+    fn v4_reads_legacy_location_channel_as_v3() {
+        for pattern in ["00BHZ", "00BH?.D", "!10LH?"] {
+            let selector = Selector::parse(ProtocolVersion::V4, pattern).unwrap();
+            assert!(matches!(selector, Selector::V3(_)), "{pattern}");
+        }
+        let p = Selector::parse(ProtocolVersion::V4, "00BHZ").unwrap();
+        assert!(p.matches("00", "BHZ", b'D'));
+        assert!(!p.matches("10", "BHZ", b'D'));
+        let p = Selector::parse(ProtocolVersion::V4, "00BH?.D").unwrap();
+        assert!(p.matches("00", "BHN", b'D'));
+        assert!(!p.matches("00", "BHN", b'R'));
+        assert!(
+            Selector::parse(ProtocolVersion::V4, "!10LH?")
+                .unwrap()
+                .is_negated()
+        );
+
+        // v4 syntax keeps its meaning
+        for pattern in ["00_BHZ", "00:BH?", "*BHZ", "B_H_Z", "BHZ"] {
+            let selector = Selector::parse(ProtocolVersion::V4, pattern).unwrap();
+            assert!(matches!(selector, Selector::V4(_)), "{pattern}");
+        }
     }
 }
@@ -0,0 +1,158 @@
+//! Listener and client socket options.
+
+use std::io;
+use std::time::Duration;
+
+use socket2::{SockRef, TcpKeepalive};
+use tokio::net::{TcpListener, TcpSocket, TcpStream};
+
+/// Listening socket and per-client TCP options, set in
+/// [`ServerConfig::socket`](crate::ServerConfig::socket).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SocketConfig {
+    /// Pending connections the kernel queues before `accept()` (`listen()`
+    /// backlog), capped by the system limit (`net.core.somaxconn` on Linux).
+    /// Default: `1024`.
+    pub backlog: u32,
+    /// `SO_REUSEADDR`, so a restarted server can bind while old connections
+    /// are in `TIME_WAIT`. Ignored on Windows, where it would allow another
+    /// process to steal the port. Default: `true`.
+    pub reuse_address: bool,
+    /// `SO_REUSEPORT`, letting several server processes bind the same address
+    /// with the kernel spreading connections between them. Unix only; elsewhere
+    /// binding fails with [`io::ErrorKind::Unsupported`]. Default: `false`.
+    pub reuse_port: bool,
+    /// `SO_SNDBUF` for each accepted client socket, in bytes. Larger buffers
+    /// keep high-latency links busy; the kernel may round or cap the value.
+    /// `None` keeps the system default. Default: `None`.
+    pub send_buffer_size: Option<u32>,
+    /// Enable TCP keepalive on accepted client sockets, probing after this
+    /// much idle time. Unlike
+    /// [`keepalive_interval`](crate::ServerConfig::keepalive_interval) it
+    /// also detects dead clients that are not streaming. `None` disables.
+    /// Default: `None`.
+    pub tcp_keepalive: Option<Duration>,
+}
+
+impl Default for SocketConfig {
+    fn default() -> Self {
+        Self {
+            backlog: 1024,
+            reuse_address: true,
+            reuse_port: false,
+            send_buffer_size: None,
+            tcp_keepalive: None,
+        }
+    }
+}
+
+impl SocketConfig {
+    /// Bind a listener to the first address `addr` resolves to that accepts
+    /// the bind, like [`TcpListener::bind()`]. For a custom accept loop fed
+    /// to [`SeedLinkServer::run_on()`](crate::SeedLinkServer::run_on).
+    pub async fn bind(&self, addr: &str) -> io::Result<TcpListener> {
+        let mut last_err = None;
+        for target in tokio::net::lookup_host(addr).await? {
+            match self.bind_addr(target) {
+                Ok(listener) => return Ok(listener),
+                Err(e) => last_err = Some(e),
+            }
+        }
+        Err(last_err.unwrap_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{addr} resolved to no addresses"),
+            )
+        }))
+    }
+
+    fn bind_addr(&self, addr: std::net::SocketAddr) -> io::Result<TcpListener> {
+        let socket = if addr.is_ipv4() {
+            TcpSocket::new_v4()?
+        } else {
+            TcpSocket::new_v6()?
+        };
+        #[cfg(not(windows))]
+        socket.set_reuseaddr(self.reuse_address)?;
+        if self.reuse_port {
+            set_reuseport(&socket)?;
+        }
+        socket.bind(addr)?;
+        socket.listen(self.backlog)
+    }
+
+    /// Apply the per-client options to an accepted connection.
+    pub fn apply(&self, stream: &TcpStream) -> io::Result<()> {
+        let sock = SockRef::from(stream);
+        if let Some(size) = self.send_buffer_size {
+            sock.set_send_buffer_size(size as usize)?;
+        }
+        if let Some(time) = self.tcp_keepalive {
+            sock.set_tcp_keepalive(&TcpKeepalive::new().with_time(time))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(
+    unix,
+    not(target_os = "solaris"),
+    not(target_os = "illumos"),
+    not(target_os = "cygwin")
+))]
+fn set_reuseport(socket: &TcpSocket) -> io::Result<()> {
+    socket.set_reuseport(true)
+}
+
+#[cfg(not(all(
+    unix,
+    not(target_os = "solaris"),
+    not(target_os = "illumos"),
+    not(target_os = "cygwin")
+)))]
+fn set_reuseport(_socket: &TcpSocket) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "SO_REUSEPORT is not supported on this platform",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn reuse_port_shares_address() {
+        let config = SocketConfig {
+            reuse_port: true,
+            ..Default::default()
+        };
+        let first = config.bind("127.0.0.1:0").await.unwrap();
+        let addr = first.local_addr().unwrap().to_string();
+        let second = config.bind(&addr).await.unwrap();
+        assert_eq!(second.local_addr().unwrap(), first.local_addr().unwrap());
+
+        // Without it the address is taken
+        assert!(SocketConfig::default().bind(&addr).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn accepted_socket_gets_options() {
+        let config = SocketConfig {
+            send_buffer_size: Some(256 * 1024),
+            tcp_keepalive: Some(Duration::from_secs(60)),
+            ..Default::default()
+        };
+        let listener = config.bind("127.0.0.1:0").await.unwrap();
+        let _client = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (stream, _) = listener.accept().await.unwrap();
+        config.apply(&stream).unwrap();
+
+        let sock = SockRef::from(&stream);
+        assert!(sock.keepalive().unwrap());
+        assert!(sock.send_buffer_size().unwrap() >= 256 * 1024);
+    }
+}
@@ -0,0 +1,159 @@
+//! Pluggable data sources.
+//!
+//! A [`DataSource`] feeds records into the server's [`DataStore`]. Sources are
+//! registered with [`SeedLinkServer::add_source()`](crate::SeedLinkServer::add_source)
+//! and started by [`SeedLinkServer::run()`](crate::SeedLinkServer::run), which
+//! supervises each one in its own task: a source that panics or returns an
+//! error is restarted after
+//! [`ServerConfig::source_restart_delay`](crate::ServerConfig::source_restart_delay).
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::task::JoinSet;
+use tracing::{info, warn};
+
+use crate::error::Result;
+use crate::metrics::SenderMetrics;
+use crate::store::DataStore;
+
+/// Boxed future returned by [`DataSource::run()`].
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Sources registered with a server, shared with its metrics handle.
+pub(crate) type SourceList = Arc<Mutex<Vec<Arc<dyn DataSource>>>>;
+
+/// A producer of records for the server, such as a file tailer, UDP listener,
+/// or upstream relay.
+///
+/// ```no_run
+/// use seedlink_rs_server::{DataStore, Result};
+/// use seedlink_rs_server::source::{BoxFuture, DataSource};
+///
+/// struct Heartbeat;
+///
+/// impl DataSource for Heartbeat {
+///     fn name(&self) -> &str {
+///         "heartbeat"
+///     }
+///
+///     fn run(&self, store: DataStore) -> BoxFuture<'_, Result<()>> {
+///         Box::pin(async move {
+///             loop {
+///                 store.push("XX", "TEST", &[0u8; 512]);
+///                 tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+///             }
+///         })
+///     }
+/// }
+/// ```
+pub trait DataSource: Send + Sync + 'static {
+    /// Name used in log messages. Default: `"source"`.
+    fn name(&self) -> &str {
+        "source"
+    }
+
+    /// Push records into `store` until the source is exhausted.
+    ///
+    /// Returning `Ok(())` stops the source for good. Returning an error or
+    /// panicking makes the server call `run` again after the restart delay.
+    fn run(&self, store: DataStore) -> BoxFuture<'_, Result<()>>;
+
+    /// Per-sender statistics, included in
+    /// [`MetricsSnapshot::senders`](crate::MetricsSnapshot::senders).
+    /// Default: none.
+    fn sender_metrics(&self) -> Vec<SenderMetrics> {
+        Vec::new()
+    }
+}
+
+/// Run `source` until it finishes, restarting it after errors and panics.
+///
+/// Dropping the returned future aborts the running source.
+pub(crate) async fn supervise(
+    source: Arc<dyn DataSource>,
+    store: DataStore,
+    restart_delay: Duration,
+) {
+    loop {
+        let mut task = JoinSet::new();
+        let (src, st) = (source.clone(), store.clone());
+        task.spawn(async move { src.run(st).await });
+
+        match task.join_next().await {
+            Some(Ok(Ok(()))) => {
+                info!(source = source.name(), "data source finished");
+                return;
+            }
+            Some(Ok(Err(e))) => {
+                warn!(source = source.name(), error = %e, "data source failed, restarting");
+            }
+            Some(Err(e)) if e.is_panic() => {
+                warn!(source = source.name(), "data source panicked, restarting");
+            }
+            _ => return,
+        }
+        tokio::time::sleep(restart_delay).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Panics on the first run, pushes one record on the second.
+    struct Flaky {
+        runs: AtomicUsize,
+    }
+
+    impl DataSource for Flaky {
+        fn run(&self, store: DataStore) -> BoxFuture<'_, Result<()>> {
+            Box::pin(async move {
+                if self.runs.fetch_add(1, Ordering::SeqCst) == 0 {
+                    panic!("first run fails");
+                }
+                store.push("IU", "ANMO", &[0u8; 512]);
+                Ok(())
+            })
+        }
+    }
+
+    /// Fails with an error until its third run.
+    struct Failing {
+        runs: AtomicUsize,
+    }
+
+    impl DataSource for Failing {
+        fn run(&self, _store: DataStore) -> BoxFuture<'_, Result<()>> {
+            Box::pin(async move {
+                if self.runs.fetch_add(1, Ordering::SeqCst) < 2 {
+                    return Err(crate::ServerError::Source("not ready".into()));
+                }
+                Ok(())
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn restarts_after_panic() {
+        let store = DataStore::new(10);
+        let source = Arc::new(Flaky {
+            runs: AtomicUsize::new(0),
+        });
+        supervise(source.clone(), store.clone(), Duration::from_millis(1)).await;
+        assert_eq!(source.runs.load(Ordering::SeqCst), 2);
+        assert_eq!(store.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn restarts_after_error() {
+        let source = Arc::new(Failing {
+            runs: AtomicUsize::new(0),
+        });
+        supervise(source.clone(), DataStore::new(10), Duration::from_millis(1)).await;
+        assert_eq!(source.runs.load(Ordering::SeqCst), 3);
+    }
+}
@@ -1,43 +1,126 @@
-use std::collections::{BTreeMap, VecDeque};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
 
-use seedlink_rs_protocol::SequenceNumber;
+use futures_core::Stream;
 use seedlink_rs_protocol::frame::v3;
-use tokio::sync::Notify;
+use seedlink_rs_protocol::validation::validate_record;
+use seedlink_rs_protocol::{SequenceNumber, ValidationMode};
+use tokio::sync::broadcast;
+use tracing::{debug, warn};
 
-use crate::select::SelectPattern;
-use crate::time::{TimeWindow, Timestamp};
+use crate::acl::glob_match;
+use crate::error::{Result, ServerError};
+use crate::metrics::StreamMetrics;
+use crate::select::{SelectPattern, Selector};
+use crate::time::{TimeWindow, Timestamp, record_end_time, record_start_time};
 
 /// A single record in the ring buffer.
+///
+/// Cloning is cheap: the payload is shared, not copied, so fanning a record
+/// out to many clients does not duplicate the 512-byte body.
 #[derive(Clone, Debug)]
 pub struct Record {
     pub sequence: SequenceNumber,
     pub network: String,
     pub station: String,
-    pub payload: Vec<u8>,
+    /// Location code from the fixed header (bytes 13..15), blanks trimmed.
+    pub location: String,
+    /// Channel code from the fixed header (bytes 15..18), blanks trimmed.
+    pub channel: String,
+    /// Data quality indicator from the fixed header (byte 6).
+    pub quality: u8,
+    pub payload: Arc<[u8]>,
+}
+
+impl Record {
+    /// Build a record, reading location, channel and quality from the
+    /// payload's fixed header once so filters need not re-parse it.
+    pub(crate) fn new(
+        sequence: SequenceNumber,
+        network: String,
+        station: String,
+        payload: Arc<[u8]>,
+    ) -> Self {
+        let code = |range: std::ops::Range<usize>| {
+            payload
+                .get(range)
+                .map(|b| String::from_utf8_lossy(b).trim().to_owned())
+                .unwrap_or_default()
+        };
+        Self {
+            sequence,
+            network,
+            station,
+            location: code(13..15),
+            channel: code(15..18),
+            quality: payload.get(6).copied().unwrap_or_default(),
+            payload,
+        }
+    }
 }
 
 /// Station subscription filter (network + station + optional SELECT/TIME filters).
+///
+/// Built by client handlers from STATION/SELECT/TIME, or with
+/// [`new()`](Self::new) and [`select()`](Self::select) for
+/// [`DataStore::subscribe()`].
 #[derive(Clone, Debug)]
-pub(crate) struct Subscription {
-    pub network: String,
-    pub station: String,
-    pub select_patterns: Vec<SelectPattern>,
-    pub time_window: Option<TimeWindow>,
+pub struct Subscription {
+    pub(crate) network: String,
+    pub(crate) station: String,
+    pub(crate) select_patterns: Vec<Selector>,
+    pub(crate) time_window: Option<TimeWindow>,
+    /// Set by `DATA seq` (or `DATA -1`): only records after this sequence
+    /// are delivered. Cleared once the stream reaches it, see
+    /// [`clear_passed_resume()`].
+    pub(crate) resume_seq: Option<u64>,
 }
 
 impl Subscription {
-    /// Check if a payload matches this subscription's SELECT patterns.
+    /// Subscribe to every channel of a station (case-insensitive, `*` and
+    /// `?` wildcards allowed).
+    pub fn new(network: &str, station: &str) -> Self {
+        Self {
+            network: network.to_owned(),
+            station: station.to_owned(),
+            select_patterns: Vec::new(),
+            time_window: None,
+            resume_seq: None,
+        }
+    }
+
+    /// Add a SELECT pattern (`[!][LL]CCC[.T]`, `?` wildcards), as the
+    /// `SELECT` command would.
+    pub fn select(mut self, pattern: &str) -> Result<Self> {
+        let pattern = SelectPattern::parse(pattern)
+            .ok_or_else(|| ServerError::InvalidSelect(pattern.to_owned()))?;
+        self.select_patterns.push(Selector::V3(pattern));
+        Ok(self)
+    }
+
+    /// Check if a record matches this subscription's SELECT patterns.
     ///
-    /// Empty `select_patterns` → match all (no SELECT = all channels).
-    /// Non-empty → any pattern matches = pass (OR logic).
-    pub fn matches_channel(&self, payload: &[u8]) -> bool {
-        if self.select_patterns.is_empty() {
-            return true;
+    /// Positive patterns are OR'ed: the record must match at least one of
+    /// them, or there must be none (no SELECT = all channels). Negated (`!`)
+    /// patterns are AND'ed as exclusions: matching any of them rejects the
+    /// record. So `!LOG` alone means "everything except LOG".
+    pub(crate) fn matches_channel(&self, record: &Record) -> bool {
+        let mut has_positive = false;
+        let mut included = false;
+        for p in &self.select_patterns {
+            let matched = p.matches(&record.location, &record.channel, record.quality);
+            if p.is_negated() {
+                if matched {
+                    return false;
+                }
+            } else {
+                has_positive = true;
+                included = included || matched;
+            }
         }
-        self.select_patterns
-            .iter()
-            .any(|p| p.matches_payload(payload))
+        included || !has_positive
     }
 
     /// Check if a payload's BTime timestamp falls within the TIME window.
@@ -45,7 +128,7 @@ impl Subscription {
     /// - `None` time_window → pass all (no TIME = no filter)
     /// - `Some(tw)` → parse BTime from payload, check `tw.contains()`
     /// - Unparseable BTime → reject (return false)
-    pub fn matches_time(&self, payload: &[u8]) -> bool {
+    pub(crate) fn matches_time(&self, payload: &[u8]) -> bool {
         let Some(ref tw) = self.time_window else {
             return true;
         };
@@ -54,6 +137,56 @@ impl Subscription {
             None => false,
         }
     }
+
+    /// Check if this subscription has a TIME window with an end time.
+    pub(crate) fn is_bounded(&self) -> bool {
+        self.time_window.as_ref().is_some_and(|tw| tw.end.is_some())
+    }
+
+    /// Check if `record` comes from this subscription's station. `*` and `?`
+    /// match as in [`AclRule`](crate::AclRule) patterns.
+    fn is_station_of(&self, record: &Record) -> bool {
+        self.is_station(&record.network, &record.station)
+    }
+
+    fn is_station(&self, network: &str, station: &str) -> bool {
+        glob_match(&self.network, network) && glob_match(&self.station, station)
+    }
+
+    /// Check if `record` is a stream of this subscription with data beyond
+    /// the TIME window end — i.e. the window is complete for this station.
+    pub(crate) fn is_past_end(&self, record: &Record) -> bool {
+        let Some(ref tw) = self.time_window else {
+            return false;
+        };
+        self.is_station_of(record)
+            && self.matches_channel(record)
+            && Timestamp::from_mseed_payload(&record.payload).is_some_and(|ts| tw.is_past_end(ts))
+    }
+}
+
+/// Check if a record matches any of the given subscriptions.
+pub(crate) fn matches_any(subscriptions: &[Subscription], record: &Record) -> bool {
+    subscriptions.iter().any(|s| {
+        s.is_station_of(record)
+            && s.resume_seq
+                .is_none_or(|seq| is_after_cursor(record.sequence, seq))
+            && s.matches_channel(record)
+            && s.matches_time(&record.payload)
+    })
+}
+
+/// Drop each `DATA seq` cursor the stream has reached: every later record is
+/// after it, and a cursor kept past the sequence wrap would hide records that
+/// only look older.
+pub(crate) fn clear_passed_resume(subscriptions: &mut [Subscription], cursor: u64) {
+    for s in subscriptions {
+        if s.resume_seq
+            .is_some_and(|seq| !is_after_cursor(SequenceNumber::new(seq), cursor))
+        {
+            s.resume_seq = None;
+        }
+    }
 }
 
 /// Station info returned by `DataStore::station_info()`.
@@ -75,71 +208,537 @@ pub(crate) struct StreamInfo {
     pub type_code: String,
     pub begin_seq: u64,
     pub end_seq: u64,
+    /// BTime start of the earliest buffered record with a valid start time.
+    pub begin_time: Option<SystemTime>,
+    /// End time (start plus samples) of the latest buffered record.
+    pub end_time: Option<SystemTime>,
+    /// Time since the end of the latest record of this stream.
+    pub latency: Option<Duration>,
+}
+
+/// Extract network and station from a miniSEED v2 fixed header.
+///
+/// Requires a numeric (or blank) sequence field, a valid quality indicator
+/// (`D`, `R`, `Q`, `M`), ASCII codes and a station code.
+pub(crate) fn record_ids(record: &[u8]) -> Result<(String, String)> {
+    let invalid = |reason: String| Err(ServerError::InvalidHeader(reason));
+    if record.len() < 20 {
+        return invalid(format!(
+            "{} bytes is shorter than the fixed header",
+            record.len()
+        ));
+    }
+    let seq_ok = record[0..6]
+        .iter()
+        .all(|b| b.is_ascii_digit() || *b == b' ' || *b == 0);
+    if !seq_ok {
+        return invalid("sequence number is not numeric".into());
+    }
+    if !b"DRQM".contains(&record[6]) {
+        return invalid(format!(
+            "invalid data quality indicator {:?}",
+            char::from(record[6])
+        ));
+    }
+    if !record[8..20].is_ascii() {
+        return invalid("non-ASCII station identifiers".into());
+    }
+    let station = String::from_utf8_lossy(&record[8..13]).trim().to_owned();
+    let network = String::from_utf8_lossy(&record[18..20]).trim().to_owned();
+    if station.is_empty() {
+        return invalid("empty station code".into());
+    }
+    Ok((network, station))
+}
+
+/// What the server does when a client resumes with `DATA seq` or `FETCH seq`
+/// but records of a subscribed station after `seq` have been evicted, and the
+/// client gave no start time to fall back to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ResumeMissPolicy {
+    /// Stream from the oldest buffered record.
+    Oldest,
+    /// Reply `ERROR` (`ERROR ARGUMENTS` with extended replies and v4), so the
+    /// client can resume by time or accept the gap explicitly.
+    #[default]
+    Reject,
+}
+
+/// What [`DataStore::push_with_sequence()`] does with a sequence that does
+/// not come after the last sequence in the store.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SequencePolicy {
+    /// Drop the record and return [`ServerError::SequenceOutOfOrder`].
+    #[default]
+    Reject,
+    /// Store the record under the next local sequence instead.
+    Reassign,
+}
+
+/// Configuration for a [`DataStore`].
+#[derive(Clone, Debug, Default)]
+pub struct StoreConfig {
+    /// Handling of out-of-order sequences passed to
+    /// [`DataStore::push_with_sequence()`]. Default: [`SequencePolicy::Reject`].
+    pub sequence_policy: SequencePolicy,
+    /// Number of recent records remembered to drop duplicates, keyed by stream
+    /// (NSLC) and record start time. `0` disables. Default: `0`.
+    pub dedup_window: usize,
+    /// Records held back per stream and released in start-time order, so late
+    /// records are sequenced before the ones they precede. `0` disables.
+    /// Default: `0`.
+    pub reorder_depth: usize,
+    /// Check pushed records for corruption (miniSEED 2 header sanity) and
+    /// drop or flag invalid ones. [`push()`](DataStore::push) cannot fail, so
+    /// there [`ValidationMode::Error`] drops like [`ValidationMode::Drop`];
+    /// [`push_record()`](DataStore::push_record) and
+    /// [`push_with_sequence()`](DataStore::push_with_sequence) return
+    /// [`ServerError::InvalidRecord`] for both. Default: [`ValidationMode::Off`].
+    pub validation: ValidationMode,
+    /// Evict records whose BTime start is older than this, on push and on
+    /// [`evict_expired()`](DataStore::evict_expired). Records without a valid
+    /// start time are kept. `None` disables. Default: `None`.
+    pub retention: Option<Duration>,
+    /// Most records kept per (network, station), within the ring capacity. A
+    /// station over its limit loses its own oldest record, so a busy station
+    /// cannot push the others out of the ring. `None` disables. Default: `None`.
+    pub station_capacity: Option<usize>,
+    /// Per-station overrides of [`station_capacity`](Self::station_capacity),
+    /// keyed by (network, station). Default: empty.
+    pub station_capacities: HashMap<(String, String), usize>,
+}
+
+/// One record of a [`RingSnapshot`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SnapshotRecord {
+    /// Sequence number the record was stored under.
+    pub sequence: u64,
+    pub network: String,
+    pub station: String,
+    pub payload: Vec<u8>,
+}
+
+/// Contents of a [`DataStore`] ring, from [`DataStore::snapshot()`].
+///
+/// With the `serde` feature it can be serialized, e.g. to hand the buffer to
+/// the next process in a blue/green deployment.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RingSnapshot {
+    /// Sequence the next pushed record would get.
+    pub next_sequence: u64,
+    /// Buffered records, oldest first.
+    pub records: Vec<SnapshotRecord>,
+}
+
+/// Stream (station, location, channel, network) and BTime bytes of a record.
+type RecordKey = ([u8; 12], [u8; 9]);
+
+/// Identify a record by its fixed header: NSLC bytes and start time.
+///
+/// The BTime bytes (year, day, hour, minute, second, ticks) are big-endian,
+/// so comparing them orders records by start time. Returns `None` if the
+/// start time is not valid.
+fn record_key(payload: &[u8]) -> Option<RecordKey> {
+    Timestamp::from_mseed_payload(payload)?;
+    let stream: [u8; 12] = payload[8..20].try_into().ok()?;
+    let mut btime = [0u8; 9];
+    btime[..7].copy_from_slice(&payload[20..27]);
+    btime[7..].copy_from_slice(&payload[28..30]);
+    Some((stream, btime))
+}
+
+/// A record waiting in the reorder buffer.
+struct Held {
+    start: [u8; 9],
+    network: String,
+    station: String,
+    payload: Arc<[u8]>,
 }
 
 struct Ring {
-    buf: VecDeque<Record>,
+    /// Buffered records by insertion slot, oldest first. A map rather than a
+    /// deque so a per-station limit can drop a record from the middle.
+    buf: BTreeMap<u64, Record>,
+    /// Slot the next stored record gets.
+    next_slot: u64,
     capacity: usize,
+    /// Payload byte budget; `0` means unlimited.
+    capacity_bytes: usize,
+    /// Payload bytes currently in `buf`.
+    bytes: usize,
     next_seq: u64,
+    last_seq: Option<u64>,
+    pushed: u64,
+    evicted: u64,
+    duplicates: u64,
+    dedup_window: usize,
+    seen: HashSet<RecordKey>,
+    seen_order: VecDeque<RecordKey>,
+    reorder_depth: usize,
+    held: HashMap<[u8; 12], Vec<Held>>,
+    retention: Option<Duration>,
+    /// Latest record end time per stream (NSLC bytes), kept after eviction.
+    stream_end: HashMap<[u8; 12], SystemTime>,
+    /// Newest evicted sequence per (network, station), to tell whether a
+    /// client resuming after a sequence has missed records.
+    evicted_through: HashMap<(String, String), u64>,
+    station_capacity: Option<usize>,
+    station_capacities: HashMap<(String, String), usize>,
+    /// Slots in `buf` per (network, station), oldest first, kept only while
+    /// a per-station limit is configured.
+    station_slots: HashMap<(String, String), VecDeque<u64>>,
 }
 
 impl Ring {
-    fn new(capacity: usize) -> Self {
+    fn new(capacity: usize, capacity_bytes: usize, config: &StoreConfig) -> Self {
         Self {
-            buf: VecDeque::with_capacity(capacity),
+            buf: BTreeMap::new(),
+            next_slot: 0,
             capacity,
+            capacity_bytes,
+            bytes: 0,
             next_seq: 1,
+            last_seq: None,
+            pushed: 0,
+            evicted: 0,
+            duplicates: 0,
+            dedup_window: config.dedup_window,
+            seen: HashSet::new(),
+            seen_order: VecDeque::new(),
+            reorder_depth: config.reorder_depth,
+            held: HashMap::new(),
+            retention: config.retention,
+            stream_end: HashMap::new(),
+            evicted_through: HashMap::new(),
+            station_capacity: config.station_capacity,
+            station_capacities: config.station_capacities.clone(),
+            station_slots: HashMap::new(),
+        }
+    }
+
+    fn limits_stations(&self) -> bool {
+        self.station_capacity.is_some() || !self.station_capacities.is_empty()
+    }
+
+    /// Record limit of `key`, if any.
+    fn station_limit(&self, key: &(String, String)) -> Option<usize> {
+        self.station_capacities
+            .get(key)
+            .copied()
+            .or(self.station_capacity)
+    }
+
+    /// Drop `slot` from the station index once its record has left `buf`.
+    fn forget_station(&mut self, network: &str, station: &str, slot: u64) {
+        if !self.limits_stations() {
+            return;
+        }
+        let key = (network.to_owned(), station.to_owned());
+        if let Some(slots) = self.station_slots.get_mut(&key) {
+            if slots.front() == Some(&slot) {
+                slots.pop_front();
+            } else {
+                slots.retain(|&s| s != slot);
+            }
+            if slots.is_empty() {
+                self.station_slots.remove(&key);
+            }
+        }
+    }
+
+    /// Index the record just stored at `slot` for `key` and evict the
+    /// station's oldest record if that takes it over its limit; the newest
+    /// record always stays.
+    fn enforce_station_limit(&mut self, key: (String, String), slot: u64) {
+        let limit = self.station_limit(&key);
+        let slots = self.station_slots.entry(key).or_default();
+        slots.push_back(slot);
+        if limit.is_none_or(|limit| slots.len() <= limit.max(1)) {
+            return;
         }
+        let oldest = slots.pop_front().expect("station has an older record");
+        let old = self.buf.remove(&oldest).expect("indexed slot is buffered");
+        self.bytes -= old.payload.len();
+        self.evicted += 1;
+        self.note_evicted(old.network, old.station, old.sequence);
     }
 
-    fn push(&mut self, network: String, station: String, payload: Vec<u8>) -> SequenceNumber {
-        let seq = SequenceNumber::new(self.next_seq);
+    /// Remember `payload` for duplicate detection. Returns `false` if a record
+    /// with the same stream and start time was seen within the window.
+    fn remember(&mut self, payload: &[u8]) -> bool {
+        if self.dedup_window == 0 {
+            return true;
+        }
+        let Some(key) = record_key(payload) else {
+            return true;
+        };
+        if !self.seen.insert(key) {
+            self.duplicates += 1;
+            return false;
+        }
+        self.seen_order.push_back(key);
+        if self.seen_order.len() > self.dedup_window
+            && let Some(old) = self.seen_order.pop_front()
+        {
+            self.seen.remove(&old);
+        }
+        true
+    }
 
-        self.buf.push_back(Record {
-            sequence: seq,
+    /// Pass a record through the reorder buffer and store whatever it releases.
+    ///
+    /// Returns the stored records, in sequence order.
+    fn ingest(&mut self, network: String, station: String, payload: Arc<[u8]>) -> Vec<Record> {
+        let key = record_key(&payload).filter(|_| self.reorder_depth > 0);
+        let Some((stream, start)) = key else {
+            return vec![self.push(network, station, payload)];
+        };
+        let held = self.held.entry(stream).or_default();
+        held.push(Held {
+            start,
             network,
             station,
             payload,
         });
+        if held.len() <= self.reorder_depth {
+            return Vec::new();
+        }
+        let (earliest, _) = held
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, h)| h.start)
+            .expect("held is not empty");
+        let h = held.swap_remove(earliest);
+        vec![self.push(h.network, h.station, h.payload)]
+    }
+
+    /// Store every held record, earliest start time first within each stream.
+    fn release_held(&mut self) -> Vec<Record> {
+        let mut held: Vec<Held> = self.held.drain().flat_map(|(_, v)| v).collect();
+        held.sort_by_key(|h| h.start);
+        held.into_iter()
+            .map(|h| self.push(h.network, h.station, h.payload))
+            .collect()
+    }
+
+    fn push(&mut self, network: String, station: String, payload: Arc<[u8]>) -> Record {
+        self.push_at(self.next_seq, network, station, payload)
+    }
+
+    /// Whether `seq` comes after the last sequence stored (wrap-aware).
+    fn accepts(&self, seq: u64) -> bool {
+        self.last_seq
+            .is_none_or(|last| is_after_cursor(SequenceNumber::new(seq), last))
+    }
 
-        // Evict oldest if over capacity
-        if self.buf.len() > self.capacity {
-            self.buf.pop_front();
+    fn push_at(
+        &mut self,
+        seq: u64,
+        network: String,
+        station: String,
+        payload: Arc<[u8]>,
+    ) -> Record {
+        if let Some(end) = record_end_time(&payload)
+            && let Ok(stream) = <[u8; 12]>::try_from(&payload[8..20])
+        {
+            self.stream_end
+                .entry(stream)
+                .and_modify(|latest| *latest = (*latest).max(end))
+                .or_insert(end);
+        }
+        let record = Record::new(SequenceNumber::new(seq), network, station, payload);
+        let slot = self.next_slot;
+        self.next_slot += 1;
+        self.bytes += record.payload.len();
+        self.buf.insert(slot, record.clone());
+        self.pushed += 1;
+        if self.limits_stations() {
+            self.enforce_station_limit((record.network.clone(), record.station.clone()), slot);
+        }
+
+        // Evict oldest while over either limit; the newest record always stays
+        while self.buf.len() > self.capacity || (self.over_bytes() && self.buf.len() > 1) {
+            self.evict_front();
+        }
+        if self.retention.is_some() {
+            let now = SystemTime::now();
+            while self
+                .buf
+                .first_key_value()
+                .is_some_and(|(_, r)| self.is_expired(r, now))
+            {
+                self.evict_front();
+            }
         }
 
         // Advance and wrap at V3_MAX back to 1
-        self.next_seq += 1;
+        self.last_seq = Some(seq);
+        self.next_seq = seq + 1;
         if self.next_seq > SequenceNumber::V3_MAX {
             self.next_seq = 1;
         }
 
-        seq
+        record
+    }
+
+    fn evict_front(&mut self) {
+        if let Some((slot, old)) = self.buf.pop_first() {
+            self.forget_station(&old.network, &old.station, slot);
+            self.bytes -= old.payload.len();
+            self.evicted += 1;
+            self.note_evicted(old.network, old.station, old.sequence);
+        }
+    }
+
+    fn note_evicted(&mut self, network: String, station: String, seq: SequenceNumber) {
+        let last = self.evicted_through.entry((network, station)).or_insert(0);
+        if is_after_cursor(seq, *last) {
+            *last = seq.value();
+        }
+    }
+
+    /// Whether `record` started more than the retention window before `now`.
+    fn is_expired(&self, record: &Record, now: SystemTime) -> bool {
+        self.retention.is_some_and(|retention| {
+            record_start_time(&record.payload)
+                .is_some_and(|start| now.duration_since(start).unwrap_or_default() > retention)
+        })
+    }
+
+    /// Evict every expired record, wherever it sits in the ring.
+    fn evict_expired(&mut self, now: SystemTime) -> usize {
+        if self.retention.is_none() {
+            return 0;
+        }
+        let before = self.buf.len();
+        let mut freed = 0;
+        let mut expired = Vec::new();
+        let mut buf = std::mem::take(&mut self.buf);
+        buf.retain(|&slot, r| {
+            let keep = !self.is_expired(r, now);
+            if !keep {
+                freed += r.payload.len();
+                expired.push((slot, r.network.clone(), r.station.clone(), r.sequence));
+            }
+            keep
+        });
+        self.buf = buf;
+        for (slot, network, station, seq) in expired {
+            self.forget_station(&network, &station, slot);
+            self.note_evicted(network, station, seq);
+        }
+        let n = before - self.buf.len();
+        self.bytes -= freed;
+        self.evicted += n as u64;
+        n
+    }
+
+    fn over_bytes(&self) -> bool {
+        self.capacity_bytes > 0 && self.bytes > self.capacity_bytes
+    }
+
+    fn snapshot(&self) -> RingSnapshot {
+        RingSnapshot {
+            next_sequence: self.next_seq,
+            records: self
+                .buf
+                .values()
+                .map(|r| SnapshotRecord {
+                    sequence: r.sequence.value(),
+                    network: r.network.clone(),
+                    station: r.station.clone(),
+                    payload: r.payload.to_vec(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Replace the buffered records with `records`, keeping the counters.
+    fn restore(&mut self, records: Vec<SnapshotRecord>, next_seq: u64) {
+        let (pushed, evicted) = (self.pushed, self.evicted);
+        self.buf.clear();
+        self.bytes = 0;
+        self.last_seq = None;
+        self.held.clear();
+        self.seen.clear();
+        self.seen_order.clear();
+        self.stream_end.clear();
+        self.evicted_through.clear();
+        self.station_slots.clear();
+        for r in records {
+            self.remember(&r.payload);
+            self.push_at(r.sequence, r.network, r.station, Arc::from(r.payload));
+        }
+        self.next_seq = next_seq;
+        self.pushed = pushed;
+        self.evicted = evicted;
     }
 
     fn read_since(&self, cursor: u64, subscriptions: &[Subscription]) -> Vec<Record> {
         self.buf
-            .iter()
-            .filter(|r| r.sequence.value() > cursor)
-            .filter(|r| {
-                subscriptions.iter().any(|s| {
-                    s.network.eq_ignore_ascii_case(&r.network)
-                        && s.station.eq_ignore_ascii_case(&r.station)
-                        && s.matches_channel(&r.payload)
-                        && s.matches_time(&r.payload)
-                })
-            })
+            .values()
+            .filter(|r| is_after_cursor(r.sequence, cursor))
+            .filter(|r| matches_any(subscriptions, r))
             .cloned()
             .collect()
     }
 }
 
+/// The earlier of two optional times, ignoring `None`.
+fn earliest(a: Option<SystemTime>, b: Option<SystemTime>) -> Option<SystemTime> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Whether `seq` comes after `cursor`, accounting for the wrap at
+/// [`SequenceNumber::V3_MAX`]. Sequence 0 is never assigned, so a cursor of 0
+/// means nothing has been delivered yet.
+pub(crate) fn is_after_cursor(seq: SequenceNumber, cursor: u64) -> bool {
+    cursor == 0 || seq.is_after(SequenceNumber::new(cursor), SequenceNumber::V3_WRAP_WINDOW)
+}
+
+/// How many sequences `seq` lies behind `newest`, following the wrap from
+/// [`SequenceNumber::V3_MAX`] back to 1.
+fn sequences_behind(newest: u64, seq: u64) -> u64 {
+    let max = SequenceNumber::V3_MAX;
+    (newest % max + max - seq % max) % max
+}
+
+/// Evict expired records every tenth of `retention` (between 1 s and 1 min),
+/// so the ring shrinks even while nothing is pushed.
+pub(crate) async fn sweep_expired(store: DataStore, retention: Duration) {
+    let period = (retention / 10).clamp(Duration::from_secs(1), Duration::from_secs(60));
+    let mut interval = tokio::time::interval(period);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        let evicted = store.evict_expired();
+        if evicted > 0 {
+            debug!(evicted, "evicted expired records");
+        }
+    }
+}
+
+/// Number of live records buffered per streaming client before it is
+/// considered lagged and must catch up from the ring instead.
+const LIVE_CAPACITY: usize = 1024;
+
 struct StoreInner {
     ring: Mutex<Ring>,
-    notify: Notify,
+    live: broadcast::Sender<Record>,
+    config: StoreConfig,
+    invalid: AtomicU64,
 }
 
 /// Thread-safe data store backed by an in-memory ring buffer.
 ///
+/// New records are fanned out to streaming clients over a broadcast channel;
+/// the ring is only scanned when a client starts streaming or falls behind.
+///
 /// Clone is cheap (Arc).
 #[derive(Clone)]
 pub struct DataStore(Arc<StoreInner>);
@@ -147,9 +746,23 @@ pub struct DataStore(Arc<StoreInner>);
 impl DataStore {
     /// Create a new store with the given ring buffer capacity.
     pub fn new(capacity: usize) -> Self {
+        Self::with_config(capacity, StoreConfig::default())
+    }
+
+    /// Create a new store with the given ring buffer capacity and configuration.
+    pub fn with_config(capacity: usize, config: StoreConfig) -> Self {
+        Self::with_limits(capacity, 0, config)
+    }
+
+    /// Create a new store that evicts the oldest records once it holds more
+    /// than `capacity` records or more than `capacity_bytes` payload bytes,
+    /// whichever comes first. A `capacity_bytes` of `0` means no byte limit.
+    pub fn with_limits(capacity: usize, capacity_bytes: usize, config: StoreConfig) -> Self {
         Self(Arc::new(StoreInner {
-            ring: Mutex::new(Ring::new(capacity)),
-            notify: Notify::new(),
+            ring: Mutex::new(Ring::new(capacity, capacity_bytes, &config)),
+            live: broadcast::channel(LIVE_CAPACITY).0,
+            config,
+            invalid: AtomicU64::new(0),
         }))
     }
 
@@ -158,6 +771,12 @@ impl DataStore {
     /// Payload must be exactly 512 bytes (miniSEED v2 record size).
     /// Returns the assigned sequence number.
     ///
+    /// With [`StoreConfig::dedup_window`] set, a duplicate record is dropped;
+    /// with [`StoreConfig::reorder_depth`] set, the record may be held back
+    /// and sequenced later. In both cases [`SequenceNumber::UNSET`] is
+    /// returned. Records without a readable start time bypass both.
+    /// Records failing [`StoreConfig::validation`] are dropped the same way.
+    ///
     /// # Panics
     ///
     /// Panics if `payload.len() != 512`.
@@ -170,130 +789,496 @@ impl DataStore {
             payload.len()
         );
 
-        let seq = self.0.ring.lock().unwrap().push(
-            network.to_owned(),
-            station.to_owned(),
-            payload.to_vec(),
-        );
+        if self.check(network, station, payload).is_err() {
+            return SequenceNumber::UNSET;
+        }
+        self.store(network, station, payload)
+    }
 
-        self.0.notify.notify_waiters();
-        seq
+    /// Push a miniSEED v2 record, taking network and station from its fixed
+    /// header instead of the caller.
+    ///
+    /// Unlike [`push()`](Self::push), bad input is an error: a payload that
+    /// is not 512 bytes is [`ServerError::InvalidPayloadLength`], a header
+    /// without a numeric sequence field, valid quality indicator or station
+    /// code is [`ServerError::InvalidHeader`], and a record failing
+    /// [`StoreConfig::validation`] is [`ServerError::InvalidRecord`] as in
+    /// [`push_with_sequence()`](Self::push_with_sequence). Duplicates and
+    /// records held for reordering return [`SequenceNumber::UNSET`] as in
+    /// [`push()`](Self::push).
+    pub fn push_record(&self, payload: &[u8]) -> Result<SequenceNumber> {
+        if payload.len() != v3::PAYLOAD_LEN {
+            return Err(ServerError::InvalidPayloadLength(payload.len()));
+        }
+        let (network, station) = record_ids(payload)?;
+        self.check(&network, &station, payload)?;
+        Ok(self.store(&network, &station, payload))
     }
 
-    /// Read all records with sequence > cursor that match the given subscriptions.
-    pub(crate) fn read_since(&self, cursor: u64, subscriptions: &[Subscription]) -> Vec<Record> {
-        self.0
-            .ring
-            .lock()
-            .unwrap()
-            .read_since(cursor, subscriptions)
+    /// Dedup, reorder and store a checked record; broadcast what is stored.
+    fn store(&self, network: &str, station: &str, payload: &[u8]) -> SequenceNumber {
+        let payload: Arc<[u8]> = Arc::from(payload);
+        let records = {
+            let mut ring = self.0.ring.lock().unwrap();
+            if !ring.remember(&payload) {
+                return SequenceNumber::UNSET;
+            }
+            ring.ingest(network.to_owned(), station.to_owned(), payload.clone())
+        };
+        let seq = records
+            .iter()
+            .find(|r| Arc::ptr_eq(&r.payload, &payload))
+            .map_or(SequenceNumber::UNSET, |r| r.sequence);
+        self.broadcast(records);
+        seq
     }
 
-    /// Returns a future that completes when new data is pushed.
+    /// Store every record held in the reorder buffer now.
     ///
-    /// **Important:** call this *before* `read_since()` to avoid missing
-    /// pushes that happen between read and wait.
-    pub(crate) fn notified(&self) -> tokio::sync::futures::Notified<'_> {
-        self.0.notify.notified()
+    /// Call before shutting down, or when a feed pauses, so held records are
+    /// not left waiting for [`StoreConfig::reorder_depth`] later ones. Returns
+    /// the number of records released.
+    pub fn flush_held(&self) -> usize {
+        let records = self.0.ring.lock().unwrap().release_held();
+        let n = records.len();
+        self.broadcast(records);
+        n
     }
 
-    /// Enumerate unique stations in the ring with min/max sequence numbers.
-    pub(crate) fn station_info(&self) -> Vec<StationInfo> {
-        let ring = self.0.ring.lock().unwrap();
-        // Key: (network, station) → (begin_seq, end_seq)
-        let mut map: BTreeMap<(String, String), (u64, u64)> = BTreeMap::new();
-        for r in &ring.buf {
-            let key = (r.network.clone(), r.station.clone());
-            let seq = r.sequence.value();
-            map.entry(key)
-                .and_modify(|(begin, end)| {
-                    if seq < *begin {
-                        *begin = seq;
-                    }
-                    if seq > *end {
-                        *end = seq;
-                    }
-                })
-                .or_insert((seq, seq));
+    fn broadcast(&self, records: Vec<Record>) {
+        for record in records {
+            // No receivers is fine — nobody is streaming right now.
+            let _ = self.0.live.send(record);
         }
-        map.into_iter()
-            .map(|((network, station), (begin_seq, end_seq))| StationInfo {
-                network,
-                station,
-                begin_seq,
-                end_seq,
-            })
-            .collect()
     }
 
-    /// Enumerate unique streams in the ring with channel detail extracted from payload bytes.
-    pub(crate) fn stream_info(&self) -> Vec<StreamInfo> {
-        type StreamKey = (String, String, String, String);
-        type StreamVal = (String, u64, u64);
+    /// Push a record under a sequence number chosen by the caller, e.g. the
+    /// upstream sequence when relaying, so clients can move between servers
+    /// and resume with the same `DATA seq`.
+    ///
+    /// `sequence` must be in `1..=V3_MAX` and, wrap-aware, come after the last
+    /// sequence in the store; otherwise the configured [`SequencePolicy`]
+    /// applies. Later [`push()`](Self::push) calls continue from `sequence`.
+    /// Duplicates are rejected as in [`push()`](Self::push), but the reorder
+    /// buffer is bypassed. Returns the assigned sequence number.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `payload.len() != 512`.
+    pub fn push_with_sequence(
+        &self,
+        network: &str,
+        station: &str,
+        payload: &[u8],
+        sequence: SequenceNumber,
+    ) -> Result<SequenceNumber> {
+        assert_eq!(
+            payload.len(),
+            v3::PAYLOAD_LEN,
+            "payload must be exactly {} bytes, got {}",
+            v3::PAYLOAD_LEN,
+            payload.len()
+        );
+        if !(1..=SequenceNumber::V3_MAX).contains(&sequence.value()) {
+            return Err(ServerError::InvalidSequence(sequence));
+        }
+        self.check(network, station, payload)?;
 
-        let ring = self.0.ring.lock().unwrap();
-        // Key: (network, station, location, channel) → (type_code, begin_seq, end_seq)
-        let mut map: BTreeMap<StreamKey, StreamVal> = BTreeMap::new();
-        for r in &ring.buf {
-            if r.payload.len() < 20 {
-                continue;
+        let mut ring = self.0.ring.lock().unwrap();
+        let seq = if ring.accepts(sequence.value()) {
+            sequence.value()
+        } else {
+            match self.0.config.sequence_policy {
+                SequencePolicy::Reject => {
+                    return Err(ServerError::SequenceOutOfOrder {
+                        sequence,
+                        last: SequenceNumber::new(ring.last_seq.unwrap_or_default()),
+                    });
+                }
+                SequencePolicy::Reassign => ring.next_seq,
             }
-            let location = String::from_utf8_lossy(&r.payload[13..15]).to_string();
-            let channel = String::from_utf8_lossy(&r.payload[15..18]).to_string();
-            let type_code = String::from_utf8_lossy(&r.payload[6..7]).to_string();
-            let key = (r.network.clone(), r.station.clone(), location, channel);
-            let seq = r.sequence.value();
-            map.entry(key)
-                .and_modify(|(tc, begin, end)| {
-                    // Keep latest type code
-                    *tc = type_code.clone();
-                    if seq < *begin {
-                        *begin = seq;
-                    }
-                    if seq > *end {
-                        *end = seq;
-                    }
-                })
-                .or_insert((type_code, seq, seq));
+        };
+        if !ring.remember(payload) {
+            return Err(ServerError::DuplicateRecord);
         }
-        map.into_iter()
-            .map(
-                |((network, station, location, channel), (type_code, begin_seq, end_seq))| {
-                    StreamInfo {
-                        network,
-                        station,
-                        channel,
-                        location,
-                        type_code,
-                        begin_seq,
-                        end_seq,
-                    }
-                },
-            )
-            .collect()
+        let record = ring.push_at(
+            seq,
+            network.to_owned(),
+            station.to_owned(),
+            Arc::from(payload),
+        );
+        drop(ring);
+
+        let seq = record.sequence;
+        let _ = self.0.live.send(record);
+        Ok(seq)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Evict every record older than [`StoreConfig::retention`].
+    ///
+    /// Pushes only evict expired records from the front of the ring, and not
+    /// at all while the feed is idle; [`SeedLinkServer::run()`](crate::SeedLinkServer::run)
+    /// calls this periodically. Returns the number of records evicted.
+    pub fn evict_expired(&self) -> usize {
+        self.evict_expired_at(SystemTime::now())
+    }
 
-    fn dummy_payload() -> Vec<u8> {
-        vec![0u8; v3::PAYLOAD_LEN]
+    pub(crate) fn evict_expired_at(&self, now: SystemTime) -> usize {
+        self.0.ring.lock().unwrap().evict_expired(now)
     }
 
-    #[test]
-    fn push_assigns_increasing_sequences() {
-        let store = DataStore::new(100);
-        let s1 = store.push("IU", "ANMO", &dummy_payload());
-        let s2 = store.push("IU", "ANMO", &dummy_payload());
-        let s3 = store.push("GE", "WLF", &dummy_payload());
-        assert_eq!(s1.value(), 1);
-        assert_eq!(s2.value(), 2);
-        assert_eq!(s3.value(), 3);
+    /// Retention window from [`StoreConfig::retention`].
+    pub(crate) fn retention(&self) -> Option<Duration> {
+        self.0.config.retention
     }
 
-    #[test]
+    /// Apply [`StoreConfig::validation`]. Returns an error if the record must
+    /// not be stored.
+    fn check(&self, network: &str, station: &str, payload: &[u8]) -> Result<()> {
+        let mode = self.0.config.validation;
+        if mode == ValidationMode::Off {
+            return Ok(());
+        }
+        let Err(error) = validate_record(payload) else {
+            return Ok(());
+        };
+        self.0.invalid.fetch_add(1, Ordering::Relaxed);
+        if mode == ValidationMode::Flag {
+            warn!(network, station, %error, "storing invalid record");
+            return Ok(());
+        }
+        warn!(network, station, %error, "dropping invalid record");
+        Err(ServerError::InvalidRecord(error))
+    }
+
+    /// Copy the records currently in the ring, with the next sequence number.
+    ///
+    /// Records held in the reorder buffer are not included; call
+    /// [`flush_held()`](Self::flush_held) first to keep them.
+    pub fn snapshot(&self) -> RingSnapshot {
+        self.0.ring.lock().unwrap().snapshot()
+    }
+
+    /// Replace the ring contents with `snapshot`, e.g. one taken by another
+    /// process, so clients can resume with `DATA seq` across the handover.
+    ///
+    /// Records are stored as-is, in snapshot order, and not sent to clients
+    /// already streaming. If the snapshot holds more than this store's
+    /// capacity, the oldest records are evicted. Push and eviction counters
+    /// are kept. Returns [`ServerError::InvalidSequence`], leaving the store
+    /// unchanged, if any sequence is outside `1..=V3_MAX`.
+    pub fn restore(&self, snapshot: RingSnapshot) -> Result<()> {
+        let valid = |seq: u64| (1..=SequenceNumber::V3_MAX).contains(&seq);
+        if let Some(bad) = std::iter::once(snapshot.next_sequence)
+            .chain(snapshot.records.iter().map(|r| r.sequence))
+            .find(|&seq| !valid(seq))
+        {
+            return Err(ServerError::InvalidSequence(SequenceNumber::new(bad)));
+        }
+        self.0
+            .ring
+            .lock()
+            .unwrap()
+            .restore(snapshot.records, snapshot.next_sequence);
+        Ok(())
+    }
+
+    /// Read all records after `cursor` that match the given subscriptions.
+    ///
+    /// The comparison is wrap-aware (see [`is_after_cursor`]), so a cursor
+    /// just before the wrap still returns the records after it.
+    pub(crate) fn read_since(&self, cursor: u64, subscriptions: &[Subscription]) -> Vec<Record> {
+        self.0
+            .ring
+            .lock()
+            .unwrap()
+            .read_since(cursor, subscriptions)
+    }
+
+    /// Whether records of a station matching `sub` that came after sequence
+    /// `seq` have been evicted, i.e. resuming after `seq` leaves a gap.
+    ///
+    /// Sequences are compared by how far they lie behind the newest one, so
+    /// a resume from more than [`SequenceNumber::V3_WRAP_WINDOW`] ago is
+    /// still judged correctly.
+    pub(crate) fn resume_missed(&self, sub: &Subscription, seq: u64) -> bool {
+        let ring = self.0.ring.lock().unwrap();
+        let Some(newest) = ring.last_seq else {
+            return false;
+        };
+        let behind = sequences_behind(newest, seq);
+        ring.evicted_through
+            .iter()
+            .any(|((network, station), &last)| {
+                sub.is_station(network, station) && sequences_behind(newest, last) < behind
+            })
+    }
+
+    /// Sequence of the newest record in the ring.
+    pub(crate) fn last_sequence(&self) -> Option<SequenceNumber> {
+        self.0
+            .ring
+            .lock()
+            .unwrap()
+            .buf
+            .last_key_value()
+            .map(|(_, r)| r.sequence)
+    }
+
+    /// Number of records currently in the ring.
+    pub fn len(&self) -> usize {
+        self.0.ring.lock().unwrap().buf.len()
+    }
+
+    /// Returns `true` if the ring holds no records.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Maximum number of records the ring holds before evicting.
+    pub fn capacity(&self) -> usize {
+        self.0.ring.lock().unwrap().capacity
+    }
+
+    /// Payload bytes of the records currently in the ring.
+    pub fn bytes(&self) -> usize {
+        self.0.ring.lock().unwrap().bytes
+    }
+
+    /// Maximum payload bytes the ring holds before evicting (`0` = no limit).
+    pub fn capacity_bytes(&self) -> usize {
+        self.0.ring.lock().unwrap().capacity_bytes
+    }
+
+    /// Total records pushed and total records evicted since creation.
+    pub(crate) fn push_counts(&self) -> (u64, u64) {
+        let ring = self.0.ring.lock().unwrap();
+        (ring.pushed, ring.evicted)
+    }
+
+    /// Total records dropped as duplicates since creation.
+    pub(crate) fn duplicate_count(&self) -> u64 {
+        self.0.ring.lock().unwrap().duplicates
+    }
+
+    /// Total records that failed [`StoreConfig::validation`] since creation.
+    pub(crate) fn invalid_count(&self) -> u64 {
+        self.0.invalid.load(Ordering::Relaxed)
+    }
+
+    /// Check if any record currently in the ring satisfies `pred`.
+    pub(crate) fn any(&self, pred: impl Fn(&Record) -> bool) -> bool {
+        self.0.ring.lock().unwrap().buf.values().any(pred)
+    }
+
+    /// Subscribe to records pushed from now on.
+    ///
+    /// **Important:** subscribe *before* `read_since()` to avoid missing
+    /// pushes that happen between the ring scan and the first `recv()`.
+    /// Records already returned by the scan must be skipped by sequence.
+    pub(crate) fn subscribe_live(&self) -> broadcast::Receiver<Record> {
+        self.0.live.subscribe()
+    }
+
+    /// Stream records pushed from now on that match any of `subscriptions`,
+    /// for in-process consumers (e.g. a QC task) that do not go through the
+    /// SeedLink protocol.
+    ///
+    /// A consumer that falls behind the live feed catches up from the ring,
+    /// as streaming clients do; records evicted meanwhile are skipped. The
+    /// stream never ends on its own.
+    pub fn subscribe(
+        &self,
+        subscriptions: Vec<Subscription>,
+    ) -> impl Stream<Item = Record> + Send + 'static {
+        let store = self.clone();
+        // Under the ring lock, so every record after `cursor` is broadcast
+        // after `live` exists
+        let (mut live, mut cursor) = {
+            let ring = self.0.ring.lock().unwrap();
+            (self.0.live.subscribe(), ring.last_seq.unwrap_or(0))
+        };
+        async_stream::stream! {
+            loop {
+                match live.recv().await {
+                    Ok(r) => {
+                        if !is_after_cursor(r.sequence, cursor) {
+                            continue;
+                        }
+                        cursor = r.sequence.value();
+                        if matches_any(&subscriptions, &r) {
+                            yield r;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        debug!(skipped, "store subscriber lagged, catching up from ring");
+                        for r in store.read_since(cursor, &subscriptions) {
+                            cursor = r.sequence.value();
+                            yield r;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        }
+    }
+
+    /// Enumerate unique stations in the ring with min/max sequence numbers.
+    pub(crate) fn station_info(&self) -> Vec<StationInfo> {
+        let ring = self.0.ring.lock().unwrap();
+        // Key: (network, station) → (begin_seq, end_seq)
+        let mut map: BTreeMap<(String, String), (u64, u64)> = BTreeMap::new();
+        for r in ring.buf.values() {
+            let key = (r.network.clone(), r.station.clone());
+            let seq = r.sequence.value();
+            map.entry(key)
+                .and_modify(|(begin, end)| {
+                    if seq < *begin {
+                        *begin = seq;
+                    }
+                    if seq > *end {
+                        *end = seq;
+                    }
+                })
+                .or_insert((seq, seq));
+        }
+        map.into_iter()
+            .map(|((network, station), (begin_seq, end_seq))| StationInfo {
+                network,
+                station,
+                begin_seq,
+                end_seq,
+            })
+            .collect()
+    }
+
+    /// Enumerate unique streams in the ring with channel detail, sequence
+    /// and BTime ranges of their buffered records.
+    pub(crate) fn stream_info(&self) -> Vec<StreamInfo> {
+        type StreamKey = (String, String, String, String);
+
+        let now = SystemTime::now();
+        let ring = self.0.ring.lock().unwrap();
+        let mut map: BTreeMap<StreamKey, StreamInfo> = BTreeMap::new();
+        for r in ring.buf.values() {
+            if r.payload.len() < 20 {
+                continue;
+            }
+            let location = format!("{:2}", r.location);
+            let channel = format!("{:3}", r.channel);
+            let type_code = char::from(r.quality).to_string();
+            let seq = r.sequence.value();
+            let (start, end) = (record_start_time(&r.payload), record_end_time(&r.payload));
+            let key = (r.network.clone(), r.station.clone(), location, channel);
+            map.entry(key.clone())
+                .and_modify(|s| {
+                    // Keep latest type code
+                    s.type_code = type_code.clone();
+                    s.begin_seq = s.begin_seq.min(seq);
+                    s.end_seq = s.end_seq.max(seq);
+                    s.begin_time = earliest(s.begin_time, start);
+                    s.end_time = s.end_time.max(end);
+                })
+                .or_insert_with(|| {
+                    let stream_end = ring.stream_end.get(&r.payload[8..20]).copied();
+                    StreamInfo {
+                        network: key.0,
+                        station: key.1,
+                        channel: key.3,
+                        location: key.2,
+                        type_code,
+                        begin_seq: seq,
+                        end_seq: seq,
+                        begin_time: start,
+                        end_time: end,
+                        latency: stream_end.map(|t| now.duration_since(t).unwrap_or_default()),
+                    }
+                });
+        }
+        map.into_values().collect()
+    }
+
+    /// Latency of every stream seen since creation as of `now`, sorted by
+    /// network, station, location, and channel.
+    ///
+    /// Streams stay listed after their records are evicted, so a station that
+    /// stopped sending keeps a growing latency.
+    pub(crate) fn stream_latencies(&self, now: SystemTime) -> Vec<StreamMetrics> {
+        let ring = self.0.ring.lock().unwrap();
+        let text = |b: &[u8]| String::from_utf8_lossy(b).trim().to_owned();
+        let mut streams: Vec<StreamMetrics> = ring
+            .stream_end
+            .iter()
+            .map(|(nslc, &end_time)| StreamMetrics {
+                network: text(&nslc[10..12]),
+                station: text(&nslc[0..5]),
+                location: text(&nslc[5..7]),
+                channel: text(&nslc[7..10]),
+                end_time,
+                latency: now.duration_since(end_time).unwrap_or_default(),
+            })
+            .collect();
+        streams.sort_by(|a, b| {
+            (&a.network, &a.station, &a.location, &a.channel).cmp(&(
+                &b.network,
+                &b.station,
+                &b.location,
+                &b.channel,
+            ))
+        });
+        streams
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use seedlink_rs_protocol::testing::TestRecordBuilder;
+
+    fn dummy_payload() -> Vec<u8> {
+        vec![0u8; v3::PAYLOAD_LEN]
+    }
+
+    #[test]
+    fn push_assigns_increasing_sequences() {
+        let store = DataStore::new(100);
+        let s1 = store.push("IU", "ANMO", &dummy_payload());
+        let s2 = store.push("IU", "ANMO", &dummy_payload());
+        let s3 = store.push("GE", "WLF", &dummy_payload());
+        assert_eq!(s1.value(), 1);
+        assert_eq!(s2.value(), 2);
+        assert_eq!(s3.value(), 3);
+    }
+
+    #[test]
+    fn resume_cursor_is_cleared_once_passed() {
+        let record = |seq| {
+            Record::new(
+                SequenceNumber::new(seq),
+                "IU".into(),
+                "ANMO".into(),
+                Arc::from(dummy_payload()),
+            )
+        };
+        let mut subs = vec![Subscription {
+            resume_seq: Some(100),
+            ..Subscription::new("IU", "ANMO")
+        }];
+        assert!(!matches_any(&subs, &record(100)));
+        assert!(matches_any(&subs, &record(101)));
+
+        clear_passed_resume(&mut subs, 99);
+        assert_eq!(subs[0].resume_seq, Some(100));
+
+        // More than half the sequence space later, a record only looks older
+        let far = 101 + SequenceNumber::V3_WRAP_WINDOW;
+        assert!(!matches_any(&subs, &record(far)));
+        clear_passed_resume(&mut subs, 100);
+        assert_eq!(subs[0].resume_seq, None);
+        assert!(matches_any(&subs, &record(far)));
+    }
+
+    #[test]
     fn read_since_filters_by_subscription() {
         let store = DataStore::new(100);
         store.push("IU", "ANMO", &dummy_payload());
@@ -305,6 +1290,7 @@ mod tests {
             station: "ANMO".into(),
             select_patterns: vec![],
             time_window: None,
+            resume_seq: None,
         }];
 
         let records = store.read_since(0, &subs);
@@ -325,6 +1311,7 @@ mod tests {
             station: "ANMO".into(),
             select_patterns: vec![],
             time_window: None,
+            resume_seq: None,
         }];
 
         let records = store.read_since(2, &subs);
@@ -344,6 +1331,7 @@ mod tests {
             station: "ANMO".into(),
             select_patterns: vec![],
             time_window: None,
+            resume_seq: None,
         }];
 
         let records = store.read_since(0, &subs);
@@ -353,6 +1341,122 @@ mod tests {
         assert_eq!(records[2].sequence.value(), 5);
     }
 
+    #[test]
+    fn resume_missed_after_eviction() {
+        let store = DataStore::new(3);
+        store.push("GE", "WLF", &dummy_payload());
+        for _ in 0..4 {
+            store.push("IU", "ANMO", &dummy_payload());
+        }
+        // Evicted: GE_WLF 1, IU_ANMO 2
+        let anmo = Subscription::new("IU", "ANMO");
+        assert!(store.resume_missed(&anmo, 1));
+        assert!(!store.resume_missed(&anmo, 2));
+        assert!(!store.resume_missed(&anmo, 4));
+
+        let wlf = Subscription::new("GE", "WLF");
+        assert!(!store.resume_missed(&wlf, 1));
+        assert!(store.resume_missed(&Subscription::new("*", "*"), 1));
+    }
+
+    #[test]
+    fn resume_missed_beyond_wrap_window() {
+        let max = SequenceNumber::V3_MAX;
+        assert_eq!(sequences_behind(5, 5), 0);
+        assert_eq!(sequences_behind(5, 1), 4);
+        assert_eq!(sequences_behind(5, max), 5);
+        assert_eq!(sequences_behind(max, 1), max - 1);
+
+        // The newest sequence is 0x900005; IU_ANMO was evicted through 0x900002
+        let store = DataStore::new(3);
+        let first = SequenceNumber::new(0x90_0000);
+        store
+            .push_with_sequence("IU", "ANMO", &dummy_payload(), first)
+            .unwrap();
+        for _ in 0..5 {
+            store.push("IU", "ANMO", &dummy_payload());
+        }
+        let anmo = Subscription::new("IU", "ANMO");
+        // More than V3_WRAP_WINDOW behind the eviction: still a miss
+        assert!(store.resume_missed(&anmo, 0x10_0000));
+        assert!(store.resume_missed(&anmo, 0x90_0001));
+        assert!(!store.resume_missed(&anmo, 0x90_0002));
+        assert!(!store.resume_missed(&anmo, 0x90_0005));
+    }
+
+    #[test]
+    fn station_capacity_evicts_within_station() {
+        let config = StoreConfig {
+            station_capacity: Some(2),
+            station_capacities: HashMap::from([(("GE".to_owned(), "WLF".to_owned()), 3)]),
+            ..StoreConfig::default()
+        };
+        let store = DataStore::with_config(6, config);
+        store.push("II", "BFO", &dummy_payload());
+        for _ in 0..4 {
+            store.push("IU", "ANMO", &dummy_payload());
+            store.push("GE", "WLF", &dummy_payload());
+        }
+        // ANMO 2, 4 and WLF 3 went to the per-station limits; BFO 1 stays
+        let seqs = |sta: &str| {
+            let sub = [Subscription::new("*", sta)];
+            let records = store.read_since(0, &sub);
+            records
+                .iter()
+                .map(|r| r.sequence.value())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(seqs("BFO"), [1]);
+        assert_eq!(seqs("ANMO"), [6, 8]);
+        assert_eq!(seqs("WLF"), [5, 7, 9]);
+        assert_eq!(store.len(), 6);
+        assert_eq!(store.bytes(), 6 * v3::PAYLOAD_LEN);
+        assert_eq!(store.push_counts(), (9, 3));
+        assert!(store.resume_missed(&Subscription::new("IU", "ANMO"), 2));
+        assert!(!store.resume_missed(&Subscription::new("II", "BFO"), 0));
+
+        // Ring eviction keeps the station index in step
+        for _ in 0..3 {
+            store.push("II", "BFO", &dummy_payload());
+        }
+        assert_eq!(seqs("BFO"), [11, 12]);
+        assert_eq!(seqs("WLF"), [7, 9]);
+        store.push("GE", "WLF", &dummy_payload());
+        store.push("GE", "WLF", &dummy_payload());
+        assert_eq!(seqs("ANMO"), [8]);
+        store.push("IU", "ANMO", &dummy_payload());
+        store.push("IU", "ANMO", &dummy_payload());
+        assert_eq!(seqs("ANMO"), [15, 16]);
+        assert_eq!(seqs("WLF"), [13, 14]);
+    }
+
+    #[test]
+    fn eviction_on_byte_budget() {
+        // 3 records by bytes, 4 by count: the byte budget evicts first
+        let store = DataStore::with_limits(4, 3 * v3::PAYLOAD_LEN, StoreConfig::default());
+        for _ in 0..5 {
+            store.push("IU", "ANMO", &dummy_payload());
+        }
+        assert_eq!(store.len(), 3);
+        assert_eq!(store.bytes(), 3 * v3::PAYLOAD_LEN);
+        assert_eq!(store.push_counts(), (5, 2));
+
+        // Count limit still applies alongside a generous byte budget
+        let store = DataStore::with_limits(2, 1 << 20, StoreConfig::default());
+        for _ in 0..5 {
+            store.push("IU", "ANMO", &dummy_payload());
+        }
+        assert_eq!(store.len(), 2);
+        assert_eq!(store.bytes(), 2 * v3::PAYLOAD_LEN);
+
+        // A budget below one record keeps only the newest
+        let store = DataStore::with_limits(10, 100, StoreConfig::default());
+        store.push("IU", "ANMO", &dummy_payload());
+        let seq = store.push("IU", "ANMO", &dummy_payload());
+        assert_eq!(store.len(), 1);
+        assert!(store.any(|r| r.sequence == seq));
+    }
+
     #[test]
     fn sequence_wraps_at_v3_max() {
         let store = DataStore::new(10);
@@ -369,10 +1473,632 @@ mod tests {
         assert_eq!(s2.value(), 1); // wrapped
     }
 
+    #[test]
+    fn push_with_sequence_preserves_and_continues() {
+        let store = DataStore::new(10);
+        let seq = store
+            .push_with_sequence("IU", "ANMO", &dummy_payload(), SequenceNumber::new(100))
+            .unwrap();
+        assert_eq!(seq.value(), 100);
+        // Gaps are fine, and push() continues after the last sequence
+        store
+            .push_with_sequence("IU", "ANMO", &dummy_payload(), SequenceNumber::new(105))
+            .unwrap();
+        assert_eq!(store.push("IU", "ANMO", &dummy_payload()).value(), 106);
+
+        let err = store
+            .push_with_sequence("IU", "ANMO", &dummy_payload(), SequenceNumber::new(106))
+            .unwrap_err();
+        assert!(matches!(err, ServerError::SequenceOutOfOrder { .. }));
+        assert!(matches!(
+            store.push_with_sequence("IU", "ANMO", &dummy_payload(), SequenceNumber::new(0)),
+            Err(ServerError::InvalidSequence(_))
+        ));
+        assert!(matches!(
+            store.push_with_sequence("IU", "ANMO", &dummy_payload(), SequenceNumber::new(1 << 24)),
+            Err(ServerError::InvalidSequence(_))
+        ));
+        assert_eq!(store.len(), 3);
+
+        // Wrapping past V3_MAX is in order; a jump of more than half the
+        // sequence space is not
+        let store = DataStore::new(10);
+        let near_max = SequenceNumber::new(SequenceNumber::V3_MAX - 1);
+        store
+            .push_with_sequence("IU", "ANMO", &dummy_payload(), near_max)
+            .unwrap();
+        store
+            .push_with_sequence("IU", "ANMO", &dummy_payload(), SequenceNumber::new(2))
+            .unwrap();
+        assert!(
+            store
+                .push_with_sequence("IU", "ANMO", &dummy_payload(), near_max)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn push_with_sequence_reassign_policy() {
+        let config = StoreConfig {
+            sequence_policy: SequencePolicy::Reassign,
+            ..StoreConfig::default()
+        };
+        let store = DataStore::with_config(10, config);
+        store
+            .push_with_sequence("IU", "ANMO", &dummy_payload(), SequenceNumber::new(50))
+            .unwrap();
+        let seq = store
+            .push_with_sequence("IU", "ANMO", &dummy_payload(), SequenceNumber::new(40))
+            .unwrap();
+        assert_eq!(seq.value(), 51);
+    }
+
+    #[test]
+    fn read_since_across_wrap() {
+        let store = DataStore::new(10);
+        {
+            let mut ring = store.0.ring.lock().unwrap();
+            ring.next_seq = SequenceNumber::V3_MAX - 1;
+        }
+        for _ in 0..4 {
+            store.push("IU", "ANMO", &dummy_payload());
+        }
+        let subs = vec![Subscription {
+            network: "IU".into(),
+            station: "ANMO".into(),
+            select_patterns: vec![],
+            time_window: None,
+            resume_seq: None,
+        }];
+
+        let seqs = |cursor| -> Vec<u64> {
+            store
+                .read_since(cursor, &subs)
+                .iter()
+                .map(|r| r.sequence.value())
+                .collect()
+        };
+        assert_eq!(
+            seqs(0),
+            [SequenceNumber::V3_MAX - 1, SequenceNumber::V3_MAX, 1, 2]
+        );
+        assert_eq!(
+            seqs(SequenceNumber::V3_MAX - 1),
+            [SequenceNumber::V3_MAX, 1, 2]
+        );
+        assert_eq!(seqs(SequenceNumber::V3_MAX), [1, 2]);
+        assert_eq!(seqs(1), [2]);
+        assert!(seqs(2).is_empty());
+    }
+
+    /// IU.ANMO.00.BHZ record starting at 2024-001 00:00:`second`.`ticks`.
+    fn timed_payload(second: u8, ticks: u16) -> Vec<u8> {
+        TestRecordBuilder::new()
+            .btime(2024, 1, 0, 0, second, ticks)
+            .build()
+    }
+
+    #[test]
+    fn snapshot_restores_into_another_store() {
+        let source = DataStore::new(10);
+        source.push("IU", "ANMO", &dummy_payload());
+        source
+            .push_with_sequence("GE", "WLF", &dummy_payload(), SequenceNumber::new(41))
+            .unwrap();
+        let snapshot = source.snapshot();
+        assert_eq!(snapshot.next_sequence, 42);
+        assert_eq!(snapshot.records.len(), 2);
+
+        let target = DataStore::new(1);
+        target.push("XX", "TEST", &dummy_payload());
+        target.restore(snapshot.clone()).unwrap();
+        // Over capacity: only the newest record stays
+        assert_eq!(target.len(), 1);
+        assert_eq!(target.last_sequence(), Some(SequenceNumber::new(41)));
+        assert_eq!(target.push("IU", "ANMO", &dummy_payload()).value(), 42);
+        assert_eq!(target.push_counts().0, 2);
+
+        let mut bad = snapshot;
+        bad.records[0].sequence = 0;
+        assert!(matches!(
+            source.restore(bad),
+            Err(ServerError::InvalidSequence(_))
+        ));
+        assert_eq!(source.len(), 2);
+    }
+
+    #[test]
+    fn retention_evicts_old_records() {
+        let config = StoreConfig {
+            retention: Some(Duration::from_secs(10)),
+            ..StoreConfig::default()
+        };
+        let start = |second: u8| record_start_time(&timed_payload(second, 0)).unwrap();
+
+        // 2024 records are far outside the window by the wall clock: the
+        // record evicts itself on push
+        let store = DataStore::with_config(10, config.clone());
+        store.push("IU", "ANMO", &timed_payload(1, 0));
+        assert!(store.is_empty());
+
+        // Sweep with a clock 15 s after the first record
+        let store = DataStore::with_config(10, config);
+        {
+            // Fill without evicting on push
+            let mut ring = store.0.ring.lock().unwrap();
+            ring.retention = None;
+            for second in [1, 8, 2, 9] {
+                ring.push(
+                    "IU".into(),
+                    "ANMO".into(),
+                    Arc::from(timed_payload(second, 0)),
+                );
+            }
+            ring.push("IU".into(), "ANMO".into(), Arc::from(dummy_payload()));
+            ring.retention = Some(Duration::from_secs(10));
+        }
+        assert_eq!(store.evict_expired_at(start(15)), 2);
+        let left: Vec<u64> = store
+            .read_since(
+                0,
+                &[Subscription {
+                    network: "IU".into(),
+                    station: "ANMO".into(),
+                    select_patterns: vec![],
+                    time_window: None,
+                    resume_seq: None,
+                }],
+            )
+            .iter()
+            .map(|r| r.sequence.value())
+            .collect();
+        // Records without a start time are kept
+        assert_eq!(left, [2, 4, 5]);
+        assert_eq!(store.bytes(), 3 * v3::PAYLOAD_LEN);
+        assert_eq!(store.push_counts(), (5, 2));
+        assert_eq!(store.evict_expired_at(start(15)), 0);
+    }
+
+    #[test]
+    fn validation_drops_or_flags_invalid_records() {
+        let mut valid = timed_payload(1, 0);
+        valid[..8].copy_from_slice(b"000001D ");
+        let mut corrupt = valid.clone();
+        corrupt[24] = 99; // hour
+
+        let store = DataStore::with_config(
+            10,
+            StoreConfig {
+                validation: ValidationMode::Drop,
+                ..StoreConfig::default()
+            },
+        );
+        assert_eq!(store.push("IU", "ANMO", &valid).value(), 1);
+        assert!(store.push("IU", "ANMO", &corrupt) == SequenceNumber::UNSET);
+        assert!(matches!(
+            store.push_with_sequence("IU", "ANMO", &corrupt, SequenceNumber::new(5)),
+            Err(ServerError::InvalidRecord(_))
+        ));
+        assert_eq!(store.len(), 1);
+        assert_eq!(store.invalid_count(), 2);
+
+        let store = DataStore::with_config(
+            10,
+            StoreConfig {
+                validation: ValidationMode::Flag,
+                ..StoreConfig::default()
+            },
+        );
+        assert_eq!(store.push("IU", "ANMO", &corrupt).value(), 1);
+        assert_eq!(store.len(), 1);
+        assert_eq!(store.invalid_count(), 1);
+
+        // Off by default: nothing is checked
+        let store = DataStore::new(10);
+        store.push("IU", "ANMO", &corrupt);
+        assert_eq!(store.invalid_count(), 0);
+    }
+
+    fn start_seconds(store: &DataStore) -> Vec<u8> {
+        store
+            .read_since(0, &[subscription_with(&[])])
+            .iter()
+            .map(|r| r.payload[26])
+            .collect()
+    }
+
+    #[test]
+    fn dedup_window_drops_repeated_records() {
+        let config = StoreConfig {
+            dedup_window: 2,
+            ..StoreConfig::default()
+        };
+        let store = DataStore::with_config(10, config);
+        assert_eq!(store.push("IU", "ANMO", &timed_payload(1, 0)).value(), 1);
+        assert!(store.push("IU", "ANMO", &timed_payload(1, 0)) == SequenceNumber::UNSET);
+        // Same second, different ticks: not a duplicate
+        assert_eq!(store.push("IU", "ANMO", &timed_payload(1, 5)).value(), 2);
+        // Same start time on another channel: not a duplicate
+        let mut other = timed_payload(1, 0);
+        other[15..18].copy_from_slice(b"BHN");
+        assert_eq!(store.push("IU", "ANMO", &other).value(), 3);
+        // Window of 2: the first record has been forgotten
+        assert_eq!(store.push("IU", "ANMO", &timed_payload(1, 0)).value(), 4);
+        assert_eq!(store.duplicate_count(), 1);
+        // No readable start time: never deduplicated
+        store.push("IU", "ANMO", &dummy_payload());
+        store.push("IU", "ANMO", &dummy_payload());
+        assert_eq!(store.len(), 6);
+        assert!(matches!(
+            store.push_with_sequence("IU", "ANMO", &other, SequenceNumber::new(9)),
+            Err(ServerError::DuplicateRecord)
+        ));
+    }
+
+    #[test]
+    fn stream_latency_tracks_latest_end_time() {
+        let store = DataStore::new(1);
+        let mut payload = timed_payload(0, 0);
+        payload[30..32].copy_from_slice(&40u16.to_be_bytes());
+        payload[32..34].copy_from_slice(&20i16.to_be_bytes());
+        payload[34..36].copy_from_slice(&1i16.to_be_bytes());
+        store.push("IU", "ANMO", &payload);
+        // An earlier record does not move the end time back
+        store.push("IU", "ANMO", &timed_payload(0, 0));
+
+        // 2024-001 00:00:02 (start + 40 samples at 20 Hz)
+        let end = SystemTime::UNIX_EPOCH + Duration::from_secs(1_704_067_202);
+        let now = end + Duration::from_secs(10);
+        let streams = store.stream_latencies(now);
+        assert_eq!(streams.len(), 1);
+        assert_eq!(
+            (streams[0].network.as_str(), streams[0].station.as_str()),
+            ("IU", "ANMO")
+        );
+        assert_eq!(streams[0].channel, "BHZ");
+        assert_eq!(streams[0].end_time, end);
+        assert_eq!(streams[0].latency, Duration::from_secs(10));
+        assert_eq!(
+            store.stream_latencies(end - Duration::from_secs(1))[0].latency,
+            Duration::ZERO
+        );
+
+        let info = store.stream_info();
+        assert!(info[0].latency.unwrap() > Duration::from_secs(10));
+        // The time range only covers records still in the ring
+        assert_eq!(info[0].end_time, Some(end - Duration::from_secs(2)));
+
+        // Records without a start time leave no latency entry
+        let store = DataStore::new(1);
+        store.push("IU", "ANMO", &dummy_payload());
+        assert!(store.stream_latencies(now).is_empty());
+        assert_eq!(store.stream_info()[0].latency, None);
+        assert_eq!(store.stream_info()[0].begin_time, None);
+    }
+
+    #[test]
+    fn stream_info_reports_time_range() {
+        let store = DataStore::new(3);
+        let at = |second: u8| {
+            TestRecordBuilder::new()
+                .btime(2024, 1, 0, 0, second, 0)
+                .samples(&[0; 20])
+                .build()
+        };
+        // Out of order: the range covers the earliest start and latest end
+        for second in [10, 0, 20, 5] {
+            store.push("IU", "ANMO", &at(second));
+        }
+        let info = store.stream_info();
+        assert_eq!(info.len(), 1);
+        let t0 = SystemTime::UNIX_EPOCH + Duration::from_secs(1_704_067_200);
+        // The record starting at :10 was evicted
+        assert_eq!(info[0].begin_time, Some(t0));
+        assert_eq!(info[0].end_time, Some(t0 + Duration::from_secs(21)));
+        assert_eq!((info[0].begin_seq, info[0].end_seq), (2, 4));
+    }
+
+    #[test]
+    fn reorder_depth_sorts_by_start_time() {
+        let config = StoreConfig {
+            reorder_depth: 2,
+            ..StoreConfig::default()
+        };
+        let store = DataStore::with_config(10, config);
+        let mut live = store.subscribe_live();
+        assert_eq!(
+            store.push("IU", "ANMO", &timed_payload(3, 0)),
+            SequenceNumber::UNSET
+        );
+        assert_eq!(
+            store.push("IU", "ANMO", &timed_payload(1, 0)),
+            SequenceNumber::UNSET
+        );
+        // Third record releases the earliest, which is the one just pushed here
+        assert_eq!(store.push("IU", "ANMO", &timed_payload(0, 0)).value(), 1);
+        store.push("IU", "ANMO", &timed_payload(4, 0));
+        assert_eq!(start_seconds(&store), [0, 1]);
+        assert_eq!(live.try_recv().unwrap().payload[26], 0);
+
+        assert_eq!(store.flush_held(), 2);
+        assert_eq!(start_seconds(&store), [0, 1, 3, 4]);
+        assert_eq!(store.flush_held(), 0);
+    }
+
+    fn channel_payload(channel: &[u8; 3]) -> Vec<u8> {
+        let mut payload = dummy_payload();
+        payload[15..18].copy_from_slice(channel);
+        payload
+    }
+
+    fn channel_record(channel: &[u8; 3]) -> Record {
+        let payload = channel_payload(channel);
+        Record::new(
+            SequenceNumber::new(1),
+            "IU".into(),
+            "ANMO".into(),
+            payload.into(),
+        )
+    }
+
+    #[tokio::test]
+    async fn subscribe_streams_matching_records() {
+        use tokio_stream::StreamExt;
+
+        let store = DataStore::new(4096);
+        store.push("IU", "ANMO", &channel_payload(b"BHZ")); // before subscribing
+
+        let sub = Subscription::new("iu", "anmo").select("BHZ").unwrap();
+        let mut stream = Box::pin(store.subscribe(vec![sub]));
+        store.push("IU", "ANMO", &channel_payload(b"BHN"));
+        let want = store.push("IU", "ANMO", &channel_payload(b"BHZ"));
+        store.push("GE", "WLF", &channel_payload(b"BHZ"));
+        assert_eq!(stream.next().await.unwrap().sequence, want);
+
+        // Falling behind the live feed catches up from the ring
+        let pushed: Vec<_> = (0..LIVE_CAPACITY + 10)
+            .map(|_| store.push("IU", "ANMO", &channel_payload(b"BHZ")))
+            .collect();
+        for seq in pushed {
+            assert_eq!(stream.next().await.unwrap().sequence, seq);
+        }
+
+        assert!(matches!(
+            Subscription::new("IU", "ANMO").select(""),
+            Err(ServerError::InvalidSelect(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn wildcard_subscription_sees_late_stations() {
+        use tokio_stream::StreamExt;
+
+        let store = DataStore::new(4096);
+        let subs = vec![Subscription::new("IU", "*"), Subscription::new("G?", "W*")];
+        let mut stream = Box::pin(store.subscribe(subs.clone()));
+
+        // Stations that did not exist when the subscription was made
+        store.push("XX", "STA1", &dummy_payload());
+        let cola = store.push("IU", "COLA", &dummy_payload());
+        let wlf = store.push("GE", "WLF", &dummy_payload());
+        store.push("GE", "APE", &dummy_payload());
+        assert_eq!(stream.next().await.unwrap().sequence, cola);
+        assert_eq!(stream.next().await.unwrap().sequence, wlf);
+
+        // Catching up from the ring after lagging applies the same patterns
+        let pushed: Vec<_> = (0..LIVE_CAPACITY + 10)
+            .filter_map(|i| match i % 3 {
+                0 => Some(store.push("IU", &format!("N{i}"), &dummy_payload())),
+                1 => Some(store.push("GR", &format!("W{i}"), &dummy_payload())),
+                _ => {
+                    store.push("XX", &format!("S{i}"), &dummy_payload());
+                    None
+                }
+            })
+            .collect();
+        for seq in pushed {
+            assert_eq!(stream.next().await.unwrap().sequence, seq);
+        }
+        let caught_up = store.read_since(0, &subs);
+        assert!(caught_up.iter().all(|r| r.network != "XX"));
+        assert!(caught_up.iter().any(|r| r.station == "COLA"));
+    }
+
+    fn subscription_with(patterns: &[&str]) -> Subscription {
+        Subscription {
+            network: "IU".into(),
+            station: "ANMO".into(),
+            select_patterns: patterns
+                .iter()
+                .map(|p| Selector::V3(SelectPattern::parse(p).unwrap()))
+                .collect(),
+            time_window: None,
+            resume_seq: None,
+        }
+    }
+
+    #[test]
+    fn matches_channel_negation_only() {
+        let sub = subscription_with(&["!LOG"]);
+        assert!(sub.matches_channel(&channel_record(b"BHZ")));
+        assert!(!sub.matches_channel(&channel_record(b"LOG")));
+    }
+
+    #[test]
+    fn matches_channel_positive_and_negative() {
+        // All BH? channels except BHE
+        let sub = subscription_with(&["BH?", "!BHE"]);
+        assert!(sub.matches_channel(&channel_record(b"BHZ")));
+        assert!(sub.matches_channel(&channel_record(b"BHN")));
+        assert!(!sub.matches_channel(&channel_record(b"BHE")));
+        assert!(!sub.matches_channel(&channel_record(b"LHZ")));
+    }
+
+    #[test]
+    fn matches_channel_negation_overrides_any_positive() {
+        let sub = subscription_with(&["BHZ", "HHZ", "!HHZ"]);
+        assert!(sub.matches_channel(&channel_record(b"BHZ")));
+        assert!(!sub.matches_channel(&channel_record(b"HHZ")));
+    }
+
+    #[test]
+    fn matches_channel_uses_record_fields_not_payload() {
+        // Fields parsed at push time are authoritative; the payload is not re-read
+        let mut record = channel_record(b"BHZ");
+        record.location = "10".into();
+        record.channel = "HHZ".into();
+        record.quality = b'R';
+        assert!(subscription_with(&["10HHZ.R"]).matches_channel(&record));
+        assert!(!subscription_with(&["BHZ"]).matches_channel(&record));
+    }
+
     #[test]
     #[should_panic(expected = "payload must be exactly 512 bytes")]
     fn push_rejects_wrong_payload_size() {
         let store = DataStore::new(10);
         store.push("IU", "ANMO", &[0u8; 100]);
     }
+
+    #[test]
+    fn subscribe_receives_live_pushes() {
+        let store = DataStore::new(10);
+        store.push("IU", "ANMO", &dummy_payload()); // before subscribe: not delivered
+        let mut live = store.subscribe_live();
+        store.push("GE", "WLF", &dummy_payload());
+
+        let r = live.try_recv().unwrap();
+        assert_eq!(r.sequence.value(), 2);
+        assert_eq!(r.station, "WLF");
+        assert!(live.try_recv().is_err());
+    }
+
+    #[test]
+    fn matches_any_checks_network_and_station() {
+        let store = DataStore::new(10);
+        let mut live = store.subscribe_live();
+        store.push("IU", "ANMO", &dummy_payload());
+        let record = live.try_recv().unwrap();
+
+        let sub = |net: &str, sta: &str| Subscription {
+            network: net.into(),
+            station: sta.into(),
+            select_patterns: vec![],
+            time_window: None,
+            resume_seq: None,
+        };
+        assert!(matches_any(&[sub("GE", "WLF"), sub("iu", "anmo")], &record));
+        assert!(!matches_any(&[sub("IU", "COLA")], &record));
+        assert!(!matches_any(&[], &record));
+    }
+
+    #[test]
+    fn read_since_shares_payload() {
+        let store = DataStore::new(10);
+        let mut live = store.subscribe_live();
+        store.push("IU", "ANMO", &dummy_payload());
+
+        let subs = vec![Subscription {
+            network: "IU".into(),
+            station: "ANMO".into(),
+            select_patterns: vec![],
+            time_window: None,
+            resume_seq: None,
+        }];
+        let a = store.read_since(0, &subs);
+        let b = store.read_since(0, &subs);
+        let c = live.try_recv().unwrap();
+        assert!(Arc::ptr_eq(&a[0].payload, &b[0].payload));
+        assert!(Arc::ptr_eq(&a[0].payload, &c.payload));
+    }
+
+    #[test]
+    fn occupancy_and_eviction_counts() {
+        let store = DataStore::new(3);
+        assert!(store.is_empty());
+        assert_eq!(store.capacity(), 3);
+        for _ in 0..5 {
+            store.push("IU", "ANMO", &dummy_payload());
+        }
+        assert_eq!(store.len(), 3);
+        assert_eq!(store.push_counts(), (5, 2));
+    }
+
+    #[test]
+    fn record_ids_from_fixed_header() {
+        let mut payload = vec![b' '; 512];
+        payload[0..6].copy_from_slice(b"000001");
+        payload[6] = b'D';
+        payload[8..12].copy_from_slice(b"ANMO");
+        payload[18..20].copy_from_slice(b"IU");
+        assert_eq!(
+            record_ids(&payload).unwrap(),
+            ("IU".to_owned(), "ANMO".to_owned())
+        );
+        assert!(matches!(
+            record_ids(&[0u8; 512]),
+            Err(ServerError::InvalidHeader(_))
+        ));
+    }
+
+    #[test]
+    fn push_record_reads_ids_from_header() {
+        let store = DataStore::new(16);
+        let payload = TestRecordBuilder::new()
+            .nslc("GE", "WLF", "", "HHZ")
+            .quality(b'R')
+            .build();
+        let seq = store.push_record(&payload).unwrap();
+
+        let records = store.read_since(0, &[Subscription::new("GE", "WLF")]);
+        assert_eq!(records.len(), 1);
+        let r = &records[0];
+        assert_eq!(r.sequence, seq);
+        assert_eq!(
+            (r.location.as_str(), r.channel.as_str(), r.quality),
+            ("", "HHZ", b'R')
+        );
+
+        // SELECT filters on the stored fields
+        let sub = Subscription::new("GE", "WLF").select("HHZ.R").unwrap();
+        assert_eq!(store.read_since(0, &[sub]).len(), 1);
+        let sub = Subscription::new("GE", "WLF").select("00HHZ").unwrap();
+        assert!(store.read_since(0, &[sub]).is_empty());
+    }
+
+    #[test]
+    fn push_record_rejects_bad_input() {
+        let store = DataStore::with_config(
+            16,
+            StoreConfig {
+                validation: ValidationMode::Error,
+                ..StoreConfig::default()
+            },
+        );
+        assert!(matches!(
+            store.push_record(&[0u8; 100]),
+            Err(ServerError::InvalidPayloadLength(100))
+        ));
+
+        let mut bad_quality = TestRecordBuilder::new().build();
+        bad_quality[6] = b'X';
+        let err = store.push_record(&bad_quality).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid miniSEED header: invalid data quality indicator 'X'"
+        );
+
+        let no_station = TestRecordBuilder::new().station("").build();
+        assert!(matches!(
+            store.push_record(&no_station),
+            Err(ServerError::InvalidHeader(_))
+        ));
+
+        let mut bad_btime = TestRecordBuilder::new().build();
+        bad_btime[24] = 25; // hour
+        assert!(matches!(
+            store.push_record(&bad_btime),
+            Err(ServerError::InvalidRecord(_))
+        ));
+        assert!(store.is_empty());
+    }
 }
@@ -0,0 +1,71 @@
+//! Per-client bandwidth limit for streaming.
+//!
+//! A token bucket refilled at [`ServerConfig::max_bytes_per_second`](crate::ServerConfig::max_bytes_per_second)
+//! and holding up to one second of it. A write larger than the bucket
+//! borrows against future refills, so it waits instead of being refused.
+
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+#[derive(Debug)]
+pub(crate) struct TokenBucket {
+    rate: f64,
+    /// Available bytes; negative while a write is being paid off.
+    tokens: f64,
+    last: Instant,
+}
+
+impl TokenBucket {
+    /// A full bucket for `bytes_per_second` (minimum 1).
+    pub fn new(bytes_per_second: u64) -> Self {
+        let rate = bytes_per_second.max(1) as f64;
+        Self {
+            rate,
+            tokens: rate,
+            last: Instant::now(),
+        }
+    }
+
+    /// Largest batch worth writing at once: one second of rate.
+    pub fn burst(&self) -> usize {
+        self.rate as usize
+    }
+
+    /// Charge `bytes` and return how long to wait before writing them.
+    pub fn reserve(&mut self, bytes: usize) -> Duration {
+        self.reserve_at(bytes, Instant::now())
+    }
+
+    fn reserve_at(&mut self, bytes: usize, now: Instant) -> Duration {
+        let elapsed = now.saturating_duration_since(self.last).as_secs_f64();
+        self.last = now;
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate) - bytes as f64;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn waits_once_the_burst_is_spent() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(1000);
+        bucket.last = start;
+        assert_eq!(bucket.burst(), 1000);
+        assert_eq!(bucket.reserve_at(600, start), Duration::ZERO);
+        assert_eq!(bucket.reserve_at(400, start), Duration::ZERO);
+        assert_eq!(bucket.reserve_at(500, start), Duration::from_millis(500));
+
+        // After paying off the debt the bucket refills, capped at one second
+        let later = start + Duration::from_secs(10);
+        assert_eq!(bucket.reserve_at(1000, later), Duration::ZERO);
+        assert_eq!(bucket.reserve_at(2000, later), Duration::from_secs(2));
+    }
+}
@@ -1,8 +1,12 @@
-//! Timestamp parsing and time window filtering for SeedLink TIME command.
+//! Timestamps and time window filtering for SeedLink TIME/DATA commands.
 //!
-//! Handles two timestamp formats:
-//! - TIME command: `"YYYY,M,D,h,m,s"` (month/day based)
-//! - miniSEED v2 BTime: binary day-of-year based (payload bytes 20..30)
+//! Command times arrive already parsed as [`SlTime`] (v3 comma format or
+//! v4 ISO-8601); record times come from the miniSEED v2 BTime (binary
+//! day-of-year based, payload bytes 20..30).
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use seedlink_rs_protocol::SlTime;
 
 /// Comparable timestamp represented as seconds since Unix epoch.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -11,32 +15,12 @@ pub(crate) struct Timestamp {
 }
 
 impl Timestamp {
-    /// Parse TIME command format: `"2024,1,15,10,30,45"`.
-    ///
-    /// Fields: year, month, day, hour, minute, second.
-    pub fn from_time_command(s: &str) -> Option<Self> {
-        let parts: Vec<&str> = s.split(',').collect();
-        if parts.len() != 6 {
-            return None;
+    /// Whole seconds of a TIME/DATA command time; sub-second digits are
+    /// dropped.
+    pub fn from_sl_time(time: SlTime) -> Self {
+        Self {
+            seconds: time.unix_seconds(),
         }
-        let year: i64 = parts[0].parse().ok()?;
-        let month: u32 = parts[1].parse().ok()?;
-        let day: u32 = parts[2].parse().ok()?;
-        let hour: u32 = parts[3].parse().ok()?;
-        let minute: u32 = parts[4].parse().ok()?;
-        let second: u32 = parts[5].parse().ok()?;
-
-        if !(1..=12).contains(&month)
-            || !(1..=31).contains(&day)
-            || hour > 23
-            || minute > 59
-            || second > 59
-        {
-            return None;
-        }
-
-        let doy = month_day_to_doy(year, month, day)?;
-        Some(Self::from_components(year, doy, hour, minute, second))
     }
 
     /// Parse miniSEED v2 BTime from payload bytes 20..30.
@@ -87,41 +71,44 @@ impl Timestamp {
     }
 }
 
-fn is_leap(y: i64) -> bool {
-    (y % 4 == 0 && y % 100 != 0) || y % 400 == 0
+/// Start time of a miniSEED v2 record: BTime including ticks.
+///
+/// Returns `None` if the start time is invalid or before 1970.
+pub(crate) fn record_start_time(payload: &[u8]) -> Option<SystemTime> {
+    let start = Timestamp::from_mseed_payload(payload)?;
+    let ticks = u16::from_be_bytes([payload[28], payload[29]]);
+    let seconds = u64::try_from(start.seconds).ok()?;
+    Some(UNIX_EPOCH + Duration::from_secs(seconds) + Duration::from_micros(u64::from(ticks) * 100))
 }
 
-/// Convert month (1-12) and day (1-31) to day-of-year (1-366).
-fn month_day_to_doy(year: i64, month: u32, day: u32) -> Option<u32> {
-    let leap = is_leap(year);
-    let month_days: [u32; 12] = [
-        31,
-        if leap { 29 } else { 28 },
-        31,
-        30,
-        31,
-        30,
-        31,
-        31,
-        30,
-        31,
-        30,
-        31,
-    ];
-
-    if !(1..=12).contains(&month) {
+/// End time of a miniSEED v2 record: BTime start (with ticks) plus
+/// `nsamples / sample_rate`.
+///
+/// The nominal sample rate comes from the fixed header factor and multiplier
+/// (bytes 32..36). Returns `None` if the start time is invalid or before 1970.
+pub(crate) fn record_end_time(payload: &[u8]) -> Option<SystemTime> {
+    if payload.len() < 36 {
         return None;
     }
-    let max_day = month_days[(month - 1) as usize];
-    if day < 1 || day > max_day {
-        return None;
+    let mut end = record_start_time(payload)?;
+    let nsamples = u16::from_be_bytes([payload[30], payload[31]]);
+    let factor = f64::from(i16::from_be_bytes([payload[32], payload[33]]));
+    let multiplier = f64::from(i16::from_be_bytes([payload[34], payload[35]]));
+
+    if factor != 0.0 && multiplier != 0.0 {
+        let rate = if factor > 0.0 { factor } else { -1.0 / factor };
+        let rate = if multiplier > 0.0 {
+            rate * multiplier
+        } else {
+            rate / -multiplier
+        };
+        end += Duration::from_secs_f64(f64::from(nsamples) / rate);
     }
+    Some(end)
+}
 
-    let mut doy = day;
-    for &md in month_days.iter().take((month - 1) as usize) {
-        doy += md;
-    }
-    Some(doy)
+fn is_leap(y: i64) -> bool {
+    (y % 4 == 0 && y % 100 != 0) || y % 400 == 0
 }
 
 /// Time window filter attached to a subscription.
@@ -132,17 +119,12 @@ pub(crate) struct TimeWindow {
 }
 
 impl TimeWindow {
-    /// Parse TIME command arguments into a TimeWindow.
-    pub fn parse(start: &str, end: Option<&str>) -> Option<Self> {
-        let start_ts = Timestamp::from_time_command(start)?;
-        let end_ts = match end {
-            Some(e) => Some(Timestamp::from_time_command(e)?),
-            None => None,
-        };
-        Some(Self {
-            start: start_ts,
-            end: end_ts,
-        })
+    /// Window from TIME or DATA command times.
+    pub fn new(start: SlTime, end: Option<SlTime>) -> Self {
+        Self {
+            start: Timestamp::from_sl_time(start),
+            end: end.map(Timestamp::from_sl_time),
+        }
     }
 
     /// Check if a timestamp falls within this window.
@@ -160,57 +142,28 @@ impl TimeWindow {
         }
         true
     }
+
+    /// Check if a timestamp lies beyond the end of this window.
+    ///
+    /// Always `false` for open-ended windows.
+    pub fn is_past_end(&self, ts: Timestamp) -> bool {
+        self.end.is_some_and(|end| ts > end)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn parse_time_command_valid() {
-        let ts = Timestamp::from_time_command("2024,1,15,10,30,45").unwrap();
-        // 2024-01-15 10:30:45 UTC
-        // Days from 1970 to 2024: 54 years
-        // DOY for Jan 15 = 15
-        assert!(ts.seconds > 0);
-    }
-
-    #[test]
-    fn parse_time_command_invalid() {
-        assert!(Timestamp::from_time_command("").is_none());
-        assert!(Timestamp::from_time_command("2024,13,1,0,0,0").is_none()); // month 13
-        assert!(Timestamp::from_time_command("2024,0,1,0,0,0").is_none()); // month 0
-        assert!(Timestamp::from_time_command("2024,1,32,0,0,0").is_none()); // day 32
-        assert!(Timestamp::from_time_command("2024,2,30,0,0,0").is_none()); // Feb 30 (leap)
-        assert!(Timestamp::from_time_command("2023,2,29,0,0,0").is_none()); // Feb 29 non-leap
-        assert!(Timestamp::from_time_command("2024,1,1,24,0,0").is_none()); // hour 24
-        assert!(Timestamp::from_time_command("not,a,time,at,all,x").is_none());
+    fn ts(s: &str) -> Timestamp {
+        Timestamp::from_sl_time(SlTime::parse(s).unwrap())
     }
 
-    #[test]
-    fn month_day_to_doy_regular() {
-        // Jan 1 = DOY 1
-        assert_eq!(month_day_to_doy(2023, 1, 1), Some(1));
-        // Jan 31 = DOY 31
-        assert_eq!(month_day_to_doy(2023, 1, 31), Some(31));
-        // Feb 1 = DOY 32 (non-leap)
-        assert_eq!(month_day_to_doy(2023, 2, 1), Some(32));
-        // Feb 28 = DOY 59 (non-leap)
-        assert_eq!(month_day_to_doy(2023, 2, 28), Some(59));
-        // Mar 1 = DOY 60 (non-leap)
-        assert_eq!(month_day_to_doy(2023, 3, 1), Some(60));
-        // Dec 31 = DOY 365 (non-leap)
-        assert_eq!(month_day_to_doy(2023, 12, 31), Some(365));
-    }
-
-    #[test]
-    fn month_day_to_doy_leap() {
-        // Feb 29 valid in leap year
-        assert_eq!(month_day_to_doy(2024, 2, 29), Some(60));
-        // Mar 1 = DOY 61 in leap year
-        assert_eq!(month_day_to_doy(2024, 3, 1), Some(61));
-        // Dec 31 = DOY 366 in leap year
-        assert_eq!(month_day_to_doy(2024, 12, 31), Some(366));
+    fn window(start: &str, end: Option<&str>) -> TimeWindow {
+        TimeWindow::new(
+            SlTime::parse(start).unwrap(),
+            end.map(|e| SlTime::parse(e).unwrap()),
+        )
     }
 
     #[test]
@@ -227,9 +180,39 @@ mod tests {
         payload[25] = 30;
         payload[26] = 45;
 
-        let ts = Timestamp::from_mseed_payload(&payload).unwrap();
-        let expected = Timestamp::from_time_command("2024,1,15,10,30,45").unwrap();
-        assert_eq!(ts, expected);
+        let btime = Timestamp::from_mseed_payload(&payload).unwrap();
+        assert_eq!(btime, ts("2024,1,15,10,30,45"));
+    }
+
+    #[test]
+    fn record_end_time_adds_samples() {
+        let mut payload = vec![0u8; 512];
+        payload[20..22].copy_from_slice(&1970u16.to_be_bytes());
+        payload[22..24].copy_from_slice(&2u16.to_be_bytes());
+        payload[28..30].copy_from_slice(&5000u16.to_be_bytes()); // 0.5 s
+        payload[30..32].copy_from_slice(&100u16.to_be_bytes());
+        payload[32..34].copy_from_slice(&20i16.to_be_bytes()); // 20 Hz
+        payload[34..36].copy_from_slice(&1i16.to_be_bytes());
+        let end = record_end_time(&payload).unwrap();
+        assert_eq!(
+            end,
+            UNIX_EPOCH + Duration::from_millis(86_400_000 + 500 + 5_000)
+        );
+
+        // Negative factor is a period: -10 = one sample per 10 s
+        payload[32..34].copy_from_slice(&(-10i16).to_be_bytes());
+        let end = record_end_time(&payload).unwrap();
+        assert_eq!(
+            end,
+            UNIX_EPOCH + Duration::from_millis(86_400_000 + 500 + 1_000_000)
+        );
+
+        // No sample rate: end equals start
+        payload[32..34].copy_from_slice(&0i16.to_be_bytes());
+        let end = record_end_time(&payload).unwrap();
+        assert_eq!(end, UNIX_EPOCH + Duration::from_millis(86_400_500));
+
+        assert!(record_end_time(&[0u8; 512]).is_none());
     }
 
     #[test]
@@ -242,10 +225,10 @@ mod tests {
 
     #[test]
     fn time_window_contains() {
-        let tw = TimeWindow::parse("2024,1,1,0,0,0", Some("2024,1,31,23,59,59")).unwrap();
+        let tw = window("2024,1,1,0,0,0", Some("2024,1,31,23,59,59"));
 
         // Within range
-        let mid = Timestamp::from_time_command("2024,1,15,12,0,0").unwrap();
+        let mid = ts("2024,1,15,12,0,0");
         assert!(tw.contains(mid));
 
         // At start boundary
@@ -255,40 +238,59 @@ mod tests {
         assert!(tw.contains(tw.end.unwrap()));
 
         // Before start
-        let before = Timestamp::from_time_command("2023,12,31,23,59,59").unwrap();
+        let before = ts("2023,12,31,23,59,59");
         assert!(!tw.contains(before));
 
         // After end
-        let after = Timestamp::from_time_command("2024,2,1,0,0,0").unwrap();
+        let after = ts("2024,2,1,0,0,0");
         assert!(!tw.contains(after));
     }
 
     #[test]
     fn time_window_open_ended() {
-        let tw = TimeWindow::parse("2024,1,1,0,0,0", None).unwrap();
+        let tw = window("2024,1,1,0,0,0", None);
 
         // Start boundary passes
         assert!(tw.contains(tw.start));
 
         // Way in the future passes
-        let future = Timestamp::from_time_command("2030,12,31,23,59,59").unwrap();
+        let future = ts("2030,12,31,23,59,59");
         assert!(tw.contains(future));
 
         // Before start fails
-        let before = Timestamp::from_time_command("2023,12,31,23,59,59").unwrap();
+        let before = ts("2023,12,31,23,59,59");
         assert!(!tw.contains(before));
     }
 
     #[test]
     fn timestamp_ordering() {
-        let t1 = Timestamp::from_time_command("2024,1,1,0,0,0").unwrap();
-        let t2 = Timestamp::from_time_command("2024,1,1,0,0,1").unwrap();
-        let t3 = Timestamp::from_time_command("2024,6,15,12,0,0").unwrap();
-        let t4 = Timestamp::from_time_command("2025,1,1,0,0,0").unwrap();
+        let t1 = ts("2024,1,1,0,0,0");
+        let t2 = ts("2024,1,1,0,0,1");
+        let t3 = ts("2024,6,15,12,0,0");
+        let t4 = ts("2025,1,1,0,0,0");
 
         assert!(t1 < t2);
         assert!(t2 < t3);
         assert!(t3 < t4);
         assert_eq!(t1, t1);
     }
+
+    #[test]
+    fn is_past_end_bounded_and_open() {
+        let bounded = window("2024,1,1,0,0,0", Some("2024,1,31,0,0,0"));
+        let open = window("2024,1,1,0,0,0", None);
+        let inside = ts("2024,1,15,0,0,0");
+        let after = ts("2024,2,1,0,0,0");
+        assert!(!bounded.is_past_end(inside));
+        assert!(bounded.is_past_end(after));
+        assert!(!open.is_past_end(after));
+    }
+
+    #[test]
+    fn window_from_sl_time_drops_subseconds() {
+        let tw = window("2024-01-01T00:00:00.9Z", Some("2024-01-31T00:00:00Z"));
+        assert_eq!(tw.start, ts("2024,1,1,0,0,0"));
+        assert!(tw.contains(ts("2024-01-15")));
+        assert!(!tw.contains(ts("2024-02-01")));
+    }
 }
@@ -0,0 +1,283 @@
+//! UDP/multicast source for miniSEED records.
+//!
+//! Many dataloggers emit 512-byte miniSEED v2 records as UDP datagrams.
+//! [`UdpSource`] listens on a unicast address or joins a multicast group,
+//! validates each record header, and pushes the records into the
+//! [`DataStore`] under the network/station codes from the header. Per-sender
+//! counts are reported through
+//! [`MetricsSnapshot::senders`](crate::MetricsSnapshot::senders).
+
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use seedlink_rs_protocol::frame::v3;
+use tokio::net::UdpSocket;
+use tracing::{debug, info};
+
+use crate::error::{Result, ServerError};
+use crate::metrics::SenderMetrics;
+use crate::source::{BoxFuture, DataSource};
+use crate::store::DataStore;
+
+/// Largest datagram accepted; anything longer is truncated and rejected.
+const MAX_DATAGRAM: usize = 65_536;
+
+/// Default for [`UdpConfig::max_senders`].
+const DEFAULT_MAX_SENDERS: usize = 1024;
+
+/// Configuration for [`UdpSource`].
+#[derive(Clone, Debug)]
+pub struct UdpConfig {
+    /// Local address to bind. For multicast, use the group port with an
+    /// unspecified address (e.g. `0.0.0.0:5000`).
+    pub bind: SocketAddr,
+    /// Multicast group to join. Default: none (unicast).
+    pub multicast_group: Option<IpAddr>,
+    /// Interface for IPv4 multicast membership. Default: `0.0.0.0` (any).
+    pub multicast_interface: Ipv4Addr,
+    /// Most senders to keep stats for. When a new sender arrives at the
+    /// limit, the one heard from least recently is dropped. Default: 1024.
+    pub max_senders: usize,
+}
+
+impl UdpConfig {
+    /// Unicast listener on `bind`.
+    pub fn new(bind: SocketAddr) -> Self {
+        Self {
+            bind,
+            multicast_group: None,
+            multicast_interface: Ipv4Addr::UNSPECIFIED,
+            max_senders: DEFAULT_MAX_SENDERS,
+        }
+    }
+
+    /// Join the multicast `group` after binding.
+    pub fn multicast(mut self, group: IpAddr) -> Self {
+        self.multicast_group = Some(group);
+        self
+    }
+}
+
+pub(crate) struct SenderStats {
+    pub(crate) records: u64,
+    pub(crate) rejected: u64,
+    pub(crate) bytes: u64,
+    last_seen: Instant,
+}
+
+impl Default for SenderStats {
+    fn default() -> Self {
+        Self {
+            records: 0,
+            rejected: 0,
+            bytes: 0,
+            last_seen: Instant::now(),
+        }
+    }
+}
+
+/// A [`DataSource`] that receives miniSEED records over UDP.
+///
+/// Each datagram must hold one or more whole 512-byte records. Datagrams of
+/// any other length are rejected, as are records whose header does not look
+/// like miniSEED v2 or that fail [`StoreConfig::validation`](crate::StoreConfig::validation);
+/// valid records in the same datagram are still pushed.
+///
+/// ```no_run
+/// # async fn example() -> seedlink_rs_server::Result<()> {
+/// use seedlink_rs_server::SeedLinkServer;
+/// use seedlink_rs_server::udp::{UdpConfig, UdpSource};
+///
+/// let udp = UdpSource::bind(UdpConfig::new("0.0.0.0:5000".parse().unwrap())).await?;
+/// let mut server = SeedLinkServer::bind("0.0.0.0:18000").await?;
+/// server.add_source(Box::new(udp));
+/// server.run().await;
+/// # Ok(())
+/// # }
+/// ```
+pub struct UdpSource {
+    socket: UdpSocket,
+    senders: Mutex<HashMap<SocketAddr, SenderStats>>,
+    max_senders: usize,
+}
+
+impl UdpSource {
+    /// Bind the socket and join the multicast group, if any.
+    pub async fn bind(config: UdpConfig) -> Result<Self> {
+        let socket = UdpSocket::bind(config.bind)
+            .await
+            .map_err(ServerError::Bind)?;
+        match config.multicast_group {
+            Some(IpAddr::V4(group)) => {
+                socket.join_multicast_v4(group, config.multicast_interface)?
+            }
+            Some(IpAddr::V6(group)) => socket.join_multicast_v6(&group, 0)?,
+            None => {}
+        }
+        info!(
+            addr = %socket.local_addr()?,
+            group = ?config.multicast_group,
+            "UDP source bound"
+        );
+        Ok(Self {
+            socket,
+            senders: Mutex::new(HashMap::new()),
+            max_senders: config.max_senders,
+        })
+    }
+
+    /// Address the socket is bound to.
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.socket.local_addr()?)
+    }
+
+    /// Validate and push the records in one datagram, updating `from`'s stats.
+    fn ingest(&self, store: &DataStore, from: SocketAddr, data: &[u8]) {
+        let mut senders = self.senders.lock().unwrap();
+        if !senders.contains_key(&from) && senders.len() >= self.max_senders {
+            let oldest = senders
+                .iter()
+                .min_by_key(|(_, s)| s.last_seen)
+                .map(|(addr, _)| *addr);
+            if let Some(oldest) = oldest {
+                senders.remove(&oldest);
+            }
+        }
+        let stats = senders.entry(from).or_default();
+        stats.last_seen = Instant::now();
+        stats.bytes += data.len() as u64;
+
+        if data.is_empty() || !data.len().is_multiple_of(v3::PAYLOAD_LEN) {
+            debug!(%from, len = data.len(), "rejecting datagram with partial record");
+            stats.rejected += 1;
+            return;
+        }
+        for record in data.chunks_exact(v3::PAYLOAD_LEN) {
+            match store.push_record(record) {
+                Ok(_) => stats.records += 1,
+                Err(error) => {
+                    debug!(%from, %error, "rejecting record");
+                    stats.rejected += 1;
+                }
+            }
+        }
+    }
+}
+
+impl DataSource for UdpSource {
+    fn name(&self) -> &str {
+        "udp"
+    }
+
+    fn run(&self, store: DataStore) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            let mut buf = vec![0u8; MAX_DATAGRAM];
+            loop {
+                let (n, from) = self.socket.recv_from(&mut buf).await?;
+                self.ingest(&store, from, &buf[..n]);
+            }
+        })
+    }
+
+    fn sender_metrics(&self) -> Vec<SenderMetrics> {
+        let mut senders: Vec<_> = self
+            .senders
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(addr, s)| SenderMetrics {
+                source: self.name().to_owned(),
+                addr: *addr,
+                records: s.records,
+                rejected: s.rejected,
+                bytes: s.bytes,
+            })
+            .collect();
+        senders.sort_by_key(|s| s.addr);
+        senders
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use seedlink_rs_protocol::testing::TestRecordBuilder;
+    use std::time::Duration;
+
+    fn record(network: &str, station: &str) -> Vec<u8> {
+        TestRecordBuilder::new()
+            .network(network)
+            .station(station)
+            .build()
+    }
+
+    async fn wait_for(source: &UdpSource, packets: u64) -> Vec<SenderMetrics> {
+        tokio::time::timeout(Duration::from_secs(2), async {
+            loop {
+                let m = source.sender_metrics();
+                if m.iter().map(|s| s.records + s.rejected).sum::<u64>() >= packets {
+                    return m;
+                }
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("UDP datagrams not received in time")
+    }
+
+    #[tokio::test]
+    async fn ingests_datagrams_with_sender_stats() {
+        let source = std::sync::Arc::new(
+            UdpSource::bind(UdpConfig::new("127.0.0.1:0".parse().unwrap()))
+                .await
+                .unwrap(),
+        );
+        let target = source.local_addr().unwrap();
+        let store = DataStore::new(10);
+        tokio::spawn({
+            let (source, store) = (source.clone(), store.clone());
+            async move { source.run(store).await }
+        });
+
+        let sender = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let mut two = record("IU", "ANMO");
+        two.extend(record("GE", "WLF"));
+        sender.send_to(&two, target).await.unwrap();
+        sender.send_to(&[0u8; 100], target).await.unwrap(); // partial
+        sender.send_to(&[0u8; 512], target).await.unwrap(); // bad header
+
+        let metrics = wait_for(&source, 4).await;
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].addr, sender.local_addr().unwrap());
+        assert_eq!(metrics[0].source, "udp");
+        assert_eq!(metrics[0].records, 2);
+        assert_eq!(metrics[0].rejected, 2);
+        assert_eq!(metrics[0].bytes, 1024 + 100 + 512);
+        assert_eq!(store.len(), 2);
+        assert_eq!(store.station_info().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn sender_stats_are_capped() {
+        let config = UdpConfig {
+            max_senders: 2,
+            ..UdpConfig::new("127.0.0.1:0".parse().unwrap())
+        };
+        let source = UdpSource::bind(config).await.unwrap();
+        let store = DataStore::new(10);
+        let addr = |port| SocketAddr::from(([192, 0, 2, 1], port));
+
+        source.ingest(&store, addr(1), &record("IU", "ANMO"));
+        source.ingest(&store, addr(2), &record("IU", "ANMO"));
+        source.ingest(&store, addr(1), &record("IU", "ANMO"));
+        source.ingest(&store, addr(3), &record("IU", "ANMO"));
+
+        // addr(2) was heard from least recently
+        let metrics = source.sender_metrics();
+        let addrs: Vec<_> = metrics.iter().map(|s| s.addr).collect();
+        assert_eq!(addrs, [addr(1), addr(3)]);
+        assert_eq!(metrics[0].records, 2);
+    }
+}